@@ -152,7 +152,11 @@ impl<const OSC_RAW_BUF_SIZE: usize> Parser<OSC_RAW_BUF_SIZE> {
     {
         let mut receiver = VtUtf8Receiver(performer, &mut self.state);
         let utf8_parser = &mut self.utf8_parser;
-        utf8_parser.advance(&mut receiver, byte);
+        if !utf8_parser.advance(&mut receiver, byte) {
+            // The byte ended an incomplete sequence, it can be the start
+            // of another character or an escape sequence.
+            self.advance(performer, byte);
+        }
     }
 
     #[inline]
@@ -458,6 +462,7 @@ extern crate std;
 mod tests {
     use super::*;
 
+    use std::string::String;
     use std::vec::Vec;
 
     static OSC_BYTES: &[u8] = &[
@@ -1090,6 +1095,93 @@ mod tests {
             _ => panic!("expected osc sequence"),
         }
     }
+
+    #[derive(Default)]
+    struct Printer {
+        printed: String,
+        executed: Vec<u8>,
+    }
+
+    impl Perform for Printer {
+        fn print(&mut self, c: char) {
+            self.printed.push(c);
+        }
+
+        fn execute(&mut self, byte: u8) {
+            self.executed.push(byte);
+        }
+    }
+
+    fn print(chunks: &[&[u8]]) -> Printer {
+        let mut printer = Printer::default();
+        let mut parser = Parser::new();
+        for chunk in chunks {
+            for byte in *chunk {
+                parser.advance(&mut printer, *byte);
+            }
+        }
+        printer
+    }
+
+    #[test]
+    fn utf8_split_across_chunks() {
+        let printer = print(&[b"a\xe2\x82", b"\xac", b"\xf0\x9f", b"\x98\x80b"]);
+        assert_eq!(printer.printed, "a\u{20ac}\u{1f600}b");
+    }
+
+    #[test]
+    fn utf8_invalid_bytes_are_replaced() {
+        // Stray continuation bytes, overlong and out of range leading bytes.
+        let printer = print(&[b"a\xa0\xbf\xc0\xc1\xf5\xffb"]);
+        assert_eq!(
+            printer.printed,
+            "a\u{fffd}\u{fffd}\u{fffd}\u{fffd}\u{fffd}\u{fffd}b"
+        );
+        assert!(printer.executed.is_empty());
+
+        // Surrogates are invalid, every byte is replaced.
+        let printer = print(&[b"\xed\xa0\xbf"]);
+        assert_eq!(printer.printed, "\u{fffd}\u{fffd}\u{fffd}");
+
+        // Bytes after an invalid sequence start over in the ground state,
+        // where 0x80..=0x9f are C1 controls.
+        let printer = print(&[b"\xed\xa0\x85"]);
+        assert_eq!(printer.printed, "\u{fffd}\u{fffd}");
+        assert_eq!(printer.executed, [0x85]);
+    }
+
+    #[test]
+    fn c1_controls_are_executed() {
+        // IND, NEL and ST outside of a UTF-8 sequence are C1 controls.
+        let printer = print(&[b"a\x84\x85\x9cb"]);
+        assert_eq!(printer.printed, "ab");
+        assert_eq!(printer.executed, [0x84, 0x85, 0x9c]);
+
+        // As continuation bytes they're part of the character.
+        let printer = print(&[b"\xe2\x80\x9c"]);
+        assert_eq!(printer.printed, "\u{201c}");
+        assert!(printer.executed.is_empty());
+    }
+
+    #[test]
+    fn utf8_incomplete_sequence_keeps_next_byte() {
+        let printer = print(&[b"\xe2\x82A\xe2\xc3\xa9"]);
+        assert_eq!(printer.printed, "\u{fffd}A\u{fffd}\u{e9}");
+
+        let printer = print(&[b"\xe2", b"\r\xf0\x9f"]);
+        assert_eq!(printer.printed, "\u{fffd}");
+        assert_eq!(printer.executed, b"\r");
+
+        let mut dispatcher = Dispatcher::default();
+        let mut parser = Parser::new();
+        for byte in b"\xe2\x1b[1m" {
+            parser.advance(&mut dispatcher, *byte);
+        }
+        assert_eq!(
+            dispatcher.dispatched,
+            [Sequence::Csi(vec![vec![1]], vec![], false, 'm')]
+        );
+    }
 }
 
 // #[cfg(all(feature = "nightly", test))]
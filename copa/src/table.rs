@@ -18,9 +18,14 @@ generate_state_changes!(state_changes, {
         0x19        => (Anywhere, Execute),
         0x1c..=0x1f => (Anywhere, Execute),
         0x20..=0x7f => (Anywhere, Print),
+        // C1 controls, they're never the first byte of a UTF-8 sequence.
         0x80..=0x8f => (Anywhere, Execute),
         0x91..=0x9a => (Anywhere, Execute),
         0x9c        => (Anywhere, Execute),
+        // Not valid as the first byte of a UTF-8 sequence, the UTF-8
+        // parser prints a replacement character for each of them.
+        0xa0..=0xc1 => (Anywhere, BeginUtf8),
+        0xf5..=0xff => (Anywhere, BeginUtf8),
         // Beginning of UTF-8 2 byte sequence
         0xc2..=0xdf => (Utf8, BeginUtf8),
         // Beginning of UTF-8 3 byte sequence
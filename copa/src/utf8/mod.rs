@@ -46,13 +46,19 @@ impl Parser {
     ///
     /// The provider receiver will be called whenever a codepoint is completed or an invalid
     /// sequence is detected.
-    pub fn advance<R>(&mut self, receiver: &mut R, byte: u8)
+    ///
+    /// Returns false when `byte` interrupted an incomplete sequence, like the WHATWG
+    /// decoder the sequence is reported as invalid and `byte` must be processed again.
+    pub fn advance<R>(&mut self, receiver: &mut R, byte: u8) -> bool
     where
         R: Receiver,
     {
         let (state, action) = self.state.advance(byte);
+        let consumed =
+            self.state == State::Ground || !matches!(action, Action::InvalidSequence);
         self.perform_action(receiver, byte, action);
         self.state = state;
+        consumed
     }
 
     fn perform_action<R>(&mut self, receiver: &mut R, byte: u8, action: Action)
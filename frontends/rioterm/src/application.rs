@@ -1,5 +1,7 @@
+use crate::context::TabIndicator;
 use crate::event::{ClickState, EventPayload, EventProxy, RioEvent, RioEventType};
 use crate::ime::Preedit;
+use crate::platform::screen_lock::ScreenLockWatcher;
 use crate::renderer::utils::update_colors_based_on_theme;
 use crate::router::{routes::RoutePath, Router};
 use crate::scheduler::{Scheduler, TimerId, Topic};
@@ -7,7 +9,9 @@ use crate::screen::touch::on_touch;
 use crate::watcher::configuration_file_updates;
 use raw_window_handle::HasDisplayHandle;
 use rio_backend::clipboard::{Clipboard, ClipboardType};
+use rio_backend::config::bell::BellFallback;
 use rio_backend::config::colors::ColorRgb;
+use rio_backend::event::WindowState;
 use rio_window::application::ApplicationHandler;
 use rio_window::event::{
     ElementState, Hook, Ime, MouseButton, MouseScrollDelta, StartCause, TouchPhase,
@@ -30,6 +34,10 @@ pub struct Application<'a> {
     event_proxy: EventProxy,
     router: Router<'a>,
     scheduler: Scheduler,
+    bell: crate::bell::BellPlayer,
+    screen_lock: Option<ScreenLockWatcher>,
+    #[cfg(unix)]
+    control: Option<crate::control::ControlSocket>,
 }
 
 impl Application<'_> {
@@ -43,28 +51,66 @@ impl Application<'_> {
         let clipboard =
             unsafe { Clipboard::new(event_loop.display_handle().unwrap().as_raw()) };
 
-        let mut router = Router::new(config.fonts.to_owned(), clipboard);
+        let proxy = event_loop.create_proxy();
+        let event_proxy = EventProxy::new(proxy.clone());
+
+        let mut router =
+            Router::new(config.fonts.to_owned(), clipboard, event_proxy.clone());
         if let Some(error) = config_error {
             router.propagate_error_to_next_route(error.into());
         }
 
-        let proxy = event_loop.create_proxy();
-        let event_proxy = EventProxy::new(proxy.clone());
-        let _ = configuration_file_updates(
-            rio_backend::config::config_dir_path(),
-            event_proxy.clone(),
-        );
+        // Safe mode keeps the defaults even if the config file changes.
+        if !config.safe_mode {
+            configuration_file_updates(
+                rio_backend::config::config_dir_path(),
+                config.watched_files(),
+                event_proxy.clone(),
+            );
+        }
         let scheduler = Scheduler::new(proxy);
         event_loop.listen_device_events(DeviceEvents::Never);
 
         #[cfg(target_os = "macos")]
         event_loop.set_confirm_before_quit(config.confirm_before_quit);
 
+        #[cfg(unix)]
+        let control = config.control_socket.enabled.then(|| {
+            let path = config
+                .control_socket
+                .path
+                .as_ref()
+                .map(std::path::PathBuf::from)
+                .unwrap_or_else(crate::control::ControlSocket::default_path);
+            let proxy = event_proxy.clone();
+            let on_snapshot = move |request| {
+                proxy.send_event(
+                    RioEventType::Rio(RioEvent::ControlSnapshot(request)),
+                    rio_window::window::WindowId::from(0),
+                );
+            };
+            match crate::control::ControlSocket::bind(&path, on_snapshot) {
+                Ok(control) => {
+                    // Shells spawned from now on can find the socket.
+                    router.control_socket = Some(control.path().to_path_buf());
+                    Some(control)
+                }
+                Err(err) => {
+                    tracing::error!("unable to create control socket: {err}");
+                    None
+                }
+            }
+        });
+
         Application {
             config,
             event_proxy,
             router,
             scheduler,
+            bell: crate::bell::BellPlayer::default(),
+            screen_lock: None,
+            #[cfg(unix)]
+            control: control.flatten(),
         }
     }
 
@@ -85,10 +131,102 @@ impl Application<'_> {
                 | WindowEvent::HoveredFileCancelled
                 | WindowEvent::Destroyed
                 | WindowEvent::HoveredFile(_)
-                | WindowEvent::Moved(_)
         )
     }
 
+    /// Bell of the window, from its active profile or the config.
+    fn bell_config(&self, window_id: WindowId) -> rio_backend::config::bell::Bell {
+        self.router
+            .routes
+            .get(&window_id)
+            .and_then(|route| route.window.screen.context_manager.profile_bell())
+            .unwrap_or(&self.config.bell)
+            .clone()
+    }
+
+    /// Replies to a control socket snapshot request, the window and
+    /// route default to the focused ones.
+    #[cfg(unix)]
+    fn control_snapshot(&self, request: rio_backend::snapshot::SnapshotRequest) {
+        use crate::control::{reply, ControlEvent};
+        use rio_backend::crosswords::pos::Line;
+        use rio_backend::snapshot::{snapshot, visible_lines};
+
+        let window_id = match request.window {
+            Some(window) => Some(WindowId::from(window)),
+            None => self
+                .router
+                .get_focused_route()
+                .or_else(|| self.router.routes.keys().next().copied()),
+        };
+        let context_manager = window_id
+            .and_then(|window_id| self.router.routes.get(&window_id))
+            .map(|route| &route.window.screen.context_manager);
+        let context = context_manager.and_then(|context_manager| match request.route {
+            Some(route) => context_manager.get_by_route_id(route),
+            None => Some(context_manager.current()),
+        });
+        let (Some(window_id), Some(context)) = (window_id, context) else {
+            reply(
+                &request,
+                &ControlEvent::Error {
+                    message: "window or route not found",
+                },
+            );
+            return;
+        };
+
+        let lines = {
+            let terminal = context.terminal.lock();
+            let lines = match request.lines {
+                Some([start, end]) => Line(start)..=Line(end),
+                None => visible_lines(&terminal),
+            };
+            snapshot(&terminal, lines)
+        };
+        reply(
+            &request,
+            &ControlEvent::Snapshot {
+                window: window_id.into(),
+                route: context.route_id,
+                lines: &lines,
+            },
+        );
+    }
+
+    /// Schedules clearing the text copied to the clipboard after the
+    /// configured delay and/or once the screen gets locked.
+    fn schedule_clipboard_clear(&mut self, window_id: WindowId) {
+        if let Some(seconds) = self.config.clipboard.clear_after {
+            let timer_id = TimerId::new(Topic::ClearClipboard, window_id);
+            let event =
+                EventPayload::new(RioEventType::Rio(RioEvent::ClearClipboard), window_id);
+            // Copying again restarts the delay
+            self.scheduler.unschedule(timer_id);
+            self.scheduler
+                .schedule(event, Duration::from_secs(seconds), false, timer_id);
+        }
+
+        if self.config.clipboard.clear_on_lock && self.screen_lock.is_none() {
+            self.screen_lock = Some(ScreenLockWatcher::spawn(
+                self.event_proxy.clone(),
+                window_id,
+            ));
+        }
+    }
+
+    fn close_window(&mut self, event_loop: &ActiveEventLoop, window_id: WindowId) {
+        self.router.routes.remove(&window_id);
+
+        if cfg!(target_os = "macos") && self.config.confirm_before_quit {
+            return;
+        }
+
+        if self.router.routes.is_empty() {
+            event_loop.exit();
+        }
+    }
+
     pub fn run(
         &mut self,
         event_loop: EventLoop<EventPayload>,
@@ -135,6 +273,26 @@ impl ApplicationHandler<EventPayload> for Application<'_> {
             None,
         );
 
+        if cause == StartCause::Init && self.config.check_for_updates {
+            if let Some(window_id) = self.router.routes.keys().next() {
+                crate::update_check::check(self.event_proxy.clone(), *window_id);
+            }
+        }
+
+        // Explains once why Rio is slower and effects are missing.
+        if cause == StartCause::Init {
+            if let Some(route) = self.router.routes.values_mut().next() {
+                let sugarloaf = &route.window.screen.sugarloaf;
+                if sugarloaf.is_software_rendered() {
+                    let message = format!(
+                        "Rendering without a GPU ({}), background shaders and image blur are disabled",
+                        sugarloaf.ctx.adapter_info.name
+                    );
+                    route.window.screen.renderer.show_toast(message);
+                }
+            }
+        }
+
         tracing::info!("Initialisation complete");
     }
 
@@ -176,6 +334,13 @@ impl ApplicationHandler<EventPayload> for Application<'_> {
                                 route.request_redraw();
                             }
                         }
+                    } else if route
+                        .window
+                        .screen
+                        .context_manager
+                        .mark_tab(route_id, TabIndicator::Activity)
+                    {
+                        route.request_redraw();
                     }
                 }
             }
@@ -201,18 +366,39 @@ impl ApplicationHandler<EventPayload> for Application<'_> {
                     route.report_error(&error);
                 }
             }
+            RioEventType::Rio(RioEvent::FontsLoaded(fonts_not_found)) => {
+                for (_id, route) in self.router.routes.iter_mut() {
+                    // Characters could have been matched to a fallback font
+                    // while it wasn't loaded yet.
+                    route.window.screen.renderer.clear_font_cache();
+                    if !fonts_not_found.is_empty() {
+                        route
+                            .window
+                            .screen
+                            .context_manager
+                            .report_error_fonts_not_found(fonts_not_found.clone());
+                    }
+                    route.request_redraw();
+                }
+            }
             RioEventType::Rio(RioEvent::UpdateConfig) => {
-                let (config, config_error) = match rio_backend::config::Config::try_load()
-                {
-                    Ok(config) => (config, None),
-                    Err(error) => (rio_backend::config::Config::default(), Some(error)),
-                };
+                let (mut config, config_error) =
+                    match rio_backend::config::Config::try_load() {
+                        Ok(config) => (config, None),
+                        Err(error) => {
+                            (rio_backend::config::Config::default(), Some(error))
+                        }
+                    };
+                if self.config.crash_recovery {
+                    crate::crash_loop::disable_culprits(&mut config);
+                }
 
                 let has_font_updates = self.config.fonts != config.fonts;
 
                 let font_library_errors = if has_font_updates {
-                    let new_font_library = rio_backend::sugarloaf::font::FontLibrary::new(
+                    let new_font_library = crate::router::load_font_library(
                         config.fonts.to_owned(),
+                        self.event_proxy.clone(),
                     );
                     self.router.font_library = Box::new(new_font_library.0);
                     new_font_library.1
@@ -221,7 +407,7 @@ impl ApplicationHandler<EventPayload> for Application<'_> {
                 };
 
                 self.config = config;
-                for (_id, route) in self.router.routes.iter_mut() {
+                for (window_id, route) in self.router.routes.iter_mut() {
                     if has_font_updates {
                         if let Some(ref err) = font_library_errors {
                             route
@@ -236,24 +422,52 @@ impl ApplicationHandler<EventPayload> for Application<'_> {
 
                     route.update_config(&self.config, &self.router.font_library);
                     route.window.configure_window(&self.config);
+                    route
+                        .window
+                        .screen
+                        .context_manager
+                        .reset_profile(&self.config.profiles);
 
                     if let Some(error) = &config_error {
                         route.report_error(&error.to_owned().into());
                     } else {
                         route.clear_errors();
                     }
+
+                    // Starts the schedule if night mode was just enabled
+                    if self.config.night_mode.enabled {
+                        self.event_proxy.send_event(
+                            RioEventType::Rio(RioEvent::CheckNightMode),
+                            *window_id,
+                        );
+                    }
                 }
             }
             RioEventType::Rio(RioEvent::Exit) => {
+                let running_processes = self.router.running_processes();
                 if let Some(route) = self.router.routes.get_mut(&window_id) {
                     if cfg!(target_os = "macos") && self.config.confirm_before_quit {
-                        route.confirm_quit();
+                        route.confirm_quit(running_processes);
                         route.request_redraw();
                     } else {
                         route.quit();
                     }
                 }
             }
+            RioEventType::Rio(RioEvent::Quit) => {
+                let running_processes = self.router.running_processes();
+                if !self.config.confirm_quit.should_confirm(&running_processes) {
+                    event_loop.exit();
+                } else if let Some(route) = self.router.routes.get_mut(&window_id) {
+                    route.confirm_quit(running_processes);
+                    route.request_redraw();
+                }
+            }
+            RioEventType::Rio(RioEvent::ForceCloseWindow)
+                if self.router.routes.contains_key(&window_id) =>
+            {
+                self.close_window(event_loop, window_id);
+            }
             RioEventType::Rio(RioEvent::CloseTerminal(route_id)) => {
                 if let Some(route) = self.router.routes.get_mut(&window_id) {
                     if route
@@ -334,11 +548,244 @@ impl ApplicationHandler<EventPayload> for Application<'_> {
                     );
                 }
             }
-            RioEventType::Rio(RioEvent::Title(title)) => {
+            RioEventType::Rio(RioEvent::Bell(route_id)) => {
+                let bell = self.bell_config(window_id);
+                let event_proxy = self.event_proxy.clone();
+                self.bell.ring(&bell, move || {
+                    event_proxy
+                        .send_event(RioEventType::Rio(RioEvent::BellUnheard), window_id);
+                });
+                if let Some(route) = self.router.routes.get_mut(&window_id) {
+                    if route
+                        .window
+                        .screen
+                        .context_manager
+                        .mark_tab(route_id, TabIndicator::Bell)
+                    {
+                        route.request_redraw();
+                    }
+                    if bell.urgent && !route.window.is_focused {
+                        route.request_attention();
+                    }
+                    if bell.visual {
+                        route
+                            .window
+                            .screen
+                            .renderer
+                            .flash_bell(Duration::from_millis(bell.visual_duration));
+                        route.request_redraw();
+                    }
+                }
+
+                #[cfg(unix)]
+                if let Some(control) = &self.control {
+                    control.publish(&crate::control::ControlEvent::Bell {
+                        window: window_id.into(),
+                    });
+                }
+            }
+            RioEventType::Rio(RioEvent::BellUnheard) => {
+                let bell = self.bell_config(window_id);
+                if let Some(route) = self.router.routes.get_mut(&window_id) {
+                    match bell.fallback {
+                        // Already flashed when the bell rang.
+                        BellFallback::Visual if !bell.visual => {
+                            route
+                                .window
+                                .screen
+                                .renderer
+                                .flash_bell(Duration::from_millis(bell.visual_duration));
+                            route.request_redraw();
+                        }
+                        BellFallback::Notification => {
+                            let context_manager = &route.window.screen.context_manager;
+                            let body = context_manager.current_label().unwrap_or("Rio");
+                            crate::notification::notify("Bell", body);
+                        }
+                        BellFallback::Visual | BellFallback::None => {}
+                    }
+                }
+            }
+            RioEventType::Rio(RioEvent::CommandFinished(finished)) => {
+                let threshold = self.config.notifications.command_duration_threshold;
+                let is_long = threshold.is_some_and(|threshold| {
+                    finished.duration >= Duration::from_secs(threshold)
+                });
+
+                let is_focused =
+                    self.router.routes.get(&window_id).is_some_and(|route| {
+                        route.window.is_focused
+                            && route.window.screen.ctx().current_route()
+                                == finished.route_id
+                    });
+                if is_long && !is_focused {
+                    let title = match finished.exit_code {
+                        Some(0) | None => String::from("Command finished"),
+                        Some(code) => format!("Command failed with exit code {code}"),
+                    };
+                    let body = format!(
+                        "{} ({})",
+                        finished.command,
+                        crate::notification::format_duration(finished.duration)
+                    );
+                    crate::notification::notify(&title, &body);
+                }
+
+                if finished.exit_code == Some(crate::command_not_found::EXIT_CODE) {
+                    crate::command_not_found::suggest(
+                        &self.config.command_not_found,
+                        &finished.command,
+                        self.event_proxy.clone(),
+                        window_id,
+                    );
+                }
+            }
+            RioEventType::Rio(RioEvent::PrivateOsc(osc)) => {
+                crate::osc_hook::run(&self.config.osc_hooks, &osc, window_id);
+
+                #[cfg(unix)]
+                if let Some(control) = &self.control {
+                    control.publish(&crate::control::ControlEvent::Osc {
+                        window: window_id.into(),
+                        route: osc.route_id,
+                        code: osc.code,
+                        payload: &osc.payload,
+                    });
+                }
+            }
+            RioEventType::Rio(RioEvent::ControlSnapshot(request)) => {
+                #[cfg(unix)]
+                self.control_snapshot(request);
+                #[cfg(not(unix))]
+                drop(request);
+            }
+            RioEventType::Rio(RioEvent::Toast(message)) => {
+                if let Some(route) = self.router.routes.get_mut(&window_id) {
+                    route.window.screen.renderer.show_toast(message);
+                    route.request_redraw();
+                }
+            }
+            RioEventType::Rio(RioEvent::BlinkText(millis, route_id)) => {
+                let timer_id = TimerId::new(Topic::TextBlinking, window_id);
+                let event = EventPayload::new(
+                    RioEventType::Rio(RioEvent::RenderRoute(route_id)),
+                    window_id,
+                );
+
+                if !self.scheduler.scheduled(timer_id) {
+                    self.scheduler.schedule(
+                        event,
+                        Duration::from_millis(millis),
+                        false,
+                        timer_id,
+                    );
+                }
+            }
+            RioEventType::Rio(RioEvent::SwitchProfile(profile)) => {
+                if let Some(route) = self.router.routes.get_mut(&window_id) {
+                    let mut config = self.config.clone();
+                    if let Some(colors) = profile.and_then(|profile| profile.colors) {
+                        config.colors = colors;
+                    }
+
+                    route.update_config(&config, &self.router.font_library);
+                    route.window.configure_window(&config);
+                }
+            }
+            RioEventType::Rio(RioEvent::ConfirmPrint) => {
+                if let Some(route) = self.router.routes.get_mut(&window_id) {
+                    route.confirm_print();
+                    route.request_redraw();
+                }
+            }
+            RioEventType::Rio(RioEvent::ShowPaneDiff) => {
+                if let Some(route) = self.router.routes.get_mut(&window_id) {
+                    route.show_pane_diff();
+                    route.request_redraw();
+                }
+            }
+            RioEventType::Rio(RioEvent::SampleProcessUsage) => {
+                if let Some(route) = self.router.routes.get_mut(&window_id) {
+                    route.window.screen.context_manager.refresh_titles();
+                    route.request_redraw();
+
+                    // Repeats until the window is closed
+                    let timer_id = TimerId::new(Topic::ProcessUsage, window_id);
+                    if !self.scheduler.scheduled(timer_id) {
+                        let event = EventPayload::new(
+                            RioEventType::Rio(RioEvent::SampleProcessUsage),
+                            window_id,
+                        );
+                        self.scheduler.schedule(
+                            event,
+                            Duration::from_secs(2),
+                            true,
+                            timer_id,
+                        );
+                    }
+                }
+            }
+            RioEventType::Rio(RioEvent::CheckNightMode) => {
+                let timer_id = TimerId::new(Topic::NightMode, window_id);
+                if !self.config.night_mode.enabled {
+                    self.scheduler.unschedule(timer_id);
+                    return;
+                }
+
+                if let Some(route) = self.router.routes.get_mut(&window_id) {
+                    if route.window.screen.update_night_mode() {
+                        route.request_redraw();
+                    }
+
+                    // Repeats until the window is closed
+                    if !self.scheduler.scheduled(timer_id) {
+                        let event = EventPayload::new(
+                            RioEventType::Rio(RioEvent::CheckNightMode),
+                            window_id,
+                        );
+                        self.scheduler.schedule(
+                            event,
+                            Duration::from_secs(60),
+                            true,
+                            timer_id,
+                        );
+                    }
+                }
+            }
+            RioEventType::Rio(RioEvent::Title(route_id, title)) => {
                 if let Some(route) = self.router.routes.get_mut(&window_id) {
+                    // Background tabs don't change the window title
+                    if route.window.screen.context_manager.current_route() != route_id {
+                        return;
+                    }
+
+                    // `title.content` takes precedence over titles set by programs
+                    let title = route
+                        .window
+                        .screen
+                        .context_manager
+                        .current_label()
+                        .map(str::to_owned)
+                        .unwrap_or(title);
                     route.set_window_title(&title);
                 }
             }
+            RioEventType::Rio(RioEvent::ResetTitle(route_id)) => {
+                if let Some(route) = self.router.routes.get_mut(&window_id) {
+                    if route.window.screen.context_manager.current_route() != route_id {
+                        return;
+                    }
+
+                    if let Some(label) =
+                        route.window.screen.context_manager.current_label()
+                    {
+                        let label = label.to_owned();
+                        route.set_window_title(&label);
+                    } else {
+                        route.reset_window_title();
+                    }
+                }
+            }
             RioEventType::Rio(RioEvent::TitleWithSubtitle(title, subtitle)) => {
                 if let Some(route) = self.router.routes.get_mut(&window_id) {
                     route.set_window_title(&title);
@@ -385,9 +832,23 @@ impl ApplicationHandler<EventPayload> for Application<'_> {
                             .clipboard
                             .borrow_mut()
                             .set(clipboard_type, content);
+                        self.schedule_clipboard_clear(window_id);
                     }
                 }
             }
+            RioEventType::Rio(RioEvent::ClipboardCopied) => {
+                self.schedule_clipboard_clear(window_id);
+            }
+            RioEventType::Rio(RioEvent::ClearClipboard) => {
+                self.router.clipboard.borrow_mut().clear_copied();
+                self.screen_lock = None;
+            }
+            RioEventType::Rio(RioEvent::ScreenLocked) => {
+                self.router.clipboard.borrow_mut().clear_copied();
+                self.scheduler
+                    .unschedule(TimerId::new(Topic::ClearClipboard, window_id));
+                self.screen_lock = None;
+            }
             RioEventType::Rio(RioEvent::PtyWrite(text)) => {
                 if let Some(route) = self.router.routes.get_mut(&window_id) {
                     route
@@ -413,6 +874,27 @@ impl ApplicationHandler<EventPayload> for Application<'_> {
                         .send_bytes(text.into_bytes());
                 }
             }
+            RioEventType::Rio(RioEvent::WindowStateRequest(format)) => {
+                if let Some(route) = self.router.routes.get_mut(&window_id) {
+                    let winit_window = &route.window.winit_window;
+                    let position = winit_window.outer_position().unwrap_or_default();
+                    let size = winit_window.outer_size();
+                    let text = format(WindowState {
+                        minimized: winit_window.is_minimized().unwrap_or(false),
+                        x: position.x,
+                        y: position.y,
+                        width: size.width,
+                        height: size.height,
+                    });
+                    route
+                        .window
+                        .screen
+                        .ctx_mut()
+                        .current_mut()
+                        .messenger
+                        .send_bytes(text.into_bytes());
+                }
+            }
             RioEventType::Rio(RioEvent::ColorRequest(index, format)) => {
                 // TODO: colors could be coming terminal as well
                 // if colors has been declaratively changed
@@ -433,11 +915,18 @@ impl ApplicationHandler<EventPayload> for Application<'_> {
                         .send_bytes(format(rgb).into_bytes());
                 }
             }
-            RioEventType::Rio(RioEvent::CreateWindow) => {
+            RioEventType::Rio(RioEvent::CreateWindow(working_dir_overwrite)) => {
+                let config = match working_dir_overwrite {
+                    Some(working_dir) => rio_backend::config::Config {
+                        working_dir: Some(working_dir),
+                        ..self.config.clone()
+                    },
+                    None => self.config.clone(),
+                };
                 self.router.create_window(
                     event_loop,
                     self.event_proxy.clone(),
-                    &self.config,
+                    &config,
                     None,
                 );
             }
@@ -608,15 +1097,17 @@ impl ApplicationHandler<EventPayload> for Application<'_> {
 
         match event {
             WindowEvent::CloseRequested => {
-                self.router.routes.remove(&window_id);
-
-                if cfg!(target_os = "macos") && self.config.confirm_before_quit {
-                    return;
+                // Closing again while asked confirms it
+                if route.path != RoutePath::ConfirmClose {
+                    let running_processes = route.running_processes();
+                    if self.config.confirm_quit.should_confirm(&running_processes) {
+                        route.confirm_close(running_processes);
+                        route.request_redraw();
+                        return;
+                    }
                 }
 
-                if self.router.routes.is_empty() {
-                    event_loop.exit();
-                }
+                self.close_window(event_loop, window_id);
             }
 
             WindowEvent::ModifiersChanged(modifiers) => {
@@ -628,7 +1119,7 @@ impl ApplicationHandler<EventPayload> for Application<'_> {
                     }
 
                     route.window.winit_window.set_cursor(CursorIcon::Pointer);
-                    route.window.screen.context_manager.schedule_render(60);
+                    route.window.screen.context_manager.schedule_next_frame();
                 }
             }
 
@@ -667,6 +1158,15 @@ impl ApplicationHandler<EventPayload> for Application<'_> {
                             return;
                         }
 
+                        if button == MouseButton::Left
+                            && (route.window.screen.click_tab_bar()
+                                || route.window.screen.click_minimap()
+                                || route.window.screen.start_scrollbar_drag())
+                        {
+                            route.request_redraw();
+                            return;
+                        }
+
                         // Process mouse press before bindings to update the `click_state`.
                         if !route.window.screen.modifiers.state().shift_key()
                             && route.window.screen.mouse_mode()
@@ -729,6 +1229,13 @@ impl ApplicationHandler<EventPayload> for Application<'_> {
                         route.window.screen.process_mouse_bindings(button);
                     }
                     ElementState::Released => {
+                        if button == MouseButton::Left
+                            && route.window.screen.end_scrollbar_drag()
+                        {
+                            route.request_redraw();
+                            return;
+                        }
+
                         if !route.window.screen.modifiers.state().shift_key()
                             && route.window.screen.mouse_mode()
                         {
@@ -748,6 +1255,17 @@ impl ApplicationHandler<EventPayload> for Application<'_> {
                             return;
                         }
 
+                        // A click without dragging within the prompt moves its cursor.
+                        if button == MouseButton::Left
+                            && self.config.click_to_move_cursor
+                            && route.window.screen.mouse.click_state == ClickState::Click
+                            && route.window.screen.selection_is_empty()
+                        {
+                            let display_offset = route.window.screen.display_offset();
+                            let pos = route.window.screen.mouse_position(display_offset);
+                            route.window.screen.move_prompt_cursor(pos);
+                        }
+
                         if let MouseButton::Left | MouseButton::Right = button {
                             // Copy selection on release, to prevent flooding the display server.
                             route.window.screen.copy_selection(ClipboardType::Selection);
@@ -807,6 +1325,12 @@ impl ApplicationHandler<EventPayload> for Application<'_> {
                 route.window.screen.mouse.x = x;
                 route.window.screen.mouse.y = y;
 
+                if route.window.screen.is_dragging_scrollbar() {
+                    route.window.screen.drag_scrollbar();
+                    route.window.screen.context_manager.schedule_next_frame();
+                    return;
+                }
+
                 let point = route.window.screen.mouse_position(display_offset);
 
                 let square_changed = old_point != point;
@@ -824,7 +1348,7 @@ impl ApplicationHandler<EventPayload> for Application<'_> {
 
                 if route.window.screen.search_nearest_hyperlink_from_pos() {
                     route.window.winit_window.set_cursor(CursorIcon::Pointer);
-                    route.window.screen.context_manager.schedule_render(60);
+                    route.window.screen.context_manager.schedule_next_frame();
                 } else {
                     let cursor_icon =
                         if !route.window.screen.modifiers.state().shift_key()
@@ -840,7 +1364,7 @@ impl ApplicationHandler<EventPayload> for Application<'_> {
                     // In case hyperlink range has cleaned trigger one more render
                     if route.window.screen.renderer.has_hyperlink_range() {
                         route.window.screen.renderer.set_hyperlink_range(None);
-                        route.window.screen.context_manager.schedule_render(60);
+                        route.window.screen.context_manager.schedule_next_frame();
                     }
                 }
 
@@ -852,7 +1376,7 @@ impl ApplicationHandler<EventPayload> for Application<'_> {
                         || !route.window.screen.mouse_mode())
                 {
                     route.window.screen.update_selection(point, square_side);
-                    route.window.screen.context_manager.schedule_render(60);
+                    route.window.screen.context_manager.schedule_next_frame();
                 } else if square_changed
                     && route.window.screen.has_mouse_motion_and_drag()
                 {
@@ -933,6 +1457,8 @@ impl ApplicationHandler<EventPayload> for Application<'_> {
 
                 route.window.screen.renderer.last_typing = Some(Instant::now());
                 route.window.screen.process_key_event(&key_event);
+                // Bindings may have switched tabs or toggled the input method.
+                route.window.sync_ime();
 
                 if key_event.state == ElementState::Released
                     && self.config.hide_cursor_when_typing
@@ -980,18 +1506,23 @@ impl ApplicationHandler<EventPayload> for Application<'_> {
                     route.window.winit_window.set_cursor_visible(true);
                 }
 
-                // let has_regained_focus = !route.window.is_focused && focused;
-                // route.window.is_focused = focused;
+                let has_regained_focus = !route.window.is_focused && focused;
+                let has_changed = route.window.is_focused != focused;
+                route.window.set_focused(focused);
 
-                // if has_regained_focus {
-                // route.request_redraw();
-                // }
+                // Frames were throttled while in the background, and
+                // dimming follows the focus.
+                if has_regained_focus
+                    || (has_changed && route.window.screen.renderer.dims_inactive())
+                {
+                    route.request_redraw();
+                }
 
                 route.window.screen.on_focus_change(focused);
             }
 
             WindowEvent::Occluded(occluded) => {
-                route.window.is_occluded = occluded;
+                route.window.set_occluded(occluded);
             }
 
             WindowEvent::ThemeChanged(new_theme) => {
@@ -1001,6 +1532,11 @@ impl ApplicationHandler<EventPayload> for Application<'_> {
                     .screen
                     .update_config(&self.config, &self.router.font_library);
                 route.window.configure_window(&self.config);
+                route
+                    .window
+                    .screen
+                    .context_manager
+                    .reset_profile(&self.config.profiles);
             }
 
             WindowEvent::DroppedFile(path) => {
@@ -1020,6 +1556,11 @@ impl ApplicationHandler<EventPayload> for Application<'_> {
                 route.window.screen.resize(new_size);
             }
 
+            // The window may be on a display with another refresh rate.
+            WindowEvent::Moved(_) => {
+                route.window.update_vblank_interval();
+            }
+
             WindowEvent::ScaleFactorChanged {
                 inner_size_writer: _,
                 scale_factor,
@@ -1043,13 +1584,40 @@ impl ApplicationHandler<EventPayload> for Application<'_> {
                         route.window.screen.render_welcome();
                     }
                     RoutePath::Terminal => {
+                        route.window.sync_ime();
                         route.window.screen.render();
+
+                        #[cfg(unix)]
+                        if let Some(control) = &self.control {
+                            route
+                                .window
+                                .screen
+                                .publish_control_changes(control, window_id.into());
+                        }
                     }
                     RoutePath::ConfirmQuit => {
-                        route
-                            .window
-                            .screen
-                            .render_dialog("Do you want to leave Rio?");
+                        route.window.screen.render_dialog(
+                            "Do you want to leave Rio?",
+                            "quit",
+                            &route.running_processes,
+                        );
+                    }
+                    RoutePath::ConfirmClose => {
+                        route.window.screen.render_dialog(
+                            "Do you want to close this window?",
+                            "quit",
+                            &route.running_processes,
+                        );
+                    }
+                    RoutePath::ConfirmPrint => {
+                        route.window.screen.render_dialog(
+                            "Do you want to print?",
+                            "print",
+                            &[],
+                        );
+                    }
+                    RoutePath::Diff => {
+                        route.window.screen.render_pane_diff();
                     }
                 }
                 // println!("Time elapsed in render() is: {:?}", duration);
@@ -1132,6 +1700,7 @@ impl ApplicationHandler<EventPayload> for Application<'_> {
             &mut std::rc::Rc::new(std::cell::RefCell::new(Clipboard::new_nop())),
         );
 
+        crate::crash_loop::clear();
         std::process::exit(0);
     }
 }
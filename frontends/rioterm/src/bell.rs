@@ -0,0 +1,192 @@
+// Sounds played for BEL. Players are spawned in their own thread so a slow
+// or missing player never blocks the event loop. Without an active audio
+// output, e.g. muted or with headphones unplugged and no speakers, the sound
+// is skipped and the caller is told so it can fall back to something visible.
+
+use rio_backend::config::bell::{Bell, BellSound};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// Bells arriving faster than this only play one sound, e.g. `cat` of a
+/// binary file.
+const MIN_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Default)]
+pub struct BellPlayer {
+    last_played: Option<Instant>,
+}
+
+impl BellPlayer {
+    /// Plays the bell sound, `unheard` is called from the player thread
+    /// instead when no audio output is active.
+    pub fn ring(&mut self, config: &Bell, unheard: impl FnOnce() + Send + 'static) {
+        if config.sound == BellSound::None {
+            return;
+        }
+
+        let now = Instant::now();
+        if self
+            .last_played
+            .is_some_and(|last_played| now.duration_since(last_played) < MIN_INTERVAL)
+        {
+            return;
+        }
+        self.last_played = Some(now);
+
+        let sound = config.sound;
+        let sound_file = config.sound_file.clone();
+        rio_backend::performer::spawn_named("bell", move || {
+            if is_audio_output_active() == Some(false) {
+                unheard();
+                return;
+            }
+
+            match sound {
+                BellSound::None => {}
+                BellSound::System => play_system_sound(),
+                BellSound::File => match &sound_file {
+                    Some(path) => play_file(path),
+                    None => {
+                        tracing::warn!("bell: sound is \"file\" but sound-file is unset")
+                    }
+                },
+            }
+        });
+    }
+}
+
+/// Whether the default audio output can be heard, `None` when it's unknown.
+#[cfg(target_os = "macos")]
+fn is_audio_output_active() -> Option<bool> {
+    // `missing value` without any output device.
+    let muted = query(&["osascript", "-e", "output muted of (get volume settings)"])?;
+    Some(muted.trim() == "false")
+}
+
+#[cfg(windows)]
+fn is_audio_output_active() -> Option<bool> {
+    None
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+fn is_audio_output_active() -> Option<bool> {
+    if let Some(output) = query(&["pactl", "get-sink-mute", "@DEFAULT_SINK@"]) {
+        return Some(parse_pactl_mute(&output) == Some(false));
+    }
+
+    let output = query(&["wpctl", "get-volume", "@DEFAULT_AUDIO_SINK@"])?;
+    Some(parse_wpctl_volume(&output).is_some_and(|volume| volume > 0.))
+}
+
+/// `Mute: no` of `pactl get-sink-mute`.
+#[cfg(not(any(target_os = "macos", windows)))]
+fn parse_pactl_mute(output: &str) -> Option<bool> {
+    match output.trim().strip_prefix("Mute:")?.trim() {
+        "yes" => Some(true),
+        "no" => Some(false),
+        _ => None,
+    }
+}
+
+/// Volume of `wpctl get-volume`, e.g. `Volume: 0.40` or
+/// `Volume: 0.40 [MUTED]` which is zero.
+#[cfg(not(any(target_os = "macos", windows)))]
+fn parse_wpctl_volume(output: &str) -> Option<f32> {
+    let output = output.trim().strip_prefix("Volume:")?;
+    if output.contains("[MUTED]") {
+        return Some(0.);
+    }
+
+    output.split_whitespace().next()?.parse().ok()
+}
+
+/// Output of a command, `None` when it couldn't run at all. A failing
+/// command gives an empty output, e.g. `pactl` without any sink.
+fn query(command: &[&str]) -> Option<String> {
+    let output = Command::new(command[0])
+        .args(&command[1..])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return Some(String::new());
+    }
+
+    Some(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+#[cfg(target_os = "macos")]
+fn play_system_sound() {
+    #[link(name = "AppKit", kind = "framework")]
+    extern "C" {
+        fn NSBeep();
+    }
+
+    unsafe { NSBeep() };
+}
+
+#[cfg(windows)]
+fn play_system_sound() {
+    use windows_sys::Win32::System::Diagnostics::Debug::MessageBeep;
+    use windows_sys::Win32::UI::WindowsAndMessaging::MB_OK;
+
+    unsafe { MessageBeep(MB_OK) };
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+fn play_system_sound() {
+    run_player(&[&["canberra-gtk-play", "--id", "bell"]]);
+}
+
+fn play_file(path: &str) {
+    #[cfg(target_os = "macos")]
+    run_player(&[&["afplay", path]]);
+
+    #[cfg(windows)]
+    {
+        let script = format!(
+            "(New-Object Media.SoundPlayer '{}').PlaySync()",
+            path.replace('\'', "''")
+        );
+        run_player(&[&["powershell", "-NoProfile", "-Command", &script]]);
+    }
+
+    #[cfg(not(any(target_os = "macos", windows)))]
+    run_player(&[&["paplay", path], &["aplay", "-q", path]]);
+}
+
+/// Run the first player that exists and wait for it, so it doesn't linger.
+fn run_player(players: &[&[&str]]) {
+    for player in players {
+        let status = Command::new(player[0])
+            .args(&player[1..])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status();
+        match status {
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(err) => tracing::warn!("bell: failed to run {}: {err}", player[0]),
+            Ok(_) => {}
+        }
+        return;
+    }
+}
+
+#[cfg(test)]
+#[cfg(not(any(target_os = "macos", windows)))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_audio_output() {
+        assert_eq!(parse_pactl_mute("Mute: no\n"), Some(false));
+        assert_eq!(parse_pactl_mute("Mute: yes\n"), Some(true));
+        assert_eq!(parse_pactl_mute(""), None);
+
+        assert_eq!(parse_wpctl_volume("Volume: 0.40\n"), Some(0.4));
+        assert_eq!(parse_wpctl_volume("Volume: 0.40 [MUTED]\n"), Some(0.));
+        assert_eq!(parse_wpctl_volume(""), None);
+    }
+}
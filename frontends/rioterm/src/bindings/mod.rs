@@ -231,6 +231,7 @@ impl From<String> for Action {
                 Some(Action::Search(SearchAction::SearchHistoryPrevious))
             }
             "clearhistory" => Some(Action::ClearHistory),
+            "clearscreenandhistory" => Some(Action::ClearScreenAndHistory),
             "resetfontsize" => Some(Action::ResetFontSize),
             "increasefontsize" => Some(Action::IncreaseFontSize),
             "decreasefontsize" => Some(Action::DecreaseFontSize),
@@ -248,6 +249,17 @@ impl From<String> for Action {
             "scrolltotop" => Some(Action::ScrollToTop),
             "scrolltobottom" => Some(Action::ScrollToBottom),
             "togglevimode" => Some(Action::ToggleViMode),
+            "jumptopreviousprompt" => Some(Action::JumpToPreviousPrompt),
+            "jumptonextprompt" => Some(Action::JumpToNextPrompt),
+            "selectlastcommandoutput" => Some(Action::SelectLastCommandOutput),
+            "respawnshell" => Some(Action::RespawnShell),
+            "respawnshellandclearhistory" => Some(Action::RespawnShellAndClearHistory),
+            "print" => Some(Action::Print),
+            "snapshotpane" => Some(Action::SnapshotPane),
+            "diffsnapshot" => Some(Action::DiffSnapshot),
+            "diffsnapshots" => Some(Action::DiffSnapshots),
+            "togglepredictiveecho" => Some(Action::TogglePredictiveEcho),
+            "toggleime" => Some(Action::ToggleIme),
             "none" => Some(Action::None),
             _ => None,
         };
@@ -368,6 +380,43 @@ pub enum Action {
     /// Clear the display buffer(s) to remove history.
     ClearHistory,
 
+    /// Clear the screen and the history, keeping the current prompt.
+    ClearScreenAndHistory,
+
+    /// Scroll to the previous shell prompt (requires OSC 133).
+    JumpToPreviousPrompt,
+
+    /// Scroll to the next shell prompt (requires OSC 133).
+    JumpToNextPrompt,
+
+    /// Select and copy the output of the last command (requires OSC 133).
+    SelectLastCommandOutput,
+
+    /// Kill the process of the current tab and spawn the shell again in the
+    /// same directory, keeping the scrollback.
+    RespawnShell,
+
+    /// Same as `RespawnShell` but starting with an empty scrollback.
+    RespawnShellAndClearHistory,
+
+    /// Print the selection, or the scrollback when nothing is selected.
+    Print,
+
+    /// Snapshot the visible text of the current pane.
+    SnapshotPane,
+
+    /// Diff the last pane snapshot against the live content.
+    DiffSnapshot,
+
+    /// Diff the two last pane snapshots.
+    DiffSnapshots,
+
+    /// Toggle predictive local echo for the current tab.
+    TogglePredictiveEcho,
+
+    /// Allow or disallow the input method for the current tab.
+    ToggleIme,
+
     /// Hide the Rio window.
     #[allow(dead_code)]
     Hide,
@@ -538,6 +587,12 @@ macro_rules! trigger {
             location: KeyLocation::Standard,
         }
     }};
+    (KeyBinding, $key:expr, $location:expr) => {{
+        BindingKey::Keycode {
+            key: $key,
+            location: $location,
+        }
+    }};
     ($ty:ident, $key:expr,) => {{
         $key
     }};
@@ -582,6 +637,38 @@ pub fn default_key_bindings(
         Key::Named(ArrowLeft),  +BindingMode::APP_CURSOR, ~BindingMode::VI;
             Action::Esc("\x1bOD".into());
 
+        // Keypad, arrows are sent by the keypad when num lock is off.
+        Key::Named(Home) => KeyLocation::Numpad, ~BindingMode::APP_CURSOR, ~BindingMode::VI, ~BindingMode::SEARCH, ~BindingMode::DISAMBIGUATE_KEYS; Action::Esc("\x1b[H".into());
+        Key::Named(End) => KeyLocation::Numpad, ~BindingMode::APP_CURSOR, ~BindingMode::VI, ~BindingMode::SEARCH, ~BindingMode::DISAMBIGUATE_KEYS; Action::Esc("\x1b[F".into());
+        Key::Named(ArrowUp) => KeyLocation::Numpad, ~BindingMode::APP_CURSOR, ~BindingMode::VI, ~BindingMode::SEARCH, ~BindingMode::DISAMBIGUATE_KEYS; Action::Esc("\x1b[A".into());
+        Key::Named(ArrowDown) => KeyLocation::Numpad, ~BindingMode::APP_CURSOR, ~BindingMode::VI, ~BindingMode::SEARCH, ~BindingMode::DISAMBIGUATE_KEYS; Action::Esc("\x1b[B".into());
+        Key::Named(ArrowRight) => KeyLocation::Numpad, ~BindingMode::APP_CURSOR, ~BindingMode::VI, ~BindingMode::SEARCH, ~BindingMode::DISAMBIGUATE_KEYS; Action::Esc("\x1b[C".into());
+        Key::Named(ArrowLeft) => KeyLocation::Numpad, ~BindingMode::APP_CURSOR, ~BindingMode::VI, ~BindingMode::SEARCH, ~BindingMode::DISAMBIGUATE_KEYS; Action::Esc("\x1b[D".into());
+        Key::Named(Home) => KeyLocation::Numpad, +BindingMode::APP_CURSOR, ~BindingMode::VI, ~BindingMode::DISAMBIGUATE_KEYS; Action::Esc("\x1bOH".into());
+        Key::Named(End) => KeyLocation::Numpad, +BindingMode::APP_CURSOR, ~BindingMode::VI, ~BindingMode::DISAMBIGUATE_KEYS; Action::Esc("\x1bOF".into());
+        Key::Named(ArrowUp) => KeyLocation::Numpad, +BindingMode::APP_CURSOR, ~BindingMode::VI, ~BindingMode::DISAMBIGUATE_KEYS; Action::Esc("\x1bOA".into());
+        Key::Named(ArrowDown) => KeyLocation::Numpad, +BindingMode::APP_CURSOR, ~BindingMode::VI, ~BindingMode::DISAMBIGUATE_KEYS; Action::Esc("\x1bOB".into());
+        Key::Named(ArrowRight) => KeyLocation::Numpad, +BindingMode::APP_CURSOR, ~BindingMode::VI, ~BindingMode::DISAMBIGUATE_KEYS; Action::Esc("\x1bOC".into());
+        Key::Named(ArrowLeft) => KeyLocation::Numpad, +BindingMode::APP_CURSOR, ~BindingMode::VI, ~BindingMode::DISAMBIGUATE_KEYS; Action::Esc("\x1bOD".into());
+        Key::Named(Enter) => KeyLocation::Numpad, +BindingMode::APP_KEYPAD, ~BindingMode::VI, ~BindingMode::SEARCH, ~BindingMode::DISAMBIGUATE_KEYS; Action::Esc("\x1bOM".into());
+        Key::Character("=".into()) => KeyLocation::Numpad, +BindingMode::APP_KEYPAD, ~BindingMode::VI, ~BindingMode::SEARCH, ~BindingMode::DISAMBIGUATE_KEYS; Action::Esc("\x1bOX".into());
+        Key::Character("*".into()) => KeyLocation::Numpad, +BindingMode::APP_KEYPAD, ~BindingMode::VI, ~BindingMode::SEARCH, ~BindingMode::DISAMBIGUATE_KEYS; Action::Esc("\x1bOj".into());
+        Key::Character("+".into()) => KeyLocation::Numpad, +BindingMode::APP_KEYPAD, ~BindingMode::VI, ~BindingMode::SEARCH, ~BindingMode::DISAMBIGUATE_KEYS; Action::Esc("\x1bOk".into());
+        Key::Character(",".into()) => KeyLocation::Numpad, +BindingMode::APP_KEYPAD, ~BindingMode::VI, ~BindingMode::SEARCH, ~BindingMode::DISAMBIGUATE_KEYS; Action::Esc("\x1bOl".into());
+        Key::Character("-".into()) => KeyLocation::Numpad, +BindingMode::APP_KEYPAD, ~BindingMode::VI, ~BindingMode::SEARCH, ~BindingMode::DISAMBIGUATE_KEYS; Action::Esc("\x1bOm".into());
+        Key::Character(".".into()) => KeyLocation::Numpad, +BindingMode::APP_KEYPAD, ~BindingMode::VI, ~BindingMode::SEARCH, ~BindingMode::DISAMBIGUATE_KEYS; Action::Esc("\x1bOn".into());
+        Key::Character("/".into()) => KeyLocation::Numpad, +BindingMode::APP_KEYPAD, ~BindingMode::VI, ~BindingMode::SEARCH, ~BindingMode::DISAMBIGUATE_KEYS; Action::Esc("\x1bOo".into());
+        Key::Character("0".into()) => KeyLocation::Numpad, +BindingMode::APP_KEYPAD, ~BindingMode::VI, ~BindingMode::SEARCH, ~BindingMode::DISAMBIGUATE_KEYS; Action::Esc("\x1bOp".into());
+        Key::Character("1".into()) => KeyLocation::Numpad, +BindingMode::APP_KEYPAD, ~BindingMode::VI, ~BindingMode::SEARCH, ~BindingMode::DISAMBIGUATE_KEYS; Action::Esc("\x1bOq".into());
+        Key::Character("2".into()) => KeyLocation::Numpad, +BindingMode::APP_KEYPAD, ~BindingMode::VI, ~BindingMode::SEARCH, ~BindingMode::DISAMBIGUATE_KEYS; Action::Esc("\x1bOr".into());
+        Key::Character("3".into()) => KeyLocation::Numpad, +BindingMode::APP_KEYPAD, ~BindingMode::VI, ~BindingMode::SEARCH, ~BindingMode::DISAMBIGUATE_KEYS; Action::Esc("\x1bOs".into());
+        Key::Character("4".into()) => KeyLocation::Numpad, +BindingMode::APP_KEYPAD, ~BindingMode::VI, ~BindingMode::SEARCH, ~BindingMode::DISAMBIGUATE_KEYS; Action::Esc("\x1bOt".into());
+        Key::Character("5".into()) => KeyLocation::Numpad, +BindingMode::APP_KEYPAD, ~BindingMode::VI, ~BindingMode::SEARCH, ~BindingMode::DISAMBIGUATE_KEYS; Action::Esc("\x1bOu".into());
+        Key::Character("6".into()) => KeyLocation::Numpad, +BindingMode::APP_KEYPAD, ~BindingMode::VI, ~BindingMode::SEARCH, ~BindingMode::DISAMBIGUATE_KEYS; Action::Esc("\x1bOv".into());
+        Key::Character("7".into()) => KeyLocation::Numpad, +BindingMode::APP_KEYPAD, ~BindingMode::VI, ~BindingMode::SEARCH, ~BindingMode::DISAMBIGUATE_KEYS; Action::Esc("\x1bOw".into());
+        Key::Character("8".into()) => KeyLocation::Numpad, +BindingMode::APP_KEYPAD, ~BindingMode::VI, ~BindingMode::SEARCH, ~BindingMode::DISAMBIGUATE_KEYS; Action::Esc("\x1bOx".into());
+        Key::Character("9".into()) => KeyLocation::Numpad, +BindingMode::APP_KEYPAD, ~BindingMode::VI, ~BindingMode::SEARCH, ~BindingMode::DISAMBIGUATE_KEYS; Action::Esc("\x1bOy".into());
+
         // VI Mode
         Key::Named(Space), ModifiersState::ALT | ModifiersState::SHIFT; Action::ToggleViMode;
         "/", +BindingMode::VI, ~BindingMode::SEARCH; Action::SearchForward;
@@ -616,8 +703,8 @@ pub fn default_key_bindings(
         Key::Named(ArrowDown), +BindingMode::VI; ViMotion::Down;
         Key::Named(ArrowLeft), +BindingMode::VI; ViMotion::Left;
         Key::Named(ArrowRight), +BindingMode::VI; ViMotion::Right;
-        Key::Named(ArrowUp), ModifiersState::SUPER, ~BindingMode::VI; Action::None;
-        Key::Named(ArrowDown), ModifiersState::SUPER, ~BindingMode::VI; Action::None;
+        Key::Named(ArrowUp), ModifiersState::SUPER, ~BindingMode::VI; Action::JumpToPreviousPrompt;
+        Key::Named(ArrowDown), ModifiersState::SUPER, ~BindingMode::VI; Action::JumpToNextPrompt;
         Key::Named(ArrowLeft), ModifiersState::SUPER, ~BindingMode::VI; Action::None;
         Key::Named(ArrowRight), ModifiersState::SUPER, ~BindingMode::VI; Action::None;
         "0",                          +BindingMode::VI, ~BindingMode::SEARCH;
@@ -1105,6 +1192,8 @@ pub fn platform_key_bindings(
         "-", ModifiersState::CONTROL;  Action::DecreaseFontSize;
         "n", ModifiersState::CONTROL | ModifiersState::SHIFT; Action::WindowCreateNew;
         ",", ModifiersState::CONTROL | ModifiersState::SHIFT; Action::ConfigEditor;
+        "z", ModifiersState::CONTROL | ModifiersState::SHIFT, ~BindingMode::VI; Action::JumpToPreviousPrompt;
+        "x", ModifiersState::CONTROL | ModifiersState::SHIFT, ~BindingMode::VI; Action::JumpToNextPrompt;
 
         // Search
         "f", ModifiersState::CONTROL | ModifiersState::SHIFT, ~BindingMode::SEARCH; Action::SearchForward;
@@ -1163,6 +1252,8 @@ pub fn platform_key_bindings(
         // https://github.com/raphamorim/rio/issues/220#issuecomment-1761651339
         Key::Named(Backspace), ModifiersState::CONTROL, ~BindingMode::VI; Action::Esc("\u{0017}".into());
         Key::Named(Space), ModifiersState::CONTROL | ModifiersState::SHIFT; Action::ToggleViMode;
+        "z", ModifiersState::CONTROL | ModifiersState::SHIFT, ~BindingMode::VI; Action::JumpToPreviousPrompt;
+        "x", ModifiersState::CONTROL | ModifiersState::SHIFT, ~BindingMode::VI; Action::JumpToNextPrompt;
 
         // Search
         "f", ModifiersState::CONTROL | ModifiersState::SHIFT, ~BindingMode::SEARCH; Action::SearchForward;
@@ -1476,4 +1567,40 @@ mod tests {
         assert_eq!(new_bindings.len(), 2);
         assert_eq!(new_bindings[1].action, Action::ReceiveChar);
     }
+
+    #[test]
+    fn keypad_application_mode() {
+        let bindings = default_key_bindings(vec![], false, ConfigKeyboard::default());
+        let esc = |key: Key, mode: BindingMode| {
+            let trigger = BindingKey::Keycode {
+                key,
+                location: KeyLocation::Numpad,
+            };
+            bindings
+                .iter()
+                .find(|b| {
+                    b.is_triggered_by(mode.clone(), ModifiersState::empty(), &trigger)
+                })
+                .map(|b| b.action.clone())
+        };
+
+        let one = Key::Character("1".into());
+        assert_eq!(esc(one.clone(), BindingMode::empty()), None);
+        assert_eq!(
+            esc(one, BindingMode::APP_KEYPAD),
+            Some(Action::Esc("\x1bOq".into()))
+        );
+        assert_eq!(
+            esc(Key::Named(Enter), BindingMode::APP_KEYPAD),
+            Some(Action::Esc("\x1bOM".into()))
+        );
+        assert_eq!(
+            esc(Key::Named(ArrowUp), BindingMode::empty()),
+            Some(Action::Esc("\x1b[A".into()))
+        );
+        assert_eq!(
+            esc(Key::Named(ArrowUp), BindingMode::APP_CURSOR),
+            Some(Action::Esc("\x1bOA".into()))
+        );
+    }
 }
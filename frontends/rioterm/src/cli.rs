@@ -27,6 +27,15 @@ pub struct TerminalOptions {
     #[clap(short = 'e', long, allow_hyphen_values = true, num_args = 1..)]
     pub command: Vec<String>,
 
+    /// Keep the window open after the command exits, showing its exit status.
+    #[clap(long)]
+    pub hold: bool,
+
+    /// Show the output piped into Rio instead of spawning a shell, with
+    /// scrollback and search, e.g. `git log -p --color | rio --stdin`.
+    #[clap(long)]
+    pub stdin: bool,
+
     /// Start the shell in the specified working directory.
     #[clap(short, long, value_hint = ValueHint::FilePath)]
     pub working_dir: Option<String>,
@@ -38,6 +47,28 @@ pub struct TerminalOptions {
     /// Writes the logs to a file inside the config directory.
     #[clap(long)]
     pub enable_log_file: bool,
+
+    /// Starts with the built-in defaults, ignoring the config file, themes
+    /// and background shaders. Useful to fix a config that breaks Rio.
+    #[clap(long)]
+    pub safe_mode: bool,
+
+    /// Keeps the config, themes and logs in a `rio` directory next to the
+    /// executable instead of the user directories.
+    #[clap(long)]
+    pub portable: bool,
+
+    /// Prints the rio terminfo source, e.g. `rio --print-terminfo | tic -x -`.
+    #[clap(long)]
+    pub print_terminfo: bool,
+
+    /// Compiles the rio terminfo into `~/.terminfo` with `tic`.
+    #[clap(long)]
+    pub install_terminfo: bool,
+
+    /// Checks the environment used by spawned shells, like the locale.
+    #[clap(long)]
+    pub doctor: bool,
 }
 
 impl TerminalOptions {
@@ -51,6 +82,8 @@ impl TerminalOptions {
         Some(Shell {
             program: program.clone(),
             args: args.to_vec(),
+            env: vec![],
+            login: None,
         })
     }
 
@@ -0,0 +1,103 @@
+// Suggestions for commands that weren't found, sourced from a helper like
+// `pkgfile` or `command-not-found`. The helper runs in its own thread and
+// its output is shown as a toast, the shell itself is left untouched.
+
+use rio_backend::config::command_not_found::CommandNotFound;
+use rio_backend::event::{EventProxy, RioEvent, RioEventType, WindowId};
+use std::process::{Command, Stdio};
+
+// Exit status of shells for a command that wasn't found.
+pub const EXIT_CODE: i32 = 127;
+const MAX_LINES: usize = 8;
+
+pub fn suggest(
+    config: &CommandNotFound,
+    command_line: &str,
+    event_proxy: EventProxy,
+    window_id: WindowId,
+) {
+    let (Some(helper), Some(program)) = (&config.program, missing_program(command_line))
+    else {
+        return;
+    };
+
+    let mut command = Command::new(helper);
+    command
+        .args(&config.args)
+        // Programs like `-rf` must not be taken as options of the helper.
+        .arg("--")
+        .arg(program)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    rio_backend::performer::spawn_named("command-not-found", move || {
+        let output = match command.output() {
+            Ok(output) => output,
+            Err(err) => {
+                tracing::warn!("failed to run the command-not-found helper: {err}");
+                return;
+            }
+        };
+
+        // `pkgfile` prints to stdout while `command-not-found` uses stderr.
+        let output = if output.stdout.iter().all(u8::is_ascii_whitespace) {
+            output.stderr
+        } else {
+            output.stdout
+        };
+        if let Some(message) = toast_message(&String::from_utf8_lossy(&output)) {
+            event_proxy
+                .send_event(RioEventType::Rio(RioEvent::Toast(message)), window_id);
+        }
+    });
+}
+
+/// Program of a command line, skipping leading variable assignments.
+fn missing_program(command_line: &str) -> Option<&str> {
+    command_line
+        .split_whitespace()
+        .find(|word| !word.contains('='))
+}
+
+fn toast_message(output: &str) -> Option<String> {
+    let lines: Vec<&str> = output
+        .lines()
+        .map(str::trim_end)
+        .filter(|line| !line.trim().is_empty())
+        .take(MAX_LINES)
+        .collect();
+    if lines.is_empty() {
+        return None;
+    }
+
+    Some(lines.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_program() {
+        assert_eq!(missing_program("htop"), Some("htop"));
+        assert_eq!(missing_program("  cowsay hello"), Some("cowsay"));
+        assert_eq!(missing_program("FOO=1 BAR=2 sl -a"), Some("sl"));
+        assert_eq!(missing_program("FOO=1"), None);
+        assert_eq!(missing_program(""), None);
+    }
+
+    #[test]
+    fn test_toast_message() {
+        assert_eq!(toast_message(""), None);
+        assert_eq!(toast_message("\n  \n"), None);
+        assert_eq!(
+            toast_message("\nCommand 'sl' not found, but can be installed with:\n\nsudo apt install sl\n")
+                .as_deref(),
+            Some("Command 'sl' not found, but can be installed with:\nsudo apt install sl")
+        );
+        assert_eq!(
+            toast_message(&"line\n".repeat(20)).unwrap().lines().count(),
+            8
+        );
+    }
+}
@@ -1,44 +1,158 @@
 use crate::ansi::CursorShape;
 use crate::crosswords::pos::CursorState;
-use crate::event::sync::FairMutex;
+use crate::event::sync::{FairMutex, FrameCounter};
 use crate::event::RioEvent;
 use crate::messenger::Messenger;
-use crate::performer::Machine;
-use rio_backend::config::Shell;
+use rio_backend::config::predictive_echo::PredictiveEcho;
+use rio_backend::config::print::Print;
+use rio_backend::config::profiles::{find_profile, Profile};
+use rio_backend::config::serial::Serial;
+use rio_backend::config::title::TitleVars;
+use rio_backend::config::{Scroll, Shell};
+use rio_backend::crosswords::grid::Dimensions;
+use rio_backend::crosswords::prediction::Predictions;
 use rio_backend::crosswords::CrosswordsSize;
 use rio_backend::crosswords::{Crosswords, MIN_COLUMNS, MIN_LINES};
+use rio_backend::embed::{Terminal, TerminalOptions};
 use rio_backend::error::{RioError, RioErrorLevel, RioErrorType};
 use rio_backend::event::EventListener;
 use rio_backend::event::WindowId;
-use rio_backend::sugarloaf::layout::SugarloafLayout;
+use rio_backend::sugarloaf::layout::{SugarDimensions, SugarloafLayout};
 use rio_backend::sugarloaf::{font::SugarloafFont, SugarloafErrors};
-use std::borrow::Cow;
 use std::collections::HashMap;
 use std::error::Error;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
-#[cfg(target_os = "windows")]
-use teletypewriter::create_pty;
-#[cfg(not(target_os = "windows"))]
-use teletypewriter::{create_pty_with_fork, create_pty_with_spawn};
-
 const DEFAULT_CONTEXT_CAPACITY: usize = 28;
 
 pub struct Context<T: EventListener> {
     pub route_id: usize,
     pub terminal: Arc<FairMutex<Crosswords<T>>>,
+    /// Frames drawn of the terminal, shared with its output parser.
+    pub frames: Arc<FrameCounter>,
     pub messenger: Messenger,
+    /// Program or device of the context, stops reading it when dropped.
+    #[cfg_attr(target_os = "windows", allow(dead_code))]
+    pub process: Option<Terminal<T>>,
+    pub dimension: ContextDimension,
     #[cfg(not(target_os = "windows"))]
     pub main_fd: Arc<i32>,
     #[cfg(not(target_os = "windows"))]
     pub shell_pid: u32,
+    /// Last CPU time of the foreground process and when it was sampled.
+    #[cfg(not(target_os = "windows"))]
+    pub usage_sample: Option<(Instant, Duration)>,
+    /// Whether the input method is allowed while this context is focused.
+    pub ime_allowed: bool,
+}
+
+impl<T: EventListener> Context<T> {
+    /// Resizes the grid and the PTY of this context, returns false
+    /// if the dimension didn't change.
+    pub fn resize(&mut self, dimension: ContextDimension) -> bool {
+        if self.dimension == dimension {
+            return false;
+        }
+
+        self.dimension = dimension;
+        self.terminal.lock().resize(dimension);
+        let _ = self.messenger.send_resize(dimension.winsize());
+        true
+    }
+
+    /// Samples the foreground process, CPU usage is measured from the
+    /// previous sample so the first one reports 0%.
+    #[cfg(not(target_os = "windows"))]
+    pub fn sample_process_usage(&mut self) -> Option<ProcessUsage> {
+        let usage =
+            teletypewriter::foreground_process_usage(*self.main_fd, self.shell_pid)?;
+        let now = Instant::now();
+
+        let (cpu_time, elapsed) = match self.usage_sample {
+            // The foreground process may have changed since then.
+            Some((sampled_at, cpu_time)) => (
+                usage.cpu_time.saturating_sub(cpu_time),
+                now.duration_since(sampled_at),
+            ),
+            None => (Duration::ZERO, Duration::ZERO),
+        };
+        self.usage_sample = Some((now, usage.cpu_time));
+
+        Some(ProcessUsage::new(cpu_time, elapsed, usage.memory))
+    }
+}
+
+/// Area of the window drawn by a context. Every context of a window
+/// has its own, so they can be laid out next to each other.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ContextDimension {
+    pub columns: usize,
+    pub lines: usize,
+    /// Size in pixels of the text area.
+    pub width: f32,
+    pub height: f32,
+    pub dimension: SugarDimensions,
+}
+
+impl ContextDimension {
+    #[inline]
+    pub fn winsize(&self) -> teletypewriter::WinsizeBuilder {
+        teletypewriter::WinsizeBuilder {
+            width: self.width as u16,
+            height: self.height as u16,
+            cols: self.columns as u16,
+            rows: self.lines as u16,
+        }
+    }
+}
+
+impl From<&SugarloafLayout> for ContextDimension {
+    fn from(layout: &SugarloafLayout) -> Self {
+        let winsize = crate::renderer::utils::terminal_dimensions(layout);
+        Self {
+            columns: layout.columns,
+            lines: layout.lines,
+            width: winsize.width as f32,
+            height: winsize.height as f32,
+            dimension: layout.dimensions,
+        }
+    }
+}
+
+impl Dimensions for ContextDimension {
+    #[inline]
+    fn columns(&self) -> usize {
+        self.columns
+    }
+
+    #[inline]
+    fn screen_lines(&self) -> usize {
+        self.lines
+    }
+
+    #[inline]
+    fn total_lines(&self) -> usize {
+        self.screen_lines()
+    }
+
+    fn square_width(&self) -> f32 {
+        self.dimension.width
+    }
+
+    fn square_height(&self) -> f32 {
+        self.dimension.height
+    }
 }
 
 impl<T: rio_backend::event::EventListener> Drop for Context<T> {
     fn drop(&mut self) {
+        // Attached and dead contexts have no shell process.
         #[cfg(not(target_os = "windows"))]
-        teletypewriter::kill_pid(self.shell_pid as i32);
+        if let Some(pid) = self.process.as_ref().and_then(Terminal::pid) {
+            teletypewriter::kill_pid(pid as i32);
+        }
     }
 }
 
@@ -52,11 +166,63 @@ pub struct ContextManagerConfig {
     pub use_current_path: bool,
     pub is_native: bool,
     pub should_update_titles: bool,
+    pub process_usage: bool,
+    pub profiles: Vec<Profile>,
+    pub title_template: Option<String>,
+    pub print: Print,
+    pub allow_private_graphics: bool,
+    pub allow_window_ops: bool,
+    pub osc_hooks: Option<std::ops::RangeInclusive<u16>>,
+    pub hold: bool,
+    pub predictive_echo: PredictiveEcho,
+    pub serial: Serial,
+    pub scroll: Scroll,
+    /// Exported to the shells as `RIO_CONTROL_SOCKET`.
+    pub control_socket: Option<PathBuf>,
+}
+
+/// Usage of a foreground process, `cpu` is the percentage of one core
+/// used since the previous sample.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProcessUsage {
+    pub cpu: u32,
+    pub memory: u64,
+}
+
+impl ProcessUsage {
+    pub fn new(cpu_time: Duration, elapsed: Duration, memory: u64) -> ProcessUsage {
+        let cpu = if elapsed.is_zero() {
+            0
+        } else {
+            (cpu_time.as_secs_f64() / elapsed.as_secs_f64() * 100.).round() as u32
+        };
+
+        ProcessUsage { cpu, memory }
+    }
+}
+
+impl std::fmt::Display for ProcessUsage {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        const MIB: u64 = 1024 * 1024;
+        const GIB: u64 = 1024 * MIB;
+
+        if self.memory >= GIB {
+            let memory = self.memory as f64 / GIB as f64;
+            write!(f, "{}% {:.1}G", self.cpu, memory)
+        } else {
+            write!(f, "{}% {}M", self.cpu, self.memory / MIB)
+        }
+    }
 }
 
 pub struct ContextManagerTitles {
     last_title_update: Instant,
-    pub titles: HashMap<usize, [String; 3]>,
+    /// Program, title set by the program, path, command line and nested
+    /// session.
+    pub titles: HashMap<usize, [String; 5]>,
+    /// Titles rendered from `title.content`.
+    pub labels: HashMap<usize, String>,
+    pub usage: HashMap<usize, ProcessUsage>,
     pub key: String,
 }
 
@@ -68,9 +234,15 @@ impl ContextManagerTitles {
         path: String,
     ) -> ContextManagerTitles {
         let last_title_update = Instant::now();
+        let command = program.clone();
         ContextManagerTitles {
             key: format!("{}{}{};", idx, program, terminal_title),
-            titles: HashMap::from([(idx, [program, terminal_title, path])]),
+            titles: HashMap::from([(
+                idx,
+                [program, terminal_title, path, command, String::new()],
+            )]),
+            labels: HashMap::new(),
+            usage: HashMap::new(),
             last_title_update,
         }
     }
@@ -82,8 +254,11 @@ impl ContextManagerTitles {
         program: String,
         terminal_title: String,
         path: String,
+        command: String,
+        session: String,
     ) {
-        self.titles.insert(idx, [program, terminal_title, path]);
+        self.titles
+            .insert(idx, [program, terminal_title, path, command, session]);
     }
 
     #[inline]
@@ -92,6 +267,14 @@ impl ContextManagerTitles {
     }
 }
 
+/// Shown on a tab in the background until it's selected.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TabIndicator {
+    /// The terminal wrote new output.
+    Activity,
+    Bell,
+}
+
 pub struct ContextManager<T: EventListener> {
     contexts: Vec<Context<T>>,
     current_index: usize,
@@ -103,6 +286,12 @@ pub struct ContextManager<T: EventListener> {
     window_id: WindowId,
     pub config: ContextManagerConfig,
     pub titles: ContextManagerTitles,
+    /// Interval between frames of the monitor the window is on.
+    pub frame_interval: Duration,
+    /// Index of the profile matching the current context.
+    profile: Option<usize>,
+    /// Indicators of the tabs in the background, by route.
+    indicators: HashMap<usize, TabIndicator>,
 }
 
 impl<T: EventListener + Clone + std::marker::Send + 'static> ContextManager<T> {
@@ -115,6 +304,7 @@ impl<T: EventListener + Clone + std::marker::Send + 'static> ContextManager<T> {
         let size = CrosswordsSize::new(MIN_COLUMNS, MIN_LINES);
         let terminal =
             Crosswords::new(size, CursorShape::Block, event_proxy, window_id, route_id);
+        let frames = Arc::clone(&terminal.frames);
         let terminal: Arc<FairMutex<Crosswords<T>>> = Arc::new(FairMutex::new(terminal));
         let (sender, _receiver) = corcovado::channel::channel();
 
@@ -124,8 +314,14 @@ impl<T: EventListener + Clone + std::marker::Send + 'static> ContextManager<T> {
             main_fd: Arc::new(-1),
             #[cfg(not(target_os = "windows"))]
             shell_pid: 1,
+            #[cfg(not(target_os = "windows"))]
+            usage_sample: None,
             messenger: Messenger::new(sender),
+            process: None,
+            dimension: ContextDimension::from(&SugarloafLayout::default()),
             terminal,
+            frames,
+            ime_allowed: true,
         }
     }
 
@@ -149,86 +345,130 @@ impl<T: EventListener + Clone + std::marker::Send + 'static> ContextManager<T> {
             route_id,
         );
         terminal.blinking_cursor = cursor_state.1;
+        terminal.default_blinking_cursor = cursor_state.1;
+        terminal.allow_private_graphics = config.allow_private_graphics;
+        terminal.allow_window_ops = config.allow_window_ops;
+        terminal.osc_hooks = config.osc_hooks.clone();
+        terminal.hold = config.hold;
+        terminal.predictions = Predictions::new(&config.predictive_echo);
+        terminal.scroll_to_bottom_on_output = config.scroll.jump_to_bottom_on_output;
         let terminal: Arc<FairMutex<Crosswords<T>>> = Arc::new(FairMutex::new(terminal));
 
-        let pty;
         #[cfg(not(target_os = "windows"))]
-        {
-            if config.use_fork {
-                tracing::info!("rio -> teletypewriter: create_pty_with_fork");
-                pty = match create_pty_with_fork(
-                    &Cow::Borrowed(&config.shell.program),
-                    cols,
-                    rows,
-                ) {
-                    Ok(created_pty) => created_pty,
-                    Err(err) => {
-                        tracing::error!("{err:?}");
-                        return Err(Box::new(err));
-                    }
-                }
-            } else {
-                tracing::info!("rio -> teletypewriter: create_pty_with_spawn");
-                pty = match create_pty_with_spawn(
-                    &Cow::Borrowed(&config.shell.program),
-                    config.shell.args.clone(),
-                    &config.working_dir,
-                    cols,
-                    rows,
-                ) {
-                    Ok(created_pty) => created_pty,
-                    Err(err) => {
-                        tracing::error!("{err:?}");
-                        return Err(Box::new(err));
-                    }
-                }
-            };
+        if let Some(target) = attach_target(config)? {
+            return Self::create_attached_context(
+                &target,
+                terminal,
+                event_proxy,
+                window_id,
+                route_id,
+                ContextDimension::from(&size),
+                config,
+            );
+        }
+
+        let env = context_env_pairs(
+            &config.shell,
+            window_id,
+            route_id,
+            config.control_socket.as_deref(),
+        );
+        let options = TerminalOptions {
+            program: config.shell.program.clone(),
+            args: config.shell.args.clone(),
+            env,
+            login: config.shell.is_login(),
+            #[cfg(not(target_os = "windows"))]
+            use_fork: config.use_fork,
+            working_dir: config.working_dir.clone(),
+            columns: cols as usize,
+            lines: rows as usize,
+            cursor_shape: cursor_state.0.content,
+        };
+        let mut process = Terminal::create(
+            Arc::clone(&terminal),
+            options,
+            event_proxy,
+            window_id,
+            route_id,
+        )?;
+        if config.spawn_performer {
+            process.start();
         }
 
         #[cfg(not(target_os = "windows"))]
-        let main_fd = pty.child.id.clone();
+        let main_fd = process.main_fd().cloned().unwrap_or_default();
         #[cfg(not(target_os = "windows"))]
-        let shell_pid = *pty.child.pid.clone() as u32;
+        let shell_pid = process.pid().unwrap_or_default();
 
-        #[cfg(target_os = "windows")]
-        {
-            pty = match create_pty(
-                &Cow::Borrowed(&config.shell.program),
-                config.shell.args.clone(),
-                &config.working_dir,
-                cols,
-                rows,
-            ) {
-                Ok(created_pty) => created_pty,
-                Err(err) => {
-                    tracing::error!("{err:?}");
-                    return Err(Box::new(err));
-                }
+        let messenger = Messenger::new(process.channel().clone());
+        let frames = Arc::clone(&terminal.lock().frames);
+
+        Ok(Context {
+            route_id,
+            #[cfg(not(target_os = "windows"))]
+            main_fd,
+            #[cfg(not(target_os = "windows"))]
+            shell_pid,
+            #[cfg(not(target_os = "windows"))]
+            usage_sample: None,
+            messenger,
+            process: Some(process),
+            dimension: ContextDimension::from(&size),
+            frames,
+            terminal,
+            ime_allowed: true,
+        })
+    }
+
+    /// Context attached to a serial device, socket, pipe or stdin instead
+    /// of a shell, see [`teletypewriter::Target`].
+    #[cfg(not(target_os = "windows"))]
+    fn create_attached_context(
+        target: &teletypewriter::Target,
+        terminal: Arc<FairMutex<Crosswords<T>>>,
+        event_proxy: T,
+        window_id: WindowId,
+        route_id: usize,
+        dimension: ContextDimension,
+        config: &ContextManagerConfig,
+    ) -> Result<Context<T>, Box<dyn Error>> {
+        tracing::info!("rio -> teletypewriter: attach {target:?}");
+        let attached = match teletypewriter::attach(target) {
+            Ok(attached) => attached,
+            Err(err) => {
+                tracing::error!("{err:?}");
+                return Err(Box::new(err));
             }
+        };
+        if *target == teletypewriter::Target::Stdin {
+            terminal.lock().pager = true;
         }
+        let main_fd = Arc::new(attached.fd());
 
-        let machine = Machine::new(
+        let mut process = Terminal::from_pty(
             Arc::clone(&terminal),
-            pty,
-            event_proxy.clone(),
+            attached,
+            event_proxy,
             window_id,
             route_id,
         )?;
-        let channel = machine.channel();
         if config.spawn_performer {
-            machine.spawn();
+            process.start();
         }
 
-        let messenger = Messenger::new(channel);
-
+        let frames = Arc::clone(&terminal.lock().frames);
         Ok(Context {
             route_id,
-            #[cfg(not(target_os = "windows"))]
             main_fd,
-            #[cfg(not(target_os = "windows"))]
-            shell_pid,
-            messenger,
+            shell_pid: 0,
+            usage_sample: None,
+            messenger: Messenger::new(process.channel().clone()),
+            process: Some(process),
+            dimension,
+            frames,
             terminal,
+            ime_allowed: true,
         })
     }
 
@@ -279,6 +519,10 @@ impl<T: EventListener + Clone + std::marker::Send + 'static> ContextManager<T> {
             ctx_config.working_dir.clone().unwrap_or_default(),
         );
 
+        if ctx_config.process_usage {
+            event_proxy.send_event(RioEvent::SampleProcessUsage, window_id);
+        }
+
         // Sugarloaf has found errors and context need to notify it for the user
         if let Some(errors) = sugarloaf_errors {
             if !errors.fonts_not_found.is_empty() {
@@ -304,6 +548,9 @@ impl<T: EventListener + Clone + std::marker::Send + 'static> ContextManager<T> {
             window_id,
             config: ctx_config,
             titles,
+            frame_interval: Duration::from_micros(16_666),
+            profile: None,
+            indicators: HashMap::new(),
         })
     }
 
@@ -320,11 +567,25 @@ impl<T: EventListener + Clone + std::marker::Send + 'static> ContextManager<T> {
             shell: Shell {
                 program: std::env::var("SHELL").unwrap_or("bash".to_string()),
                 args: vec![],
+                env: vec![],
+                login: Some(false),
             },
             spawn_performer: false,
             is_native: false,
             should_update_titles: false,
+            process_usage: false,
+            profiles: Vec::default(),
+            title_template: None,
+            print: Print::default(),
             use_current_path: false,
+            allow_private_graphics: false,
+            allow_window_ops: false,
+            osc_hooks: None,
+            hold: false,
+            predictive_echo: PredictiveEcho::default(),
+            serial: Serial::default(),
+            scroll: Scroll::default(),
+            control_socket: None,
         };
         let initial_context = ContextManager::create_context(
             (&CursorState::new('_'), false),
@@ -348,6 +609,9 @@ impl<T: EventListener + Clone + std::marker::Send + 'static> ContextManager<T> {
             window_id,
             config,
             titles,
+            frame_interval: Duration::from_micros(16_666),
+            profile: None,
+            indicators: HashMap::new(),
         })
     }
 
@@ -381,6 +645,8 @@ impl<T: EventListener + Clone + std::marker::Send + 'static> ContextManager<T> {
                 }
                 self.contexts.remove(index_to_remove);
                 self.titles.titles.remove(&index_to_remove);
+                self.titles.labels.remove(&index_to_remove);
+                self.titles.usage.remove(&index_to_remove);
 
                 if should_set_current {
                     self.set_current(0);
@@ -397,6 +663,13 @@ impl<T: EventListener + Clone + std::marker::Send + 'static> ContextManager<T> {
             .send_event(RioEvent::PrepareRender(scheduled_time), self.window_id);
     }
 
+    /// Schedules a render for the next frame of the current monitor.
+    #[inline]
+    pub fn schedule_next_frame(&mut self) {
+        let millis = self.frame_interval.as_millis().max(1) as u64;
+        self.schedule_render(millis);
+    }
+
     #[inline]
     pub fn blink_cursor(&mut self, scheduled_time: u64) {
         // PrepareRender will force a render for any route that is focused on window
@@ -407,6 +680,14 @@ impl<T: EventListener + Clone + std::marker::Send + 'static> ContextManager<T> {
         );
     }
 
+    #[inline]
+    pub fn blink_text(&mut self, scheduled_time: u64) {
+        self.event_proxy.send_event(
+            RioEvent::BlinkText(scheduled_time, self.current_route),
+            self.window_id,
+        );
+    }
+
     #[inline]
     pub fn report_error_fonts_not_found(&mut self, fonts_not_found: Vec<SugarloafFont>) {
         if !fonts_not_found.is_empty() {
@@ -424,14 +705,48 @@ impl<T: EventListener + Clone + std::marker::Send + 'static> ContextManager<T> {
 
     #[inline]
     pub fn create_new_window(&self) {
-        self.event_proxy
-            .send_event(RioEvent::CreateWindow, self.window_id);
+        self.event_proxy.send_event(
+            RioEvent::CreateWindow(self.inherited_working_dir()),
+            self.window_id,
+        );
+    }
+
+    /// Working directory for new tabs and windows when `use-current-path` is
+    /// enabled: the foreground process directory, or the last one reported
+    /// by the shell through OSC 7 when it can't be read (e.g. Windows).
+    fn inherited_working_dir(&self) -> Option<String> {
+        if !self.config.use_current_path || self.config.working_dir.is_some() {
+            return None;
+        }
+
+        self.current_working_dir()
+    }
+
+    /// Directory of the foreground process of the current context, or the
+    /// last one reported by the shell.
+    fn current_working_dir(&self) -> Option<String> {
+        let current_context = self.current();
+
+        #[cfg(not(target_os = "windows"))]
+        if let Ok(path) = teletypewriter::foreground_process_path(
+            *current_context.main_fd,
+            current_context.shell_pid,
+        ) {
+            return Some(path.to_string_lossy().to_string());
+        }
+
+        let reported = current_context.terminal.lock().current_directory.clone();
+        reported
+            .filter(|path| path.is_dir())
+            .map(|path| path.to_string_lossy().to_string())
     }
 
     #[inline]
     pub fn close_unfocused_tabs(&mut self) {
         let current_route_id = self.current().route_id;
         self.titles.titles.retain(|&i, _| i == self.current_index);
+        self.titles.labels.retain(|&i, _| i == self.current_index);
+        self.titles.usage.retain(|&i, _| i == self.current_index);
         self.contexts.retain(|ctx| ctx.route_id == current_route_id);
         self.current_route = self.contexts[0].route_id;
         self.set_current(0);
@@ -470,6 +785,45 @@ impl<T: EventListener + Clone + std::marker::Send + 'static> ContextManager<T> {
         self.event_proxy.send_event(RioEvent::Quit, self.window_id);
     }
 
+    #[inline]
+    pub fn force_close_window(&mut self) {
+        self.event_proxy
+            .send_event(RioEvent::ForceCloseWindow, self.window_id);
+    }
+
+    /// Names of the programs started from the shells of every tab, see
+    /// `teletypewriter::running_processes`.
+    #[cfg(not(target_os = "windows"))]
+    pub fn running_processes(&self) -> Vec<String> {
+        self.contexts
+            .iter()
+            .flat_map(|context| teletypewriter::running_processes(context.shell_pid))
+            .collect()
+    }
+
+    #[cfg(target_os = "windows")]
+    pub fn running_processes(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    #[inline]
+    pub fn clipboard_copied(&mut self) {
+        self.event_proxy
+            .send_event(RioEvent::ClipboardCopied, self.window_id);
+    }
+
+    #[inline]
+    pub fn show_pane_diff(&mut self) {
+        self.event_proxy
+            .send_event(RioEvent::ShowPaneDiff, self.window_id);
+    }
+
+    #[inline]
+    pub fn confirm_print(&mut self) {
+        self.event_proxy
+            .send_event(RioEvent::ConfirmPrint, self.window_id);
+    }
+
     #[cfg(target_os = "macos")]
     #[inline]
     pub fn hide_other_apps(&mut self) {
@@ -494,6 +848,85 @@ impl<T: EventListener + Clone + std::marker::Send + 'static> ContextManager<T> {
             .send_event(RioEvent::CreateConfigEditor, self.window_id);
     }
 
+    /// Switches to the profile matching the foreground process of the
+    /// current context, a profile title is applied on every call.
+    pub fn update_profile(&mut self) {
+        if self.config.profiles.is_empty() {
+            return;
+        }
+
+        let Some([program, terminal_title, path, command, session]) =
+            self.titles.titles.get(&self.current_index)
+        else {
+            return;
+        };
+
+        let profile = find_profile(&self.config.profiles, program, path);
+        let title = profile.and_then(|index| {
+            let vars = TitleVars {
+                program,
+                command,
+                path,
+                title: terminal_title,
+                session,
+            };
+            let title = self.config.profiles[index].format_title(&vars)?;
+            let terminal = self.contexts[self.current_index].terminal.lock();
+            Some(terminal.expand_user_vars(&title).into_owned())
+        });
+
+        if profile != self.profile {
+            let had_title = self
+                .profile
+                .is_some_and(|index| self.config.profiles[index].title.is_some());
+            self.profile = profile;
+            self.event_proxy.send_event(
+                RioEvent::SwitchProfile(
+                    profile.map(|index| Box::new(self.config.profiles[index].clone())),
+                ),
+                self.window_id,
+            );
+
+            // Back to the title set by the program
+            if had_title && title.is_none() {
+                let event = if terminal_title.is_empty() {
+                    RioEvent::ResetTitle(self.current_route)
+                } else {
+                    RioEvent::Title(self.current_route, terminal_title.to_owned())
+                };
+                self.event_proxy.send_event(event, self.window_id);
+            }
+        }
+
+        if let Some(title) = title {
+            self.event_proxy
+                .send_event(RioEvent::Title(self.current_route, title), self.window_id);
+        }
+    }
+
+    /// Forgets the active profile so it's applied again, e.g. after the
+    /// colors of the config were reloaded.
+    pub fn reset_profile(&mut self, profiles: &[Profile]) {
+        self.config.profiles = profiles.to_vec();
+        self.profile = None;
+        self.update_profile();
+    }
+
+    /// Bell of the active profile, replacing the `[bell]` section.
+    #[inline]
+    pub fn profile_bell(&self) -> Option<&rio_backend::config::bell::Bell> {
+        self.config.profiles[self.profile?].bell.as_ref()
+    }
+
+    /// Title of the current context rendered from `title.content`.
+    #[inline]
+    pub fn current_label(&self) -> Option<&str> {
+        self.titles
+            .labels
+            .get(&self.current_index)
+            .map(String::as_str)
+    }
+
     #[inline]
     pub fn len(&self) -> usize {
         self.contexts.len()
@@ -504,77 +937,175 @@ impl<T: EventListener + Clone + std::marker::Send + 'static> ContextManager<T> {
             return;
         }
 
+        if self.titles.last_title_update.elapsed() > Duration::from_secs(2) {
+            self.refresh_titles();
+        }
+    }
+
+    /// Same as `update_titles` without waiting for the interval, used by
+    /// the process usage timer.
+    pub fn refresh_titles(&mut self) {
+        self.titles.last_title_update = Instant::now();
+
         #[cfg(unix)]
         {
-            let interval_time = Duration::from_secs(2);
-            if self.titles.last_title_update.elapsed() > interval_time {
-                self.titles.last_title_update = Instant::now();
-                let mut id = String::default();
-                for (i, context) in self.contexts.iter_mut().enumerate() {
-                    let program = teletypewriter::foreground_process_name(
+            // Nested sessions are only looked up when a title shows them
+            let has_session = self
+                .config
+                .title_template
+                .iter()
+                .chain(self.config.profiles.iter().filter_map(|p| p.title.as_ref()))
+                .any(|template| template.contains("{session}"));
+
+            let mut id = String::default();
+            for (i, context) in self.contexts.iter_mut().enumerate() {
+                let program = teletypewriter::foreground_process_name(
+                    *context.main_fd,
+                    context.shell_pid,
+                );
+
+                let path = teletypewriter::foreground_process_path(
+                    *context.main_fd,
+                    context.shell_pid,
+                )
+                .map(|p| p.to_string_lossy().to_string())
+                .unwrap_or_default();
+
+                let command = if self.config.title_template.is_some()
+                    || !self.config.profiles.is_empty()
+                {
+                    teletypewriter::foreground_process_command(
                         *context.main_fd,
                         context.shell_pid,
-                    );
+                    )
+                } else {
+                    program.to_owned()
+                };
 
-                    let path = teletypewriter::foreground_process_path(
+                let session = if has_session {
+                    teletypewriter::foreground_session(
                         *context.main_fd,
                         context.shell_pid,
                     )
-                    .map(|p| p.to_string_lossy().to_string())
-                    .unwrap_or_default();
-
-                    let terminal_title = {
-                        let terminal = context.terminal.lock();
-                        terminal.title.to_string()
+                } else {
+                    String::new()
+                };
+
+                let (terminal_title, label) = {
+                    let terminal = context.terminal.lock();
+                    let vars = TitleVars {
+                        program: &program,
+                        command: &command,
+                        path: &path,
+                        title: &terminal.title,
+                        session: &session,
                     };
-
-                    if self.config.is_native {
-                        let window_title = if terminal_title.is_empty() {
-                            program.to_owned()
-                        } else {
-                            format!("{} ({})", terminal_title, program)
-                        };
-
-                        if cfg!(target_os = "macos") {
-                            self.event_proxy.send_event(
-                                RioEvent::TitleWithSubtitle(window_title, path.clone()),
-                                self.window_id,
-                            );
-                        } else {
-                            self.event_proxy.send_event(
-                                RioEvent::Title(window_title),
-                                self.window_id,
-                            );
-                        }
+                    let label = self.config.title_template.as_ref().map(|template| {
+                        terminal
+                            .expand_user_vars(&vars.format(template))
+                            .into_owned()
+                    });
+                    (terminal.title.to_string(), label)
+                };
+
+                let usage = if self.config.process_usage {
+                    context.sample_process_usage()
+                } else {
+                    None
+                };
+
+                if self.config.is_native {
+                    let mut window_title = if let Some(label) = &label {
+                        label.to_owned()
+                    } else if terminal_title.is_empty() {
+                        program.to_owned()
+                    } else {
+                        format!("{} ({})", terminal_title, program)
+                    };
+                    if let Some(usage) = usage {
+                        window_title = format!("{window_title} [{usage}]");
                     }
 
-                    id =
-                        id.to_owned() + &(format!("{}{}{};", i, program, terminal_title));
-                    self.titles.set_key_val(i, program, terminal_title, path);
+                    if cfg!(target_os = "macos") {
+                        self.event_proxy.send_event(
+                            RioEvent::TitleWithSubtitle(window_title, path.clone()),
+                            self.window_id,
+                        );
+                    } else {
+                        self.event_proxy.send_event(
+                            RioEvent::Title(context.route_id, window_title),
+                            self.window_id,
+                        );
+                    }
+                } else if let Some(label) = &label {
+                    if i == self.current_index {
+                        self.event_proxy.send_event(
+                            RioEvent::Title(context.route_id, label.to_owned()),
+                            self.window_id,
+                        );
+                    }
                 }
-                self.titles.set_key(id);
+
+                match usage {
+                    Some(usage) => self.titles.usage.insert(i, usage),
+                    None => self.titles.usage.remove(&i),
+                };
+
+                id = id.to_owned()
+                    + &(format!(
+                        "{}{}{}{};",
+                        i,
+                        program,
+                        terminal_title,
+                        label.as_deref().unwrap_or_default()
+                    ));
+                match label {
+                    Some(label) => self.titles.labels.insert(i, label),
+                    None => self.titles.labels.remove(&i),
+                };
+                self.titles.set_key_val(
+                    i,
+                    program,
+                    terminal_title,
+                    path,
+                    command,
+                    session,
+                );
             }
+            self.titles.set_key(id);
         }
 
         #[cfg(not(unix))]
         {
-            if self.titles.last_title_update.elapsed() > Duration::from_secs(2) {
-                self.titles.last_title_update = Instant::now();
-                let mut id = String::from("");
-                for (i, _context) in self.contexts.iter().enumerate() {
-                    let program = self.config.shell.program.to_owned();
-                    id = id.to_owned()
-                        + &(format!("{}{}{};", i, program, String::default()));
-                    self.titles.set_key_val(
-                        i,
-                        program,
-                        String::default(),
-                        String::default(),
-                    );
-                }
-                self.titles.set_key(id);
+            let mut id = String::from("");
+            for (i, _context) in self.contexts.iter().enumerate() {
+                let program = self.config.shell.program.to_owned();
+                id = id.to_owned() + &(format!("{}{}{};", i, program, String::default()));
+                let label = self.config.title_template.as_ref().map(|template| {
+                    let vars = TitleVars {
+                        program: &program,
+                        command: &program,
+                        ..TitleVars::default()
+                    };
+                    vars.format(template)
+                });
+                match label {
+                    Some(label) => self.titles.labels.insert(i, label),
+                    None => self.titles.labels.remove(&i),
+                };
+                self.titles.set_key_val(
+                    i,
+                    program.clone(),
+                    String::default(),
+                    String::default(),
+                    program,
+                    String::default(),
+                );
             }
+            self.titles.set_key(id);
         }
+
+        self.update_profile();
     }
 
     #[inline]
@@ -592,6 +1123,20 @@ impl<T: EventListener + Clone + std::marker::Send + 'static> ContextManager<T> {
         if context_id < self.contexts.len() {
             self.current_index = context_id;
             self.current_route = self.contexts[self.current_index].route_id;
+
+            // Window title follows the title set by the current tab.
+            let title = self.contexts[self.current_index]
+                .terminal
+                .lock()
+                .title
+                .clone();
+            let event = if title.is_empty() {
+                RioEvent::ResetTitle(self.current_route)
+            } else {
+                RioEvent::Title(self.current_route, title)
+            };
+            self.event_proxy.send_event(event, self.window_id);
+            self.update_profile();
         }
     }
 
@@ -617,6 +1162,8 @@ impl<T: EventListener + Clone + std::marker::Send + 'static> ContextManager<T> {
         }
 
         self.titles.titles.remove(&index_to_remove);
+        self.titles.labels.remove(&index_to_remove);
+        self.titles.usage.remove(&index_to_remove);
         self.contexts.remove(index_to_remove);
 
         if should_set_current {
@@ -634,6 +1181,66 @@ impl<T: EventListener + Clone + std::marker::Send + 'static> ContextManager<T> {
         self.current_route
     }
 
+    /// Shows `indicator` on the tab of `route_id` when it's in the
+    /// background, a bell isn't replaced by activity.
+    pub fn mark_tab(&mut self, route_id: usize, indicator: TabIndicator) -> bool {
+        if route_id == self.current_route || self.get_by_route_id(route_id).is_none() {
+            return false;
+        }
+
+        match self.indicators.get(&route_id) {
+            Some(current) if *current >= indicator => false,
+            _ => {
+                self.indicators.insert(route_id, indicator);
+                true
+            }
+        }
+    }
+
+    /// Indicator shown on the tab at `index`.
+    #[inline]
+    pub fn tab_indicator(&self, index: usize) -> Option<TabIndicator> {
+        self.contexts
+            .get(index)
+            .and_then(|context| self.indicators.get(&context.route_id))
+            .copied()
+    }
+
+    /// Called once the current context is drawn. Contexts of other tabs
+    /// are hidden, so their output isn't held back waiting for frames.
+    pub fn frame_drawn(&self) {
+        for (index, context) in self.contexts.iter().enumerate() {
+            context.frames.set_visible(index == self.current_index);
+        }
+        self.current().frames.frame_drawn();
+    }
+
+    /// Clears the indicator of the current tab, once it's shown.
+    #[inline]
+    pub fn clear_current_indicator(&mut self) {
+        if !self.indicators.is_empty() {
+            self.indicators.remove(&self.current_route);
+            let contexts = &self.contexts;
+            self.indicators
+                .retain(|route_id, _| contexts.iter().any(|c| c.route_id == *route_id));
+        }
+    }
+
+    #[inline]
+    pub fn get_by_route_id(&self, route_id: usize) -> Option<&Context<T>> {
+        self.contexts
+            .iter()
+            .find(|context| context.route_id == route_id)
+    }
+
+    /// Resizes every context to `dimension`, contexts already at that
+    /// size are left untouched.
+    pub fn resize_all(&mut self, dimension: ContextDimension) {
+        for context in self.contexts.iter_mut() {
+            context.resize(dimension);
+        }
+    }
+
     #[inline]
     pub fn current(&self) -> &Context<T> {
         &self.contexts[self.current_index]
@@ -678,35 +1285,53 @@ impl<T: EventListener + Clone + std::marker::Send + 'static> ContextManager<T> {
         self.current_route = self.contexts[self.current_index].route_id;
     }
 
-    #[inline]
-    pub fn add_context(
+    /// Replaces the current context with a new one running the configured
+    /// shell in the same directory, the previous process is killed. With
+    /// `keep_history` the scrollback is moved to the new context.
+    pub fn respawn_current_context(
         &mut self,
-        redirect: bool,
         layout: SugarloafLayout,
         cursor_state: (&CursorState, bool),
+        keep_history: bool,
     ) {
-        let mut working_dir = None;
-        if self.config.use_current_path && self.config.working_dir.is_none() {
-            #[cfg(not(target_os = "windows"))]
-            {
-                let current_context = self.current();
-                if let Ok(path) = teletypewriter::foreground_process_path(
-                    *current_context.main_fd,
-                    current_context.shell_pid,
-                ) {
-                    working_dir = Some(path.to_string_lossy().to_string());
+        let mut config = self.config.clone();
+        if let Some(working_dir) = self.current_working_dir() {
+            config.working_dir = Some(working_dir);
+        }
+
+        let route_id = self.current_route;
+        match ContextManager::create_context(
+            cursor_state,
+            self.event_proxy.clone(),
+            self.window_id,
+            route_id,
+            layout,
+            &config,
+        ) {
+            Ok(new_context) => {
+                if keep_history {
+                    let previous = &self.contexts[self.current_index];
+                    new_context
+                        .terminal
+                        .lock()
+                        .restore_history(&mut previous.terminal.lock());
                 }
+                self.contexts[self.current_index] = new_context;
             }
-
-            #[cfg(target_os = "windows")]
-            {
-                // if let Ok(path) = teletypewriter::foreground_process_path() {
-                //     working_dir =
-                //         Some(path.to_string_lossy().to_string());
-                // }
-                working_dir = None;
+            Err(..) => {
+                tracing::error!("not able to respawn context");
             }
         }
+    }
+
+    #[inline]
+    pub fn add_context(
+        &mut self,
+        redirect: bool,
+        layout: SugarloafLayout,
+        cursor_state: (&CursorState, bool),
+    ) {
+        let working_dir = self.inherited_working_dir();
 
         if self.config.is_native {
             self.event_proxy
@@ -747,6 +1372,51 @@ impl<T: EventListener + Clone + std::marker::Send + 'static> ContextManager<T> {
     }
 }
 
+/// Environment of the shell, with the window and pane it runs in so scripts
+/// can target them through the control socket.
+fn context_env_pairs(
+    shell: &Shell,
+    window_id: WindowId,
+    route_id: usize,
+    control_socket: Option<&Path>,
+) -> Vec<(String, String)> {
+    let mut env = shell.env_pairs();
+    env.push((
+        String::from("RIO_WINDOW_ID"),
+        u64::from(window_id).to_string(),
+    ));
+    env.push((String::from("RIO_PANE_ID"), route_id.to_string()));
+    if let Some(path) = control_socket {
+        env.push((
+            String::from("RIO_CONTROL_SOCKET"),
+            path.to_string_lossy().into_owned(),
+        ));
+    }
+    env
+}
+
+/// Attach target of the configured shell program, e.g. `serial:/dev/ttyUSB0:115200`.
+#[cfg(not(target_os = "windows"))]
+fn attach_target(
+    config: &ContextManagerConfig,
+) -> Result<Option<teletypewriter::Target>, Box<dyn Error>> {
+    use rio_backend::config::serial::FlowControl;
+
+    let defaults = teletypewriter::SerialSettings {
+        baud_rate: config.serial.baud_rate,
+        flow_control: match config.serial.flow_control {
+            FlowControl::None => teletypewriter::FlowControl::None,
+            FlowControl::Software => teletypewriter::FlowControl::Software,
+            FlowControl::Hardware => teletypewriter::FlowControl::Hardware,
+        },
+    };
+
+    teletypewriter::Target::parse(&config.shell.program, defaults).map_err(|err| {
+        tracing::error!("{err:?}");
+        Box::new(err) as Box<dyn Error>
+    })
+}
+
 pub fn process_open_url(
     mut shell: Shell,
     mut working_dir: Option<String>,
@@ -766,6 +1436,8 @@ pub fn process_open_url(
                     shell = Shell {
                         program: editor.program,
                         args,
+                        env: editor.env,
+                        login: editor.login,
                     }
                 } else if path_buf.is_dir() {
                     working_dir = Some(path_buf.display().to_string());
@@ -782,6 +1454,55 @@ pub mod test {
     use super::*;
     use crate::event::VoidListener;
 
+    #[test]
+    fn test_process_usage() {
+        let usage = ProcessUsage::new(
+            Duration::from_millis(500),
+            Duration::from_secs(2),
+            340 * 1024 * 1024,
+        );
+        assert_eq!(usage.cpu, 25);
+        assert_eq!(usage.to_string(), "25% 340M");
+
+        let usage =
+            ProcessUsage::new(Duration::from_secs(3), Duration::from_secs(2), 1288490188);
+        assert_eq!(usage.to_string(), "150% 1.2G");
+
+        let usage = ProcessUsage::new(Duration::ZERO, Duration::ZERO, 0);
+        assert_eq!(usage.to_string(), "0% 0M");
+    }
+
+    #[test]
+    fn test_context_env_pairs() {
+        let shell = Shell {
+            env: vec![String::from("EDITOR=vim")],
+            ..Shell::default()
+        };
+        let env = context_env_pairs(
+            &shell,
+            WindowId::from(3),
+            7,
+            Some(Path::new("/tmp/rio.sock")),
+        );
+        let pairs: Vec<(&str, &str)> = env
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+            .collect();
+        assert_eq!(
+            pairs,
+            [
+                ("EDITOR", "vim"),
+                ("RIO_WINDOW_ID", "3"),
+                ("RIO_PANE_ID", "7"),
+                ("RIO_CONTROL_SOCKET", "/tmp/rio.sock")
+            ]
+        );
+        assert_eq!(
+            context_env_pairs(&shell, WindowId::from(3), 7, None).len(),
+            3
+        );
+    }
+
     #[test]
     fn test_capacity() {
         let window_id: WindowId = WindowId::from(0);
@@ -824,6 +1545,46 @@ pub mod test {
         assert_eq!(context_manager.current_index, 2);
     }
 
+    #[test]
+    fn test_resize_contexts_independently() {
+        let window_id: WindowId = WindowId::from(0);
+
+        let mut context_manager =
+            ContextManager::start_with_capacity(5, VoidListener {}, window_id).unwrap();
+        context_manager.add_context(
+            false,
+            SugarloafLayout::default(),
+            (&CursorState::new('_'), false),
+        );
+
+        let initial = context_manager.contexts()[0].dimension;
+        let dimension = ContextDimension {
+            columns: 100,
+            lines: 30,
+            ..initial
+        };
+
+        assert!(context_manager.contexts[1].resize(dimension));
+        assert!(!context_manager.contexts[1].resize(dimension));
+        assert_eq!(context_manager.contexts[0].dimension, initial);
+        assert_eq!(
+            context_manager.contexts[1].terminal.lock().grid.columns(),
+            100
+        );
+        assert_ne!(
+            context_manager.contexts[0].terminal.lock().grid.columns(),
+            100
+        );
+
+        context_manager.resize_all(dimension);
+        assert!(context_manager
+            .contexts()
+            .iter()
+            .all(|context| context.dimension == dimension));
+        assert!(context_manager.get_by_route_id(1).is_some());
+        assert!(context_manager.get_by_route_id(2).is_none());
+    }
+
     #[test]
     fn test_add_context_start_with_capacity_limit() {
         let window_id: WindowId = WindowId::from(0);
@@ -926,6 +1687,42 @@ pub mod test {
         assert_eq!(context_manager.len(), 2);
     }
 
+    #[test]
+    fn test_tab_indicators() {
+        let window_id: WindowId = WindowId::from(0);
+
+        let mut context_manager =
+            ContextManager::start_with_capacity(5, VoidListener {}, window_id).unwrap();
+        context_manager.add_context(
+            false,
+            SugarloafLayout::default(),
+            (&CursorState::new('_'), false),
+        );
+        let background = context_manager.contexts[1].route_id;
+
+        // The current tab is already seen
+        assert!(
+            !context_manager.mark_tab(context_manager.current_route, TabIndicator::Bell)
+        );
+        assert!(context_manager.mark_tab(background, TabIndicator::Activity));
+        assert!(!context_manager.mark_tab(background, TabIndicator::Activity));
+        assert_eq!(
+            context_manager.tab_indicator(1),
+            Some(TabIndicator::Activity)
+        );
+
+        // A bell isn't replaced by more output
+        assert!(context_manager.mark_tab(background, TabIndicator::Bell));
+        assert!(!context_manager.mark_tab(background, TabIndicator::Activity));
+        assert_eq!(context_manager.tab_indicator(1), Some(TabIndicator::Bell));
+
+        context_manager.clear_current_indicator();
+        assert_eq!(context_manager.tab_indicator(1), Some(TabIndicator::Bell));
+        context_manager.set_current(1);
+        context_manager.clear_current_indicator();
+        assert_eq!(context_manager.tab_indicator(1), None);
+    }
+
     #[test]
     fn test_close_context_upcoming_ids() {
         let window_id: WindowId = WindowId::from(0);
@@ -1055,4 +1852,25 @@ pub mod test {
         context_manager.switch_to_next();
         assert_eq!(context_manager.current_index, 1);
     }
+
+    #[test]
+    fn test_frame_drawn_hides_other_tabs() {
+        let window_id: WindowId = WindowId::from(0);
+
+        let mut context_manager =
+            ContextManager::start_with_capacity(2, VoidListener {}, window_id).unwrap();
+        context_manager.add_context(
+            false,
+            SugarloafLayout::default(),
+            (&CursorState::new('_'), false),
+        );
+        context_manager.frame_drawn();
+        assert_eq!(context_manager.contexts()[0].frames.frames(), 1);
+
+        // Output of the background tab doesn't wait for frames.
+        let frames = &context_manager.contexts()[1].frames;
+        let start = Instant::now();
+        frames.wait_after(frames.frames(), Duration::from_secs(10));
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
 }
@@ -0,0 +1,486 @@
+// The control socket streams terminal changes (line updates, cursor moves,
+// bells and OSC codes forwarded to hooks) as JSON lines so assistive tools
+// and status bar widgets can follow the terminal without scraping it.
+//
+// Clients choose what they receive by sending a line like
+// `{"subscribe": ["line", "cursor", "bell"]}`, which can be repeated to change
+// the subscription. Each subscriber has a bounded queue: once it's full new
+// events are dropped and an `overflow` event with the amount of dropped events
+// is delivered when there's room again.
+//
+// A client can also ask for the styled text of a window with a line like
+// `{"snapshot": {"window": 1, "route": 0, "lines": [-10, 23]}}`, every field
+// is optional. The reply is a `snapshot` event sent to that client only.
+
+use rio_backend::snapshot::{SnapshotLine, SnapshotRequest};
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::fs::FileTypeExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+
+/// Events a subscriber can queue before new ones are dropped.
+const SUBSCRIBER_QUEUE_SIZE: usize = 1024;
+
+const KIND_LINE: u8 = 1 << 0;
+const KIND_CURSOR: u8 = 1 << 1;
+const KIND_BELL: u8 = 1 << 2;
+const KIND_OSC: u8 = 1 << 3;
+/// Set once the client disconnected, the subscriber is removed on the next
+/// event.
+const CLOSED: u8 = 1 << 7;
+
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+pub enum ControlEvent<'a> {
+    Line {
+        window: u64,
+        route: usize,
+        line: usize,
+        text: &'a str,
+    },
+    Cursor {
+        window: u64,
+        route: usize,
+        line: usize,
+        column: usize,
+    },
+    Bell {
+        window: u64,
+    },
+    Osc {
+        window: u64,
+        route: usize,
+        code: u16,
+        payload: &'a str,
+    },
+    Overflow {
+        dropped: usize,
+    },
+    Snapshot {
+        window: u64,
+        route: usize,
+        lines: &'a [SnapshotLine],
+    },
+    Error {
+        message: &'a str,
+    },
+}
+
+impl ControlEvent<'_> {
+    fn kind(&self) -> u8 {
+        match self {
+            ControlEvent::Line { .. } => KIND_LINE,
+            ControlEvent::Cursor { .. } => KIND_CURSOR,
+            ControlEvent::Bell { .. } => KIND_BELL,
+            ControlEvent::Osc { .. } => KIND_OSC,
+            ControlEvent::Overflow { .. }
+            | ControlEvent::Snapshot { .. }
+            | ControlEvent::Error { .. } => u8::MAX,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Subscribe {
+    subscribe: Vec<String>,
+}
+
+fn parse_subscription(line: &str) -> Option<u8> {
+    let request: Subscribe = serde_json::from_str(line).ok()?;
+    Some(request.subscribe.iter().fold(0, |kinds, kind| {
+        kinds
+            | match kind.as_str() {
+                "line" => KIND_LINE,
+                "cursor" => KIND_CURSOR,
+                "bell" => KIND_BELL,
+                "osc" => KIND_OSC,
+                _ => 0,
+            }
+    }))
+}
+
+#[derive(Deserialize, Default, Debug, PartialEq)]
+#[serde(default)]
+struct SnapshotQuery {
+    window: Option<u64>,
+    route: Option<usize>,
+    lines: Option<[i32; 2]>,
+}
+
+#[derive(Deserialize)]
+struct Snapshot {
+    snapshot: SnapshotQuery,
+}
+
+fn parse_snapshot(line: &str) -> Option<SnapshotQuery> {
+    serde_json::from_str::<Snapshot>(line)
+        .ok()
+        .map(|request| request.snapshot)
+}
+
+/// Sends the reply of a snapshot request, dropped if the client
+/// queue is full.
+pub fn reply(request: &SnapshotRequest, event: &ControlEvent) {
+    let _ = request.reply.try_send(encode(event));
+}
+
+type SnapshotHandler = Arc<dyn Fn(SnapshotRequest) + Send + Sync>;
+
+struct Subscriber {
+    kinds: Arc<AtomicU8>,
+    sender: SyncSender<Arc<str>>,
+    dropped: usize,
+}
+
+impl Subscriber {
+    /// Queue `message`, returns false once the subscriber is gone.
+    fn deliver(&mut self, message: &Arc<str>) -> bool {
+        if self.dropped > 0 {
+            let overflow = ControlEvent::Overflow {
+                dropped: self.dropped,
+            };
+            match self.sender.try_send(encode(&overflow)) {
+                Ok(()) => self.dropped = 0,
+                Err(TrySendError::Full(_)) => {
+                    self.dropped += 1;
+                    return true;
+                }
+                Err(TrySendError::Disconnected(_)) => return false,
+            }
+        }
+
+        match self.sender.try_send(message.clone()) {
+            Ok(()) => true,
+            Err(TrySendError::Full(_)) => {
+                self.dropped += 1;
+                true
+            }
+            Err(TrySendError::Disconnected(_)) => false,
+        }
+    }
+}
+
+fn encode(event: &ControlEvent) -> Arc<str> {
+    serde_json::to_string(event).unwrap_or_default().into()
+}
+
+#[derive(Clone)]
+pub struct ControlSocket {
+    path: PathBuf,
+    subscribers: Arc<Mutex<Vec<Subscriber>>>,
+}
+
+impl ControlSocket {
+    /// Default path, unique for each Rio process.
+    pub fn default_path() -> PathBuf {
+        let dir = std::env::var_os("XDG_RUNTIME_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(std::env::temp_dir);
+        dir.join(format!("rio-{}.sock", std::process::id()))
+    }
+
+    /// Binds the socket, snapshot requests are given to `on_snapshot`
+    /// which has to reply through [`reply`].
+    pub fn bind(
+        path: &Path,
+        on_snapshot: impl Fn(SnapshotRequest) + Send + Sync + 'static,
+    ) -> std::io::Result<ControlSocket> {
+        // A socket left by a previous process would fail the bind, anything
+        // else at the path isn't ours to remove.
+        match std::fs::symlink_metadata(path) {
+            Ok(metadata) if !metadata.file_type().is_socket() => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::AlreadyExists,
+                    format!("{} exists and is not a socket", path.display()),
+                ));
+            }
+            Ok(_) if UnixStream::connect(path).is_err() => {
+                std::fs::remove_file(path)?;
+            }
+            _ => {}
+        }
+
+        let listener = UnixListener::bind(path)?;
+        let subscribers: Arc<Mutex<Vec<Subscriber>>> = Arc::default();
+        let accepted = subscribers.clone();
+        let on_snapshot: SnapshotHandler = Arc::new(on_snapshot);
+        rio_backend::performer::spawn_named("control socket", move || {
+            for stream in listener.incoming().flatten() {
+                if let Err(err) = accept(stream, &accepted, &on_snapshot) {
+                    tracing::warn!("control socket: {err}");
+                }
+            }
+        });
+
+        Ok(ControlSocket {
+            path: path.to_path_buf(),
+            subscribers,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Whether any subscriber wants line or cursor events, used to skip
+    /// computing them.
+    pub fn wants_screen_changes(&self) -> bool {
+        self.subscribers.lock().unwrap().iter().any(|subscriber| {
+            let kinds = subscriber.kinds.load(Ordering::Relaxed);
+            kinds & CLOSED == 0 && kinds & (KIND_LINE | KIND_CURSOR) != 0
+        })
+    }
+
+    pub fn publish(&self, event: &ControlEvent) {
+        let kind = event.kind();
+        let mut subscribers = self.subscribers.lock().unwrap();
+        let mut message = None;
+        subscribers.retain_mut(|subscriber| {
+            let kinds = subscriber.kinds.load(Ordering::Relaxed);
+            if kinds & CLOSED != 0 {
+                return false;
+            }
+            if kinds & kind == 0 {
+                return true;
+            }
+
+            subscriber.deliver(message.get_or_insert_with(|| encode(event)))
+        });
+    }
+}
+
+impl Drop for ControlSocket {
+    fn drop(&mut self) {
+        if Arc::strong_count(&self.subscribers) == 2 {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+fn accept(
+    stream: UnixStream,
+    subscribers: &Arc<Mutex<Vec<Subscriber>>>,
+    on_snapshot: &SnapshotHandler,
+) -> std::io::Result<()> {
+    let kinds = Arc::new(AtomicU8::new(0));
+    let (sender, receiver) = sync_channel(SUBSCRIBER_QUEUE_SIZE);
+
+    let reader = stream.try_clone()?;
+    let reader_kinds = kinds.clone();
+    let reply = sender.clone();
+    let on_snapshot = on_snapshot.clone();
+    rio_backend::performer::spawn_named("control socket reader", move || {
+        for line in BufReader::new(reader).lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            if let Some(subscription) = parse_subscription(&line) {
+                reader_kinds.store(subscription, Ordering::Relaxed);
+            } else if let Some(query) = parse_snapshot(&line) {
+                on_snapshot(SnapshotRequest {
+                    window: query.window,
+                    route: query.route,
+                    lines: query.lines,
+                    reply: reply.clone(),
+                });
+            }
+        }
+        // Dropping the subscriber also stops the writer.
+        reader_kinds.store(CLOSED, Ordering::Relaxed);
+    });
+
+    rio_backend::performer::spawn_named("control socket writer", move || {
+        write_events(stream, receiver)
+    });
+
+    subscribers.lock().unwrap().push(Subscriber {
+        kinds,
+        sender,
+        dropped: 0,
+    });
+    Ok(())
+}
+
+fn write_events(mut stream: UnixStream, receiver: Receiver<Arc<str>>) {
+    for message in receiver {
+        if stream.write_all(message.as_bytes()).is_err()
+            || stream.write_all(b"\n").is_err()
+        {
+            break;
+        }
+    }
+    let _ = stream.shutdown(std::net::Shutdown::Both);
+}
+
+/// Last published state of a route, changes are published against it.
+#[derive(Default)]
+pub struct ScreenSnapshot {
+    lines: Vec<String>,
+    cursor: Option<(usize, usize)>,
+}
+
+impl ScreenSnapshot {
+    pub fn publish_changes(
+        &mut self,
+        control: &ControlSocket,
+        (window, route): (u64, usize),
+        lines: &[SnapshotLine],
+        cursor: (usize, usize),
+    ) {
+        self.lines.resize(lines.len(), String::new());
+        for (line, snapshot) in lines.iter().enumerate() {
+            let text = snapshot.text();
+            if self.lines[line] != text {
+                control.publish(&ControlEvent::Line {
+                    window,
+                    route,
+                    line,
+                    text: &text,
+                });
+                self.lines[line] = text;
+            }
+        }
+
+        if self.cursor != Some(cursor) {
+            self.cursor = Some(cursor);
+            control.publish(&ControlEvent::Cursor {
+                window,
+                route,
+                line: cursor.0,
+                column: cursor.1,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_subscription() {
+        assert_eq!(
+            parse_subscription(r#"{"subscribe": ["line", "bell"]}"#),
+            Some(KIND_LINE | KIND_BELL)
+        );
+        assert_eq!(
+            parse_subscription(r#"{"subscribe": ["osc"]}"#),
+            Some(KIND_OSC)
+        );
+        assert_eq!(parse_subscription(r#"{"subscribe": []}"#), Some(0));
+        assert_eq!(parse_subscription("line"), None);
+    }
+
+    #[test]
+    fn test_snapshot_query() {
+        assert_eq!(
+            parse_snapshot(r#"{"snapshot": {"window": 1, "lines": [-2, 3]}}"#),
+            Some(SnapshotQuery {
+                window: Some(1),
+                route: None,
+                lines: Some([-2, 3]),
+            })
+        );
+        assert_eq!(
+            parse_snapshot(r#"{"snapshot": {}}"#),
+            Some(SnapshotQuery::default())
+        );
+        assert_eq!(parse_snapshot(r#"{"subscribe": ["line"]}"#), None);
+    }
+
+    #[test]
+    fn test_subscriber_overflow() {
+        let (sender, receiver) = sync_channel(2);
+        let mut subscriber = Subscriber {
+            kinds: Arc::new(AtomicU8::new(KIND_BELL)),
+            sender,
+            dropped: 0,
+        };
+
+        let bell = encode(&ControlEvent::Bell { window: 1 });
+        for _ in 0..4 {
+            assert!(subscriber.deliver(&bell));
+        }
+        assert_eq!(subscriber.dropped, 2);
+
+        assert_eq!(receiver.try_recv().unwrap(), bell);
+        assert_eq!(receiver.try_recv().unwrap(), bell);
+        assert!(subscriber.deliver(&bell));
+        assert_eq!(
+            &*receiver.try_recv().unwrap(),
+            r#"{"event":"overflow","dropped":2}"#
+        );
+        assert_eq!(subscriber.dropped, 0);
+
+        drop(receiver);
+        assert!(!subscriber.deliver(&bell));
+    }
+
+    #[test]
+    fn test_bind_keeps_other_files() {
+        let path = std::env::temp_dir()
+            .join(format!("rio-control-test-{}.txt", std::process::id()));
+        std::fs::write(&path, "data").unwrap();
+
+        assert!(ControlSocket::bind(&path, |_| {}).is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "data");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_publish_to_subscribers() {
+        let path = std::env::temp_dir()
+            .join(format!("rio-control-test-{}.sock", std::process::id()));
+        let control = ControlSocket::bind(&path, |request| {
+            reply(
+                &request,
+                &ControlEvent::Snapshot {
+                    window: request.window.unwrap_or_default(),
+                    route: 0,
+                    lines: &[],
+                },
+            )
+        })
+        .unwrap();
+
+        let mut client = UnixStream::connect(&path).unwrap();
+        client.write_all(b"{\"subscribe\": [\"line\"]}\n").unwrap();
+        while !control.wants_screen_changes() {
+            std::thread::yield_now();
+        }
+
+        control.publish(&ControlEvent::Bell { window: 1 });
+        control.publish(&ControlEvent::Line {
+            window: 1,
+            route: 0,
+            line: 2,
+            text: "hello",
+        });
+
+        let mut reader = BufReader::new(client);
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        assert_eq!(
+            line,
+            "{\"event\":\"line\",\"window\":1,\"route\":0,\"line\":2,\"text\":\"hello\"}\n"
+        );
+
+        reader
+            .get_mut()
+            .write_all(b"{\"snapshot\": {\"window\": 3}}\n")
+            .unwrap();
+        line.clear();
+        reader.read_line(&mut line).unwrap();
+        assert_eq!(
+            line,
+            "{\"event\":\"snapshot\",\"window\":3,\"route\":0,\"lines\":[]}\n"
+        );
+
+        drop(control);
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,233 @@
+// Every startup is appended to a marker file in the config directory with
+// the PID of the process, the entry is removed on a clean exit or once Rio
+// ran for a while. Finding a few recent startups there from processes that
+// aren't running anymore means the previous launches crashed.
+
+use rio_backend::config::{config_dir_path, Config};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Startups older than this don't count towards a crash loop.
+const CRASH_WINDOW: Duration = Duration::from_secs(60);
+
+/// Number of crashed startups inside of `CRASH_WINDOW` to be a loop.
+const MAX_CRASHES: usize = 3;
+
+/// Running this long means the startup went fine.
+const STABLE_AFTER: Duration = Duration::from_secs(10);
+
+/// A `pid timestamp` line of the marker.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Startup {
+    pid: u32,
+    timestamp: u64,
+}
+
+#[inline]
+fn marker_path() -> PathBuf {
+    config_dir_path().join("startup-marker")
+}
+
+#[inline]
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
+
+fn parse_startups(marker: &str) -> Vec<Startup> {
+    marker
+        .lines()
+        .filter_map(|line| {
+            let (pid, timestamp) = line.trim().split_once(' ')?;
+            Some(Startup {
+                pid: pid.parse().ok()?,
+                timestamp: timestamp.parse().ok()?,
+            })
+        })
+        .collect()
+}
+
+fn write_startups(startups: &[Startup]) -> std::io::Result<()> {
+    write_marker(&marker_path(), startups)
+}
+
+/// Replaces the marker at `path`, it's written to a file of this process
+/// first and renamed so other instances never read a partial marker.
+fn write_marker(path: &Path, startups: &[Startup]) -> std::io::Result<()> {
+    if startups.is_empty() {
+        return match std::fs::remove_file(path) {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err),
+            _ => Ok(()),
+        };
+    }
+
+    let marker: String = startups
+        .iter()
+        .map(|startup| format!("{} {}\n", startup.pid, startup.timestamp))
+        .collect();
+    let temp = path.with_extension(format!("{}.tmp", std::process::id()));
+    std::fs::write(&temp, marker)?;
+    std::fs::rename(&temp, path).inspect_err(|_| {
+        let _ = std::fs::remove_file(&temp);
+    })
+}
+
+/// Startups of the marker which are still inside of `CRASH_WINDOW`.
+fn recent_startups(startups: Vec<Startup>, now: u64) -> Vec<Startup> {
+    let since = now.saturating_sub(CRASH_WINDOW.as_secs());
+    startups
+        .into_iter()
+        .filter(|startup| startup.timestamp >= since && startup.timestamp <= now)
+        .collect()
+}
+
+/// Recent startups which crashed, others are still running and clear their
+/// own entry.
+fn count_crashes(startups: &[Startup], is_running: impl Fn(u32) -> bool) -> usize {
+    startups
+        .iter()
+        .filter(|startup| !is_running(startup.pid))
+        .count()
+}
+
+#[cfg(unix)]
+fn is_running(pid: u32) -> bool {
+    // Signal 0 only checks whether the process exists.
+    let exists = unsafe { libc::kill(pid as libc::pid_t, 0) == 0 };
+    exists || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+#[cfg(windows)]
+fn is_running(pid: u32) -> bool {
+    use windows_sys::Win32::Foundation::{CloseHandle, STILL_ACTIVE};
+    use windows_sys::Win32::System::Threading::{
+        GetExitCodeProcess, OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION,
+    };
+
+    unsafe {
+        let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+        if process == 0 {
+            return false;
+        }
+
+        let mut exit_code = 0;
+        let running = GetExitCodeProcess(process, &mut exit_code) != 0
+            && exit_code == STILL_ACTIVE as u32;
+        CloseHandle(process);
+        running
+    }
+}
+
+/// Records the startup, returns true when the previous ones crashed in a loop.
+/// The entry is removed after `STABLE_AFTER` if Rio keeps running.
+pub fn record_startup() -> bool {
+    let pid = std::process::id();
+    let now = now();
+    let mut startups = recent_startups(
+        parse_startups(&std::fs::read_to_string(marker_path()).unwrap_or_default()),
+        now,
+    );
+    let is_crash_loop = count_crashes(&startups, is_running) >= MAX_CRASHES;
+
+    startups.retain(|startup| startup.pid != pid);
+    startups.push(Startup {
+        pid,
+        timestamp: now,
+    });
+    if let Err(err) = write_startups(&startups) {
+        tracing::warn!("unable to write the startup marker: {err}");
+        return false;
+    }
+
+    rio_backend::performer::spawn_named("startup marker", || {
+        std::thread::sleep(STABLE_AFTER);
+        clear();
+    });
+
+    is_crash_loop
+}
+
+/// Removes the entry of this process, called on clean exits which happen
+/// before `STABLE_AFTER` for short lived commands like `rio -e ls`.
+pub fn clear() {
+    let Ok(marker) = std::fs::read_to_string(marker_path()) else {
+        return;
+    };
+
+    let pid = std::process::id();
+    let mut startups = parse_startups(&marker);
+    let len = startups.len();
+    startups.retain(|startup| startup.pid != pid);
+    if startups.len() != len {
+        let _ = write_startups(&startups);
+    }
+}
+
+/// Turns off what most likely crashes Rio, background shaders run user
+/// code on the GPU.
+pub fn disable_culprits(config: &mut Config) {
+    config.crash_recovery = true;
+    if let Some(shader) = config.window.background_shader.take() {
+        tracing::warn!("Rio crashed repeatedly, disabled the background shader {shader}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn startup(pid: u32, timestamp: u64) -> Startup {
+        Startup { pid, timestamp }
+    }
+
+    #[test]
+    fn test_recent_startups() {
+        assert!(recent_startups(parse_startups(""), 1000).is_empty());
+        assert_eq!(
+            recent_startups(parse_startups("1 900\n2 950\nrio\n3 990\n4 1000\n"), 1000),
+            vec![startup(2, 950), startup(3, 990), startup(4, 1000)]
+        );
+        // Clocks going backwards don't keep old startups around
+        assert!(recent_startups(parse_startups("1 2000\n"), 1000).is_empty());
+    }
+
+    #[test]
+    fn test_write_marker() {
+        let dir = std::env::temp_dir().join(format!("rio-marker-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("startup-marker");
+
+        write_marker(&path, &[startup(1, 990), startup(2, 995)]).unwrap();
+        let marker = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            parse_startups(&marker),
+            vec![startup(1, 990), startup(2, 995)]
+        );
+        // Only the marker is left, the temporary file was renamed
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+
+        write_marker(&path, &[]).unwrap();
+        assert!(!path.exists());
+        std::fs::remove_dir(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_count_crashes() {
+        let startups = [startup(1, 990), startup(2, 995), startup(3, 1000)];
+        assert_eq!(count_crashes(&startups, |_| false), 3);
+        // Instances opened in quick succession are still running
+        assert_eq!(count_crashes(&startups, |pid| pid != 1), 1);
+        assert!(is_running(std::process::id()));
+    }
+
+    #[test]
+    fn test_disable_culprits() {
+        let mut config = Config::default();
+        config.window.background_shader = Some(String::from("waves.wgsl"));
+
+        disable_culprits(&mut config);
+        assert!(config.crash_recovery);
+        assert_eq!(config.window.background_shader, None);
+    }
+}
@@ -0,0 +1,114 @@
+// `rio --doctor` checks the environment inherited by spawned shells,
+// problems there usually show up as mojibake or broken key handling.
+
+/// Locale used by programs, `LC_ALL` and `LC_CTYPE` take precedence over `LANG`.
+fn effective_locale(
+    lc_all: Option<String>,
+    lc_ctype: Option<String>,
+    lang: Option<String>,
+) -> Option<String> {
+    [lc_all, lc_ctype, lang]
+        .into_iter()
+        .flatten()
+        .find(|locale| !locale.is_empty())
+}
+
+/// Whether the codeset of a locale like `en_US.UTF-8` or `sr_RS.utf8@latin`
+/// is UTF-8, on MacOS `LC_CTYPE` can also be only the codeset.
+fn is_utf8_locale(locale: &str) -> bool {
+    let codeset = locale
+        .split_once('.')
+        .map_or(locale, |(_, codeset)| codeset);
+    let codeset = codeset
+        .split_once('@')
+        .map_or(codeset, |(codeset, _)| codeset);
+    codeset.eq_ignore_ascii_case("utf-8") || codeset.eq_ignore_ascii_case("utf8")
+}
+
+/// Locale of the spawned shells when it isn't UTF-8, programs fall back
+/// to the `C` locale when there is none.
+pub fn locale_without_utf8() -> Option<String> {
+    if cfg!(windows) {
+        return None;
+    }
+
+    let locale = effective_locale(
+        std::env::var("LC_ALL").ok(),
+        std::env::var("LC_CTYPE").ok(),
+        std::env::var("LANG").ok(),
+    )
+    .unwrap_or_else(|| String::from("C"));
+
+    (!is_utf8_locale(&locale)).then_some(locale)
+}
+
+/// Prints the result of every check, returns false if any failed.
+pub fn run() -> bool {
+    let mut healthy = true;
+
+    match locale_without_utf8() {
+        Some(locale) => {
+            healthy = false;
+            println!(
+                "[!] locale: {locale:?} is not UTF-8, set `locale = \"en_US.UTF-8\"` in the config"
+            );
+        }
+        None => println!("[ok] locale"),
+    }
+
+    #[cfg(unix)]
+    {
+        if !teletypewriter::terminfo_exists("rio") {
+            healthy = false;
+            println!(
+                "[!] terminfo: rio is not installed, install it with `rio --install-terminfo`"
+            );
+        } else if rio_backend::ansi::terminfo::is_outdated() {
+            healthy = false;
+            println!(
+                "[!] terminfo: rio is outdated, update it with `rio --install-terminfo`"
+            );
+        } else {
+            println!("[ok] terminfo: rio");
+        }
+    }
+
+    healthy
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_effective_locale() {
+        let locale = |value: &str| Some(String::from(value));
+
+        assert_eq!(effective_locale(None, None, None), None);
+        assert_eq!(
+            effective_locale(None, None, locale("en_US.UTF-8")),
+            locale("en_US.UTF-8")
+        );
+        assert_eq!(
+            effective_locale(locale(""), locale("UTF-8"), locale("C")),
+            locale("UTF-8")
+        );
+        assert_eq!(
+            effective_locale(locale("C"), locale("UTF-8"), locale("en_US.UTF-8")),
+            locale("C")
+        );
+    }
+
+    #[test]
+    fn test_is_utf8_locale() {
+        assert!(is_utf8_locale("en_US.UTF-8"));
+        assert!(is_utf8_locale("C.utf8"));
+        assert!(is_utf8_locale("UTF-8"));
+        assert!(is_utf8_locale("sr_RS.UTF-8@latin"));
+        assert!(!is_utf8_locale("C"));
+        assert!(!is_utf8_locale("POSIX"));
+        assert!(!is_utf8_locale("en_US"));
+        assert!(!is_utf8_locale("de_DE.ISO-8859-1"));
+        assert!(!is_utf8_locale("de_DE@euro"));
+    }
+}
@@ -24,7 +24,6 @@ impl Ime {
     }
 
     #[inline]
-    #[allow(unused)]
     pub fn is_enabled(&self) -> bool {
         self.enabled
     }
@@ -5,26 +5,36 @@
 #![windows_subsystem = "windows"]
 
 mod application;
+mod bell;
 mod bindings;
 mod cli;
+mod command_not_found;
 mod constants;
 mod context;
+#[cfg(unix)]
+mod control;
+mod crash_loop;
+mod doctor;
 mod ime;
 mod messenger;
 mod mouse;
+mod notification;
+mod osc_hook;
 #[cfg(windows)]
 mod panic;
 mod platform;
+mod print;
 mod renderer;
 mod router;
 mod scheduler;
 mod screen;
+mod update_check;
 mod watcher;
 
 use clap::Parser;
 use rio_backend::config::config_dir_path;
 use rio_backend::event::EventPayload;
-use rio_backend::{ansi, crosswords, event, performer, selection};
+use rio_backend::{ansi, crosswords, event, selection};
 use std::path::PathBuf;
 use std::str::FromStr;
 use tracing::level_filters::LevelFilter;
@@ -62,10 +72,21 @@ pub fn setup_environment_variables(config: &rio_backend::config::Config) {
     std::env::set_var("COLORTERM", "truecolor");
     std::env::remove_var("DESKTOP_STARTUP_ID");
     std::env::remove_var("XDG_ACTIVATION_TOKEN");
-    #[cfg(target_os = "macos")]
-    {
+    if let Some(locale) = &config.locale {
+        // Both take precedence over LANG, other LC_* categories are kept.
+        std::env::remove_var("LC_ALL");
+        std::env::remove_var("LC_CTYPE");
+        std::env::set_var("LANG", locale);
+    } else {
+        #[cfg(target_os = "macos")]
         platform::macos::set_locale_environment();
-        std::env::set_current_dir(dirs::home_dir().unwrap()).unwrap();
+    }
+
+    #[cfg(target_os = "macos")]
+    std::env::set_current_dir(dirs::home_dir().unwrap()).unwrap();
+
+    if let Some(locale) = doctor::locale_without_utf8() {
+        tracing::warn!("locale {locale:?} is not UTF-8, run `rio --doctor`");
     }
 
     // Set env vars from config.
@@ -139,6 +160,37 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Load command line options.
     let args = cli::Cli::parse();
 
+    // Has to happen before anything reads from the config directory.
+    if args.window_options.terminal_options.portable {
+        if let Err(err) = rio_backend::config::enable_portable_mode() {
+            eprintln!("unable to enable the portable mode: {err}");
+        }
+    }
+
+    if args.window_options.terminal_options.print_terminfo {
+        print!("{}", rio_backend::ansi::terminfo::RIO_TERMINFO);
+        return Ok(());
+    }
+
+    if args.window_options.terminal_options.install_terminfo {
+        #[cfg(unix)]
+        if let Err(err) = rio_backend::ansi::terminfo::install() {
+            eprintln!("unable to install the rio terminfo: {err}");
+            std::process::exit(1);
+        }
+
+        #[cfg(not(unix))]
+        eprintln!("terminfo is not used on this platform");
+        return Ok(());
+    }
+
+    if args.window_options.terminal_options.doctor {
+        let config = rio_backend::config::Config::try_load().unwrap_or_default();
+        setup_environment_variables(&config);
+        let healthy = doctor::run();
+        std::process::exit(if healthy { 0 } else { 1 });
+    }
+
     let write_config_path = args.window_options.terminal_options.write_config.clone();
     if let Some(config_path) = write_config_path {
         let _ = setup_logs_by_filter_level("TRACE", false);
@@ -146,11 +198,26 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         return Ok(());
     }
 
-    let (mut config, config_error) = match rio_backend::config::Config::try_load() {
-        Ok(config) => (config, None),
-        Err(err) => (rio_backend::config::Config::default(), Some(err)),
+    let safe_mode = args.window_options.terminal_options.safe_mode;
+    let is_crash_loop = !safe_mode && crash_loop::record_startup();
+
+    let (mut config, config_error) = if safe_mode {
+        let config = rio_backend::config::Config {
+            safe_mode: true,
+            ..rio_backend::config::Config::default()
+        };
+        (config, None)
+    } else {
+        match rio_backend::config::Config::try_load() {
+            Ok(config) => (config, None),
+            Err(err) => (rio_backend::config::Config::default(), Some(err)),
+        }
     };
 
+    if is_crash_loop {
+        crash_loop::disable_culprits(&mut config);
+    }
+
     {
         let log_to_file = args.window_options.terminal_options.enable_log_file;
         if let Err(e) = setup_logs_by_filter_level(
@@ -165,6 +232,21 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             config.use_fork = false;
         }
 
+        #[cfg(unix)]
+        if args.window_options.terminal_options.stdin {
+            config.shell = rio_backend::config::Shell {
+                program: String::from("stdin:"),
+                args: vec![],
+                env: vec![],
+                login: None,
+            };
+            config.use_fork = false;
+        }
+
+        if args.window_options.terminal_options.hold {
+            config.hold = true;
+        }
+
         if let Some(working_dir_cli) = args.window_options.terminal_options.working_dir {
             config.working_dir = Some(working_dir_cli);
         }
@@ -69,6 +69,18 @@ impl Mouse {
     }
 }
 
+/// Whole wheel steps of `step` pixels in `accumulated` scroll, reported with
+/// the `(positive, negative)` button matching the direction they go to.
+#[inline]
+pub fn wheel_steps(accumulated: f64, step: f64, buttons: (u8, u8)) -> (u8, usize) {
+    let button = if accumulated > 0. {
+        buttons.0
+    } else {
+        buttons.1
+    };
+    (button, (accumulated / step).abs() as usize)
+}
+
 #[inline]
 pub fn calculate_mouse_position(
     mouse: &Mouse,
@@ -110,6 +122,14 @@ pub fn calculate_mouse_position(
 pub mod test {
     use super::*;
 
+    #[test]
+    fn test_wheel_steps() {
+        assert_eq!(wheel_steps(0., 10., (64, 65)), (65, 0));
+        assert_eq!(wheel_steps(25., 10., (64, 65)), (64, 2));
+        assert_eq!(wheel_steps(-35., 10., (66, 67)), (67, 3));
+        assert_eq!(wheel_steps(-5., 10., (66, 67)), (67, 0));
+    }
+
     #[test]
     fn test_pos_calc_moving_mouse_x_with_scale_1() {
         let display_offset = 0;
@@ -0,0 +1,81 @@
+// Desktop notifications, sent through the notifier of each platform in its
+// own thread so the event loop never waits for it.
+
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+pub fn notify(title: &str, body: &str) {
+    let mut command = notifier(title, body);
+    rio_backend::performer::spawn_named("notification", move || {
+        let status = command
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status();
+        if let Err(err) = status {
+            tracing::warn!("failed to send notification: {err}");
+        }
+    });
+}
+
+#[cfg(target_os = "macos")]
+fn notifier(title: &str, body: &str) -> Command {
+    let quote = |text: &str| text.replace('\\', "\\\\").replace('"', "\\\"");
+    let mut command = Command::new("osascript");
+    command.arg("-e").arg(format!(
+        "display notification \"{}\" with title \"{}\"",
+        quote(body),
+        quote(title)
+    ));
+    command
+}
+
+#[cfg(windows)]
+fn notifier(title: &str, body: &str) -> Command {
+    let quote = |text: &str| text.replace('\'', "''");
+    let script = format!(
+        "[Windows.UI.Notifications.ToastNotificationManager, Windows.UI.Notifications, ContentType = WindowsRuntime] | Out-Null; \
+         $xml = [Windows.UI.Notifications.ToastNotificationManager]::GetTemplateContent([Windows.UI.Notifications.ToastTemplateType]::ToastText02); \
+         $text = $xml.GetElementsByTagName('text'); \
+         $text.Item(0).AppendChild($xml.CreateTextNode('{}')) | Out-Null; \
+         $text.Item(1).AppendChild($xml.CreateTextNode('{}')) | Out-Null; \
+         [Windows.UI.Notifications.ToastNotificationManager]::CreateToastNotifier('Rio').Show([Windows.UI.Notifications.ToastNotification]::new($xml))",
+        quote(title),
+        quote(body)
+    );
+    let mut command = Command::new("powershell");
+    command.args(["-NoProfile", "-Command", &script]);
+    command
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+fn notifier(title: &str, body: &str) -> Command {
+    let mut command = Command::new("notify-send");
+    command.args(["--app-name=Rio", title, body]);
+    command
+}
+
+/// Short human readable duration, e.g. `1h 2m`, `3m 20s` or `12s`.
+pub fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 {
+        format!("{hours}h {minutes}m")
+    } else if minutes > 0 {
+        format!("{minutes}m {seconds}s")
+    } else {
+        format!("{seconds}s")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::from_millis(12_400)), "12s");
+        assert_eq!(format_duration(Duration::from_secs(200)), "3m 20s");
+        assert_eq!(format_duration(Duration::from_secs(3720)), "1h 2m");
+    }
+}
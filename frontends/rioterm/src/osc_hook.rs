@@ -0,0 +1,182 @@
+// Runs the user hook for OSC codes in the `osc-hooks` range, so in-house
+// tools can talk to their own scripts through the terminal. The hook gets
+// the code as its last argument and the payload on stdin.
+//
+// Any program writing to the terminal can send these codes, so only a few
+// hooks run at once and codes received meanwhile are dropped. Hooks still
+// running after `TIMEOUT` are killed to free their slot.
+
+use rio_backend::config::osc_hooks::OscHooks;
+use rio_backend::event::{PrivateOsc, WindowId};
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+/// Hooks running at once across all windows.
+const MAX_RUNNING: usize = 4;
+
+/// Time a hook can run before it's killed.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Interval between checks of whether a hook exited.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+static RUNNING: AtomicUsize = AtomicUsize::new(0);
+
+/// Releases the slot of a hook once it exited.
+struct Slot(&'static AtomicUsize);
+
+impl Slot {
+    fn acquire(running: &'static AtomicUsize) -> Option<Slot> {
+        running
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |count| {
+                (count < MAX_RUNNING).then_some(count + 1)
+            })
+            .ok()
+            .map(|_| Slot(running))
+    }
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+pub fn run(config: &OscHooks, osc: &PrivateOsc, window_id: WindowId) {
+    spawn(config, osc, window_id, &RUNNING, TIMEOUT);
+}
+
+/// Whether the hook was started.
+fn spawn(
+    config: &OscHooks,
+    osc: &PrivateOsc,
+    window_id: WindowId,
+    running: &'static AtomicUsize,
+    timeout: Duration,
+) -> bool {
+    let Some(program) = &config.program else {
+        return false;
+    };
+
+    let Some(slot) = Slot::acquire(running) else {
+        tracing::debug!("too many osc hooks running, dropped OSC {}", osc.code);
+        return false;
+    };
+
+    let mut command = Command::new(program);
+    command
+        .args(&config.args)
+        .arg(osc.code.to_string())
+        .env("RIO_WINDOW_ID", u64::from(window_id).to_string())
+        .env("RIO_PANE_ID", osc.route_id.to_string())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    let payload = osc.payload.clone();
+    rio_backend::performer::spawn_named("osc-hook", move || {
+        let _slot = slot;
+        let mut child = match command.spawn() {
+            Ok(child) => child,
+            Err(err) => {
+                tracing::warn!("failed to run the osc hook: {err}");
+                return;
+            }
+        };
+
+        // A hook that doesn't read its stdin would block the write, it
+        // fails once the hook is killed.
+        if let Some(mut stdin) = child.stdin.take() {
+            rio_backend::performer::spawn_named("osc-hook stdin", move || {
+                let _ = stdin.write_all(payload.as_bytes());
+            });
+        }
+
+        let deadline = Instant::now() + timeout;
+        loop {
+            match child.try_wait() {
+                Ok(Some(_)) => return,
+                Ok(None) if Instant::now() < deadline => {
+                    std::thread::sleep(POLL_INTERVAL);
+                }
+                Ok(None) => {
+                    tracing::warn!("osc hook timed out, killing it");
+                    break;
+                }
+                Err(err) => {
+                    tracing::warn!("failed to wait for the osc hook: {err}");
+                    break;
+                }
+            }
+        }
+        let _ = child.kill();
+        let _ = child.wait();
+    });
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flood_is_bounded() {
+        static RUNNING: AtomicUsize = AtomicUsize::new(0);
+
+        let config = OscHooks {
+            range: Some([7700, 7799]),
+            program: Some(String::from("sh")),
+            args: vec![
+                String::from("-c"),
+                String::from("sleep 1"),
+                String::from("sh"),
+            ],
+        };
+        let osc = PrivateOsc {
+            route_id: 0,
+            code: 7700,
+            payload: String::from("flood"),
+        };
+        let window_id = unsafe { WindowId::dummy() };
+
+        let spawned = (0..100)
+            .filter(|_| spawn(&config, &osc, window_id, &RUNNING, TIMEOUT))
+            .count();
+        assert_eq!(spawned, MAX_RUNNING);
+        assert!(RUNNING.load(Ordering::Acquire) <= MAX_RUNNING);
+    }
+
+    #[test]
+    fn test_hung_hooks_are_killed() {
+        static RUNNING: AtomicUsize = AtomicUsize::new(0);
+
+        let config = OscHooks {
+            range: Some([7700, 7799]),
+            program: Some(String::from("sh")),
+            args: vec![
+                String::from("-c"),
+                String::from("sleep 30"),
+                String::from("sh"),
+            ],
+        };
+        let osc = PrivateOsc {
+            route_id: 0,
+            code: 7700,
+            payload: String::from("hang"),
+        };
+        let window_id = unsafe { WindowId::dummy() };
+        let timeout = Duration::from_millis(100);
+
+        for _ in 0..MAX_RUNNING {
+            assert!(spawn(&config, &osc, window_id, &RUNNING, timeout));
+        }
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while RUNNING.load(Ordering::Acquire) != 0 && Instant::now() < deadline {
+            std::thread::sleep(POLL_INTERVAL);
+        }
+        assert_eq!(RUNNING.load(Ordering::Acquire), 0);
+        assert!(spawn(&config, &osc, window_id, &RUNNING, timeout));
+    }
+}
@@ -12,6 +12,8 @@ use objc::{msg_send, sel, sel_impl};
 use tracing::debug;
 const FALLBACK_LOCALE: &str = "UTF-8";
 
+/// Processes launched from the Finder or the Dock have no locale in the
+/// environment, derive `LANG` from the system preferences for the shell.
 pub fn set_locale_environment() {
     let env_locale_c = CString::new("").unwrap();
     let env_locale_ptr = unsafe { setlocale(LC_ALL, env_locale_c.as_ptr()) };
@@ -42,10 +44,10 @@ pub fn set_locale_environment() {
 
         env::set_var("LC_CTYPE", FALLBACK_LOCALE);
     } else {
-        // Use system locale.
+        // Use system locale, as LANG so LC_* set by the user still apply.
         debug!("Using system locale: {}", system_locale);
 
-        env::set_var("LC_ALL", system_locale);
+        env::set_var("LANG", system_locale);
     }
 }
 
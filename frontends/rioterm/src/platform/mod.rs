@@ -1,2 +1,3 @@
 #[cfg(target_os = "macos")]
 pub mod macos;
+pub mod screen_lock;
@@ -0,0 +1,103 @@
+// Whether the session is locked, polled while text copied from Rio waits
+// to be cleared (see `clipboard.clear-on-lock`). Checking can spawn a
+// process, so it's polled from its own thread instead of the event loop.
+
+use rio_backend::event::{EventProxy, RioEvent, RioEventType, WindowId};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Sends `RioEvent::ScreenLocked` once the screen gets locked, polling
+/// stops when it's dropped.
+pub struct ScreenLockWatcher {
+    stop: Arc<AtomicBool>,
+}
+
+impl ScreenLockWatcher {
+    pub fn spawn(event_proxy: EventProxy, window_id: WindowId) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopped = stop.clone();
+        rio_backend::performer::spawn_named("screen lock watcher", move || loop {
+            std::thread::sleep(POLL_INTERVAL);
+            if stopped.load(Ordering::Relaxed) {
+                return;
+            }
+
+            if is_screen_locked() {
+                event_proxy
+                    .send_event(RioEventType::Rio(RioEvent::ScreenLocked), window_id);
+                return;
+            }
+        });
+
+        ScreenLockWatcher { stop }
+    }
+}
+
+impl Drop for ScreenLockWatcher {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+#[cfg(target_os = "macos")]
+pub fn is_screen_locked() -> bool {
+    use objc::runtime::{Class, Object};
+    use objc::{msg_send, sel, sel_impl};
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGSessionCopyCurrentDictionary() -> *mut Object;
+    }
+
+    unsafe {
+        let session = CGSessionCopyCurrentDictionary();
+        if session.is_null() {
+            return false;
+        }
+
+        let key: *mut Object = msg_send![
+            Class::get("NSString").unwrap(),
+            stringWithUTF8String: c"CGSSessionScreenIsLocked".as_ptr()
+        ];
+        let value: *mut Object = msg_send![session, objectForKey: key];
+        let locked = !value.is_null() && {
+            let locked: bool = msg_send![value, boolValue];
+            locked
+        };
+        let _: () = msg_send![session, release];
+
+        locked
+    }
+}
+
+// The input desktop can't be opened while the lock screen is shown.
+#[cfg(windows)]
+pub fn is_screen_locked() -> bool {
+    use windows_sys::Win32::System::StationsAndDesktops::{
+        CloseDesktop, OpenInputDesktop, DESKTOP_SWITCHDESKTOP,
+    };
+
+    unsafe {
+        let desktop = OpenInputDesktop(0, 0, DESKTOP_SWITCHDESKTOP);
+        if desktop == 0 {
+            return true;
+        }
+
+        CloseDesktop(desktop);
+        false
+    }
+}
+
+// Screen lockers report the lock to systemd-logind.
+#[cfg(not(any(target_os = "macos", windows)))]
+pub fn is_screen_locked() -> bool {
+    std::process::Command::new("loginctl")
+        .args(["show-session", "auto", "--property=LockedHint", "--value"])
+        .stdin(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .output()
+        .is_ok_and(|output| output.stdout.trim_ascii() == b"yes")
+}
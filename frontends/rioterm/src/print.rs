@@ -0,0 +1,76 @@
+// Printing of the selection or the scrollback. The PDF is sent to the print
+// service of each platform in its own thread, when there isn't one it's kept
+// in the downloads directory instead.
+
+use rio_backend::print::{pdf, PrintLine};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub fn print(lines: Vec<PrintLine>) {
+    rio_backend::performer::spawn_named("print", move || {
+        let document = pdf(&lines);
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+        let name = format!("rio-{seconds}.pdf");
+
+        let spool = std::env::temp_dir().join(&name);
+        if std::fs::write(&spool, &document).is_ok() && send_to_printer(&spool) {
+            return;
+        }
+        let _ = std::fs::remove_file(&spool);
+
+        let path = output_directory().join(name);
+        match std::fs::write(&path, &document) {
+            Ok(()) => crate::notification::notify(
+                "Rio",
+                &format!("No print service found, saved {}", path.display()),
+            ),
+            Err(err) => tracing::error!("failed to write {}: {err}", path.display()),
+        }
+    });
+}
+
+fn output_directory() -> PathBuf {
+    dirs::download_dir()
+        .or_else(dirs::home_dir)
+        .unwrap_or_else(std::env::temp_dir)
+}
+
+// CUPS copies the file to its spool, so it can be removed once `lp` returns.
+#[cfg(not(windows))]
+fn send_to_printer(path: &Path) -> bool {
+    let printed = Command::new("lp")
+        .arg("-t")
+        .arg("Rio")
+        .arg(path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success());
+    if printed {
+        let _ = std::fs::remove_file(path);
+    }
+
+    printed
+}
+
+// The PDF viewer prints it with its own dialog, the file is read after
+// this returns so it's left in the temporary directory.
+#[cfg(windows)]
+fn send_to_printer(path: &Path) -> bool {
+    let script = format!(
+        "Start-Process -FilePath '{}' -Verb Print",
+        path.display().to_string().replace('\'', "''")
+    );
+    Command::new("powershell")
+        .args(["-NoProfile", "-Command", &script])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
@@ -0,0 +1,37 @@
+use crate::constants::*;
+use rio_backend::config::colors::Colors;
+use rio_backend::sugarloaf::{Object, Rect, Text};
+
+/// Draws a message in the bottom left corner, opposite to the toasts,
+/// for things that stay for the whole session like `--safe-mode`.
+#[inline]
+pub fn draw_banner(
+    objects: &mut Vec<Object>,
+    colors: &Colors,
+    dimensions: (f32, f32, f32),
+    message: &str,
+) {
+    let (width, height, scale) = dimensions;
+
+    // Rough width of the text, the banner doesn't need to be exact.
+    let banner_width = (message.chars().count() as f32 * 7. + 20.).min(width / scale);
+    let banner_height = 28.;
+    let position_y = (height / scale) - PADDING_Y_BOTTOM_TABS - banner_height - 8.;
+
+    objects.push(Object::Rect(Rect {
+        position: [8., position_y],
+        color: colors.bar,
+        size: [banner_width, banner_height],
+    }));
+    objects.push(Object::Rect(Rect {
+        position: [8., position_y],
+        color: colors.tabs_active_highlight,
+        size: [3., banner_height],
+    }));
+    objects.push(Object::Text(Text::single_line(
+        (18., position_y + 6.),
+        message.to_string(),
+        12.,
+        colors.foreground,
+    )));
+}
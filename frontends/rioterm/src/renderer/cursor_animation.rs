@@ -0,0 +1,206 @@
+use rio_backend::config::colors::ColorArray;
+use rio_backend::config::CursorAnimation as Style;
+use rio_backend::sugarloaf::{Object, Rect};
+use std::time::{Duration, Instant};
+
+/// Position and size of the cursor shape, in logical pixels.
+type Area = ([f32; 2], [f32; 2]);
+
+/// Quads drawn between the tail and the head of a smear.
+const SMEAR_QUADS: usize = 4;
+
+/// Moves the cursor quad from its previous cell to the new one, the
+/// cursor in the text is hidden meanwhile.
+pub struct CursorAnimation {
+    style: Style,
+    duration: Duration,
+    from: Area,
+    // Nothing to animate from while the cursor isn't shown.
+    to: Option<Area>,
+    started_at: Option<Instant>,
+    // Route of the cursor, switching tabs jumps to the new cursor.
+    route: usize,
+}
+
+impl CursorAnimation {
+    pub fn new(style: Style, duration: Duration) -> Self {
+        Self {
+            style,
+            duration,
+            from: ([0., 0.], [0., 0.]),
+            to: None,
+            started_at: None,
+            route: 0,
+        }
+    }
+
+    #[inline]
+    fn progress(&self) -> f32 {
+        match self.started_at {
+            Some(started_at) if !self.duration.is_zero() => {
+                (started_at.elapsed().as_secs_f32() / self.duration.as_secs_f32()).min(1.)
+            }
+            _ => 1.,
+        }
+    }
+
+    /// Starts moving towards the cursor `area` when it changed, `None`
+    /// while the cursor isn't shown.
+    pub fn update(&mut self, route: usize, area: Option<Area>) {
+        if self.style == Style::None {
+            return;
+        }
+
+        if route != self.route {
+            self.route = route;
+            self.to = None;
+        }
+
+        match (self.to, area) {
+            (Some(to), Some(area)) if to == area => {}
+            (Some(to), Some(area)) => {
+                // An interrupted smear continues from where its head is.
+                self.from = match self.style {
+                    Style::Smear => lerp(self.from, to, ease_out(self.progress())),
+                    _ => to,
+                };
+                self.to = Some(area);
+                self.started_at = Some(Instant::now());
+            }
+            (_, area) => {
+                if let Some(area) = area {
+                    self.from = area;
+                }
+                self.to = area;
+                self.started_at = None;
+            }
+        }
+    }
+
+    /// The next cursor is jumped to, e.g. after a resize moved it.
+    #[inline]
+    pub fn reset(&mut self) {
+        self.to = None;
+        self.started_at = None;
+    }
+
+    #[inline]
+    pub fn is_animating(&self) -> bool {
+        self.to.is_some() && self.progress() < 1.
+    }
+
+    /// Quads of the current frame with their opacity.
+    fn quads(&self) -> Vec<(Area, f32)> {
+        let Some(to) = self.to.filter(|_| self.is_animating()) else {
+            return Vec::new();
+        };
+
+        let progress = self.progress();
+        match self.style {
+            Style::None => Vec::new(),
+            Style::Smear => {
+                // The head leaves right away and the tail follows late,
+                // both arrive at the end.
+                let head = ease_out(progress);
+                let tail = progress * progress;
+                (0..SMEAR_QUADS)
+                    .map(|i| {
+                        let step = i as f32 / (SMEAR_QUADS - 1) as f32;
+                        let area = lerp(self.from, to, head + (tail - head) * step);
+                        (area, 1. - i as f32 / SMEAR_QUADS as f32)
+                    })
+                    .collect()
+            }
+            Style::Fade => {
+                let opacity = ease_out(progress);
+                vec![(self.from, 1. - opacity), (to, opacity)]
+            }
+        }
+    }
+
+    pub fn draw(&self, objects: &mut Vec<Object>, color: ColorArray) {
+        for (([x, y], size), opacity) in self.quads() {
+            objects.push(Object::Rect(Rect {
+                position: [x, y],
+                color: [color[0], color[1], color[2], color[3] * opacity],
+                size,
+            }));
+        }
+    }
+}
+
+/// Ease out, so it starts right away and slows down at the end.
+#[inline]
+fn ease_out(progress: f32) -> f32 {
+    1. - (1. - progress).powi(3)
+}
+
+#[inline]
+fn lerp(from: Area, to: Area, t: f32) -> Area {
+    let mix = |a: f32, b: f32| a + (b - a) * t;
+    (
+        [mix(from.0[0], to.0[0]), mix(from.0[1], to.0[1])],
+        [mix(from.1[0], to.1[0]), mix(from.1[1], to.1[1])],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIRST: Area = ([0., 0.], [10., 20.]);
+    const SECOND: Area = ([10., 0.], [10., 20.]);
+
+    #[test]
+    fn test_disabled() {
+        let mut animation = CursorAnimation::new(Style::None, Duration::from_secs(60));
+        animation.update(0, Some(FIRST));
+        animation.update(0, Some(SECOND));
+        assert!(!animation.is_animating());
+        assert!(animation.quads().is_empty());
+    }
+
+    #[test]
+    fn test_smear() {
+        let mut animation = CursorAnimation::new(Style::Smear, Duration::from_secs(60));
+        // Nothing to animate from on the first frame
+        animation.update(0, Some(FIRST));
+        assert!(!animation.is_animating());
+
+        animation.update(0, Some(SECOND));
+        assert!(animation.is_animating());
+        let quads = animation.quads();
+        assert_eq!(quads.len(), SMEAR_QUADS);
+        // Starts from the previous cell with a fading trail
+        assert_eq!(quads[0].1, 1.);
+        assert!(quads[0].0 .0[0] < 1.);
+        assert!(quads[SMEAR_QUADS - 1].1 < quads[0].1);
+
+        animation.reset();
+        animation.update(0, Some(FIRST));
+        assert!(!animation.is_animating());
+    }
+
+    #[test]
+    fn test_fade() {
+        let mut animation = CursorAnimation::new(Style::Fade, Duration::from_secs(60));
+        animation.update(0, Some(FIRST));
+        animation.update(0, Some(SECOND));
+        let quads = animation.quads();
+        assert_eq!(quads.len(), 2);
+        assert_eq!((quads[0].0, quads[1].0), (FIRST, SECOND));
+    }
+
+    #[test]
+    fn test_jumps() {
+        let mut animation = CursorAnimation::new(Style::Smear, Duration::from_secs(60));
+        animation.update(0, Some(FIRST));
+
+        // Hidden cursors and other tabs don't animate from the last cursor
+        animation.update(0, None);
+        animation.update(0, Some(SECOND));
+        assert!(!animation.is_animating());
+        animation.update(1, Some(FIRST));
+        assert!(!animation.is_animating());
+    }
+}
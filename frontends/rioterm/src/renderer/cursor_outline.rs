@@ -0,0 +1,27 @@
+use rio_backend::config::colors::ColorArray;
+use rio_backend::sugarloaf::{Object, Rect};
+
+/// Draws a one pixel border around the cursor shape, `area` is the
+/// position and size of the shape.
+#[inline]
+pub fn draw_cursor_outline(
+    objects: &mut Vec<Object>,
+    color: ColorArray,
+    area: ([f32; 2], [f32; 2]),
+) {
+    let ([x, y], [width, height]) = area;
+    let edges = [
+        ([x - 1., y - 1.], [width + 2., 1.]),
+        ([x - 1., y + height], [width + 2., 1.]),
+        ([x - 1., y], [1., height]),
+        ([x + width, y], [1., height]),
+    ];
+
+    for (position, size) in edges {
+        objects.push(Object::Rect(Rect {
+            position,
+            color,
+            size,
+        }));
+    }
+}
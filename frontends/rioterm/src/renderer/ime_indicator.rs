@@ -0,0 +1,33 @@
+use rio_backend::config::colors::Colors;
+use rio_backend::sugarloaf::{Object, Rect, Text};
+
+const FONT_SIZE: f32 = 10.;
+
+/// Draws a small badge above and to the right of the cursor cell,
+/// `position` is the top left corner of the cell after the cursor.
+#[inline]
+pub fn draw_ime_indicator(
+    objects: &mut Vec<Object>,
+    colors: &Colors,
+    position: (f32, f32),
+    width: f32,
+    label: &str,
+) {
+    // Rough width of the text, the badge doesn't need to be exact.
+    let badge_width = label.chars().count() as f32 * 6. + 8.;
+    let badge_height = FONT_SIZE + 4.;
+    let position_x = position.0.min(width - badge_width).max(0.);
+    let position_y = (position.1 - badge_height).max(0.);
+
+    objects.push(Object::Rect(Rect {
+        position: [position_x, position_y],
+        color: colors.bar,
+        size: [badge_width, badge_height],
+    }));
+    objects.push(Object::Text(Text::single_line(
+        (position_x + 4., position_y + 2.),
+        label.to_string(),
+        FONT_SIZE,
+        colors.foreground,
+    )));
+}
@@ -0,0 +1,32 @@
+use crate::constants::*;
+use rio_backend::config::colors::Colors;
+use rio_backend::sugarloaf::{Object, Text};
+use std::time::Duration;
+
+#[inline]
+pub fn draw_latency_indicator(
+    objects: &mut Vec<Object>,
+    colors: &Colors,
+    dimensions: (f32, f32, f32),
+    latency: Option<Duration>,
+) {
+    let (width, height, scale) = dimensions;
+    let position_y = (height / scale) - PADDING_Y_BOTTOM_TABS;
+
+    let content = match latency {
+        Some(latency) => format!("{}ms", latency.as_millis()),
+        None => String::from("--ms"),
+    };
+
+    objects.push(Object::Text(Text::single_line(
+        ((width / scale) - 60., position_y + 10.),
+        content,
+        12.,
+        [
+            colors.foreground[0],
+            colors.foreground[1],
+            colors.foreground[2],
+            colors.foreground[3] - 0.3,
+        ],
+    )));
+}
@@ -0,0 +1,201 @@
+use crate::crosswords::grid::row::RowMarks;
+use crate::crosswords::grid::Dimensions;
+use crate::crosswords::pos::{Column, Direction, Line, Pos};
+use crate::crosswords::search::{RegexIter, RegexSearch};
+use crate::crosswords::square::LineLength;
+use crate::crosswords::Crosswords;
+use rio_backend::config::colors::Colors;
+use rio_backend::sugarloaf::layout::SugarloafLayout;
+use rio_backend::sugarloaf::{Minimap, MinimapMarker, Object};
+
+const MINIMAP_WIDTH: f32 = 10.;
+
+/// Logical pixels for each sampled line.
+const ROW_HEIGHT: f32 = 2.;
+
+/// Search matches after this many aren't looked for anymore.
+const MAX_SEARCH_MATCHES: usize = 1000;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MarkerKind {
+    Prompt,
+    /// Prompt of a command that exited with a non zero status.
+    Error,
+    SearchMatch,
+}
+
+/// Scrollback summary sampled from the grid, positions go from 0 at the
+/// top of the scrollback to 1 at the bottom of the screen.
+#[derive(Debug, Default, PartialEq)]
+pub struct MinimapSample {
+    pub rows: Vec<f32>,
+    pub markers: Vec<(f32, MarkerKind)>,
+    pub viewport: [f32; 2],
+}
+
+/// Position and size of the minimap, on the right edge of the text area.
+#[inline]
+pub fn area(layout: &SugarloafLayout) -> ([f32; 2], [f32; 2]) {
+    let scale = layout.dimensions.scale;
+    let line_height = layout.dimensions.height * layout.line_height / scale;
+    (
+        [layout.width / scale - MINIMAP_WIDTH, layout.margin.top_y],
+        [MINIMAP_WIDTH, layout.lines as f32 * line_height],
+    )
+}
+
+/// Samples one line for every row of the minimap instead of reading the
+/// whole scrollback, marks are cheap to check so all lines are used for
+/// markers. Returns `None` without scrollback.
+pub fn sample<T: rio_backend::event::EventListener>(
+    term: &Crosswords<T>,
+    height: f32,
+    search: Option<&mut RegexSearch>,
+) -> Option<MinimapSample> {
+    let total = term.grid.total_lines();
+    let history = term.grid.history_size();
+    if history == 0 {
+        return None;
+    }
+
+    let buckets = ((height / ROW_HEIGHT) as usize).clamp(1, total);
+    let topmost = term.grid.topmost_line();
+    let columns = term.grid.columns() as f32;
+    // Lines of the same row share a position, so markers don't pile up.
+    let position = |line: Line| {
+        let bucket = (line.0 - topmost.0) as usize * buckets / total;
+        bucket as f32 / buckets as f32
+    };
+
+    let rows = (0..buckets)
+        .map(|bucket| {
+            let line = topmost + bucket * total / buckets;
+            term.grid[line].line_length().0 as f32 / columns
+        })
+        .collect();
+
+    let mut markers: Vec<(f32, MarkerKind)> = Vec::new();
+    let mut push_marker = |marker: (f32, MarkerKind)| {
+        if markers.last() != Some(&marker) {
+            markers.push(marker);
+        }
+    };
+
+    let bottommost = term.grid.bottommost_line();
+    let mut line = topmost;
+    while line <= bottommost {
+        let row = &term.grid[line];
+        if row.marks.contains(RowMarks::PROMPT) {
+            let kind = match row.exit_code {
+                Some(exit_code) if exit_code != 0 => MarkerKind::Error,
+                _ => MarkerKind::Prompt,
+            };
+            push_marker((position(line), kind));
+        }
+        line += 1;
+    }
+
+    if let Some(regex) = search {
+        let start = Pos::new(topmost, Column(0));
+        let end = Pos::new(bottommost, term.grid.last_column());
+        for regex_match in RegexIter::new(start, end, Direction::Right, term, regex)
+            .take(MAX_SEARCH_MATCHES)
+        {
+            push_marker((position(regex_match.start().row), MarkerKind::SearchMatch));
+        }
+    }
+
+    let viewport_start = (history - term.display_offset()) as f32 / total as f32;
+    let viewport_end = viewport_start + term.grid.screen_lines() as f32 / total as f32;
+
+    Some(MinimapSample {
+        rows,
+        markers,
+        viewport: [viewport_start, viewport_end],
+    })
+}
+
+/// Display offset that centers the viewport on `position`.
+pub fn display_offset_at(position: f32, history: usize, screen_lines: usize) -> usize {
+    let total = (history + screen_lines) as f32;
+    let center = position.clamp(0., 1.) * total;
+    let top = (center - screen_lines as f32 / 2.).max(0.) as usize;
+    history.saturating_sub(top)
+}
+
+#[inline]
+pub fn draw_minimap(
+    objects: &mut Vec<Object>,
+    colors: &Colors,
+    area: ([f32; 2], [f32; 2]),
+    sample: &MinimapSample,
+) {
+    let foreground = colors.foreground;
+    let markers = sample
+        .markers
+        .iter()
+        .map(|(position, kind)| MinimapMarker {
+            position: *position,
+            color: match kind {
+                MarkerKind::Prompt => colors.blue,
+                MarkerKind::Error => colors.red,
+                MarkerKind::SearchMatch => colors.search_match_background,
+            },
+        })
+        .collect();
+
+    objects.push(Object::Minimap(Minimap {
+        position: area.0,
+        size: area.1,
+        background: colors.bar,
+        foreground: [foreground[0], foreground[1], foreground[2], 0.3],
+        viewport_color: [foreground[0], foreground[1], foreground[2], 0.15],
+        rows: sample.rows.clone(),
+        markers,
+        viewport: sample.viewport,
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ansi::{CursorShape, SemanticPrompt};
+    use crate::crosswords::CrosswordsSize;
+    use crate::event::{VoidListener, WindowId};
+    use rio_backend::performer::handler::Handler;
+
+    #[test]
+    fn test_display_offset_at() {
+        // 90 lines of history and 10 on screen
+        assert_eq!(display_offset_at(0., 90, 10), 90);
+        assert_eq!(display_offset_at(1., 90, 10), 0);
+        assert_eq!(display_offset_at(0.5, 90, 10), 45);
+        assert_eq!(display_offset_at(2., 90, 10), 0);
+    }
+
+    #[test]
+    fn test_sample() {
+        let size = CrosswordsSize::new(10, 5);
+        let window_id = WindowId::from(0);
+        let mut term =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+        assert_eq!(sample(&term, 100., None), None);
+
+        term.semantic_prompt(SemanticPrompt::PromptStart { redraw: true });
+        for c in "hello".chars() {
+            term.input(c);
+        }
+        term.semantic_prompt(SemanticPrompt::CommandExecuted);
+        for _ in 0..9 {
+            term.carriage_return();
+            term.linefeed();
+        }
+        term.semantic_prompt(SemanticPrompt::CommandFinished(Some(1)));
+
+        // 5 lines of history and 5 on screen, a row for each line
+        let sample = sample(&term, 20., None).unwrap();
+        assert_eq!(sample.rows, vec![0.5, 0., 0., 0., 0., 0., 0., 0., 0., 0.]);
+        assert_eq!(sample.markers, vec![(0., MarkerKind::Error)]);
+        assert_eq!(sample.viewport, [0.5, 1.]);
+    }
+}
@@ -1,5 +1,15 @@
+mod banner;
+mod cursor_animation;
+mod cursor_outline;
+mod ime_indicator;
+mod latency;
+pub mod minimap;
 pub mod navigation;
+mod new_lines;
+pub mod scrollbar;
 mod search;
+pub mod smooth_scroll;
+mod toast;
 pub mod utils;
 
 use crate::ansi::CursorShape;
@@ -10,14 +20,17 @@ use crate::ime::Preedit;
 use crate::screen::hint::HintMatches;
 use crate::selection::SelectionRange;
 use navigation::ScreenNavigation;
+use rio_backend::ansi::LineSize;
 use rio_backend::config::colors::{
     term::{List, TermColors},
     AnsiColor, ColorArray, Colors, NamedColor,
 };
-use rio_backend::config::Config;
+use rio_backend::config::dim_inactive::DimInactive;
+use rio_backend::config::night_mode::NightMode;
+use rio_backend::config::{Config, CursorSize};
 use rio_backend::sugarloaf::{
-    Content, FragmentStyle, FragmentStyleDecoration, Graphic, Stretch, Style,
-    SugarCursor, Sugarloaf, UnderlineInfo, UnderlineShape, Weight,
+    self, Content, DrawableChar, FragmentStyle, FragmentStyleDecoration, Graphic, Object,
+    Rect, Stretch, Style, SugarCursor, Sugarloaf, UnderlineInfo, UnderlineShape, Weight,
 };
 use std::collections::HashMap;
 use std::ops::RangeInclusive;
@@ -26,6 +39,9 @@ use std::time::{Duration, Instant};
 use rustc_hash::FxHashMap;
 use unicode_width::UnicodeWidthChar;
 
+// How long toasts, e.g. command-not-found suggestions, stay on screen.
+const TOAST_DURATION: Duration = Duration::from_secs(8);
+
 struct Cursor {
     state: CursorState,
     content: char,
@@ -44,22 +60,59 @@ pub struct Renderer {
     pub colors: List,
     pub navigation: ScreenNavigation,
     cursor: Cursor,
+    cursor_beam_width: CursorSize,
+    cursor_underline_height: CursorSize,
+    cursor_outline_color: Option<ColorArray>,
+    // Beam width and underline height in pixels for the current cell size.
+    cursor_thickness: (f32, f32),
+    pub cursor_animation: cursor_animation::CursorAnimation,
     pub selection_range: Option<SelectionRange>,
     pub config_has_blinking_enabled: bool,
     pub config_blinking_interval: u64,
     term_has_blinking_enabled: bool,
     pub is_blinking: bool,
+    // Blinking phases of the cursor and text are counted from here,
+    // so extra renders don't toggle them.
+    blinking_epoch: Instant,
+    // Text blinking interval, `None` when it's disabled.
+    text_blinking_interval: Option<u64>,
+    is_text_visible: bool,
+    has_blinking_text: bool,
     ignore_selection_fg_color: bool,
     // Dynamic background keep track of the original bg color and
     // the same r,g,b with the mutated alpha channel.
     pub dynamic_background: ([f32; 4], wgpu::Color, bool),
     hyperlink_range: Option<SelectionRange>,
     active_search: Option<String>,
+    night_mode: NightMode,
+    is_night_mode_active: bool,
+    dim_inactive: DimInactive,
+    is_window_focused: bool,
+    is_dimmed: bool,
+    show_latency: bool,
+    latency: Option<Duration>,
+    show_new_lines_indicator: bool,
+    new_lines: usize,
+    pub show_minimap: bool,
+    minimap: Option<minimap::MinimapSample>,
+    pub scrollbar: scrollbar::Scrollbar,
+    pub smooth_scroll: smooth_scroll::SmoothScroll,
+    // Lines of scrollback, for the scrollbar.
+    history: usize,
+    // Duration of the running visual bell.
+    bell_flash_duration: Option<Duration>,
+    bell_flash_started: Option<Instant>,
+    toast: Option<(String, Instant)>,
+    show_ime_indicator: bool,
+    ime_indicator: Option<&'static str>,
+    banner: Option<&'static str>,
     font_context: rio_backend::sugarloaf::font::FontLibrary,
     font_cache: FxHashMap<
         (char, rio_backend::sugarloaf::font_introspector::Attributes),
         (usize, f32),
     >,
+    // Reused by `create_line` to avoid allocating a string per line.
+    line_content: String,
 }
 
 impl Renderer {
@@ -98,6 +151,13 @@ impl Renderer {
             is_ime_enabled: false,
             is_vi_mode_enabled: false,
             is_blinking: false,
+            blinking_epoch: Instant::now(),
+            text_blinking_interval: config
+                .text_blinking
+                .enabled
+                .then(|| config.text_blinking.interval.clamp(200, 2000)),
+            is_text_visible: true,
+            has_blinking_text: false,
             last_typing: None,
             config_has_blinking_enabled: config.cursor.blinking,
             term_has_blinking_enabled: false,
@@ -114,19 +174,80 @@ impl Renderer {
             named_colors,
             dynamic_background,
             active_search: None,
+            night_mode: config.night_mode.clone(),
+            is_night_mode_active: false,
+            dim_inactive: config.dim_inactive,
+            is_window_focused: true,
+            is_dimmed: false,
+            show_latency: config.predictive_echo.show_latency,
+            latency: None,
+            show_new_lines_indicator: config.scroll.new_lines_indicator,
+            new_lines: 0,
+            show_minimap: config.scroll.minimap,
+            minimap: None,
+            scrollbar: scrollbar::Scrollbar::new(config.scroll.scrollbar),
+            smooth_scroll: smooth_scroll::SmoothScroll::new(Duration::from_millis(
+                config.scroll.smooth_scroll_duration,
+            )),
+            history: 0,
+            bell_flash_duration: None,
+            bell_flash_started: None,
+            toast: None,
+            show_ime_indicator: config.keyboard.ime_indicator,
+            ime_indicator: None,
+            banner: if config.safe_mode {
+                Some("Safe mode: the config file is ignored")
+            } else if config.crash_recovery {
+                Some("Rio crashed repeatedly, the background shader is disabled")
+            } else {
+                None
+            },
             cursor: Cursor {
                 content: config.cursor.shape.into(),
                 content_ref: config.cursor.shape.into(),
                 state: CursorState::new(config.cursor.shape.into()),
             },
+            cursor_beam_width: config.cursor.beam_width,
+            cursor_underline_height: config.cursor.underline_height,
+            cursor_outline_color: config.cursor.outline_color,
+            cursor_thickness: (3., 3.),
+            cursor_animation: cursor_animation::CursorAnimation::new(
+                config.cursor.animation,
+                Duration::from_millis(config.cursor.animation_duration),
+            ),
             font_cache: FxHashMap::default(),
             font_context: font_context.clone(),
+            line_content: String::new(),
         }
     }
 
     #[inline]
     pub fn has_blinking_enabled(&self) -> bool {
-        self.config_has_blinking_enabled && self.term_has_blinking_enabled
+        // Starts from the config and applications can change it (DECSCUSR).
+        self.term_has_blinking_enabled
+    }
+
+    #[inline]
+    fn is_blinking_phase_visible(&self, interval: u64) -> bool {
+        (self.blinking_epoch.elapsed().as_millis() / interval as u128) % 2 == 0
+    }
+
+    /// Milliseconds until the next blinking phase of `interval`.
+    #[inline]
+    pub fn next_blinking_phase(&self, interval: u64) -> u64 {
+        let elapsed = self.blinking_epoch.elapsed().as_millis() as u64;
+        (interval - elapsed % interval).max(1)
+    }
+
+    /// Milliseconds until blinking text in the last frame toggles, if any.
+    #[inline]
+    pub fn next_text_blinking_phase(&self) -> Option<u64> {
+        if !self.has_blinking_text {
+            return None;
+        }
+
+        self.text_blinking_interval
+            .map(|interval| self.next_blinking_phase(interval))
     }
 
     #[inline]
@@ -144,6 +265,82 @@ impl Renderer {
         self.active_search = active_search;
     }
 
+    /// Re-evaluate the night mode schedule, returns true if it was
+    /// toggled and the text colors have to be redrawn.
+    pub fn update_night_mode(&mut self, sugarloaf: &mut Sugarloaf) -> bool {
+        let is_active = self.night_mode.enabled
+            && self.night_mode.is_active_at(utils::local_minutes_of_day());
+        if is_active == self.is_night_mode_active {
+            return false;
+        }
+        self.is_night_mode_active = is_active;
+        self.update_background(sugarloaf);
+        true
+    }
+
+    /// Dims everything while the window isn't focused, when enabled.
+    #[inline]
+    pub fn set_window_focused(&mut self, is_focused: bool) {
+        self.is_window_focused = is_focused;
+    }
+
+    #[inline]
+    pub fn is_window_focused(&self) -> bool {
+        self.is_window_focused
+    }
+
+    #[inline]
+    pub fn dims_inactive(&self) -> bool {
+        self.dim_inactive.is_enabled()
+    }
+
+    fn update_dim_inactive(&mut self, sugarloaf: &mut Sugarloaf) {
+        let is_dimmed = !self.is_window_focused && self.dim_inactive.is_enabled();
+        if is_dimmed == self.is_dimmed {
+            return;
+        }
+        self.is_dimmed = is_dimmed;
+        self.update_background(sugarloaf);
+    }
+
+    /// Background with the night mode and dimming applied.
+    fn update_background(&self, sugarloaf: &mut Sugarloaf) {
+        if !cfg!(target_os = "macos") {
+            let mut background = self.dynamic_background.1;
+            let mut color = [
+                background.r as f32,
+                background.g as f32,
+                background.b as f32,
+                background.a as f32,
+            ];
+            if self.is_night_mode_active {
+                color = self.night_mode.apply(color);
+            }
+            if self.is_dimmed {
+                color = self.dim_inactive.apply(color);
+            }
+            background.r = color[0] as f64;
+            background.g = color[1] as f64;
+            background.b = color[2] as f64;
+            sugarloaf.set_background_color(Some(background));
+        }
+    }
+
+    #[inline]
+    fn map_style_colors(
+        style: &mut FragmentStyle,
+        apply: impl Fn(ColorArray) -> ColorArray,
+    ) {
+        style.color = apply(style.color);
+        style.background_color = style.background_color.map(&apply);
+        style.decoration_color = style.decoration_color.map(&apply);
+        style.cursor = style.cursor.map(|cursor| match cursor {
+            SugarCursor::Block(c) => SugarCursor::Block(apply(c)),
+            SugarCursor::Caret(c, width) => SugarCursor::Caret(apply(c), width),
+            SugarCursor::Underline(c, height) => SugarCursor::Underline(apply(c), height),
+        });
+    }
+
     #[inline]
     pub fn set_hyperlink_range(&mut self, hyperlink_range: Option<SelectionRange>) {
         self.hyperlink_range = hyperlink_range;
@@ -161,7 +358,10 @@ impl Renderer {
         let mut foreground_color = self.compute_color(&square.fg, flags);
         let mut background_color = self.compute_bg_color(square);
 
-        let content = if square.c == '\t' || flags.contains(Flags::HIDDEN) {
+        let content = if square.c == '\t'
+            || flags.contains(Flags::HIDDEN)
+            || (flags.contains(Flags::BLINK) && !self.is_text_visible)
+        {
             ' '
         } else {
             square.c
@@ -201,6 +401,8 @@ impl Renderer {
                 font_attrs: font_attrs.into(),
                 decoration,
                 decoration_color,
+                strikethrough: square.flags.contains(Flags::STRIKEOUT),
+                overline: square.flags.contains(Flags::OVERLINE),
                 ..FragmentStyle::default()
             },
             content,
@@ -222,8 +424,6 @@ impl Renderer {
                 is_doubled: false,
                 shape: UnderlineShape::Regular,
             }));
-        } else if square.flags.contains(Flags::STRIKEOUT) {
-            decoration = Some(FragmentStyleDecoration::Strikethrough);
         } else if square.flags.contains(Flags::DOUBLE_UNDERLINE) {
             decoration = Some(FragmentStyleDecoration::Underline(UnderlineInfo {
                 offset: -1.0,
@@ -273,11 +473,22 @@ impl Renderer {
         search_hints: &mut Option<HintMatches>,
         focused_match: &Option<RangeInclusive<Pos>>,
     ) {
-        let columns: usize = row.len();
-        let mut content = String::default();
+        let mut columns: usize = row.len();
+        let mut content = std::mem::take(&mut self.line_content);
+        content.clear();
         let mut last_char_was_space = false;
         let mut last_style = FragmentStyle::default();
 
+        if row.size.is_double() {
+            // Double lines only fit the first half of the columns.
+            columns = std::cmp::max(columns / 2, 1);
+            content_builder.set_line_size(match row.size {
+                LineSize::DoubleWidth => sugarloaf::LineSize::DoubleWidth,
+                LineSize::DoubleHeightTop => sugarloaf::LineSize::DoubleHeightTop,
+                _ => sugarloaf::LineSize::DoubleHeightBottom,
+            });
+        }
+
         for column in 0..columns {
             let square = &row.inner[column];
 
@@ -285,6 +496,10 @@ impl Renderer {
                 continue;
             }
 
+            if square.flags.contains(Flags::BLINK) {
+                self.has_blinking_text = true;
+            }
+
             let (mut style, square_content) =
                 if has_cursor && column == self.cursor.state.pos.col {
                     self.create_cursor_style(square)
@@ -358,6 +573,16 @@ impl Renderer {
                 style.background_color = None;
             }
 
+            style.drawable_char = DrawableChar::new(square_content);
+
+            if self.is_night_mode_active {
+                Self::map_style_colors(&mut style, |c| self.night_mode.apply(c));
+            }
+
+            if self.is_dimmed {
+                Self::map_style_colors(&mut style, |c| self.dim_inactive.apply(c));
+            }
+
             if let Some((font_id, width)) =
                 self.font_cache.get(&(square_content, style.font_attrs))
             {
@@ -392,6 +617,11 @@ impl Renderer {
                 );
             };
 
+            // Grapheme clusters widened by the terminal, e.g. emoji presentation.
+            if square.flags.contains(Flags::WIDE_CHAR) {
+                style.width = 2.0;
+            }
+
             if square_content == ' ' {
                 if !last_char_was_space {
                     if !content.is_empty() {
@@ -421,6 +651,10 @@ impl Renderer {
             }
 
             content.push(square_content);
+            // Combining marks and the rest of grapheme clusters.
+            if let Some(zerowidth) = square.zerowidth() {
+                content.extend(zerowidth);
+            }
 
             // Render last column and break row
             if column == (columns - 1) {
@@ -433,6 +667,7 @@ impl Renderer {
         }
 
         content_builder.new_line();
+        self.line_content = content;
     }
 
     #[inline]
@@ -634,6 +869,8 @@ impl Renderer {
             color,
             background_color,
             font_attrs: font_attrs.into(),
+            strikethrough: square.flags.contains(Flags::STRIKEOUT),
+            overline: square.flags.contains(Flags::OVERLINE),
             ..FragmentStyle::default()
         };
 
@@ -649,20 +886,17 @@ impl Renderer {
 
         match self.cursor.state.content {
             CursorShape::Underline => {
-                style.decoration =
-                    Some(FragmentStyleDecoration::Underline(UnderlineInfo {
-                        offset: 0.0,
-                        size: 3.0,
-                        is_doubled: false,
-                        shape: UnderlineShape::Regular,
-                    }));
-                style.decoration_color = Some(cursor_color);
+                style.cursor = Some(SugarCursor::Underline(
+                    cursor_color,
+                    self.cursor_thickness.1,
+                ));
             }
             CursorShape::Block => {
                 style.cursor = Some(SugarCursor::Block(cursor_color));
             }
             CursorShape::Beam => {
-                style.cursor = Some(SugarCursor::Caret(cursor_color));
+                style.cursor =
+                    Some(SugarCursor::Caret(cursor_color, self.cursor_thickness.0));
             }
             CursorShape::Hidden => {}
         }
@@ -670,6 +904,60 @@ impl Renderer {
         (style, content)
     }
 
+    #[inline]
+    pub fn set_latency(&mut self, latency: Option<Duration>) {
+        self.latency = latency;
+    }
+
+    /// Start the visual bell.
+    #[inline]
+    pub fn flash_bell(&mut self, duration: Duration) {
+        self.bell_flash_duration = Some(duration);
+        self.bell_flash_started = Some(Instant::now());
+    }
+
+    /// Time left until the visual bell ends and has to be cleared.
+    #[inline]
+    pub fn bell_flash_remaining(&self) -> Option<Duration> {
+        let started = self.bell_flash_started?;
+        self.bell_flash_duration?.checked_sub(started.elapsed())
+    }
+
+    /// Show a message over the terminal for a few seconds.
+    #[inline]
+    pub fn show_toast(&mut self, message: String) {
+        self.toast = Some((message, Instant::now()));
+    }
+
+    /// Time left until the toast ends and has to be cleared.
+    #[inline]
+    pub fn toast_remaining(&self) -> Option<Duration> {
+        let (_, shown_at) = self.toast.as_ref()?;
+        TOAST_DURATION.checked_sub(shown_at.elapsed())
+    }
+
+    /// Forget which font each character was matched to.
+    #[inline]
+    pub fn clear_font_cache(&mut self) {
+        self.font_cache.clear();
+    }
+
+    /// Lines printed below the viewport while it's scrolled up.
+    #[inline]
+    pub fn set_new_lines(&mut self, new_lines: usize) {
+        self.new_lines = new_lines;
+    }
+
+    #[inline]
+    pub fn set_minimap(&mut self, minimap: Option<minimap::MinimapSample>) {
+        self.minimap = minimap;
+    }
+
+    #[inline]
+    pub fn set_history(&mut self, history: usize) {
+        self.history = history;
+    }
+
     #[inline]
     pub fn set_ime(&mut self, ime_preedit: Option<&Preedit>) {
         if let Some(preedit) = ime_preedit {
@@ -684,6 +972,13 @@ impl Renderer {
         self.cursor.content = self.cursor.content_ref;
     }
 
+    /// Label shown next to the cursor when `keyboard.ime-indicator`
+    /// is enabled, `None` hides it.
+    #[inline]
+    pub fn set_ime_indicator(&mut self, label: Option<&'static str>) {
+        self.ime_indicator = label;
+    }
+
     #[inline]
     pub fn set_selection(&mut self, selection_range: Option<SelectionRange>) {
         self.selection_range = selection_range;
@@ -694,6 +989,39 @@ impl Renderer {
         self.is_vi_mode_enabled = is_vi_mode_enabled;
     }
 
+    /// Position and size of the cursor shape on `row`, `None` when
+    /// it's hidden.
+    fn cursor_area(
+        &self,
+        row: &Row<Square>,
+        origin: (f32, f32),
+        cell: (f32, f32),
+    ) -> Option<([f32; 2], [f32; 2])> {
+        let pos = self.cursor.state.pos;
+        let (cell_width, line_height) = cell;
+        let columns = if row
+            .inner
+            .get(pos.col.0)
+            .is_some_and(|square| square.flags.contains(Flags::WIDE_CHAR))
+        {
+            2.
+        } else {
+            1.
+        };
+        let x = origin.0 + pos.col.0 as f32 * cell_width;
+        let y = origin.1 + pos.row.0 as f32 * line_height;
+        let (beam_width, underline_height) = self.cursor_thickness;
+        match self.cursor.state.content {
+            CursorShape::Block => Some(([x, y], [cell_width * columns, line_height])),
+            CursorShape::Beam => Some(([x, y], [beam_width, line_height])),
+            CursorShape::Underline => Some((
+                [x, y + line_height - underline_height],
+                [cell_width * columns, underline_height],
+            )),
+            CursorShape::Hidden => None,
+        }
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub fn prepare_term(
         &mut self,
@@ -706,6 +1034,8 @@ impl Renderer {
         hints: &mut Option<HintMatches>,
         focused_match: &Option<RangeInclusive<Pos>>,
     ) {
+        self.update_dim_inactive(sugarloaf);
+
         let layout = sugarloaf.layout();
         self.cursor.state = cursor;
         let mut is_cursor_visible = self.cursor.state.is_visible();
@@ -713,6 +1043,15 @@ impl Renderer {
         self.font_size = layout.font_size;
         self.term_has_blinking_enabled = has_blinking_enabled;
 
+        let scale = layout.dimensions.scale;
+        let cell_width = layout.dimensions.width / scale;
+        let line_height =
+            layout.dimensions.height * layout.line_height / layout.dimensions.scale;
+        self.cursor_thickness = (
+            self.cursor_beam_width.resolve(cell_width),
+            self.cursor_underline_height.resolve(line_height),
+        );
+
         // Only blink cursor if does not contain selection
         let has_selection = self.selection_range.is_some();
         if !has_selection && self.has_blinking_enabled() {
@@ -724,11 +1063,36 @@ impl Renderer {
             }
 
             if should_blink {
-                self.is_blinking = !self.is_blinking;
+                self.is_blinking =
+                    self.is_blinking_phase_visible(self.config_blinking_interval);
                 is_cursor_visible = self.is_blinking;
             }
         }
 
+        self.has_blinking_text = false;
+        self.is_text_visible = self
+            .text_blinking_interval
+            .map_or(true, |interval| self.is_blinking_phase_visible(interval));
+
+        let pos = self.cursor.state.pos;
+        let is_cursor_on_screen = (0..rows.len() as i32).contains(&pos.row.0);
+        let cursor_area = if is_cursor_visible && is_cursor_on_screen {
+            self.cursor_area(
+                &rows[pos.row.0 as usize],
+                (layout.margin.x, layout.margin.top_y),
+                (cell_width, line_height),
+            )
+        } else {
+            None
+        };
+        self.cursor_animation
+            .update(context_manager.current_route(), cursor_area);
+        // The animation draws the cursor until it gets to the new cell.
+        let is_cursor_animating = self.cursor_animation.is_animating();
+        if is_cursor_animating {
+            is_cursor_visible = false;
+        }
+
         let content = sugarloaf.content();
 
         // let start = std::time::Instant::now();
@@ -747,6 +1111,42 @@ impl Renderer {
         // println!("Total loop rows: {:?}", duration);
 
         let mut objects = Vec::with_capacity(30);
+
+        // Exit status indicators for prompts that reported it via OSC 133
+        for (i, row) in rows.iter().enumerate() {
+            if let Some(exit_code) = row.exit_code {
+                let color = if exit_code == 0 {
+                    self.named_colors.green
+                } else {
+                    self.named_colors.red
+                };
+                objects.push(Object::Rect(Rect {
+                    position: [
+                        (layout.margin.x - 4.).max(0.),
+                        layout.margin.top_y + (i as f32 * line_height),
+                    ],
+                    color,
+                    size: [2., line_height],
+                }));
+            }
+        }
+
+        if let Some(sample) = &self.minimap {
+            minimap::draw_minimap(
+                &mut objects,
+                &self.named_colors,
+                minimap::area(&layout),
+                sample,
+            );
+        } else if self.scrollbar.enabled {
+            self.scrollbar.draw(
+                &mut objects,
+                &self.named_colors,
+                &layout,
+                (display_offset.max(0) as usize, self.history, rows.len()),
+            );
+        }
+
         self.navigation.build_objects(
             (layout.width, layout.height, layout.dimensions.scale),
             &self.named_colors,
@@ -755,6 +1155,78 @@ impl Renderer {
             &mut objects,
         );
 
+        if self.bell_flash_remaining().is_some() {
+            let foreground = self.named_colors.foreground;
+            objects.push(Object::Rect(Rect {
+                position: [0., 0.],
+                color: [foreground[0], foreground[1], foreground[2], 0.2],
+                size: [
+                    layout.width / layout.dimensions.scale,
+                    layout.height / layout.dimensions.scale,
+                ],
+            }));
+        } else {
+            self.bell_flash_started = None;
+        }
+
+        if self.show_new_lines_indicator && self.new_lines > 0 {
+            new_lines::draw_new_lines_indicator(
+                &mut objects,
+                &self.named_colors,
+                (layout.width, layout.height, layout.dimensions.scale),
+                self.new_lines,
+            );
+        }
+
+        if is_cursor_animating {
+            let cursor_color = if !self.is_vi_mode_enabled {
+                self.named_colors.cursor
+            } else {
+                self.named_colors.vi_cursor
+            };
+            self.cursor_animation.draw(&mut objects, cursor_color);
+        } else if let Some((color, area)) = self.cursor_outline_color.zip(cursor_area) {
+            cursor_outline::draw_cursor_outline(&mut objects, color, area);
+        }
+
+        if let Some(label) = self
+            .ime_indicator
+            .filter(|_| self.show_ime_indicator && is_cursor_on_screen)
+        {
+            ime_indicator::draw_ime_indicator(
+                &mut objects,
+                &self.named_colors,
+                (
+                    layout.margin.x + (pos.col.0 + 1) as f32 * cell_width,
+                    layout.margin.top_y + pos.row.0 as f32 * line_height,
+                ),
+                layout.width / scale,
+                label,
+            );
+        }
+
+        if self.toast_remaining().is_some() {
+            if let Some((message, _)) = &self.toast {
+                toast::draw_toast(
+                    &mut objects,
+                    &self.named_colors,
+                    (layout.width, layout.height, layout.dimensions.scale),
+                    message,
+                );
+            }
+        } else {
+            self.toast = None;
+        }
+
+        if let Some(message) = self.banner {
+            banner::draw_banner(
+                &mut objects,
+                &self.named_colors,
+                (layout.width, layout.height, layout.dimensions.scale),
+                message,
+            );
+        }
+
         if let Some(active_search_content) = &self.active_search {
             search::draw_search_bar(
                 &mut objects,
@@ -764,6 +1236,13 @@ impl Renderer {
             );
 
             self.active_search = None;
+        } else if self.show_latency {
+            latency::draw_latency_indicator(
+                &mut objects,
+                &self.named_colors,
+                (layout.width, layout.height, layout.dimensions.scale),
+                self.latency,
+            );
         }
 
         sugarloaf.set_objects(objects);
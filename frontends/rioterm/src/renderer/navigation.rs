@@ -1,13 +1,49 @@
 use crate::constants::*;
+use crate::context::{ContextManagerTitles, ProcessUsage, TabIndicator};
 use rio_backend::config::colors::Colors;
 use rio_backend::config::navigation::{Navigation, NavigationMode};
 use rio_backend::sugarloaf::{Object, Rect, Text};
 use std::collections::HashMap;
 
+const TAB_WIDTH: f32 = 130.;
+const CLOSE_BUTTON_WIDTH: f32 = 16.;
+const INDICATOR_SIZE: f32 = 6.;
+
+/// What a click on the tab bar does.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TabBarAction {
+    Select(usize),
+    Close(usize),
+}
+
+/// Tab and its close button, in logical pixels.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct TabArea {
+    index: usize,
+    position: [f32; 2],
+    size: [f32; 2],
+}
+
+impl TabArea {
+    #[inline]
+    fn contains(&self, x: f32, y: f32) -> bool {
+        let ([left, top], [width, height]) = (self.position, self.size);
+        x >= left && x < left + width && y >= top && y < top + height
+    }
+
+    #[inline]
+    fn close_button_x(&self) -> f32 {
+        self.position[0] + self.size[0] - CLOSE_BUTTON_WIDTH
+    }
+}
+
 pub struct ScreenNavigation {
     pub navigation: Navigation,
     pub objects: Vec<Object>,
     keys: String,
+    usage: HashMap<usize, ProcessUsage>,
+    indicators: Vec<Option<TabIndicator>>,
+    tab_areas: Vec<TabArea>,
     current: usize,
     len: usize,
     width: f32,
@@ -27,6 +63,9 @@ impl ScreenNavigation {
             navigation,
             objects: Vec::with_capacity(26),
             keys: String::from(""),
+            usage: HashMap::new(),
+            indicators: Vec::new(),
+            tab_areas: Vec::new(),
             color_automation,
             current: 0,
             len: 0,
@@ -67,6 +106,7 @@ impl ScreenNavigation {
         // When search is active then BottomTab should not be rendered
         if is_search_active && self.navigation.mode == NavigationMode::BottomTab {
             self.objects.clear();
+            self.tab_areas.clear();
             self.keys.clear();
             return;
         }
@@ -77,6 +117,12 @@ impl ScreenNavigation {
             has_changes = true;
         }
 
+        let usage = &context_manager.titles.usage;
+        if usage != &self.usage {
+            self.usage = usage.clone();
+            has_changes = true;
+        }
+
         let current = context_manager.current_index();
         if current != self.current {
             self.current = current;
@@ -89,12 +135,19 @@ impl ScreenNavigation {
             has_changes = true;
         }
 
+        let indicators = (0..len).map(|i| context_manager.tab_indicator(i));
+        if !indicators.clone().eq(self.indicators.iter().copied()) {
+            self.indicators = indicators.collect();
+            has_changes = true;
+        }
+
         if !has_changes {
             objects.extend(self.objects.clone());
             return;
         }
 
         self.objects.clear();
+        self.tab_areas.clear();
 
         let titles = &context_manager.titles.titles;
 
@@ -107,7 +160,7 @@ impl ScreenNavigation {
             NavigationMode::TopTab => {
                 let position_y = 0.0;
                 self.tab(
-                    titles,
+                    &context_manager.titles,
                     colors,
                     len,
                     position_y,
@@ -117,7 +170,7 @@ impl ScreenNavigation {
             NavigationMode::BottomTab => {
                 let position_y = (self.height / self.scale) - PADDING_Y_BOTTOM_TABS;
                 self.tab(
-                    titles,
+                    &context_manager.titles,
                     colors,
                     len,
                     position_y,
@@ -134,7 +187,7 @@ impl ScreenNavigation {
     #[inline]
     pub fn bookmark(
         &mut self,
-        titles: &HashMap<usize, [String; 3]>,
+        titles: &HashMap<usize, [String; 5]>,
         colors: &Colors,
         len: usize,
         hide_if_single: bool,
@@ -174,7 +227,7 @@ impl ScreenNavigation {
     #[inline]
     pub fn tab(
         &mut self,
-        titles: &HashMap<usize, [String; 3]>,
+        titles: &ContextManagerTitles,
         colors: &Colors,
         len: usize,
         position_y: f32,
@@ -197,13 +250,13 @@ impl ScreenNavigation {
         let iter = 0..len;
         let mut tabs = Vec::from_iter(iter);
 
-        let max_tab_width = 140.;
-        let screen_limit = ((self.width / self.scale) / max_tab_width).floor() as usize;
+        let screen_limit = ((self.width / self.scale) / TAB_WIDTH).floor() as usize;
         if len > screen_limit && self.current > screen_limit {
             tabs = Vec::from_iter(self.current - screen_limit..len);
         }
 
         let text_pos_mod = 11.;
+        let clickable = self.navigation.clickable;
         for i in tabs {
             let mut background_color = colors.bar;
             let mut foreground_color = colors.tabs_foreground;
@@ -215,8 +268,10 @@ impl ScreenNavigation {
             }
 
             let mut name = String::from("tab");
-            if let Some(title) = titles.get(&i) {
-                if title[1].is_empty() {
+            if let Some(title) = titles.titles.get(&i) {
+                if let Some(label) = titles.labels.get(&i) {
+                    name = label.to_string();
+                } else if title[1].is_empty() {
                     name = title[0].to_string();
                 } else {
                     name = format!("{} ({})", title[0], title[1]);
@@ -230,15 +285,29 @@ impl ScreenNavigation {
                 }
             }
 
-            let name_modifier = 90.;
-            if name.len() >= 14 {
-                name = name[0..14].to_string();
+            // CPU usage takes the end of the name, memory only fits the window title
+            let cpu = titles.usage.get(&i).map(|usage| format!(" {}%", usage.cpu));
+            let max_name_len = if clickable { 12 } else { 14 };
+            let max_len =
+                max_name_len - cpu.as_ref().map_or(0, String::len).min(max_name_len);
+            if name.chars().count() >= max_len {
+                name = name.chars().take(max_len).collect();
+            }
+            if let Some(cpu) = cpu {
+                name.push_str(&cpu);
             }
 
-            self.objects.push(Object::Rect(Rect {
+            let area = TabArea {
+                index: i,
                 position: [initial_position_x, position_y],
+                size: [TAB_WIDTH, PADDING_Y_BOTTOM_TABS],
+            };
+            self.tab_areas.push(area);
+
+            self.objects.push(Object::Rect(Rect {
+                position: area.position,
                 color: background_color,
-                size: [250., PADDING_Y_BOTTOM_TABS],
+                size: area.size,
             }));
 
             if is_current {
@@ -252,7 +321,7 @@ impl ScreenNavigation {
                 self.objects.push(Object::Rect(Rect {
                     position: [initial_position_x, position],
                     color: colors.tabs_active_highlight,
-                    size: [250., PADDING_Y_BOTTOM_TABS / 10.],
+                    size: [TAB_WIDTH, PADDING_Y_BOTTOM_TABS / 10.],
                 }));
             }
 
@@ -269,7 +338,55 @@ impl ScreenNavigation {
                 foreground_color,
             )));
 
-            initial_position_x += name_modifier + 40.;
+            // The close button takes the end of the tab when it's clickable
+            let end_x = if clickable {
+                area.close_button_x()
+            } else {
+                area.position[0] + TAB_WIDTH
+            };
+
+            // Tabs in the background show new output and bells
+            let indicator = self.indicators.get(i).copied().flatten();
+            if let Some(indicator) = indicator.filter(|_| !is_current) {
+                let color = match indicator {
+                    TabIndicator::Activity => colors.tabs_active_highlight,
+                    TabIndicator::Bell => colors.red,
+                };
+                self.objects.push(Object::Rect(Rect {
+                    position: [
+                        end_x - INDICATOR_SIZE - 4.,
+                        position_y + (PADDING_Y_BOTTOM_TABS - INDICATOR_SIZE) / 2.,
+                    ],
+                    color,
+                    size: [INDICATOR_SIZE, INDICATOR_SIZE],
+                }));
+            }
+
+            if clickable {
+                self.objects.push(Object::Text(Text::single_line(
+                    (end_x + 4., position_y + text_pos_mod),
+                    String::from("×"),
+                    14.,
+                    foreground_color,
+                )));
+            }
+
+            initial_position_x += TAB_WIDTH;
+        }
+    }
+
+    /// Tab bar action under the point, in logical pixels, only with
+    /// `clickable` tabs.
+    pub fn hit(&self, x: f32, y: f32) -> Option<TabBarAction> {
+        if !self.navigation.clickable {
+            return None;
+        }
+
+        let area = self.tab_areas.iter().find(|area| area.contains(x, y))?;
+        if x >= area.close_button_x() {
+            Some(TabBarAction::Close(area.index))
+        } else {
+            Some(TabBarAction::Select(area.index))
         }
     }
 }
@@ -288,9 +405,59 @@ fn get_color_overwrite<'a>(
 
 #[cfg(test)]
 mod tests {
-    use std::collections::HashMap;
+    use super::*;
 
-    use crate::renderer::navigation::get_color_overwrite;
+    #[test]
+    fn test_tab_bar() {
+        let navigation = Navigation {
+            mode: NavigationMode::TopTab,
+            clickable: true,
+            ..Navigation::default()
+        };
+        let mut screen_navigation =
+            ScreenNavigation::new(navigation, HashMap::new(), [0., 0.]);
+        screen_navigation.width = 1000.;
+        screen_navigation.scale = 1.;
+        screen_navigation.indicators = vec![None, Some(TabIndicator::Bell), None];
+
+        let colors = Colors::default();
+        let titles = ContextManagerTitles::new(
+            0,
+            String::from("zsh"),
+            String::new(),
+            String::new(),
+        );
+        screen_navigation.tab(&titles, &colors, 3, 0., true);
+
+        // Only the tab in the background shows the bell
+        let bells = screen_navigation
+            .objects
+            .iter()
+            .filter(
+                |object| matches!(object, Object::Rect(rect) if rect.color == colors.red),
+            )
+            .count();
+        assert_eq!(bells, 1);
+
+        assert_eq!(
+            screen_navigation.hit(10., 10.),
+            Some(TabBarAction::Select(0))
+        );
+        assert_eq!(
+            screen_navigation.hit(TAB_WIDTH + 10., 10.),
+            Some(TabBarAction::Select(1))
+        );
+        assert_eq!(
+            screen_navigation.hit(TAB_WIDTH * 2. - 5., 10.),
+            Some(TabBarAction::Close(1))
+        );
+        assert_eq!(screen_navigation.hit(10., PADDING_Y_BOTTOM_TABS + 1.), None);
+        assert_eq!(screen_navigation.hit(TAB_WIDTH * 3. + 10., 10.), None);
+
+        // Clicks go through to the terminal unless tabs are clickable
+        screen_navigation.navigation.clickable = false;
+        assert_eq!(screen_navigation.hit(10., 10.), None);
+    }
 
     #[test]
     fn test_get_color_overwrite() {
@@ -0,0 +1,39 @@
+use crate::constants::*;
+use rio_backend::config::colors::Colors;
+use rio_backend::sugarloaf::{Object, Rect, Text};
+
+const PILL_HEIGHT: f32 = 22.;
+
+/// Draws the "N new lines ↓" pill shown when output arrives while the
+/// viewport is scrolled up.
+#[inline]
+pub fn draw_new_lines_indicator(
+    objects: &mut Vec<Object>,
+    colors: &Colors,
+    dimensions: (f32, f32, f32),
+    lines: usize,
+) {
+    let (width, height, scale) = dimensions;
+    let content = if lines == 1 {
+        String::from("1 new line ↓")
+    } else {
+        format!("{lines} new lines ↓")
+    };
+
+    // Rough width of the text, the pill doesn't need to be exact.
+    let pill_width = content.chars().count() as f32 * 7. + 20.;
+    let position_x = ((width / scale) - pill_width) / 2.;
+    let position_y = (height / scale) - PADDING_Y_BOTTOM_TABS - PILL_HEIGHT - 8.;
+
+    objects.push(Object::Rect(Rect {
+        position: [position_x, position_y],
+        color: colors.bar,
+        size: [pill_width, PILL_HEIGHT],
+    }));
+    objects.push(Object::Text(Text::single_line(
+        (position_x + 10., position_y + 5.),
+        content,
+        12.,
+        colors.foreground,
+    )));
+}
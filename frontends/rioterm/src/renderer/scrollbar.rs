@@ -0,0 +1,193 @@
+use rio_backend::config::colors::Colors;
+use rio_backend::sugarloaf::layout::SugarloafLayout;
+use rio_backend::sugarloaf::{Object, Rect};
+use std::time::{Duration, Instant};
+
+const SCROLLBAR_WIDTH: f32 = 6.;
+const MIN_THUMB_HEIGHT: f32 = 20.;
+const FADE_DURATION: Duration = Duration::from_millis(200);
+
+/// Overlay scrollbar, shown while the viewport is scrolled into the
+/// scrollback and faded in and out.
+#[derive(Default)]
+pub struct Scrollbar {
+    pub enabled: bool,
+    is_visible: bool,
+    changed_at: Option<Instant>,
+    /// Track and thumb of the last frame, in logical pixels.
+    track: ([f32; 2], [f32; 2]),
+    thumb: Option<([f32; 2], [f32; 2])>,
+    /// Distance from the top of the thumb to where it was grabbed.
+    pub drag: Option<f32>,
+}
+
+impl Scrollbar {
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            ..Self::default()
+        }
+    }
+
+    #[inline]
+    fn set_visible(&mut self, is_visible: bool) {
+        if is_visible != self.is_visible {
+            self.is_visible = is_visible;
+            self.changed_at = Some(Instant::now());
+        }
+    }
+
+    fn opacity(&self) -> f32 {
+        let progress = self.changed_at.map_or(1., |changed_at| {
+            (changed_at.elapsed().as_secs_f32() / FADE_DURATION.as_secs_f32()).min(1.)
+        });
+        if self.is_visible {
+            progress
+        } else {
+            1. - progress
+        }
+    }
+
+    /// Time left of the running fade.
+    #[inline]
+    pub fn fade_remaining(&self) -> Option<Duration> {
+        self.changed_at
+            .and_then(|changed_at| FADE_DURATION.checked_sub(changed_at.elapsed()))
+    }
+
+    /// Track and thumb under the point, when the scrollbar is shown.
+    #[inline]
+    pub fn hit(&self, x: f32, y: f32) -> Option<([f32; 2], [f32; 2])> {
+        let ([track_x, track_y], [width, height]) = self.track;
+        let is_inside =
+            x >= track_x && x <= track_x + width && y >= track_y && y <= track_y + height;
+        self.thumb.filter(|_| self.is_visible && is_inside)
+    }
+
+    /// Track of the last frame.
+    #[inline]
+    pub fn track(&self) -> ([f32; 2], [f32; 2]) {
+        self.track
+    }
+
+    /// Thumb of the last frame.
+    #[inline]
+    pub fn thumb(&self) -> Option<([f32; 2], [f32; 2])> {
+        self.thumb
+    }
+
+    pub fn draw(
+        &mut self,
+        objects: &mut Vec<Object>,
+        colors: &Colors,
+        layout: &SugarloafLayout,
+        scroll: (usize, usize, usize),
+    ) {
+        let (display_offset, history, screen_lines) = scroll;
+        self.set_visible(display_offset > 0 || self.drag.is_some());
+
+        self.track = track(layout);
+        self.thumb = thumb(self.track, display_offset, history, screen_lines);
+        let opacity = self.opacity();
+        if let Some((position, size)) = self.thumb.filter(|_| opacity > 0.) {
+            let foreground = colors.foreground;
+            objects.push(Object::Rect(Rect {
+                position,
+                color: [foreground[0], foreground[1], foreground[2], 0.4 * opacity],
+                size,
+            }));
+        }
+    }
+}
+
+/// Right edge of the text area.
+#[inline]
+fn track(layout: &SugarloafLayout) -> ([f32; 2], [f32; 2]) {
+    let scale = layout.dimensions.scale;
+    let line_height = layout.dimensions.height * layout.line_height / scale;
+    (
+        [
+            layout.width / scale - SCROLLBAR_WIDTH - 2.,
+            layout.margin.top_y,
+        ],
+        [SCROLLBAR_WIDTH, layout.lines as f32 * line_height],
+    )
+}
+
+/// Thumb sized by the share of the scrollback on screen, `None` without
+/// scrollback.
+fn thumb(
+    track: ([f32; 2], [f32; 2]),
+    display_offset: usize,
+    history: usize,
+    screen_lines: usize,
+) -> Option<([f32; 2], [f32; 2])> {
+    if history == 0 {
+        return None;
+    }
+
+    let ([x, y], [width, height]) = track;
+    let total = (history + screen_lines) as f32;
+    let thumb_height = (height * screen_lines as f32 / total)
+        .max(MIN_THUMB_HEIGHT)
+        .min(height);
+    let scrolled = (history - display_offset.min(history)) as f32 / history as f32;
+    Some((
+        [x, y + scrolled * (height - thumb_height)],
+        [width, thumb_height],
+    ))
+}
+
+/// Display offset for the top of the thumb at `thumb_y`.
+pub fn display_offset_at(
+    track: ([f32; 2], [f32; 2]),
+    thumb_height: f32,
+    thumb_y: f32,
+    history: usize,
+) -> usize {
+    let ([_, y], [_, height]) = track;
+    let range = (height - thumb_height).max(1.);
+    let scrolled = ((thumb_y - y) / range).clamp(0., 1.);
+    history - (scrolled * history as f32).round() as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRACK: ([f32; 2], [f32; 2]) = ([100., 10.], [6., 200.]);
+
+    #[test]
+    fn test_thumb() {
+        assert_eq!(thumb(TRACK, 0, 0, 50), None);
+        // A quarter of the lines are on screen, at the bottom
+        assert_eq!(thumb(TRACK, 0, 150, 50), Some(([100., 160.], [6., 50.])));
+        assert_eq!(thumb(TRACK, 150, 150, 50), Some(([100., 10.], [6., 50.])));
+        // Long scrollbacks keep the thumb large enough to grab
+        assert_eq!(
+            thumb(TRACK, 0, 100_000, 50),
+            Some(([100., 190.], [6., MIN_THUMB_HEIGHT]))
+        );
+    }
+
+    #[test]
+    fn test_display_offset_at() {
+        assert_eq!(display_offset_at(TRACK, 50., 160., 150), 0);
+        assert_eq!(display_offset_at(TRACK, 50., 10., 150), 150);
+        assert_eq!(display_offset_at(TRACK, 50., 85., 150), 75);
+        // Dragging past the ends stops there
+        assert_eq!(display_offset_at(TRACK, 50., -40., 150), 150);
+        assert_eq!(display_offset_at(TRACK, 50., 400., 150), 0);
+    }
+
+    #[test]
+    fn test_fade() {
+        let mut scrollbar = Scrollbar::new(true);
+        assert_eq!(scrollbar.opacity(), 0.);
+        assert_eq!(scrollbar.fade_remaining(), None);
+
+        scrollbar.set_visible(true);
+        assert!(scrollbar.fade_remaining().is_some());
+        assert!(scrollbar.opacity() < 1.);
+    }
+}
@@ -0,0 +1,138 @@
+use std::time::{Duration, Instant};
+
+/// Animates the viewport between display offsets. Positions count lines
+/// from the top of the scrollback, unlike the display offset they don't
+/// change when new output is pushed into the scrollback.
+pub struct SmoothScroll {
+    duration: Duration,
+    from: f32,
+    // Nothing to animate from before the first frame.
+    to: Option<usize>,
+    started_at: Option<Instant>,
+    // Route and whether it's the alternate screen, the viewport jumps
+    // when either changes.
+    view: (usize, bool),
+}
+
+impl SmoothScroll {
+    pub fn new(duration: Duration) -> Self {
+        Self {
+            duration,
+            from: 0.,
+            to: None,
+            started_at: None,
+            view: (0, false),
+        }
+    }
+
+    #[inline]
+    fn progress(&self) -> f32 {
+        match self.started_at {
+            Some(started_at) if !self.duration.is_zero() => {
+                (started_at.elapsed().as_secs_f32() / self.duration.as_secs_f32()).min(1.)
+            }
+            _ => 1.,
+        }
+    }
+
+    #[inline]
+    fn position(&self) -> f32 {
+        // Ease out, so it starts right away and slows down at the end.
+        let eased = 1. - (1. - self.progress()).powi(3);
+        let to = self.to.unwrap_or_default() as f32;
+        self.from + (to - self.from) * eased
+    }
+
+    /// Display offset to draw, with a fraction while the viewport moves.
+    pub fn update(
+        &mut self,
+        view: (usize, bool),
+        history: usize,
+        display_offset: usize,
+    ) -> f32 {
+        let to = history - display_offset.min(history);
+        if view != self.view {
+            self.view = view;
+            self.to = None;
+        }
+
+        match self.to {
+            Some(current) if current == to => {}
+            Some(_) if !self.duration.is_zero() => {
+                self.from = self.position();
+                self.to = Some(to);
+                self.started_at = Some(Instant::now());
+            }
+            _ => {
+                self.from = to as f32;
+                self.to = Some(to);
+                self.started_at = None;
+            }
+        }
+
+        (history as f32 - self.position()).clamp(0., history as f32)
+    }
+
+    /// The next position is jumped to, e.g. after a resize reflowed
+    /// the lines.
+    #[inline]
+    pub fn reset(&mut self) {
+        self.to = None;
+        self.started_at = None;
+    }
+
+    #[inline]
+    pub fn is_animating(&self) -> bool {
+        self.progress() < 1.
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disabled() {
+        let mut smooth_scroll = SmoothScroll::new(Duration::ZERO);
+        assert_eq!(smooth_scroll.update((0, false), 100, 0), 0.);
+        assert_eq!(smooth_scroll.update((0, false), 100, 10), 10.);
+        assert!(!smooth_scroll.is_animating());
+    }
+
+    #[test]
+    fn test_animation() {
+        let mut smooth_scroll = SmoothScroll::new(Duration::from_secs(60));
+        assert_eq!(smooth_scroll.update((0, false), 100, 0), 0.);
+
+        // Starts where the viewport was and moves towards the new offset
+        let offset = smooth_scroll.update((0, false), 100, 10);
+        assert!((0. ..1.).contains(&offset));
+        assert!(smooth_scroll.is_animating());
+
+        smooth_scroll.reset();
+        assert_eq!(smooth_scroll.update((0, false), 100, 10), 10.);
+        assert!(!smooth_scroll.is_animating());
+    }
+
+    #[test]
+    fn test_output_while_scrolled_up() {
+        let mut smooth_scroll = SmoothScroll::new(Duration::from_secs(60));
+        assert_eq!(smooth_scroll.update((0, false), 100, 10), 10.);
+        smooth_scroll.reset();
+
+        // New lines keep the viewport on the same lines, nothing moves
+        assert_eq!(smooth_scroll.update((0, false), 105, 15), 15.);
+        assert!(!smooth_scroll.is_animating());
+    }
+
+    #[test]
+    fn test_switching_view() {
+        let mut smooth_scroll = SmoothScroll::new(Duration::from_secs(60));
+        assert_eq!(smooth_scroll.update((0, false), 100, 0), 0.);
+
+        // Other tabs and the alternate screen don't animate from this one
+        assert_eq!(smooth_scroll.update((1, false), 50, 10), 10.);
+        assert_eq!(smooth_scroll.update((1, true), 0, 0), 0.);
+        assert!(!smooth_scroll.is_animating());
+    }
+}
@@ -0,0 +1,38 @@
+use crate::constants::*;
+use rio_backend::config::colors::Colors;
+use rio_backend::sugarloaf::{Object, Rect, Text};
+
+const LINE_HEIGHT: f32 = 16.;
+
+/// Draws a message in the bottom right corner, above the bottom tabs.
+#[inline]
+pub fn draw_toast(
+    objects: &mut Vec<Object>,
+    colors: &Colors,
+    dimensions: (f32, f32, f32),
+    message: &str,
+) {
+    let (width, height, scale) = dimensions;
+    let lines: Vec<&str> = message.lines().collect();
+
+    // Rough width of the text, the toast doesn't need to be exact.
+    let longest = lines.iter().map(|line| line.chars().count()).max();
+    let toast_width = (longest.unwrap_or_default() as f32 * 7. + 20.).min(width / scale);
+    let toast_height = lines.len() as f32 * LINE_HEIGHT + 12.;
+    let position_x = (width / scale) - toast_width - 8.;
+    let position_y = (height / scale) - PADDING_Y_BOTTOM_TABS - toast_height - 8.;
+
+    objects.push(Object::Rect(Rect {
+        position: [position_x, position_y],
+        color: colors.bar,
+        size: [toast_width, toast_height],
+    }));
+    for (i, line) in lines.into_iter().enumerate() {
+        objects.push(Object::Text(Text::single_line(
+            (position_x + 10., position_y + 6. + i as f32 * LINE_HEIGHT),
+            line.to_string(),
+            12.,
+            colors.foreground,
+        )));
+    }
+}
@@ -60,11 +60,13 @@ pub fn padding_bottom_from_config(
 pub fn terminal_dimensions(
     layout: &rio_backend::sugarloaf::layout::SugarloafLayout,
 ) -> teletypewriter::WinsizeBuilder {
-    let width = layout.width - (layout.margin.x * 2.);
-    let height = (layout.height - layout.margin.top_y) - layout.margin.bottom_y;
+    // Size of the grid in physical pixels, so programs dividing it by the
+    // columns and lines (e.g. for sixel images) get the size of a cell.
+    let width = layout.columns as f32 * layout.dimensions.width;
+    let height = layout.lines as f32 * layout.dimensions.height * layout.line_height;
     teletypewriter::WinsizeBuilder {
-        width: width as u16,
-        height: height as u16,
+        width: width.min(u16::MAX as f32) as u16,
+        height: height.min(u16::MAX as f32) as u16,
         cols: layout.columns as u16,
         rows: layout.lines as u16,
     }
@@ -88,3 +90,25 @@ pub fn update_colors_based_on_theme(config: &mut Config, theme_opt: Option<Theme
         }
     }
 }
+
+/// Minutes elapsed since midnight in the local timezone.
+#[cfg(unix)]
+pub fn local_minutes_of_day() -> u16 {
+    unsafe {
+        let now = libc::time(std::ptr::null_mut());
+        let mut tm: libc::tm = std::mem::zeroed();
+        libc::localtime_r(&now, &mut tm);
+        (tm.tm_hour * 60 + tm.tm_min) as u16
+    }
+}
+
+/// Minutes elapsed since midnight in the local timezone.
+#[cfg(windows)]
+pub fn local_minutes_of_day() -> u16 {
+    use windows_sys::Win32::Foundation::SYSTEMTIME;
+    use windows_sys::Win32::System::SystemInformation::GetLocalTime;
+
+    let mut time: SYSTEMTIME = unsafe { std::mem::zeroed() };
+    unsafe { GetLocalTime(&mut time) };
+    time.wHour * 60 + time.wMinute
+}
@@ -1,6 +1,6 @@
 pub mod routes;
 mod window;
-use crate::event::EventProxy;
+use crate::event::{EventProxy, RioEvent, RioEventType};
 use crate::router::window::{configure_window, create_window_builder};
 use crate::screen::{Screen, ScreenWindowProperties};
 use assistant::Assistant;
@@ -8,13 +8,14 @@ use raw_window_handle::{HasDisplayHandle, HasWindowHandle};
 use rio_backend::clipboard::Clipboard;
 use rio_backend::config::Config as RioConfig;
 use rio_backend::error::{RioError, RioErrorLevel, RioErrorType};
+use rio_window::event::ElementState;
 use rio_window::event_loop::ActiveEventLoop;
 use rio_window::keyboard::{Key, NamedKey};
 #[cfg(not(any(target_os = "macos", windows)))]
 use rio_window::platform::startup_notify::{
     self, EventLoopExtStartupNotify, WindowAttributesExtStartupNotify,
 };
-use rio_window::window::{Window, WindowId};
+use rio_window::window::{UserAttentionType, Window, WindowId};
 use routes::{assistant, RoutePath};
 use rustc_hash::FxHashMap;
 use std::cell::RefCell;
@@ -32,6 +33,8 @@ pub struct Route<'a> {
     pub assistant: assistant::Assistant,
     pub path: RoutePath,
     pub window: RouteWindow<'a>,
+    /// Programs listed by the quit and close confirmations.
+    pub running_processes: Vec<String>,
 }
 
 impl Route<'_> {
@@ -46,6 +49,7 @@ impl Route<'_> {
             assistant,
             path,
             window,
+            running_processes: Vec::new(),
         }
     }
 }
@@ -56,6 +60,14 @@ impl Route<'_> {
         self.window.winit_window.request_redraw();
     }
 
+    /// Mark the window as urgent until it gets focused.
+    #[inline]
+    pub fn request_attention(&mut self) {
+        self.window
+            .winit_window
+            .request_user_attention(Some(UserAttentionType::Informational));
+    }
+
     #[inline]
     pub fn update_config(
         &mut self,
@@ -77,6 +89,13 @@ impl Route<'_> {
         self.window.winit_window.set_title(title);
     }
 
+    #[inline]
+    pub fn reset_window_title(&mut self) {
+        self.window
+            .winit_window
+            .set_title(&self.window.default_title);
+    }
+
     #[inline]
     pub fn report_error(&mut self, error: &RioError) {
         if error.report == RioErrorType::ConfigurationNotFound {
@@ -95,12 +114,42 @@ impl Route<'_> {
     }
 
     #[inline]
-    pub fn confirm_quit(&mut self) {
+    pub fn confirm_quit(&mut self, running_processes: Vec<String>) {
+        self.running_processes = running_processes;
         self.path = RoutePath::ConfirmQuit;
     }
 
+    #[inline]
+    pub fn confirm_close(&mut self, running_processes: Vec<String>) {
+        self.running_processes = running_processes;
+        self.path = RoutePath::ConfirmClose;
+    }
+
+    /// Programs started from the shells of this window, each name once.
+    pub fn running_processes(&self) -> Vec<String> {
+        let mut processes = self.window.screen.context_manager.running_processes();
+        processes.sort_unstable();
+        processes.dedup();
+        processes
+    }
+
+    #[inline]
+    pub fn confirm_print(&mut self) {
+        if self.window.screen.pending_print.is_some() {
+            self.path = RoutePath::ConfirmPrint;
+        }
+    }
+
+    #[inline]
+    pub fn show_pane_diff(&mut self) {
+        if self.window.screen.pane_diff.is_some() {
+            self.path = RoutePath::Diff;
+        }
+    }
+
     #[inline]
     pub fn quit(&mut self) {
+        crate::crash_loop::clear();
         std::process::exit(0);
     }
 
@@ -130,6 +179,54 @@ impl Route<'_> {
             return true;
         }
 
+        if self.path == RoutePath::ConfirmClose {
+            if key_event.logical_key == Key::Named(NamedKey::Escape) {
+                self.path = RoutePath::Terminal;
+            } else if is_enter && key_event.state == ElementState::Pressed {
+                self.window.screen.context_manager.force_close_window();
+            }
+
+            return true;
+        }
+
+        if self.path == RoutePath::ConfirmPrint {
+            if key_event.logical_key == Key::Named(NamedKey::Escape) {
+                self.window.screen.pending_print = None;
+                self.path = RoutePath::Terminal;
+            } else if is_enter && key_event.state == ElementState::Pressed {
+                if let Some(lines) = self.window.screen.pending_print.take() {
+                    crate::print::print(lines);
+                }
+                self.path = RoutePath::Terminal;
+            }
+
+            return true;
+        }
+
+        if self.path == RoutePath::Diff {
+            let scroll = match &key_event.logical_key {
+                Key::Named(NamedKey::ArrowUp) => -1,
+                Key::Named(NamedKey::ArrowDown) => 1,
+                Key::Named(NamedKey::PageUp) => -20,
+                Key::Named(NamedKey::PageDown) => 20,
+                Key::Named(NamedKey::Escape) | Key::Named(NamedKey::Enter) => {
+                    self.window.screen.pane_diff = None;
+                    self.path = RoutePath::Terminal;
+                    return true;
+                }
+                _ => return true,
+            };
+
+            if key_event.state == ElementState::Pressed {
+                if let Some(diff) = &mut self.window.screen.pane_diff {
+                    diff.scroll_by(scroll);
+                }
+                self.request_redraw();
+            }
+
+            return true;
+        }
+
         if self.path == RoutePath::Welcome && is_enter {
             rio_backend::config::create_config_file(None);
             self.path = RoutePath::Terminal;
@@ -146,15 +243,36 @@ pub struct Router<'a> {
     pub font_library: Box<rio_backend::sugarloaf::font::FontLibrary>,
     pub config_route: Option<WindowId>,
     pub clipboard: Rc<RefCell<Clipboard>>,
+    /// Path of the control socket, exported to the shells.
+    pub control_socket: Option<std::path::PathBuf>,
+}
+
+/// Loads the primary fonts and notifies with `RioEvent::FontsLoaded` once
+/// the remaining ones were loaded in the background.
+pub fn load_font_library(
+    fonts: rio_backend::sugarloaf::font::SugarloafFonts,
+    event_proxy: EventProxy,
+) -> (
+    rio_backend::sugarloaf::font::FontLibrary,
+    Option<rio_backend::sugarloaf::SugarloafErrors>,
+) {
+    rio_backend::sugarloaf::font::FontLibrary::new_lazy(fonts, move |errors| {
+        event_proxy.send_event(
+            RioEventType::Rio(RioEvent::FontsLoaded(
+                errors.map(|err| err.fonts_not_found).unwrap_or_default(),
+            )),
+            rio_backend::event::WindowId::from(0),
+        );
+    })
 }
 
 impl Router<'_> {
     pub fn new<'b>(
         fonts: rio_backend::sugarloaf::font::SugarloafFonts,
         clipboard: Clipboard,
+        event_proxy: EventProxy,
     ) -> Router<'b> {
-        let (font_library, fonts_not_found) =
-            rio_backend::sugarloaf::font::FontLibrary::new(fonts);
+        let (font_library, fonts_not_found) = load_font_library(fonts, event_proxy);
 
         let mut propagated_report = None;
 
@@ -173,6 +291,7 @@ impl Router<'_> {
             config_route: None,
             font_library: Box::new(font_library),
             clipboard,
+            control_socket: None,
         }
     }
 
@@ -230,6 +349,8 @@ impl Router<'_> {
             shell: rio_backend::config::Shell {
                 program: editor.program,
                 args,
+                env: editor.env,
+                login: editor.login,
             },
             ..current_config
         };
@@ -243,6 +364,7 @@ impl Router<'_> {
             None,
             None,
             self.clipboard.clone(),
+            self.control_socket.as_deref(),
         );
         let id = window.winit_window.id();
         let route = Route::new(Assistant::new(), RoutePath::Terminal, window);
@@ -250,6 +372,18 @@ impl Router<'_> {
         self.config_route = Some(id);
     }
 
+    /// Programs started from the shells of every window, each name once.
+    pub fn running_processes(&self) -> Vec<String> {
+        let mut processes: Vec<String> = self
+            .routes
+            .values()
+            .flat_map(|route| route.running_processes())
+            .collect();
+        processes.sort_unstable();
+        processes.dedup();
+        processes
+    }
+
     #[inline]
     pub fn create_window<'a>(
         &'a mut self,
@@ -273,14 +407,11 @@ impl Router<'_> {
             tab_id.as_deref(),
             open_url,
             self.clipboard.clone(),
+            self.control_socket.as_deref(),
         );
         let id = window.winit_window.id();
 
-        let mut route = Route {
-            window,
-            path: RoutePath::Terminal,
-            assistant: Assistant::new(),
-        };
+        let mut route = Route::new(Assistant::new(), RoutePath::Terminal, window);
 
         if let Some(err) = &self.propagated_report {
             route.report_error(err);
@@ -309,14 +440,11 @@ impl Router<'_> {
             tab_id,
             open_url,
             self.clipboard.clone(),
+            self.control_socket.as_deref(),
         );
         self.routes.insert(
             window.winit_window.id(),
-            Route {
-                window,
-                path: RoutePath::Terminal,
-                assistant: Assistant::new(),
-            },
+            Route::new(Assistant::new(), RoutePath::Terminal, window),
         );
     }
 }
@@ -325,10 +453,16 @@ pub struct RouteWindow<'a> {
     pub is_focused: bool,
     pub is_occluded: bool,
     has_fps_target: bool,
+    max_fps: Option<u64>,
+    unfocused_interval: Option<Duration>,
+    // Input method state last applied to the window.
+    ime_allowed: bool,
     pub render_timestamp: Instant,
     pub vblank_interval: Duration,
     pub winit_window: Window,
     pub screen: Screen<'a>,
+    // Title used when applications reset the window title.
+    pub default_title: String,
     #[cfg(target_os = "macos")]
     pub is_macos_deadzone: bool,
 }
@@ -338,38 +472,78 @@ impl<'a> RouteWindow<'a> {
         configure_window(&self.winit_window, config);
     }
 
+    /// Applies the input method state of the focused context, each
+    /// context remembers its own.
+    pub fn sync_ime(&mut self) {
+        let allowed = self.screen.ctx().current().ime_allowed;
+        if self.ime_allowed != allowed {
+            self.ime_allowed = allowed;
+            self.winit_window.set_ime_allowed(allowed);
+            if !allowed {
+                self.screen.ime.set_enabled(false);
+            }
+        }
+    }
+
     pub fn start_render_timestamp(&mut self) {
         self.render_timestamp = Instant::now();
     }
 
     pub fn wait_until(&self) -> Option<Duration> {
-        let elapsed_time = Instant::now()
-            .duration_since(self.render_timestamp)
-            .as_millis() as u64;
-        let vblank_interval = self.vblank_interval.as_millis() as u64;
-
-        match vblank_interval >= elapsed_time {
-            true => Some(Duration::from_millis(vblank_interval - elapsed_time)),
-            // false => None,
-            false => Some(Duration::from_millis(vblank_interval.wrapping_sub(1))),
+        let elapsed_time = Instant::now().duration_since(self.render_timestamp);
+        let render_interval = self.render_interval();
+
+        match render_interval.checked_sub(elapsed_time) {
+            Some(remaining) => Some(remaining),
+            // None => None,
+            None => Some(render_interval.saturating_sub(Duration::from_millis(1))),
         }
     }
 
+    /// Interval between frames, reduced while the window is in the background.
+    #[inline]
+    pub fn render_interval(&self) -> Duration {
+        throttled_interval(
+            self.vblank_interval,
+            self.unfocused_interval,
+            self.is_focused,
+            self.is_occluded,
+        )
+    }
+
+    /// Scheduled renders, e.g. for animations, follow the reduced rate.
+    #[inline]
+    fn apply_render_interval(&mut self) {
+        self.screen.context_manager.frame_interval = self.render_interval();
+    }
+
+    pub fn set_focused(&mut self, is_focused: bool) {
+        self.is_focused = is_focused;
+        self.screen.renderer.set_window_focused(is_focused);
+        self.apply_render_interval();
+    }
+
+    pub fn set_occluded(&mut self, is_occluded: bool) {
+        self.is_occluded = is_occluded;
+        self.apply_render_interval();
+    }
+
+    /// Refreshes the frame interval from the monitor the window is on,
+    /// called whenever the window may have moved to another display.
     pub fn update_vblank_interval(&mut self) {
-        if !self.has_fps_target {
-            // Get the display vblank interval.
-            let monitor_vblank_interval = 1_000_000.
-                / self
-                    .winit_window
-                    .current_monitor()
-                    .and_then(|monitor| monitor.refresh_rate_millihertz())
-                    .unwrap_or(60_000) as f64;
-
-            // Now convert it to micro seconds.
-            let monitor_vblank_interval =
-                Duration::from_micros((1000. * monitor_vblank_interval) as u64);
-
-            self.vblank_interval = monitor_vblank_interval;
+        if self.has_fps_target {
+            return;
+        }
+
+        let refresh_rate = self
+            .winit_window
+            .current_monitor()
+            .and_then(|monitor| monitor.refresh_rate_millihertz());
+        let vblank_interval = frame_interval(refresh_rate, None, self.max_fps);
+        if vblank_interval != self.vblank_interval {
+            tracing::info!("frame interval changed to {vblank_interval:?}");
+            self.vblank_interval = vblank_interval;
+            self.apply_render_interval();
         }
     }
 
@@ -383,6 +557,7 @@ impl<'a> RouteWindow<'a> {
         tab_id: Option<&str>,
         open_url: Option<String>,
         clipboard: Rc<RefCell<Clipboard>>,
+        control_socket: Option<&std::path::Path>,
     ) -> RouteWindow<'a> {
         #[allow(unused_mut)]
         let mut window_builder = create_window_builder(window_name, config, tab_id);
@@ -407,13 +582,14 @@ impl<'a> RouteWindow<'a> {
             window_id: winit_window.id(),
         };
 
-        let screen = Screen::new(
+        let mut screen = Screen::new(
             properties,
             config,
             event_proxy,
             font_library,
             open_url,
             clipboard,
+            control_socket,
         )
         .expect("Screen not created");
 
@@ -425,34 +601,151 @@ impl<'a> RouteWindow<'a> {
             winit_window.set_cloaked(false);
         }
 
-        // Get the display vblank interval.
-        let monitor_vblank_interval = 1_000_000.
-            / winit_window
-                .current_monitor()
-                .and_then(|monitor| monitor.refresh_rate_millihertz())
-                .unwrap_or(60_000) as f64;
-
-        // Now convert it to micro seconds.
-        let mut monitor_vblank_interval =
-            Duration::from_micros((1000. * monitor_vblank_interval) as u64);
-        let mut has_fps_target = false;
-
-        if let Some(target_fps) = config.renderer.target_fps {
-            monitor_vblank_interval =
-                Duration::from_millis(1000 / target_fps.clamp(1, 1000));
-            has_fps_target = true;
-        }
+        let refresh_rate = winit_window
+            .current_monitor()
+            .and_then(|monitor| monitor.refresh_rate_millihertz());
+        let vblank_interval = frame_interval(
+            refresh_rate,
+            config.renderer.target_fps,
+            config.renderer.max_fps,
+        );
+        screen.context_manager.frame_interval = vblank_interval;
 
         Self {
-            vblank_interval: monitor_vblank_interval,
-            has_fps_target,
+            vblank_interval,
+            has_fps_target: config.renderer.target_fps.is_some(),
+            max_fps: config.renderer.max_fps,
+            unfocused_interval: unfocused_interval(config.renderer.unfocused_fps),
             render_timestamp: Instant::now(),
             is_focused: true,
             is_occluded: false,
+            ime_allowed: true,
             winit_window,
             screen,
+            default_title: window_name.to_string(),
             #[cfg(target_os = "macos")]
             is_macos_deadzone: false,
         }
     }
 }
+
+// Frame interval while the window is hidden, to save battery.
+const OCCLUDED_FRAME_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Interval between frames, either from `target-fps` or from the monitor
+/// refresh rate (in millihertz), defaults to 60Hz when it's unknown.
+/// `max-fps` only ever makes the interval longer.
+fn frame_interval(
+    refresh_rate: Option<u32>,
+    target_fps: Option<u64>,
+    max_fps: Option<u64>,
+) -> Duration {
+    let interval = if let Some(target_fps) = target_fps {
+        Duration::from_micros(1_000_000 / target_fps.clamp(1, 1000))
+    } else {
+        let refresh_rate = refresh_rate.filter(|rate| *rate > 0).unwrap_or(60_000);
+        Duration::from_micros(1_000_000_000 / refresh_rate as u64)
+    };
+
+    match max_fps {
+        Some(max_fps) => {
+            interval.max(Duration::from_micros(1_000_000 / max_fps.clamp(1, 1000)))
+        }
+        None => interval,
+    }
+}
+
+/// Frame interval of unfocused windows from `unfocused-fps`, `None` when
+/// it's 0 and they aren't limited.
+fn unfocused_interval(unfocused_fps: u64) -> Option<Duration> {
+    (unfocused_fps > 0)
+        .then(|| Duration::from_micros(1_000_000 / unfocused_fps.min(1000)))
+}
+
+#[inline]
+fn throttled_interval(
+    interval: Duration,
+    unfocused_interval: Option<Duration>,
+    is_focused: bool,
+    is_occluded: bool,
+) -> Duration {
+    if is_occluded {
+        interval.max(OCCLUDED_FRAME_INTERVAL)
+    } else if !is_focused {
+        unfocused_interval.map_or(interval, |unfocused| interval.max(unfocused))
+    } else {
+        interval
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_interval() {
+        assert_eq!(
+            frame_interval(None, None, None),
+            Duration::from_micros(16_666)
+        );
+        assert_eq!(
+            frame_interval(Some(0), None, None),
+            Duration::from_micros(16_666)
+        );
+        assert_eq!(
+            frame_interval(Some(120_000), None, None),
+            Duration::from_micros(8_333)
+        );
+        assert_eq!(
+            frame_interval(Some(59_940), None, None),
+            Duration::from_micros(16_683)
+        );
+        assert_eq!(
+            frame_interval(Some(120_000), Some(30), None),
+            Duration::from_micros(33_333)
+        );
+    }
+
+    #[test]
+    fn test_frame_interval_max_fps() {
+        assert_eq!(
+            frame_interval(Some(120_000), None, Some(60)),
+            Duration::from_micros(16_666)
+        );
+        // Slower monitors aren't sped up
+        assert_eq!(
+            frame_interval(Some(30_000), None, Some(60)),
+            Duration::from_micros(33_333)
+        );
+        assert_eq!(
+            frame_interval(Some(60_000), Some(240), Some(120)),
+            Duration::from_micros(8_333)
+        );
+    }
+
+    #[test]
+    fn test_throttled_interval() {
+        let interval = Duration::from_micros(8_333);
+        let unfocused = unfocused_interval(10);
+        assert_eq!(unfocused, Some(Duration::from_millis(100)));
+        assert_eq!(
+            throttled_interval(interval, unfocused, true, false),
+            interval
+        );
+        assert_eq!(
+            throttled_interval(interval, unfocused, false, false),
+            Duration::from_millis(100)
+        );
+        assert_eq!(
+            throttled_interval(interval, unfocused, true, true),
+            OCCLUDED_FRAME_INTERVAL
+        );
+        assert_eq!(
+            throttled_interval(Duration::from_secs(2), unfocused, false, true),
+            Duration::from_secs(2)
+        );
+        // unfocused-fps = 0 keeps the rate of the focused window
+        assert_eq!(unfocused_interval(0), None);
+        assert_eq!(throttled_interval(interval, None, false, false), interval);
+    }
+}
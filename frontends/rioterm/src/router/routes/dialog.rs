@@ -1,7 +1,12 @@
 use rio_backend::sugarloaf::{Object, Rect, Sugarloaf, Text};
 
 #[inline]
-pub fn screen(sugarloaf: &mut Sugarloaf, content: &str) {
+pub fn screen(
+    sugarloaf: &mut Sugarloaf,
+    content: &str,
+    action: &str,
+    running_processes: &[String],
+) {
     let blue = [0.1764706, 0.6039216, 1.0, 1.0];
     let yellow = [0.9882353, 0.7294118, 0.15686275, 1.0];
     let red = [1.0, 0.07058824, 0.38039216, 1.0];
@@ -10,7 +15,7 @@ pub fn screen(sugarloaf: &mut Sugarloaf, content: &str) {
     let layout = sugarloaf.layout();
     let height = layout.height / layout.dimensions.scale;
 
-    let mut objects = Vec::with_capacity(7);
+    let mut objects = Vec::with_capacity(8);
 
     objects.push(Object::Rect(Rect {
         position: [0., 0.0],
@@ -44,7 +49,7 @@ pub fn screen(sugarloaf: &mut Sugarloaf, content: &str) {
 
     objects.push(Object::Text(Text::single_line(
         (70., mid_screen + 30.),
-        String::from("To quit press enter key"),
+        format!("To {action} press enter key"),
         18.,
         yellow,
     )));
@@ -56,5 +61,14 @@ pub fn screen(sugarloaf: &mut Sugarloaf, content: &str) {
         blue,
     )));
 
+    if !running_processes.is_empty() {
+        objects.push(Object::Text(Text::single_line(
+            (70., mid_screen + 80.),
+            format!("Still running: {}", running_processes.join(", ")),
+            18.,
+            [1., 1., 1., 1.],
+        )));
+    }
+
     sugarloaf.set_objects(objects);
 }
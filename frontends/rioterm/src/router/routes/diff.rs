@@ -0,0 +1,226 @@
+use rio_backend::snapshot::SnapshotLine;
+use rio_backend::sugarloaf::{Object, Rect, Sugarloaf, Text};
+
+const FONT_SIZE: f32 = 14.;
+const LINE_HEIGHT: f32 = 18.;
+const TOP: f32 = 60.;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DiffKind {
+    Unchanged,
+    Added,
+    Removed,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiffLine {
+    pub kind: DiffKind,
+    pub text: String,
+}
+
+/// Line diff between a pane snapshot and the live content or another
+/// snapshot, shown by the diff route.
+pub struct PaneDiff {
+    pub title: String,
+    pub lines: Vec<DiffLine>,
+    pub scroll: usize,
+}
+
+impl PaneDiff {
+    pub fn new(title: String, old: &[String], new: &[String]) -> PaneDiff {
+        PaneDiff {
+            title,
+            lines: diff_lines(old, new),
+            scroll: 0,
+        }
+    }
+
+    #[inline]
+    pub fn has_changes(&self) -> bool {
+        self.lines
+            .iter()
+            .any(|line| line.kind != DiffKind::Unchanged)
+    }
+
+    #[inline]
+    pub fn scroll_by(&mut self, lines: isize) {
+        let max = self.lines.len().saturating_sub(1);
+        self.scroll = self.scroll.saturating_add_signed(lines).min(max);
+    }
+}
+
+/// Text of the rows, trailing blank lines are dropped so a shorter output
+/// isn't reported as removed empty lines.
+pub fn snapshot(lines: &[SnapshotLine]) -> Vec<String> {
+    let mut lines: Vec<String> = lines.iter().map(SnapshotLine::text).collect();
+
+    while lines.last().is_some_and(|line| line.is_empty()) {
+        lines.pop();
+    }
+
+    lines
+}
+
+/// Longest common subsequence diff, removed lines come before the lines
+/// added in their place.
+pub fn diff_lines(old: &[String], new: &[String]) -> Vec<DiffLine> {
+    // lengths[i][j] is the LCS length of old[i..] and new[j..]
+    let mut lengths = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lengths[i][j] = if old[i] == new[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let line = |kind, text: &String| DiffLine {
+        kind,
+        text: text.to_owned(),
+    };
+
+    let mut diff = Vec::with_capacity(old.len().max(new.len()));
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            diff.push(line(DiffKind::Unchanged, &old[i]));
+            i += 1;
+            j += 1;
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            diff.push(line(DiffKind::Removed, &old[i]));
+            i += 1;
+        } else {
+            diff.push(line(DiffKind::Added, &new[j]));
+            j += 1;
+        }
+    }
+    diff.extend(old[i..].iter().map(|text| line(DiffKind::Removed, text)));
+    diff.extend(new[j..].iter().map(|text| line(DiffKind::Added, text)));
+
+    diff
+}
+
+#[inline]
+pub fn screen(sugarloaf: &mut Sugarloaf, diff: &PaneDiff) {
+    let blue = [0.1764706, 0.6039216, 1.0, 1.0];
+    let green = [0.3137255, 0.8627451, 0.3921569, 1.0];
+    let red = [1.0, 0.07058824, 0.38039216, 1.0];
+    let gray = [0.6, 0.6, 0.6, 1.0];
+    let black = [0.0, 0.0, 0.0, 1.0];
+
+    let layout = sugarloaf.layout();
+    let height = layout.height / layout.dimensions.scale;
+    let visible = ((height - TOP - LINE_HEIGHT) / LINE_HEIGHT).max(1.) as usize;
+
+    let mut objects = Vec::with_capacity(visible + 3);
+
+    objects.push(Object::Rect(Rect {
+        position: [0., 0.0],
+        color: black,
+        size: [layout.width, layout.height],
+    }));
+    objects.push(Object::Text(Text::single_line(
+        (20., layout.margin.top_y + 30.),
+        diff.title.to_owned(),
+        18.,
+        blue,
+    )));
+    objects.push(Object::Text(Text::single_line(
+        (20., height - 10.),
+        String::from("To scroll use arrow or page keys, to close press escape key"),
+        FONT_SIZE,
+        gray,
+    )));
+
+    if !diff.has_changes() {
+        objects.push(Object::Text(Text::single_line(
+            (20., layout.margin.top_y + TOP),
+            String::from("No differences"),
+            FONT_SIZE,
+            gray,
+        )));
+    }
+
+    for (index, line) in diff
+        .lines
+        .iter()
+        .skip(diff.scroll)
+        .take(visible)
+        .enumerate()
+    {
+        let (prefix, color) = match line.kind {
+            DiffKind::Unchanged => ("  ", gray),
+            DiffKind::Added => ("+ ", green),
+            DiffKind::Removed => ("- ", red),
+        };
+
+        objects.push(Object::Text(Text::single_line(
+            (20., layout.margin.top_y + TOP + index as f32 * LINE_HEIGHT),
+            format!("{prefix}{}", line.text),
+            FONT_SIZE,
+            color,
+        )));
+    }
+
+    sugarloaf.set_objects(objects);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(text: &[&str]) -> Vec<String> {
+        text.iter().map(|line| line.to_string()).collect()
+    }
+
+    fn kinds(diff: &[DiffLine]) -> Vec<(DiffKind, &str)> {
+        diff.iter()
+            .map(|line| (line.kind, line.text.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_diff_lines() {
+        let old = lines(&["$ cargo test", "test a ... ok", "test b ... FAILED"]);
+        let new = lines(&["$ cargo test", "test a ... ok", "test b ... ok", "done"]);
+
+        let diff = diff_lines(&old, &new);
+        assert_eq!(
+            kinds(&diff),
+            vec![
+                (DiffKind::Unchanged, "$ cargo test"),
+                (DiffKind::Unchanged, "test a ... ok"),
+                (DiffKind::Removed, "test b ... FAILED"),
+                (DiffKind::Added, "test b ... ok"),
+                (DiffKind::Added, "done"),
+            ]
+        );
+
+        assert!(diff_lines(&old, &old)
+            .iter()
+            .all(|line| line.kind == DiffKind::Unchanged));
+        assert_eq!(
+            kinds(&diff_lines(&[], &old[..1])),
+            vec![(DiffKind::Added, "$ cargo test")]
+        );
+    }
+
+    #[test]
+    fn test_pane_diff_scroll() {
+        let old = lines(&["a", "b"]);
+        let new = lines(&["a", "c"]);
+        let mut diff = PaneDiff::new(String::from("Snapshot"), &old, &new);
+        assert!(diff.has_changes());
+
+        diff.scroll_by(-1);
+        assert_eq!(diff.scroll, 0);
+        diff.scroll_by(10);
+        assert_eq!(diff.scroll, 2);
+        diff.scroll_by(-1);
+        assert_eq!(diff.scroll, 1);
+
+        assert!(!PaneDiff::new(String::new(), &old, &old).has_changes());
+    }
+}
@@ -1,5 +1,6 @@
 pub mod assistant;
 pub mod dialog;
+pub mod diff;
 pub mod welcome;
 
 #[derive(PartialEq)]
@@ -8,4 +9,7 @@ pub enum RoutePath {
     Terminal,
     Welcome,
     ConfirmQuit,
+    ConfirmClose,
+    ConfirmPrint,
+    Diff,
 }
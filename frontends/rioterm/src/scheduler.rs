@@ -27,6 +27,10 @@ pub enum Topic {
     Render,
     RenderRoute,
     CursorBlinking,
+    TextBlinking,
+    ProcessUsage,
+    NightMode,
+    ClearClipboard,
 }
 
 /// Event scheduled to be emitted at a specific time.
@@ -9,13 +9,14 @@
 pub mod hint;
 pub mod touch;
 
+use crate::ansi::CursorShape;
 use crate::bindings::{
     Action as Act, BindingKey, BindingMode, FontSizeAction, MouseBinding, SearchAction,
     ViAction,
 };
 #[cfg(target_os = "macos")]
 use crate::constants::{DEADZONE_END_Y, DEADZONE_START_Y};
-use crate::context::{self, process_open_url, ContextManager};
+use crate::context::{self, process_open_url, ContextDimension, ContextManager};
 use crate::crosswords::{
     grid::{Dimensions, Scroll},
     pos::{Column, Pos, Side},
@@ -24,24 +25,34 @@ use crate::crosswords::{
     Mode,
 };
 use crate::ime::Ime;
-use crate::mouse::{calculate_mouse_position, Mouse};
+use crate::mouse::{calculate_mouse_position, wheel_steps, Mouse};
 use crate::renderer::{
+    minimap,
+    navigation::TabBarAction,
+    scrollbar,
     utils::{padding_bottom_from_config, padding_top_from_config},
     Renderer,
 };
+use crate::router::routes::diff::PaneDiff;
 use crate::screen::hint::HintMatches;
 use crate::selection::{Selection, SelectionType};
 use core::fmt::Debug;
 use raw_window_handle::{RawDisplayHandle, RawWindowHandle};
 use rio_backend::clipboard::Clipboard;
 use rio_backend::clipboard::ClipboardType;
+use rio_backend::config::predictive_echo::PredictiveEchoMode;
 use rio_backend::config::{
     colors::term::List,
-    renderer::{Backend as RendererBackend, Performance as RendererPerformance},
+    renderer::{
+        Backend as RendererBackend, Performance as RendererPerformance,
+        PresentMode as RendererPresentMode,
+    },
 };
 use rio_backend::crosswords::pos::{Boundary, Direction, Line};
+use rio_backend::crosswords::prediction::Predictions;
 use rio_backend::crosswords::search::RegexSearch;
-use rio_backend::event::{ClickState, EventProxy, SearchState};
+use rio_backend::event::{ClickState, EventProxy, RioEvent, RioEventType, SearchState};
+use rio_backend::snapshot::{snapshot, visible_lines};
 use rio_backend::sugarloaf::{
     layout::SugarloafLayout, Sugarloaf, SugarloafErrors, SugarloafRenderer,
     SugarloafWindow, SugarloafWindowSize,
@@ -73,6 +84,15 @@ const MAX_SEARCH_WHILE_TYPING: Option<usize> = Some(1000);
 /// Maximum number of search terms stored in the history.
 const MAX_SEARCH_HISTORY_SIZE: usize = 255;
 
+#[inline]
+fn present_mode_from_config(present_mode: RendererPresentMode) -> wgpu::PresentMode {
+    match present_mode {
+        RendererPresentMode::AutoVsync => wgpu::PresentMode::AutoVsync,
+        RendererPresentMode::Mailbox => wgpu::PresentMode::Mailbox,
+        RendererPresentMode::Immediate => wgpu::PresentMode::Immediate,
+    }
+}
+
 pub struct Screen<'screen> {
     bindings: crate::bindings::KeyBindings,
     mouse_bindings: Vec<MouseBinding>,
@@ -85,6 +105,13 @@ pub struct Screen<'screen> {
     pub sugarloaf: Sugarloaf<'screen>,
     pub context_manager: context::ContextManager<EventProxy>,
     pub clipboard: Rc<RefCell<Clipboard>>,
+    // The two last snapshots of pane text, the newest one last
+    pane_snapshots: Vec<Vec<String>>,
+    pub pane_diff: Option<crate::router::routes::diff::PaneDiff>,
+    /// Lines waiting for the print confirmation.
+    pub pending_print: Option<Vec<rio_backend::print::PrintLine>>,
+    #[cfg(unix)]
+    control_snapshots: std::collections::HashMap<usize, crate::control::ScreenSnapshot>,
 }
 
 pub struct ScreenWindowProperties {
@@ -103,6 +130,7 @@ impl Screen<'_> {
         font_library: &rio_backend::sugarloaf::font::FontLibrary,
         open_url: Option<String>,
         clipboard: Rc<RefCell<Clipboard>>,
+        control_socket: Option<&std::path::Path>,
     ) -> Result<Screen<'screen>, Box<dyn Error>> {
         let size = window_properties.size;
         let scale = window_properties.scale;
@@ -159,7 +187,10 @@ impl Screen<'_> {
         let sugarloaf_renderer = SugarloafRenderer {
             power_preference,
             backend,
+            adapter: config.renderer.adapter.clone(),
             font_features: config.fonts.features.clone(),
+            hdr: config.renderer.hdr,
+            present_mode: present_mode_from_config(config.renderer.present_mode),
         };
 
         let mut sugarloaf: Sugarloaf = match Sugarloaf::new(
@@ -205,8 +236,28 @@ impl Screen<'_> {
             // When navigation is collapsed and does not contain any color rule
             // does not make sense fetch for foreground process names
             should_update_titles: !(is_collapsed
-                && config.navigation.color_automation.is_empty()),
+                && config.navigation.color_automation.is_empty())
+                || config.navigation.process_usage
+                || !config.profiles.is_empty()
+                || config.title.content.is_some(),
+            process_usage: config.navigation.process_usage,
+            profiles: config.profiles.clone(),
+            title_template: config.title.content.clone(),
+            print: config.print,
+            allow_private_graphics: config.security.allow_private_graphics,
+            allow_window_ops: config.security.allow_window_ops,
+            osc_hooks: config.osc_hooks.codes(),
+            hold: config.hold,
+            predictive_echo: config.predictive_echo,
+            serial: config.serial,
+            scroll: config.scroll.clone(),
+            control_socket: control_socket.map(std::path::Path::to_path_buf),
         };
+        if config.night_mode.enabled {
+            event_proxy
+                .send_event(RioEventType::Rio(RioEvent::CheckNightMode), window_id);
+        }
+
         let context_manager = context::ContextManager::start(
             (&renderer.get_cursor_state(), config.cursor.blinking),
             event_proxy,
@@ -226,6 +277,11 @@ impl Screen<'_> {
         if let Some(image) = &config.window.background_image {
             sugarloaf.set_background_image(image);
         }
+        if let Some(shader) = &config.window.background_shader {
+            sugarloaf.set_background_shader(shader);
+        }
+        sugarloaf
+            .set_text_gamma(config.renderer.text_gamma, config.renderer.text_contrast);
         sugarloaf.render();
 
         Ok(Screen {
@@ -240,9 +296,46 @@ impl Screen<'_> {
             renderer,
             bindings,
             clipboard,
+            pane_snapshots: Vec::with_capacity(2),
+            pane_diff: None,
+            pending_print: None,
+            #[cfg(unix)]
+            control_snapshots: std::collections::HashMap::new(),
         })
     }
 
+    /// Publish the lines and cursor of the current route that changed since
+    /// the last call to the control socket subscribers.
+    #[cfg(unix)]
+    pub fn publish_control_changes(
+        &mut self,
+        control: &crate::control::ControlSocket,
+        window_id: u64,
+    ) {
+        if !control.wants_screen_changes() {
+            self.control_snapshots.clear();
+            return;
+        }
+
+        let route = self.context_manager.current_route();
+        let (lines, cursor) = {
+            let terminal = self.context_manager.current().terminal.lock();
+            let cursor = terminal.cursor().pos;
+            (
+                snapshot(&terminal, visible_lines(&terminal)),
+                (cursor.row.0.max(0) as usize, cursor.col.0),
+            )
+        };
+
+        self.control_snapshots.retain(|route_id, _| {
+            self.context_manager.get_by_route_id(*route_id).is_some()
+        });
+        self.control_snapshots
+            .entry(route)
+            .or_default()
+            .publish_changes(control, (window_id, route), &lines, cursor);
+    }
+
     #[inline]
     pub fn ctx(&self) -> &ContextManager<EventProxy> {
         &self.context_manager
@@ -325,14 +418,35 @@ impl Screen<'_> {
         );
 
         self.sugarloaf.layout_mut().update();
+        let is_window_focused = self.renderer.is_window_focused();
         self.renderer = Renderer::new(config, font_library);
-
+        self.renderer.set_window_focused(is_window_focused);
+        self.sugarloaf
+            .set_present_mode(present_mode_from_config(config.renderer.present_mode))
+            .set_text_gamma(config.renderer.text_gamma, config.renderer.text_contrast);
+
+        self.ctx_mut().config.allow_private_graphics =
+            config.security.allow_private_graphics;
+        self.ctx_mut().config.allow_window_ops = config.security.allow_window_ops;
+        self.ctx_mut().config.osc_hooks = config.osc_hooks.codes();
+        self.ctx_mut().config.hold = config.hold;
+        self.ctx_mut().config.predictive_echo = config.predictive_echo;
+        self.ctx_mut().config.scroll = config.scroll.clone();
+        self.ctx_mut().config.title_template = config.title.content.clone();
+        self.ctx_mut().config.print = config.print;
         for context in self.ctx().contexts() {
             let mut terminal = context.terminal.lock();
             let cursor = self.renderer.get_cursor_state_from_ref().content;
             terminal.cursor_shape = cursor;
             terminal.default_cursor_shape = cursor;
             terminal.blinking_cursor = config.cursor.blinking;
+            terminal.default_blinking_cursor = config.cursor.blinking;
+            terminal.allow_private_graphics = config.security.allow_private_graphics;
+            terminal.allow_window_ops = config.security.allow_window_ops;
+            terminal.osc_hooks = config.osc_hooks.codes();
+            terminal.hold = config.hold;
+            terminal.predictions = Predictions::new(&config.predictive_echo);
+            terminal.scroll_to_bottom_on_output = config.scroll.jump_to_bottom_on_output;
             drop(terminal);
         }
 
@@ -348,12 +462,35 @@ impl Screen<'_> {
 
         if let Some(image) = &config.window.background_image {
             self.sugarloaf.set_background_image(image);
+        } else {
+            self.sugarloaf.remove_background_image();
         }
 
+        if let Some(shader) = &config.window.background_shader {
+            self.sugarloaf.set_background_shader(shader);
+        } else {
+            self.sugarloaf.remove_background_shader();
+        }
+
+        // The renderer was recreated, don't wait for the next check.
+        self.update_night_mode();
         self.render();
         self.resize_all_contexts();
     }
 
+    /// Re-evaluate the night mode schedule, every context is redrawn when
+    /// it's toggled. Returns whether it was toggled.
+    pub fn update_night_mode(&mut self) -> bool {
+        if !self.renderer.update_night_mode(&mut self.sugarloaf) {
+            return false;
+        }
+
+        for context in self.ctx().contexts() {
+            context.terminal.lock().mark_fully_damaged();
+        }
+        true
+    }
+
     #[inline]
     pub fn change_font_size(&mut self, action: FontSizeAction) {
         let action: u8 = match action {
@@ -399,19 +536,15 @@ impl Screen<'_> {
         // the wakeup from pty it will also trigger a sugarloaf.render()
         // and then eventually a render with the new layout computation.
         let layout = self.sugarloaf.layout();
-        for context in self.ctx().contexts() {
-            let mut terminal = context.terminal.lock();
-            terminal.resize::<SugarloafLayout>(layout);
-            drop(terminal);
-            let winsize = crate::renderer::utils::terminal_dimensions(&layout);
-            let _ = context.messenger.send_resize(winsize);
-        }
+        self.ctx_mut().resize_all(ContextDimension::from(&layout));
+        self.renderer.smooth_scroll.reset();
+        self.renderer.cursor_animation.reset();
     }
 
     #[inline]
     pub fn scroll_bottom_when_cursor_not_visible(&mut self) {
         let mut terminal = self.ctx_mut().current_mut().terminal.lock();
-        if terminal.display_offset() != 0 {
+        if terminal.display_offset() != 0 && !terminal.scroll_to_command_input() {
             terminal.scroll_display(Scroll::Bottom);
         }
         drop(terminal);
@@ -507,6 +640,14 @@ impl Screen<'_> {
             return;
         }
 
+        // The process is gone, only respawning makes sense.
+        if self.ctx().current().terminal.lock().exited {
+            if key.logical_key == Key::Named(NamedKey::Enter) {
+                self.respawn_current_context(false);
+            }
+            return;
+        }
+
         let text = key.text_with_all_modifiers().unwrap_or_default();
 
         if self.search_active() {
@@ -519,7 +660,8 @@ impl Screen<'_> {
         }
 
         // Vi mode on its own doesn't have any input, the search input was done before.
-        if mode.contains(Mode::VI) {
+        // Neither does a pager, its stream is read only.
+        if mode.contains(Mode::VI) || self.ctx().current().terminal.lock().pager {
             return;
         }
 
@@ -567,10 +709,25 @@ impl Screen<'_> {
         };
 
         if !bytes.is_empty() {
-            self.scroll_bottom_when_cursor_not_visible();
+            if self.ctx().config.scroll.jump_to_bottom_on_input {
+                self.scroll_bottom_when_cursor_not_visible();
+            }
             self.clear_selection();
 
+            let mut terminal = self.ctx_mut().current_mut().terminal.lock();
+            // Predictions are also recorded while inactive to measure latency.
+            let has_predictions = terminal.predictions.is_active();
+            // Escape sequences (e.g. alt or kitty keys) can't be predicted.
+            match std::str::from_utf8(&bytes) {
+                Ok(text) if !text.starts_with('\x1b') => terminal.predict_input(text),
+                _ => terminal.predictions.clear(),
+            }
+            drop(terminal);
+
             self.ctx_mut().current_mut().messenger.send_bytes(bytes);
+            if has_predictions {
+                self.render();
+            }
         }
     }
 
@@ -647,17 +804,28 @@ impl Screen<'_> {
                 *ignore_chars.get_or_insert(true) &= binding.action != Act::ReceiveChar;
 
                 match &binding.action {
-                    Act::Run(program) => self.exec(program.program(), program.args()),
+                    Act::Run(program) => {
+                        let args: Vec<String> = {
+                            let terminal = self.ctx().current().terminal.lock();
+                            program
+                                .args()
+                                .iter()
+                                .map(|arg| terminal.expand_user_vars(arg).into_owned())
+                                .collect()
+                        };
+                        self.exec(program.program(), &args);
+                    }
                     Act::Esc(s) => {
                         let current_context = self.context_manager.current_mut();
                         self.renderer.set_selection(None);
                         let mut terminal = current_context.terminal.lock();
                         terminal.selection.take();
-                        terminal.scroll_display(Scroll::Bottom);
+                        if !terminal.scroll_to_command_input() {
+                            terminal.scroll_display(Scroll::Bottom);
+                        }
+                        let text = terminal.expand_user_vars(s).into_owned();
                         drop(terminal);
-                        current_context
-                            .messenger
-                            .send_bytes(s.to_owned().into_bytes());
+                        current_context.messenger.send_bytes(text.into_bytes());
                     }
                     Act::Paste => {
                         let content =
@@ -898,6 +1066,102 @@ impl Screen<'_> {
                         drop(terminal);
                         self.render();
                     }
+                    Act::ClearScreenAndHistory => {
+                        let mut terminal =
+                            self.context_manager.current_mut().terminal.lock();
+                        terminal.clear_screen_and_history();
+                        drop(terminal);
+                        self.render();
+                    }
+                    Act::JumpToPreviousPrompt => {
+                        let mut terminal =
+                            self.context_manager.current_mut().terminal.lock();
+                        terminal.scroll_to_previous_prompt();
+                        drop(terminal);
+                        self.render();
+                    }
+                    Act::JumpToNextPrompt => {
+                        let mut terminal =
+                            self.context_manager.current_mut().terminal.lock();
+                        terminal.scroll_to_next_prompt();
+                        drop(terminal);
+                        self.render();
+                    }
+                    Act::SelectLastCommandOutput => {
+                        let terminal = self.context_manager.current().terminal.lock();
+                        let output = terminal.last_command_output();
+                        drop(terminal);
+
+                        if let Some((start, end)) = output {
+                            self.start_selection(
+                                SelectionType::Lines,
+                                Pos::new(start, Column(0)),
+                                Side::Left,
+                            );
+                            self.update_selection(Pos::new(end, Column(0)), Side::Right);
+                            self.copy_selection(ClipboardType::Clipboard);
+                            self.render();
+                        }
+                    }
+                    Act::RespawnShell => {
+                        self.respawn_current_context(true);
+                    }
+                    Act::RespawnShellAndClearHistory => {
+                        self.respawn_current_context(false);
+                    }
+                    Act::Print => {
+                        let colors = self.context_manager.config.print.colors;
+                        let terminal = self.context_manager.current().terminal.lock();
+                        let lines = rio_backend::print::print_lines(&terminal, colors);
+                        drop(terminal);
+                        self.pending_print = Some(lines);
+                        self.context_manager.confirm_print();
+                    }
+                    Act::SnapshotPane => {
+                        let snapshot = self.pane_text();
+                        if self.pane_snapshots.len() == 2 {
+                            self.pane_snapshots.remove(0);
+                        }
+                        self.pane_snapshots.push(snapshot);
+                    }
+                    Act::DiffSnapshot => {
+                        if let Some(snapshot) = self.pane_snapshots.last() {
+                            let live = self.pane_text();
+                            self.pane_diff = Some(PaneDiff::new(
+                                String::from("Snapshot → live content"),
+                                snapshot,
+                                &live,
+                            ));
+                            self.context_manager.show_pane_diff();
+                        }
+                    }
+                    Act::DiffSnapshots => {
+                        if let [previous, last] = self.pane_snapshots.as_slice() {
+                            self.pane_diff = Some(PaneDiff::new(
+                                String::from("Previous snapshot → last snapshot"),
+                                previous,
+                                last,
+                            ));
+                            self.context_manager.show_pane_diff();
+                        }
+                    }
+                    Act::TogglePredictiveEcho => {
+                        let mut terminal =
+                            self.context_manager.current_mut().terminal.lock();
+                        let mode = if terminal.predictions.is_active() {
+                            PredictiveEchoMode::Never
+                        } else {
+                            PredictiveEchoMode::Always
+                        };
+                        terminal.predictions.set_mode(mode);
+                        drop(terminal);
+                        self.render();
+                    }
+                    Act::ToggleIme => {
+                        let context = self.context_manager.current_mut();
+                        context.ime_allowed = !context.ime_allowed;
+                        self.render();
+                    }
                     Act::ToggleFullscreen => self.context_manager.toggle_full_screen(),
                     Act::Minimize => {
                         self.context_manager.minimize();
@@ -959,6 +1223,22 @@ impl Screen<'_> {
         self.render();
     }
 
+    pub fn respawn_current_context(&mut self, keep_history: bool) {
+        let layout = self.sugarloaf.layout();
+        self.context_manager.respawn_current_context(
+            layout,
+            (
+                &self.renderer.get_cursor_state_from_ref(),
+                self.renderer.config_has_blinking_enabled,
+            ),
+            keep_history,
+        );
+
+        self.clear_selection();
+        self.cancel_search();
+        self.render();
+    }
+
     pub fn close_tab(&mut self) {
         self.clear_selection();
         self.context_manager.close_current_context();
@@ -1136,6 +1416,7 @@ impl Screen<'_> {
                 .set(ClipboardType::Clipboard, text.clone());
         }
         self.clipboard.borrow_mut().set(ty, text);
+        self.context_manager.clipboard_copied();
     }
 
     #[inline]
@@ -1245,6 +1526,112 @@ impl Screen<'_> {
     }
 
     #[inline]
+    /// Scrolls to the part of the scrollback under the mouse when it's
+    /// over the minimap.
+    /// Selects or closes the tab under the mouse in the tab bar.
+    pub fn click_tab_bar(&mut self) -> bool {
+        let scale = self.sugarloaf.layout().dimensions.scale;
+        let (mouse_x, mouse_y) =
+            (self.mouse.x as f32 / scale, self.mouse.y as f32 / scale);
+        let Some(action) = self.renderer.navigation.hit(mouse_x, mouse_y) else {
+            return false;
+        };
+
+        match action {
+            TabBarAction::Select(index) => {
+                self.context_manager.select_tab(index);
+                self.cancel_search();
+                self.render();
+            }
+            TabBarAction::Close(index) => {
+                self.context_manager.select_tab(index);
+                self.close_tab();
+            }
+        }
+        true
+    }
+
+    pub fn click_minimap(&mut self) -> bool {
+        if !self.renderer.show_minimap {
+            return false;
+        }
+
+        let layout = self.sugarloaf.layout();
+        let scale = layout.dimensions.scale;
+        let ([x, y], [width, height]) = minimap::area(&layout);
+        let (mouse_x, mouse_y) =
+            (self.mouse.x as f32 / scale, self.mouse.y as f32 / scale);
+        if mouse_x < x || mouse_x > x + width || mouse_y < y || mouse_y > y + height {
+            return false;
+        }
+
+        let mut terminal = self.context_manager.current().terminal.lock();
+        let history = terminal.grid.history_size();
+        if history == 0 || terminal.mode().contains(Mode::ALT_SCREEN) {
+            return false;
+        }
+
+        let display_offset = minimap::display_offset_at(
+            (mouse_y - y) / height,
+            history,
+            terminal.grid.screen_lines(),
+        );
+        let delta = display_offset as i32 - terminal.display_offset() as i32;
+        terminal.scroll_display(Scroll::Delta(delta));
+        true
+    }
+
+    /// Grabs the scrollbar thumb under the mouse, clicking the track
+    /// moves the middle of the thumb there.
+    pub fn start_scrollbar_drag(&mut self) -> bool {
+        let scale = self.sugarloaf.layout().dimensions.scale;
+        let (x, y) = (self.mouse.x as f32 / scale, self.mouse.y as f32 / scale);
+        let Some(([_, thumb_y], [_, thumb_height])) = self.renderer.scrollbar.hit(x, y)
+        else {
+            return false;
+        };
+
+        let grab = if (thumb_y..=thumb_y + thumb_height).contains(&y) {
+            y - thumb_y
+        } else {
+            thumb_height / 2.
+        };
+        self.renderer.scrollbar.drag = Some(grab);
+        self.drag_scrollbar();
+        true
+    }
+
+    #[inline]
+    pub fn is_dragging_scrollbar(&self) -> bool {
+        self.renderer.scrollbar.drag.is_some()
+    }
+
+    pub fn drag_scrollbar(&mut self) {
+        let (Some(grab), Some((_, [_, thumb_height]))) = (
+            self.renderer.scrollbar.drag,
+            self.renderer.scrollbar.thumb(),
+        ) else {
+            return;
+        };
+
+        let y = self.mouse.y as f32 / self.sugarloaf.layout().dimensions.scale;
+        let mut terminal = self.context_manager.current().terminal.lock();
+        let display_offset = scrollbar::display_offset_at(
+            self.renderer.scrollbar.track(),
+            thumb_height,
+            y - grab,
+            terminal.grid.history_size(),
+        );
+        let delta = display_offset as i32 - terminal.display_offset() as i32;
+        terminal.scroll_display(Scroll::Delta(delta));
+    }
+
+    /// Returns true when the scrollbar was being dragged.
+    #[inline]
+    pub fn end_scrollbar_drag(&mut self) -> bool {
+        self.renderer.scrollbar.drag.take().is_some()
+    }
+
     pub fn trigger_hyperlink(&self) -> bool {
         #[cfg(target_os = "macos")]
         let is_hyperlink_key_active = self.modifiers.state().super_key();
@@ -1377,6 +1764,37 @@ impl Screen<'_> {
         self.renderer.selection_range.is_none()
     }
 
+    /// Moves the cursor of the shell to `point` by sending arrow keys,
+    /// returns false if it's outside of the command input.
+    pub fn move_prompt_cursor(&mut self, point: Pos) -> bool {
+        let mode = self.get_mode();
+        if mode.intersects(Mode::ALT_SCREEN | Mode::VI) || self.search_active() {
+            return false;
+        }
+
+        let terminal = self.ctx().current().terminal.lock();
+        let moves = terminal.command_input_moves(point);
+        drop(terminal);
+
+        let Some(moves) = moves else {
+            return false;
+        };
+
+        let arrow = match (mode.contains(Mode::APP_CURSOR), moves < 0) {
+            (true, true) => "\x1bOD",
+            (true, false) => "\x1bOC",
+            (false, true) => "\x1b[D",
+            (false, false) => "\x1b[C",
+        };
+        if moves != 0 {
+            self.ctx_mut()
+                .current_mut()
+                .messenger
+                .send_bytes(arrow.repeat(moves.unsigned_abs() as usize).into_bytes());
+        }
+        true
+    }
+
     #[inline]
     pub fn on_left_click(&mut self, point: Pos) {
         let side = self.mouse.square_side;
@@ -1498,8 +1916,13 @@ impl Screen<'_> {
 
         self.search_state.history_index = None;
 
-        // Clear focused match.
+        // Clear focused match, every match loses its highlight.
         self.search_state.focused_match = None;
+        self.context_manager
+            .current()
+            .terminal
+            .lock()
+            .mark_fully_damaged();
 
         self.render();
     }
@@ -1608,16 +2031,20 @@ impl Screen<'_> {
             match terminal.search_next(dfas, clamped_origin, direction, Side::Left, limit)
             {
                 Some(regex_match) => {
+                    if let Some(focused_match) = &self.search_state.focused_match {
+                        terminal.damage_search_match(focused_match);
+                    }
+
                     let old_offset = terminal.display_offset() as i32;
                     if terminal.mode().contains(Mode::VI) {
                         // Move vi cursor to the start of the match.
                         terminal.vi_goto_pos(*regex_match.start());
-                    } else {
-                        // Select the match when vi mode is not active.
-                        terminal.scroll_to_pos(*regex_match.start());
                     }
+                    // Keep the whole match in the viewport.
+                    terminal.scroll_to_range(&regex_match);
 
                     // Update the focused match.
+                    terminal.damage_search_match(&regex_match);
                     self.search_state.focused_match = Some(regex_match);
 
                     // Store number of lines the viewport had to be moved.
@@ -1642,7 +2069,9 @@ impl Screen<'_> {
                     // }
 
                     // Clear focused match.
-                    self.search_state.focused_match = None;
+                    if let Some(focused_match) = self.search_state.focused_match.take() {
+                        terminal.damage_search_match(&focused_match);
+                    }
                 }
             }
             drop(terminal);
@@ -1778,24 +2207,23 @@ impl Screen<'_> {
             self.mouse.accumulated_scroll.x += new_scroll_x_px;
             self.mouse.accumulated_scroll.y += new_scroll_y_px;
 
-            let code = if new_scroll_y_px > 0. {
-                MOUSE_WHEEL_UP
-            } else {
-                MOUSE_WHEEL_DOWN
-            };
-            let lines = (self.mouse.accumulated_scroll.y / height).abs() as usize;
-
+            // Directions come from the accumulated scroll, a trackpad going
+            // back and forth could otherwise report the wrong button.
+            let (code, lines) = wheel_steps(
+                self.mouse.accumulated_scroll.y,
+                height,
+                (MOUSE_WHEEL_UP, MOUSE_WHEEL_DOWN),
+            );
             for _ in 0..lines {
                 self.mouse_report(code, ElementState::Pressed);
             }
 
-            let code = if new_scroll_x_px > 0. {
-                MOUSE_WHEEL_LEFT
-            } else {
-                MOUSE_WHEEL_RIGHT
-            };
-            let columns = (self.mouse.accumulated_scroll.x / width).abs() as usize;
-
+            // Buttons 6 and 7 of the horizontal wheel.
+            let (code, columns) = wheel_steps(
+                self.mouse.accumulated_scroll.x,
+                width,
+                (MOUSE_WHEEL_LEFT, MOUSE_WHEEL_RIGHT),
+            );
             for _ in 0..columns {
                 self.mouse_report(code, ElementState::Pressed);
             }
@@ -1895,15 +2323,42 @@ impl Screen<'_> {
         self.sugarloaf.render();
     }
 
+    pub fn render_pane_diff(&mut self) {
+        if let Some(diff) = &self.pane_diff {
+            self.sugarloaf.clear();
+            crate::router::routes::diff::screen(&mut self.sugarloaf, diff);
+            self.sugarloaf.render();
+        }
+    }
+
+    /// Visible text of the current pane.
+    fn pane_text(&self) -> Vec<String> {
+        let terminal = self.context_manager.current().terminal.lock();
+        crate::router::routes::diff::snapshot(&snapshot(
+            &terminal,
+            visible_lines(&terminal),
+        ))
+    }
+
     pub fn render_welcome(&mut self) {
         self.sugarloaf.clear();
         crate::router::routes::welcome::screen(&mut self.sugarloaf);
         self.sugarloaf.render();
     }
 
-    pub fn render_dialog(&mut self, content: &str) {
+    pub fn render_dialog(
+        &mut self,
+        content: &str,
+        action: &str,
+        running_processes: &[String],
+    ) {
         self.sugarloaf.clear();
-        crate::router::routes::dialog::screen(&mut self.sugarloaf, content);
+        crate::router::routes::dialog::screen(
+            &mut self.sugarloaf,
+            content,
+            action,
+            running_processes,
+        );
         self.sugarloaf.render();
     }
 
@@ -1931,18 +2386,78 @@ impl Screen<'_> {
             None
         };
 
-        let (rows, cursor, display_offset, has_blinking_enabled) = {
+        let (rows, cursor, display_offset, has_blinking_enabled, latency, new_lines) = {
             let terminal = self.context_manager.current().terminal.lock();
+            let display_offset = terminal.display_offset();
+            let history = terminal.grid.history_size();
+            let visual_offset = self.renderer.smooth_scroll.update(
+                (
+                    self.context_manager.current_route(),
+                    terminal.mode().contains(Mode::ALT_SCREEN),
+                ),
+                history,
+                display_offset,
+            );
+            // While the viewport moves it's drawn from the line above it
+            // and shifted up by the rest of the line.
+            let view_offset = visual_offset.ceil() as usize;
+            let fraction = view_offset as f32 - visual_offset;
+            let mut cursor = terminal.cursor();
+            let rows = if view_offset != display_offset || fraction > 0. {
+                cursor.content = CursorShape::Hidden;
+                let extra_line = usize::from(fraction > 0.);
+                terminal.rows_at(view_offset, terminal.grid.screen_lines() + extra_line)
+            } else {
+                let mut rows = terminal.visible_rows();
+                terminal.overlay_predictions(&mut rows, &mut cursor);
+                rows
+            };
+            let layout = self.sugarloaf.layout();
+            let line_height =
+                layout.dimensions.height * layout.line_height / layout.dimensions.scale;
+            self.sugarloaf.set_scroll_offset(-fraction * line_height);
+            let minimap = if self.renderer.show_minimap
+                && !terminal.mode().contains(Mode::ALT_SCREEN)
+            {
+                let (_, size) = minimap::area(&self.sugarloaf.layout());
+                let search = if is_search_active {
+                    self.search_state.dfas_mut()
+                } else {
+                    None
+                };
+                minimap::sample(&terminal, size[1], search)
+            } else {
+                None
+            };
+            self.renderer.set_minimap(minimap);
+            self.renderer.set_history(history);
             let data = (
-                terminal.visible_rows(),
-                terminal.cursor(),
-                terminal.display_offset(),
+                rows,
+                cursor,
+                view_offset,
                 terminal.blinking_cursor,
+                terminal.predictions.latency(),
+                // Lines scrolled back into view aren't new anymore.
+                std::cmp::min(terminal.unseen_lines, display_offset),
             );
             drop(terminal);
             data
         };
         self.renderer.set_ime(self.ime.preedit());
+        self.renderer.set_ime_indicator(
+            match (
+                self.context_manager.current().ime_allowed,
+                self.ime.is_enabled(),
+            ) {
+                (false, _) => Some("IME off"),
+                (true, true) => Some("IME"),
+                (true, false) => None,
+            },
+        );
+        self.renderer.set_latency(latency);
+        self.renderer.set_new_lines(new_lines);
+        // The current tab is seen, so its indicator goes away.
+        self.context_manager.clear_current_indicator();
         self.renderer.prepare_term(
             &rows,
             cursor,
@@ -1954,12 +2469,45 @@ impl Screen<'_> {
             &self.search_state.focused_match,
         );
         self.sugarloaf.render();
+        self.context_manager.frame_drawn();
         // In this case the configuration of blinking cursor is enabled
         // and the terminal also have instructions of blinking enabled
         // TODO: enable blinking for selection after adding debounce (https://github.com/raphamorim/rio/issues/437)
         if self.renderer.has_blinking_enabled() && self.selection_is_empty() {
+            self.context_manager.blink_cursor(
+                self.renderer
+                    .next_blinking_phase(self.renderer.config_blinking_interval),
+            );
+        }
+
+        if let Some(millis) = self.renderer.next_text_blinking_phase() {
+            self.context_manager.blink_text(millis);
+        }
+
+        // Render once more to clear the visual bell.
+        if let Some(remaining) = self.renderer.bell_flash_remaining() {
+            self.context_manager
+                .schedule_render(remaining.as_millis() as u64 + 1);
+        }
+
+        // Keep rendering while the scrollbar fades in or out, the
+        // viewport moves or the cursor moves to its new cell.
+        if self.renderer.scrollbar.fade_remaining().is_some()
+            || self.renderer.smooth_scroll.is_animating()
+            || self.renderer.cursor_animation.is_animating()
+        {
+            self.context_manager.schedule_next_frame();
+        }
+
+        // Same for the toast.
+        if let Some(remaining) = self.renderer.toast_remaining() {
             self.context_manager
-                .blink_cursor(self.renderer.config_blinking_interval);
+                .schedule_render(remaining.as_millis() as u64 + 1);
+        }
+
+        // Throttled by the window while unfocused or occluded.
+        if self.sugarloaf.has_background_shader() {
+            self.context_manager.schedule_next_frame();
         }
 
         // let duration = start_total.elapsed();
@@ -0,0 +1,121 @@
+// Optional check for a newer release, enabled with `check-for-updates`.
+// The latest release is fetched with `curl` in its own thread and shown
+// as a toast with the start of its changelog, nothing gets installed.
+
+use rio_backend::event::{EventProxy, RioEvent, RioEventType, WindowId};
+use std::process::{Command, Stdio};
+
+const LATEST_RELEASE_URL: &str =
+    "https://api.github.com/repos/raphamorim/rio/releases/latest";
+const MAX_CHANGELOG_LINES: usize = 5;
+
+pub fn check(event_proxy: EventProxy, window_id: WindowId) {
+    let mut command = Command::new("curl");
+    command
+        .args(["-fsSL", "--max-time", "10"])
+        .args(["-H", "Accept: application/vnd.github+json"])
+        .arg(LATEST_RELEASE_URL)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null());
+    // Rio runs in the windows subsystem, curl would open a console.
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        command.creation_flags(windows_sys::Win32::System::Threading::CREATE_NO_WINDOW);
+    }
+    rio_backend::performer::spawn_named("update check", move || {
+        let output = match command.output() {
+            Ok(output) if output.status.success() => output.stdout,
+            Ok(output) => {
+                tracing::warn!("failed to fetch the latest release: {}", output.status);
+                return;
+            }
+            Err(err) => {
+                tracing::warn!("failed to run curl for the update check: {err}");
+                return;
+            }
+        };
+
+        if let Some(message) = toast_message(&output, env!("CARGO_PKG_VERSION")) {
+            event_proxy
+                .send_event(RioEventType::Rio(RioEvent::Toast(message)), window_id);
+        }
+    });
+}
+
+/// Numeric parts of a version like `v0.2.1`, pre-release suffixes are ignored.
+fn parse_version(version: &str) -> Option<Vec<u64>> {
+    let version = version.trim().trim_start_matches('v');
+    let version = version
+        .split_once('-')
+        .map_or(version, |(version, _)| version);
+    version.split('.').map(|part| part.parse().ok()).collect()
+}
+
+fn is_newer(latest: &str, current: &str) -> bool {
+    match (parse_version(latest), parse_version(current)) {
+        (Some(latest), Some(current)) => latest > current,
+        _ => false,
+    }
+}
+
+/// Toast for a release newer than `current`, from the GitHub release JSON.
+fn toast_message(release: &[u8], current: &str) -> Option<String> {
+    let release: serde_json::Value = serde_json::from_slice(release).ok()?;
+    let latest = release.get("tag_name")?.as_str()?;
+    if !is_newer(latest, current) {
+        return None;
+    }
+
+    let mut message = format!("Rio {latest} is available, you are on {current}");
+    let changelog = release
+        .get("body")
+        .and_then(|body| body.as_str())
+        .unwrap_or_default();
+    for line in changelog
+        .lines()
+        .map(str::trim_end)
+        .filter(|line| !line.trim().is_empty())
+        .take(MAX_CHANGELOG_LINES)
+    {
+        message.push('\n');
+        message.push_str(line);
+    }
+    if let Some(url) = release.get("html_url").and_then(|url| url.as_str()) {
+        message.push('\n');
+        message.push_str(url);
+    }
+
+    Some(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_newer() {
+        assert!(is_newer("v0.2.1", "0.2.0"));
+        assert!(is_newer("v0.10.0", "0.9.9"));
+        assert!(is_newer("v1.0.0-beta", "0.9.0"));
+        assert!(!is_newer("v0.2.0", "0.2.0"));
+        assert!(!is_newer("v0.1.9", "0.2.0"));
+        assert!(!is_newer("nightly", "0.2.0"));
+    }
+
+    #[test]
+    fn test_toast_message() {
+        let release = br#"{
+            "tag_name": "v99.0.0",
+            "html_url": "https://github.com/raphamorim/rio/releases/tag/v99.0.0",
+            "body": "- Faster rendering\n\n- Fix scrolling\n"
+        }"#;
+        assert_eq!(
+            toast_message(release, "0.2.0").as_deref(),
+            Some("Rio v99.0.0 is available, you are on 0.2.0\n- Faster rendering\n- Fix scrolling\nhttps://github.com/raphamorim/rio/releases/tag/v99.0.0")
+        );
+        assert_eq!(toast_message(release, "99.0.0"), None);
+        assert_eq!(toast_message(b"not json", "0.2.0"), None);
+    }
+}
@@ -1,6 +1,6 @@
 use crate::event::{EventListener, RioEvent};
 use notify::{Config, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
 const POLLING_TIMEOUT: Duration = Duration::from_secs(2);
@@ -10,18 +10,27 @@ pub fn configuration_file_updates<
     T: EventListener + std::marker::Send + 'static,
 >(
     path: P,
+    files: Vec<PathBuf>,
     event_proxy: T,
-) -> notify::Result<()> {
-    let (tx, rx) = std::sync::mpsc::channel();
+) {
+    // Setting up the watcher can be slow (e.g. FSEvents on macOS), so
+    // it's done off the startup path.
+    rio_backend::performer::spawn_named("config watcher", move || {
+        let (tx, rx) = std::sync::mpsc::channel();
 
-    // Automatically select the best implementation for your platform.
-    // You can also access each implementation directly e.g. INotifyWatcher.
-    let mut watcher = RecommendedWatcher::new(
-        tx,
-        Config::default().with_poll_interval(POLLING_TIMEOUT),
-    )?;
+        // Automatically select the best implementation for your platform.
+        // You can also access each implementation directly e.g. INotifyWatcher.
+        let mut watcher = match RecommendedWatcher::new(
+            tx,
+            Config::default().with_poll_interval(POLLING_TIMEOUT),
+        ) {
+            Ok(watcher) => watcher,
+            Err(err_message) => {
+                tracing::warn!("unable to create config watcher {err_message:?}");
+                return;
+            }
+        };
 
-    std::thread::spawn(move || {
         // Add a path to be watched. All files and directories at that path and
         // below will be monitored for changes.
         if let Err(err_message) =
@@ -30,6 +39,22 @@ pub fn configuration_file_updates<
             tracing::warn!("unable to watch config directory {err_message:?}");
         };
 
+        // Files used by the config, like theme files generated by tools like
+        // pywal or background images, are usually replaced instead of modified
+        // in place, so their parent directories are watched instead.
+        let mut file_dirs: Vec<&Path> = Vec::new();
+        for file_dir in files.iter().filter_map(|file| file.parent()) {
+            if file_dir != path.as_ref() && !file_dirs.contains(&file_dir) {
+                file_dirs.push(file_dir);
+            }
+        }
+        for file_dir in &file_dirs {
+            if let Err(err_message) = watcher.watch(file_dir, RecursiveMode::NonRecursive)
+            {
+                tracing::warn!("unable to watch {file_dir:?} directory {err_message:?}");
+            };
+        }
+
         for res in rx {
             match res {
                 Ok(event) => match event.kind {
@@ -37,6 +62,17 @@ pub fn configuration_file_updates<
                     | EventKind::Create(_)
                     | EventKind::Modify(_)
                     | EventKind::Other => {
+                        // Skip unrelated files living next to the watched files.
+                        let is_unrelated = !event.paths.is_empty()
+                            && event.paths.iter().all(|event_path| {
+                                event_path.parent().is_some_and(|event_dir| {
+                                    file_dirs.contains(&event_dir)
+                                }) && !files.contains(event_path)
+                            });
+                        if is_unrelated {
+                            continue;
+                        }
+
                         tracing::info!(
                             "config directory has dispatched an event {event:?}"
                         );
@@ -53,6 +89,4 @@ pub fn configuration_file_updates<
             }
         }
     });
-
-    Ok(())
 }
@@ -0,0 +1,232 @@
+// Embeds a terminal running the default shell in a window, drawing the grid
+// with sugarloaf.
+//
+// cargo run -p rio-backend --example embed
+
+use raw_window_handle::{HasDisplayHandle, HasWindowHandle};
+use rio_backend::config::colors::{term::List, AnsiColor, ColorArray, NamedColor};
+use rio_backend::crosswords::square::{Flags, Square};
+use rio_backend::embed::{Terminal, TerminalOptions};
+use rio_backend::event::{EventListener, RioEvent};
+use rio_backend::sugarloaf::{
+    self, layout::SugarloafLayout, FragmentStyle, Sugarloaf, SugarloafWindow,
+    SugarloafWindowSize,
+};
+use rio_window::application::ApplicationHandler;
+use rio_window::dpi::LogicalSize;
+use rio_window::event::{ElementState, WindowEvent};
+use rio_window::event_loop::{ActiveEventLoop, EventLoop, EventLoopProxy};
+use rio_window::window::{Window, WindowAttributes, WindowId};
+
+/// Forwards the terminal events to the window event loop.
+#[derive(Clone)]
+struct EventProxy(EventLoopProxy<RioEvent>);
+
+impl EventListener for EventProxy {
+    fn event(&self) -> (Option<RioEvent>, bool) {
+        (None, false)
+    }
+
+    fn send_event(&self, event: RioEvent, _id: WindowId) {
+        let _ = self.0.send_event(event);
+    }
+}
+
+struct Application {
+    proxy: EventLoopProxy<RioEvent>,
+    window: Option<Window>,
+    sugarloaf: Option<Sugarloaf<'static>>,
+    terminal: Option<Terminal<EventProxy>>,
+}
+
+impl Application {
+    fn resize(&mut self) {
+        let (Some(sugarloaf), Some(terminal)) = (&self.sugarloaf, &self.terminal) else {
+            return;
+        };
+
+        terminal.resize(sugarloaf.layout());
+    }
+
+    fn draw(&mut self) {
+        let (Some(sugarloaf), Some(terminal)) = (&mut self.sugarloaf, &self.terminal)
+        else {
+            return;
+        };
+
+        let crosswords = terminal.crosswords().lock();
+        let colors = crosswords.colors();
+        let rows = crosswords.visible_rows();
+        let cursor = crosswords.grid.cursor.pos;
+        drop(crosswords);
+
+        let content = sugarloaf.content();
+        for (line, row) in rows.iter().enumerate() {
+            let mut text = String::new();
+            let mut last_style = None;
+            for (column, square) in row.inner.iter().enumerate() {
+                if square.flags.contains(Flags::WIDE_CHAR_SPACER) {
+                    continue;
+                }
+
+                let has_cursor = cursor.row == line && cursor.col == column;
+                let style = style(&colors, square, has_cursor);
+                if last_style.is_some_and(|last_style| last_style != style) {
+                    content.add_text(&text, last_style.unwrap());
+                    text.clear();
+                }
+
+                text.push(square.c);
+                last_style = Some(style);
+            }
+
+            if let Some(last_style) = last_style {
+                content.add_text(&text, last_style);
+            }
+            content.new_line();
+        }
+
+        sugarloaf.render();
+    }
+}
+
+fn color(colors: &List, color: AnsiColor) -> ColorArray {
+    match color {
+        AnsiColor::Named(name) => colors[name],
+        AnsiColor::Spec(rgb) => rgb.to_arr(),
+        AnsiColor::Indexed(index) => colors[index as usize],
+    }
+}
+
+fn style(colors: &List, square: &Square, has_cursor: bool) -> FragmentStyle {
+    let mut foreground = color(colors, square.fg);
+    let mut background = color(colors, square.bg);
+    if has_cursor ^ square.flags.contains(Flags::INVERSE) {
+        std::mem::swap(&mut foreground, &mut background);
+    }
+
+    FragmentStyle {
+        color: foreground,
+        background_color: Some(background),
+        width: if square.flags.contains(Flags::WIDE_CHAR) {
+            2.0
+        } else {
+            1.0
+        },
+        ..FragmentStyle::default()
+    }
+}
+
+impl ApplicationHandler<RioEvent> for Application {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        let attributes = WindowAttributes::default()
+            .with_title("Embedded terminal")
+            .with_inner_size(LogicalSize::new(800.0, 500.0))
+            .with_resizable(true);
+        let window = event_loop.create_window(attributes).unwrap();
+
+        let scale = window.scale_factor() as f32;
+        let size = window.inner_size();
+        let layout = SugarloafLayout::new(
+            size.width as f32 / scale,
+            size.height as f32 / scale,
+            (10.0, 10.0, 0.0),
+            scale,
+            16.0,
+            1.0,
+        );
+
+        let sugarloaf_window = SugarloafWindow {
+            handle: window.window_handle().unwrap().into(),
+            display: window.display_handle().unwrap().into(),
+            scale,
+            size: SugarloafWindowSize {
+                width: size.width as f32,
+                height: size.height as f32,
+            },
+        };
+
+        let mut sugarloaf = Sugarloaf::new(
+            sugarloaf_window,
+            sugarloaf::SugarloafRenderer::default(),
+            &sugarloaf::font::FontLibrary::default(),
+            layout,
+        )
+        .expect("Sugarloaf instance should be created");
+        let background = List::from(&Default::default())[NamedColor::Background];
+        sugarloaf.set_background_color(Some(wgpu::Color {
+            r: background[0] as f64,
+            g: background[1] as f64,
+            b: background[2] as f64,
+            a: 1.0,
+        }));
+
+        let terminal = Terminal::spawn(
+            TerminalOptions::default(),
+            EventProxy(self.proxy.clone()),
+            window.id(),
+        )
+        .expect("Shell should be spawned");
+
+        self.sugarloaf = Some(sugarloaf);
+        self.terminal = Some(terminal);
+        self.window = Some(window);
+        self.resize();
+    }
+
+    fn user_event(&mut self, event_loop: &ActiveEventLoop, event: RioEvent) {
+        match event {
+            RioEvent::RenderRoute(_) | RioEvent::Render => {
+                if let Some(window) = &self.window {
+                    window.request_redraw();
+                }
+            }
+            RioEvent::PtyWrite(text) => {
+                if let Some(terminal) = &self.terminal {
+                    terminal.write(text.into_bytes());
+                }
+            }
+            RioEvent::CloseTerminal(_) => event_loop.exit(),
+            _ => {}
+        }
+    }
+
+    fn window_event(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        _window_id: WindowId,
+        event: WindowEvent,
+    ) {
+        match event {
+            WindowEvent::CloseRequested => event_loop.exit(),
+            WindowEvent::Resized(size) => {
+                if let Some(sugarloaf) = &mut self.sugarloaf {
+                    sugarloaf.resize(size.width, size.height);
+                }
+                self.resize();
+            }
+            WindowEvent::KeyboardInput { event, .. } => {
+                if event.state != ElementState::Pressed {
+                    return;
+                }
+
+                if let (Some(terminal), Some(text)) = (&self.terminal, event.text) {
+                    terminal.write(text.as_bytes().to_vec());
+                }
+            }
+            WindowEvent::RedrawRequested => self.draw(),
+            _ => {}
+        }
+    }
+}
+
+fn main() {
+    let event_loop = EventLoop::<RioEvent>::with_user_event().build().unwrap();
+    let mut application = Application {
+        proxy: event_loop.create_proxy(),
+        window: None,
+        sugarloaf: None,
+        terminal: None,
+    };
+    event_loop.run_app(&mut application).unwrap();
+}
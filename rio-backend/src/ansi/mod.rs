@@ -6,7 +6,10 @@ pub mod control;
 pub mod graphics;
 pub mod iterm2_image_protocol;
 pub mod mode;
+pub mod private_graphics;
+pub mod qrcode;
 pub mod sixel;
+pub mod terminfo;
 
 #[derive(Default, Clone, Serialize, Deserialize, Copy, Debug, Eq, PartialEq)]
 pub enum CursorShape {
@@ -75,6 +78,55 @@ pub enum LineClearMode {
     All,
 }
 
+/// Area of the DEC rectangle operations (DECCRA, DECFRA, DECERA).
+///
+/// Edges are 1-based and inclusive, as sent by the application. Missing
+/// bottom and right edges default to the last line and column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RectangularArea {
+    pub top: usize,
+    pub left: usize,
+    pub bottom: Option<usize>,
+    pub right: Option<usize>,
+}
+
+/// Size of a line set with DECDWL (`ESC # 6`), DECDHL (`ESC # 3` and
+/// `ESC # 4`) and DECSWL (`ESC # 5`).
+///
+/// Double height lines are sent in pairs, the top half followed by the
+/// bottom half with the same text.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum LineSize {
+    #[default]
+    Normal,
+    DoubleWidth,
+    DoubleHeightTop,
+    DoubleHeightBottom,
+}
+
+impl LineSize {
+    #[inline]
+    pub fn is_double(self) -> bool {
+        self != LineSize::Normal
+    }
+}
+
+/// Shell integration marks sent through OSC 133.
+///
+/// See <https://gitlab.freedesktop.org/Per_Bothner/specifications/blob/master/proposals/semantic-prompts.md>
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SemanticPrompt {
+    /// `OSC 133 ; A [; redraw=0]`: start of the prompt, `redraw` tells if the
+    /// shell redraws the prompt when the terminal is resized.
+    PromptStart { redraw: bool },
+    /// `OSC 133 ; B`: end of the prompt and start of the command input.
+    CommandStart,
+    /// `OSC 133 ; C`: command was submitted, output starts here.
+    CommandExecuted,
+    /// `OSC 133 ; D [; exit code]`: command has finished.
+    CommandFinished(Option<i32>),
+}
+
 bitflags! {
     /// A set of [`kitty keyboard protocol'] modes.
     ///
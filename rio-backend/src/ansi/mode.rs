@@ -58,6 +58,8 @@ impl PrivateMode {
             7 => Self::Named(NamedPrivateMode::LineWrap),
             12 => Self::Named(NamedPrivateMode::BlinkingCursor),
             25 => Self::Named(NamedPrivateMode::ShowCursor),
+            66 => Self::Named(NamedPrivateMode::ApplicationKeypad),
+            69 => Self::Named(NamedPrivateMode::LeftRightMargin),
             1000 => Self::Named(NamedPrivateMode::ReportMouseClicks),
             1002 => Self::Named(NamedPrivateMode::ReportCellMouseMotion),
             1003 => Self::Named(NamedPrivateMode::ReportAllMouseMotion),
@@ -69,6 +71,7 @@ impl PrivateMode {
             1049 => Self::Named(NamedPrivateMode::SwapScreenAndSetRestoreCursor),
             2004 => Self::Named(NamedPrivateMode::BracketedPaste),
             2026 => Self::Named(NamedPrivateMode::SyncUpdate),
+            2027 => Self::Named(NamedPrivateMode::GraphemeClustering),
             _ => Self::Unknown(mode),
         }
     }
@@ -108,6 +111,11 @@ pub enum NamedPrivateMode {
     LineWrap = 7,
     BlinkingCursor = 12,
     ShowCursor = 25,
+    /// Send application sequences from the keypad (DECNKM), same as
+    /// DECKPAM/DECKPNM.
+    ApplicationKeypad = 66,
+    /// Enable the left and right margins set by DECSLRM (DECLRMM).
+    LeftRightMargin = 69,
     ReportMouseClicks = 1000,
     ReportCellMouseMotion = 1002,
     ReportAllMouseMotion = 1003,
@@ -120,6 +128,9 @@ pub enum NamedPrivateMode {
     BracketedPaste = 2004,
     /// The mode is handled automatically by [`Processor`].
     SyncUpdate = 2026,
+    /// Grapheme cluster segmentation, see
+    /// <https://github.com/contour-terminal/terminal-unicode-core>.
+    GraphemeClustering = 2027,
 }
 
 /// Mode for clearing line.
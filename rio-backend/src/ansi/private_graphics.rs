@@ -0,0 +1,538 @@
+// Rio private graphics escapes.
+//
+// OSC 1338 renders text based content as an inline image through the
+// graphics pipeline, it's only handled when `security.allow-private-graphics`
+// is enabled.
+//
+// ```notrust
+// ESC ] 1338 ; qr ; <text> ST
+// ESC ] 1338 ; math ; <expression> ST
+// ```
+//
+// Math expressions support a small subset of TeX: `^` and `_` scripts,
+// `{}` groups, `\frac{}{}`, `\sqrt{}` and common symbols like `\alpha`
+// or `\sum`.
+
+use crate::ansi::qrcode::QrCode;
+use ab_glyph::{Font, FontRef, PxScale, ScaleFont};
+use sugarloaf::font::constants::FONT_CASCADIAMONO_REGULAR;
+use sugarloaf::{ColorType, GraphicData, GraphicId};
+
+/// Longest accepted math expression.
+const MAX_MATH_LEN: usize = 512;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PrivateGraphic {
+    QrCode(String),
+    Math(String),
+}
+
+/// Parse the OSC 1338 parameters.
+pub fn parse(params: &[&[u8]]) -> Option<PrivateGraphic> {
+    if params.len() < 3 {
+        return None;
+    }
+
+    // The content may contain `;`, which is used as parameter separator.
+    let content = params[2..].join(&b';');
+    let content = String::from_utf8(content).ok()?;
+
+    match params[1] {
+        b"qr" => Some(PrivateGraphic::QrCode(content)),
+        b"math" if content.len() <= MAX_MATH_LEN => Some(PrivateGraphic::Math(content)),
+        _ => None,
+    }
+}
+
+impl PrivateGraphic {
+    /// Render the graphic, sized for cells of `cell_height` pixels.
+    pub fn render(&self, cell_height: usize) -> Option<GraphicData> {
+        match self {
+            PrivateGraphic::QrCode(text) => render_qrcode(text, cell_height),
+            PrivateGraphic::Math(expression) => render_math(expression, cell_height),
+        }
+    }
+}
+
+fn render_qrcode(text: &str, cell_height: usize) -> Option<GraphicData> {
+    const QUIET_ZONE: usize = 4;
+
+    let qr = QrCode::encode(text.as_bytes())?;
+    let module_size = std::cmp::max(2, cell_height / 4);
+    let size = (qr.size() + QUIET_ZONE * 2) * module_size;
+
+    let mut pixels = vec![255; size * size * 3];
+    for y in 0..qr.size() {
+        for x in 0..qr.size() {
+            if !qr.is_dark(x, y) {
+                continue;
+            }
+
+            for py in 0..module_size {
+                let row = (y + QUIET_ZONE) * module_size + py;
+                let start = (row * size + (x + QUIET_ZONE) * module_size) * 3;
+                pixels[start..start + module_size * 3].fill(0);
+            }
+        }
+    }
+
+    Some(GraphicData {
+        id: GraphicId(0),
+        width: size,
+        height: size,
+        color_type: ColorType::Rgb,
+        pixels,
+        is_opaque: true,
+        resize: None,
+    })
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Symbol(char),
+    Group(Vec<Node>),
+    Script {
+        base: Box<Node>,
+        sup: Option<Box<Node>>,
+        sub: Option<Box<Node>>,
+    },
+    Fraction(Box<Node>, Box<Node>),
+    Sqrt(Box<Node>),
+}
+
+fn command_symbol(name: &str) -> Option<char> {
+    let symbol = match name {
+        "alpha" => 'α',
+        "beta" => 'β',
+        "gamma" => 'γ',
+        "delta" => 'δ',
+        "epsilon" => 'ε',
+        "theta" => 'θ',
+        "lambda" => 'λ',
+        "mu" => 'μ',
+        "pi" => 'π',
+        "rho" => 'ρ',
+        "sigma" => 'σ',
+        "tau" => 'τ',
+        "phi" => 'φ',
+        "omega" => 'ω',
+        "Gamma" => 'Γ',
+        "Delta" => 'Δ',
+        "Theta" => 'Θ',
+        "Lambda" => 'Λ',
+        "Pi" => 'Π',
+        "Sigma" => 'Σ',
+        "Phi" => 'Φ',
+        "Omega" => 'Ω',
+        "sum" => '∑',
+        "prod" => '∏',
+        "int" => '∫',
+        "infty" => '∞',
+        "partial" => '∂',
+        "nabla" => '∇',
+        "cdot" => '·',
+        "times" => '×',
+        "div" => '÷',
+        "pm" => '±',
+        "le" | "leq" => '≤',
+        "ge" | "geq" => '≥',
+        "ne" | "neq" => '≠',
+        "approx" => '≈',
+        "equiv" => '≡',
+        "to" | "rightarrow" => '→',
+        "leftarrow" => '←',
+        "in" => '∈',
+        _ => return None,
+    };
+
+    Some(symbol)
+}
+
+struct MathParser<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+}
+
+impl MathParser<'_> {
+    fn parse_list(&mut self, nested: bool) -> Vec<Node> {
+        let mut nodes = Vec::new();
+        while let Some(c) = self.chars.next() {
+            match c {
+                '}' if nested => break,
+                '^' | '_' => {
+                    let base = nodes.pop().unwrap_or(Node::Group(Vec::new()));
+                    let script = Box::new(self.parse_atom());
+                    nodes.push(match base {
+                        Node::Script { base, sup, sub } => {
+                            if c == '^' {
+                                Node::Script {
+                                    base,
+                                    sup: Some(script),
+                                    sub,
+                                }
+                            } else {
+                                Node::Script {
+                                    base,
+                                    sup,
+                                    sub: Some(script),
+                                }
+                            }
+                        }
+                        base => Node::Script {
+                            base: Box::new(base),
+                            sup: (c == '^').then_some(script.clone()),
+                            sub: (c == '_').then_some(script),
+                        },
+                    });
+                }
+                _ => nodes.push(self.parse_node(c)),
+            }
+        }
+        nodes
+    }
+
+    fn parse_atom(&mut self) -> Node {
+        while self.chars.next_if_eq(&' ').is_some() {}
+        match self.chars.next() {
+            Some(c) => self.parse_node(c),
+            None => Node::Group(Vec::new()),
+        }
+    }
+
+    fn parse_node(&mut self, c: char) -> Node {
+        match c {
+            '{' => Node::Group(self.parse_list(true)),
+            '\\' => self.parse_command(),
+            c => Node::Symbol(c),
+        }
+    }
+
+    fn parse_command(&mut self) -> Node {
+        let mut name = String::new();
+        while let Some(c) = self.chars.next_if(|c| c.is_ascii_alphabetic()) {
+            name.push(c);
+        }
+
+        // Escaped characters like `\{` or `\\`
+        if name.is_empty() {
+            return Node::Symbol(self.chars.next().unwrap_or('\\'));
+        }
+
+        match name.as_str() {
+            "frac" => {
+                let numerator = self.parse_atom();
+                let denominator = self.parse_atom();
+                Node::Fraction(Box::new(numerator), Box::new(denominator))
+            }
+            "sqrt" => Node::Sqrt(Box::new(self.parse_atom())),
+            name => match command_symbol(name) {
+                Some(symbol) => Node::Symbol(symbol),
+                None => Node::Group(name.chars().map(Node::Symbol).collect()),
+            },
+        }
+    }
+}
+
+fn parse_math(expression: &str) -> Vec<Node> {
+    MathParser {
+        chars: expression.chars().peekable(),
+    }
+    .parse_list(false)
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Item {
+    Glyph {
+        c: char,
+        x: f32,
+        y: f32,
+        size: f32,
+    },
+    Rule {
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+    },
+}
+
+impl Item {
+    fn translate(self, dx: f32, dy: f32) -> Item {
+        match self {
+            Item::Glyph { c, x, y, size } => Item::Glyph {
+                c,
+                x: x + dx,
+                y: y + dy,
+                size,
+            },
+            Item::Rule {
+                x,
+                y,
+                width,
+                height,
+            } => Item::Rule {
+                x: x + dx,
+                y: y + dy,
+                width,
+                height,
+            },
+        }
+    }
+}
+
+/// Laid out node, positions are relative to the left of its baseline.
+#[derive(Debug, Default)]
+struct MathBox {
+    width: f32,
+    ascent: f32,
+    descent: f32,
+    items: Vec<Item>,
+}
+
+impl MathBox {
+    fn append(&mut self, other: MathBox, dx: f32, dy: f32) {
+        self.items
+            .extend(other.items.into_iter().map(|item| item.translate(dx, dy)));
+    }
+}
+
+fn layout(font: &FontRef, node: &Node, size: f32) -> MathBox {
+    let thickness = (size * 0.06).max(1.0);
+    let gap = size * 0.1;
+
+    match node {
+        Node::Symbol(c) => {
+            let scaled = font.as_scaled(PxScale::from(size));
+            MathBox {
+                width: scaled.h_advance(font.glyph_id(*c)),
+                ascent: scaled.ascent(),
+                descent: -scaled.descent(),
+                items: vec![Item::Glyph {
+                    c: *c,
+                    x: 0.,
+                    y: 0.,
+                    size,
+                }],
+            }
+        }
+        Node::Group(nodes) => layout_row(font, nodes, size),
+        Node::Script { base, sup, sub } => {
+            let base = layout(font, base, size);
+            let mut result = MathBox {
+                width: base.width,
+                ascent: base.ascent,
+                descent: base.descent,
+                items: Vec::new(),
+            };
+            let x = base.width;
+            result.append(base, 0., 0.);
+
+            let mut scripts_width: f32 = 0.;
+            if let Some(sup) = sup {
+                let sup = layout(font, sup, size * 0.7);
+                let shift = size * 0.4;
+                scripts_width = scripts_width.max(sup.width);
+                result.ascent = result.ascent.max(sup.ascent + shift);
+                result.append(sup, x, -shift);
+            }
+            if let Some(sub) = sub {
+                let sub = layout(font, sub, size * 0.7);
+                let shift = size * 0.2;
+                scripts_width = scripts_width.max(sub.width);
+                result.descent = result.descent.max(sub.descent + shift);
+                result.append(sub, x, shift);
+            }
+            result.width += scripts_width;
+            result
+        }
+        Node::Fraction(numerator, denominator) => {
+            let numerator = layout(font, numerator, size * 0.8);
+            let denominator = layout(font, denominator, size * 0.8);
+            // Height of the fraction bar above the baseline
+            let axis = size * 0.3;
+            let width = numerator.width.max(denominator.width) + size * 0.2;
+
+            let numerator_y = -(axis + thickness / 2. + gap + numerator.descent);
+            let denominator_y = -axis + thickness / 2. + gap + denominator.ascent;
+            let mut result = MathBox {
+                width,
+                ascent: -numerator_y + numerator.ascent,
+                descent: (denominator_y + denominator.descent).max(0.),
+                items: vec![Item::Rule {
+                    x: 0.,
+                    y: -axis - thickness / 2.,
+                    width,
+                    height: thickness,
+                }],
+            };
+            let numerator_x = (width - numerator.width) / 2.;
+            let denominator_x = (width - denominator.width) / 2.;
+            result.append(numerator, numerator_x, numerator_y);
+            result.append(denominator, denominator_x, denominator_y);
+            result
+        }
+        Node::Sqrt(inner) => {
+            let radical = layout(font, &Node::Symbol('√'), size);
+            let inner = layout(font, inner, size);
+            let x = radical.width;
+            let mut result = MathBox {
+                width: radical.width + inner.width,
+                ascent: (inner.ascent + gap + thickness).max(radical.ascent),
+                descent: inner.descent.max(radical.descent),
+                items: vec![Item::Rule {
+                    x,
+                    y: -(inner.ascent + gap + thickness),
+                    width: inner.width,
+                    height: thickness,
+                }],
+            };
+            result.append(radical, 0., 0.);
+            result.append(inner, x, 0.);
+            result
+        }
+    }
+}
+
+fn layout_row(font: &FontRef, nodes: &[Node], size: f32) -> MathBox {
+    let mut result = MathBox::default();
+    for node in nodes {
+        let node = layout(font, node, size);
+        let x = result.width;
+        result.width += node.width;
+        result.ascent = result.ascent.max(node.ascent);
+        result.descent = result.descent.max(node.descent);
+        result.append(node, x, 0.);
+    }
+    result
+}
+
+fn render_math(expression: &str, cell_height: usize) -> Option<GraphicData> {
+    let font = FontRef::try_from_slice(FONT_CASCADIAMONO_REGULAR).ok()?;
+    let size = cell_height.max(8) as f32;
+    let math = layout_row(&font, &parse_math(expression), size);
+    if math.items.is_empty() {
+        return None;
+    }
+
+    let padding = (size * 0.25).ceil();
+    let width = (math.width + padding * 2.).ceil() as usize;
+    let height = (math.ascent + math.descent + padding * 2.).ceil() as usize;
+    let baseline = padding + math.ascent;
+
+    let mut pixels = vec![255u8; width * height * 3];
+    let mut darken = |x: i32, y: i32, coverage: f32| {
+        if x < 0 || y < 0 || x as usize >= width || y as usize >= height {
+            return;
+        }
+        let index = (y as usize * width + x as usize) * 3;
+        let value = (255. * (1. - coverage.clamp(0., 1.))) as u8;
+        for channel in &mut pixels[index..index + 3] {
+            *channel = (*channel).min(value);
+        }
+    };
+
+    for item in math.items {
+        match item.translate(padding, baseline) {
+            Item::Glyph { c, x, y, size } => {
+                let glyph = font
+                    .glyph_id(c)
+                    .with_scale_and_position(size, ab_glyph::point(x, y));
+                if let Some(outlined) = font.outline_glyph(glyph) {
+                    let bounds = outlined.px_bounds();
+                    outlined.draw(|gx, gy, coverage| {
+                        darken(
+                            bounds.min.x as i32 + gx as i32,
+                            bounds.min.y as i32 + gy as i32,
+                            coverage,
+                        );
+                    });
+                }
+            }
+            Item::Rule {
+                x,
+                y,
+                width,
+                height,
+            } => {
+                let (x0, y0) = (x.round() as i32, y.round() as i32);
+                let (x1, y1) = ((x + width).round() as i32, (y + height).round() as i32);
+                for py in y0..y1.max(y0 + 1) {
+                    for px in x0..x1 {
+                        darken(px, py, 1.);
+                    }
+                }
+            }
+        }
+    }
+
+    Some(GraphicData {
+        id: GraphicId(0),
+        width,
+        height,
+        color_type: ColorType::Rgb,
+        pixels,
+        is_opaque: true,
+        resize: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_params() {
+        assert_eq!(
+            parse(&[b"1338", b"qr", b"otpauth://totp/rio?secret=A;B"]),
+            Some(PrivateGraphic::QrCode(
+                "otpauth://totp/rio?secret=A;B".to_string()
+            ))
+        );
+        assert_eq!(
+            parse(&[b"1338", b"math", b"x^2"]),
+            Some(PrivateGraphic::Math("x^2".to_string()))
+        );
+        assert_eq!(parse(&[b"1338", b"qr"]), None);
+        assert_eq!(parse(&[b"1338", b"unknown", b"x"]), None);
+    }
+
+    #[test]
+    fn test_parse_math() {
+        assert_eq!(
+            parse_math("x_i^2"),
+            vec![Node::Script {
+                base: Box::new(Node::Symbol('x')),
+                sup: Some(Box::new(Node::Symbol('2'))),
+                sub: Some(Box::new(Node::Symbol('i'))),
+            }]
+        );
+        assert_eq!(
+            parse_math("\\frac{\\pi}{2}"),
+            vec![Node::Fraction(
+                Box::new(Node::Group(vec![Node::Symbol('π')])),
+                Box::new(Node::Group(vec![Node::Symbol('2')])),
+            )]
+        );
+        assert_eq!(
+            parse_math("\\sqrt x"),
+            vec![Node::Sqrt(Box::new(Node::Symbol('x')))]
+        );
+    }
+
+    #[test]
+    fn test_render() {
+        let qr = PrivateGraphic::QrCode("rio".to_string())
+            .render(16)
+            .unwrap();
+        // Version 1 is 21 modules plus 4 modules of quiet zone on each side
+        assert_eq!(qr.width, 29 * 4);
+        assert_eq!(qr.pixels.len(), qr.width * qr.height * 3);
+
+        let math = PrivateGraphic::Math("\\frac{a}{b}".to_string())
+            .render(16)
+            .unwrap();
+        let single = PrivateGraphic::Math("a".to_string()).render(16).unwrap();
+        assert!(math.height > single.height);
+        assert!(math.pixels.contains(&0));
+        assert!(PrivateGraphic::Math(String::new()).render(16).is_none());
+    }
+}
@@ -0,0 +1,494 @@
+// Minimal QR code encoder used to render inline QR codes.
+//
+// Only byte mode and the medium (M) error correction level are supported,
+// with versions 1 to 10 (up to 213 bytes), which covers URLs and 2FA
+// provisioning URIs. The structure follows Nayuki's QR Code generator
+// https://www.nayuki.io/page/qr-code-generator-library (MIT license).
+
+/// Total codewords, error correction codewords per block and number of
+/// blocks for versions 1 to 10 with error correction level M.
+const VERSIONS: [(usize, usize, usize); 10] = [
+    (26, 10, 1),
+    (44, 16, 1),
+    (70, 26, 1),
+    (100, 18, 2),
+    (134, 24, 2),
+    (172, 16, 4),
+    (196, 18, 4),
+    (242, 22, 4),
+    (292, 22, 5),
+    (346, 26, 5),
+];
+
+/// Format bits of error correction level M.
+const ECC_FORMAT_BITS: u32 = 0;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QrCode {
+    size: usize,
+    modules: Vec<bool>,
+    is_function: Vec<bool>,
+}
+
+impl QrCode {
+    /// Encode `data` using the smallest version that fits it. Returns `None`
+    /// if the data is too long.
+    pub fn encode(data: &[u8]) -> Option<QrCode> {
+        let (version, (total, ecc_len, blocks)) = VERSIONS
+            .iter()
+            .copied()
+            .enumerate()
+            .find_map(|(i, entry)| {
+                let version = i + 1;
+                let count_bits = if version < 10 { 8 } else { 16 };
+                let capacity_bits = (entry.0 - entry.1 * entry.2) * 8;
+                (4 + count_bits + data.len() * 8 <= capacity_bits)
+                    .then_some((version, entry))
+            })?;
+
+        let data_len = total - ecc_len * blocks;
+        let mut bits = BitBuffer::default();
+        bits.append(0b0100, 4);
+        bits.append(data.len() as u32, if version < 10 { 8 } else { 16 });
+        for byte in data {
+            bits.append(*byte as u32, 8);
+        }
+
+        // Terminator and padding up to a byte boundary.
+        let capacity_bits = data_len * 8;
+        bits.append(0, std::cmp::min(4, capacity_bits - bits.0.len()));
+        bits.append(0, (8 - bits.0.len() % 8) % 8);
+
+        let mut codewords: Vec<u8> = bits
+            .0
+            .chunks(8)
+            .map(|byte| byte.iter().fold(0u8, |acc, bit| (acc << 1) | *bit as u8))
+            .collect();
+        for pad in [0xEC, 0x11].iter().cycle() {
+            if codewords.len() >= data_len {
+                break;
+            }
+            codewords.push(*pad);
+        }
+
+        let size = version * 4 + 17;
+        let mut qr = QrCode {
+            size,
+            modules: vec![false; size * size],
+            is_function: vec![false; size * size],
+        };
+
+        qr.draw_function_patterns(version);
+        let all_codewords = add_ecc_and_interleave(&codewords, total, ecc_len, blocks);
+        qr.draw_codewords(&all_codewords);
+
+        // Pick the mask with the lowest penalty.
+        let mut best = None;
+        for mask in 0..8 {
+            qr.apply_mask(mask);
+            qr.draw_format_bits(mask);
+            let penalty = qr.penalty_score();
+            let is_better = match best {
+                Some((_, lowest)) => penalty < lowest,
+                None => true,
+            };
+            if is_better {
+                best = Some((mask, penalty));
+            }
+            // Masks are reverted by applying them again.
+            qr.apply_mask(mask);
+        }
+
+        let (mask, _) = best?;
+        qr.apply_mask(mask);
+        qr.draw_format_bits(mask);
+
+        Some(qr)
+    }
+
+    /// Width and height in modules, without the quiet zone.
+    #[inline]
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Whether the module at `x`, `y` is dark.
+    #[inline]
+    pub fn is_dark(&self, x: usize, y: usize) -> bool {
+        self.modules[y * self.size + x]
+    }
+
+    #[inline]
+    fn set_function(&mut self, x: usize, y: usize, is_dark: bool) {
+        self.modules[y * self.size + x] = is_dark;
+        self.is_function[y * self.size + x] = true;
+    }
+
+    fn draw_function_patterns(&mut self, version: usize) {
+        let size = self.size;
+
+        // Timing patterns
+        for i in 0..size {
+            self.set_function(6, i, i % 2 == 0);
+            self.set_function(i, 6, i % 2 == 0);
+        }
+
+        // Finder patterns, including separators
+        for (x, y) in [(3, 3), (size - 4, 3), (3, size - 4)] {
+            for dy in -4i32..=4 {
+                for dx in -4i32..=4 {
+                    let xx = x as i32 + dx;
+                    let yy = y as i32 + dy;
+                    if (0..size as i32).contains(&xx) && (0..size as i32).contains(&yy) {
+                        let distance = dx.abs().max(dy.abs());
+                        self.set_function(
+                            xx as usize,
+                            yy as usize,
+                            distance != 2 && distance != 4,
+                        );
+                    }
+                }
+            }
+        }
+
+        // Alignment patterns
+        let positions = alignment_positions(version);
+        let count = positions.len();
+        for i in 0..count {
+            for j in 0..count {
+                // Skip the corners taken by finder patterns
+                if [(0, 0), (0, count - 1), (count - 1, 0)].contains(&(i, j)) {
+                    continue;
+                }
+                for dy in -2i32..=2 {
+                    for dx in -2i32..=2 {
+                        self.set_function(
+                            (positions[i] as i32 + dx) as usize,
+                            (positions[j] as i32 + dy) as usize,
+                            dx.abs().max(dy.abs()) != 1,
+                        );
+                    }
+                }
+            }
+        }
+
+        // Reserve format bits, they're drawn once the mask is known.
+        self.draw_format_bits(0);
+
+        // Version information
+        if version >= 7 {
+            let mut remainder = version as u32;
+            for _ in 0..12 {
+                remainder = (remainder << 1) ^ ((remainder >> 11) * 0x1F25);
+            }
+            let bits = (version as u32) << 12 | remainder;
+            for i in 0..18 {
+                let is_dark = (bits >> i) & 1 != 0;
+                let a = size - 11 + i % 3;
+                let b = i / 3;
+                self.set_function(a, b, is_dark);
+                self.set_function(b, a, is_dark);
+            }
+        }
+    }
+
+    fn draw_format_bits(&mut self, mask: u32) {
+        let size = self.size;
+        let data = ECC_FORMAT_BITS << 3 | mask;
+        let mut remainder = data;
+        for _ in 0..10 {
+            remainder = (remainder << 1) ^ ((remainder >> 9) * 0x537);
+        }
+        let bits = (data << 10 | remainder) ^ 0x5412;
+        let bit = |i: usize| (bits >> i) & 1 != 0;
+
+        // First copy, around the top left finder pattern
+        for i in 0..=5 {
+            self.set_function(8, i, bit(i));
+        }
+        self.set_function(8, 7, bit(6));
+        self.set_function(8, 8, bit(7));
+        self.set_function(7, 8, bit(8));
+        for i in 9..15 {
+            self.set_function(14 - i, 8, bit(i));
+        }
+
+        // Second copy, split between the other finder patterns
+        for i in 0..8 {
+            self.set_function(size - 1 - i, 8, bit(i));
+        }
+        for i in 8..15 {
+            self.set_function(8, size - 15 + i, bit(i));
+        }
+        // Always dark module
+        self.set_function(8, size - 8, true);
+    }
+
+    fn draw_codewords(&mut self, data: &[u8]) {
+        let size = self.size as i32;
+        let mut i = 0;
+        let mut right = size - 1;
+        while right >= 1 {
+            if right == 6 {
+                right = 5;
+            }
+            for vertical in 0..size {
+                for j in 0..2 {
+                    let x = (right - j) as usize;
+                    let upward = (right + 1) & 2 == 0;
+                    let y = if upward {
+                        size - 1 - vertical
+                    } else {
+                        vertical
+                    } as usize;
+                    if !self.is_function[y * self.size + x] && i < data.len() * 8 {
+                        self.modules[y * self.size + x] =
+                            (data[i >> 3] >> (7 - (i & 7))) & 1 != 0;
+                        i += 1;
+                    }
+                }
+            }
+            right -= 2;
+        }
+    }
+
+    fn apply_mask(&mut self, mask: u32) {
+        for y in 0..self.size {
+            for x in 0..self.size {
+                let invert = match mask {
+                    0 => (x + y) % 2 == 0,
+                    1 => y % 2 == 0,
+                    2 => x % 3 == 0,
+                    3 => (x + y) % 3 == 0,
+                    4 => (x / 3 + y / 2) % 2 == 0,
+                    5 => x * y % 2 + x * y % 3 == 0,
+                    6 => (x * y % 2 + x * y % 3) % 2 == 0,
+                    _ => ((x + y) % 2 + x * y % 3) % 2 == 0,
+                };
+                let index = y * self.size + x;
+                if invert && !self.is_function[index] {
+                    self.modules[index] = !self.modules[index];
+                }
+            }
+        }
+    }
+
+    fn penalty_score(&self) -> usize {
+        let size = self.size;
+        let mut penalty = 0;
+        let finder_like: [[bool; 11]; 2] = [
+            [
+                true, false, true, true, true, false, true, false, false, false, false,
+            ],
+            [
+                false, false, false, false, true, false, true, true, true, false, true,
+            ],
+        ];
+
+        for horizontal in [true, false] {
+            for a in 0..size {
+                let module = |b: usize| {
+                    if horizontal {
+                        self.is_dark(b, a)
+                    } else {
+                        self.is_dark(a, b)
+                    }
+                };
+
+                // Runs of five or more modules of the same color
+                let mut run = 1;
+                for b in 1..size {
+                    if module(b) == module(b - 1) {
+                        run += 1;
+                        if run == 5 {
+                            penalty += 3;
+                        } else if run > 5 {
+                            penalty += 1;
+                        }
+                    } else {
+                        run = 1;
+                    }
+                }
+
+                // Patterns that look like finder patterns
+                for b in 0..size.saturating_sub(10) {
+                    for pattern in &finder_like {
+                        if (0..11).all(|k| module(b + k) == pattern[k]) {
+                            penalty += 40;
+                        }
+                    }
+                }
+            }
+        }
+
+        // Blocks of 2x2 modules of the same color
+        for y in 0..size - 1 {
+            for x in 0..size - 1 {
+                let color = self.is_dark(x, y);
+                if color == self.is_dark(x + 1, y)
+                    && color == self.is_dark(x, y + 1)
+                    && color == self.is_dark(x + 1, y + 1)
+                {
+                    penalty += 3;
+                }
+            }
+        }
+
+        // Balance of dark and light modules
+        let dark = self.modules.iter().filter(|m| **m).count();
+        let total = size * size;
+        let k = (dark * 20)
+            .abs_diff(total * 10)
+            .div_ceil(total)
+            .saturating_sub(1);
+        penalty + k * 10
+    }
+}
+
+#[derive(Default)]
+struct BitBuffer(Vec<bool>);
+
+impl BitBuffer {
+    fn append(&mut self, value: u32, len: usize) {
+        for i in (0..len).rev() {
+            self.0.push((value >> i) & 1 != 0);
+        }
+    }
+}
+
+fn alignment_positions(version: usize) -> Vec<usize> {
+    match version {
+        1 => vec![],
+        2 => vec![6, 18],
+        3 => vec![6, 22],
+        4 => vec![6, 26],
+        5 => vec![6, 30],
+        6 => vec![6, 34],
+        7 => vec![6, 22, 38],
+        8 => vec![6, 24, 42],
+        9 => vec![6, 26, 46],
+        _ => vec![6, 28, 50],
+    }
+}
+
+fn add_ecc_and_interleave(
+    data: &[u8],
+    total: usize,
+    ecc_len: usize,
+    blocks: usize,
+) -> Vec<u8> {
+    let short_blocks = blocks - total % blocks;
+    let short_block_len = total / blocks;
+    let divisor = reed_solomon_divisor(ecc_len);
+
+    let mut all_blocks = Vec::with_capacity(blocks);
+    let mut offset = 0;
+    for i in 0..blocks {
+        let len = short_block_len - ecc_len + usize::from(i >= short_blocks);
+        let mut block = data[offset..offset + len].to_vec();
+        offset += len;
+        let ecc = reed_solomon_remainder(&block, &divisor);
+        if i < short_blocks {
+            block.push(0);
+        }
+        block.extend_from_slice(&ecc);
+        all_blocks.push(block);
+    }
+
+    let mut result = Vec::with_capacity(total);
+    for i in 0..all_blocks[0].len() {
+        for (j, block) in all_blocks.iter().enumerate() {
+            // Skip the padding byte of short blocks
+            if i != short_block_len - ecc_len || j >= short_blocks {
+                result.push(block[i]);
+            }
+        }
+    }
+
+    result
+}
+
+fn reed_solomon_divisor(degree: usize) -> Vec<u8> {
+    let mut result = vec![0u8; degree];
+    result[degree - 1] = 1;
+    let mut root = 1u8;
+    for _ in 0..degree {
+        for j in 0..degree {
+            result[j] = gf_multiply(result[j], root);
+            if j + 1 < degree {
+                result[j] ^= result[j + 1];
+            }
+        }
+        root = gf_multiply(root, 0x02);
+    }
+    result
+}
+
+fn reed_solomon_remainder(data: &[u8], divisor: &[u8]) -> Vec<u8> {
+    let mut result = vec![0u8; divisor.len()];
+    for byte in data {
+        let factor = byte ^ result.remove(0);
+        result.push(0);
+        for (x, y) in result.iter_mut().zip(divisor) {
+            *x ^= gf_multiply(*y, factor);
+        }
+    }
+    result
+}
+
+// Multiplication in GF(2^8) modulo x^8 + x^4 + x^3 + x^2 + 1
+fn gf_multiply(x: u8, y: u8) -> u8 {
+    let mut z: u8 = 0;
+    for i in (0..8).rev() {
+        z = (z << 1) ^ ((z >> 7) * 0x1D);
+        z ^= ((y >> i) & 1) * x;
+    }
+    z
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_smallest_version_is_used() {
+        assert_eq!(QrCode::encode(b"rio").unwrap().size(), 21);
+        // 14 bytes is the capacity of version 1-M
+        assert_eq!(QrCode::encode(&[b'a'; 14]).unwrap().size(), 21);
+        assert_eq!(QrCode::encode(&[b'a'; 15]).unwrap().size(), 25);
+        assert_eq!(QrCode::encode(&[b'a'; 213]).unwrap().size(), 57);
+        assert!(QrCode::encode(&[b'a'; 214]).is_none());
+    }
+
+    #[test]
+    fn test_function_patterns() {
+        let qr = QrCode::encode(b"https://rioterm.com").unwrap();
+        let size = qr.size();
+
+        // Finder pattern corners and separators
+        assert!(qr.is_dark(0, 0));
+        assert!(qr.is_dark(size - 1, 0));
+        assert!(qr.is_dark(0, size - 1));
+        assert!(!qr.is_dark(7, 0));
+        assert!(!qr.is_dark(1, 1));
+        // Dark module
+        assert!(qr.is_dark(8, size - 8));
+        // Timing pattern
+        assert!(qr.is_dark(8, 6));
+        assert!(!qr.is_dark(9, 6));
+    }
+
+    #[test]
+    fn test_reed_solomon() {
+        // Example from the QR code specification (version 1-M "01234567")
+        let data = [
+            0x10, 0x20, 0x0C, 0x56, 0x61, 0x80, 0xEC, 0x11, 0xEC, 0x11, 0xEC, 0x11, 0xEC,
+            0x11, 0xEC, 0x11,
+        ];
+        let ecc = reed_solomon_remainder(&data, &reed_solomon_divisor(10));
+        assert_eq!(
+            ecc,
+            [0xA5, 0x24, 0xD4, 0xC1, 0xED, 0x36, 0xC7, 0x87, 0x2C, 0x55]
+        );
+    }
+}
@@ -0,0 +1,293 @@
+// This module exposes the rio terminfo entry (misc/rio.terminfo) to the
+// terminal itself. It's used to answer XTGETTCAP (`DCS + q Pt ST`) queries
+// with the same values programs would read from the terminfo database, and
+// to install the entry with `rio --install-terminfo`.
+
+/// Source of the `rio` terminfo entry.
+pub const RIO_TERMINFO: &str = include_str!("../../../misc/rio.terminfo");
+
+/// Name of the terminfo entry.
+pub const RIO_TERMINFO_NAME: &str = "rio";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Capability {
+    Boolean,
+    Number(u32),
+    String(Vec<u8>),
+}
+
+/// Look up a capability of the `rio` entry, following `use=` entries.
+///
+/// Besides terminfo names, a few extra names queried by programs through
+/// XTGETTCAP are also supported (`TN`, `Co` and `RGB`).
+pub fn lookup(name: &str) -> Option<Capability> {
+    match name {
+        "TN" | "name" => {
+            return Some(Capability::String(RIO_TERMINFO_NAME.as_bytes().to_vec()))
+        }
+        "Co" => return lookup("colors"),
+        "RGB" => return Some(Capability::String(b"8/8/8".to_vec())),
+        _ => {}
+    }
+
+    lookup_in_entry(RIO_TERMINFO_NAME, name, 0)
+}
+
+fn lookup_in_entry(entry_name: &str, name: &str, depth: u8) -> Option<Capability> {
+    // Guard against `use=` cycles.
+    if depth > 4 {
+        return None;
+    }
+
+    let fields = entry_fields(entry_name)?;
+    let mut uses = Vec::new();
+    for field in &fields {
+        if let Some(inherited) = field.strip_prefix("use=") {
+            uses.push(inherited.to_string());
+            continue;
+        }
+
+        if let Some((capability, value)) = field.split_once('=') {
+            if capability == name {
+                return Some(Capability::String(unescape(value)));
+            }
+        } else if let Some(capability) = field.strip_suffix('@') {
+            if capability == name {
+                return None;
+            }
+        } else if let Some((capability, value)) = field.split_once('#') {
+            if capability == name {
+                let number = match value.strip_prefix("0x") {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                    None => value.parse().ok()?,
+                };
+                return Some(Capability::Number(number));
+            }
+        } else if field == name {
+            return Some(Capability::Boolean);
+        }
+    }
+
+    uses.iter()
+        .find_map(|inherited| lookup_in_entry(inherited, name, depth + 1))
+}
+
+/// Fields of an entry, with continuation lines joined and the
+/// entry names removed.
+fn entry_fields(entry_name: &str) -> Option<Vec<String>> {
+    let mut entry = String::new();
+    let mut found = false;
+
+    for line in RIO_TERMINFO.lines() {
+        if line.trim_start().starts_with('#') || line.trim().is_empty() {
+            continue;
+        }
+
+        let is_continuation = line.starts_with(char::is_whitespace);
+        if !is_continuation {
+            if found {
+                break;
+            }
+            let names = line.split(',').next().unwrap_or_default();
+            found = names.split('|').any(|name| name == entry_name);
+        }
+
+        if found {
+            entry.push_str(line.trim());
+        }
+    }
+
+    if !found {
+        return None;
+    }
+
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut chars = entry.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                field.push(c);
+                if let Some(next) = chars.next() {
+                    field.push(next);
+                }
+            }
+            ',' => {
+                fields.push(field.trim().to_string());
+                field.clear();
+            }
+            _ => field.push(c),
+        }
+    }
+
+    // Skip the entry names.
+    Some(
+        fields
+            .into_iter()
+            .skip(1)
+            .filter(|f| !f.is_empty())
+            .collect(),
+    )
+}
+
+/// Decode terminfo string escapes into the bytes sent to the terminal.
+fn unescape(value: &str) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut iter = value.bytes().peekable();
+
+    while let Some(byte) = iter.next() {
+        match byte {
+            b'\\' => match iter.next() {
+                Some(b'E') | Some(b'e') => bytes.push(0x1b),
+                Some(b'n') | Some(b'l') => bytes.push(b'\n'),
+                Some(b'r') => bytes.push(b'\r'),
+                Some(b't') => bytes.push(b'\t'),
+                Some(b'b') => bytes.push(0x08),
+                Some(b'f') => bytes.push(0x0c),
+                Some(b's') => bytes.push(b' '),
+                Some(digit @ b'0'..=b'7') => {
+                    let mut octal = (digit - b'0') as u32;
+                    for _ in 0..2 {
+                        match iter.peek() {
+                            Some(next @ b'0'..=b'7') => {
+                                octal = octal * 8 + (next - b'0') as u32;
+                                iter.next();
+                            }
+                            _ => break,
+                        }
+                    }
+                    // \0 is the terminfo representation of NUL
+                    bytes.push(if octal == 0 { 0x80 } else { octal as u8 });
+                }
+                Some(other) => bytes.push(other),
+                None => bytes.push(b'\\'),
+            },
+            b'^' => match iter.next() {
+                Some(b'?') => bytes.push(0x7f),
+                Some(other) => bytes.push(other & 0x1f),
+                None => bytes.push(b'^'),
+            },
+            _ => bytes.push(byte),
+        }
+    }
+
+    bytes
+}
+
+/// Copy of the source the entry in `~/.terminfo` was compiled from, it
+/// tells whether that entry is current.
+#[cfg(unix)]
+const INSTALLED_SOURCE: &str = ".rio.terminfo";
+
+/// Whether the entry in `~/.terminfo` was compiled from `source`.
+#[cfg(unix)]
+fn is_current(terminfo_dir: &std::path::Path, source: &str) -> bool {
+    let compiled = ["r", "72"]
+        .iter()
+        .any(|dir| terminfo_dir.join(dir).join(RIO_TERMINFO_NAME).exists());
+    compiled
+        && std::fs::read_to_string(terminfo_dir.join(INSTALLED_SOURCE))
+            .is_ok_and(|installed| installed == source)
+}
+
+/// Whether an entry installed by an older Rio has to be compiled again.
+#[cfg(unix)]
+pub fn is_outdated() -> bool {
+    let Some(home) = dirs::home_dir() else {
+        return false;
+    };
+    let terminfo_dir = home.join(".terminfo");
+    terminfo_dir.join(INSTALLED_SOURCE).exists()
+        && !is_current(&terminfo_dir, RIO_TERMINFO)
+}
+
+/// Compile and install the `rio` entry into `~/.terminfo` using `tic`,
+/// unless it's already there and current.
+#[cfg(unix)]
+pub fn install() -> Result<(), String> {
+    let home = dirs::home_dir().ok_or("unable to find home directory")?;
+    let terminfo_dir = home.join(".terminfo");
+    if is_current(&terminfo_dir, RIO_TERMINFO) {
+        return Ok(());
+    }
+
+    std::fs::create_dir_all(&terminfo_dir).map_err(|e| e.to_string())?;
+    let source = terminfo_dir.join(INSTALLED_SOURCE);
+    std::fs::write(&source, RIO_TERMINFO).map_err(|e| e.to_string())?;
+
+    let output = std::process::Command::new("tic")
+        .arg("-x")
+        .arg("-o")
+        .arg(&terminfo_dir)
+        .arg(&source)
+        .output();
+
+    match output {
+        Ok(output) if output.status.success() => Ok(()),
+        Ok(output) => {
+            // Compiled again on the next start.
+            let _ = std::fs::remove_file(&source);
+            Err(String::from_utf8_lossy(&output.stderr).into_owned())
+        }
+        Err(err) => {
+            let _ = std::fs::remove_file(&source);
+            Err(err.to_string())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup_string_capability() {
+        assert_eq!(
+            lookup("smxx"),
+            Some(Capability::String(b"\x1b[9m".to_vec()))
+        );
+        assert_eq!(
+            lookup("Sync"),
+            Some(Capability::String(b"\x1bP=%p1%ds\x1b\\".to_vec()))
+        );
+        assert_eq!(lookup("kbs"), Some(Capability::String(vec![0x7f])));
+        assert_eq!(
+            lookup("ich"),
+            Some(Capability::String(b"\x1b[%p1%d@".to_vec()))
+        );
+    }
+
+    #[test]
+    fn test_lookup_overridden_capability() {
+        // `rio` overrides `colors#8` from `rio+base`
+        assert_eq!(lookup("colors"), Some(Capability::Number(256)));
+        assert_eq!(lookup("Co"), Some(Capability::Number(256)));
+        // `setb@` cancels the inherited capability
+        assert_eq!(lookup("setb"), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_is_current() {
+        let dir = std::env::temp_dir()
+            .join(format!("rio-terminfo-test-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("r")).unwrap();
+        assert!(!is_current(&dir, RIO_TERMINFO));
+
+        std::fs::write(dir.join("r").join(RIO_TERMINFO_NAME), "").unwrap();
+        std::fs::write(dir.join(INSTALLED_SOURCE), "rio|older entry,").unwrap();
+        assert!(!is_current(&dir, RIO_TERMINFO));
+
+        std::fs::write(dir.join(INSTALLED_SOURCE), RIO_TERMINFO).unwrap();
+        assert!(is_current(&dir, RIO_TERMINFO));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_lookup_boolean_and_extra_capabilities() {
+        assert_eq!(lookup("am"), Some(Capability::Boolean));
+        assert_eq!(lookup("TN"), Some(Capability::String(b"rio".to_vec())));
+        assert_eq!(lookup("RGB"), Some(Capability::String(b"8/8/8".to_vec())));
+        assert_eq!(lookup("not-a-capability"), None);
+    }
+}
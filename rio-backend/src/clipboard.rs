@@ -22,6 +22,8 @@ use copypasta::ClipboardProvider;
 pub struct Clipboard {
     clipboard: Box<dyn ClipboardProvider>,
     selection: Option<Box<dyn ClipboardProvider>>,
+    // Last text stored in each clipboard, see `clear_copied`
+    copied: [Option<String>; 2],
 }
 
 impl Clipboard {
@@ -37,6 +39,7 @@ impl Clipboard {
                 Self {
                     clipboard: Box::new(clipboard),
                     selection: Some(Box::new(selection)),
+                    copied: Default::default(),
                 }
             }
             _ => Self::default(),
@@ -49,6 +52,7 @@ impl Clipboard {
         Self {
             clipboard: Box::new(NopClipboardContext::new().unwrap()),
             selection: None,
+            copied: Default::default(),
         }
     }
 }
@@ -59,6 +63,7 @@ impl Default for Clipboard {
         return Self {
             clipboard: Box::new(ClipboardContext::new().unwrap()),
             selection: None,
+            copied: Default::default(),
         };
 
         #[cfg(all(feature = "x11", not(any(target_os = "macos", windows))))]
@@ -67,6 +72,7 @@ impl Default for Clipboard {
             selection: Some(Box::new(
                 X11ClipboardContext::<X11SelectionClipboard>::new().unwrap(),
             )),
+            copied: Default::default(),
         };
 
         #[cfg(not(any(feature = "x11", target_os = "macos", windows)))]
@@ -82,9 +88,31 @@ impl Clipboard {
             _ => &mut self.clipboard,
         };
 
-        clipboard.set_contents(text.into()).unwrap_or_else(|err| {
+        let text = text.into();
+        clipboard.set_contents(text.clone()).unwrap_or_else(|err| {
             warn!("Unable to store text in clipboard: {}", err);
         });
+        self.copied[ty as usize] = Some(text);
+    }
+
+    /// Clears the text stored by `set`, unless something else was copied
+    /// since then.
+    pub fn clear_copied(&mut self) {
+        for ty in [ClipboardType::Clipboard, ClipboardType::Selection] {
+            let Some(text) = self.copied[ty as usize].take() else {
+                continue;
+            };
+
+            if self.get(ty) == text {
+                let clipboard = match (ty, &mut self.selection) {
+                    (ClipboardType::Selection, Some(provider)) => provider,
+                    _ => &mut self.clipboard,
+                };
+                clipboard.set_contents(String::new()).unwrap_or_else(|err| {
+                    warn!("Unable to clear clipboard: {}", err);
+                });
+            }
+        }
     }
 
     pub fn get(&mut self, ty: ClipboardType) -> String {
@@ -0,0 +1,95 @@
+use serde::{Deserialize, Serialize};
+
+#[inline]
+fn default_visual_duration() -> u64 {
+    150
+}
+
+#[inline]
+fn default_urgent() -> bool {
+    true
+}
+
+#[derive(Default, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BellSound {
+    #[default]
+    #[serde(alias = "none")]
+    None,
+    // Sound of the desktop environment
+    #[serde(alias = "system")]
+    System,
+    // Plays `sound-file`
+    #[serde(alias = "file")]
+    File,
+}
+
+// What to do instead of the sound when no audio output is active, e.g. muted
+// or without any output device
+#[derive(Default, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BellFallback {
+    #[serde(alias = "none")]
+    None,
+    #[default]
+    #[serde(alias = "visual")]
+    Visual,
+    #[serde(alias = "notification")]
+    Notification,
+}
+
+#[derive(PartialEq, Serialize, Deserialize, Clone, Debug)]
+pub struct Bell {
+    #[serde(default = "BellSound::default")]
+    pub sound: BellSound,
+    #[serde(default = "Option::default", rename = "sound-file")]
+    pub sound_file: Option<String>,
+    #[serde(default = "BellFallback::default")]
+    pub fallback: BellFallback,
+    // Flash the terminal background
+    #[serde(default = "bool::default")]
+    pub visual: bool,
+    // Duration of the flash in milliseconds
+    #[serde(default = "default_visual_duration", rename = "visual-duration")]
+    pub visual_duration: u64,
+    // Mark the window as urgent when it isn't focused
+    #[serde(default = "default_urgent")]
+    pub urgent: bool,
+}
+
+impl Default for Bell {
+    fn default() -> Bell {
+        Bell {
+            sound: BellSound::default(),
+            sound_file: None,
+            fallback: BellFallback::default(),
+            visual: false,
+            visual_duration: default_visual_duration(),
+            urgent: default_urgent(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, Deserialize, PartialEq)]
+    struct Root {
+        #[serde(default = "Bell::default")]
+        bell: Bell,
+    }
+
+    #[test]
+    fn test_bell_fallback() {
+        let decoded = toml::from_str::<Root>("").unwrap();
+        assert_eq!(decoded.bell.fallback, BellFallback::Visual);
+
+        let content = r#"
+            [bell]
+            sound = 'system'
+            fallback = 'notification'
+        "#;
+        let decoded = toml::from_str::<Root>(content).unwrap();
+        assert_eq!(decoded.bell.sound, BellSound::System);
+        assert_eq!(decoded.bell.fallback, BellFallback::Notification);
+    }
+}
@@ -0,0 +1,40 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Default, PartialEq, Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct Clipboard {
+    // Text copied from Rio is cleared after this many seconds, unless
+    // something else was copied since
+    #[serde(default = "Option::default", rename = "clear-after")]
+    pub clear_after: Option<u64>,
+    // Text copied from Rio is cleared when the screen gets locked
+    #[serde(default = "bool::default", rename = "clear-on-lock")]
+    pub clear_on_lock: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, Deserialize, PartialEq)]
+    struct Root {
+        #[serde(default = "Clipboard::default")]
+        clipboard: Clipboard,
+    }
+
+    #[test]
+    fn test_clipboard() {
+        let decoded = toml::from_str::<Root>("").unwrap();
+        assert_eq!(decoded.clipboard.clear_after, None);
+        assert!(!decoded.clipboard.clear_on_lock);
+
+        let content = r#"
+            [clipboard]
+            clear-after = 30
+            clear-on-lock = true
+        "#;
+
+        let decoded = toml::from_str::<Root>(content).unwrap();
+        assert_eq!(decoded.clipboard.clear_after, Some(30));
+        assert!(decoded.clipboard.clear_on_lock);
+    }
+}
@@ -633,6 +633,15 @@ where
     }
 }
 
+pub fn deserialize_to_optional_arr<'de, D>(
+    deserializer: D,
+) -> Result<Option<ColorArray>, D::Error>
+where
+    D: de::Deserializer<'de>,
+{
+    deserialize_to_arr(deserializer).map(Some)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,40 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Default, PartialEq, Serialize, Deserialize, Clone, Debug)]
+pub struct CommandNotFound {
+    // Helper run with the missing program appended to `args` when a command
+    // exits with 127, e.g. `pkgfile` or `command-not-found`
+    #[serde(default = "Option::default")]
+    pub program: Option<String>,
+    #[serde(default = "Vec::default")]
+    pub args: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, Deserialize, PartialEq)]
+    struct Root {
+        #[serde(default = "CommandNotFound::default", rename = "command-not-found")]
+        command_not_found: CommandNotFound,
+    }
+
+    #[test]
+    fn test_command_not_found() {
+        let decoded = toml::from_str::<Root>("").unwrap();
+        assert_eq!(decoded.command_not_found.program, None);
+
+        let content = r#"
+            [command-not-found]
+            program = "pkgfile"
+            args = ["--binaries"]
+        "#;
+        let decoded = toml::from_str::<Root>(content).unwrap();
+        assert_eq!(
+            decoded.command_not_found.program.as_deref(),
+            Some("pkgfile")
+        );
+        assert_eq!(decoded.command_not_found.args, ["--binaries"]);
+    }
+}
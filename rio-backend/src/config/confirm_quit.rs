@@ -0,0 +1,53 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Default, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConfirmQuit {
+    // Quit right away
+    #[serde(alias = "never")]
+    Never,
+    // Confirm while programs started from the shells are still running
+    #[default]
+    #[serde(alias = "processes")]
+    Processes,
+    // Always confirm
+    #[serde(alias = "always")]
+    Always,
+}
+
+impl ConfirmQuit {
+    /// Whether quitting with `running` processes needs a confirmation.
+    #[inline]
+    pub fn should_confirm(&self, running: &[String]) -> bool {
+        match self {
+            ConfirmQuit::Never => false,
+            ConfirmQuit::Processes => !running.is_empty(),
+            ConfirmQuit::Always => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, Deserialize, PartialEq)]
+    struct Root {
+        #[serde(default = "ConfirmQuit::default", rename = "confirm-quit")]
+        confirm_quit: ConfirmQuit,
+    }
+
+    #[test]
+    fn test_confirm_quit() {
+        let decoded = toml::from_str::<Root>("").unwrap();
+        assert_eq!(decoded.confirm_quit, ConfirmQuit::Processes);
+
+        let decoded = toml::from_str::<Root>("confirm-quit = 'always'").unwrap();
+        assert_eq!(decoded.confirm_quit, ConfirmQuit::Always);
+
+        let running = vec![String::from("vim")];
+        assert!(ConfirmQuit::Processes.should_confirm(&running));
+        assert!(!ConfirmQuit::Processes.should_confirm(&[]));
+        assert!(ConfirmQuit::Always.should_confirm(&[]));
+        assert!(!ConfirmQuit::Never.should_confirm(&running));
+    }
+}
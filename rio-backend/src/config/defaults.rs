@@ -1,4 +1,7 @@
-use crate::{ansi::CursorShape, config::Shell};
+use crate::{
+    ansi::CursorShape,
+    config::{CursorSize, Shell},
+};
 
 #[inline]
 pub fn default_bool_true() -> bool {
@@ -15,6 +18,16 @@ pub fn default_cursor_interval() -> u64 {
     800
 }
 
+#[inline]
+pub fn default_cursor_animation_duration() -> u64 {
+    80
+}
+
+#[inline]
+pub fn default_text_blinking_interval() -> u64 {
+    500
+}
+
 #[inline]
 pub fn default_padding_y() -> [f32; 2] {
     [0., 0.]
@@ -26,7 +39,9 @@ pub fn default_shell() -> crate::config::Shell {
     {
         crate::config::Shell {
             program: String::from(""),
-            args: vec![String::from("--login")],
+            args: vec![],
+            env: vec![],
+            login: None,
         }
     }
 
@@ -35,6 +50,8 @@ pub fn default_shell() -> crate::config::Shell {
         crate::config::Shell {
             program: String::from("powershell"),
             args: vec![],
+            env: vec![],
+            login: None,
         }
     }
 }
@@ -77,6 +94,11 @@ pub fn default_cursor() -> CursorShape {
     CursorShape::default()
 }
 
+#[inline]
+pub fn default_cursor_thickness() -> CursorSize {
+    CursorSize::Pixels(3.0)
+}
+
 #[inline]
 pub fn default_theme() -> String {
     String::from("")
@@ -89,6 +111,8 @@ pub fn default_editor() -> Shell {
         Shell {
             program: String::from("vi"),
             args: vec![],
+            env: vec![],
+            login: None,
         }
     }
 
@@ -97,6 +121,8 @@ pub fn default_editor() -> Shell {
         Shell {
             program: String::from("notepad"),
             args: vec![],
+            env: vec![],
+            login: None,
         }
     }
 }
@@ -132,6 +158,22 @@ pub fn default_config_file_content() -> String {
 #
 # hide-cursor-when-typing = false
 
+# Click to move cursor
+#
+# Clicking within the command input of the prompt moves the cursor there
+# by sending arrow keys. Requires shell integration (OSC 133).
+# Default is `false`
+#
+# click-to-move-cursor = true
+
+# Check for updates
+#
+# Fetches the latest release at startup with `curl` and shows a toast
+# with its changelog when it is newer, nothing is installed.
+# Default is `false`
+#
+# check-for-updates = true
+
 # Ignore theme selection foreground color
 #
 # Default is false
@@ -148,6 +190,209 @@ pub fn default_config_file_content() -> String {
 # Example:
 # theme = "dracula"
 
+# Theme file
+#
+# Reads colors from a file generated by tools like pywal or matugen,
+# it's reloaded whenever the file changes and takes precedence over theme.
+# Files with .json extension are read as pywal colors.json
+#
+# Example:
+# theme-file = "~/.cache/wal/colors.json"
+
+# Title
+#
+# Template of tab and window titles, by default tabs show the program
+# and the window shows the title set by the program.
+# {program} - Name of the foreground process, e.g: cargo
+# {command} - Command line of the foreground process, e.g: ssh host
+# {path} - Working directory of the foreground process
+# {title} - Title set by the program, or the command when there is none
+# {user_var:NAME} - Value set with OSC 1337 SetUserVar
+# {session} - Nested session, e.g: ssh: host, docker: name or tmux
+#
+# Example:
+# [title]
+# content = "{command} in {path}"
+
+# Clipboard
+#
+# Clear text copied from Rio, for example secrets, unless something else
+# was copied since then.
+#
+# • clear-after - Clear it after this many seconds (default is disabled)
+#
+# • clear-on-lock - Clear it when the screen gets locked (default is false)
+#
+# Example:
+# [clipboard]
+# clear-after = 30
+# clear-on-lock = true
+
+# Print
+#
+# The Print action sends the selection, or the scrollback when nothing is
+# selected, to the print service as a PDF. Without a print service the
+# PDF is saved in the downloads directory.
+#
+# • colors - Keep the foreground colors of the text (default is false)
+#
+# Example:
+# [print]
+# colors = true
+
+# Profiles
+#
+# Switch the theme and the window title while the foreground process of the
+# current tab matches "program" and/or its directory matches "path". "path"
+# also matches subdirectories, supports a leading ~/ and * wildcards.
+# "title" supports the same placeholders as title.content. The first matching
+# profile is used.
+#
+# Example:
+# [[profiles]]
+# program = "ssh"
+# theme = "red-alert"
+# title = "SSH: {title}"
+#
+# [[profiles]]
+# path = "~/work/*-prod"
+# theme = "red-alert"
+
+# Night mode
+#
+# Shifts every color toward warmer tones between the configured
+# hours (local time), it works on top of any theme.
+#
+# • enabled - Enable the schedule (default is false)
+#
+# • start and end - Hours in "HH:MM" format, can wrap around midnight
+#
+# • warmth - How much blue light is removed, from 0.0 to 1.0 (default is 0.5)
+#
+# • dim - How much brightness is reduced, from 0.0 to 1.0 (default is 0.0)
+#
+# Example:
+# [night-mode]
+# enabled = true
+# start = "20:00"
+# end = "07:00"
+# warmth = 0.5
+# dim = 0.1
+
+# Dim inactive
+#
+# Dims the text and background of windows that aren't focused, so the
+# active one stands out.
+#
+# • brightness - From 0.0 (black) to 1.0 (unchanged, default)
+#
+# • saturation - From 0.0 (grayscale) to 1.0 (unchanged, default)
+#
+# Example:
+# [dim-inactive]
+# brightness = 0.7
+# saturation = 0.8
+
+# Predictive echo
+#
+# Renders typed characters locally (underlined) before the remote
+# echo arrives, useful for high-latency connections like ssh.
+# Predictions are reconciled with the real echo and only shown once
+# the program is confirmed to echo input (so passwords are never shown).
+#
+# • mode - "never" (default), "always" or "auto"
+#   auto enables predictions once the round-trip latency exceeds
+#   the threshold
+#
+# • latency-threshold - Latency in milliseconds (default is 100)
+#
+# • show-latency - Show the measured echo latency of the current tab
+#   in the bottom right corner (default is false)
+#
+# Example:
+# [predictive-echo]
+# mode = "auto"
+# latency-threshold = 100
+# show-latency = false
+
+# Serial
+#
+# Defaults for `rio -e serial:<path>[:<baud>[:<flow-control>]]`,
+# which attaches the tab to a serial device instead of a shell.
+# Settings in the command take precedence.
+#
+# • baud-rate - Baud rate (default is 115200)
+#
+# • flow-control - "none" (default), "software" (XON/XOFF)
+#   or "hardware" (RTS/CTS)
+#
+# Example:
+# [serial]
+# baud-rate = 115200
+# flow-control = "none"
+
+# Bell
+#
+# Response to the BEL character.
+#
+# • sound - "none" (default), "system" to play the sound of the
+#   desktop environment or "file" to play `sound-file`
+#
+# • sound-file - Path of the sound played with sound = "file"
+#
+# • fallback - Used instead of the sound when no audio output is active,
+#   e.g. muted: "visual" (default) flashes the terminal, "notification"
+#   sends a desktop notification and "none" does nothing
+#
+# • visual - Flash the terminal background (default is false)
+#
+# • visual-duration - Duration of the flash in milliseconds (default is 150)
+#
+# • urgent - Mark the window as urgent when it isn't focused (default is true)
+#
+# Example:
+# [bell]
+# sound = "none"
+# fallback = "visual"
+# visual = false
+# visual-duration = 150
+# urgent = true
+
+# Notifications
+#
+# • command-duration-threshold - Send a desktop notification when a command
+#   that ran longer than this many seconds finishes while its tab isn't
+#   focused. Requires shell integration (OSC 133), disabled by default.
+#
+# Example:
+# [notifications]
+# command-duration-threshold = 10
+
+# Command not found
+#
+# • program - Helper run when a command exits with 127, the missing program
+#   is appended to its arguments and the output is shown as a toast.
+#   Requires shell integration (OSC 133), disabled by default.
+# • args - Arguments passed to the helper before the missing program.
+#
+# Example:
+# [command-not-found]
+# program = "pkgfile"
+# args = ["--binaries"]
+
+# OSC hooks
+#
+# • range - First and last code of OSC sequences forwarded to `program`
+#   instead of dropped, disabled by default
+# • program - Hook run with the code appended to `args` and the payload
+#   of the sequence on stdin
+# • args - Arguments passed to the hook before the code
+#
+# Example:
+# [osc-hooks]
+# range = [7700, 7799]
+# program = "/usr/local/bin/rio-osc-hook"
+
 # Padding-x
 #
 # define x axis padding (default is 0)
@@ -208,6 +453,29 @@ pub fn default_config_file_content() -> String {
 #
 # confirm-before-quit = false
 
+# Confirm quit
+#
+# Ask before quitting Rio or closing a window:
+# • "processes" - While programs started from the shells are still running (default)
+# • "always" - Every time
+# • "never" - Never
+#
+# confirm-quit = "always"
+
+# Hold
+#
+# Keep the terminal open after the shell or command exits,
+# showing its exit status. Press Enter to respawn it. Default is `false`.
+#
+# hold = true
+
+# Locale
+#
+# Sets LANG for the shells spawned by Rio. By default, on MacOS it's
+# derived from the system preferences when it's not in the environment.
+#
+# locale = "en_US.UTF-8"
+
 # Cursor
 #
 # shape - Default cursor shape is 'block'
@@ -217,10 +485,57 @@ pub fn default_config_file_content() -> String {
 #
 # blinking-interval - Cursor update on milliseconds interval
 #
+# beam-width - Width of the beam cursor in pixels, or in percent
+# of the cell width like '10%'. The default is 3
+#
+# underline-height - Height of the underline cursor in pixels, or in
+# percent of the cell height like '10%'. The default is 3
+#
+# outline-color - Draws an outline of this color around the cursor
+#
+# animation - How the cursor moves to a new cell, options are 'none',
+# 'smear' and 'fade'. The default is 'none'
+#
+# animation-duration - Duration of the animation in milliseconds.
+# The default is 80
+#
 # [cursor]
 # shape = 'block'
 # blinking = false
 # blinking-interval = 800
+# beam-width = 3
+# underline-height = 3
+# outline-color = '#000000'
+# animation = 'none'
+# animation-duration = 80
+
+# Control socket
+#
+# Streams terminal changes (line updates, cursor moves and bells) as
+# JSON lines on a unix socket, for assistive tools and status bars.
+# The path is exported to shells as RIO_CONTROL_SOCKET.
+#
+# • enabled - Whether the socket is created (default is false)
+#
+# • path - Socket path, by default rio-<pid>.sock inside of
+#   XDG_RUNTIME_DIR or the temporary directory
+#
+# Example:
+# [control-socket]
+# enabled = true
+
+# Text blinking
+#
+# Text with the blink attribute (SGR 5 and 6) toggles its visibility
+#
+# enabled - Whether blinking text blinks, otherwise it's always
+# visible. The default is true
+#
+# interval - Blinking update on milliseconds interval (default is 500)
+#
+# [text-blinking]
+# enabled = true
+# interval = 500
 
 # Editor
 #
@@ -274,8 +589,30 @@ pub fn default_config_file_content() -> String {
 #   - DX12: Supported on Windows 10
 #   - Metal: Supported on macOS/iOS
 #
+# • adapter: Part of the GPU name to use, e.g. "nvidia", requires a restart.
+#
 # • disable-unfocused-render: This property disable renderer processes while Rio is unfocused.
 #
+# • target-fps: Frames per second to draw, by default the refresh rate
+#   of the monitor the window is on.
+#
+# • max-fps: Upper limit of frames per second, it never raises the rate.
+#
+# • unfocused-fps: Frames per second while the window is unfocused,
+#   0 doesn't limit them (default 10).
+#
+# • hdr: Use an HDR surface on displays that support it, requires a restart.
+#
+# • present-mode: How frames are presented
+#   - auto-vsync: Waits for the vertical blank (default)
+#   - mailbox: Newest frame on the next vertical blank, no tearing
+#   - immediate: Lowest latency, can tear
+#
+# • text-gamma: Thickness of the text, higher values make it thicker (default 1.0).
+#
+# • text-contrast: Thickens text darker than its background, like dark
+#   text on light themes, from 0.0 (default) to 1.0.
+#
 # • level: Configure renderer level
 #   - Available options: 0 and 1.
 #       Higher the level more rendering features and computations
@@ -296,10 +633,13 @@ pub fn default_config_file_content() -> String {
 # disable-ctlseqs-alt - Disable ctlseqs with ALT keys
 #   - For example: Terminal.app does not deal with ctlseqs with ALT keys
 #
+# ime-indicator - Show whether the input method is active next to the cursor
+#
 # Example:
 # [keyboard]
 # use-kitty-keyboard-protocol = false
 # disable-ctlseqs-alt = false
+# ime-indicator = false
 
 # Fonts
 #
@@ -356,12 +696,32 @@ pub fn default_config_file_content() -> String {
 # If you want to reduce scroll speed you will need to increase the divider.
 # You can use both properties also to find the best scroll for you.
 #
+# Typing jumps to the bottom when scrolled up ("jump-to-bottom-on-input").
+# New output keeps the viewport where it is while scrolled up, unless
+# "jump-to-bottom-on-output" is enabled, and a "N new lines ↓" indicator
+# shows how many lines were printed below it ("new-lines-indicator").
+#
+# "minimap" shows a compressed view of the scrollback on the right edge,
+# clicking it jumps to that part of the scrollback.
+#
+# "scrollbar" fades in while scrolled into the scrollback, its thumb
+# can be dragged with the mouse. It's replaced by the minimap when enabled.
+#
+# "smooth-scroll-duration" animates the viewport for that many
+# milliseconds when scrolling, 0 (default) jumps right away.
+#
 # Multiplier default is 3.0.
 # Divider default is 1.0.
 # Example:
 # [scroll]
 # multiplier = 3.0
 # divider = 1.0
+# jump-to-bottom-on-input = true
+# jump-to-bottom-on-output = false
+# new-lines-indicator = true
+# minimap = false
+# scrollbar = true
+# smooth-scroll-duration = 0
 
 # Navigation
 #
@@ -374,8 +734,11 @@ pub fn default_config_file_content() -> String {
 #
 # "hide-if-single" - Hide navigation UI if is single.
 # "clickable" - Enable click on tabs to switch.
-# "use-current-path" - Use same path whenever a new tab is created (Note: requires `use-fork` to be set to false).
+# "use-current-path" - Use same path whenever a new tab or window is created,
+# taken from the foreground process or from the last path reported with OSC 7
+# (Note: requires `use-fork` to be set to false).
 # "color-automation" - Set a specific color for the tab whenever a specific program is running, or in a specific directory.
+# "process-usage" - Show the CPU and memory usage of the foreground process of each tab.
 #
 # Example:
 # [navigation]
@@ -384,22 +747,32 @@ pub fn default_config_file_content() -> String {
 # hide-if-single = true
 # use-current-path = false
 # color-automation = []
+# process-usage = false
 
 # Shell
 #
 # You can set `shell.program` to the path of your favorite shell, e.g. `/bin/fish`.
 # Entries in `shell.args` are passed unmodified as arguments to the shell.
+# Entries in `shell.env` are `KEY=value` pairs added to the shell environment.
+# `shell.login` starts the program as a login shell (Linux/BSD/macOS),
+# it defaults to true on macOS when `shell.program` is empty.
+#
+# When `shell.program` is empty Rio uses `$SHELL` and falls back to the
+# user shell from the passwd database. `TERM`, `TERM_PROGRAM` and
+# `COLORTERM` are always set by Rio.
 #
 # Default:
 #   - (macOS) user login shell
-#   - (Linux/BSD) user login shell
+#   - (Linux/BSD) user shell
 #   - (Windows) powershell
 #
 # Example 1 using fish shell from bin path:
 #
 # [shell]
 # program = "/bin/fish"
-# args = ["--login"]
+# args = []
+# env = ["EDITOR=nvim"]
+# login = true
 #
 # Example 2 for Windows using powershell
 #
@@ -449,6 +822,19 @@ pub fn default_config_file_content() -> String {
 #   { key = "home", with = "super | shift", bytes = [27, 91, 53, 126] }
 # ]
 
+# Security
+#
+# • allow-private-graphics - Allow programs to render QR codes and math
+#   expressions as inline images with OSC 1338 (default is false)
+# • allow-window-ops - Allow programs to minimize and restore the window
+#   with XTWINOPS (CSI 1 t and CSI 2 t), reports are always answered
+#   (default is false)
+#
+# Example:
+# [security]
+# allow-private-graphics = true
+# allow-window-ops = true
+
 # Log level
 #
 # This property enables log level filter and file. The default level is "OFF" and the logs are not logged to a file as default.
@@ -0,0 +1,80 @@
+use crate::config::colors::ColorArray;
+use serde::{Deserialize, Serialize};
+
+#[inline]
+fn default_dim_inactive_value() -> f32 {
+    1.0
+}
+
+#[derive(PartialEq, Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct DimInactive {
+    // Brightness of unfocused windows, from 0.0 (black) to 1.0 (unchanged)
+    #[serde(default = "default_dim_inactive_value")]
+    pub brightness: f32,
+    // Saturation of unfocused windows, from 0.0 (grayscale) to 1.0 (unchanged)
+    #[serde(default = "default_dim_inactive_value")]
+    pub saturation: f32,
+}
+
+impl Default for DimInactive {
+    fn default() -> DimInactive {
+        DimInactive {
+            brightness: default_dim_inactive_value(),
+            saturation: default_dim_inactive_value(),
+        }
+    }
+}
+
+impl DimInactive {
+    #[inline]
+    pub fn is_enabled(&self) -> bool {
+        self.brightness < 1.0 || self.saturation < 1.0
+    }
+
+    /// Desaturate and darken a color, alpha is kept.
+    #[inline]
+    pub fn apply(&self, color: ColorArray) -> ColorArray {
+        let brightness = self.brightness.clamp(0.0, 1.0);
+        let saturation = self.saturation.clamp(0.0, 1.0);
+        let luma = 0.2126 * color[0] + 0.7152 * color[1] + 0.0722 * color[2];
+        let adjust = |c: f32| (luma + (c - luma) * saturation) * brightness;
+        [
+            adjust(color[0]),
+            adjust(color[1]),
+            adjust(color[2]),
+            color[3],
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disabled_by_default() {
+        let dim_inactive = DimInactive::default();
+        assert!(!dim_inactive.is_enabled());
+        assert_eq!(
+            dim_inactive.apply([0.2, 0.4, 0.6, 0.5]),
+            [0.2, 0.4, 0.6, 0.5]
+        );
+    }
+
+    #[test]
+    fn test_apply() {
+        let dim_inactive = DimInactive {
+            brightness: 0.5,
+            saturation: 0.0,
+        };
+        assert!(dim_inactive.is_enabled());
+        assert_eq!(
+            dim_inactive.apply([1.0, 1.0, 1.0, 1.0]),
+            [0.5, 0.5, 0.5, 1.0]
+        );
+        // Without saturation every channel gets the luma
+        let [r, g, b, a] = dim_inactive.apply([1.0, 0.0, 0.0, 0.8]);
+        assert!(r == g && g == b);
+        assert_eq!(a, 0.8);
+    }
+}
@@ -14,6 +14,9 @@ pub struct Keyboard {
         rename = "disable-ctlseqs-alt"
     )]
     pub disable_ctlseqs_alt: bool,
+    // Show whether the input method is active next to the cursor
+    #[serde(default = "bool::default", rename = "ime-indicator")]
+    pub ime_indicator: bool,
 }
 
 #[allow(clippy::derivable_impls)]
@@ -25,6 +28,33 @@ impl Default for Keyboard {
             disable_ctlseqs_alt: true,
             #[cfg(not(target_os = "macos"))]
             disable_ctlseqs_alt: false,
+            ime_indicator: false,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, Clone, Deserialize, PartialEq)]
+    struct Root {
+        #[serde(default = "Keyboard::default")]
+        keyboard: Keyboard,
+    }
+
+    #[test]
+    fn test_ime_indicator() {
+        let root: Root = toml::from_str("").unwrap();
+        assert!(!root.keyboard.ime_indicator);
+
+        let content = r#"
+            [keyboard]
+            ime-indicator = true
+        "#;
+        let root: Root = toml::from_str(content).unwrap();
+        assert!(root.keyboard.ime_indicator);
+        assert!(!root.keyboard.use_kitty_keyboard_protocol);
+    }
+}
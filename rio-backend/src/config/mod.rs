@@ -1,23 +1,50 @@
+pub mod bell;
 pub mod bindings;
+pub mod clipboard;
 pub mod colors;
+pub mod command_not_found;
+pub mod confirm_quit;
 pub mod defaults;
+pub mod dim_inactive;
 pub mod keyboard;
 pub mod navigation;
+pub mod night_mode;
+pub mod notifications;
+pub mod osc_hooks;
+pub mod predictive_echo;
+pub mod print;
+pub mod profiles;
 pub mod renderer;
+pub mod serial;
 pub mod theme;
+pub mod title;
 pub mod window;
 
 use crate::ansi::CursorShape;
+use crate::config::bell::Bell;
 use crate::config::bindings::Bindings;
+use crate::config::clipboard::Clipboard;
+use crate::config::command_not_found::CommandNotFound;
+use crate::config::confirm_quit::ConfirmQuit;
 use crate::config::defaults::*;
+use crate::config::dim_inactive::DimInactive;
 use crate::config::keyboard::Keyboard;
 use crate::config::navigation::Navigation;
+use crate::config::night_mode::NightMode;
+use crate::config::notifications::Notifications;
+use crate::config::osc_hooks::OscHooks;
+use crate::config::predictive_echo::PredictiveEcho;
+use crate::config::print::Print;
+use crate::config::profiles::Profile;
 use crate::config::renderer::Renderer;
+use crate::config::serial::Serial;
+use crate::config::title::Title;
 use crate::config::window::Window;
-use colors::Colors;
+use colors::{deserialize_to_optional_arr, ColorArray, Colors};
 use serde::{Deserialize, Serialize};
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 use std::{default::Default, fs::File};
 use sugarloaf::font::fonts::SugarloafFonts;
 use theme::{AdaptiveColors, AdaptiveTheme, Theme};
@@ -34,12 +61,55 @@ pub enum ConfigError {
 pub struct Shell {
     pub program: String,
     pub args: Vec<String>,
+    /// Environment variables for the shell, e.g. `EDITOR=vim`.
+    #[serde(default = "Vec::default")]
+    pub env: Vec<String>,
+    /// Start the shell as a login shell, by default only the user shell
+    /// on macOS is.
+    #[serde(default = "Option::default")]
+    pub login: Option<bool>,
+}
+
+impl Shell {
+    /// Whether the shell is started as a login shell.
+    #[inline]
+    pub fn is_login(&self) -> bool {
+        self.login
+            .unwrap_or(cfg!(target_os = "macos") && self.program.is_empty())
+    }
+
+    /// Environment variables as pairs, entries without `=` are ignored.
+    pub fn env_pairs(&self) -> Vec<(String, String)> {
+        self.env
+            .iter()
+            .filter_map(|var| var.split_once('='))
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub struct Scroll {
     pub multiplier: f64,
     pub divider: f64,
+    /// Jump to the bottom when typing while scrolled up.
+    #[serde(default = "default_bool_true", rename = "jump-to-bottom-on-input")]
+    pub jump_to_bottom_on_input: bool,
+    /// Jump to the bottom on new output while scrolled up.
+    #[serde(default = "bool::default", rename = "jump-to-bottom-on-output")]
+    pub jump_to_bottom_on_output: bool,
+    /// Show how many lines were printed below the viewport while scrolled up.
+    #[serde(default = "default_bool_true", rename = "new-lines-indicator")]
+    pub new_lines_indicator: bool,
+    /// Show a compressed view of the scrollback on the right edge.
+    #[serde(default = "bool::default")]
+    pub minimap: bool,
+    /// Overlay scrollbar shown while scrolled into the scrollback.
+    #[serde(default = "default_bool_true")]
+    pub scrollbar: bool,
+    /// Milliseconds to animate the viewport when scrolling, 0 disables it.
+    #[serde(default = "u64::default", rename = "smooth-scroll-duration")]
+    pub smooth_scroll_duration: u64,
 }
 
 impl Default for Scroll {
@@ -47,10 +117,32 @@ impl Default for Scroll {
         Scroll {
             multiplier: 3.0,
             divider: 1.0,
+            jump_to_bottom_on_input: true,
+            jump_to_bottom_on_output: false,
+            new_lines_indicator: true,
+            minimap: false,
+            scrollbar: true,
+            smooth_scroll_duration: 0,
         }
     }
 }
 
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Security {
+    #[serde(default = "bool::default", rename = "allow-private-graphics")]
+    pub allow_private_graphics: bool,
+    #[serde(default = "bool::default", rename = "allow-window-ops")]
+    pub allow_window_ops: bool,
+}
+
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
+pub struct ControlSocket {
+    #[serde(default = "bool::default")]
+    pub enabled: bool,
+    #[serde(default = "Option::default")]
+    pub path: Option<String>,
+}
+
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct Developer {
     #[serde(default = "bool::default", rename = "enable-fps-counter")]
@@ -75,8 +167,18 @@ impl Default for Developer {
 pub struct Config {
     #[serde(default)]
     pub cursor: CursorConfig,
+    #[serde(default, rename = "text-blinking")]
+    pub text_blinking: TextBlinking,
     #[serde(default = "Navigation::default")]
     pub navigation: Navigation,
+    #[serde(default = "Vec::default", skip_serializing)]
+    pub profiles: Vec<Profile>,
+    #[serde(default = "Title::default")]
+    pub title: Title,
+    #[serde(default = "Print::default")]
+    pub print: Print,
+    #[serde(default = "Clipboard::default")]
+    pub clipboard: Clipboard,
     #[serde(default = "Window::default")]
     pub window: Window,
     #[serde(default = "default_shell")]
@@ -91,6 +193,8 @@ pub struct Config {
     pub line_height: f32,
     #[serde(default = "String::default")]
     pub theme: String,
+    #[serde(default = "Option::default", rename = "theme-file")]
+    pub theme_file: Option<String>,
     #[serde(default = "Scroll::default")]
     pub scroll: Scroll,
     #[serde(
@@ -115,6 +219,26 @@ pub struct Config {
     pub colors: Colors,
     #[serde(default = "Option::default", skip_serializing)]
     pub adaptive_colors: Option<AdaptiveColors>,
+    #[serde(default = "NightMode::default", rename = "night-mode")]
+    pub night_mode: NightMode,
+    #[serde(default = "DimInactive::default", rename = "dim-inactive")]
+    pub dim_inactive: DimInactive,
+    #[serde(default = "PredictiveEcho::default", rename = "predictive-echo")]
+    pub predictive_echo: PredictiveEcho,
+    #[serde(default = "Serial::default")]
+    pub serial: Serial,
+    #[serde(default = "Bell::default")]
+    pub bell: Bell,
+    #[serde(default = "Notifications::default")]
+    pub notifications: Notifications,
+    #[serde(default = "CommandNotFound::default", rename = "command-not-found")]
+    pub command_not_found: CommandNotFound,
+    #[serde(default = "OscHooks::default", rename = "osc-hooks")]
+    pub osc_hooks: OscHooks,
+    #[serde(default, rename = "control-socket")]
+    pub control_socket: ControlSocket,
+    #[serde(default = "Security::default")]
+    pub security: Security,
     #[serde(default = "Developer::default")]
     pub developer: Developer,
     #[serde(default = "Bindings::default")]
@@ -126,14 +250,30 @@ pub struct Config {
     pub ignore_selection_fg_color: bool,
     #[serde(default = "default_bool_true", rename = "confirm-before-quit")]
     pub confirm_before_quit: bool,
+    #[serde(default = "ConfirmQuit::default", rename = "confirm-quit")]
+    pub confirm_quit: ConfirmQuit,
+    #[serde(default = "bool::default")]
+    pub hold: bool,
+    #[serde(default = "Option::default")]
+    pub locale: Option<String>,
     #[serde(
         default = "bool::default",
         rename = "hide-mouse-cursor-when-typing",
         alias = "hide-cursor-when-typing"
     )]
     pub hide_cursor_when_typing: bool,
+    #[serde(default = "bool::default", rename = "click-to-move-cursor")]
+    pub click_to_move_cursor: bool,
+    #[serde(default = "bool::default", rename = "check-for-updates")]
+    pub check_for_updates: bool,
     #[serde(default = "Renderer::default")]
     pub renderer: Renderer,
+    /// Set by `--safe-mode`, the config file is never read.
+    #[serde(skip)]
+    pub safe_mode: bool,
+    /// Set after a crash loop, background shaders are turned off.
+    #[serde(skip)]
+    pub crash_recovery: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -144,22 +284,159 @@ pub struct CursorConfig {
     pub blinking: bool,
     #[serde(default = "default_cursor_interval", rename = "blinking-interval")]
     pub blinking_interval: u64,
+    #[serde(default = "default_cursor_thickness", rename = "beam-width")]
+    pub beam_width: CursorSize,
+    #[serde(default = "default_cursor_thickness", rename = "underline-height")]
+    pub underline_height: CursorSize,
+    // Drawn around the cursor, helps to find it on light backgrounds
+    #[serde(
+        default = "Option::default",
+        deserialize_with = "deserialize_to_optional_arr",
+        skip_serializing,
+        rename = "outline-color"
+    )]
+    pub outline_color: Option<ColorArray>,
+    #[serde(default = "CursorAnimation::default")]
+    pub animation: CursorAnimation,
+    #[serde(
+        default = "default_cursor_animation_duration",
+        rename = "animation-duration"
+    )]
+    pub animation_duration: u64,
+}
+
+/// How the cursor moves between cells.
+#[derive(Default, Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum CursorAnimation {
+    #[default]
+    #[serde(alias = "none")]
+    None,
+    // Stretches towards the new cell and leaves a short trail
+    #[serde(alias = "smear")]
+    Smear,
+    // Fades out of the old cell and into the new one
+    #[serde(alias = "fade")]
+    Fade,
+}
+
+/// Thickness of the beam and underline cursors, either in pixels or in
+/// percent of the cell with a string like `'10%'`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(try_from = "CursorSizeValue", into = "CursorSizeValue")]
+pub enum CursorSize {
+    Pixels(f32),
+    Percent(f32),
+}
+
+impl CursorSize {
+    /// Size in pixels for a cell dimension, never thinner than a pixel.
+    #[inline]
+    pub fn resolve(&self, cell: f32) -> f32 {
+        let size = match self {
+            CursorSize::Pixels(pixels) => *pixels,
+            CursorSize::Percent(percent) => cell * percent / 100.,
+        };
+        size.clamp(1., cell.max(1.))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+enum CursorSizeValue {
+    Pixels(f32),
+    Percent(String),
+}
+
+impl TryFrom<CursorSizeValue> for CursorSize {
+    type Error = String;
+
+    fn try_from(value: CursorSizeValue) -> Result<Self, Self::Error> {
+        match value {
+            CursorSizeValue::Pixels(pixels) => Ok(CursorSize::Pixels(pixels)),
+            CursorSizeValue::Percent(percent) => percent
+                .strip_suffix('%')
+                .and_then(|percent| percent.trim().parse::<f32>().ok())
+                .map(CursorSize::Percent)
+                .ok_or_else(|| format!("invalid cursor size \"{percent}\"")),
+        }
+    }
+}
+
+impl From<CursorSize> for CursorSizeValue {
+    fn from(size: CursorSize) -> Self {
+        match size {
+            CursorSize::Pixels(pixels) => CursorSizeValue::Pixels(pixels),
+            CursorSize::Percent(percent) => {
+                CursorSizeValue::Percent(format!("{percent}%"))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct TextBlinking {
+    #[serde(default = "default_bool_true")]
+    pub enabled: bool,
+    #[serde(default = "default_text_blinking_interval")]
+    pub interval: u64,
+}
+
+/// Directory used by `--portable`, set once at startup before the
+/// config is loaded.
+static PORTABLE_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Keeps the config, themes and logs in a `rio` directory next to the
+/// executable instead of the user directories, e.g. to carry Rio on
+/// a USB stick.
+pub fn enable_portable_mode() -> Result<PathBuf, String> {
+    let executable = std::env::current_exe().map_err(|err| err.to_string())?;
+    let dir = executable
+        .parent()
+        .ok_or("executable has no parent directory")?
+        .join("rio");
+    Ok(PORTABLE_DIR.get_or_init(|| dir).clone())
 }
 
 #[cfg(not(target_os = "windows"))]
 #[inline]
-pub fn config_dir_path() -> PathBuf {
-    let home_dir = dirs::home_dir().unwrap();
+fn platform_config_dir_path(home_dir: &Path) -> PathBuf {
     home_dir.join(".config").join("rio")
 }
 
 #[cfg(target_os = "windows")]
 #[inline]
-pub fn config_dir_path() -> PathBuf {
-    let home_dir = dirs::home_dir().unwrap();
+fn platform_config_dir_path(home_dir: &Path) -> PathBuf {
     home_dir.join("AppData").join("Local").join("rio")
 }
 
+/// Portable mode wins, then `$XDG_CONFIG_HOME/rio` and then the
+/// platform path. Following the XDG spec an empty or relative
+/// `$XDG_CONFIG_HOME` is ignored.
+fn resolve_config_dir_path(
+    portable: Option<&Path>,
+    xdg_config_home: Option<&Path>,
+    home_dir: &Path,
+) -> PathBuf {
+    if let Some(portable) = portable {
+        return portable.to_path_buf();
+    }
+
+    match xdg_config_home {
+        Some(xdg) if xdg.is_absolute() => xdg.join("rio"),
+        _ => platform_config_dir_path(home_dir),
+    }
+}
+
+#[inline]
+pub fn config_dir_path() -> PathBuf {
+    let xdg_config_home = std::env::var_os("XDG_CONFIG_HOME").map(PathBuf::from);
+    resolve_config_dir_path(
+        PORTABLE_DIR.get().map(PathBuf::as_path),
+        xdg_config_home.as_deref(),
+        &dirs::home_dir().unwrap_or_default(),
+    )
+}
+
 #[inline]
 pub fn config_file_path() -> PathBuf {
     config_dir_path().join("config.toml")
@@ -234,12 +511,15 @@ impl Config {
             let content = std::fs::read_to_string(path).unwrap();
             match toml::from_str::<Config>(&content) {
                 Ok(mut decoded) => {
+                    let tmp = std::env::temp_dir();
+                    decoded.load_profile_themes(&tmp);
+
                     let theme = &decoded.theme;
                     if theme.is_empty() {
+                        decoded.load_theme_file();
                         return Ok(decoded);
                     }
 
-                    let tmp = std::env::temp_dir();
                     let path = tmp.join(theme).with_extension("toml");
                     if let Ok(loaded_theme) = Config::load_theme(&path) {
                         decoded.colors = loaded_theme.colors;
@@ -276,6 +556,7 @@ impl Config {
                         }
                     }
 
+                    decoded.load_theme_file();
                     Ok(decoded)
                 }
                 Err(err_message) => Err(format!("error parsing: {:?}", err_message)),
@@ -297,6 +578,74 @@ impl Config {
         }
     }
 
+    /// Loads the colors of every profile that sets a theme.
+    fn load_profile_themes(&mut self, themes_path: &Path) {
+        for profile in self.profiles.iter_mut() {
+            if profile.theme.is_empty() {
+                continue;
+            }
+
+            // A broken profile theme only leaves that profile without colors.
+            let path = themes_path.join(&profile.theme).with_extension("toml");
+            match Config::load_theme(&path) {
+                Ok(loaded_theme) => profile.colors = Some(loaded_theme.colors),
+                Err(err_message) => {
+                    warn!(
+                        "failed to load profile theme {}: {err_message}",
+                        profile.theme
+                    );
+                }
+            }
+        }
+    }
+
+    /// Path of `theme-file` with a leading `~` expanded to the home directory.
+    pub fn theme_file_path(&self) -> Option<PathBuf> {
+        let theme_file = self.theme_file.as_ref()?;
+        if theme_file.is_empty() {
+            return None;
+        }
+
+        match theme_file.strip_prefix("~/") {
+            Some(relative) => dirs::home_dir().map(|home| home.join(relative)),
+            None => Some(PathBuf::from(theme_file)),
+        }
+    }
+
+    /// Files outside of the config directory which are reloaded on changes.
+    pub fn watched_files(&self) -> Vec<PathBuf> {
+        let background_image = self
+            .window
+            .background_image
+            .as_ref()
+            .map(|image| PathBuf::from(&image.path));
+        let background_shader = self.window.background_shader.as_ref().map(PathBuf::from);
+        self.theme_file_path()
+            .into_iter()
+            .chain(background_image)
+            .chain(background_shader)
+            .collect()
+    }
+
+    /// Colors from `theme-file` take precedence over `theme`. The file is
+    /// usually rewritten by external tools (e.g: pywal or matugen) so failing
+    /// to read it only keeps the previous colors instead of erroring.
+    fn load_theme_file(&mut self) {
+        if let Some(path) = self.theme_file_path() {
+            match Theme::from_file(&path) {
+                Ok(loaded_theme) => {
+                    self.colors = loaded_theme.colors;
+                }
+                Err(err_message) => {
+                    warn!(
+                        "failed to load theme file {}: {err_message}",
+                        path.display()
+                    );
+                }
+            }
+        }
+    }
+
     pub fn to_string(&self) -> Result<String, toml::ser::Error> {
         toml::to_string(self)
     }
@@ -309,20 +658,20 @@ impl Config {
             match toml::from_str::<Config>(&content) {
                 Ok(mut decoded) => {
                     let theme = &decoded.theme;
-                    if theme.is_empty() {
-                        return decoded;
-                    }
-
-                    let path = config_path
-                        .join("themes")
-                        .join(theme)
-                        .with_extension("toml");
-                    if let Ok(loaded_theme) = Config::load_theme(&path) {
-                        decoded.colors = loaded_theme.colors;
-                    } else {
-                        warn!("failed to load theme: {}", theme);
+                    if !theme.is_empty() {
+                        let path = config_path
+                            .join("themes")
+                            .join(theme)
+                            .with_extension("toml");
+                        if let Ok(loaded_theme) = Config::load_theme(&path) {
+                            decoded.colors = loaded_theme.colors;
+                        } else {
+                            warn!("failed to load theme: {}", theme);
+                        }
                     }
 
+                    decoded.load_theme_file();
+                    decoded.load_profile_themes(&config_path.join("themes"));
                     decoded
                 }
                 Err(err_message) => {
@@ -357,6 +706,10 @@ impl Config {
                             }
                         }
 
+                        decoded.load_theme_file();
+
+                        decoded.load_profile_themes(&theme_path);
+
                         if let Some(adaptive_theme) = &decoded.adaptive_theme {
                             let mut adaptive_colors = AdaptiveColors {
                                 dark: None,
@@ -420,30 +773,53 @@ impl Default for Config {
     fn default() -> Self {
         Config {
             cursor: CursorConfig::default(),
+            text_blinking: TextBlinking::default(),
             editor: default_editor(),
             adaptive_theme: None,
             adaptive_colors: None,
+            night_mode: NightMode::default(),
+            dim_inactive: DimInactive::default(),
+            predictive_echo: PredictiveEcho::default(),
+            serial: Serial::default(),
+            bell: Bell::default(),
+            notifications: Notifications::default(),
+            command_not_found: CommandNotFound::default(),
+            osc_hooks: OscHooks::default(),
+            control_socket: ControlSocket::default(),
             bindings: Bindings::default(),
             colors: Colors::default(),
             scroll: Scroll::default(),
             keyboard: Keyboard::default(),
+            security: Security::default(),
             developer: Developer::default(),
             env_vars: vec![],
             fonts: SugarloafFonts::default(),
             line_height: default_line_height(),
             navigation: Navigation::default(),
+            profiles: Vec::default(),
+            title: Title::default(),
+            print: Print::default(),
+            clipboard: Clipboard::default(),
             option_as_alt: default_option_as_alt(),
             padding_x: f32::default(),
             padding_y: default_padding_y(),
             renderer: Renderer::default(),
+            safe_mode: false,
+            crash_recovery: false,
             shell: default_shell(),
             theme: String::default(),
+            theme_file: None,
             use_fork: default_use_fork(),
             window: Window::default(),
             working_dir: default_working_dir(),
             ignore_selection_fg_color: false,
             confirm_before_quit: true,
+            confirm_quit: ConfirmQuit::default(),
+            hold: false,
+            locale: None,
             hide_cursor_when_typing: false,
+            click_to_move_cursor: false,
+            check_for_updates: false,
         }
     }
 }
@@ -454,6 +830,20 @@ impl Default for CursorConfig {
             shape: default_cursor(),
             blinking: false,
             blinking_interval: default_cursor_interval(),
+            beam_width: default_cursor_thickness(),
+            underline_height: default_cursor_thickness(),
+            outline_color: None,
+            animation: CursorAnimation::default(),
+            animation_duration: default_cursor_animation_duration(),
+        }
+    }
+}
+
+impl Default for TextBlinking {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval: default_text_blinking_interval(),
         }
     }
 }
@@ -589,11 +979,26 @@ mod tests {
             [renderer]
             performance = "Low"
             backend = "Vulkan"
+            adapter = "nvidia"
+            hdr = true
+            present-mode = "mailbox"
+            max-fps = 30
+            unfocused-fps = 0
+            text-gamma = 1.4
+            text-contrast = 0.5
         "#,
         );
 
         assert_eq!(result.renderer.performance, renderer::Performance::Low);
         assert_eq!(result.renderer.backend, renderer::Backend::Vulkan);
+        assert_eq!(result.renderer.adapter.as_deref(), Some("nvidia"));
+        assert!(result.renderer.hdr);
+        assert_eq!(result.renderer.present_mode, renderer::PresentMode::Mailbox);
+        assert_eq!(result.renderer.max_fps, Some(30));
+        assert_eq!(result.renderer.target_fps, None);
+        assert_eq!(result.renderer.unfocused_fps, 0);
+        assert_eq!(result.renderer.text_gamma, 1.4);
+        assert_eq!(result.renderer.text_contrast, 0.5);
         assert_eq!(result.fonts, SugarloafFonts::default());
         assert_eq!(result.theme, String::default());
         // Colors
@@ -603,6 +1008,184 @@ mod tests {
         assert_eq!(result.colors.cursor, colors::defaults::cursor());
     }
 
+    #[test]
+    fn test_change_night_mode() {
+        let result = create_temporary_config(
+            "change-night-mode",
+            r#"
+            [night-mode]
+            enabled = true
+            start = "22:30"
+            warmth = 0.8
+        "#,
+        );
+
+        assert!(result.night_mode.enabled);
+        assert_eq!(result.night_mode.start, "22:30");
+        assert_eq!(result.night_mode.end, "07:00");
+        assert_eq!(result.night_mode.warmth, 0.8);
+        assert_eq!(result.night_mode.dim, 0.0);
+    }
+
+    #[test]
+    fn test_change_dim_inactive() {
+        let result = create_temporary_config(
+            "change-dim-inactive",
+            r#"
+            [dim-inactive]
+            brightness = 0.7
+        "#,
+        );
+
+        assert_eq!(result.dim_inactive.brightness, 0.7);
+        assert_eq!(result.dim_inactive.saturation, 1.0);
+        assert!(result.dim_inactive.is_enabled());
+    }
+
+    #[test]
+    fn test_change_predictive_echo() {
+        let result = create_temporary_config(
+            "change-predictive-echo",
+            r#"
+            [predictive-echo]
+            mode = "auto"
+        "#,
+        );
+
+        assert_eq!(
+            result.predictive_echo.mode,
+            predictive_echo::PredictiveEchoMode::Auto
+        );
+        assert_eq!(result.predictive_echo.latency_threshold, 100);
+    }
+
+    #[test]
+    fn test_change_serial() {
+        let result = create_temporary_config(
+            "change-serial",
+            r#"
+            [serial]
+            flow-control = "hardware"
+        "#,
+        );
+
+        assert_eq!(result.serial.flow_control, serial::FlowControl::Hardware);
+        assert_eq!(result.serial.baud_rate, 115_200);
+    }
+
+    #[test]
+    fn test_change_scroll() {
+        let result = create_temporary_config(
+            "change-scroll",
+            r#"
+            [scroll]
+            multiplier = 2.0
+            divider = 1.0
+            jump-to-bottom-on-output = true
+            new-lines-indicator = false
+            minimap = true
+            scrollbar = false
+            smooth-scroll-duration = 120
+        "#,
+        );
+
+        assert_eq!(result.scroll.multiplier, 2.0);
+        assert!(result.scroll.jump_to_bottom_on_input);
+        assert!(result.scroll.jump_to_bottom_on_output);
+        assert!(!result.scroll.new_lines_indicator);
+        assert!(result.scroll.minimap);
+        assert!(!result.scroll.scrollbar);
+        assert_eq!(result.scroll.smooth_scroll_duration, 120);
+    }
+
+    #[test]
+    fn test_change_bell() {
+        let result = create_temporary_config(
+            "change-bell",
+            r#"
+            [bell]
+            sound = "file"
+            sound-file = "/tmp/bell.wav"
+            visual = true
+        "#,
+        );
+
+        assert_eq!(result.bell.sound, bell::BellSound::File);
+        assert_eq!(result.bell.sound_file, Some(String::from("/tmp/bell.wav")));
+        assert!(result.bell.visual);
+        assert_eq!(result.bell.visual_duration, 150);
+        assert!(result.bell.urgent);
+    }
+
+    #[test]
+    fn test_change_notifications() {
+        let result = create_temporary_config(
+            "change-notifications",
+            r#"
+            [notifications]
+            command-duration-threshold = 10
+        "#,
+        );
+
+        assert_eq!(result.notifications.command_duration_threshold, Some(10));
+        assert_eq!(
+            Config::default().notifications.command_duration_threshold,
+            None
+        );
+    }
+
+    #[test]
+    fn test_change_security() {
+        let result = create_temporary_config(
+            "change-security",
+            r#"
+            [security]
+            allow-private-graphics = true
+            allow-window-ops = true
+        "#,
+        );
+
+        assert!(result.security.allow_private_graphics);
+        assert!(result.security.allow_window_ops);
+        assert_eq!(Config::default().security, Security::default());
+    }
+
+    #[test]
+    fn test_change_shell() {
+        let result = create_temporary_config(
+            "change-shell",
+            r#"
+            [shell]
+            program = "/bin/fish"
+            args = ["-i"]
+            env = ["EDITOR=nvim", "INVALID", "EMPTY="]
+            login = false
+        "#,
+        );
+
+        assert_eq!(result.shell.program, "/bin/fish");
+        assert_eq!(result.shell.args, [String::from("-i")]);
+        assert!(!result.shell.is_login());
+        assert_eq!(
+            result.shell.env_pairs(),
+            [
+                (String::from("EDITOR"), String::from("nvim")),
+                (String::from("EMPTY"), String::new()),
+            ]
+        );
+
+        let result = create_temporary_config(
+            "change-shell-default-login",
+            r#"
+            [shell]
+            program = ""
+            args = []
+        "#,
+        );
+        assert_eq!(result.shell.is_login(), cfg!(target_os = "macos"));
+        assert!(result.shell.env_pairs().is_empty());
+    }
+
     #[test]
     fn test_change_config_environment_variables() {
         let result = create_temporary_config(
@@ -654,6 +1237,51 @@ mod tests {
         assert_eq!(result.colors.cursor, colors::defaults::cursor());
     }
 
+    #[test]
+    fn test_change_config_cursor_geometry() {
+        let result = create_temporary_config(
+            "change-cursor-geometry",
+            r#"
+            [cursor]
+            beam-width = 2
+            underline-height = '25%'
+            outline-color = '#000000'
+        "#,
+        );
+
+        assert_eq!(result.cursor.shape, default_cursor());
+        assert_eq!(result.cursor.beam_width, CursorSize::Pixels(2.0));
+        assert_eq!(result.cursor.underline_height, CursorSize::Percent(25.0));
+        assert_eq!(result.cursor.outline_color, Some([0.0, 0.0, 0.0, 1.0]));
+        assert_eq!(result.cursor.underline_height.resolve(20.0), 5.0);
+        // Never thinner than a pixel or larger than the cell
+        assert_eq!(CursorSize::Percent(1.0).resolve(20.0), 1.0);
+        assert_eq!(CursorSize::Pixels(30.0).resolve(20.0), 20.0);
+
+        let result = create_temporary_config("default-cursor-geometry", "");
+        assert_eq!(result.cursor.beam_width, CursorSize::Pixels(3.0));
+        assert_eq!(result.cursor.outline_color, None);
+    }
+
+    #[test]
+    fn test_change_config_cursor_animation() {
+        let result = create_temporary_config(
+            "change-cursor-animation",
+            r#"
+            [cursor]
+            animation = 'smear'
+            animation-duration = 120
+        "#,
+        );
+
+        assert_eq!(result.cursor.animation, CursorAnimation::Smear);
+        assert_eq!(result.cursor.animation_duration, 120);
+
+        let result = create_temporary_config("default-cursor-animation", "");
+        assert_eq!(result.cursor.animation, CursorAnimation::None);
+        assert_eq!(result.cursor.animation_duration, 80);
+    }
+
     #[test]
     fn test_change_option_as_alt() {
         let result = create_temporary_config(
@@ -763,6 +1391,109 @@ mod tests {
         assert_eq!(result.colors.cursor, colors::defaults::cursor());
     }
 
+    #[test]
+    fn test_background_image_fit() {
+        let result = create_temporary_config(
+            "background-image-fit",
+            r#"
+            theme-file = "/tmp/rio-theme.toml"
+
+            [window.background-image]
+            path = "/tmp/wallpaper.png"
+            fit = "cover"
+            opacity = 0.3
+            blur = 4.0
+        "#,
+        );
+
+        let image = result.window.background_image.as_ref().unwrap();
+        assert_eq!(image.fit, sugarloaf::ImageFit::Cover);
+        assert_eq!(image.opacity, 0.3);
+        assert_eq!(image.blur, 4.0);
+        assert_eq!(
+            result.watched_files(),
+            vec![
+                PathBuf::from("/tmp/rio-theme.toml"),
+                PathBuf::from("/tmp/wallpaper.png")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_background_shader() {
+        let result = create_temporary_config(
+            "background-shader",
+            r#"
+            [window]
+            background-shader = "/tmp/waves.wgsl"
+        "#,
+        );
+
+        assert_eq!(
+            result.window.background_shader,
+            Some(String::from("/tmp/waves.wgsl"))
+        );
+        assert_eq!(
+            result.watched_files(),
+            vec![PathBuf::from("/tmp/waves.wgsl")]
+        );
+    }
+
+    #[test]
+    fn test_check_for_updates() {
+        assert!(!Config::default().check_for_updates);
+
+        let result = create_temporary_config(
+            "check-for-updates",
+            r#"
+            check-for-updates = true
+        "#,
+        );
+        assert!(result.check_for_updates);
+    }
+
+    #[test]
+    fn test_safe_mode_is_not_read_from_config() {
+        let result = create_temporary_config(
+            "safe-mode",
+            r#"
+            safe-mode = true
+            safe_mode = true
+            crash_recovery = true
+        "#,
+        );
+
+        assert!(!result.safe_mode);
+        assert!(!result.crash_recovery);
+    }
+
+    #[test]
+    fn test_config_dir_path_precedence() {
+        let home = PathBuf::from("/home/rio");
+        let platform = platform_config_dir_path(&home);
+        let xdg = std::env::temp_dir().join("xdg");
+        let portable = std::env::temp_dir().join("usb").join("rio");
+
+        assert_eq!(resolve_config_dir_path(None, None, &home), platform);
+        assert_eq!(
+            resolve_config_dir_path(None, Some(&xdg), &home),
+            xdg.join("rio")
+        );
+        // Relative paths are invalid for XDG and get ignored
+        assert_eq!(
+            resolve_config_dir_path(None, Some(Path::new("")), &home),
+            platform
+        );
+        assert_eq!(
+            resolve_config_dir_path(None, Some(Path::new("xdg")), &home),
+            platform
+        );
+        assert_eq!(
+            resolve_config_dir_path(Some(&portable), Some(&xdg), &home),
+            portable
+        );
+    }
+
     #[test]
     fn test_change_theme() {
         let result = create_temporary_config(
@@ -782,6 +1513,44 @@ mod tests {
         assert_eq!(result.colors.cursor, colors::defaults::cursor());
     }
 
+    #[test]
+    fn test_profiles_load_theme() {
+        create_temporary_theme(
+            "profile-red-alert",
+            r#"
+            [colors]
+            background       = '#550000'
+        "#,
+        );
+
+        let result = create_temporary_config(
+            "profiles-load-theme",
+            r#"
+            [[profiles]]
+            program = "top"
+            theme = "profile-missing-theme"
+
+            [[profiles]]
+            program = "ssh"
+            theme = "profile-red-alert"
+
+            [[profiles]]
+            path = "~/work"
+            title = "work: {program}"
+        "#,
+        );
+
+        assert_eq!(result.colors.background, colors::defaults::background());
+        assert_eq!(result.profiles.len(), 3);
+        // A missing theme doesn't keep the other profiles from loading theirs.
+        assert_eq!(result.profiles[0].colors, None);
+        assert_eq!(
+            result.profiles[1].colors.map(|colors| colors.background.0),
+            Some(hex_to_color_arr("#550000"))
+        );
+        assert_eq!(result.profiles[2].colors, None);
+    }
+
     #[test]
     fn test_change_theme_with_colors_overwrite() {
         create_temporary_theme(
@@ -811,6 +1580,55 @@ mod tests {
         assert_eq!(result.colors.background.0, hex_to_color_arr("#2B3E50"));
     }
 
+    #[test]
+    fn test_theme_file_overwrites_theme() {
+        create_temporary_theme(
+            "lucario-under-theme-file",
+            r#"
+            [colors]
+            background       = '#2B3E50'
+            foreground       = '#F8F8F2'
+        "#,
+        );
+
+        let theme_file = tmp_dir().join("test-rio-theme-file.toml");
+        let mut file = std::fs::File::create(&theme_file).unwrap();
+        writeln!(
+            file,
+            r#"
+            [colors]
+            foreground = '#C5C8C6'
+        "#
+        )
+        .unwrap();
+
+        let result = create_temporary_config(
+            "theme-file",
+            &format!(
+                r#"
+                theme = "lucario-under-theme-file"
+                theme-file = "{}"
+            "#,
+                theme_file.display().to_string().replace('\\', "/")
+            ),
+        );
+
+        assert_eq!(result.colors.foreground, hex_to_color_arr("#C5C8C6"));
+        assert_eq!(result.colors.background, colors::defaults::background());
+    }
+
+    #[test]
+    fn test_theme_file_missing_keeps_theme() {
+        let result = create_temporary_config(
+            "theme-file-missing",
+            r#"
+            theme-file = "it-should-never-exist.json"
+        "#,
+        );
+
+        assert_eq!(result.colors.foreground, colors::defaults::foreground());
+    }
+
     #[test]
     fn test_change_one_color() {
         let result = create_temporary_config(
@@ -918,6 +1736,32 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_hold() {
+        let result = create_temporary_config(
+            "change-hold",
+            r#"
+            hold = true
+        "#,
+        );
+
+        assert!(result.hold);
+        assert!(!Config::default().hold);
+    }
+
+    #[test]
+    fn test_locale() {
+        let result = create_temporary_config(
+            "change-locale",
+            r#"
+            locale = "pt_BR.UTF-8"
+        "#,
+        );
+
+        assert_eq!(result.locale, Some(String::from("pt_BR.UTF-8")));
+        assert_eq!(Config::default().locale, None);
+    }
+
     #[test]
     fn test_use_fork() {
         let result = create_temporary_config(
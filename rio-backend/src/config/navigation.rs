@@ -121,6 +121,8 @@ pub struct Navigation {
     pub use_terminal_title: bool,
     #[serde(default = "default_bool_true", rename = "hide-if-single")]
     pub hide_if_single: bool,
+    #[serde(default = "bool::default", rename = "process-usage")]
+    pub process_usage: bool,
 }
 
 impl Default for Navigation {
@@ -132,6 +134,7 @@ impl Default for Navigation {
             use_current_path: false,
             use_terminal_title: false,
             hide_if_single: true,
+            process_usage: false,
         }
     }
 }
@@ -224,6 +227,22 @@ mod tests {
         assert!(decoded.navigation.color_automation.is_empty());
     }
 
+    #[test]
+    fn test_process_usage() {
+        let content = r#"
+            [navigation]
+            mode = 'TopTab'
+            process-usage = true
+        "#;
+
+        let decoded = toml::from_str::<Root>(content).unwrap();
+        assert_eq!(decoded.navigation.mode, NavigationMode::TopTab);
+        assert!(decoded.navigation.process_usage);
+
+        let decoded = toml::from_str::<Root>("[navigation]").unwrap();
+        assert!(!decoded.navigation.process_usage);
+    }
+
     #[test]
     fn test_color_automation() {
         let content = r#"
@@ -0,0 +1,144 @@
+use crate::config::colors::ColorArray;
+use serde::{Deserialize, Serialize};
+
+#[inline]
+fn default_night_mode_start() -> String {
+    String::from("20:00")
+}
+
+#[inline]
+fn default_night_mode_end() -> String {
+    String::from("07:00")
+}
+
+#[inline]
+fn default_night_mode_warmth() -> f32 {
+    0.5
+}
+
+#[derive(PartialEq, Serialize, Deserialize, Clone, Debug)]
+pub struct NightMode {
+    #[serde(default = "bool::default")]
+    pub enabled: bool,
+    #[serde(default = "default_night_mode_start")]
+    pub start: String,
+    #[serde(default = "default_night_mode_end")]
+    pub end: String,
+    // How much blue light is removed, from 0.0 (none) to 1.0 (max)
+    #[serde(default = "default_night_mode_warmth")]
+    pub warmth: f32,
+    // How much the overall brightness is reduced, from 0.0 (none) to 1.0 (black)
+    #[serde(default = "f32::default")]
+    pub dim: f32,
+}
+
+impl Default for NightMode {
+    fn default() -> NightMode {
+        NightMode {
+            enabled: false,
+            start: default_night_mode_start(),
+            end: default_night_mode_end(),
+            warmth: default_night_mode_warmth(),
+            dim: 0.0,
+        }
+    }
+}
+
+// Parses "HH:MM" into minutes since midnight
+fn parse_time_of_day(value: &str) -> Option<u16> {
+    let (hours, minutes) = value.trim().split_once(':')?;
+    let hours: u16 = hours.parse().ok()?;
+    let minutes: u16 = minutes.parse().ok()?;
+    if hours > 23 || minutes > 59 {
+        return None;
+    }
+
+    Some(hours * 60 + minutes)
+}
+
+impl NightMode {
+    /// Whether the schedule covers `minutes` since local midnight. The
+    /// schedule can wrap around midnight (e.g. from 20:00 to 07:00).
+    pub fn is_active_at(&self, minutes: u16) -> bool {
+        if !self.enabled {
+            return false;
+        }
+
+        let (Some(start), Some(end)) =
+            (parse_time_of_day(&self.start), parse_time_of_day(&self.end))
+        else {
+            return false;
+        };
+
+        if start <= end {
+            minutes >= start && minutes < end
+        } else {
+            minutes >= start || minutes < end
+        }
+    }
+
+    /// Shift a color toward warmer tones and dim it, alpha is kept.
+    #[inline]
+    pub fn apply(&self, color: ColorArray) -> ColorArray {
+        let warmth = self.warmth.clamp(0.0, 1.0);
+        let brightness = 1.0 - self.dim.clamp(0.0, 1.0);
+        [
+            color[0] * brightness,
+            color[1] * (1.0 - 0.2 * warmth) * brightness,
+            color[2] * (1.0 - 0.5 * warmth) * brightness,
+            color[3],
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_time_of_day() {
+        assert_eq!(parse_time_of_day("00:00"), Some(0));
+        assert_eq!(parse_time_of_day("07:30"), Some(450));
+        assert_eq!(parse_time_of_day("23:59"), Some(1439));
+        assert_eq!(parse_time_of_day("24:00"), None);
+        assert_eq!(parse_time_of_day("7"), None);
+    }
+
+    #[test]
+    fn test_schedule_wrapping_midnight() {
+        let night_mode = NightMode {
+            enabled: true,
+            ..NightMode::default()
+        };
+
+        assert!(night_mode.is_active_at(20 * 60));
+        assert!(night_mode.is_active_at(2 * 60));
+        assert!(!night_mode.is_active_at(7 * 60));
+        assert!(!night_mode.is_active_at(12 * 60));
+    }
+
+    #[test]
+    fn test_schedule_same_day() {
+        let night_mode = NightMode {
+            enabled: true,
+            start: String::from("13:00"),
+            end: String::from("14:00"),
+            ..NightMode::default()
+        };
+
+        assert!(night_mode.is_active_at(13 * 60 + 30));
+        assert!(!night_mode.is_active_at(14 * 60));
+        assert!(!NightMode::default().is_active_at(13 * 60 + 30));
+    }
+
+    #[test]
+    fn test_apply_keeps_red_and_alpha() {
+        let night_mode = NightMode {
+            enabled: true,
+            warmth: 1.0,
+            ..NightMode::default()
+        };
+
+        assert_eq!(night_mode.apply([1.0, 1.0, 1.0, 0.5]), [1.0, 0.8, 0.5, 0.5]);
+    }
+}
@@ -0,0 +1,9 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Default, PartialEq, Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct Notifications {
+    // Commands reported through shell integration (OSC 133) running for
+    // longer than this many seconds notify when they finish unfocused
+    #[serde(default = "Option::default", rename = "command-duration-threshold")]
+    pub command_duration_threshold: Option<u64>,
+}
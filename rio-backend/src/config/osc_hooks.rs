@@ -0,0 +1,52 @@
+use serde::{Deserialize, Serialize};
+use std::ops::RangeInclusive;
+
+#[derive(Default, PartialEq, Serialize, Deserialize, Clone, Debug)]
+pub struct OscHooks {
+    // First and last OSC code forwarded to `program` instead of dropped,
+    // codes handled by Rio itself are never forwarded
+    #[serde(default = "Option::default")]
+    pub range: Option<[u16; 2]>,
+    // Run with the code appended to `args` and the payload on stdin
+    #[serde(default = "Option::default")]
+    pub program: Option<String>,
+    #[serde(default = "Vec::default")]
+    pub args: Vec<String>,
+}
+
+impl OscHooks {
+    /// Codes forwarded to the hooks, `None` when disabled.
+    #[inline]
+    pub fn codes(&self) -> Option<RangeInclusive<u16>> {
+        let [start, end] = self.range?;
+        Some(start..=end)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, Deserialize, PartialEq)]
+    struct Root {
+        #[serde(default = "OscHooks::default", rename = "osc-hooks")]
+        osc_hooks: OscHooks,
+    }
+
+    #[test]
+    fn test_osc_hooks() {
+        let decoded = toml::from_str::<Root>("").unwrap();
+        assert_eq!(decoded.osc_hooks.codes(), None);
+
+        let content = r#"
+            [osc-hooks]
+            range = [7700, 7799]
+            program = "rio-osc-hook"
+            args = ["--verbose"]
+        "#;
+        let decoded = toml::from_str::<Root>(content).unwrap();
+        assert_eq!(decoded.osc_hooks.codes(), Some(7700..=7799));
+        assert_eq!(decoded.osc_hooks.program.as_deref(), Some("rio-osc-hook"));
+        assert_eq!(decoded.osc_hooks.args, ["--verbose"]);
+    }
+}
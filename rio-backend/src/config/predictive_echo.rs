@@ -0,0 +1,42 @@
+use serde::{Deserialize, Serialize};
+
+#[inline]
+fn default_latency_threshold() -> u64 {
+    100
+}
+
+#[derive(Default, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PredictiveEchoMode {
+    // Never show predictions
+    #[default]
+    #[serde(alias = "never")]
+    Never,
+    // Show predictions once the round-trip latency exceeds the threshold
+    #[serde(alias = "auto")]
+    Auto,
+    // Always show predictions
+    #[serde(alias = "always")]
+    Always,
+}
+
+#[derive(PartialEq, Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct PredictiveEcho {
+    #[serde(default = "PredictiveEchoMode::default")]
+    pub mode: PredictiveEchoMode,
+    // Round-trip latency in milliseconds used by the auto mode
+    #[serde(default = "default_latency_threshold", rename = "latency-threshold")]
+    pub latency_threshold: u64,
+    // Show the echo latency of the current tab
+    #[serde(default = "bool::default", rename = "show-latency")]
+    pub show_latency: bool,
+}
+
+impl Default for PredictiveEcho {
+    fn default() -> PredictiveEcho {
+        PredictiveEcho {
+            mode: PredictiveEchoMode::default(),
+            latency_threshold: default_latency_threshold(),
+            show_latency: false,
+        }
+    }
+}
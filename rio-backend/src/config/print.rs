@@ -0,0 +1,34 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Default, PartialEq, Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct Print {
+    // Keep the foreground colors of the text, the default foreground
+    // is always printed in black
+    #[serde(default = "bool::default")]
+    pub colors: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, Deserialize, PartialEq)]
+    struct Root {
+        #[serde(default = "Print::default")]
+        print: Print,
+    }
+
+    #[test]
+    fn test_print() {
+        let decoded = toml::from_str::<Root>("").unwrap();
+        assert!(!decoded.print.colors);
+
+        let content = r#"
+            [print]
+            colors = true
+        "#;
+
+        let decoded = toml::from_str::<Root>(content).unwrap();
+        assert!(decoded.print.colors);
+    }
+}
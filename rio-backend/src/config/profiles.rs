@@ -0,0 +1,180 @@
+use crate::config::bell::Bell;
+use crate::config::colors::Colors;
+use crate::config::title::TitleVars;
+use serde::{Deserialize, Serialize};
+
+/// Switches theme and title of a window while the foreground process of the
+/// current tab matches `program` and/or its working directory matches `path`.
+#[derive(Default, Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct Profile {
+    #[serde(default = "String::new")]
+    pub program: String,
+    #[serde(default = "String::new")]
+    pub path: String,
+    #[serde(default = "String::new")]
+    pub theme: String,
+    // Same placeholders as `title.content`
+    #[serde(default = "Option::default")]
+    pub title: Option<String>,
+    // Replaces the `[bell]` section while the profile is active
+    #[serde(default = "Option::default")]
+    pub bell: Option<Bell>,
+    // Colors of `theme`, loaded along with the config
+    #[serde(skip)]
+    pub colors: Option<Colors>,
+}
+
+impl Profile {
+    /// A rule without `program` and `path` never matches.
+    pub fn matches(&self, program: &str, path: &str) -> bool {
+        if self.program.is_empty() && self.path.is_empty() {
+            return false;
+        }
+
+        (self.program.is_empty() || self.program == program)
+            && (self.path.is_empty() || path_matches(&self.path, path))
+    }
+
+    #[inline]
+    pub fn format_title(&self, vars: &TitleVars) -> Option<String> {
+        self.title.as_ref().map(|template| vars.format(template))
+    }
+}
+
+/// Index of the first profile matching the foreground process.
+pub fn find_profile(profiles: &[Profile], program: &str, path: &str) -> Option<usize> {
+    profiles
+        .iter()
+        .position(|profile| profile.matches(program, path))
+}
+
+// The pattern matches the directory and everything below it, a leading `~/`
+// is the home directory and `*` matches any part of a path component.
+fn path_matches(pattern: &str, path: &str) -> bool {
+    let pattern = match pattern.strip_prefix("~/") {
+        Some(relative) => match dirs::home_dir() {
+            Some(home) => home.join(relative).to_string_lossy().to_string(),
+            None => return false,
+        },
+        None => pattern.to_string(),
+    };
+
+    let mut components = path.split('/').filter(|c| !c.is_empty());
+    pattern
+        .split('/')
+        .filter(|c| !c.is_empty())
+        .all(|expected| {
+            components
+                .next()
+                .is_some_and(|component| wildcard_matches(expected, component))
+        })
+}
+
+fn wildcard_matches(pattern: &str, value: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == value,
+        Some((prefix, rest)) => {
+            let Some(value) = value.strip_prefix(prefix) else {
+                return false;
+            };
+
+            (0..=value.len())
+                .filter(|&i| value.is_char_boundary(i))
+                .any(|i| wildcard_matches(rest, &value[i..]))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, Clone, Deserialize, PartialEq)]
+    struct Root {
+        #[serde(default = "Vec::default")]
+        profiles: Vec<Profile>,
+    }
+
+    #[test]
+    fn test_profiles() {
+        let content = r#"
+            [[profiles]]
+            program = 'ssh'
+            theme = 'red-alert'
+            title = 'PROD {title}'
+
+            [[profiles]]
+            path = '/home/rio/work'
+            bell = { sound = 'none', fallback = 'none' }
+        "#;
+
+        let decoded = toml::from_str::<Root>(content).unwrap();
+        assert_eq!(decoded.profiles.len(), 2);
+        assert_eq!(decoded.profiles[0].program, "ssh");
+        assert_eq!(decoded.profiles[0].theme, "red-alert");
+        assert_eq!(
+            decoded.profiles[0].title,
+            Some(String::from("PROD {title}"))
+        );
+        assert_eq!(decoded.profiles[0].colors, None);
+        assert_eq!(decoded.profiles[1].program, "");
+        assert_eq!(decoded.profiles[1].path, "/home/rio/work");
+        assert_eq!(decoded.profiles[1].title, None);
+        assert_eq!(decoded.profiles[0].bell, None);
+        let bell = decoded.profiles[1].bell.as_ref().unwrap();
+        assert_eq!(bell.sound, crate::config::bell::BellSound::None);
+        assert_eq!(bell.fallback, crate::config::bell::BellFallback::None);
+    }
+
+    #[test]
+    fn test_find_profile() {
+        let profile = |program: &str, path: &str| Profile {
+            program: program.to_string(),
+            path: path.to_string(),
+            ..Profile::default()
+        };
+        let profiles = [
+            profile("", ""),
+            profile("ssh", ""),
+            profile("nvim", "/home/rio/work"),
+            profile("", "/srv/*-prod"),
+        ];
+
+        assert_eq!(find_profile(&profiles, "ssh", "/tmp"), Some(1));
+        assert_eq!(
+            find_profile(&profiles, "nvim", "/home/rio/work/rio"),
+            Some(2)
+        );
+        assert_eq!(find_profile(&profiles, "nvim", "/home/rio/workspace"), None);
+        assert_eq!(
+            find_profile(&profiles, "zsh", "/srv/api-prod/logs"),
+            Some(3)
+        );
+        assert_eq!(find_profile(&profiles, "zsh", "/srv/api-staging"), None);
+        assert_eq!(find_profile(&profiles, "zsh", "/"), None);
+    }
+
+    #[test]
+    fn test_format_title() {
+        let profile = Profile {
+            program: String::from("ssh"),
+            title: Some(String::from("PROD {program}: {title}")),
+            ..Profile::default()
+        };
+
+        let vars = TitleVars {
+            program: "ssh",
+            command: "ssh db-1",
+            path: "/tmp",
+            title: "",
+            session: "ssh: db-1",
+        };
+
+        assert_eq!(
+            profile.format_title(&vars),
+            Some(String::from("PROD ssh: ssh db-1"))
+        );
+        assert_eq!(Profile::default().format_title(&vars), None);
+    }
+}
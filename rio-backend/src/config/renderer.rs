@@ -7,10 +7,35 @@ pub struct Renderer {
     pub performance: Performance,
     #[serde(default = "Backend::default", skip_serializing)]
     pub backend: Backend,
+    #[serde(default = "Option::default")]
+    pub adapter: Option<String>,
     #[serde(default = "bool::default", rename = "disable-unfocused-render")]
     pub disable_unfocused_render: bool,
     #[serde(default = "Option::default", rename = "target-fps")]
     pub target_fps: Option<u64>,
+    #[serde(default = "Option::default", rename = "max-fps")]
+    pub max_fps: Option<u64>,
+    // Frames per second while the window is unfocused, 0 doesn't limit them
+    #[serde(default = "default_unfocused_fps", rename = "unfocused-fps")]
+    pub unfocused_fps: u64,
+    #[serde(default = "bool::default")]
+    pub hdr: bool,
+    #[serde(default = "PresentMode::default", rename = "present-mode")]
+    pub present_mode: PresentMode,
+    #[serde(default = "default_text_gamma", rename = "text-gamma")]
+    pub text_gamma: f32,
+    #[serde(default = "f32::default", rename = "text-contrast")]
+    pub text_contrast: f32,
+}
+
+#[inline]
+fn default_text_gamma() -> f32 {
+    1.0
+}
+
+#[inline]
+fn default_unfocused_fps() -> u64 {
+    10
 }
 
 #[allow(clippy::derivable_impls)]
@@ -19,12 +44,34 @@ impl Default for Renderer {
         Renderer {
             performance: Performance::default(),
             backend: Backend::default(),
+            adapter: None,
             disable_unfocused_render: false,
             target_fps: None,
+            max_fps: None,
+            unfocused_fps: default_unfocused_fps(),
+            hdr: false,
+            present_mode: PresentMode::default(),
+            text_gamma: default_text_gamma(),
+            text_contrast: 0.0,
         }
     }
 }
 
+#[derive(Default, Debug, Serialize, Deserialize, PartialEq, Clone, Copy)]
+pub enum PresentMode {
+    // Waits for the vertical blank, tearing only when a frame is late
+    // if the driver supports it
+    #[default]
+    #[serde(alias = "auto-vsync")]
+    AutoVsync,
+    // Replaces the queued frame with the newest one, no tearing
+    #[serde(alias = "mailbox")]
+    Mailbox,
+    // Presents right away, the lowest latency but can tear
+    #[serde(alias = "immediate")]
+    Immediate,
+}
+
 #[derive(Default, Debug, Serialize, Deserialize, PartialEq, Clone, Copy)]
 pub enum Performance {
     #[default]
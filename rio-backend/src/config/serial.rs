@@ -0,0 +1,38 @@
+use serde::{Deserialize, Serialize};
+
+#[inline]
+fn default_baud_rate() -> u32 {
+    115_200
+}
+
+#[derive(Default, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FlowControl {
+    #[default]
+    #[serde(alias = "none")]
+    None,
+    // XON/XOFF
+    #[serde(alias = "software")]
+    Software,
+    // RTS/CTS
+    #[serde(alias = "hardware")]
+    Hardware,
+}
+
+#[derive(PartialEq, Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct Serial {
+    // Used when `serial:<path>` doesn't specify a baud rate
+    #[serde(default = "default_baud_rate", rename = "baud-rate")]
+    pub baud_rate: u32,
+    // Used when `serial:<path>` doesn't specify a flow control
+    #[serde(default = "FlowControl::default", rename = "flow-control")]
+    pub flow_control: FlowControl,
+}
+
+impl Default for Serial {
+    fn default() -> Serial {
+        Serial {
+            baud_rate: default_baud_rate(),
+            flow_control: FlowControl::default(),
+        }
+    }
+}
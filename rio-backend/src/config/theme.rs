@@ -1,5 +1,7 @@
-use crate::config::colors::Colors;
+use crate::config::colors::{ColorArray, ColorBuilder, ColorRgb, Colors, Format};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
 
 #[derive(Default, Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub struct AdaptiveColors {
@@ -20,3 +22,139 @@ pub struct Theme {
     #[serde(default = "Colors::default")]
     pub colors: Colors,
 }
+
+/// Color scheme written by pywal (`~/.cache/wal/colors.json`), matugen
+/// and other wallpaper based generators that follow the same format.
+#[derive(Debug, Deserialize)]
+struct PywalScheme {
+    special: PywalSpecial,
+    colors: HashMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PywalSpecial {
+    background: String,
+    foreground: String,
+    cursor: Option<String>,
+}
+
+impl Theme {
+    /// Loads a theme from a file outside of the `themes` directory.
+    ///
+    /// Files with `.json` extension are parsed as pywal color schemes,
+    /// anything else is expected to be a regular Rio theme.
+    pub fn from_file(path: &Path) -> Result<Theme, String> {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(err_message) => return Err(format!("error reading: {:?}", err_message)),
+        };
+
+        if path.extension().is_some_and(|ext| ext == "json") {
+            return Theme::from_pywal(&content);
+        }
+
+        toml::from_str::<Theme>(&content)
+            .map_err(|err_message| format!("error parsing: {:?}", err_message))
+    }
+
+    pub fn from_pywal(content: &str) -> Result<Theme, String> {
+        let scheme = serde_json::from_str::<PywalScheme>(content)
+            .map_err(|err_message| format!("error parsing: {:?}", err_message))?;
+
+        let color = |hex: &str| -> Result<ColorBuilder, String> {
+            ColorBuilder::from_hex(hex.to_string(), Format::SRGB0_1)
+        };
+        let indexed = |index: usize| -> Result<ColorArray, String> {
+            match scheme.colors.get(&format!("color{index}")) {
+                Some(hex) => Ok(color(hex)?.to_arr()),
+                None => Err(format!("missing color{index}")),
+            }
+        };
+        let dim = |arr: ColorArray| -> ColorArray {
+            ColorRgb::from_color_arr(arr).to_arr_with_dim()
+        };
+
+        let background = color(&scheme.special.background)?;
+        let foreground = color(&scheme.special.foreground)?.to_arr();
+        let cursor = match &scheme.special.cursor {
+            Some(cursor) => color(cursor)?.to_arr(),
+            None => foreground,
+        };
+
+        let mut colors = Colors {
+            background: (background.to_arr(), background.to_wgpu()),
+            foreground,
+            cursor,
+            black: indexed(0)?,
+            red: indexed(1)?,
+            green: indexed(2)?,
+            yellow: indexed(3)?,
+            blue: indexed(4)?,
+            magenta: indexed(5)?,
+            cyan: indexed(6)?,
+            white: indexed(7)?,
+            light_black: indexed(8)?,
+            light_red: indexed(9)?,
+            light_green: indexed(10)?,
+            light_yellow: indexed(11)?,
+            light_blue: indexed(12)?,
+            light_magenta: indexed(13)?,
+            light_cyan: indexed(14)?,
+            light_white: indexed(15)?,
+            light_foreground: foreground,
+            dim_foreground: dim(foreground),
+            ..Colors::default()
+        };
+        colors.dim_black = dim(colors.black);
+        colors.dim_red = dim(colors.red);
+        colors.dim_green = dim(colors.green);
+        colors.dim_yellow = dim(colors.yellow);
+        colors.dim_blue = dim(colors.blue);
+        colors.dim_magenta = dim(colors.magenta);
+        colors.dim_cyan = dim(colors.cyan);
+        colors.dim_white = dim(colors.white);
+
+        Ok(Theme { colors })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::colors::hex_to_color_arr;
+
+    const PYWAL_COLORS: &str = r##"{
+        "wallpaper": "/home/rio/wallpaper.png",
+        "alpha": "100",
+        "special": {
+            "background": "#0F1419",
+            "foreground": "#C5C8C6",
+            "cursor": "#F07178"
+        },
+        "colors": {
+            "color0": "#0F1419", "color1": "#A54242", "color2": "#8C9440",
+            "color3": "#DE935F", "color4": "#5F819D", "color5": "#85678F",
+            "color6": "#5E8D87", "color7": "#707880", "color8": "#373B41",
+            "color9": "#CC6666", "color10": "#B5BD68", "color11": "#F0C674",
+            "color12": "#81A2BE", "color13": "#B294BB", "color14": "#8ABEB7",
+            "color15": "#C5C8C6"
+        }
+    }"##;
+
+    #[test]
+    fn test_pywal_scheme() {
+        let theme = Theme::from_pywal(PYWAL_COLORS).unwrap();
+        assert_eq!(theme.colors.background.0, hex_to_color_arr("#0F1419"));
+        assert_eq!(theme.colors.foreground, hex_to_color_arr("#C5C8C6"));
+        assert_eq!(theme.colors.cursor, hex_to_color_arr("#F07178"));
+        assert_eq!(theme.colors.red, hex_to_color_arr("#A54242"));
+        assert_eq!(theme.colors.light_white, hex_to_color_arr("#C5C8C6"));
+        assert_ne!(theme.colors.dim_red, theme.colors.red);
+    }
+
+    #[test]
+    fn test_pywal_scheme_missing_color() {
+        let content = PYWAL_COLORS.replace("\"color15\": \"#C5C8C6\"", "\"x\": \"\"");
+        assert!(Theme::from_pywal(&content).is_err());
+    }
+}
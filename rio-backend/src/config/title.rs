@@ -0,0 +1,87 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Default, Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct Title {
+    // Template of tab and window titles, when it's unset the tab shows
+    // the program and the window shows the title set by the program
+    #[serde(default = "Option::default")]
+    pub content: Option<String>,
+}
+
+/// Values of the placeholders in title templates.
+#[derive(Default, Debug, Clone, Copy)]
+pub struct TitleVars<'a> {
+    pub program: &'a str,
+    pub command: &'a str,
+    pub path: &'a str,
+    pub title: &'a str,
+    pub session: &'a str,
+}
+
+impl TitleVars<'_> {
+    /// Replaces {program}, {command}, {path}, {title} and {session}, {title}
+    /// falls back to the command when the program didn't set any.
+    pub fn format(&self, template: &str) -> String {
+        let title = if self.title.is_empty() {
+            self.command
+        } else {
+            self.title
+        };
+
+        template
+            .replace("{program}", self.program)
+            .replace("{command}", self.command)
+            .replace("{path}", self.path)
+            .replace("{title}", title)
+            .replace("{session}", self.session)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, Deserialize, PartialEq)]
+    struct Root {
+        #[serde(default = "Title::default")]
+        title: Title,
+    }
+
+    #[test]
+    fn test_title_content() {
+        let decoded = toml::from_str::<Root>("").unwrap();
+        assert_eq!(decoded.title.content, None);
+
+        let content = r#"
+            [title]
+            content = '{command} in {path}'
+        "#;
+
+        let decoded = toml::from_str::<Root>(content).unwrap();
+        assert_eq!(
+            decoded.title.content,
+            Some(String::from("{command} in {path}"))
+        );
+    }
+
+    #[test]
+    fn test_format() {
+        let vars = TitleVars {
+            program: "ssh",
+            command: "ssh host",
+            path: "/home/rio",
+            title: "",
+            session: "ssh: host",
+        };
+
+        assert_eq!(vars.format("{command} in {path}"), "ssh host in /home/rio");
+        assert_eq!(vars.format("[{session}] {path}"), "[ssh: host] /home/rio");
+        assert_eq!(vars.format("[{program}] {title}"), "[ssh] ssh host");
+
+        let vars = TitleVars {
+            title: "vim README.md",
+            ..vars
+        };
+        assert_eq!(vars.format("{title}"), "vim README.md");
+    }
+}
@@ -53,6 +53,8 @@ pub struct Window {
     pub blur: bool,
     #[serde(rename = "background-image", skip_serializing)]
     pub background_image: Option<ImageProperties>,
+    #[serde(default = "Option::default", rename = "background-shader")]
+    pub background_shader: Option<String>,
     #[serde(default = "Decorations::default")]
     pub decorations: Decorations,
 }
@@ -65,6 +67,7 @@ impl Default for Window {
             mode: WindowMode::default(),
             opacity: default_opacity(),
             background_image: None,
+            background_shader: None,
             decorations: Decorations::default(),
             blur: false,
         }
@@ -30,6 +30,8 @@ pub enum Attr {
     Hidden,
     /// Strikeout text.
     Strike,
+    /// Overlined text.
+    Overline,
     /// Cancel bold.
     CancelBold,
     /// Cancel bold and dim.
@@ -46,6 +48,8 @@ pub enum Attr {
     CancelHidden,
     /// Cancel strikeout.
     CancelStrike,
+    /// Cancel overline.
+    CancelOverline,
     /// Set indexed foreground color.
     Foreground(AnsiColor),
     /// Set indexed background color.
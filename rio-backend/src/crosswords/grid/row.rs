@@ -2,15 +2,30 @@
 // https://github.com/alacritty/alacritty/blob/e35e5ad14fce8456afdd89f2b392b9924bb27471/alacritty_terminal/src/grid/row.rs
 // which is licensed under Apache 2.0 license.
 
+use crate::ansi::LineSize;
 use crate::crosswords::grid::GridSquare;
 use crate::crosswords::square::Flags;
 use crate::crosswords::square::ResetDiscriminant;
 use crate::crosswords::Column;
+use bitflags::bitflags;
 use core::cmp::min;
 use std::cmp::max;
 use std::ops::{Index, IndexMut, Range, RangeFrom, RangeFull, RangeTo, RangeToInclusive};
 use std::{ptr, slice};
 
+bitflags! {
+    /// Shell integration (OSC 133) marks recorded for a row.
+    #[derive(Default, Clone, Copy, Debug, Eq, PartialEq)]
+    pub struct RowMarks: u8 {
+        /// Row where a prompt starts.
+        const PROMPT  = 0b0000_0001;
+        /// Row where the command input starts.
+        const COMMAND = 0b0000_0010;
+        /// Row where the command output starts.
+        const OUTPUT  = 0b0000_0100;
+    }
+}
+
 /// A row in the grid.
 #[derive(Default, Clone, Debug)]
 pub struct Row<T> {
@@ -21,6 +36,15 @@ pub struct Row<T> {
     /// This is the upper bound on the number of elements in the row, which have been modified
     /// since the last reset. All cells after this point are guaranteed to be equal.
     pub(crate) occ: usize,
+
+    /// Shell integration marks of this row.
+    pub marks: RowMarks,
+
+    /// Exit code of the command started by the prompt in this row.
+    pub exit_code: Option<i32>,
+
+    /// Line size set through DECDWL and DECDHL.
+    pub size: LineSize,
 }
 
 impl<T: PartialEq> PartialEq for Row<T> {
@@ -51,7 +75,13 @@ impl<T: Clone + Default> Row<T> {
             inner.set_len(columns);
         }
 
-        Row { inner, occ: 0 }
+        Row {
+            inner,
+            occ: 0,
+            marks: RowMarks::empty(),
+            exit_code: None,
+            size: LineSize::Normal,
+        }
     }
 
     /// Increase the number of columns in the row.
@@ -114,6 +144,9 @@ impl<T: Clone + Default> Row<T> {
         }
 
         self.occ = 0;
+        self.marks = RowMarks::empty();
+        self.exit_code = None;
+        self.size = LineSize::Normal;
     }
 }
 
@@ -121,7 +154,13 @@ impl<T: Clone + Default> Row<T> {
 impl<T> Row<T> {
     #[inline]
     pub fn from_vec(vec: Vec<T>, occ: usize) -> Row<T> {
-        Row { inner: vec, occ }
+        Row {
+            inner: vec,
+            occ,
+            marks: RowMarks::empty(),
+            exit_code: None,
+            size: LineSize::Normal,
+        }
     }
 
     #[inline]
@@ -17,6 +17,7 @@
 pub mod attr;
 pub mod grid;
 pub mod pos;
+pub mod prediction;
 pub mod search;
 pub mod square;
 pub mod vi_mode;
@@ -28,10 +29,13 @@ use crate::ansi::graphics::UpdateQueues;
 use crate::ansi::mode::NamedMode;
 use crate::ansi::mode::NamedPrivateMode;
 use crate::ansi::mode::PrivateMode;
+use crate::ansi::private_graphics::PrivateGraphic;
 use crate::ansi::sixel;
+use crate::ansi::terminfo;
 use crate::ansi::{
     mode::Mode as AnsiMode, ClearMode, CursorShape, KeyboardModes,
-    KeyboardModesApplyBehavior, LineClearMode, TabulationClearMode,
+    KeyboardModesApplyBehavior, LineClearMode, LineSize, RectangularArea, SemanticPrompt,
+    TabulationClearMode,
 };
 use crate::clipboard::ClipboardType;
 use crate::config::colors::{
@@ -40,20 +44,23 @@ use crate::config::colors::{
     AnsiColor, ColorRgb,
 };
 use crate::crosswords::grid::{BidirectionalIterator, Dimensions, Grid, Scroll};
+use crate::event::sync::FrameCounter;
 use crate::event::WindowId;
-use crate::event::{EventListener, RioEvent};
+use crate::event::{CommandFinished, EventListener, PrivateOsc, RioEvent};
 use crate::performer::handler::Handler;
 use crate::selection::{Selection, SelectionRange, SelectionType};
 use attr::*;
 use base64::{engine::general_purpose, Engine as _};
 use bitflags::bitflags;
 use copa::Params;
-use grid::row::Row;
+use grid::row::{Row, RowMarks};
 use pos::{
     Boundary, CharsetIndex, Column, Cursor, CursorState, Direction, Line, Pos, Side,
 };
+use prediction::Predictions;
 use square::{Hyperlink, LineLength, Square};
-use std::collections::HashSet;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
 use std::mem;
 use std::ops::{Index, IndexMut, Range};
 use std::option::Option;
@@ -72,6 +79,27 @@ pub const MIN_LINES: usize = 1;
 /// Max. number of graphics stored in a single cell.
 const MAX_GRAPHICS_PER_CELL: usize = 20;
 
+/// Max. number of zero width characters stored in a single cell, the
+/// following ones are dropped.
+const MAX_ZEROWIDTH_PER_CELL: usize = 32;
+
+/// Max. number of squares read on each side of the mouse when looking for a
+/// URL, so very long lines without spaces (e.g. minified JSON) stay cheap.
+const MAX_HYPERLINK_SEARCH: usize = 4096;
+
+const ZERO_WIDTH_JOINER: char = '\u{200d}';
+const VARIATION_SELECTOR_16: char = '\u{fe0f}';
+
+#[inline]
+fn is_regional_indicator(c: char) -> bool {
+    ('\u{1f1e6}'..='\u{1f1ff}').contains(&c)
+}
+
+#[inline]
+fn is_emoji_modifier(c: char) -> bool {
+    ('\u{1f3fb}'..='\u{1f3ff}').contains(&c)
+}
+
 bitflags! {
     #[derive(Debug, Copy, Clone)]
      pub struct Mode: u32 {
@@ -99,6 +127,8 @@ bitflags! {
         const REPORT_ALTERNATE_KEYS   = 1 << 20;
         const REPORT_ALL_KEYS_AS_ESC  = 1 << 21;
         const REPORT_ASSOCIATED_TEXT  = 1 << 22;
+        const GRAPHEME_CLUSTERS       = 1 << 23;
+        const LEFT_RIGHT_MARGIN       = 1 << 24;
         const MOUSE_MODE = Self::MOUSE_REPORT_CLICK.bits() | Self::MOUSE_MOTION.bits() | Self::MOUSE_DRAG.bits();
         const KITTY_KEYBOARD_PROTOCOL = Self::DISAMBIGUATE_ESC_CODES.bits()
                                       | Self::REPORT_EVENT_TYPES.bits()
@@ -272,7 +302,6 @@ impl TermDamageState {
         self.lines[line].expand(left, right);
     }
 
-    #[allow(dead_code)]
     fn damage_selection(
         &mut self,
         selection: SelectionRange,
@@ -353,6 +382,61 @@ impl IndexMut<Column> for TabStops {
     }
 }
 
+/// SGR parameters that reproduce the attributes of `template`.
+fn sgr_parameters(template: &Square) -> String {
+    fn push_color(params: &mut Vec<String>, color: AnsiColor, base: u8) {
+        match color {
+            AnsiColor::Named(named) if (named as usize) < 8 => {
+                params.push((base + named as u8).to_string())
+            }
+            AnsiColor::Named(named) if (named as usize) < 16 => {
+                params.push((base + 60 + named as u8 - 8).to_string())
+            }
+            AnsiColor::Named(_) => {}
+            AnsiColor::Indexed(index) => params.push(format!("{};5;{index}", base + 8)),
+            AnsiColor::Spec(rgb) => {
+                params.push(format!("{};2;{};{};{}", base + 8, rgb.r, rgb.g, rgb.b))
+            }
+        }
+    }
+
+    let mut params = vec![String::from("0")];
+    let flags = template.flags;
+    for (flag, param) in [
+        (square::Flags::BOLD, "1"),
+        (square::Flags::DIM, "2"),
+        (square::Flags::ITALIC, "3"),
+        (square::Flags::UNDERLINE, "4"),
+        (square::Flags::DOUBLE_UNDERLINE, "4:2"),
+        (square::Flags::UNDERCURL, "4:3"),
+        (square::Flags::DOTTED_UNDERLINE, "4:4"),
+        (square::Flags::DASHED_UNDERLINE, "4:5"),
+        (square::Flags::INVERSE, "7"),
+        (square::Flags::HIDDEN, "8"),
+        (square::Flags::STRIKEOUT, "9"),
+        (square::Flags::OVERLINE, "53"),
+    ] {
+        if flags.contains(flag) {
+            params.push(String::from(param));
+        }
+    }
+
+    push_color(&mut params, template.fg, 30);
+    push_color(&mut params, template.bg, 40);
+    if let Some(color) = template.underline_color() {
+        // Underline color has no 8 and 16 colors parameters.
+        let color = match color {
+            AnsiColor::Named(named) if (named as usize) < 16 => {
+                AnsiColor::Indexed(named as u8)
+            }
+            color => color,
+        };
+        push_color(&mut params, color, 50);
+    }
+
+    params.join(";")
+}
+
 /// Terminal version for escape sequence reports.
 ///
 /// This returns the current terminal version as a unique number based on rio's
@@ -380,6 +464,10 @@ const TITLE_STACK_MAX_DEPTH: usize = 4096;
 // Max size of the keyboard modes.
 const KEYBOARD_MODE_STACK_MAX_DEPTH: usize = 16384;
 
+// Max number of user vars and max length of their names and values.
+const USER_VARS_MAX: usize = 64;
+const USER_VAR_MAX_LEN: usize = 1024;
+
 #[derive(Debug)]
 pub struct Crosswords<U>
 where
@@ -392,20 +480,56 @@ where
     pub grid: Grid<Square>,
     inactive_grid: Grid<Square>,
     scroll_region: Range<Line>,
+    /// Columns between the left and right margins (DECSLRM).
+    horizontal_margins: Range<Column>,
     tabs: TabStops,
     event_proxy: U,
     pub selection: Option<Selection>,
     #[allow(dead_code)]
     colors: List,
     pub title: String,
+    /// Working directory reported by the shell through OSC 7.
+    pub current_directory: Option<std::path::PathBuf>,
+    /// Unknown OSC codes forwarded to the user hooks.
+    pub osc_hooks: Option<std::ops::RangeInclusive<u16>>,
+    /// Values published by the shell with OSC 1337 SetUserVar.
+    pub user_vars: HashMap<String, String>,
     damage: TermDamageState,
     graphics: Graphics,
     pub cursor_shape: CursorShape,
     pub default_cursor_shape: CursorShape,
+    /// Blinking restored by DECSCUSR 0 and resets.
+    pub default_blinking_cursor: bool,
     pub blinking_cursor: bool,
+    pub allow_private_graphics: bool,
+    /// Allow programs to minimize and restore the window with XTWINOPS.
+    pub allow_window_ops: bool,
+    /// Keep the terminal open once the child process exits.
+    pub hold: bool,
+    /// Child process exited while holding, input should respawn it.
+    pub exited: bool,
+    /// Showing a stream read from stdin, there is no tty to turn line feeds
+    /// into new lines and the output stays once the stream ends.
+    pub pager: bool,
+    /// Jump to the bottom on new output while scrolled up.
+    pub scroll_to_bottom_on_output: bool,
+    /// Lines printed below the viewport since it was scrolled up.
+    pub unseen_lines: usize,
+    /// Frames drawn by the renderer, used to throttle parsing under flood.
+    pub frames: Arc<FrameCounter>,
+    pub predictions: Predictions,
     pub window_id: WindowId,
     pub route_id: usize,
     title_stack: Vec<String>,
+    // Whether the shell is showing a prompt (between OSC 133 A and C).
+    is_at_prompt: bool,
+    // Whether the shell redraws its prompt on resize.
+    prompt_redraw: bool,
+    // Column where the command input starts (OSC 133 B), its line is the
+    // last one marked with `RowMarks::COMMAND`.
+    command_start_column: Column,
+    // Command line and start of the running command (OSC 133 C).
+    running_command: Option<(String, std::time::Instant)>,
     hyperlink_re: regex::Regex,
 
     // The stack for the keyboard modes.
@@ -444,10 +568,14 @@ impl<U: EventListener> Crosswords<U> {
             inactive_grid: alt,
             active_charset: CharsetIndex::default(),
             scroll_region,
+            horizontal_margins: Column(0)..Column(cols),
             event_proxy,
             colors,
             hyperlink_re: regex::Regex::new(url_regex).unwrap(),
             title: String::from(""),
+            current_directory: None,
+            user_vars: HashMap::new(),
+            osc_hooks: None,
             tabs: TabStops::new(cols),
             mode: Mode::SHOW_CURSOR
                 | Mode::LINE_WRAP
@@ -456,11 +584,25 @@ impl<U: EventListener> Crosswords<U> {
             damage: TermDamageState::new(cols, rows),
             graphics: Graphics::new(&dimensions),
             default_cursor_shape: cursor_shape,
+            default_blinking_cursor: false,
             cursor_shape,
             blinking_cursor: false,
+            allow_private_graphics: false,
+            allow_window_ops: false,
+            hold: false,
+            exited: false,
+            pager: false,
+            scroll_to_bottom_on_output: false,
+            unseen_lines: 0,
+            frames: Arc::new(FrameCounter::default()),
+            predictions: Predictions::default(),
             window_id,
             route_id,
             title_stack: Default::default(),
+            is_at_prompt: false,
+            prompt_redraw: true,
+            command_start_column: Column(0),
+            running_command: None,
             keyboard_mode_stack: Default::default(),
             inactive_keyboard_mode_stack: Default::default(),
         }
@@ -485,6 +627,112 @@ impl<U: EventListener> Crosswords<U> {
         self.clear_screen(ClearMode::Saved);
     }
 
+    /// Clear the screen and the scrollback, the current prompt is moved to
+    /// the top of the screen.
+    pub fn clear_screen_and_history(&mut self) {
+        if self.mode.contains(Mode::ALT_SCREEN) {
+            self.clear_saved_history();
+            return;
+        }
+
+        let cursor_row = self.grid.cursor.pos.row;
+        let start = self.prompt_start_line().unwrap_or(cursor_row);
+        let kept: Vec<_> = (start.0..=cursor_row.0)
+            .map(|line| self.grid[Line(line)].clone())
+            .collect();
+
+        self.grid.clear_history();
+        self.grid.reset_region(..);
+        for (line, row) in kept.into_iter().enumerate() {
+            self.grid[Line(line as i32)] = row;
+        }
+        self.grid.cursor.pos.row = cursor_row - start;
+
+        self.selection = None;
+        self.vi_mode_cursor.pos.row = self
+            .vi_mode_cursor
+            .pos
+            .row
+            .grid_clamp(&self.grid, Boundary::Cursor);
+        self.mark_fully_damaged();
+    }
+
+    /// Lines addressable by the application, relative to the scrolling region
+    /// in origin mode.
+    fn addressable_lines(&self) -> (Line, Line) {
+        if self.mode.contains(Mode::ORIGIN) {
+            (self.scroll_region.start, self.scroll_region.end - 1)
+        } else {
+            (Line(0), self.grid.bottommost_line())
+        }
+    }
+
+    /// Resolve a DEC rectangular area to inclusive screen positions, `None` if
+    /// the area is empty.
+    fn rectangular_area(&self, area: RectangularArea) -> Option<(Pos, Pos)> {
+        let (origin, last_line) = self.addressable_lines();
+        let last_column = self.grid.last_column();
+
+        let top = origin + area.top.saturating_sub(1);
+        let left = Column(area.left.saturating_sub(1));
+        let bottom = area.bottom.map_or(last_line, |bottom| {
+            std::cmp::min(origin + bottom.saturating_sub(1), last_line)
+        });
+        let right = area.right.map_or(last_column, |right| {
+            std::cmp::min(Column(right.saturating_sub(1)), last_column)
+        });
+
+        (top <= bottom && left <= right)
+            .then(|| (Pos::new(top, left), Pos::new(bottom, right)))
+    }
+
+    /// Replace every square of a rectangular area.
+    fn fill_rectangle(&mut self, start: Pos, end: Pos, square: Square) {
+        for line in start.row.0..=end.row.0 {
+            for cell in &mut self.grid[Line(line)][start.col..end.col + 1] {
+                *cell = square.clone();
+            }
+        }
+
+        self.selection = self
+            .selection
+            .take()
+            .filter(|s| !s.intersects_range(start.row..=end.row));
+        self.mark_fully_damaged();
+    }
+
+    /// First line of the prompt the shell is currently showing.
+    fn prompt_start_line(&self) -> Option<Line> {
+        if !self.is_at_prompt {
+            return None;
+        }
+
+        let mut line = self.grid.cursor.pos.row;
+        while line >= 0 {
+            if self.grid[line].marks.contains(RowMarks::PROMPT) {
+                return Some(line);
+            }
+            line -= 1;
+        }
+
+        None
+    }
+
+    /// Start of the command input of the prompt the shell is currently
+    /// showing (OSC 133 B).
+    fn command_input_start(&self) -> Option<Pos> {
+        let prompt = self.prompt_start_line()?;
+        let mut line = self.grid.cursor.pos.row;
+        while line >= prompt {
+            if self.grid[line].marks.contains(RowMarks::COMMAND) {
+                return Some(Pos::new(line, self.command_start_column));
+            }
+            line -= 1;
+        }
+
+        None
+    }
+
     #[inline]
     pub fn scroll_display(&mut self, scroll: Scroll) {
         let old_display_offset = self.grid.display_offset();
@@ -492,6 +740,11 @@ impl<U: EventListener> Crosswords<U> {
             .send_event(RioEvent::MouseCursorDirty, self.window_id);
         self.grid.scroll_display(scroll);
 
+        // Counting starts over once the viewport leaves or reaches the bottom.
+        if old_display_offset == 0 || self.grid.display_offset() == 0 {
+            self.unseen_lines = 0;
+        }
+
         // Clamp vi mode cursor to the viewport.
         let viewport_start = -(self.grid.display_offset() as i32);
         let viewport_end = viewport_start + self.grid.bottommost_line().0;
@@ -535,16 +788,74 @@ impl<U: EventListener> Crosswords<U> {
             .send_event(RioEvent::CloseTerminal(self.route_id), self.window_id);
     }
 
+    /// Called once the child process exits. Closes the terminal, or with
+    /// `hold` keeps it open with a banner showing the exit status. A pager
+    /// just keeps its output.
+    pub fn child_exited(&mut self, exit_code: Option<i32>)
+    where
+        U: EventListener,
+    {
+        if self.pager {
+            self.mode.remove(Mode::SHOW_CURSOR);
+            self.event_proxy
+                .send_event(RioEvent::RenderRoute(self.route_id), self.window_id);
+            return;
+        }
+
+        if !self.hold {
+            self.exit();
+            return;
+        }
+
+        let status = match exit_code {
+            Some(code) => format!("Process exited with code {code}"),
+            None => String::from("Process exited"),
+        };
+        let message = format!(" {status}, press Enter to respawn ");
+
+        self.terminal_attribute(Attr::Reset);
+        if self.grid.cursor.pos.col != Column(0) {
+            self.carriage_return();
+            self.linefeed();
+        }
+        self.terminal_attribute(Attr::Reverse);
+        for c in message.chars() {
+            self.input(c);
+        }
+        self.terminal_attribute(Attr::Reset);
+        self.mode.remove(Mode::SHOW_CURSOR);
+        self.exited = true;
+
+        self.event_proxy
+            .send_event(RioEvent::RenderRoute(self.route_id), self.window_id);
+    }
+
     pub fn resize<S: Dimensions>(&mut self, size: S) {
         let old_cols = self.grid.columns();
         let old_lines = self.grid.screen_lines();
         let num_cols = size.columns();
         let num_lines = size.screen_lines();
 
+        // Update size information for graphics, the font size may change
+        // without changing the grid.
+        self.graphics.resize(&size);
+
         if old_cols == num_cols && old_lines == num_lines {
             info!("Crosswords::resize dimensions unchanged");
             return;
         }
+
+        let is_alt = self.mode.contains(Mode::ALT_SCREEN);
+
+        // Erase the prompt so the shell redraws it at the same position
+        // instead of leaving a copy wrapped by the old size behind.
+        if !is_alt && self.prompt_redraw {
+            if let Some(start) = self.prompt_start_line() {
+                self.grid.reset_region(start..);
+                self.grid.cursor.pos = Pos::new(start, Column(0));
+            }
+        }
+
         // Move vi mode cursor with the content.
         let history_size = self.history_size();
         let mut delta = num_lines as i32 - old_lines as i32;
@@ -554,7 +865,6 @@ impl<U: EventListener> Crosswords<U> {
         delta = std::cmp::min(std::cmp::max(delta, min_delta), history_size as i32);
         self.vi_mode_cursor.pos.row += delta;
 
-        let is_alt = self.mode.contains(Mode::ALT_SCREEN);
         self.grid.resize(!is_alt, num_lines, num_cols);
         self.inactive_grid.resize(is_alt, num_lines, num_cols);
 
@@ -579,14 +889,12 @@ impl<U: EventListener> Crosswords<U> {
         self.vi_mode_cursor.pos.col =
             std::cmp::min(vi_pos.pos.col, self.grid.last_column());
 
-        // Reset scrolling region.
+        // Reset scrolling region and margins.
         self.scroll_region = Line(0)..Line(self.grid.screen_lines() as i32);
+        self.horizontal_margins = Column(0)..Column(num_cols);
 
         // Resize damage information.
         self.damage.resize(num_cols, num_lines);
-
-        // Update size information for graphics.
-        self.graphics.resize(&size);
     }
 
     /// Toggle the vi mode.
@@ -677,6 +985,268 @@ impl<U: EventListener> Crosswords<U> {
         }
     }
 
+    /// Scroll display so the whole `range` is visible, or its start when
+    /// it doesn't fit in the viewport.
+    #[inline]
+    pub fn scroll_to_range(&mut self, range: &search::Match)
+    where
+        U: EventListener,
+    {
+        self.scroll_to_pos(*range.end());
+        self.scroll_to_pos(*range.start());
+    }
+
+    /// Damage the lines of a search match, so its highlight is redrawn
+    /// when it's focused or left.
+    #[inline]
+    pub fn damage_search_match(&mut self, range: &search::Match) {
+        let selection = SelectionRange::new(*range.start(), *range.end(), false);
+        self.damage.damage_selection(
+            selection,
+            self.grid.display_offset(),
+            self.grid.columns(),
+        );
+    }
+
+    /// Scroll display so the closest prompt above the viewport top
+    /// (marked via OSC 133) becomes the first visible line.
+    #[inline]
+    pub fn scroll_to_previous_prompt(&mut self) {
+        let viewport_top = Line(-(self.grid.display_offset() as i32));
+        let topmost_line = self.grid.topmost_line();
+
+        let mut line: Line = viewport_top - 1;
+        while line >= topmost_line {
+            if self.grid[line].marks.contains(RowMarks::PROMPT) {
+                self.scroll_display(Scroll::Delta(viewport_top.0 - line.0));
+                return;
+            }
+            line -= 1;
+        }
+    }
+
+    /// Scroll display so the closest prompt below the viewport top
+    /// (marked via OSC 133) becomes the first visible line.
+    #[inline]
+    pub fn scroll_to_next_prompt(&mut self) {
+        let viewport_top = Line(-(self.grid.display_offset() as i32));
+        let bottommost_line = self.grid.bottommost_line();
+
+        let mut line: Line = viewport_top + 1;
+        while line <= bottommost_line {
+            if self.grid[line].marks.contains(RowMarks::PROMPT) {
+                self.scroll_display(Scroll::Delta(viewport_top.0 - line.0));
+                return;
+            }
+            line += 1;
+        }
+
+        // No prompt left below, return to the bottom like a regular shell.
+        self.scroll_display(Scroll::Bottom);
+    }
+
+    /// Scroll display just enough to show the command input the shell is
+    /// editing, returns false when it isn't showing a prompt.
+    pub fn scroll_to_command_input(&mut self) -> bool {
+        if self.command_input_start().is_none() {
+            return false;
+        }
+
+        self.scroll_to_pos(self.grid.cursor.pos);
+        true
+    }
+
+    /// Number of arrow key presses moving the cursor of the shell to `pos`,
+    /// negative to the left. Returns `None` if `pos` is outside of the
+    /// command input (marked via OSC 133).
+    pub fn command_input_moves(&self, pos: Pos) -> Option<i32> {
+        let start = self.command_input_start()?;
+        if pos < start {
+            return None;
+        }
+
+        // Lines of the input wrapped after the cursor are part of it too.
+        let mut end = self.grid.cursor.pos.row;
+        while end < self.bottommost_line() && self.is_wrapped(end) {
+            end += 1;
+        }
+        if pos.row > end {
+            return None;
+        }
+
+        Some(
+            self.command_input_index(start, pos)
+                - self.command_input_index(start, self.grid.cursor.pos),
+        )
+    }
+
+    /// Characters of the command input between `start` and `pos`, each
+    /// line break counts as one.
+    fn command_input_index(&self, start: Pos, pos: Pos) -> i32 {
+        let mut index = 0;
+        let mut column = start.col;
+        let mut line = start.row;
+        while line < pos.row {
+            if self.is_wrapped(line) {
+                index += self.count_chars(line, column, Column(self.grid.columns()));
+            } else {
+                index +=
+                    self.count_chars(line, column, self.grid[line].line_length()) + 1;
+            }
+            column = Column(0);
+            line += 1;
+        }
+
+        let end = if self.is_wrapped(line) {
+            pos.col
+        } else {
+            pos.col.min(self.grid[line].line_length())
+        };
+        index + self.count_chars(line, column, end)
+    }
+
+    /// Characters between the columns `start` and `end` of `line`, wide
+    /// characters count once.
+    fn count_chars(&self, line: Line, start: Column, end: Column) -> i32 {
+        if start >= end {
+            return 0;
+        }
+
+        let spacers =
+            square::Flags::WIDE_CHAR_SPACER | square::Flags::LEADING_WIDE_CHAR_SPACER;
+        self.grid[line][start..end]
+            .iter()
+            .filter(|square| !square.flags.intersects(spacers))
+            .count() as i32
+    }
+
+    #[inline]
+    fn is_wrapped(&self, line: Line) -> bool {
+        self.grid[line][self.grid.last_column()]
+            .flags
+            .contains(square::Flags::WRAPLINE)
+    }
+
+    /// Line range of the output produced by the last command, based on
+    /// OSC 133 marks. Returns `None` if no output mark exists.
+    pub fn last_command_output(&self) -> Option<(Line, Line)> {
+        let cursor_line = self.grid.cursor.pos.row;
+        let topmost_line = self.grid.topmost_line();
+
+        let mut start = cursor_line;
+        loop {
+            if self.grid[start].marks.contains(RowMarks::OUTPUT) {
+                break;
+            }
+            if start <= topmost_line {
+                return None;
+            }
+            start -= 1;
+        }
+
+        let mut end = start;
+        while end < cursor_line {
+            if self.grid[end + 1i32].marks.contains(RowMarks::PROMPT) {
+                break;
+            }
+            end += 1;
+        }
+
+        Some((start, end))
+    }
+
+    /// Record typed text as predicted echo, see [`Predictions`].
+    pub fn predict_input(&mut self, text: &str) {
+        // Full screen programs don't echo input at the cursor.
+        if self.mode.contains(Mode::ALT_SCREEN) || self.display_offset() != 0 {
+            self.predictions.clear();
+            return;
+        }
+
+        let now = std::time::Instant::now();
+        let mut pos = match self.predictions.last() {
+            Some(last) => Pos::new(last.pos.row, last.pos.col + 1),
+            None if self.grid.cursor.should_wrap => return,
+            None => self.grid.cursor.pos,
+        };
+
+        for c in text.chars() {
+            // Only printable single width characters are predicted, control
+            // characters like enter start a new epoch.
+            if c.is_control() || c.width() != Some(1) {
+                self.predictions.clear();
+                return;
+            }
+
+            if pos.col >= self.grid.columns() {
+                return;
+            }
+
+            self.predictions.push(pos, c, now);
+            pos.col += 1;
+        }
+    }
+
+    /// Render visible predictions over `rows` and move the cursor after them.
+    pub fn overlay_predictions(
+        &self,
+        rows: &mut [Row<Square>],
+        cursor: &mut CursorState,
+    ) {
+        if self.display_offset() != 0 {
+            return;
+        }
+
+        for prediction in self.predictions.visible(std::time::Instant::now()) {
+            // Rows start at the top of the viewport.
+            let index = prediction.pos.row.0 + self.display_offset() as i32;
+            let Some(row) = usize::try_from(index).ok().and_then(|i| rows.get_mut(i))
+            else {
+                continue;
+            };
+
+            let square = &mut row[prediction.pos.col];
+            square.c = prediction.c;
+            square.flags.insert(square::Flags::UNDERLINE);
+            cursor.pos = Pos::new(
+                prediction.pos.row,
+                std::cmp::min(prediction.pos.col + 1, self.grid.last_column()),
+            );
+        }
+    }
+
+    /// Current value of a setting requested with DECRQSS, formatted as
+    /// the control function that would set it.
+    fn setting_report(&self, setting: &[u8]) -> Option<String> {
+        match setting {
+            // SGR
+            b"m" => Some(format!("{}m", sgr_parameters(&self.grid.cursor.template))),
+            // DECSTBM
+            b"r" => Some(format!(
+                "{};{}r",
+                self.scroll_region.start.0 + 1,
+                self.scroll_region.end.0
+            )),
+            // DECSCUSR
+            b" q" => {
+                let style = match self.cursor_shape {
+                    CursorShape::Block | CursorShape::Hidden => 1,
+                    CursorShape::Underline => 3,
+                    CursorShape::Beam => 5,
+                };
+                let style = if self.blinking_cursor {
+                    style
+                } else {
+                    style + 1
+                };
+                Some(format!("{style} q"))
+            }
+            // DECSCL, VT200 conformance level with 7-bit controls
+            b"\"p" => Some(String::from("62;1\"p")),
+            _ => None,
+        }
+    }
+
     /// Jump to the end of a wide cell.
     pub fn expand_wide(&self, mut pos: Pos, direction: Direction) -> Pos {
         let flags = self.grid[pos.row][pos.col].flags;
@@ -730,6 +1300,7 @@ impl<U: EventListener> Crosswords<U> {
             .flags
             .insert(square::Flags::WRAPLINE);
 
+        let left_margin = self.cursor_margins().start;
         if self.grid.cursor.pos.row + 1 >= self.scroll_region.end {
             self.linefeed();
         } else {
@@ -737,7 +1308,7 @@ impl<U: EventListener> Crosswords<U> {
             self.grid.cursor.pos.row += 1;
         }
 
-        self.grid.cursor.pos.col = Column(0);
+        self.grid.cursor.pos.col = left_margin;
         self.grid.cursor.should_wrap = false;
         self.damage_cursor();
     }
@@ -769,6 +1340,10 @@ impl<U: EventListener> Crosswords<U> {
         lines = std::cmp::min(lines, (self.scroll_region.end - origin).0 as usize);
 
         let region = origin..self.scroll_region.end;
+        if self.has_horizontal_margins() {
+            self.scroll_within_margins(region, lines, false);
+            return;
+        }
 
         // Scroll selection.
         self.selection = self
@@ -797,6 +1372,10 @@ impl<U: EventListener> Crosswords<U> {
         );
 
         let region = origin..self.scroll_region.end;
+        if self.has_horizontal_margins() {
+            self.scroll_within_margins(region, lines, true);
+            return;
+        }
 
         // Scroll selection.
         self.selection = self
@@ -806,6 +1385,14 @@ impl<U: EventListener> Crosswords<U> {
 
         self.grid.scroll_up(&region, lines);
 
+        if region.start == 0 && self.grid.display_offset() != 0 {
+            if self.scroll_to_bottom_on_output {
+                self.scroll_display(Scroll::Bottom);
+            } else {
+                self.unseen_lines = self.unseen_lines.saturating_add(lines);
+            }
+        }
+
         // Scroll vi mode cursor.
         let viewport_top = Line(-(self.grid.display_offset() as i32));
         let top = if region.start == 0 {
@@ -820,6 +1407,70 @@ impl<U: EventListener> Crosswords<U> {
         self.mark_fully_damaged();
     }
 
+    /// Whether DECSLRM margins narrower than the screen are set.
+    #[inline]
+    fn has_horizontal_margins(&self) -> bool {
+        self.horizontal_margins != (Column(0)..Column(self.grid.columns()))
+    }
+
+    /// Margins applying to the cursor, the whole line when it's outside of
+    /// the left and right margins.
+    #[inline]
+    fn cursor_margins(&self) -> Range<Column> {
+        let mut margins = if self.horizontal_margins.contains(&self.grid.cursor.pos.col) {
+            self.horizontal_margins.clone()
+        } else {
+            Column(0)..Column(self.grid.columns())
+        };
+        margins.end = std::cmp::min(
+            margins.end,
+            Column(self.line_columns(self.grid.cursor.pos.row)),
+        );
+        margins
+    }
+
+    /// Columns available in `line`, double width and double height lines
+    /// only fit half of them.
+    #[inline]
+    fn line_columns(&self, line: Line) -> usize {
+        let columns = self.grid.columns();
+        if self.grid[line].size.is_double() {
+            std::cmp::max(columns / 2, 1)
+        } else {
+            columns
+        }
+    }
+
+    /// Scroll the columns between the left and right margins of `region`,
+    /// the rest of the lines and the history are left untouched.
+    fn scroll_within_margins(&mut self, region: Range<Line>, lines: usize, up: bool) {
+        let columns = self.horizontal_margins.clone();
+        let mut rows: Vec<Vec<Square>> = (region.start.0..region.end.0)
+            .map(|line| self.grid[Line(line)][columns.clone()].to_vec())
+            .collect();
+
+        let lines = std::cmp::min(lines, rows.len());
+        let blank: Square = self.grid.cursor.template.bg.into();
+        let cleared = if up {
+            rows.rotate_left(lines);
+            rows.len() - lines..rows.len()
+        } else {
+            rows.rotate_right(lines);
+            0..lines
+        };
+        for row in &mut rows[cleared] {
+            row.fill(blank.clone());
+        }
+
+        for (line, squares) in (region.start.0..region.end.0).zip(rows) {
+            self.grid[Line(line)][columns.clone()].clone_from_slice(&squares);
+        }
+
+        let range = region.start..=region.end - 1;
+        self.selection = self.selection.take().filter(|s| !s.intersects_range(range));
+        self.mark_fully_damaged();
+    }
+
     #[inline]
     pub fn search_nearest_hyperlink_from_pos(
         &mut self,
@@ -853,7 +1504,7 @@ impl<U: EventListener> Crosswords<U> {
         let mut selection_end: Pos = pos;
 
         // Next adjacents squares
-        for square in self.grid.iter_from(pos) {
+        for square in self.grid.iter_from(pos).take(MAX_HYPERLINK_SEARCH) {
             if square.hyperlink().is_some() {
                 content.push_back(square.c);
                 selection_end = square.pos;
@@ -876,7 +1527,13 @@ impl<U: EventListener> Crosswords<U> {
 
         // Previous adjacents squares
         let mut iter = self.grid.iter_from(pos);
+        let mut searched = 0;
         while let Some(square) = iter.prev() {
+            searched += 1;
+            if searched > MAX_HYPERLINK_SEARCH {
+                break;
+            }
+
             if square.hyperlink().is_some() {
                 content.push_front(square.c);
                 selection_start = square.pos;
@@ -972,32 +1629,100 @@ impl<U: EventListener> Crosswords<U> {
         cursor_square.extra = extra;
     }
 
-    #[inline]
-    pub fn visible_rows(&self) -> Vec<Row<Square>> {
-        let mut start = self.scroll_region.start.0;
-        let mut end = self.scroll_region.end.0;
-        let mut visible_rows = Vec::with_capacity(self.grid.screen_lines());
+    /// Position of the last written square, which zero width characters are
+    /// attached to.
+    fn previous_square_pos(&self) -> Pos {
+        let mut pos = self.grid.cursor.pos;
+        if !self.grid.cursor.should_wrap {
+            pos.col.0 = pos.col.saturating_sub(1);
+        }
 
-        let scroll = self.display_offset() as i32;
-        if scroll != 0 {
-            start -= scroll;
-            end -= scroll;
+        // Put zerowidth characters over first fullwidth character cell.
+        if self.grid[pos.row][pos.col]
+            .flags
+            .contains(square::Flags::WIDE_CHAR_SPACER)
+        {
+            pos.col.0 = pos.col.saturating_sub(1);
         }
 
-        for row in start..end {
-            visible_rows.push(self.grid[Line(row)].clone());
+        pos
+    }
+
+    /// Whether `c` continues the grapheme cluster of the last written square.
+    fn extends_grapheme(&self, c: char) -> bool {
+        if self.grid.cursor.pos.col == 0 && !self.grid.cursor.should_wrap {
+            return false;
         }
 
-        visible_rows
+        let pos = self.previous_square_pos();
+        let square = &self.grid[pos.row][pos.col];
+        let zerowidth = square.zerowidth().unwrap_or_default();
+        match zerowidth.last() {
+            Some(&ZERO_WIDTH_JOINER) => true,
+            Some(_) => false,
+            None if is_emoji_modifier(c) => square.c.width() == Some(2),
+            None => is_regional_indicator(c) && is_regional_indicator(square.c),
+        }
     }
 
-    fn deccolm(&mut self)
-    where
+    /// Make the last written square take two columns when the cursor is right
+    /// after it.
+    fn widen_previous_square(&mut self, pos: Pos) {
+        if self.grid.cursor.should_wrap
+            || self.grid.cursor.pos != Pos::new(pos.row, pos.col + 1)
+            || self.grid[pos.row][pos.col]
+                .flags
+                .contains(square::Flags::WIDE_CHAR)
+        {
+            return;
+        }
+
+        self.grid[pos.row][pos.col]
+            .flags
+            .insert(square::Flags::WIDE_CHAR);
+        self.grid
+            .cursor
+            .template
+            .flags
+            .insert(square::Flags::WIDE_CHAR_SPACER);
+        self.write_at_cursor(' ');
+        self.grid
+            .cursor
+            .template
+            .flags
+            .remove(square::Flags::WIDE_CHAR_SPACER);
+
+        if self.grid.cursor.pos.col + 1 < self.grid.columns() {
+            self.grid.cursor.pos.col += 1;
+        } else {
+            self.grid.cursor.should_wrap = true;
+        }
+    }
+
+    #[inline]
+    pub fn visible_rows(&self) -> Vec<Row<Square>> {
+        self.rows_at(self.display_offset(), self.grid.screen_lines())
+    }
+
+    /// Up to `count` rows starting at the top of the viewport scrolled by
+    /// `display_offset`, which doesn't need to be the current one.
+    pub fn rows_at(&self, display_offset: usize, count: usize) -> Vec<Row<Square>> {
+        let start = -(display_offset.min(self.grid.history_size()) as i32);
+        let end = (start + count as i32).min(self.grid.screen_lines() as i32);
+
+        (start..end)
+            .map(|row| self.grid[Line(row)].clone())
+            .collect()
+    }
+
+    fn deccolm(&mut self)
+    where
         U: EventListener,
     {
         // Setting 132 column font makes no sense, but run the other side effects.
-        // Clear scrolling region.
+        // Clear scrolling region and margins.
         self.set_scrolling_region(1, None);
+        self.horizontal_margins = Column(0)..Column(self.grid.columns());
 
         // Clear grid.
         self.grid.reset_region(..);
@@ -1045,6 +1770,33 @@ impl<U: EventListener> Crosswords<U> {
         CursorState { pos, content }
     }
 
+    /// Replaces `{user_var:NAME}` with the value published with SetUserVar,
+    /// unset user vars are replaced by nothing.
+    pub fn expand_user_vars<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        const PREFIX: &str = "{user_var:";
+        if !text.contains(PREFIX) {
+            return Cow::Borrowed(text);
+        }
+
+        let mut expanded = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(start) = rest.find(PREFIX) {
+            let Some(end) = rest[start..].find('}') else {
+                break;
+            };
+
+            expanded.push_str(&rest[..start]);
+            let name = &rest[start + PREFIX.len()..start + end];
+            if let Some(value) = self.user_vars.get(name) {
+                expanded.push_str(value);
+            }
+            rest = &rest[start + end + 1..];
+        }
+        expanded.push_str(rest);
+
+        Cow::Owned(expanded)
+    }
+
     pub fn swap_alt(&mut self) {
         if !self.mode.contains(Mode::ALT_SCREEN) {
             // Set alt screen cursor to the current primary screen cursor.
@@ -1076,6 +1828,35 @@ impl<U: EventListener> Crosswords<U> {
         self.mark_fully_damaged();
     }
 
+    /// Moves the primary screen and scrollback of `previous` into this
+    /// terminal, the cursor continues on a new line below them.
+    pub fn restore_history(&mut self, previous: &mut Crosswords<U>)
+    where
+        U: EventListener,
+    {
+        let (lines, columns) = (self.grid.screen_lines(), self.grid.columns());
+        let grid = if previous.mode.contains(Mode::ALT_SCREEN) {
+            &mut previous.inactive_grid
+        } else {
+            &mut previous.grid
+        };
+        mem::swap(&mut self.grid, grid);
+
+        self.grid.cursor.template = Square::default();
+        self.grid.saved_cursor = self.grid.cursor.clone();
+        if self.grid.screen_lines() != lines || self.grid.columns() != columns {
+            self.grid.resize(true, lines, columns);
+        }
+        self.grid.scroll_display(Scroll::Bottom);
+
+        if self.grid.cursor.pos.col.0 > 0 {
+            self.carriage_return();
+            self.linefeed();
+        }
+        self.selection = None;
+        self.mark_fully_damaged();
+    }
+
     pub fn selection_to_string(&self) -> Option<String> {
         let selection_range = self.selection.as_ref().and_then(|s| s.to_range(self))?;
         let SelectionRange { start, end, .. } = selection_range;
@@ -1112,6 +1893,36 @@ impl<U: EventListener> Crosswords<U> {
         Some(res)
     }
 
+    /// Command line typed after the last OSC 133 B mark, up to the cursor.
+    fn command_line(&self) -> String {
+        let topmost_line = self.grid.topmost_line();
+        let mut line = self.grid.cursor.pos.row;
+        while line >= topmost_line {
+            if self.grid[line].marks.contains(RowMarks::COMMAND) {
+                break;
+            }
+            line -= 1;
+        }
+        if line < topmost_line {
+            return String::new();
+        }
+
+        // The cursor is usually at the start of the line after the command.
+        let cursor = self.grid.cursor.pos;
+        let end = if cursor.col == 0 {
+            Pos::new(cursor.row - 1, self.grid.last_column())
+        } else {
+            Pos::new(cursor.row, cursor.col - 1)
+        };
+        let start = Pos::new(line, self.command_start_column);
+        if end < start {
+            return String::new();
+        }
+
+        let text = self.bounds_to_string(start, end);
+        text.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+
     pub fn bounds_to_string(&self, start: Pos, end: Pos) -> String {
         let mut res = String::new();
 
@@ -1347,6 +2158,7 @@ impl<U: EventListener> Handler for Crosswords<U> {
             }
             NamedPrivateMode::ShowCursor => self.mode.insert(Mode::SHOW_CURSOR),
             NamedPrivateMode::CursorKeys => self.mode.insert(Mode::APP_CURSOR),
+            NamedPrivateMode::ApplicationKeypad => self.mode.insert(Mode::APP_KEYPAD),
             // Mouse protocols are mutually exclusive.
             NamedPrivateMode::ReportMouseClicks => {
                 self.mode.remove(Mode::MOUSE_MODE);
@@ -1387,6 +2199,12 @@ impl<U: EventListener> Handler for Crosswords<U> {
                     .send_event(RioEvent::CursorBlinkingChange, self.window_id);
             }
             NamedPrivateMode::SyncUpdate => (),
+            NamedPrivateMode::GraphemeClustering => {
+                self.mode.insert(Mode::GRAPHEME_CLUSTERS)
+            }
+            NamedPrivateMode::LeftRightMargin => {
+                self.mode.insert(Mode::LEFT_RIGHT_MARGIN)
+            }
         }
     }
 
@@ -1430,6 +2248,7 @@ impl<U: EventListener> Handler for Crosswords<U> {
             }
             NamedPrivateMode::ShowCursor => self.mode.remove(Mode::SHOW_CURSOR),
             NamedPrivateMode::CursorKeys => self.mode.remove(Mode::APP_CURSOR),
+            NamedPrivateMode::ApplicationKeypad => self.mode.remove(Mode::APP_KEYPAD),
             NamedPrivateMode::ReportMouseClicks => {
                 self.mode.remove(Mode::MOUSE_REPORT_CLICK);
                 self.event_proxy
@@ -1454,12 +2273,18 @@ impl<U: EventListener> Handler for Crosswords<U> {
             NamedPrivateMode::Origin => self.mode.remove(Mode::ORIGIN),
             NamedPrivateMode::ColumnMode => self.deccolm(),
             NamedPrivateMode::BlinkingCursor => {
-                // TODO: Update it
-                // self.blinking_cursor = false;
-                // self.event_proxy
-                // .send_event(RioEvent::CursorBlinkingChange, self.window_id);
+                self.blinking_cursor = false;
+                self.event_proxy
+                    .send_event(RioEvent::CursorBlinkingChange, self.window_id);
             }
             NamedPrivateMode::SyncUpdate => (),
+            NamedPrivateMode::GraphemeClustering => {
+                self.mode.remove(Mode::GRAPHEME_CLUSTERS)
+            }
+            NamedPrivateMode::LeftRightMargin => {
+                self.mode.remove(Mode::LEFT_RIGHT_MARGIN);
+                self.horizontal_margins = Column(0)..Column(self.grid.columns());
+            }
         }
     }
 
@@ -1471,6 +2296,9 @@ impl<U: EventListener> Handler for Crosswords<U> {
                 NamedPrivateMode::CursorKeys => {
                     self.mode.contains(Mode::APP_CURSOR).into()
                 }
+                NamedPrivateMode::ApplicationKeypad => {
+                    self.mode.contains(Mode::APP_KEYPAD).into()
+                }
                 NamedPrivateMode::Origin => self.mode.contains(Mode::ORIGIN).into(),
                 NamedPrivateMode::LineWrap => self.mode.contains(Mode::LINE_WRAP).into(),
                 NamedPrivateMode::BlinkingCursor => self.blinking_cursor.into(),
@@ -1506,8 +2334,21 @@ impl<U: EventListener> Handler for Crosswords<U> {
                     self.mode.contains(Mode::BRACKETED_PASTE).into()
                 }
                 NamedPrivateMode::SyncUpdate => ModeState::Reset,
+                NamedPrivateMode::GraphemeClustering => {
+                    self.mode.contains(Mode::GRAPHEME_CLUSTERS).into()
+                }
+                NamedPrivateMode::LeftRightMargin => {
+                    self.mode.contains(Mode::LEFT_RIGHT_MARGIN).into()
+                }
                 NamedPrivateMode::ColumnMode => ModeState::NotSupported,
             },
+            PrivateMode::Unknown(80) => self.mode.contains(Mode::SIXEL_DISPLAY).into(),
+            PrivateMode::Unknown(1070) => {
+                self.mode.contains(Mode::SIXEL_PRIV_PALETTE).into()
+            }
+            PrivateMode::Unknown(8452) => {
+                self.mode.contains(Mode::SIXEL_CURSOR_TO_THE_RIGHT).into()
+            }
             PrivateMode::Unknown(_) => ModeState::NotSupported,
         };
 
@@ -1541,16 +2382,30 @@ impl<U: EventListener> Handler for Crosswords<U> {
 
     #[inline]
     fn goto(&mut self, line: Line, col: Column) {
-        let (y_offset, max_y) = if self.mode.contains(Mode::ORIGIN) {
-            (self.scroll_region.start, self.scroll_region.end - 1)
+        let (y_offset, max_y, x_offset, max_x) = if self.mode.contains(Mode::ORIGIN) {
+            (
+                self.scroll_region.start,
+                self.scroll_region.end - 1,
+                self.horizontal_margins.start,
+                self.horizontal_margins.end - 1,
+            )
         } else {
-            (Line(0), self.grid.bottommost_line())
+            (
+                Line(0),
+                self.grid.bottommost_line(),
+                Column(0),
+                self.grid.last_column(),
+            )
         };
 
         self.damage_cursor();
         self.grid.cursor.pos.row =
             std::cmp::max(std::cmp::min(line + y_offset, max_y), Line(0));
-        self.grid.cursor.pos.col = std::cmp::min(col, self.grid.last_column());
+        let max_x = std::cmp::min(
+            max_x,
+            Column(self.line_columns(self.grid.cursor.pos.row) - 1),
+        );
+        self.grid.cursor.pos.col = std::cmp::min(col + x_offset, max_x);
         self.damage_cursor();
         self.grid.cursor.should_wrap = false;
     }
@@ -1591,15 +2446,14 @@ impl<U: EventListener> Handler for Crosswords<U> {
 
         let old_col = self.grid.cursor.pos.col.0;
         for _ in 0..count {
-            let mut col = self.grid.cursor.pos.col;
-            for i in (0..(col.0)).rev() {
-                if self.tabs[Column(i)] {
-                    col = Column(i);
-                    break;
-                }
-            }
-            self.grid.cursor.pos.col = col;
+            let col = self.grid.cursor.pos.col;
+            self.grid.cursor.pos.col = (0..col.0)
+                .rev()
+                .map(Column)
+                .find(|&i| self.tabs[i])
+                .unwrap_or(Column(0));
         }
+        self.grid.cursor.should_wrap = false;
 
         let line = self.grid.cursor.pos.row.0 as usize;
         self.damage
@@ -1629,6 +2483,27 @@ impl<U: EventListener> Handler for Crosswords<U> {
         self.mark_fully_damaged();
     }
 
+    #[inline]
+    fn set_line_size(&mut self, size: LineSize) {
+        let line = self.grid.cursor.pos.row;
+        self.grid[line].size = size;
+
+        // Squares past the half of a double line are never displayed.
+        if size.is_double() {
+            let columns = self.line_columns(line);
+            let blank: Square = self.grid.cursor.template.bg.into();
+            for square in &mut self.grid[line][Column(columns)..] {
+                *square = blank.clone();
+            }
+            if self.grid.cursor.pos.col.0 >= columns {
+                self.grid.cursor.pos.col = Column(columns - 1);
+            }
+        }
+
+        let columns = self.grid.columns();
+        self.damage.damage_line(line.0 as usize, 0, columns - 1);
+    }
+
     #[inline]
     fn move_up(&mut self, rows: usize) {
         self.goto(self.grid.cursor.pos.row - rows, self.grid.cursor.pos.col)
@@ -1660,7 +2535,10 @@ impl<U: EventListener> Handler for Crosswords<U> {
         let origin = self.grid.cursor.pos.row;
         let lines = std::cmp::min(self.grid.screen_lines() - origin.0 as usize, lines);
 
-        if lines > 0 && self.scroll_region.contains(&origin) {
+        if lines > 0
+            && self.scroll_region.contains(&origin)
+            && self.horizontal_margins.contains(&self.grid.cursor.pos.col)
+        {
             self.scroll_up_relative(origin, lines);
         }
     }
@@ -1709,15 +2587,18 @@ impl<U: EventListener> Handler for Crosswords<U> {
 
     #[inline]
     fn delete_chars(&mut self, count: usize) {
-        let columns = self.grid.columns();
+        // Characters are shifted from the right margin.
+        let columns = self.horizontal_margins.end.0;
         let cursor = &self.grid.cursor;
         let bg = cursor.template.bg;
+        if !self.horizontal_margins.contains(&cursor.pos.col) {
+            return;
+        }
 
-        // Ensure deleting within terminal bounds.
-        let count = std::cmp::min(count, columns);
-
+        // Ensure deleting within the margins.
         let start = cursor.pos.col.0;
-        let end = std::cmp::min(start + count, columns - 1);
+        let count = std::cmp::min(count, columns - start);
+        let end = start + count;
         let num_cells = columns - end;
 
         let line = cursor.pos.row;
@@ -1732,11 +2613,60 @@ impl<U: EventListener> Handler for Crosswords<U> {
         // Clear last `count` cells in the row. If deleting 1 char, need to delete
         // 1 cell.
         let end = columns - count;
-        for cell in &mut row[end..] {
+        for cell in &mut row[end..columns] {
             *cell = bg.into();
         }
     }
 
+    fn copy_rectangular_area(&mut self, area: RectangularArea, top: usize, left: usize) {
+        let Some((start, end)) = self.rectangular_area(area) else {
+            return;
+        };
+
+        // Copy first, the source and destination may overlap.
+        let rows: Vec<Vec<Square>> = (start.row.0..=end.row.0)
+            .map(|line| self.grid[Line(line)][start.col..end.col + 1].to_vec())
+            .collect();
+
+        let (origin, last_line) = self.addressable_lines();
+        let dest_top = origin + top.saturating_sub(1);
+        let dest_left = Column(left.saturating_sub(1));
+        let last_column = self.grid.last_column();
+        if dest_top > last_line || dest_left > last_column {
+            return;
+        }
+
+        let dest_bottom = std::cmp::min(dest_top + (rows.len() - 1), last_line);
+        for (line, squares) in (dest_top.0..=dest_bottom.0).zip(rows) {
+            let row = &mut self.grid[Line(line)];
+            for (col, square) in (dest_left.0..=last_column.0).zip(squares) {
+                row[Column(col)] = square;
+            }
+        }
+
+        self.selection = self
+            .selection
+            .take()
+            .filter(|s| !s.intersects_range(dest_top..=dest_bottom));
+        self.mark_fully_damaged();
+    }
+
+    fn fill_rectangular_area(&mut self, c: char, area: RectangularArea) {
+        if let Some((start, end)) = self.rectangular_area(area) {
+            let mut square = self.grid.cursor.template.clone();
+            square.c = c;
+            self.fill_rectangle(start, end, square);
+        }
+    }
+
+    fn erase_rectangular_area(&mut self, area: RectangularArea) {
+        if let Some((start, end)) = self.rectangular_area(area) {
+            // Erased cells have current background color set.
+            let square = self.grid.cursor.template.bg.into();
+            self.fill_rectangle(start, end, square);
+        }
+    }
+
     #[inline]
     fn scroll_down(&mut self, lines: usize) {
         let origin = self.scroll_region.start;
@@ -1746,22 +2676,29 @@ impl<U: EventListener> Handler for Crosswords<U> {
     #[inline]
     fn insert_blank_lines(&mut self, lines: usize) {
         let origin = self.grid.cursor.pos.row;
-        if self.scroll_region.contains(&origin) {
+        if self.scroll_region.contains(&origin)
+            && self.horizontal_margins.contains(&self.grid.cursor.pos.col)
+        {
             self.scroll_down_relative(origin, lines);
         }
     }
 
     #[inline]
     fn insert_blank(&mut self, count: usize) {
+        // Characters are shifted up to the right margin.
+        let columns = self.horizontal_margins.end.0;
         let cursor = &self.grid.cursor;
         let bg = cursor.template.bg;
+        if !self.horizontal_margins.contains(&cursor.pos.col) {
+            return;
+        }
 
-        // Ensure inserting within terminal bounds
-        let count = std::cmp::min(count, self.grid.columns() - cursor.pos.col.0);
+        // Ensure inserting within the margins.
+        let count = std::cmp::min(count, columns - cursor.pos.col.0);
 
         let source = cursor.pos.col;
         let destination = cursor.pos.col.0 + count;
-        let num_cells = self.grid.columns() - destination;
+        let num_cells = columns - destination;
 
         let line = cursor.pos.row;
         self.damage
@@ -1800,13 +2737,20 @@ impl<U: EventListener> Handler for Crosswords<U> {
         }
         self.active_charset = Default::default();
         self.cursor_shape = self.default_cursor_shape;
+        self.blinking_cursor = self.default_blinking_cursor;
         self.grid.reset();
         self.inactive_grid.reset();
         self.scroll_region = Line(0)..Line(self.grid.screen_lines() as i32);
+        self.horizontal_margins = Column(0)..Column(self.grid.columns());
         self.tabs = TabStops::new(self.grid.columns());
         self.title_stack = Vec::new();
+        self.is_at_prompt = false;
+        self.prompt_redraw = true;
         self.keyboard_mode_stack = Vec::new();
         self.title = String::from("");
+        self.event_proxy
+            .send_event(RioEvent::ResetTitle(self.route_id), self.window_id);
+        self.user_vars.clear();
         self.selection = None;
         self.vi_mode_cursor = Default::default();
         self.keyboard_mode_stack = Default::default();
@@ -1879,29 +2823,94 @@ impl<U: EventListener> Handler for Crosswords<U> {
             }
             Attr::Hidden => cursor.template.flags.insert(square::Flags::HIDDEN),
             Attr::CancelHidden => cursor.template.flags.remove(square::Flags::HIDDEN),
+            Attr::BlinkSlow | Attr::BlinkFast => {
+                cursor.template.flags.insert(square::Flags::BLINK)
+            }
+            Attr::CancelBlink => cursor.template.flags.remove(square::Flags::BLINK),
             Attr::Strike => cursor.template.flags.insert(square::Flags::STRIKEOUT),
             Attr::CancelStrike => cursor.template.flags.remove(square::Flags::STRIKEOUT),
-            _ => {
-                warn!("Term got unhandled attr: {:?}", attr);
-            }
+            Attr::Overline => cursor.template.flags.insert(square::Flags::OVERLINE),
+            Attr::CancelOverline => cursor.template.flags.remove(square::Flags::OVERLINE),
         }
     }
 
     fn set_title(&mut self, title: Option<String>) {
         self.title = title.unwrap_or_default();
+
+        let event = if self.title.is_empty() {
+            RioEvent::ResetTitle(self.route_id)
+        } else {
+            RioEvent::Title(self.route_id, self.title.clone())
+        };
+        self.event_proxy.send_event(event, self.window_id);
+    }
+
+    #[inline]
+    fn set_current_directory(&mut self, path: std::path::PathBuf) {
+        debug!("Current directory reported as {path:?}");
+        self.current_directory = Some(path);
+    }
+
+    #[inline]
+    fn set_user_var(&mut self, name: &str, base64: &[u8]) {
+        let Ok(bytes) = general_purpose::STANDARD.decode(base64) else {
+            warn!("Invalid base64 value for user var {name}");
+            return;
+        };
+
+        // Values end up in key bindings written to the PTY, control
+        // characters would turn printed output into keystrokes.
+        let value: String = String::from_utf8_lossy(&bytes)
+            .chars()
+            .filter(|c| !c.is_control())
+            .collect();
+        if name.len() > USER_VAR_MAX_LEN || value.len() > USER_VAR_MAX_LEN {
+            warn!("User var {name:.32} is too long");
+            return;
+        }
+
+        if self.user_vars.len() >= USER_VARS_MAX && !self.user_vars.contains_key(name) {
+            warn!("Too many user vars, ignoring {name}");
+            return;
+        }
+
+        debug!("User var {name} set to {value:?}");
+        self.user_vars.insert(name.to_string(), value);
+    }
+
+    #[inline]
+    fn private_osc(&mut self, code: u16, payload: &[u8]) -> bool {
+        if !self
+            .osc_hooks
+            .as_ref()
+            .is_some_and(|codes| codes.contains(&code))
+        {
+            return false;
+        }
+
+        self.event_proxy.send_event(
+            RioEvent::PrivateOsc(PrivateOsc {
+                route_id: self.route_id,
+                code,
+                payload: String::from_utf8_lossy(payload).to_string(),
+            }),
+            self.window_id,
+        );
+        true
     }
 
     #[inline]
-    fn set_cursor_style(&mut self, style: Option<CursorShape>, _blinking: bool) {
+    fn set_cursor_style(&mut self, style: Option<CursorShape>, blinking: bool) {
         if let Some(cursor_shape) = style {
             self.cursor_shape = cursor_shape;
+            self.blinking_cursor = blinking;
         } else {
             self.cursor_shape = self.default_cursor_shape;
+            self.blinking_cursor = self.default_blinking_cursor;
         }
 
-        // self.blinking_cursor = blinking;
-        // self.event_proxy
-        //     .send_event(RioEvent::CursorBlinkingChange, self.window_id);
+        self.event_proxy
+            .send_event(RioEvent::CursorBlinkingChange, self.window_id);
     }
 
     #[inline]
@@ -1957,24 +2966,22 @@ impl<U: EventListener> Handler for Crosswords<U> {
             None => return,
         };
 
-        // Handle zero-width characters.
-        if width == 0 {
-            // // Get previous column.
-            let mut column = self.grid.cursor.pos.col;
-            if !self.grid.cursor.should_wrap {
-                column.0 = column.saturating_sub(1);
-            }
-
-            // // Put zerowidth characters over first fullwidth character cell.
-            let row = self.grid.cursor.pos.row;
-            if self.grid[row][column]
-                .flags
-                .contains(square::Flags::WIDE_CHAR_SPACER)
+        // Handle zero-width characters and the continuation of grapheme
+        // clusters.
+        let is_clustering = self.mode.contains(Mode::GRAPHEME_CLUSTERS);
+        if width == 0 || (is_clustering && self.extends_grapheme(c)) {
+            let pos = self.previous_square_pos();
+            let square = &mut self.grid[pos.row][pos.col];
+            if square.zerowidth().map_or(0, |zerowidth| zerowidth.len())
+                < MAX_ZEROWIDTH_PER_CELL
             {
-                column.0 = column.saturating_sub(1);
+                square.push_zerowidth(c);
             }
 
-            self.grid[row][column].push_zerowidth(c);
+            // Emoji presentation and flags take two columns.
+            if is_clustering && (c == VARIATION_SELECTOR_16 || is_regional_indicator(c)) {
+                self.widen_previous_square(pos);
+            }
             return;
         }
 
@@ -1982,7 +2989,8 @@ impl<U: EventListener> Handler for Crosswords<U> {
             self.wrapline();
         }
 
-        let columns = self.grid.columns();
+        // Text wraps at the right margin when the cursor is within margins.
+        let columns = self.cursor_margins().end.0;
         if self.mode.contains(Mode::INSERT) && self.grid.cursor.pos.col + width < columns
         {
             let line = self.grid.cursor.pos.row;
@@ -1994,6 +3002,11 @@ impl<U: EventListener> Handler for Crosswords<U> {
             }
         }
 
+        if !self.predictions.is_empty() {
+            let pos = self.grid.cursor.pos;
+            self.predictions.confirm(pos, c, std::time::Instant::now());
+        }
+
         if width == 1 {
             self.write_at_cursor(c);
         } else {
@@ -2058,8 +3071,8 @@ impl<U: EventListener> Handler for Crosswords<U> {
         match intermediate {
             None => {
                 tracing::trace!("Reporting primary device attributes");
-                let text = String::from("\x1b[?62;4;6;22c");
-                // let text = String::from("\x1b[?62;6;22c");
+                // VT220 (62) with sixel graphics (4) and ANSI color (22).
+                let text = String::from("\x1b[?62;4;22c");
                 self.event_proxy
                     .send_event(RioEvent::PtyWrite(text), self.window_id);
             }
@@ -2074,6 +3087,14 @@ impl<U: EventListener> Handler for Crosswords<U> {
         }
     }
 
+    #[inline]
+    fn report_version(&mut self) {
+        tracing::trace!("Reporting terminal version");
+        let text = format!("\x1bP>|Rio {}\x1b\\", env!("CARGO_PKG_VERSION"));
+        self.event_proxy
+            .send_event(RioEvent::PtyWrite(text), self.window_id);
+    }
+
     #[inline]
     fn report_keyboard_mode(&mut self) {
         let current_mode = self
@@ -2086,6 +3107,47 @@ impl<U: EventListener> Handler for Crosswords<U> {
             .send_event(RioEvent::PtyWrite(text), self.window_id);
     }
 
+    #[inline]
+    fn report_setting(&mut self, setting: &[u8]) {
+        tracing::trace!("Reporting setting {:?}", setting);
+        let text = match self.setting_report(setting) {
+            Some(value) => format!("\x1bP1$r{value}\x1b\\"),
+            None => String::from("\x1bP0$r\x1b\\"),
+        };
+        self.event_proxy
+            .send_event(RioEvent::PtyWrite(text), self.window_id);
+    }
+
+    #[inline]
+    fn report_terminfo_capabilities(&mut self, names: Vec<String>) {
+        fn hex_encode(bytes: &[u8]) -> String {
+            bytes.iter().fold(String::new(), |mut hex, byte| {
+                let _ = std::fmt::Write::write_fmt(&mut hex, format_args!("{byte:02X}"));
+                hex
+            })
+        }
+
+        for name in names {
+            let hex_name = hex_encode(name.as_bytes());
+            let text = match terminfo::lookup(&name) {
+                Some(terminfo::Capability::Boolean) => {
+                    format!("\x1bP1+r{hex_name}\x1b\\")
+                }
+                Some(terminfo::Capability::Number(number)) => {
+                    let value = hex_encode(number.to_string().as_bytes());
+                    format!("\x1bP1+r{hex_name}={value}\x1b\\")
+                }
+                Some(terminfo::Capability::String(value)) => {
+                    let value = hex_encode(&value);
+                    format!("\x1bP1+r{hex_name}={value}\x1b\\")
+                }
+                None => format!("\x1bP0+r{hex_name}\x1b\\"),
+            };
+            self.event_proxy
+                .send_event(RioEvent::PtyWrite(text), self.window_id);
+        }
+    }
+
     #[inline]
     fn push_keyboard_mode(&mut self, mode: KeyboardModes) {
         if self.keyboard_mode_stack.len() >= KEYBOARD_MODE_STACK_MAX_DEPTH {
@@ -2261,6 +3323,10 @@ impl<U: EventListener> Handler for Crosswords<U> {
             self.grid.cursor.pos.row += 1;
             self.damage_cursor();
         }
+
+        if self.pager {
+            self.carriage_return();
+        }
     }
 
     #[inline]
@@ -2268,11 +3334,64 @@ impl<U: EventListener> Handler for Crosswords<U> {
         self.tabs[self.grid.cursor.pos.col] = true;
     }
 
+    #[inline]
+    fn reset_tabs(&mut self) {
+        self.tabs = TabStops::new(self.grid.columns());
+    }
+
     #[inline]
     fn set_hyperlink(&mut self, hyperlink: Option<Hyperlink>) {
         self.grid.cursor.template.set_hyperlink(hyperlink);
     }
 
+    #[inline]
+    fn semantic_prompt(&mut self, mark: SemanticPrompt) {
+        let row = self.grid.cursor.pos.row;
+        match mark {
+            SemanticPrompt::PromptStart { redraw } => {
+                self.grid[row].marks.insert(RowMarks::PROMPT);
+                self.grid[row].exit_code = None;
+                self.is_at_prompt = true;
+                self.prompt_redraw = redraw;
+            }
+            SemanticPrompt::CommandStart => {
+                self.grid[row].marks.insert(RowMarks::COMMAND);
+                self.command_start_column = self.grid.cursor.pos.col;
+            }
+            SemanticPrompt::CommandExecuted => {
+                self.grid[row].marks.insert(RowMarks::OUTPUT);
+                self.is_at_prompt = false;
+                self.running_command =
+                    Some((self.command_line(), std::time::Instant::now()));
+            }
+            SemanticPrompt::CommandFinished(exit_code) => {
+                self.is_at_prompt = false;
+                if let Some((command, started_at)) = self.running_command.take() {
+                    self.event_proxy.send_event(
+                        RioEvent::CommandFinished(CommandFinished {
+                            route_id: self.route_id,
+                            command,
+                            exit_code,
+                            duration: started_at.elapsed(),
+                        }),
+                        self.window_id,
+                    );
+                }
+                // The exit status belongs to the prompt that started the command.
+                let topmost_line = self.grid.topmost_line();
+                let mut line = row;
+                while line >= topmost_line {
+                    if self.grid[line].marks.contains(RowMarks::PROMPT) {
+                        self.grid[line].exit_code = exit_code;
+                        self.mark_fully_damaged();
+                        break;
+                    }
+                    line -= 1;
+                }
+            }
+        }
+    }
+
     /// Set the indexed color value.
     #[inline]
     fn set_color(&mut self, _index: usize, _color: ColorRgb) {
@@ -2296,7 +3415,8 @@ impl<U: EventListener> Handler for Crosswords<U> {
 
     #[inline]
     fn bell(&mut self) {
-        warn!("[unimplemented] Bell");
+        self.event_proxy
+            .send_event(RioEvent::Bell(self.route_id), self.window_id);
     }
 
     #[inline]
@@ -2359,7 +3479,11 @@ impl<U: EventListener> Handler for Crosswords<U> {
 
     #[inline]
     fn carriage_return(&mut self) {
-        let new_col = 0;
+        let new_col = if self.grid.cursor.pos.col >= self.horizontal_margins.start {
+            self.horizontal_margins.start.0
+        } else {
+            0
+        };
         let row = self.grid.cursor.pos.row.0 as usize;
         self.damage
             .damage_line(row, new_col, self.grid.cursor.pos.col.0);
@@ -2369,7 +3493,23 @@ impl<U: EventListener> Handler for Crosswords<U> {
 
     #[inline]
     fn move_forward_tabs(&mut self, count: u16) {
-        tracing::trace!("[unimplemented] Moving forward {} tabs", count);
+        self.damage_cursor();
+
+        // Unlike HT, CHT only moves the cursor and never wraps.
+        let old_col = self.grid.cursor.pos.col.0;
+        let last_col = self.grid.columns() - 1;
+        for _ in 0..count {
+            let col = self.grid.cursor.pos.col;
+            self.grid.cursor.pos.col = (col.0 + 1..=last_col)
+                .map(Column)
+                .find(|&i| self.tabs[i])
+                .unwrap_or(Column(last_col));
+        }
+        self.grid.cursor.should_wrap = false;
+
+        let line = self.grid.cursor.pos.row.0 as usize;
+        self.damage
+            .damage_line(line, old_col, self.grid.cursor.pos.col.0);
     }
 
     #[inline]
@@ -2434,6 +3574,27 @@ impl<U: EventListener> Handler for Crosswords<U> {
         self.goto(Line(0), Column(0));
     }
 
+    #[inline]
+    fn set_left_right_margins(&mut self, left: usize, right: Option<usize>) {
+        // Without DECLRMM the sequence is SCOSC.
+        if !self.mode.contains(Mode::LEFT_RIGHT_MARGIN) {
+            self.save_cursor_position();
+            return;
+        }
+
+        let columns = self.grid.columns();
+        let right = std::cmp::min(right.unwrap_or(columns), columns);
+        if left >= right {
+            warn!("Invalid left and right margins: ({};{})", left, right);
+            return;
+        }
+
+        debug!("Setting left and right margins: ({};{})", left, right);
+
+        self.horizontal_margins = Column(left.max(1) - 1)..Column(right);
+        self.goto(Line(0), Column(0));
+    }
+
     #[inline]
     fn text_area_size_pixels(&mut self) {
         debug!("text_area_size_pixels");
@@ -2472,31 +3633,76 @@ impl<U: EventListener> Handler for Crosswords<U> {
     }
 
     #[inline]
-    fn graphics_attribute(&mut self, pi: u16, pa: u16) {
-        // From Xterm documentation:
-        //
-        //   CSI ? Pi ; Pa ; Pv S
-        //
-        //   Pi = 1  -> item is number of color registers.
-        //   Pi = 2  -> item is Sixel graphics geometry (in pixels).
-        //   Pi = 3  -> item is ReGIS graphics geometry (in pixels).
-        //
-        //   Pa = 1  -> read attribute.
-        //   Pa = 2  -> reset to default.
-        //   Pa = 3  -> set to value in Pv.
-        //   Pa = 4  -> read the maximum allowed value.
-        //
-        //   Pv is ignored by xterm except when setting (Pa == 3).
-        //   Pv = n <- A single integer is used for color registers.
-        //   Pv = width ; height <- Two integers for graphics geometry.
-        //
-        //   xterm replies with a control sequence of the same form:
-        //
-        //   CSI ? Pi ; Ps ; Pv S
-        //
-        //   where Ps is the status:
-        //   Ps = 0  <- success.
-        //   Ps = 1  <- error in Pi.
+    fn window_size_pixels(&mut self) {
+        debug!("window_size_pixels");
+        self.event_proxy.send_event(
+            RioEvent::WindowStateRequest(Arc::new(|state| {
+                format!("\x1b[4;{};{}t", state.height, state.width)
+            })),
+            self.window_id,
+        );
+    }
+
+    #[inline]
+    fn window_position(&mut self) {
+        debug!("window_position");
+        self.event_proxy.send_event(
+            RioEvent::WindowStateRequest(Arc::new(|state| {
+                format!("\x1b[3;{};{}t", state.x, state.y)
+            })),
+            self.window_id,
+        );
+    }
+
+    #[inline]
+    fn window_state(&mut self) {
+        debug!("window_state");
+        self.event_proxy.send_event(
+            RioEvent::WindowStateRequest(Arc::new(|state| {
+                let state = if state.minimized { 2 } else { 1 };
+                format!("\x1b[{state}t")
+            })),
+            self.window_id,
+        );
+    }
+
+    #[inline]
+    fn set_minimized(&mut self, minimized: bool) {
+        if !self.allow_window_ops {
+            debug!("Ignoring XTWINOPS minimize({minimized}), window ops are disabled");
+            return;
+        }
+
+        self.event_proxy
+            .send_event(RioEvent::Minimize(minimized), self.window_id);
+    }
+
+    #[inline]
+    fn graphics_attribute(&mut self, pi: u16, pa: u16) {
+        // From Xterm documentation:
+        //
+        //   CSI ? Pi ; Pa ; Pv S
+        //
+        //   Pi = 1  -> item is number of color registers.
+        //   Pi = 2  -> item is Sixel graphics geometry (in pixels).
+        //   Pi = 3  -> item is ReGIS graphics geometry (in pixels).
+        //
+        //   Pa = 1  -> read attribute.
+        //   Pa = 2  -> reset to default.
+        //   Pa = 3  -> set to value in Pv.
+        //   Pa = 4  -> read the maximum allowed value.
+        //
+        //   Pv is ignored by xterm except when setting (Pa == 3).
+        //   Pv = n <- A single integer is used for color registers.
+        //   Pv = width ; height <- Two integers for graphics geometry.
+        //
+        //   xterm replies with a control sequence of the same form:
+        //
+        //   CSI ? Pi ; Ps ; Pv S
+        //
+        //   where Ps is the status:
+        //   Ps = 0  <- success.
+        //   Ps = 1  <- error in Pi.
         //   Ps = 2  <- error in Pa.
         //   Ps = 3  <- failure.
         //
@@ -2609,6 +3815,20 @@ impl<U: EventListener> Handler for Crosswords<U> {
         }
     }
 
+    #[inline]
+    fn private_graphic(&mut self, graphic: PrivateGraphic) {
+        if !self.allow_private_graphics {
+            debug!(
+                "Ignoring private graphic, security.allow-private-graphics is disabled"
+            );
+            return;
+        }
+
+        if let Some(graphic) = graphic.render(self.graphics.cell_height as usize) {
+            self.insert_graphic(graphic, None);
+        }
+    }
+
     #[inline]
     fn insert_graphic(&mut self, graphic: GraphicData, palette: Option<Vec<ColorRgb>>) {
         let cell_width = self.graphics.cell_width as usize;
@@ -2858,11 +4078,11 @@ impl Dimensions for CrosswordsSize {
     }
 
     fn square_width(&self) -> f32 {
-        0.
+        self.square_width as f32
     }
 
     fn square_height(&self) -> f32 {
-        0.
+        self.square_height as f32
     }
 }
 
@@ -3171,6 +4391,47 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_search_nearest_hyperlink_from_pos_on_long_line() {
+        let size = CrosswordsSize::new(100, 60);
+        let window_id = crate::event::WindowId::from(0);
+        let mut term =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+
+        // A single wrapped line without spaces filling the screen
+        for c in "https://rio.io/".chars().chain("a".repeat(5985).chars()) {
+            term.input(c);
+        }
+
+        let result = term
+            .search_nearest_hyperlink_from_pos(Pos::new(pos::Line(0), pos::Column(0)))
+            .unwrap();
+        assert_eq!(result.start, Pos::new(Line(0), Column(0)));
+        assert_eq!(result.end, Pos::new(Line(40), Column(96)));
+        assert!(term.grid[Line(40)][Column(97)].hyperlink().is_none());
+    }
+
+    #[test]
+    fn test_zerowidth_is_capped() {
+        let size = CrosswordsSize::new(10, 2);
+        let window_id = crate::event::WindowId::from(0);
+        let mut term =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+
+        term.input('e');
+        for _ in 0..100 {
+            term.input('\u{301}');
+        }
+        term.input('f');
+
+        let row = &term.grid[Line(0)];
+        assert_eq!(
+            row[Column(0)].zerowidth().unwrap().len(),
+            MAX_ZEROWIDTH_PER_CELL
+        );
+        assert_eq!(row[Column(1)].c, 'f');
+    }
+
     #[test]
     fn test_search_nearest_hyperlink_from_pos_on_single_line() {
         let size = CrosswordsSize::new(20, 3);
@@ -3470,6 +4731,1049 @@ mod tests {
         );
     }
 
+    #[test]
+    fn restore_history() {
+        let window_id = crate::event::WindowId::from(0);
+        let mut previous = Crosswords::new(
+            CrosswordsSize::new(5, 3),
+            CursorShape::Block,
+            VoidListener {},
+            window_id,
+            0,
+        );
+        for character in "$ ls".chars() {
+            previous.input(character);
+        }
+        previous.swap_alt();
+        previous.input('x');
+
+        let size = CrosswordsSize::new(5, 3);
+        let mut term =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+        term.restore_history(&mut previous);
+
+        let line: String = term.grid[Line(0)][..]
+            .iter()
+            .map(|square| square.c)
+            .collect();
+        assert_eq!(line, "$ ls ");
+        assert_eq!(term.grid.cursor.pos, Pos::new(Line(1), Column(0)));
+        assert!(!term.mode.contains(Mode::ALT_SCREEN));
+    }
+
+    #[test]
+    fn private_osc_forwarded_in_range() {
+        let size = CrosswordsSize::new(10, 10);
+        let window_id = crate::event::WindowId::from(0);
+        let listener = RecordingListener::default();
+        let mut term =
+            Crosswords::new(size, CursorShape::Block, listener.clone(), window_id, 3);
+        term.osc_hooks = Some(7700..=7799);
+
+        let mut parser = crate::performer::handler::ParserProcessor::new();
+        for byte in b"\x1b]7701;deploy;ok\x07\x1b]7800;ignored\x07\x1b]foo\x07" {
+            parser.advance(&mut term, *byte);
+        }
+
+        let events = listener.0.lock().unwrap();
+        assert_eq!(events.len(), 1);
+        match &events[0] {
+            RioEvent::PrivateOsc(osc) => {
+                assert_eq!(osc.route_id, 3);
+                assert_eq!(osc.code, 7701);
+                assert_eq!(osc.payload, "deploy;ok");
+            }
+            event => panic!("unexpected event {event:?}"),
+        }
+    }
+
+    #[test]
+    fn set_user_var() {
+        let size = CrosswordsSize::new(10, 10);
+        let window_id = crate::event::WindowId::from(0);
+        let mut term =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+
+        let mut parser = crate::performer::handler::ParserProcessor::new();
+        for byte in
+            b"\x1b]1337;SetUserVar=venv=cmlvLWVudg==\x07\x1b]1337;SetUserVar=host=\x07"
+        {
+            parser.advance(&mut term, *byte);
+        }
+
+        assert_eq!(term.user_vars.get("venv"), Some(&String::from("rio-env")));
+        assert_eq!(term.user_vars.get("host"), Some(&String::new()));
+        assert_eq!(
+            term.expand_user_vars("({user_var:venv}) {user_var:user}~{user_var:host"),
+            "(rio-env) ~{user_var:host"
+        );
+        assert!(matches!(
+            term.expand_user_vars("\x1bOH"),
+            std::borrow::Cow::Borrowed(_)
+        ));
+
+        // "rm -rf ~\r\x9b" has its control characters stripped.
+        for byte in b"\x1b]1337;SetUserVar=cmd=cm0gLXJmIH4Nwps=\x07" {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(term.expand_user_vars("{user_var:cmd}"), "rm -rf ~");
+
+        for index in 0..USER_VARS_MAX {
+            term.set_user_var(&format!("var{index}"), b"");
+        }
+        assert_eq!(term.user_vars.len(), USER_VARS_MAX);
+        term.set_user_var("venv", b"");
+        assert_eq!(term.user_vars.get("venv"), Some(&String::new()));
+        term.set_user_var(&"x".repeat(USER_VAR_MAX_LEN + 1), b"");
+        assert_eq!(term.user_vars.len(), USER_VARS_MAX);
+
+        term.reset_state();
+        assert!(term.user_vars.is_empty());
+    }
+
+    #[test]
+    fn semantic_prompt_marks() {
+        let size = CrosswordsSize::new(10, 10);
+        let window_id = crate::event::WindowId::from(0);
+        let mut term =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+
+        // Prompt and command on line 0, two lines of output.
+        term.semantic_prompt(SemanticPrompt::PromptStart { redraw: true });
+        term.semantic_prompt(SemanticPrompt::CommandStart);
+        term.linefeed();
+        term.semantic_prompt(SemanticPrompt::CommandExecuted);
+        term.linefeed();
+        term.linefeed();
+        term.semantic_prompt(SemanticPrompt::CommandFinished(Some(1)));
+        term.semantic_prompt(SemanticPrompt::PromptStart { redraw: true });
+
+        assert!(term.grid[Line(0)]
+            .marks
+            .contains(RowMarks::PROMPT | RowMarks::COMMAND));
+        assert_eq!(term.grid[Line(0)].exit_code, Some(1));
+        assert!(term.grid[Line(1)].marks.contains(RowMarks::OUTPUT));
+        assert!(term.grid[Line(3)].marks.contains(RowMarks::PROMPT));
+        assert_eq!(term.grid[Line(3)].exit_code, None);
+
+        assert_eq!(term.last_command_output(), Some((Line(1), Line(2))));
+    }
+
+    #[test]
+    fn semantic_prompt_command_line() {
+        let size = CrosswordsSize::new(10, 4);
+        let window_id = crate::event::WindowId::from(0);
+        let mut term =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+
+        term.goto(Line(3), Column(0));
+        term.semantic_prompt(SemanticPrompt::PromptStart { redraw: true });
+        term.input('$');
+        term.input(' ');
+        term.semantic_prompt(SemanticPrompt::CommandStart);
+        for c in "sleep  10".chars() {
+            term.input(c);
+        }
+        // The command wraps and the last line scrolls up on enter.
+        term.linefeed();
+        term.carriage_return();
+        term.semantic_prompt(SemanticPrompt::CommandExecuted);
+
+        let (command, _) = term.running_command.as_ref().unwrap();
+        assert_eq!(command, "sleep 10");
+
+        term.semantic_prompt(SemanticPrompt::CommandFinished(Some(0)));
+        assert!(term.running_command.is_none());
+    }
+
+    #[test]
+    fn semantic_prompt_command_input_moves() {
+        let size = CrosswordsSize::new(10, 4);
+        let window_id = crate::event::WindowId::from(0);
+        let mut term =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+
+        for _ in 0..6 {
+            term.linefeed();
+        }
+        term.goto(Line(1), Column(0));
+        term.semantic_prompt(SemanticPrompt::PromptStart { redraw: true });
+        term.input('$');
+        term.input(' ');
+        term.semantic_prompt(SemanticPrompt::CommandStart);
+        // Wraps after "echo hel", the wide character counts once.
+        for c in "echo hello wあ".chars() {
+            term.input(c);
+        }
+        assert_eq!(term.grid.cursor.pos, Pos::new(Line(2), Column(6)));
+
+        assert_eq!(
+            term.command_input_moves(Pos::new(Line(1), Column(2))),
+            Some(-13)
+        );
+        assert_eq!(
+            term.command_input_moves(Pos::new(Line(1), Column(7))),
+            Some(-8)
+        );
+        assert_eq!(
+            term.command_input_moves(Pos::new(Line(2), Column(4))),
+            Some(-1)
+        );
+        // Past the end of the input.
+        assert_eq!(
+            term.command_input_moves(Pos::new(Line(2), Column(9))),
+            Some(0)
+        );
+        assert_eq!(term.command_input_moves(Pos::new(Line(1), Column(1))), None);
+        assert_eq!(term.command_input_moves(Pos::new(Line(3), Column(0))), None);
+
+        // Scrolled up, only until the input is visible.
+        term.scroll_display(Scroll::Delta(3));
+        assert!(term.scroll_to_command_input());
+        assert_eq!(term.display_offset(), 1);
+
+        term.semantic_prompt(SemanticPrompt::CommandExecuted);
+        assert_eq!(term.command_input_moves(Pos::new(Line(1), Column(2))), None);
+        assert!(!term.scroll_to_command_input());
+    }
+
+    #[derive(Clone, Default)]
+    struct RecordingListener(std::sync::Arc<std::sync::Mutex<Vec<RioEvent>>>);
+
+    impl EventListener for RecordingListener {
+        fn event(&self) -> (Option<RioEvent>, bool) {
+            (None, false)
+        }
+
+        fn send_event(&self, event: RioEvent, _id: WindowId) {
+            self.0.lock().unwrap().push(event);
+        }
+    }
+
+    #[test]
+    fn window_ops() {
+        let size = CrosswordsSize::new(10, 4);
+        let window_id = crate::event::WindowId::from(0);
+        let listener = RecordingListener::default();
+        let mut term =
+            Crosswords::new(size, CursorShape::Block, listener.clone(), window_id, 0);
+
+        // Minimize is ignored unless window ops are allowed.
+        term.set_minimized(true);
+        assert!(listener.0.lock().unwrap().is_empty());
+
+        term.allow_window_ops = true;
+        term.set_minimized(true);
+        term.window_state();
+        term.window_position();
+        term.window_size_pixels();
+
+        let state = crate::event::WindowState {
+            minimized: true,
+            x: 10,
+            y: 20,
+            width: 800,
+            height: 600,
+        };
+        let events = listener.0.lock().unwrap();
+        assert!(matches!(events[0], RioEvent::Minimize(true)));
+        let reports: Vec<String> = events[1..]
+            .iter()
+            .map(|event| match event {
+                RioEvent::WindowStateRequest(format) => format(state),
+                _ => panic!("unexpected event {event:?}"),
+            })
+            .collect();
+        assert_eq!(reports, ["\x1b[2t", "\x1b[3;10;20t", "\x1b[4;600;800t"]);
+    }
+
+    #[test]
+    fn child_exited_with_hold() {
+        let size = CrosswordsSize::new(80, 4);
+        let window_id = crate::event::WindowId::from(0);
+        let listener = RecordingListener::default();
+        let mut term =
+            Crosswords::new(size, CursorShape::Block, listener.clone(), window_id, 0);
+
+        term.input('$');
+        term.hold = true;
+        term.child_exited(Some(2));
+
+        let line: String = (0..52)
+            .map(|col| term.grid[Line(1)][Column(col)].c)
+            .collect();
+        assert_eq!(line, " Process exited with code 2, press Enter to respawn ");
+        assert!(term.grid[Line(1)][Column(0)]
+            .flags
+            .contains(square::Flags::INVERSE));
+        assert!(!term.mode().contains(Mode::SHOW_CURSOR));
+        assert!(term.exited);
+        {
+            let events = listener.0.lock().unwrap();
+            assert_eq!(events.len(), 1);
+            assert!(matches!(events[0], RioEvent::RenderRoute(0)));
+        }
+
+        term.hold = false;
+        term.child_exited(None);
+        let events = listener.0.lock().unwrap();
+        assert!(matches!(events[1], RioEvent::CloseTerminal(0)));
+    }
+
+    #[test]
+    fn scroll_to_search_match() {
+        let size = CrosswordsSize::new(10, 4);
+        let window_id = crate::event::WindowId::from(0);
+        let mut term =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+        for _ in 0..13 {
+            term.linefeed();
+        }
+        assert_eq!(term.grid.history_size(), 10);
+
+        // The whole match is brought into the viewport
+        let range = Pos::new(Line(-8), Column(0))..=Pos::new(Line(-6), Column(3));
+        term.scroll_to_range(&range);
+        assert_eq!(term.display_offset(), 8);
+
+        // Matches taller than the viewport show their start
+        term.scroll_display(Scroll::Bottom);
+        let range = Pos::new(Line(-9), Column(0))..=Pos::new(Line(-2), Column(3));
+        term.scroll_to_range(&range);
+        assert_eq!(term.display_offset(), 9);
+    }
+
+    #[test]
+    fn damage_search_match() {
+        let size = CrosswordsSize::new(10, 4);
+        let window_id = crate::event::WindowId::from(0);
+        let mut term =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+        term.reset_damage();
+
+        let range = Pos::new(Line(1), Column(2))..=Pos::new(Line(2), Column(3));
+        term.damage_search_match(&range);
+        let damaged: Vec<bool> = term
+            .damage
+            .lines
+            .iter()
+            .map(LineDamageBounds::is_damaged)
+            .collect();
+        assert_eq!(damaged, [false, true, true, false]);
+    }
+
+    #[test]
+    fn pager_keeps_output() {
+        let size = CrosswordsSize::new(80, 4);
+        let window_id = crate::event::WindowId::from(0);
+        let listener = RecordingListener::default();
+        let mut term =
+            Crosswords::new(size, CursorShape::Block, listener.clone(), window_id, 0);
+        term.pager = true;
+
+        term.input('a');
+        term.linefeed();
+        term.input('b');
+        assert_eq!(term.grid[Line(1)][Column(0)].c, 'b');
+
+        term.child_exited(None);
+        assert!(!term.exited);
+        assert!(!term.mode().contains(Mode::SHOW_CURSOR));
+        let events = listener.0.lock().unwrap();
+        assert!(matches!(events[..], [RioEvent::RenderRoute(0)]));
+    }
+
+    #[test]
+    fn resize_updates_cell_size() {
+        let size = CrosswordsSize::new_with_dimensions(10, 4, 80, 64, 8, 16);
+        let window_id = crate::event::WindowId::from(0);
+        let mut term =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+        term.resize(CrosswordsSize::new_with_dimensions(10, 4, 80, 64, 8, 16));
+        assert_eq!(term.graphics.cell_width, 8.);
+
+        // A new font size keeping the same grid.
+        term.resize(CrosswordsSize::new_with_dimensions(10, 4, 100, 80, 10, 20));
+        assert_eq!(term.graphics.cell_width, 10.);
+        assert_eq!(term.graphics.cell_height, 20.);
+    }
+
+    #[test]
+    fn resize_erases_prompt_for_redraw() {
+        let size = CrosswordsSize::new(10, 10);
+        let window_id = crate::event::WindowId::from(0);
+        let mut term =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+
+        term.input('o');
+        term.linefeed();
+        term.carriage_return();
+        term.semantic_prompt(SemanticPrompt::PromptStart { redraw: true });
+        term.input('$');
+        term.semantic_prompt(SemanticPrompt::CommandStart);
+        term.input('l');
+
+        term.resize(CrosswordsSize::new(8, 10));
+        assert_eq!(term.grid.cursor.pos, Pos::new(Line(1), Column(0)));
+        assert_eq!(term.grid[Line(0)][Column(0)].c, 'o');
+        assert_eq!(term.grid[Line(1)][Column(0)].c, ' ');
+
+        // Shells which don't redraw the prompt keep it.
+        term.semantic_prompt(SemanticPrompt::PromptStart { redraw: false });
+        term.input('$');
+        term.resize(CrosswordsSize::new(10, 10));
+        assert_eq!(term.grid[Line(1)][Column(0)].c, '$');
+        assert_eq!(term.grid.cursor.pos, Pos::new(Line(1), Column(1)));
+    }
+
+    #[test]
+    fn clear_screen_and_history_keeps_prompt() {
+        let size = CrosswordsSize::new(10, 3);
+        let window_id = crate::event::WindowId::from(0);
+        let mut term =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+
+        for _ in 0..4 {
+            term.input('o');
+            term.linefeed();
+            term.carriage_return();
+        }
+        term.semantic_prompt(SemanticPrompt::PromptStart { redraw: true });
+        term.input('$');
+        term.semantic_prompt(SemanticPrompt::CommandStart);
+        term.input('l');
+        assert_eq!(term.history_size(), 2);
+
+        term.clear_screen_and_history();
+        assert_eq!(term.history_size(), 0);
+        assert_eq!(term.grid.cursor.pos, Pos::new(Line(0), Column(2)));
+        assert_eq!(term.grid[Line(0)][Column(0)].c, '$');
+        assert!(term.grid[Line(0)].marks.contains(RowMarks::PROMPT));
+        assert_eq!(term.grid[Line(1)][Column(0)].c, ' ');
+    }
+
+    #[test]
+    fn semantic_prompt_without_output() {
+        let size = CrosswordsSize::new(10, 10);
+        let window_id = crate::event::WindowId::from(0);
+        let mut term =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+
+        term.semantic_prompt(SemanticPrompt::PromptStart { redraw: true });
+        assert_eq!(term.last_command_output(), None);
+    }
+
+    #[test]
+    fn scroll_to_prompts() {
+        let size = CrosswordsSize::new(10, 3);
+        let window_id = crate::event::WindowId::from(0);
+        let mut term =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+
+        // Two prompts which end up at lines -6 and -2 of the history.
+        term.semantic_prompt(SemanticPrompt::PromptStart { redraw: true });
+        for _ in 0..4 {
+            term.linefeed();
+        }
+        term.semantic_prompt(SemanticPrompt::PromptStart { redraw: true });
+        for _ in 0..4 {
+            term.linefeed();
+        }
+        assert_eq!(term.grid.topmost_line(), Line(-6));
+
+        term.scroll_to_previous_prompt();
+        assert_eq!(term.display_offset(), 2);
+
+        term.scroll_to_previous_prompt();
+        assert_eq!(term.display_offset(), 6);
+
+        term.scroll_to_next_prompt();
+        assert_eq!(term.display_offset(), 2);
+
+        term.scroll_to_next_prompt();
+        assert_eq!(term.display_offset(), 0);
+    }
+
+    #[test]
+    fn predictive_echo_overlay() {
+        let size = CrosswordsSize::new(10, 10);
+        let window_id = crate::event::WindowId::from(0);
+        let mut term =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+        term.predictions
+            .set_mode(crate::config::predictive_echo::PredictiveEchoMode::Always);
+
+        term.predict_input("ls");
+        // The shell echoes the first character
+        term.input('l');
+
+        let mut rows = term.visible_rows();
+        let mut cursor = term.cursor();
+        term.overlay_predictions(&mut rows, &mut cursor);
+        assert_eq!(rows[0][Column(1)].c, 's');
+        assert!(rows[0][Column(1)].flags.contains(square::Flags::UNDERLINE));
+        assert_eq!(cursor.pos, Pos::new(Line(0), Column(2)));
+
+        // A different echo discards the prediction
+        term.input('x');
+        assert!(term.predictions.is_empty());
+    }
+
+    #[test]
+    fn predictive_echo_overlay_with_scrolling_region() {
+        let size = CrosswordsSize::new(10, 10);
+        let window_id = crate::event::WindowId::from(0);
+        let mut term =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+        term.predictions
+            .set_mode(crate::config::predictive_echo::PredictiveEchoMode::Always);
+
+        term.set_scrolling_region(3, Some(8));
+        term.goto(Line(5), Column(0));
+        term.predict_input("ls");
+        term.input('l');
+
+        let mut rows = term.visible_rows();
+        let mut cursor = term.cursor();
+        term.overlay_predictions(&mut rows, &mut cursor);
+        assert_eq!(rows[5][Column(1)].c, 's');
+        assert_eq!(cursor.pos, Pos::new(Line(5), Column(2)));
+    }
+
+    #[test]
+    fn title_stack() {
+        let size = CrosswordsSize::new(10, 10);
+        let window_id = crate::event::WindowId::from(0);
+        let mut term =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+
+        term.set_title(Some(String::from("shell")));
+        term.push_title();
+        term.set_title(Some(String::from("vim")));
+        assert_eq!(term.title, "vim");
+
+        term.pop_title();
+        assert_eq!(term.title, "shell");
+
+        // Popping an empty stack keeps the current title
+        term.pop_title();
+        assert_eq!(term.title, "shell");
+    }
+
+    #[test]
+    fn decrqss_setting_report() {
+        let size = CrosswordsSize::new(10, 10);
+        let window_id = crate::event::WindowId::from(0);
+        let mut term =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+
+        assert_eq!(term.setting_report(b"m"), Some(String::from("0m")));
+        term.terminal_attribute(Attr::Bold);
+        term.terminal_attribute(Attr::Foreground(AnsiColor::Named(NamedColor::Red)));
+        term.terminal_attribute(Attr::Background(AnsiColor::Indexed(100)));
+        assert_eq!(
+            term.setting_report(b"m"),
+            Some(String::from("0;1;31;48;5;100m"))
+        );
+
+        assert_eq!(term.setting_report(b"r"), Some(String::from("1;10r")));
+        term.set_scrolling_region(2, Some(5));
+        assert_eq!(term.setting_report(b"r"), Some(String::from("2;5r")));
+
+        term.blinking_cursor = false;
+        term.cursor_shape = CursorShape::Beam;
+        assert_eq!(term.setting_report(b" q"), Some(String::from("6 q")));
+
+        assert_eq!(term.setting_report(b"x"), None);
+    }
+
+    #[test]
+    fn resize_reflows_wrapped_lines() {
+        let size = CrosswordsSize::new(5, 4);
+        let window_id = crate::event::WindowId::from(0);
+        let mut term =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+
+        let text = |term: &Crosswords<VoidListener>| {
+            (term.grid.topmost_line().0..term.grid.screen_lines() as i32)
+                .map(|line| {
+                    term.grid[Line(line)][..]
+                        .iter()
+                        .map(|square| square.c)
+                        .collect::<String>()
+                })
+                .collect::<Vec<_>>()
+        };
+
+        for c in "abcdefgh".chars() {
+            term.input(c);
+        }
+        term.carriage_return();
+        term.linefeed();
+        for c in "xyz".chars() {
+            term.input(c);
+        }
+        assert_eq!(text(&term), ["abcde", "fgh  ", "xyz  ", "     "]);
+
+        term.resize(CrosswordsSize::new(10, 4));
+        assert_eq!(
+            text(&term),
+            ["abcdefgh  ", "xyz       ", "          ", "          "]
+        );
+        assert_eq!(term.grid.cursor.pos, Pos::new(Line(1), Column(3)));
+
+        // Wrapped lines move to the scrollback and come back when growing.
+        term.resize(CrosswordsSize::new(3, 4));
+        assert_eq!(text(&term), ["abc", "def", "gh ", "xyz", "   ", "   "]);
+        assert_eq!(term.history_size(), 2);
+        assert_eq!(term.grid.cursor.pos, Pos::new(Line(1), Column(2)));
+
+        term.resize(CrosswordsSize::new(10, 4));
+        assert_eq!(
+            text(&term),
+            ["abcdefgh  ", "xyz       ", "          ", "          "]
+        );
+        assert_eq!(term.grid.cursor.pos, Pos::new(Line(1), Column(3)));
+    }
+
+    #[test]
+    fn grapheme_clusters() {
+        let size = CrosswordsSize::new(10, 3);
+        let window_id = crate::event::WindowId::from(0);
+        let mut term =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+
+        let input = |term: &mut Crosswords<VoidListener>, text: &str| {
+            for c in text.chars() {
+                term.input(c);
+            }
+        };
+        let cluster = |term: &Crosswords<VoidListener>, col: usize| {
+            let square = &term.grid[Line(0)][Column(col)];
+            let mut text = String::from(square.c);
+            text.extend(square.zerowidth().unwrap_or_default());
+            text
+        };
+
+        // Combining marks stay with their base, wide chars take two columns.
+        input(&mut term, "e\u{301}漢");
+        assert_eq!(cluster(&term, 0), "e\u{301}");
+        assert!(term.grid[Line(0)][Column(1)]
+            .flags
+            .contains(square::Flags::WIDE_CHAR));
+        assert_eq!(term.grid.cursor.pos.col, Column(3));
+
+        // Without grapheme clustering, each emoji of a ZWJ sequence is a cell.
+        let family = "\u{1f468}\u{200d}\u{1f469}";
+        input(&mut term, family);
+        assert_eq!(term.grid.cursor.pos.col, Column(7));
+
+        term.carriage_return();
+        term.clear_line(LineClearMode::All);
+        term.set_private_mode(NamedPrivateMode::GraphemeClustering.into());
+
+        input(&mut term, family);
+        assert_eq!(cluster(&term, 0), family);
+        assert_eq!(term.grid.cursor.pos.col, Column(2));
+
+        // Emoji presentation selector widens a narrow character.
+        input(&mut term, "\u{2764}\u{fe0f}");
+        assert_eq!(cluster(&term, 2), "\u{2764}\u{fe0f}");
+        assert!(term.grid[Line(0)][Column(2)]
+            .flags
+            .contains(square::Flags::WIDE_CHAR));
+        assert_eq!(term.grid.cursor.pos.col, Column(4));
+
+        // Flags are a pair of regional indicators.
+        input(&mut term, "\u{1f1e7}\u{1f1f7}x");
+        assert_eq!(cluster(&term, 4), "\u{1f1e7}\u{1f1f7}");
+        assert_eq!(cluster(&term, 6), "x");
+    }
+
+    #[test]
+    fn visible_rows_with_scrolling_region() {
+        let size = CrosswordsSize::new(4, 4);
+        let window_id = crate::event::WindowId::from(0);
+        let mut term =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+
+        term.input('a');
+        term.set_scrolling_region(2, Some(3));
+        term.goto(Line(3), Column(0));
+        term.input('d');
+        term.goto(Line(1), Column(0));
+        term.scroll_up(1);
+
+        // Lines outside of the region are still visible and untouched.
+        let rows = term.visible_rows();
+        assert_eq!(rows.len(), 4);
+        assert_eq!(rows[0][Column(0)].c, 'a');
+        assert_eq!(rows[3][Column(0)].c, 'd');
+    }
+
+    #[test]
+    fn rows_at_display_offset() {
+        let size = CrosswordsSize::new(4, 2);
+        let window_id = crate::event::WindowId::from(0);
+        let mut term =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+
+        // Two lines of history, "a" and "b", then "c" and "d" on screen.
+        for c in ['a', 'b', 'c', 'd'] {
+            term.input(c);
+            if c != 'd' {
+                term.carriage_return();
+                term.linefeed();
+            }
+        }
+        let first = |rows: Vec<Row<Square>>| -> String {
+            rows.iter().map(|row| row[Column(0)].c).collect()
+        };
+
+        assert_eq!(first(term.visible_rows()), "cd");
+        assert_eq!(first(term.rows_at(1, 3)), "bcd");
+        // Offsets past the history and rows past the bottom are left out.
+        assert_eq!(first(term.rows_at(5, 3)), "abc");
+        assert_eq!(first(term.rows_at(0, 3)), "cd");
+    }
+
+    #[test]
+    fn left_right_margins() {
+        let size = CrosswordsSize::new(6, 4);
+        let window_id = crate::event::WindowId::from(0);
+        let mut term =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+        let text = |term: &Crosswords<VoidListener>| {
+            (0..4)
+                .map(|line| {
+                    term.grid[Line(line)][..]
+                        .iter()
+                        .map(|square| square.c)
+                        .collect::<String>()
+                })
+                .collect::<Vec<_>>()
+        };
+
+        for (line, c) in "abcd".chars().enumerate() {
+            term.goto(Line(line as i32), Column(0));
+            for _ in 0..6 {
+                term.input(c);
+            }
+        }
+
+        // Margins are ignored (SCOSC) until DECLRMM is set.
+        term.set_left_right_margins(2, Some(4));
+        assert_eq!(term.horizontal_margins, Column(0)..Column(6));
+
+        term.set_private_mode(NamedPrivateMode::LeftRightMargin.into());
+        term.set_left_right_margins(2, Some(4));
+        assert_eq!(term.horizontal_margins, Column(1)..Column(4));
+
+        term.goto(Line(0), Column(1));
+        term.scroll_up(1);
+        assert_eq!(text(&term), ["abbbaa", "bcccbb", "cdddcc", "d   dd"]);
+
+        // Text wraps from the right margin to the left one.
+        for c in "xyzw".chars() {
+            term.input(c);
+        }
+        assert_eq!(text(&term), ["axyzaa", "bwccbb", "cdddcc", "d   dd"]);
+        term.carriage_return();
+        assert_eq!(term.grid.cursor.pos.col, Column(1));
+
+        term.goto(Line(2), Column(1));
+        term.delete_chars(1);
+        assert_eq!(text(&term)[2], "cdd cc");
+        term.insert_blank(1);
+        assert_eq!(text(&term)[2], "c ddcc");
+
+        // Lines outside of the margins aren't inserted nor deleted.
+        term.goto(Line(2), Column(5));
+        term.delete_lines(1);
+        assert_eq!(text(&term)[2], "c ddcc");
+
+        // Origin mode addresses the cursor relative to the margins.
+        term.set_private_mode(NamedPrivateMode::Origin.into());
+        term.goto(Line(0), Column(5));
+        assert_eq!(term.grid.cursor.pos.col, Column(3));
+        term.unset_private_mode(NamedPrivateMode::Origin.into());
+
+        term.unset_private_mode(NamedPrivateMode::LeftRightMargin.into());
+        term.scroll_up(1);
+        assert_eq!(text(&term), ["bwccbb", "c ddcc", "d   dd", "      "]);
+    }
+
+    #[test]
+    fn csi_s_follows_left_right_margin_mode() {
+        let size = CrosswordsSize::new(6, 4);
+        let window_id = crate::event::WindowId::from(0);
+        let mut term =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+        let mut parser = crate::performer::handler::ParserProcessor::new();
+
+        // SCOSC saves the cursor without DECLRMM.
+        for byte in b"\x1b[2;3H\x1b[s\x1b[H\x1b[u" {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(term.grid.cursor.pos, Pos::new(Line(1), Column(2)));
+
+        // With DECLRMM a bare CSI s is DECSLRM and resets the margins.
+        for byte in b"\x1b[?69h\x1b[2;4s" {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(term.horizontal_margins, Column(1)..Column(4));
+        for byte in b"\x1b[s" {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(term.horizontal_margins, Column(0)..Column(6));
+    }
+
+    #[test]
+    fn cursor_style() {
+        let size = CrosswordsSize::new(5, 5);
+        let window_id = crate::event::WindowId::from(0);
+        let mut term =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+        term.default_blinking_cursor = true;
+        term.blinking_cursor = true;
+
+        term.set_cursor_style(Some(CursorShape::Underline), false);
+        assert_eq!(term.cursor_shape, CursorShape::Underline);
+        assert!(!term.blinking_cursor);
+
+        term.set_cursor_style(Some(CursorShape::Beam), true);
+        assert_eq!(term.cursor_shape, CursorShape::Beam);
+        assert!(term.blinking_cursor);
+
+        term.unset_private_mode(NamedPrivateMode::BlinkingCursor.into());
+        assert!(!term.blinking_cursor);
+
+        // DECSCUSR 0 restores the configured style.
+        term.set_cursor_style(None, false);
+        assert_eq!(term.cursor_shape, CursorShape::Block);
+        assert!(term.blinking_cursor);
+    }
+
+    #[test]
+    fn blink_attribute() {
+        let size = CrosswordsSize::new(5, 1);
+        let window_id = crate::event::WindowId::from(0);
+        let mut term =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+
+        term.terminal_attribute(Attr::BlinkSlow);
+        term.input('a');
+        term.terminal_attribute(Attr::CancelBlink);
+        term.input('b');
+        term.terminal_attribute(Attr::BlinkFast);
+        term.input('c');
+        term.terminal_attribute(Attr::Reset);
+        term.input('d');
+
+        let row = &term.grid[Line(0)];
+        assert!(row[Column(0)].flags.contains(square::Flags::BLINK));
+        assert!(!row[Column(1)].flags.contains(square::Flags::BLINK));
+        assert!(row[Column(2)].flags.contains(square::Flags::BLINK));
+        assert!(!row[Column(3)].flags.contains(square::Flags::BLINK));
+    }
+
+    #[test]
+    fn overline_attribute() {
+        let size = CrosswordsSize::new(5, 1);
+        let window_id = crate::event::WindowId::from(0);
+        let mut term =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+
+        let mut parser = crate::performer::handler::ParserProcessor::new();
+        for byte in b"\x1b[53;9ma\x1b[29mb\x1b[55mc" {
+            parser.advance(&mut term, *byte);
+        }
+
+        let row = &term.grid[Line(0)];
+        let flags = square::Flags::OVERLINE | square::Flags::STRIKEOUT;
+        assert_eq!(row[Column(0)].flags & flags, flags);
+        assert_eq!(row[Column(1)].flags & flags, square::Flags::OVERLINE);
+        assert!(!row[Column(2)].flags.intersects(flags));
+    }
+
+    #[test]
+    fn double_size_lines() {
+        let size = CrosswordsSize::new(6, 3);
+        let window_id = crate::event::WindowId::from(0);
+        let mut term =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+
+        for c in "abcdef".chars() {
+            term.input(c);
+        }
+        term.goto(Line(0), Column(4));
+        term.set_line_size(LineSize::DoubleWidth);
+        assert_eq!(term.grid[Line(0)].size, LineSize::DoubleWidth);
+        assert_eq!(term.grid[Line(0)][Column(2)].c, 'c');
+        assert_eq!(term.grid[Line(0)][Column(3)].c, ' ');
+        assert_eq!(term.grid.cursor.pos.col, Column(2));
+
+        // Text wraps at the half of double lines.
+        term.goto(Line(1), Column(0));
+        term.set_line_size(LineSize::DoubleHeightTop);
+        for c in "wxyz".chars() {
+            term.input(c);
+        }
+        assert_eq!(term.grid[Line(1)][Column(2)].c, 'y');
+        assert_eq!(term.grid[Line(2)][Column(0)].c, 'z');
+
+        term.goto(Line(1), Column(5));
+        assert_eq!(term.grid.cursor.pos.col, Column(2));
+
+        term.set_line_size(LineSize::Normal);
+        term.goto(Line(1), Column(5));
+        assert_eq!(term.grid.cursor.pos.col, Column(5));
+    }
+
+    #[test]
+    fn unseen_lines_while_scrolled_up() {
+        let size = CrosswordsSize::new(5, 3);
+        let window_id = crate::event::WindowId::from(0);
+        let mut term =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+
+        term.goto(Line(2), Column(0));
+        for _ in 0..5 {
+            term.linefeed();
+        }
+        assert_eq!(term.unseen_lines, 0);
+
+        term.scroll_display(Scroll::Delta(2));
+        for _ in 0..3 {
+            term.linefeed();
+        }
+        assert_eq!(term.unseen_lines, 3);
+        assert_eq!(term.display_offset(), 5);
+
+        term.scroll_display(Scroll::Bottom);
+        assert_eq!(term.unseen_lines, 0);
+
+        term.scroll_to_bottom_on_output = true;
+        term.scroll_display(Scroll::Delta(2));
+        term.linefeed();
+        assert_eq!(term.display_offset(), 0);
+        assert_eq!(term.unseen_lines, 0);
+    }
+
+    #[test]
+    fn tab_stops() {
+        let size = CrosswordsSize::new(30, 2);
+        let window_id = crate::event::WindowId::from(0);
+        let mut term =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+
+        // Default stops every 8 columns, CHT stops at the last column.
+        term.move_forward_tabs(1);
+        assert_eq!(term.grid.cursor.pos.col, Column(8));
+        term.move_forward_tabs(3);
+        assert_eq!(term.grid.cursor.pos.col, Column(29));
+
+        // Custom stops replace the default ones.
+        term.clear_tabs(TabulationClearMode::All);
+        term.goto_col(Column(5));
+        term.set_horizontal_tabstop();
+        term.goto_col(Column(12));
+        term.set_horizontal_tabstop();
+        term.goto_col(Column(0));
+        term.move_forward_tabs(1);
+        assert_eq!(term.grid.cursor.pos.col, Column(5));
+        term.put_tab(1);
+        assert_eq!(term.grid.cursor.pos.col, Column(12));
+
+        // CBT goes back to the first column without previous stops.
+        term.move_backward_tabs(1);
+        assert_eq!(term.grid.cursor.pos.col, Column(5));
+        term.clear_tabs(TabulationClearMode::Current);
+        term.goto_col(Column(12));
+        term.move_backward_tabs(1);
+        assert_eq!(term.grid.cursor.pos.col, Column(0));
+
+        // Stops are kept on resize and new columns get the default ones.
+        term.resize(CrosswordsSize::new(40, 2));
+        term.move_forward_tabs(2);
+        assert_eq!(term.grid.cursor.pos.col, Column(32));
+
+        term.reset_tabs();
+        term.goto_col(Column(0));
+        term.move_forward_tabs(1);
+        assert_eq!(term.grid.cursor.pos.col, Column(8));
+    }
+
+    #[test]
+    fn rectangular_area_operations() {
+        let size = CrosswordsSize::new(5, 5);
+        let window_id = crate::event::WindowId::from(0);
+        let mut term =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+
+        let area = |top, left, bottom, right| RectangularArea {
+            top,
+            left,
+            bottom: Some(bottom),
+            right: Some(right),
+        };
+        let text = |term: &Crosswords<VoidListener>| {
+            (0..5)
+                .map(|line| {
+                    term.grid[Line(line)][..]
+                        .iter()
+                        .map(|square| square.c)
+                        .collect::<String>()
+                })
+                .collect::<Vec<_>>()
+        };
+
+        term.fill_rectangular_area('x', area(2, 2, 3, 4));
+        assert_eq!(text(&term), ["     ", " xxx ", " xxx ", "     ", "     "]);
+
+        // Overlapping copy, clipped to the screen.
+        term.copy_rectangular_area(area(2, 2, 3, 4), 3, 3);
+        assert_eq!(text(&term), ["     ", " xxx ", " xxxx", "  xxx", "     "]);
+
+        term.erase_rectangular_area(area(1, 1, 3, 3));
+        assert_eq!(text(&term), ["     ", "   x ", "   xx", "  xxx", "     "]);
+
+        // Empty areas are ignored and missing edges reach the screen end.
+        term.fill_rectangular_area('y', area(4, 4, 2, 2));
+        term.fill_rectangular_area(
+            'y',
+            RectangularArea {
+                top: 5,
+                left: 4,
+                bottom: None,
+                right: None,
+            },
+        );
+        assert_eq!(text(&term), ["     ", "   x ", "   xx", "  xxx", "   yy"]);
+
+        // Coordinates are relative to the scrolling region in origin mode.
+        term.set_scrolling_region(4, Some(5));
+        term.set_private_mode(NamedPrivateMode::Origin.into());
+        term.erase_rectangular_area(area(1, 1, 9, 9));
+        assert_eq!(text(&term), ["     ", "   x ", "   xx", "     ", "     "]);
+    }
+
+    #[test]
+    fn decfra_rejects_characters_out_of_range() {
+        let size = CrosswordsSize::new(5, 5);
+        let window_id = crate::event::WindowId::from(0);
+        let mut term =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+
+        // 288 would be a space once narrowed to a byte.
+        let mut parser = crate::performer::handler::ParserProcessor::new();
+        for byte in b"\x1b[120;1;1;1;2$x\x1b[288;1;1;1;1$x" {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(term.grid[Line(0)][Column(0)].c, 'x');
+        assert_eq!(term.grid[Line(0)][Column(1)].c, 'x');
+    }
+
     #[test]
     fn parse_cargo_version() {
         assert_eq!(version_number("0.0.1-nightly"), 1);
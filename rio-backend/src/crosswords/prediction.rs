@@ -0,0 +1,252 @@
+// Predictive local echo, similar to mosh.
+//
+// Typed characters are predicted at the cursor position and rendered
+// before the real echo arrives from the PTY. Each character written by the
+// PTY is compared against the oldest prediction: a match confirms it and
+// is used to measure the round-trip latency, anything else discards every
+// pending prediction.
+//
+// Predictions are recorded in every mode, confirmations are the samples of
+// the echo latency (time between a keystroke and its echo) which is shown
+// by the latency indicator and used by the auto mode. They're only rendered
+// while active.
+//
+// Predictions are grouped in epochs and only displayed once a character of
+// the current epoch was confirmed, so input that is never echoed back
+// (like passwords) is never displayed.
+
+use crate::config::predictive_echo::{PredictiveEcho, PredictiveEchoMode};
+use crate::crosswords::pos::Pos;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Minimum time to wait for the echo of a prediction.
+const MIN_PREDICTION_TIMEOUT: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Prediction {
+    pub pos: Pos,
+    pub c: char,
+    sent_at: Instant,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Predictions {
+    mode: PredictiveEchoMode,
+    threshold: Duration,
+    /// Smoothed round-trip latency.
+    srtt: Option<Duration>,
+    pending: VecDeque<Prediction>,
+    /// Whether a prediction of the current epoch was confirmed.
+    confirmed_epoch: bool,
+}
+
+impl Predictions {
+    pub fn new(config: &PredictiveEcho) -> Predictions {
+        Predictions {
+            mode: config.mode,
+            threshold: Duration::from_millis(config.latency_threshold),
+            ..Predictions::default()
+        }
+    }
+
+    #[inline]
+    pub fn mode(&self) -> PredictiveEchoMode {
+        self.mode
+    }
+
+    pub fn set_mode(&mut self, mode: PredictiveEchoMode) {
+        self.mode = mode;
+    }
+
+    /// Smoothed round-trip latency between a keystroke and its echo.
+    #[inline]
+    pub fn latency(&self) -> Option<Duration> {
+        self.srtt
+    }
+
+    /// Whether predictions are displayed.
+    pub fn is_active(&self) -> bool {
+        match self.mode {
+            PredictiveEchoMode::Never => false,
+            PredictiveEchoMode::Always => true,
+            PredictiveEchoMode::Auto => {
+                self.srtt.is_some_and(|srtt| srtt >= self.threshold)
+            }
+        }
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Most recent prediction.
+    #[inline]
+    pub fn last(&self) -> Option<&Prediction> {
+        self.pending.back()
+    }
+
+    /// Discard every pending prediction and start a new epoch.
+    pub fn clear(&mut self) {
+        self.pending.clear();
+        self.confirmed_epoch = false;
+    }
+
+    pub fn push(&mut self, pos: Pos, c: char, now: Instant) {
+        self.expire(now);
+        self.pending.push_back(Prediction {
+            pos,
+            c,
+            sent_at: now,
+        });
+    }
+
+    /// Reconcile a character written by the PTY at `pos`.
+    pub fn confirm(&mut self, pos: Pos, c: char, now: Instant) {
+        let Some(front) = self.pending.front() else {
+            return;
+        };
+
+        // Output elsewhere, e.g. a prompt redraw.
+        if front.pos != pos && self.pending.iter().all(|p| p.pos != pos) {
+            return;
+        }
+
+        if front.pos != pos || front.c != c {
+            self.clear();
+            return;
+        }
+
+        let sample = now.saturating_duration_since(front.sent_at);
+        self.srtt = Some(match self.srtt {
+            Some(srtt) => (srtt * 7 + sample) / 8,
+            None => sample,
+        });
+        self.pending.pop_front();
+        self.confirmed_epoch = true;
+    }
+
+    fn timeout(&self) -> Duration {
+        self.srtt
+            .map_or(MIN_PREDICTION_TIMEOUT, |srtt| srtt * 3)
+            .max(MIN_PREDICTION_TIMEOUT)
+    }
+
+    /// Discard predictions that didn't get an echo in time.
+    pub fn expire(&mut self, now: Instant) {
+        let timeout = self.timeout();
+        if self
+            .pending
+            .front()
+            .is_some_and(|p| now.saturating_duration_since(p.sent_at) > timeout)
+        {
+            self.clear();
+        }
+    }
+
+    /// Predictions that should be rendered.
+    pub fn visible(&self, now: Instant) -> impl Iterator<Item = &Prediction> {
+        let is_visible = self.is_active() && self.confirmed_epoch;
+        let timeout = self.timeout();
+        self.pending.iter().filter(move |p| {
+            is_visible && now.saturating_duration_since(p.sent_at) <= timeout
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crosswords::pos::{Column, Line};
+
+    fn always() -> Predictions {
+        Predictions::new(&PredictiveEcho {
+            mode: PredictiveEchoMode::Always,
+            ..PredictiveEcho::default()
+        })
+    }
+
+    #[test]
+    fn test_predictions_are_shown_after_confirmation() {
+        let now = Instant::now();
+        let mut predictions = always();
+        predictions.push(Pos::new(Line(0), Column(0)), 'l', now);
+        predictions.push(Pos::new(Line(0), Column(1)), 's', now);
+        // Nothing was echoed yet in this epoch
+        assert_eq!(predictions.visible(now).count(), 0);
+
+        predictions.confirm(Pos::new(Line(0), Column(0)), 'l', now);
+        let visible: Vec<char> = predictions.visible(now).map(|p| p.c).collect();
+        assert_eq!(visible, vec!['s']);
+    }
+
+    #[test]
+    fn test_misprediction_clears_epoch() {
+        let now = Instant::now();
+        let mut predictions = always();
+        predictions.push(Pos::new(Line(0), Column(0)), 'a', now);
+        predictions.push(Pos::new(Line(0), Column(1)), 'b', now);
+        predictions.confirm(Pos::new(Line(0), Column(0)), 'a', now);
+
+        // Output somewhere else is ignored
+        predictions.confirm(Pos::new(Line(3), Column(0)), 'x', now);
+        assert!(!predictions.is_empty());
+
+        predictions.confirm(Pos::new(Line(0), Column(1)), '*', now);
+        assert!(predictions.is_empty());
+        predictions.push(Pos::new(Line(0), Column(2)), 'c', now);
+        assert_eq!(predictions.visible(now).count(), 0);
+    }
+
+    #[test]
+    fn test_auto_mode_uses_latency() {
+        let now = Instant::now();
+        let mut predictions = Predictions::new(&PredictiveEcho {
+            mode: PredictiveEchoMode::Auto,
+            ..PredictiveEcho::default()
+        });
+        assert!(!predictions.is_active());
+
+        predictions.push(Pos::new(Line(0), Column(0)), 'a', now);
+        predictions.confirm(
+            Pos::new(Line(0), Column(0)),
+            'a',
+            now + Duration::from_millis(20),
+        );
+        assert_eq!(predictions.latency(), Some(Duration::from_millis(20)));
+        assert!(!predictions.is_active());
+
+        predictions.push(Pos::new(Line(0), Column(1)), 'b', now);
+        predictions.confirm(
+            Pos::new(Line(0), Column(1)),
+            'b',
+            now + Duration::from_millis(900),
+        );
+        assert!(predictions.is_active());
+    }
+
+    #[test]
+    fn test_latency_is_measured_when_disabled() {
+        let now = Instant::now();
+        let mut predictions = Predictions::default();
+        predictions.push(Pos::new(Line(0), Column(0)), 'a', now);
+        predictions.push(Pos::new(Line(0), Column(1)), 'b', now);
+        predictions.confirm(
+            Pos::new(Line(0), Column(0)),
+            'a',
+            now + Duration::from_millis(40),
+        );
+        assert_eq!(predictions.latency(), Some(Duration::from_millis(40)));
+        assert_eq!(predictions.visible(now).count(), 0);
+    }
+
+    #[test]
+    fn test_expired_predictions_are_dropped() {
+        let now = Instant::now();
+        let mut predictions = always();
+        predictions.push(Pos::new(Line(0), Column(0)), 'a', now);
+        predictions.expire(now + Duration::from_secs(5));
+        assert!(predictions.is_empty());
+    }
+}
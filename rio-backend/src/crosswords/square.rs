@@ -12,28 +12,30 @@ use std::sync::Arc;
 
 bitflags! {
     #[derive(Clone, Copy, Debug, Eq, PartialEq)]
-    pub struct Flags: u16 {
-        const INVERSE                   = 0b0000_0000_0000_0001;
-        const BOLD                      = 0b0000_0000_0000_0010;
-        const ITALIC                    = 0b0000_0000_0000_0100;
-        const BOLD_ITALIC               = 0b0000_0000_0000_0110;
-        const UNDERLINE                 = 0b0000_0000_0000_1000;
-        const WRAPLINE                  = 0b0000_0000_0001_0000;
-        const WIDE_CHAR                 = 0b0000_0000_0010_0000;
-        const WIDE_CHAR_SPACER          = 0b0000_0000_0100_0000;
-        const DIM                       = 0b0000_0000_1000_0000;
-        const DIM_BOLD                  = 0b0000_0000_1000_0010;
-        const HIDDEN                    = 0b0000_0001_0000_0000;
-        const STRIKEOUT                 = 0b0000_0010_0000_0000;
-        const LEADING_WIDE_CHAR_SPACER  = 0b0000_0100_0000_0000;
-        const DOUBLE_UNDERLINE          = 0b0000_1000_0000_0000;
-        const UNDERCURL                 = 0b0001_0000_0000_0000;
-        const DOTTED_UNDERLINE          = 0b0010_0000_0000_0000;
-        const DASHED_UNDERLINE          = 0b0100_0000_0000_0000;
+    pub struct Flags: u32 {
+        const INVERSE                   = 0b0000_0000_0000_0000_0001;
+        const BOLD                      = 0b0000_0000_0000_0000_0010;
+        const ITALIC                    = 0b0000_0000_0000_0000_0100;
+        const BOLD_ITALIC               = 0b0000_0000_0000_0000_0110;
+        const UNDERLINE                 = 0b0000_0000_0000_0000_1000;
+        const WRAPLINE                  = 0b0000_0000_0000_0001_0000;
+        const WIDE_CHAR                 = 0b0000_0000_0000_0010_0000;
+        const WIDE_CHAR_SPACER          = 0b0000_0000_0000_0100_0000;
+        const DIM                       = 0b0000_0000_0000_1000_0000;
+        const DIM_BOLD                  = 0b0000_0000_0000_1000_0010;
+        const HIDDEN                    = 0b0000_0000_0001_0000_0000;
+        const STRIKEOUT                 = 0b0000_0000_0010_0000_0000;
+        const LEADING_WIDE_CHAR_SPACER  = 0b0000_0000_0100_0000_0000;
+        const DOUBLE_UNDERLINE          = 0b0000_0000_1000_0000_0000;
+        const UNDERCURL                 = 0b0000_0001_0000_0000_0000;
+        const DOTTED_UNDERLINE          = 0b0000_0010_0000_0000_0000;
+        const DASHED_UNDERLINE          = 0b0000_0100_0000_0000_0000;
         const ALL_UNDERLINES            = Self::UNDERLINE.bits() | Self::DOUBLE_UNDERLINE.bits()
                                         | Self::UNDERCURL.bits() | Self::DOTTED_UNDERLINE.bits()
                                         | Self::DASHED_UNDERLINE.bits();
-        const GRAPHICS = 0b1000_0000_0000_0000;
+        const GRAPHICS = 0b0000_1000_0000_0000_0000;
+        const BLINK    = 0b0001_0000_0000_0000_0000;
+        const OVERLINE = 0b0010_0000_0000_0000_0000;
     }
 }
 
@@ -233,6 +235,7 @@ impl GridSquare for Square {
                 Flags::INVERSE
                     | Flags::ALL_UNDERLINES
                     | Flags::STRIKEOUT
+                    | Flags::OVERLINE
                     | Flags::WRAPLINE
                     | Flags::WIDE_CHAR_SPACER
                     | Flags::LEADING_WIDE_CHAR_SPACER
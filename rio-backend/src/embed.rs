@@ -0,0 +1,339 @@
+//! Embed a terminal in another application.
+//!
+//! [`Terminal`] runs a program in a PTY and feeds its output to a
+//! [`Crosswords`] grid from a background thread, the application only has to
+//! forward input, resize it and draw the grid (e.g. with [`sugarloaf`]).
+//!
+//! ```no_run
+//! use rio_backend::crosswords::CrosswordsSize;
+//! use rio_backend::embed::{Terminal, TerminalOptions};
+//! use rio_backend::event::{VoidListener, WindowId};
+//!
+//! let terminal = Terminal::spawn(
+//!     TerminalOptions::default(),
+//!     VoidListener,
+//!     WindowId::from(0),
+//! )
+//! .unwrap();
+//!
+//! terminal.write(b"echo hello\r".to_vec());
+//! terminal.resize(CrosswordsSize::new_with_dimensions(120, 40, 960, 640, 8, 16));
+//!
+//! for row in terminal.crosswords().lock().visible_rows() {
+//!     let line: String = row.inner.iter().map(|square| square.c).collect();
+//!     println!("{line}");
+//! }
+//! ```
+//!
+//! See `rio-backend/examples/embed.rs` for a window rendering the grid with
+//! sugarloaf. Rio itself creates its tabs and splits with
+//! [`Terminal::create`] and [`Terminal::from_pty`].
+
+use crate::ansi::CursorShape;
+use crate::crosswords::grid::Dimensions;
+use crate::crosswords::{Crosswords, CrosswordsSize};
+use crate::event::sync::FairMutex;
+use crate::event::{EventListener, Msg, WindowId};
+use crate::performer::Machine;
+use corcovado::channel;
+use std::borrow::Cow;
+use std::error::Error;
+use std::sync::Arc;
+use teletypewriter::{EventedPty, WinsizeBuilder};
+
+#[cfg(target_os = "windows")]
+use teletypewriter::create_pty;
+#[cfg(not(target_os = "windows"))]
+use teletypewriter::{create_pty_with_fork, create_pty_with_spawn};
+
+/// Program and initial size of a [`Terminal`].
+#[derive(Debug, Clone)]
+pub struct TerminalOptions {
+    pub program: String,
+    pub args: Vec<String>,
+    /// Added to the environment of the program.
+    pub env: Vec<(String, String)>,
+    /// Start the program as a login shell, unused on Windows.
+    pub login: bool,
+    /// Fork the process instead of spawning it, `working_dir` is ignored.
+    #[cfg(not(target_os = "windows"))]
+    pub use_fork: bool,
+    pub working_dir: Option<String>,
+    pub columns: usize,
+    pub lines: usize,
+    pub cursor_shape: CursorShape,
+}
+
+impl Default for TerminalOptions {
+    fn default() -> TerminalOptions {
+        TerminalOptions {
+            program: default_shell(),
+            args: Vec::new(),
+            env: Vec::new(),
+            login: false,
+            #[cfg(not(target_os = "windows"))]
+            use_fork: false,
+            working_dir: None,
+            columns: 80,
+            lines: 24,
+            cursor_shape: CursorShape::default(),
+        }
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+fn default_shell() -> String {
+    std::env::var("SHELL").unwrap_or_else(|_| String::from("/bin/sh"))
+}
+
+#[cfg(target_os = "windows")]
+fn default_shell() -> String {
+    String::from("powershell")
+}
+
+/// A program running in a PTY and the grid it draws into.
+pub struct Terminal<U: EventListener> {
+    crosswords: Arc<FairMutex<Crosswords<U>>>,
+    channel: channel::Sender<Msg>,
+    /// Main fd of the PTY and pid of the program.
+    #[cfg(not(target_os = "windows"))]
+    child: Option<(Arc<i32>, u32)>,
+    /// Reader of the output until [`Terminal::start`] is called.
+    machine: Option<Box<dyn FnOnce() + Send>>,
+}
+
+impl<U> Terminal<U>
+where
+    U: EventListener + Clone + Send + 'static,
+{
+    /// Spawn the program and start reading its output.
+    ///
+    /// `event_proxy` receives the terminal events, [`RioEvent::RenderRoute`]
+    /// is sent whenever new content is available to draw and
+    /// [`RioEvent::CloseTerminal`] once the program exits.
+    ///
+    /// [`RioEvent::RenderRoute`]: crate::event::RioEvent::RenderRoute
+    /// [`RioEvent::CloseTerminal`]: crate::event::RioEvent::CloseTerminal
+    pub fn spawn(
+        options: TerminalOptions,
+        event_proxy: U,
+        window_id: WindowId,
+    ) -> Result<Terminal<U>, Box<dyn Error>> {
+        let size = CrosswordsSize::new(options.columns, options.lines);
+        let crosswords = Crosswords::new(
+            size,
+            options.cursor_shape,
+            event_proxy.clone(),
+            window_id,
+            0,
+        );
+
+        let mut terminal = Terminal::create(
+            Arc::new(FairMutex::new(crosswords)),
+            options,
+            event_proxy,
+            window_id,
+            0,
+        )?;
+        terminal.start();
+        Ok(terminal)
+    }
+
+    /// Spawn the program for an existing grid, its output is only read
+    /// once [`Terminal::start`] is called.
+    pub fn create(
+        crosswords: Arc<FairMutex<Crosswords<U>>>,
+        options: TerminalOptions,
+        event_proxy: U,
+        window_id: WindowId,
+        route_id: usize,
+    ) -> Result<Terminal<U>, Box<dyn Error>> {
+        let columns = options.columns as u16;
+        let lines = options.lines as u16;
+
+        #[cfg(not(target_os = "windows"))]
+        let pty = if options.use_fork {
+            tracing::info!("rio -> teletypewriter: create_pty_with_fork");
+            create_pty_with_fork(
+                &Cow::Borrowed(&options.program),
+                options.args,
+                &options.env,
+                options.login,
+                columns,
+                lines,
+            )
+        } else {
+            tracing::info!("rio -> teletypewriter: create_pty_with_spawn");
+            create_pty_with_spawn(
+                &Cow::Borrowed(&options.program),
+                options.args,
+                &options.env,
+                options.login,
+                &options.working_dir,
+                columns,
+                lines,
+            )
+        };
+        #[cfg(target_os = "windows")]
+        let pty = create_pty(
+            &Cow::Borrowed(&options.program),
+            options.args,
+            &options.env,
+            &options.working_dir,
+            columns,
+            lines,
+        );
+
+        let pty = match pty {
+            Ok(pty) => pty,
+            Err(err) => {
+                tracing::error!("{err:?}");
+                return Err(Box::new(err));
+            }
+        };
+
+        #[cfg(not(target_os = "windows"))]
+        let child = (Arc::clone(&pty.child.id), *pty.child.pid as u32);
+
+        #[allow(unused_mut)]
+        let mut terminal =
+            Terminal::from_pty(crosswords, pty, event_proxy, window_id, route_id)?;
+        #[cfg(not(target_os = "windows"))]
+        {
+            terminal.child = Some(child);
+        }
+        Ok(terminal)
+    }
+
+    /// Use `pty` for an existing grid, e.g. a serial device or a socket
+    /// instead of a program. Its output is only read once
+    /// [`Terminal::start`] is called.
+    pub fn from_pty<T>(
+        crosswords: Arc<FairMutex<Crosswords<U>>>,
+        pty: T,
+        event_proxy: U,
+        window_id: WindowId,
+        route_id: usize,
+    ) -> Result<Terminal<U>, Box<dyn Error>>
+    where
+        T: EventedPty + Send + 'static,
+    {
+        let machine = Machine::new(
+            Arc::clone(&crosswords),
+            pty,
+            event_proxy,
+            window_id,
+            route_id,
+        )?;
+        let channel = machine.channel();
+
+        Ok(Terminal {
+            crosswords,
+            channel,
+            #[cfg(not(target_os = "windows"))]
+            child: None,
+            machine: Some(Box::new(move || machine.spawn())),
+        })
+    }
+}
+
+impl<U: EventListener> Terminal<U> {
+    /// Start reading the output in a background thread, does nothing if
+    /// it was already started.
+    pub fn start(&mut self) {
+        if let Some(machine) = self.machine.take() {
+            machine();
+        }
+    }
+
+    /// Grid of the terminal, lock it to read the content or to scroll.
+    #[inline]
+    pub fn crosswords(&self) -> &Arc<FairMutex<Crosswords<U>>> {
+        &self.crosswords
+    }
+
+    /// Channel to the thread reading the output.
+    #[inline]
+    pub fn channel(&self) -> &channel::Sender<Msg> {
+        &self.channel
+    }
+
+    /// Main fd of the PTY, `None` for [`Terminal::from_pty`].
+    #[cfg(not(target_os = "windows"))]
+    #[inline]
+    pub fn main_fd(&self) -> Option<&Arc<i32>> {
+        self.child.as_ref().map(|(fd, _)| fd)
+    }
+
+    /// Pid of the program, `None` for [`Terminal::from_pty`].
+    #[cfg(not(target_os = "windows"))]
+    #[inline]
+    pub fn pid(&self) -> Option<u32> {
+        self.child.as_ref().map(|(_, pid)| *pid)
+    }
+
+    /// Write bytes to the program, e.g. keyboard input.
+    pub fn write<B: Into<Cow<'static, [u8]>>>(&self, data: B) {
+        let bytes = data.into();
+        // The PTY hangs if 0 bytes are sent through.
+        if bytes.is_empty() {
+            return;
+        }
+
+        let _ = self.channel.send(Msg::Input(bytes));
+    }
+
+    /// Resize the grid and notify the program.
+    pub fn resize<D: Dimensions>(&self, size: D) {
+        let winsize = WinsizeBuilder {
+            rows: size.screen_lines() as u16,
+            cols: size.columns() as u16,
+            width: (size.columns() as f32 * size.square_width()) as u16,
+            height: (size.screen_lines() as f32 * size.square_height()) as u16,
+        };
+
+        self.crosswords.lock().resize(size);
+        let _ = self.channel.send(Msg::Resize(winsize));
+    }
+}
+
+impl<U: EventListener> Drop for Terminal<U> {
+    fn drop(&mut self) {
+        let _ = self.channel.send(Msg::Shutdown);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::VoidListener;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    #[cfg(not(target_os = "windows"))]
+    fn test_terminal_runs_program() {
+        let terminal = Terminal::spawn(
+            TerminalOptions {
+                program: String::from("/bin/sh"),
+                args: vec![String::from("-c"), String::from("echo embedded; sleep 5")],
+                ..TerminalOptions::default()
+            },
+            VoidListener,
+            WindowId::from(0),
+        )
+        .unwrap();
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let mut text = String::new();
+        while Instant::now() < deadline && !text.contains("embedded") {
+            std::thread::sleep(Duration::from_millis(10));
+            text = terminal.crosswords().lock().visible_rows()[0]
+                .inner
+                .iter()
+                .map(|square| square.c)
+                .collect();
+        }
+
+        assert!(text.starts_with("embedded"));
+    }
+}
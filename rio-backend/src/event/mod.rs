@@ -2,6 +2,7 @@ pub mod sync;
 
 use crate::clipboard::ClipboardType;
 use crate::config::colors::ColorRgb;
+use crate::config::profiles::Profile;
 use crate::crosswords::grid::Scroll;
 use crate::crosswords::pos::{Direction, Pos};
 use crate::crosswords::search::{Match, RegexSearch};
@@ -30,7 +31,6 @@ pub enum Msg {
     /// Data that should be written to the PTY.
     Input(Cow<'static, [u8]>),
 
-    #[allow(dead_code)]
     Shutdown,
 
     Resize(WinsizeBuilder),
@@ -44,6 +44,34 @@ pub enum ClickState {
     TripleClick,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandFinished {
+    pub route_id: usize,
+    /// Command line as typed at the prompt.
+    pub command: String,
+    pub exit_code: Option<i32>,
+    pub duration: std::time::Duration,
+}
+
+/// OSC with a code in the range forwarded to the user hooks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrivateOsc {
+    pub route_id: usize,
+    pub code: u16,
+    /// Parameters after the code, joined with `;`.
+    pub payload: String,
+}
+
+/// Window geometry and state used to answer XTWINOPS reports.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct WindowState {
+    pub minimized: bool,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
 #[derive(Clone)]
 pub enum RioEvent {
     PrepareRender(u64),
@@ -61,7 +89,12 @@ pub enum RioEvent {
     Hide,
     HideOtherApplications,
     UpdateConfig,
-    CreateWindow,
+
+    /// Fonts loaded in the background are available, carries the ones
+    /// that couldn't be found.
+    FontsLoaded(Vec<sugarloaf::font::SugarloafFont>),
+    /// Open a new window, optionally in the given working directory.
+    CreateWindow(Option<String>),
     CloseWindow,
     CreateNativeTab(Option<String>),
     CreateConfigEditor,
@@ -75,14 +108,14 @@ pub enum RioEvent {
     /// Grid has changed possibly requiring a mouse cursor shape change.
     MouseCursorDirty,
 
-    /// Window title change.
-    Title(String),
+    /// Window title change, from the route with the id.
+    Title(usize, String),
 
     /// Window title change.
     TitleWithSubtitle(String, String),
 
-    /// Reset to the default window title.
-    ResetTitle,
+    /// Reset to the default window title, from the route with the id.
+    ResetTitle(usize),
 
     /// Request to store a text string in the clipboard.
     ClipboardStore(ClipboardType, String),
@@ -111,13 +144,30 @@ pub enum RioEvent {
     /// Request to write the text area size.
     TextAreaSizeRequest(Arc<dyn Fn(WinsizeBuilder) -> String + Sync + Send + 'static>),
 
+    /// Request to write a report about the window state or geometry.
+    WindowStateRequest(Arc<dyn Fn(WindowState) -> String + Sync + Send + 'static>),
+
     /// Cursor blinking state has changed.
     CursorBlinkingChange,
 
     CursorBlinkingChangeOnRoute(usize),
 
-    /// Terminal bell ring.
-    Bell,
+    /// Terminal bell ring, with the route of the terminal.
+    Bell(usize),
+
+    /// The bell sound was skipped since no audio output is active.
+    BellUnheard,
+
+    /// A command reported through shell integration (OSC 133) has finished.
+    CommandFinished(CommandFinished),
+
+    /// Show a message over the terminal for a few seconds.
+    Toast(String),
+
+    /// An OSC forwarded to the user hooks.
+    PrivateOsc(PrivateOsc),
+    /// Styled text asked by a control socket client.
+    ControlSnapshot(crate::snapshot::SnapshotRequest),
 
     /// Shutdown request.
     Exit,
@@ -130,6 +180,38 @@ pub enum RioEvent {
 
     BlinkCursor(u64, usize),
 
+    /// Redraw a route with blinking text (SGR 5/6) after the given milliseconds.
+    BlinkText(u64, usize),
+
+    /// The profile matching the current tab has changed, `None` restores
+    /// the colors of the config.
+    SwitchProfile(Option<Box<Profile>>),
+
+    /// Sample the usage of the foreground processes and keep sampling
+    /// every couple of seconds.
+    SampleProcessUsage,
+
+    /// Re-evaluate the night mode schedule and keep checking every minute.
+    CheckNightMode,
+
+    /// Show the diff of the pane snapshot in the diff route.
+    ShowPaneDiff,
+
+    /// Ask before sending the pending print job to the printer.
+    ConfirmPrint,
+
+    /// Text was copied to the clipboard, schedules clearing it.
+    ClipboardCopied,
+
+    /// Clear the text copied to the clipboard.
+    ClearClipboard,
+
+    /// The screen got locked while copied text waits to be cleared.
+    ScreenLocked,
+
+    /// Close the window without asking for confirmation.
+    ForceCloseWindow,
+
     UpdateGraphicLibrary,
 
     // No operation
@@ -144,9 +226,10 @@ impl Debug for RioEvent {
             }
             RioEvent::ClipboardLoad(ty, _) => write!(f, "ClipboardLoad({ty:?})"),
             RioEvent::TextAreaSizeRequest(_) => write!(f, "TextAreaSizeRequest"),
+            RioEvent::WindowStateRequest(_) => write!(f, "WindowStateRequest"),
             RioEvent::ColorRequest(index, _) => write!(f, "ColorRequest({index})"),
             RioEvent::PtyWrite(text) => write!(f, "PtyWrite({text})"),
-            RioEvent::Title(title) => write!(f, "Title({title})"),
+            RioEvent::Title(route_id, title) => write!(f, "Title({route_id}, {title})"),
             RioEvent::TitleWithSubtitle(title, subtitle) => {
                 write!(f, "TitleWithSubtitle({title}, {subtitle})")
             }
@@ -158,7 +241,7 @@ impl Debug for RioEvent {
                 write!(f, "CursorBlinkingChangeOnRoute {route_id}")
             }
             RioEvent::MouseCursorDirty => write!(f, "MouseCursorDirty"),
-            RioEvent::ResetTitle => write!(f, "ResetTitle"),
+            RioEvent::ResetTitle(route_id) => write!(f, "ResetTitle({route_id})"),
             RioEvent::PrepareRender(millis) => write!(f, "PrepareRender({millis})"),
             RioEvent::PrepareRenderOnRoute(millis, route) => {
                 write!(f, "PrepareRender({millis} on route {route})")
@@ -166,11 +249,20 @@ impl Debug for RioEvent {
             RioEvent::Render => write!(f, "Render"),
             RioEvent::RenderRoute(route) => write!(f, "Render route {route}"),
             RioEvent::Scroll(scroll) => write!(f, "Scroll {scroll:?}"),
-            RioEvent::Bell => write!(f, "Bell"),
+            RioEvent::Bell(route) => write!(f, "Bell {route}"),
+            RioEvent::BellUnheard => write!(f, "BellUnheard"),
+            RioEvent::CommandFinished(finished) => {
+                write!(f, "CommandFinished({finished:?})")
+            }
+            RioEvent::Toast(message) => write!(f, "Toast({message})"),
+            RioEvent::PrivateOsc(osc) => write!(f, "PrivateOsc({osc:?})"),
+            RioEvent::ControlSnapshot(request) => {
+                write!(f, "ControlSnapshot({request:?})")
+            }
             RioEvent::Exit => write!(f, "Exit"),
             RioEvent::Quit => write!(f, "Quit"),
             RioEvent::CloseTerminal(route) => write!(f, "CloseTerminal {route}"),
-            RioEvent::CreateWindow => write!(f, "CreateWindow"),
+            RioEvent::CreateWindow(_) => write!(f, "CreateWindow"),
             RioEvent::CloseWindow => write!(f, "CloseWindow"),
             RioEvent::CreateNativeTab(_) => write!(f, "CreateNativeTab"),
             RioEvent::SelectNativeTabByIndex(tab_index) => {
@@ -181,6 +273,9 @@ impl Debug for RioEvent {
             RioEvent::SelectNativeTabPrev => write!(f, "SelectNativeTabPrev"),
             RioEvent::CreateConfigEditor => write!(f, "CreateConfigEditor"),
             RioEvent::UpdateConfig => write!(f, "ReloadConfiguration"),
+            RioEvent::FontsLoaded(fonts_not_found) => {
+                write!(f, "FontsLoaded({} not found)", fonts_not_found.len())
+            }
             RioEvent::ReportToAssistant(error_report) => {
                 write!(f, "ReportToAssistant({})", error_report.report)
             }
@@ -188,6 +283,20 @@ impl Debug for RioEvent {
             RioEvent::BlinkCursor(timeout, route_id) => {
                 write!(f, "BlinkCursor {timeout} {route_id}")
             }
+            RioEvent::BlinkText(timeout, route_id) => {
+                write!(f, "BlinkText {timeout} {route_id}")
+            }
+            RioEvent::SwitchProfile(profile) => {
+                write!(f, "SwitchProfile({:?})", profile.as_ref().map(|p| &p.theme))
+            }
+            RioEvent::SampleProcessUsage => write!(f, "SampleProcessUsage"),
+            RioEvent::CheckNightMode => write!(f, "CheckNightMode"),
+            RioEvent::ShowPaneDiff => write!(f, "ShowPaneDiff"),
+            RioEvent::ConfirmPrint => write!(f, "ConfirmPrint"),
+            RioEvent::ClipboardCopied => write!(f, "ClipboardCopied"),
+            RioEvent::ClearClipboard => write!(f, "ClearClipboard"),
+            RioEvent::ScreenLocked => write!(f, "ScreenLocked"),
+            RioEvent::ForceCloseWindow => write!(f, "ForceCloseWindow"),
             RioEvent::Noop => write!(f, "Noop"),
             RioEvent::Copy(_) => write!(f, "Copy"),
             RioEvent::Paste => write!(f, "Paste"),
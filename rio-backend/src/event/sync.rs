@@ -2,7 +2,9 @@
 // https://github.com/alacritty/alacritty/blob/e35e5ad14fce8456afdd89f2b392b9924bb27471/alacritty_terminal/src/sync.rs
 // which is licensed under Apache 2.0 license.
 
-use parking_lot::{Mutex, MutexGuard};
+use parking_lot::{Condvar, Mutex, MutexGuard};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
 
 /// A fair mutex.
 ///
@@ -50,3 +52,101 @@ impl<T> FairMutex<T> {
         self.data.try_lock()
     }
 }
+
+/// Number of frames drawn for a terminal, so the PTY parser can wait for
+/// the renderer instead of parsing a flood of output nobody gets to see.
+/// Terminals in background tabs are hidden and never wait.
+#[derive(Debug, Default)]
+pub struct FrameCounter {
+    frames: Mutex<u64>,
+    drawn: Condvar,
+    hidden: AtomicBool,
+}
+
+impl FrameCounter {
+    pub fn frame_drawn(&self) {
+        *self.frames.lock() += 1;
+        self.drawn.notify_all();
+    }
+
+    pub fn frames(&self) -> u64 {
+        *self.frames.lock()
+    }
+
+    /// Hidden terminals don't get frames drawn, wakes up the parser
+    /// in case it's waiting for one.
+    pub fn set_visible(&self, visible: bool) {
+        let hidden = !visible;
+        if self.hidden.swap(hidden, Ordering::Relaxed) == hidden {
+            return;
+        }
+
+        if hidden {
+            let _frames = self.frames.lock();
+            self.drawn.notify_all();
+        }
+    }
+
+    /// Block until a frame after `frame` is drawn or `timeout` elapses,
+    /// returns right away if the terminal is hidden.
+    pub fn wait_after(&self, frame: u64, timeout: Duration) {
+        let mut frames = self.frames.lock();
+        if *frames == frame && !self.hidden.load(Ordering::Relaxed) {
+            self.drawn.wait_for(&mut frames, timeout);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::time::Instant;
+
+    #[test]
+    fn frame_counter_wakes_up_on_frame() {
+        let counter = Arc::new(FrameCounter::default());
+        let frame = counter.frames();
+
+        let drawer = Arc::clone(&counter);
+        let handle = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(10));
+            drawer.frame_drawn();
+        });
+
+        let start = Instant::now();
+        counter.wait_after(frame, Duration::from_secs(10));
+        assert!(start.elapsed() < Duration::from_secs(10));
+        assert_eq!(counter.frames(), frame + 1);
+        handle.join().unwrap();
+
+        // Frames drawn meanwhile don't wait at all.
+        let start = Instant::now();
+        counter.wait_after(frame, Duration::from_secs(10));
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn frame_counter_hidden_does_not_wait() {
+        let counter = Arc::new(FrameCounter::default());
+        let frame = counter.frames();
+        counter.set_visible(false);
+
+        let start = Instant::now();
+        counter.wait_after(frame, Duration::from_secs(10));
+        assert!(start.elapsed() < Duration::from_secs(1));
+
+        // Hiding the terminal wakes up a parser waiting for a frame.
+        counter.set_visible(true);
+        let waiter = Arc::clone(&counter);
+        let handle = std::thread::spawn(move || {
+            let start = Instant::now();
+            waiter.wait_after(frame, Duration::from_secs(10));
+            start.elapsed()
+        });
+        std::thread::sleep(Duration::from_millis(10));
+        counter.set_visible(false);
+        assert!(handle.join().unwrap() < Duration::from_secs(10));
+        assert_eq!(counter.frames(), frame);
+    }
+}
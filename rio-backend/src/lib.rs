@@ -2,9 +2,13 @@ pub mod ansi;
 pub mod clipboard;
 pub mod config;
 pub mod crosswords;
+pub mod embed;
 pub mod error;
 pub mod event;
 pub mod performer;
+pub mod print;
+pub mod remote;
 pub mod selection;
+pub mod snapshot;
 
 pub use sugarloaf;
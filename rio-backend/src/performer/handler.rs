@@ -1,10 +1,14 @@
 use crate::ansi::iterm2_image_protocol;
-use crate::ansi::CursorShape;
-use crate::ansi::{sixel, KeyboardModes, KeyboardModesApplyBehavior};
+use crate::ansi::private_graphics::{self, PrivateGraphic};
+use crate::ansi::{
+    sixel, KeyboardModes, KeyboardModesApplyBehavior, RectangularArea, SemanticPrompt,
+};
+use crate::ansi::{CursorShape, LineSize};
 use crate::config::colors::{AnsiColor, ColorRgb, NamedColor};
 use crate::crosswords::pos::{CharsetIndex, Column, Line, StandardCharset};
 use crate::crosswords::square::Hyperlink;
 use cursor_icon::CursorIcon;
+use std::path::PathBuf;
 use std::str::FromStr;
 use std::time::Duration;
 use std::time::Instant;
@@ -38,6 +42,12 @@ const BSU_CSI: [u8; SYNC_ESCAPE_LEN] = *b"\x1b[?2026h";
 /// ESU CSI sequence for terminating synchronized updates.
 const ESU_CSI: [u8; SYNC_ESCAPE_LEN] = *b"\x1b[?2026l";
 
+/// Maximum number of bytes read in one XTGETTCAP request.
+const XTGETTCAP_MAX_LEN: usize = 1024;
+
+/// Maximum number of bytes read in one DECRQSS request.
+const DECRQSS_MAX_LEN: usize = 8;
+
 fn xparse_color(color: &[u8]) -> Option<ColorRgb> {
     if !color.is_empty() && color[0] == b'#' {
         parse_legacy_color(&color[1..])
@@ -113,6 +123,23 @@ fn parse_number(input: &[u8]) -> Option<u8> {
     Some(num)
 }
 
+fn hex_decode(input: &[u8]) -> Option<Vec<u8>> {
+    if input.is_empty() {
+        return None;
+    }
+
+    input
+        .chunks(2)
+        .map(|pair| {
+            if pair.len() != 2 {
+                return None;
+            }
+            let pair = std::str::from_utf8(pair).ok()?;
+            u8::from_str_radix(pair, 16).ok()
+        })
+        .collect()
+}
+
 fn parse_sgr_color(params: &mut dyn Iterator<Item = u16>) -> Option<AnsiColor> {
     match params.next() {
         Some(2) => Some(AnsiColor::Spec(ColorRgb {
@@ -138,6 +165,18 @@ pub trait Handler {
     /// OSC to set window title.
     fn set_title(&mut self, _: Option<String>) {}
 
+    /// OSC 7 to report the current working directory.
+    fn set_current_directory(&mut self, _: PathBuf) {}
+
+    /// OSC 1337 SetUserVar, the value is base64 encoded.
+    fn set_user_var(&mut self, _name: &str, _base64: &[u8]) {}
+
+    /// OSC with a code Rio doesn't know, returns whether it was forwarded
+    /// to the user hooks.
+    fn private_osc(&mut self, _code: u16, _payload: &[u8]) -> bool {
+        false
+    }
+
     /// Set the cursor style.
     fn set_cursor_style(&mut self, _style: Option<CursorShape>, _blinking: bool) {}
 
@@ -168,6 +207,9 @@ pub trait Handler {
     /// Identify the terminal (should write back to the pty stream).
     fn identify_terminal(&mut self, _intermediate: Option<char>) {}
 
+    /// Report terminal name and version (XTVERSION).
+    fn report_version(&mut self) {}
+
     /// Report device status.
     fn device_status(&mut self, _: usize) {}
 
@@ -233,6 +275,21 @@ pub trait Handler {
     /// to the right of the deleted things is shifted left.
     fn delete_chars(&mut self, _: usize) {}
 
+    /// Copy a rectangular area to the given top-left position (DECCRA).
+    fn copy_rectangular_area(
+        &mut self,
+        _area: RectangularArea,
+        _top: usize,
+        _left: usize,
+    ) {
+    }
+
+    /// Fill a rectangular area with a character (DECFRA).
+    fn fill_rectangular_area(&mut self, _c: char, _area: RectangularArea) {}
+
+    /// Erase a rectangular area (DECERA).
+    fn erase_rectangular_area(&mut self, _area: RectangularArea) {}
+
     /// Move backward `count` tabs.
     fn move_backward_tabs(&mut self, _count: u16) {}
 
@@ -254,6 +311,9 @@ pub trait Handler {
     /// Clear tab stops.
     fn clear_tabs(&mut self, _mode: TabulationClearMode) {}
 
+    /// Set tab stops every 8 columns, removing the others.
+    fn reset_tabs(&mut self) {}
+
     /// Reset terminal state.
     fn reset_state(&mut self) {}
 
@@ -288,6 +348,9 @@ pub trait Handler {
     /// DECSTBM - Set the terminal scrolling region.
     fn set_scrolling_region(&mut self, _top: usize, _bottom: Option<usize>) {}
 
+    /// DECSLRM - Set the left and right margins, SCOSC when DECLRMM is reset.
+    fn set_left_right_margins(&mut self, _left: usize, _right: Option<usize>) {}
+
     /// DECKPAM - Set keypad to applications mode (ESCape instead of digits).
     fn set_keypad_application_mode(&mut self) {}
 
@@ -324,6 +387,9 @@ pub trait Handler {
     /// Run the decaln routine.
     fn decaln(&mut self) {}
 
+    /// Set the size of the cursor line (DECDWL, DECDHL and DECSWL).
+    fn set_line_size(&mut self, _: LineSize) {}
+
     /// Push a title onto the stack.
     fn push_title(&mut self) {}
 
@@ -339,6 +405,18 @@ pub trait Handler {
     /// Report text area size in characters.
     fn text_area_size_chars(&mut self) {}
 
+    /// Report window size in pixels.
+    fn window_size_pixels(&mut self) {}
+
+    /// Report window position in pixels.
+    fn window_position(&mut self) {}
+
+    /// Report whether the window is iconified.
+    fn window_state(&mut self) {}
+
+    /// Minimize or restore the window.
+    fn set_minimized(&mut self, _: bool) {}
+
     /// Report a graphics attribute.
     fn graphics_attribute(&mut self, _: u16, _: u16) {}
 
@@ -359,12 +437,24 @@ pub trait Handler {
     /// Set hyperlink.
     fn set_hyperlink(&mut self, _: Option<Hyperlink>) {}
 
+    /// Record a shell integration mark at the cursor position.
+    fn semantic_prompt(&mut self, _mark: SemanticPrompt) {}
+
+    /// Render a Rio private graphic (QR code or math expression) inline.
+    fn private_graphic(&mut self, _graphic: PrivateGraphic) {}
+
     /// Set mouse cursor icon.
     fn set_mouse_cursor_icon(&mut self, _: CursorIcon) {}
 
+    /// Report the value of a control function setting (DECRQSS).
+    fn report_setting(&mut self, _setting: &[u8]) {}
+
     /// Report current keyboard mode.
     fn report_keyboard_mode(&mut self) {}
 
+    /// Report terminfo capabilities requested by XTGETTCAP.
+    fn report_terminfo_capabilities(&mut self, _names: Vec<String>) {}
+
     /// Push keyboard mode into the keyboard mode stack.
     fn push_keyboard_mode(&mut self, _mode: KeyboardModes) {}
 
@@ -391,6 +481,12 @@ struct ProcessorState {
 
     /// State for synchronized terminal updates.
     sync_state: SyncState,
+
+    /// Hex encoded capability names of an ongoing XTGETTCAP request.
+    xtgettcap: Option<Vec<u8>>,
+
+    /// Setting requested by an ongoing DECRQSS request.
+    decrqss: Option<Vec<u8>>,
 }
 
 #[derive(Debug)]
@@ -553,6 +649,12 @@ impl<U: Handler> copa::Perform for Performer<'_, U> {
             ('q', []) => {
                 self.handler.sixel_graphic_start(params);
             }
+            ('q', [b'+']) => {
+                self.state.xtgettcap = Some(Vec::new());
+            }
+            ('q', [b'$']) => {
+                self.state.decrqss = Some(Vec::new());
+            }
             _ => debug!(
                 "[unhandled hook] params={:?}, ints: {:?}, ignore: {:?}, action: {:?}",
                 params, intermediates, ignore, action
@@ -561,7 +663,15 @@ impl<U: Handler> copa::Perform for Performer<'_, U> {
     }
 
     fn put(&mut self, byte: u8) {
-        if self.handler.is_sixel_graphic_active() {
+        if let Some(xtgettcap) = &mut self.state.xtgettcap {
+            if xtgettcap.len() < XTGETTCAP_MAX_LEN {
+                xtgettcap.push(byte);
+            }
+        } else if let Some(decrqss) = &mut self.state.decrqss {
+            if decrqss.len() < DECRQSS_MAX_LEN {
+                decrqss.push(byte);
+            }
+        } else if self.handler.is_sixel_graphic_active() {
             if let Err(err) = self.handler.sixel_graphic_put(byte) {
                 tracing::warn!("Failed to parse Sixel data: {}", err);
                 self.handler.sixel_graphic_reset();
@@ -573,7 +683,18 @@ impl<U: Handler> copa::Perform for Performer<'_, U> {
 
     #[inline]
     fn unhook(&mut self) {
-        if self.handler.is_sixel_graphic_active() {
+        if let Some(xtgettcap) = self.state.xtgettcap.take() {
+            let names = xtgettcap
+                .split(|byte| *byte == b';')
+                .filter_map(|name| {
+                    let name = hex_decode(name)?;
+                    String::from_utf8(name).ok()
+                })
+                .collect();
+            self.handler.report_terminfo_capabilities(names);
+        } else if let Some(setting) = self.state.decrqss.take() {
+            self.handler.report_setting(&setting);
+        } else if self.handler.is_sixel_graphic_active() {
             self.handler.sixel_graphic_finish();
         } else {
             debug!("[unhandled dcs_unhook]");
@@ -618,6 +739,19 @@ impl<U: Handler> copa::Perform for Performer<'_, U> {
                 unhandled(params);
             }
 
+            // Report current working directory.
+            b"7" if params.len() >= 2 => {
+                let uri = params[1..]
+                    .iter()
+                    .flat_map(|x| std::str::from_utf8(x))
+                    .collect::<Vec<&str>>()
+                    .join(";");
+                match parse_file_uri(&uri) {
+                    Some(path) => self.handler.set_current_directory(path),
+                    None => unhandled(params),
+                }
+            }
+
             // Set color index.
             b"4" => {
                 if params.len() <= 1 || params.len() % 2 == 0 {
@@ -772,15 +906,66 @@ impl<U: Handler> copa::Perform for Performer<'_, U> {
             // Reset text cursor color.
             b"112" => self.handler.reset_color(NamedColor::Cursor as usize),
 
+            // Shell integration semantic prompt marks.
+            b"133" if params.len() >= 2 => {
+                let mark = match params[1].first() {
+                    Some(b'A') => SemanticPrompt::PromptStart {
+                        redraw: !params[2..].contains(&&b"redraw=0"[..]),
+                    },
+                    Some(b'B') => SemanticPrompt::CommandStart,
+                    Some(b'C') => SemanticPrompt::CommandExecuted,
+                    Some(b'D') => SemanticPrompt::CommandFinished(
+                        params
+                            .get(2)
+                            .and_then(|code| std::str::from_utf8(code).ok())
+                            .and_then(|code| code.parse().ok()),
+                    ),
+                    _ => return unhandled(params),
+                };
+
+                self.handler.semantic_prompt(mark);
+            }
+
             // OSC 1337 is not necessarily only used by iTerm2 protocol
             // OSC 1337 is equal to xterm OSC 50
             b"1337" => {
+                if let Some(user_var) = params
+                    .get(1)
+                    .and_then(|param| param.strip_prefix(b"SetUserVar="))
+                {
+                    let mut user_var = user_var.splitn(2, |&byte| byte == b'=');
+                    let name = user_var
+                        .next()
+                        .and_then(|name| std::str::from_utf8(name).ok());
+                    match (name, user_var.next()) {
+                        (Some(name), Some(base64)) if !name.is_empty() => {
+                            self.handler.set_user_var(name, base64)
+                        }
+                        _ => unhandled(params),
+                    }
+                    return;
+                }
+
                 if let Some(graphic) = iterm2_image_protocol::parse(params) {
                     self.handler.insert_graphic(graphic, None);
                 }
             }
 
-            _ => unhandled(params),
+            // Rio private graphics, QR codes and math expressions.
+            b"1338" => match private_graphics::parse(params) {
+                Some(graphic) => self.handler.private_graphic(graphic),
+                None => unhandled(params),
+            },
+
+            code => {
+                let code = std::str::from_utf8(code)
+                    .ok()
+                    .and_then(|code| code.parse().ok());
+                let payload = params[1..].join(&b';');
+                if !code.is_some_and(|code| self.handler.private_osc(code, &payload)) {
+                    unhandled(params);
+                }
+            }
         }
     }
 
@@ -937,6 +1122,33 @@ impl<U: Handler> copa::Perform for Performer<'_, U> {
                 let mode = next_param_or(0);
                 handler.report_private_mode(PrivateMode::new(mode));
             }
+            ('v', [b'$']) => {
+                // DECCRA (CSI Pts ; Pls ; Pbs ; Prs ; Pps ; Ptd ; Pld ; Ppd $ v).
+                let area = next_rectangular_area(&mut next_param_or);
+                let _page = next_param_or(1);
+                let top = next_param_or(1) as usize;
+                let left = next_param_or(1) as usize;
+                handler.copy_rectangular_area(area, top, left);
+            }
+            ('x', [b'$']) => {
+                // DECFRA (CSI Pch ; Pt ; Pl ; Pb ; Pr $ x).
+                let c = next_param_or(0);
+                let area = next_rectangular_area(&mut next_param_or);
+                if matches!(c, 32..=126 | 160..=255) {
+                    handler.fill_rectangular_area(c as u8 as char, area);
+                } else {
+                    csi_unhandled!();
+                }
+            }
+            ('z', [b'$']) => {
+                // DECERA (CSI Pt ; Pl ; Pb ; Pr $ z).
+                let area = next_rectangular_area(&mut next_param_or);
+                handler.erase_rectangular_area(area);
+            }
+            ('q', [b'>']) if next_param_or(0) == 0 => {
+                // XTVERSION (CSI > Ps q) -- Report xterm name and version.
+                handler.report_version();
+            }
             ('q', [b' ']) => {
                 // DECSCUSR (CSI Ps SP q) -- Set Cursor Style.
                 let cursor_style_id = next_param_or(0);
@@ -966,14 +1178,31 @@ impl<U: Handler> copa::Perform for Performer<'_, U> {
             ('S', [b'?']) => {
                 handler.graphics_attribute(next_param_or(0), next_param_or(0))
             }
-            ('s', []) => handler.save_cursor_position(),
+            // SCOSC without DECLRMM, see `set_left_right_margins`.
+            ('s', []) => {
+                let left = next_param_or(1) as usize;
+                let right = params_iter
+                    .next()
+                    .map(|param| param[0] as usize)
+                    .filter(|&param| param != 0);
+
+                handler.set_left_right_margins(left, right);
+            }
             ('T', []) => handler.scroll_down(next_param_or(1) as usize),
             ('t', []) => match next_param_or(1) as usize {
+                1 => handler.set_minimized(false),
+                2 => handler.set_minimized(true),
+                11 => handler.window_state(),
+                13 => handler.window_position(),
+                14 if next_param_or(0) == 2 => handler.window_size_pixels(),
                 14 => handler.text_area_size_pixels(),
                 16 => handler.cells_size_pixels(),
                 18 => handler.text_area_size_chars(),
-                22 => handler.push_title(),
-                23 => handler.pop_title(),
+                // Second parameter selects icon (1), title (2) or both (0),
+                // only the window title stack is supported.
+                22 if next_param_or(0) != 1 => handler.push_title(),
+                23 if next_param_or(0) != 1 => handler.pop_title(),
+                22 | 23 => {}
                 _ => csi_unhandled!(),
             },
             ('u', [b'?']) => handler.report_keyboard_mode(),
@@ -996,6 +1225,13 @@ impl<U: Handler> copa::Perform for Performer<'_, U> {
                 handler.pop_keyboard_modes(next_param_or(1));
             }
             ('u', []) => handler.restore_cursor_position(),
+            ('W', []) => match next_param_or(0) {
+                0 => handler.set_horizontal_tabstop(),
+                2 => handler.clear_tabs(TabulationClearMode::Current),
+                5 => handler.clear_tabs(TabulationClearMode::All),
+                _ => csi_unhandled!(),
+            },
+            ('W', [b'?']) if next_param_or(0) == 5 => handler.reset_tabs(),
             ('X', []) => handler.erase_chars(Column(next_param_or(1) as usize)),
             ('Z', []) => handler.move_backward_tabs(next_param_or(1)),
             _ => csi_unhandled!(),
@@ -1048,6 +1284,10 @@ impl<U: Handler> copa::Perform for Performer<'_, U> {
                 )
             }
             (b'7', []) => self.handler.save_cursor_position(),
+            (b'3', [b'#']) => self.handler.set_line_size(LineSize::DoubleHeightTop),
+            (b'4', [b'#']) => self.handler.set_line_size(LineSize::DoubleHeightBottom),
+            (b'5', [b'#']) => self.handler.set_line_size(LineSize::Normal),
+            (b'6', [b'#']) => self.handler.set_line_size(LineSize::DoubleWidth),
             (b'8', [b'#']) => self.handler.decaln(),
             (b'8', []) => self.handler.restore_cursor_position(),
             (b'=', []) => self.handler.set_keypad_application_mode(),
@@ -1059,6 +1299,21 @@ impl<U: Handler> copa::Perform for Performer<'_, U> {
     }
 }
 
+/// Parse the `Pt ; Pl ; Pb ; Pr` edges of a DEC rectangle operation.
+#[inline]
+fn next_rectangular_area(next_param_or: &mut impl FnMut(u16) -> u16) -> RectangularArea {
+    let top = next_param_or(1) as usize;
+    let left = next_param_or(1) as usize;
+    let bottom = Some(next_param_or(0) as usize).filter(|bottom| *bottom != 0);
+    let right = Some(next_param_or(0) as usize).filter(|right| *right != 0);
+    RectangularArea {
+        top,
+        left,
+        bottom,
+        right,
+    }
+}
+
 #[inline]
 fn attrs_from_sgr_parameters(params: &mut ParamsIter<'_>) -> Vec<Option<Attr>> {
     let mut attrs = Vec::with_capacity(params.size_hint().0);
@@ -1116,6 +1371,8 @@ fn attrs_from_sgr_parameters(params: &mut ParamsIter<'_>) -> Vec<Option<Attr>> {
             }
             [48, params @ ..] => handle_colon_rgb(params).map(Attr::Background),
             [49] => Some(Attr::Background(AnsiColor::Named(NamedColor::Background))),
+            [53] => Some(Attr::Overline),
+            [55] => Some(Attr::CancelOverline),
             [58] => {
                 let mut iter = params.map(|param| param[0]);
                 parse_sgr_color(&mut iter).map(|color| Attr::UnderlineColor(Some(color)))
@@ -1147,3 +1404,48 @@ fn attrs_from_sgr_parameters(params: &mut ParamsIter<'_>) -> Vec<Option<Attr>> {
 
     attrs
 }
+
+/// Path of a `file://host/path` URI as sent by OSC 7, the host is ignored.
+pub fn parse_file_uri(uri: &str) -> Option<PathBuf> {
+    let rest = uri.strip_prefix("file://")?;
+    let path = &rest[rest.find('/')?..];
+
+    let mut bytes = Vec::with_capacity(path.len());
+    let mut iter = path.bytes();
+    while let Some(byte) = iter.next() {
+        if byte == b'%' {
+            let hex = [iter.next()?, iter.next()?];
+            let hex = std::str::from_utf8(&hex).ok()?;
+            bytes.push(u8::from_str_radix(hex, 16).ok()?);
+        } else {
+            bytes.push(byte);
+        }
+    }
+    let path = String::from_utf8(bytes).ok()?;
+
+    // `file:///C:/Users` on Windows.
+    #[cfg(windows)]
+    let path = match path.as_bytes() {
+        [b'/', drive, b':', ..] if drive.is_ascii_alphabetic() => path[1..].to_string(),
+        _ => path,
+    };
+
+    Some(PathBuf::from(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_osc7_file_uri() {
+        assert_eq!(
+            parse_file_uri("file://host/home/rio/My%20Projects"),
+            Some(PathBuf::from("/home/rio/My Projects"))
+        );
+        assert_eq!(parse_file_uri("file:///tmp"), Some(PathBuf::from("/tmp")));
+        assert_eq!(parse_file_uri("file://host"), None);
+        assert_eq!(parse_file_uri("file:///tmp/%2"), None);
+        assert_eq!(parse_file_uri("https://host/tmp"), None);
+    }
+}
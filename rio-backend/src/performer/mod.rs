@@ -1,7 +1,7 @@
 pub mod handler;
 
 use crate::crosswords::Crosswords;
-use crate::event::sync::FairMutex;
+use crate::event::sync::{FairMutex, FrameCounter};
 use crate::event::RioEvent;
 use crate::event::{EventListener, Msg, WindowId};
 use corcovado::channel;
@@ -11,9 +11,10 @@ use corcovado::{self, Events, PollOpt, Ready};
 use std::borrow::Cow;
 use std::collections::VecDeque;
 use std::io::{self, ErrorKind, Read, Write};
+use std::sync::mpsc::{self, RecvTimeoutError};
 use std::sync::Arc;
 use std::thread::{Builder, JoinHandle};
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use tracing::error;
 
 /// Like `thread::spawn`, but with a `name` argument.
@@ -29,26 +30,53 @@ where
         .expect("thread spawn works")
 }
 
-const READ_BUFFER_SIZE: usize = 0x10_0000;
-/// Max bytes to read from the PTY while the terminal is locked.
+/// Size of a single read from the PTY.
+const READ_BUFFER_SIZE: usize = 0x1_0000;
+/// Number of read buffers, bounds how much output is queued ahead of the
+/// parser. Once all of them are queued the reader waits for the parser.
+const READ_BUFFERS: usize = 16;
+/// Max bytes to parse while the terminal is locked.
 const MAX_LOCKED_READ: usize = u16::MAX as usize;
+/// Max bytes to parse before waiting for a frame with the output to be
+/// drawn. Meanwhile the read buffers fill up and reading from the PTY stops.
+const MAX_FRAME_READ: usize = 0x10_0000;
+/// Max time to wait for a frame, e.g. the window may be minimized.
+const MAX_FRAME_WAIT: Duration = Duration::from_millis(16);
+/// Max bytes to read once the child exited, background processes may keep
+/// writing to the PTY.
+const MAX_DRAIN: usize = 4 * READ_BUFFERS * READ_BUFFER_SIZE;
+/// Interval between checks for a buffer given back by the parser while
+/// draining, the recycle channel can only be polled.
+const RECYCLE_WAIT: Duration = Duration::from_millis(1);
+
+/// Handoff from the PTY reader to the parser thread. It goes through a
+/// bounded `mpsc::sync_channel`, which is a lock-free array queue on the
+/// fast path and also lets the parser block with a timeout.
+enum Output {
+    /// A read buffer and the number of bytes read into it.
+    Bytes(Vec<u8>, usize),
+    /// The child process exited, sent after its last output.
+    Exited(Option<i32>),
+}
 
+/// Reads and writes the PTY in its own thread, the output is parsed by
+/// an [`OutputParser`] thread so huge bursts of output never delay input.
 pub struct Machine<T: teletypewriter::EventedPty, U: EventListener> {
     sender: channel::Sender<Msg>,
     receiver: channel::Receiver<Msg>,
     pty: T,
     poll: corcovado::Poll,
-    terminal: Arc<FairMutex<Crosswords<U>>>,
-    event_proxy: U,
-    window_id: WindowId,
-    route_id: usize,
+    output: mpsc::SyncSender<Output>,
+    parser: Option<OutputParser<U>>,
+    /// Buffers given back by the parser once parsed.
+    recycled: channel::Receiver<Vec<u8>>,
+    free_buffers: Vec<Vec<u8>>,
 }
 
 #[derive(Default)]
 pub struct State {
     write_list: VecDeque<Cow<'static, [u8]>>,
     writing: Option<Writing>,
-    parser: handler::ParserProcessor,
 }
 
 impl State {
@@ -125,78 +153,110 @@ where
         let (sender, receiver) = channel::channel();
         let poll = corcovado::Poll::new()?;
 
+        // Every buffer is either free, queued or being parsed, so sending
+        // to the parser never blocks.
+        let (output, queued) = mpsc::sync_channel(READ_BUFFERS + 1);
+        let (recycle, recycled) = channel::channel();
+        let frames = Arc::clone(&terminal.lock().frames);
+        let parser = OutputParser {
+            parser: handler::ParserProcessor::new(),
+            frames,
+            terminal,
+            event_proxy,
+            window_id,
+            route_id,
+            queued,
+            recycle,
+        };
+
         Ok(Machine {
             sender,
             receiver,
             poll,
             pty,
-            terminal,
-            event_proxy,
-            window_id,
-            route_id,
+            output,
+            parser: Some(parser),
+            recycled,
+            free_buffers: (0..READ_BUFFERS).map(|_| Vec::new()).collect(),
         })
     }
 
+    /// Reads from the PTY into the free buffers and queues them for the
+    /// parser. Stops early when all buffers are queued, reading resumes
+    /// once the parser gives one back. Returns false in that case.
     #[inline]
-    fn pty_read(&mut self, state: &mut State, buf: &mut [u8]) -> io::Result<()> {
-        let mut unprocessed = 0;
-        let mut processed = 0;
-
-        // Reserve the next terminal lock for PTY reading.
-        let _terminal_lease = Some(self.terminal.lease());
-        let mut terminal = None;
+    fn pty_read(&mut self) -> io::Result<bool> {
+        while let Some(mut buf) = self.free_buffers.pop() {
+            if buf.is_empty() {
+                buf.resize(READ_BUFFER_SIZE, 0);
+            }
 
-        loop {
-            // Read from the PTY.
-            match self.pty.reader().read(&mut buf[unprocessed..]) {
+            match self.pty.reader().read(&mut buf) {
                 // This is received on Windows/macOS when no more data is readable from the PTY.
-                Ok(0) if unprocessed == 0 => break,
-                Ok(got) => unprocessed += got,
-                Err(err) => match err.kind() {
-                    ErrorKind::Interrupted | ErrorKind::WouldBlock => {
-                        // Go back to mio if we're caught up on parsing and the PTY would block.
-                        if unprocessed == 0 {
-                            break;
+                Ok(0) => {
+                    self.free_buffers.push(buf);
+                    return Ok(true);
+                }
+                Ok(got) => {
+                    if self.output.send(Output::Bytes(buf, got)).is_err() {
+                        return Err(ErrorKind::BrokenPipe.into());
+                    }
+                }
+                Err(err) => {
+                    self.free_buffers.push(buf);
+                    match err.kind() {
+                        // Go back to mio if the PTY would block.
+                        ErrorKind::Interrupted | ErrorKind::WouldBlock => {
+                            return Ok(true)
                         }
+                        _ => return Err(err),
                     }
-                    _ => return Err(err),
-                },
+                }
             }
+        }
 
-            // Attempt to lock the terminal.
-            let terminal = match &mut terminal {
-                Some(terminal) => terminal,
-                None => terminal.insert(match self.terminal.try_lock_unfair() {
-                    // Force block if we are at the buffer size limit.
-                    None if unprocessed >= READ_BUFFER_SIZE => {
-                        self.terminal.lock_unfair()
-                    }
-                    None => continue,
-                    Some(terminal) => terminal,
-                }),
-            };
+        Ok(false)
+    }
 
-            // Parse the incoming bytes.
-            for byte in &buf[..unprocessed] {
-                state.parser.advance(&mut **terminal, *byte);
+    /// Reads the output left in the PTY once the child exited, up to
+    /// `MAX_DRAIN` bytes. Waits for the parser to give buffers back when
+    /// all of them are queued.
+    fn pty_drain(&mut self) {
+        let mut drained = 0;
+        while drained < MAX_DRAIN {
+            let reading = self.free_buffers.len();
+            if !matches!(self.pty_read(), Ok(false)) {
+                break;
             }
 
-            processed += unprocessed;
-            unprocessed = 0;
-
-            // Assure we're not blocking the terminal too long unnecessarily.
-            if processed >= MAX_LOCKED_READ {
+            drained += reading * READ_BUFFER_SIZE;
+            if !self.wait_for_buffer() {
                 break;
             }
         }
+    }
 
-        // Queue terminal redraw unless all processed bytes were synchronized.
-        if state.parser.sync_bytes_count() < processed && processed > 0 {
-            self.event_proxy
-                .send_event(RioEvent::RenderRoute(self.route_id), self.window_id);
+    /// Blocks until the parser gives a buffer back, returns false if the
+    /// parser is gone.
+    fn wait_for_buffer(&mut self) -> bool {
+        loop {
+            match self.recycled.try_recv() {
+                Ok(buf) => {
+                    self.free_buffers.push(buf);
+                    self.recycle_buffers();
+                    return true;
+                }
+                Err(mpsc::TryRecvError::Empty) => std::thread::sleep(RECYCLE_WAIT),
+                Err(mpsc::TryRecvError::Disconnected) => return false,
+            }
         }
+    }
 
-        Ok(())
+    #[inline]
+    fn recycle_buffers(&mut self) {
+        while let Ok(buf) = self.recycled.try_recv() {
+            self.free_buffers.push(buf);
+        }
     }
 
     fn should_keep_alive(&mut self, state: &mut State) -> bool {
@@ -272,9 +332,12 @@ where
     }
 
     pub fn spawn(mut self) {
+        if let Some(parser) = self.parser.take() {
+            spawn_named("PTY parser", move || parser.run());
+        }
+
         spawn_named("PTY reader", move || {
             let mut state = State::default();
-            let mut buf = [0u8; READ_BUFFER_SIZE];
 
             let mut tokens = (0..).map(Into::into);
 
@@ -285,6 +348,11 @@ where
                 .register(&self.receiver, channel_token, Ready::readable(), poll_opts)
                 .unwrap();
 
+            let recycle_token = tokens.next().unwrap();
+            self.poll
+                .register(&self.recycled, recycle_token, Ready::readable(), poll_opts)
+                .unwrap();
+
             // Register TTY through EventedRW interface.
             self.pty
                 .register(&self.poll, &mut tokens, Ready::readable(), poll_opts)
@@ -293,27 +361,13 @@ where
             let mut events = Events::with_capacity(1024);
 
             'event_loop: loop {
-                // Wakeup the event loop when a synchronized update timeout was reached.
-                let sync_timeout = state.parser.sync_timeout();
-                let timeout =
-                    sync_timeout.map(|st| st.saturating_duration_since(Instant::now()));
-
-                if let Err(err) = self.poll.poll(&mut events, timeout) {
+                if let Err(err) = self.poll.poll(&mut events, None) {
                     match err.kind() {
                         ErrorKind::Interrupted => continue,
                         _ => panic!("EventLoop polling error: {err:?}"),
                     }
                 }
 
-                // Handle synchronized update timeout.
-                if events.is_empty() {
-                    state.parser.stop_sync(&mut *self.terminal.lock());
-                    self.event_proxy
-                        .send_event(RioEvent::RenderRoute(self.route_id), self.window_id);
-
-                    continue;
-                }
-
                 for event in events.iter() {
                     match event.token() {
                         token if token == channel_token => {
@@ -322,23 +376,31 @@ where
                                 break 'event_loop;
                             }
                         }
+                        token if token == recycle_token => {
+                            self.recycle_buffers();
+                            self.poll
+                                .reregister(
+                                    &self.recycled,
+                                    recycle_token,
+                                    Ready::readable(),
+                                    poll_opts,
+                                )
+                                .unwrap();
+
+                            // Output left in the PTY while every buffer was queued.
+                            if let Err(err) = self.pty_read() {
+                                error!("Error reading from PTY in event loop: {}", err);
+                                break 'event_loop;
+                            }
+                        }
                         token if token == self.pty.child_event_token() => {
-                            if let Some(teletypewriter::ChildEvent::Exited) =
+                            if let Some(teletypewriter::ChildEvent::Exited(code)) =
                                 self.pty.next_child_event()
                             {
-                                // In the future allow configure exit
-                                // if self.hold {
-                                //     With hold enabled, make sure the PTY is drained.
-                                //     let _ = self.pty_read(&mut state, &mut buf);
-                                // } else {
-                                //     // Without hold, shutdown the terminal.
-                                //     self.terminal.lock().exit();
-                                // }
-
-                                self.terminal.lock().exit();
-
-                                self.event_proxy
-                                    .send_event(RioEvent::Render, self.window_id);
+                                // Make sure the PTY is drained so the last output
+                                // is visible while holding.
+                                self.pty_drain();
+                                let _ = self.output.send(Output::Exited(code));
 
                                 break 'event_loop;
                             }
@@ -350,11 +412,17 @@ where
                         {
                             #[cfg(unix)]
                             if UnixReady::from(event.readiness()).is_hup() {
+                                if self.pty.exits_on_hangup() {
+                                    self.pty_drain();
+                                    let _ = self.output.send(Output::Exited(None));
+                                    break 'event_loop;
+                                }
+
                                 // Don't try to do I/O on a dead PTY.
                                 continue;
                             }
                             if event.readiness().is_readable() {
-                                if let Err(err) = self.pty_read(&mut state, &mut buf) {
+                                if let Err(err) = self.pty_read() {
                                     // On Linux, a `read` on the master side of a PTY can fail
                                     // with `EIO` if the client side hangs up.  In that case,
                                     // just loop back round for the inevitable `Exited` event.
@@ -382,8 +450,13 @@ where
                     }
                 }
 
-                // Register write interest if necessary.
-                let mut interest = Ready::readable();
+                // Register read interest only while there is a free buffer to read
+                // into, the recycled buffers wake the loop up again. Register
+                // write interest if necessary.
+                let mut interest = Ready::empty();
+                if !self.free_buffers.is_empty() {
+                    interest.insert(Ready::readable());
+                }
                 if state.needs_write() {
                     interest.insert(Ready::writable());
                 }
@@ -395,9 +468,131 @@ where
 
             // The evented instances are not dropped here so deregister them explicitly.
             let _ = self.poll.deregister(&self.receiver);
+            let _ = self.poll.deregister(&self.recycled);
             let _ = self.pty.deregister(&self.poll);
 
             (self, state)
         });
     }
 }
+
+/// Parses the output queued by the PTY reader of a [`Machine`].
+struct OutputParser<U: EventListener> {
+    parser: handler::ParserProcessor,
+    frames: Arc<FrameCounter>,
+    terminal: Arc<FairMutex<Crosswords<U>>>,
+    event_proxy: U,
+    window_id: WindowId,
+    route_id: usize,
+    queued: mpsc::Receiver<Output>,
+    recycle: channel::Sender<Vec<u8>>,
+}
+
+impl<U: EventListener> OutputParser<U> {
+    fn run(mut self) {
+        let mut frame = self.frames.frames();
+        let mut frame_read = 0;
+
+        loop {
+            // Wakeup when a synchronized update timeout was reached.
+            let output = match self.parser.sync_timeout() {
+                Some(timeout) => {
+                    let timeout = timeout.saturating_duration_since(Instant::now());
+                    match self.queued.recv_timeout(timeout) {
+                        Ok(output) => output,
+                        Err(RecvTimeoutError::Timeout) => {
+                            self.parser.stop_sync(&mut *self.terminal.lock());
+                            self.event_proxy.send_event(
+                                RioEvent::RenderRoute(self.route_id),
+                                self.window_id,
+                            );
+                            continue;
+                        }
+                        Err(RecvTimeoutError::Disconnected) => break,
+                    }
+                }
+                None => match self.queued.recv() {
+                    Ok(output) => output,
+                    Err(_) => break,
+                },
+            };
+
+            let Some(processed) = self.parse(output) else {
+                break;
+            };
+
+            let drawn = self.frames.frames();
+            if drawn != frame {
+                frame = drawn;
+                frame_read = 0;
+            }
+
+            // Flow control, give the renderer a chance to show the output
+            // instead of parsing as fast as it comes. Synchronized updates
+            // are not drawn until they end, so there is no point waiting.
+            frame_read += processed;
+            if frame_read >= MAX_FRAME_READ && self.parser.sync_timeout().is_none() {
+                self.frames.wait_after(frame, MAX_FRAME_WAIT);
+                frame = self.frames.frames();
+                frame_read = 0;
+            }
+        }
+    }
+
+    /// Parses `output` and whatever else is queued, until the locked read
+    /// limit. Returns the bytes parsed or None once the child process exited.
+    fn parse(&mut self, mut output: Output) -> Option<usize> {
+        let mut processed = 0;
+        let mut exited = None;
+
+        {
+            // Reserve the next terminal lock for parsing.
+            let _terminal_lease = self.terminal.lease();
+            let mut terminal = self.terminal.lock_unfair();
+
+            loop {
+                match output {
+                    Output::Bytes(buf, len) => {
+                        for byte in &buf[..len] {
+                            self.parser.advance(&mut *terminal, *byte);
+                        }
+                        processed += len;
+                        let _ = self.recycle.send(buf);
+                    }
+                    Output::Exited(code) => {
+                        exited = Some(code);
+                        break;
+                    }
+                }
+
+                // Assure we're not blocking the terminal too long unnecessarily.
+                if processed >= MAX_LOCKED_READ {
+                    break;
+                }
+
+                output = match self.queued.try_recv() {
+                    Ok(output) => output,
+                    Err(_) => break,
+                };
+            }
+
+            if let Some(code) = exited {
+                terminal.child_exited(code);
+            }
+        }
+
+        if exited.is_some() {
+            self.event_proxy
+                .send_event(RioEvent::Render, self.window_id);
+            return None;
+        }
+
+        // Queue terminal redraw unless all processed bytes were synchronized.
+        if self.parser.sync_bytes_count() < processed && processed > 0 {
+            self.event_proxy
+                .send_event(RioEvent::RenderRoute(self.route_id), self.window_id);
+        }
+
+        Some(processed)
+    }
+}
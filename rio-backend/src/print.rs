@@ -0,0 +1,326 @@
+//! Printable documents of the selection or the scrollback.
+//!
+//! The text is laid out in a paginated A4 PDF using the Courier standard
+//! font, so no font has to be embedded. Lines longer than a page are
+//! wrapped and characters out of the font encoding are printed as `?`.
+
+use crate::config::colors::{AnsiColor, NamedColor};
+use crate::crosswords::grid::Dimensions;
+use crate::crosswords::pos::{Column, Line, Pos};
+use crate::crosswords::Crosswords;
+use crate::event::EventListener;
+use crate::remote::resolve;
+use crate::snapshot::snapshot_line;
+
+const PAGE_WIDTH: f32 = 595.;
+const PAGE_HEIGHT: f32 = 842.;
+const MARGIN: f32 = 36.;
+const FONT_SIZE: f32 = 9.;
+const LEADING: f32 = 11.;
+// Courier glyphs are 0.6em wide
+const PAGE_COLUMNS: usize = ((PAGE_WIDTH - 2. * MARGIN) / (FONT_SIZE * 0.6)) as usize;
+const PAGE_LINES: usize = ((PAGE_HEIGHT - 2. * MARGIN) / LEADING) as usize;
+
+/// Text sharing a foreground color, `None` is the default foreground.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Span {
+    pub text: String,
+    pub color: Option<u32>,
+}
+
+pub type PrintLine = Vec<Span>;
+
+/// Lines of the selection, or of the whole scrollback when nothing is
+/// selected. Colors are `0xRRGGBB` when `colors` is enabled.
+pub fn print_lines<U: EventListener>(
+    crosswords: &Crosswords<U>,
+    colors: bool,
+) -> Vec<PrintLine> {
+    let last_column = crosswords.grid.last_column();
+    let range = crosswords
+        .selection
+        .as_ref()
+        .and_then(|selection| selection.to_range(crosswords));
+
+    let (start, end, is_block) = match range {
+        Some(range) => (range.start, range.end, range.is_block),
+        None => (
+            Pos::new(Line(-(crosswords.grid.history_size() as i32)), Column(0)),
+            Pos::new(Line(crosswords.grid.screen_lines() as i32 - 1), last_column),
+            false,
+        ),
+    };
+
+    let foreground = resolve(
+        &crosswords.colors(),
+        AnsiColor::Named(NamedColor::Foreground),
+    );
+    let mut lines = Vec::with_capacity((end.row.0 - start.row.0 + 1) as usize);
+    for row in start.row.0..=end.row.0 {
+        let first = if is_block || row == start.row.0 {
+            start.col
+        } else {
+            Column(0)
+        };
+        let last = if is_block || row == end.row.0 {
+            end.col
+        } else {
+            last_column
+        };
+
+        let mut line: PrintLine = Vec::new();
+        for span in snapshot_line(crosswords, Line(row), first..=last).spans {
+            let color = (colors && span.fg != foreground).then_some(span.fg);
+            match line.last_mut() {
+                Some(last) if last.color == color => last.text.push_str(&span.text),
+                _ => line.push(Span {
+                    text: span.text,
+                    color,
+                }),
+            }
+        }
+
+        trim_end(&mut line);
+        lines.push(line);
+    }
+
+    while lines.last().is_some_and(|line| line.is_empty()) {
+        lines.pop();
+    }
+
+    lines
+}
+
+fn trim_end(line: &mut PrintLine) {
+    while let Some(span) = line.last_mut() {
+        let len = span.text.trim_end().len();
+        span.text.truncate(len);
+        if !span.text.is_empty() {
+            break;
+        }
+        line.pop();
+    }
+}
+
+/// Paginated PDF of the lines.
+pub fn pdf(lines: &[PrintLine]) -> Vec<u8> {
+    let wrapped: Vec<PrintLine> = lines.iter().flat_map(wrap).collect();
+    let pages: Vec<&[PrintLine]> = if wrapped.is_empty() {
+        vec![&[]]
+    } else {
+        wrapped.chunks(PAGE_LINES).collect()
+    };
+
+    // 1 is the catalog, 2 the page tree, 3 the font, then every page is
+    // followed by its content stream
+    let page_id = |index: usize| 4 + index * 2;
+    let kids = (0..pages.len())
+        .map(|index| format!("{} 0 R", page_id(index)))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let mut objects: Vec<Vec<u8>> = vec![
+        b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+        format!("<< /Type /Pages /Kids [{kids}] /Count {} >>", pages.len()).into_bytes(),
+        b"<< /Type /Font /Subtype /Type1 /BaseFont /Courier /Encoding /WinAnsiEncoding >>"
+            .to_vec(),
+    ];
+    for (index, page) in pages.iter().enumerate() {
+        objects.push(
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {PAGE_WIDTH} {PAGE_HEIGHT}] \
+                 /Resources << /Font << /F1 3 0 R >> >> /Contents {} 0 R >>",
+                page_id(index) + 1
+            )
+            .into_bytes(),
+        );
+
+        let content = page_content(page);
+        let mut stream =
+            format!("<< /Length {} >>\nstream\n", content.len()).into_bytes();
+        stream.extend_from_slice(&content);
+        stream.extend_from_slice(b"\nendstream");
+        objects.push(stream);
+    }
+
+    let mut document = b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n".to_vec();
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (index, object) in objects.iter().enumerate() {
+        offsets.push(document.len());
+        document.extend_from_slice(format!("{} 0 obj\n", index + 1).as_bytes());
+        document.extend_from_slice(object);
+        document.extend_from_slice(b"\nendobj\n");
+    }
+
+    let xref = document.len();
+    document.extend_from_slice(
+        format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).as_bytes(),
+    );
+    for offset in offsets {
+        document.extend_from_slice(format!("{offset:010} 00000 n \n").as_bytes());
+    }
+    document.extend_from_slice(
+        format!(
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{xref}\n%%EOF\n",
+            objects.len() + 1
+        )
+        .as_bytes(),
+    );
+
+    document
+}
+
+/// Splits a line in lines of at most a page width.
+fn wrap(line: &PrintLine) -> Vec<PrintLine> {
+    let mut lines = vec![Vec::new()];
+    let mut width = 0;
+    for span in line {
+        for character in span.text.chars() {
+            if width == PAGE_COLUMNS {
+                lines.push(Vec::new());
+                width = 0;
+            }
+
+            let current: &mut PrintLine = lines.last_mut().unwrap();
+            match current.last_mut() {
+                Some(last) if last.color == span.color => last.text.push(character),
+                _ => current.push(Span {
+                    text: character.to_string(),
+                    color: span.color,
+                }),
+            }
+            width += 1;
+        }
+    }
+
+    lines
+}
+
+fn page_content(lines: &[PrintLine]) -> Vec<u8> {
+    let mut content = format!(
+        "BT\n/F1 {FONT_SIZE} Tf\n{LEADING} TL\n{MARGIN} {} Td\n0 0 0 rg\n",
+        PAGE_HEIGHT - MARGIN - FONT_SIZE
+    )
+    .into_bytes();
+
+    let mut current = None;
+    for line in lines {
+        for span in line {
+            if span.color != current {
+                let [r, g, b] = [16, 8, 0].map(|shift| {
+                    ((span.color.unwrap_or(0) >> shift) & 0xff) as f32 / 255.
+                });
+                content
+                    .extend_from_slice(format!("{r:.3} {g:.3} {b:.3} rg\n").as_bytes());
+                current = span.color;
+            }
+
+            content.push(b'(');
+            for character in span.text.chars() {
+                match character {
+                    '(' | ')' | '\\' => {
+                        content.extend_from_slice(&[b'\\', character as u8])
+                    }
+                    ' '..='~' | '\u{a0}'..='\u{ff}' => content.push(character as u8),
+                    _ => content.push(b'?'),
+                }
+            }
+            content.extend_from_slice(b") Tj\n");
+        }
+        content.extend_from_slice(b"T*\n");
+    }
+    content.extend_from_slice(b"ET");
+
+    content
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ansi::CursorShape;
+    use crate::crosswords::pos::Side;
+    use crate::crosswords::CrosswordsSize;
+    use crate::event::{VoidListener, WindowId};
+    use crate::performer::handler::Handler;
+    use crate::selection::{Selection, SelectionType};
+
+    fn span(text: &str, color: Option<u32>) -> Span {
+        Span {
+            text: text.to_string(),
+            color,
+        }
+    }
+
+    #[test]
+    fn test_print_lines() {
+        let size = CrosswordsSize::new(6, 3);
+        let mut crosswords =
+            Crosswords::new(size, CursorShape::Block, VoidListener, WindowId::from(0), 0);
+        for character in "ab".chars() {
+            crosswords.input(character);
+        }
+        crosswords.linefeed();
+        crosswords.carriage_return();
+        crosswords.input('c');
+
+        assert_eq!(
+            print_lines(&crosswords, false),
+            vec![vec![span("ab", None)], vec![span("c", None)]]
+        );
+
+        let mut selection = Selection::new(
+            SelectionType::Simple,
+            Pos::new(Line(0), Column(1)),
+            Side::Left,
+        );
+        selection.update(Pos::new(Line(1), Column(0)), Side::Right);
+        crosswords.selection = Some(selection);
+        assert_eq!(
+            print_lines(&crosswords, true),
+            vec![vec![span("b", None)], vec![span("c", None)]]
+        );
+    }
+
+    #[test]
+    fn test_wrap() {
+        let long = "x".repeat(PAGE_COLUMNS + 2);
+        let lines = wrap(&vec![
+            span(&long[..4], Some(0xff0000)),
+            span(&long[4..], None),
+        ]);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0][0], span("xxxx", Some(0xff0000)));
+        assert_eq!(lines[0][1].text.len(), PAGE_COLUMNS - 4);
+        assert_eq!(lines[1], vec![span("xx", None)]);
+        assert_eq!(wrap(&vec![]), vec![vec![]]);
+    }
+
+    #[test]
+    fn test_pdf() {
+        let lines: Vec<PrintLine> = (0..PAGE_LINES + 1)
+            .map(|index| vec![span(&format!("line ({index}) é 漢"), Some(0x00ff00))])
+            .collect();
+        let document = pdf(&lines);
+        let text = String::from_utf8_lossy(&document);
+
+        assert!(text.starts_with("%PDF-1.4"));
+        assert!(text.ends_with("%%EOF\n"));
+        assert!(text.contains("/Count 2"));
+        assert!(text.contains("(line \\(0\\) \u{FFFD} ?) Tj"));
+        assert!(text.contains("0.000 1.000 0.000 rg"));
+
+        // The xref table points at every object
+        let xref = text.rfind("startxref\n").unwrap();
+        let offset: usize = text[xref + 10..].lines().next().unwrap().parse().unwrap();
+        assert!(document[offset..].starts_with(b"xref\n0 8\n"));
+        let entries = String::from_utf8_lossy(&document[offset..]);
+        for (index, entry) in entries.lines().skip(3).take(7).enumerate() {
+            let object: usize = entry[..10].parse().unwrap();
+            assert!(
+                document[object..].starts_with(format!("{} 0 obj", index + 1).as_bytes())
+            );
+        }
+
+        assert!(String::from_utf8_lossy(&pdf(&[])).contains("/Count 1"));
+    }
+}
@@ -0,0 +1,182 @@
+//! Messages exchanged with remote viewers, for example as WebSocket binary
+//! messages.
+//!
+//! Integers are little endian. From the terminal to the viewer:
+//!
+//! - `S` screen: columns `u16`, lines `u16`, cursor line `u16`, cursor
+//!   column `u16`, then `columns * lines` cells of codepoint `u32`,
+//!   foreground `u32`, background `u32` (both `0xRRGGBB`) and flags `u16`
+//!   using the `RIO_FLAG_*` bits of `rio-ffi`.
+//! - `T` title: UTF-8 text.
+//!
+//! From the viewer to the terminal:
+//!
+//! - `I` input: bytes to write to the PTY.
+//! - `R` resize: columns `u16`, lines `u16`.
+
+use crate::config::colors::{term::List, AnsiColor, ColorArray};
+use crate::crosswords::grid::Dimensions;
+use crate::crosswords::square::Flags;
+use crate::crosswords::Crosswords;
+use crate::event::EventListener;
+
+pub const SCREEN: u8 = b'S';
+pub const TITLE: u8 = b'T';
+pub const INPUT: u8 = b'I';
+pub const RESIZE: u8 = b'R';
+
+/// Size in bytes of a cell in a screen message.
+pub const CELL_SIZE: usize = 14;
+
+/// Flags sent to viewers and embedders, they keep the bits of [`Flags`].
+pub const EXPOSED_FLAGS: Flags = Flags::INVERSE
+    .union(Flags::BOLD)
+    .union(Flags::ITALIC)
+    .union(Flags::UNDERLINE)
+    .union(Flags::WRAPLINE)
+    .union(Flags::WIDE_CHAR)
+    .union(Flags::WIDE_CHAR_SPACER)
+    .union(Flags::DIM)
+    .union(Flags::HIDDEN)
+    .union(Flags::STRIKEOUT);
+
+/// Message sent by a viewer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ViewerMessage {
+    Input(Vec<u8>),
+    Resize { columns: u16, lines: u16 },
+}
+
+impl ViewerMessage {
+    /// Parse a message, returns `None` if it's malformed.
+    pub fn decode(message: &[u8]) -> Option<ViewerMessage> {
+        match message.split_first()? {
+            (&INPUT, bytes) => Some(ViewerMessage::Input(bytes.to_vec())),
+            (&RESIZE, [c0, c1, l0, l1]) => {
+                let columns = u16::from_le_bytes([*c0, *c1]);
+                let lines = u16::from_le_bytes([*l0, *l1]);
+                if columns == 0 || lines == 0 {
+                    return None;
+                }
+
+                Some(ViewerMessage::Resize { columns, lines })
+            }
+            _ => None,
+        }
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        match self {
+            ViewerMessage::Input(bytes) => {
+                let mut message = Vec::with_capacity(bytes.len() + 1);
+                message.push(INPUT);
+                message.extend_from_slice(bytes);
+                message
+            }
+            ViewerMessage::Resize { columns, lines } => {
+                let mut message = vec![RESIZE];
+                message.extend_from_slice(&columns.to_le_bytes());
+                message.extend_from_slice(&lines.to_le_bytes());
+                message
+            }
+        }
+    }
+}
+
+/// Color as `0xRRGGBB`.
+pub fn rgb(color: ColorArray) -> u32 {
+    let channel = |value: f32| (value.clamp(0., 1.) * 255.).round() as u32;
+    (channel(color[0]) << 16) | (channel(color[1]) << 8) | channel(color[2])
+}
+
+/// Color of a square as `0xRRGGBB` with the palette `colors`.
+pub fn resolve(colors: &List, color: AnsiColor) -> u32 {
+    match color {
+        AnsiColor::Named(name) => rgb(colors[name]),
+        AnsiColor::Spec(spec) => {
+            ((spec.r as u32) << 16) | ((spec.g as u32) << 8) | spec.b as u32
+        }
+        AnsiColor::Indexed(index) => rgb(colors[index as usize]),
+    }
+}
+
+/// Encode the visible screen as a screen message.
+pub fn encode_screen<U: EventListener>(crosswords: &Crosswords<U>) -> Vec<u8> {
+    let columns = crosswords.columns();
+    let lines = crosswords.screen_lines();
+    let colors = crosswords.colors();
+    let cursor = crosswords.grid.cursor.pos;
+
+    let mut message = Vec::with_capacity(9 + columns * lines * CELL_SIZE);
+    message.push(SCREEN);
+    message.extend_from_slice(&(columns as u16).to_le_bytes());
+    message.extend_from_slice(&(lines as u16).to_le_bytes());
+    message.extend_from_slice(&(cursor.row.0.max(0) as u16).to_le_bytes());
+    message.extend_from_slice(&(cursor.col.0 as u16).to_le_bytes());
+
+    for row in crosswords.visible_rows() {
+        for square in row.inner.iter().take(columns) {
+            message.extend_from_slice(&(square.c as u32).to_le_bytes());
+            message.extend_from_slice(&resolve(&colors, square.fg).to_le_bytes());
+            message.extend_from_slice(&resolve(&colors, square.bg).to_le_bytes());
+            message.extend_from_slice(
+                &((square.flags & EXPOSED_FLAGS).bits() as u16).to_le_bytes(),
+            );
+        }
+    }
+
+    message
+}
+
+/// Encode the window title as a title message.
+pub fn encode_title(title: &str) -> Vec<u8> {
+    let mut message = Vec::with_capacity(title.len() + 1);
+    message.push(TITLE);
+    message.extend_from_slice(title.as_bytes());
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ansi::CursorShape;
+    use crate::crosswords::CrosswordsSize;
+    use crate::event::{VoidListener, WindowId};
+    use crate::performer::handler::Handler;
+
+    #[test]
+    fn test_viewer_messages() {
+        let input = ViewerMessage::Input(b"ls\r".to_vec());
+        assert_eq!(ViewerMessage::decode(&input.encode()), Some(input));
+
+        let resize = ViewerMessage::Resize {
+            columns: 120,
+            lines: 40,
+        };
+        assert_eq!(ViewerMessage::decode(&resize.encode()), Some(resize));
+
+        assert_eq!(ViewerMessage::decode(&[]), None);
+        assert_eq!(ViewerMessage::decode(&[RESIZE, 1, 0]), None);
+        assert_eq!(ViewerMessage::decode(&[RESIZE, 0, 0, 1, 0]), None);
+        assert_eq!(ViewerMessage::decode(b"x"), None);
+    }
+
+    #[test]
+    fn test_encode_screen() {
+        let size = CrosswordsSize::new(3, 2);
+        let mut crosswords =
+            Crosswords::new(size, CursorShape::Block, VoidListener, WindowId::from(0), 0);
+        crosswords.input('a');
+        crosswords.input('b');
+
+        let message = encode_screen(&crosswords);
+        assert_eq!(message.len(), 9 + 6 * CELL_SIZE);
+        assert_eq!(&message[..9], &[SCREEN, 3, 0, 2, 0, 0, 0, 2, 0]);
+
+        let cell = |index: usize| &message[9 + index * CELL_SIZE..][..CELL_SIZE];
+        assert_eq!(&cell(0)[..4], &('a' as u32).to_le_bytes());
+        assert_eq!(&cell(1)[..4], &('b' as u32).to_le_bytes());
+        assert_eq!(&cell(3)[..4], &(' ' as u32).to_le_bytes());
+        assert_eq!(&cell(0)[12..], &[0, 0]);
+    }
+}
@@ -0,0 +1,230 @@
+//! Styled text of the grid as structured data.
+//!
+//! Exporters, the control socket and tests read the grid through this
+//! module instead of walking squares on their own. Lines use the grid
+//! coordinates: `0` is the top of the screen and history lines are
+//! negative. Colors are resolved to `0xRRGGBB` with the current palette.
+
+use crate::config::colors::{AnsiColor, NamedColor};
+use crate::crosswords::grid::Dimensions;
+use crate::crosswords::pos::{Column, Line};
+use crate::crosswords::square::{Flags, Square};
+use crate::crosswords::Crosswords;
+use crate::event::EventListener;
+use crate::remote::resolve;
+use serde::Serialize;
+use std::ops::RangeInclusive;
+use std::sync::mpsc::SyncSender;
+use std::sync::Arc;
+
+const ATTRIBUTES: &[(Flags, &str)] = &[
+    (Flags::BOLD, "bold"),
+    (Flags::ITALIC, "italic"),
+    (Flags::DIM, "dim"),
+    (Flags::UNDERLINE, "underline"),
+    (Flags::DOUBLE_UNDERLINE, "double-underline"),
+    (Flags::UNDERCURL, "undercurl"),
+    (Flags::DOTTED_UNDERLINE, "dotted-underline"),
+    (Flags::DASHED_UNDERLINE, "dashed-underline"),
+    (Flags::STRIKEOUT, "strikeout"),
+    (Flags::OVERLINE, "overline"),
+    (Flags::INVERSE, "inverse"),
+    (Flags::HIDDEN, "hidden"),
+    (Flags::BLINK, "blink"),
+];
+
+/// Text sharing the same colors, attributes and hyperlink.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Span {
+    pub text: String,
+    pub fg: u32,
+    pub bg: u32,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub attributes: Vec<&'static str>,
+    /// Id of the OSC 8 hyperlink, explicit or generated by Rio.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hyperlink: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct SnapshotLine {
+    pub line: i32,
+    pub spans: Vec<Span>,
+}
+
+impl SnapshotLine {
+    /// Plain text of the line.
+    pub fn text(&self) -> String {
+        self.spans.iter().map(|span| span.text.as_str()).collect()
+    }
+}
+
+/// Snapshot asked by a control socket client, the JSON reply is queued
+/// on `reply`. Without a window or route the focused ones are used.
+#[derive(Debug, Clone)]
+pub struct SnapshotRequest {
+    pub window: Option<u64>,
+    pub route: Option<usize>,
+    pub lines: Option<[i32; 2]>,
+    pub reply: SyncSender<Arc<str>>,
+}
+
+/// Lines displayed in the viewport, following the scroll position.
+pub fn visible_lines<U: EventListener>(
+    crosswords: &Crosswords<U>,
+) -> RangeInclusive<Line> {
+    let offset = crosswords.display_offset() as i32;
+    Line(-offset)..=Line(crosswords.screen_lines() as i32 - 1 - offset)
+}
+
+/// Lines of `lines` which exist in the grid.
+pub fn snapshot<U: EventListener>(
+    crosswords: &Crosswords<U>,
+    lines: RangeInclusive<Line>,
+) -> Vec<SnapshotLine> {
+    let first = (*lines.start()).max(Line(-(crosswords.history_size() as i32)));
+    let last = (*lines.end()).min(Line(crosswords.screen_lines() as i32 - 1));
+    let columns = Column(0)..=crosswords.grid.last_column();
+
+    (first.0..=last.0)
+        .map(|line| snapshot_line(crosswords, Line(line), columns.clone()))
+        .collect()
+}
+
+/// Squares of `columns` in a line, wide char spacers are skipped and
+/// trailing blanks without style are trimmed.
+pub fn snapshot_line<U: EventListener>(
+    crosswords: &Crosswords<U>,
+    line: Line,
+    columns: RangeInclusive<Column>,
+) -> SnapshotLine {
+    let palette = crosswords.colors();
+    let last_column = crosswords.grid.last_column();
+    let row = &crosswords.grid[line];
+
+    let mut spans: Vec<Span> = Vec::new();
+    for column in columns.start().0..=columns.end().0.min(last_column.0) {
+        let square = &row[Column(column)];
+        if square
+            .flags
+            .intersects(Flags::WIDE_CHAR_SPACER | Flags::LEADING_WIDE_CHAR_SPACER)
+        {
+            continue;
+        }
+
+        let fg = resolve(&palette, square.fg);
+        let bg = resolve(&palette, square.bg);
+        let attributes = attributes(square);
+        let hyperlink = square.hyperlink().map(|link| link.id().to_string());
+
+        let span = match spans.last_mut() {
+            Some(span)
+                if span.fg == fg
+                    && span.bg == bg
+                    && span.attributes == attributes
+                    && span.hyperlink == hyperlink =>
+            {
+                span
+            }
+            _ => {
+                spans.push(Span {
+                    text: String::new(),
+                    fg,
+                    bg,
+                    attributes,
+                    hyperlink,
+                });
+                spans.last_mut().unwrap()
+            }
+        };
+        span.text.push(square.c);
+        if let Some(zerowidth) = square.zerowidth() {
+            span.text.extend(zerowidth);
+        }
+    }
+
+    let background = resolve(&palette, AnsiColor::Named(NamedColor::Background));
+    while let Some(span) = spans.last_mut() {
+        if span.bg != background || !span.attributes.is_empty() {
+            break;
+        }
+        let len = span.text.trim_end().len();
+        span.text.truncate(len);
+        if !span.text.is_empty() {
+            break;
+        }
+        spans.pop();
+    }
+
+    SnapshotLine {
+        line: line.0,
+        spans,
+    }
+}
+
+fn attributes(square: &Square) -> Vec<&'static str> {
+    ATTRIBUTES
+        .iter()
+        .filter(|(flag, _)| square.flags.contains(*flag))
+        .map(|(_, name)| *name)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ansi::CursorShape;
+    use crate::crosswords::square::Hyperlink;
+    use crate::crosswords::CrosswordsSize;
+    use crate::event::{VoidListener, WindowId};
+    use crate::performer::handler::Handler;
+
+    #[test]
+    fn test_snapshot() {
+        let size = CrosswordsSize::new(8, 3);
+        let mut crosswords =
+            Crosswords::new(size, CursorShape::Block, VoidListener, WindowId::from(0), 0);
+        crosswords.input('a');
+        crosswords.grid.cursor.template.flags = Flags::BOLD;
+        crosswords.input('b');
+        crosswords.grid.cursor.template.flags = Flags::empty();
+        crosswords
+            .set_hyperlink(Some(Hyperlink::new(Some("docs"), "https://rioterm.com")));
+        crosswords.input('c');
+
+        let palette = crosswords.colors();
+        let fg = resolve(&palette, AnsiColor::Named(NamedColor::Foreground));
+        let bg = resolve(&palette, AnsiColor::Named(NamedColor::Background));
+        let span =
+            |text: &str, attributes: Vec<&'static str>, hyperlink: Option<&str>| Span {
+                text: text.to_string(),
+                fg,
+                bg,
+                attributes,
+                hyperlink: hyperlink.map(String::from),
+            };
+
+        let lines = snapshot(&crosswords, visible_lines(&crosswords));
+        assert_eq!(lines.len(), 3);
+        assert_eq!(
+            lines[0],
+            SnapshotLine {
+                line: 0,
+                spans: vec![
+                    span("a", vec![], None),
+                    span("b", vec!["bold"], None),
+                    span("c", vec![], Some("docs")),
+                ],
+            }
+        );
+        assert_eq!(lines[0].text(), "abc");
+        assert!(lines[1].spans.is_empty());
+
+        // Lines out of the grid are left out
+        assert_eq!(snapshot(&crosswords, Line(-5)..=Line(0)).len(), 1);
+        assert_eq!(
+            serde_json::to_string(&lines[0].spans[1]).unwrap(),
+            format!(r#"{{"text":"b","fg":{fg},"bg":{bg},"attributes":["bold"]}}"#)
+        );
+    }
+}
@@ -0,0 +1,93 @@
+/*
+ * C ABI for the Rio terminal emulation core.
+ *
+ * A RioTerminal parses the output of a program and keeps the resulting grid,
+ * it doesn't spawn processes nor render anything. Feed it the bytes read
+ * from a PTY with rio_terminal_feed, write the replies returned by
+ * rio_terminal_read_reply back to the PTY and draw the cells.
+ *
+ * Lines and columns are 0-based and relative to the top-left corner of the
+ * screen. Functions taking a RioTerminal pointer must not be called with
+ * NULL nor from multiple threads at the same time.
+ */
+
+#ifndef RIO_H
+#define RIO_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Version of this header, incremented on incompatible changes. */
+#define RIO_ABI_VERSION 1
+
+/* Cell attributes. */
+#define RIO_FLAG_INVERSE (1 << 0)
+#define RIO_FLAG_BOLD (1 << 1)
+#define RIO_FLAG_ITALIC (1 << 2)
+#define RIO_FLAG_UNDERLINE (1 << 3)
+#define RIO_FLAG_WRAPLINE (1 << 4)
+#define RIO_FLAG_WIDE_CHAR (1 << 5)
+#define RIO_FLAG_WIDE_CHAR_SPACER (1 << 6)
+#define RIO_FLAG_DIM (1 << 7)
+#define RIO_FLAG_HIDDEN (1 << 8)
+#define RIO_FLAG_STRIKEOUT (1 << 9)
+
+typedef struct RioTerminal RioTerminal;
+
+typedef struct RioCell {
+    /* Unicode scalar value, a space for empty cells. */
+    uint32_t codepoint;
+    /* Colors as 0xRRGGBB, the default palette is applied. */
+    uint32_t foreground;
+    uint32_t background;
+    /* RIO_FLAG_* bits. */
+    uint16_t flags;
+} RioCell;
+
+/* Returns RIO_ABI_VERSION of the library. */
+uint32_t rio_abi_version(void);
+
+/* Create a terminal, returns NULL if a dimension is 0. */
+RioTerminal *rio_terminal_new(uint16_t columns, uint16_t lines);
+
+/* Free a terminal created by rio_terminal_new, NULL is ignored. */
+void rio_terminal_free(RioTerminal *terminal);
+
+/* Parse bytes written by the program. */
+void rio_terminal_feed(RioTerminal *terminal, const uint8_t *bytes, size_t len);
+
+/* Resize the grid, wrapped lines are reflowed. Dimensions of 0 are ignored. */
+void rio_terminal_resize(RioTerminal *terminal, uint16_t columns, uint16_t lines);
+
+uint16_t rio_terminal_columns(const RioTerminal *terminal);
+uint16_t rio_terminal_lines(const RioTerminal *terminal);
+
+/* Read a cell of the screen, returns false if the position is out of bounds. */
+bool rio_terminal_cell(const RioTerminal *terminal, uint16_t line, uint16_t column,
+                       RioCell *cell);
+
+/* Position of the cursor. */
+void rio_terminal_cursor(const RioTerminal *terminal, uint16_t *line, uint16_t *column);
+
+/*
+ * Copy up to len bytes of pending replies (e.g. device attributes) into buf
+ * and remove them, returns the number of bytes copied.
+ */
+size_t rio_terminal_read_reply(RioTerminal *terminal, uint8_t *buf, size_t len);
+
+/*
+ * Copy the window title as UTF-8 into buf, without a NUL terminator. Returns
+ * the length of the title, which can be larger than len.
+ */
+size_t rio_terminal_title(const RioTerminal *terminal, uint8_t *buf, size_t len);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* RIO_H */
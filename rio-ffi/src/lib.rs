@@ -0,0 +1,383 @@
+//! C ABI for the Rio terminal emulation core, see `include/rio.h`.
+//!
+//! Every function taking a `RioTerminal` pointer expects a pointer returned
+//! by [`rio_terminal_new`] which wasn't freed yet.
+
+use rio_backend::ansi::CursorShape;
+use rio_backend::config::colors::term::List;
+use rio_backend::crosswords::grid::Dimensions;
+use rio_backend::crosswords::pos::{Column, Line};
+use rio_backend::crosswords::square::Square;
+use rio_backend::crosswords::{Crosswords, CrosswordsSize};
+use rio_backend::event::{EventListener, RioEvent, WindowId};
+use rio_backend::performer::handler::ParserProcessor;
+use rio_backend::remote::{resolve, EXPOSED_FLAGS};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Keep in sync with `RIO_ABI_VERSION`.
+pub const ABI_VERSION: u32 = 1;
+
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RioCell {
+    pub codepoint: u32,
+    pub foreground: u32,
+    pub background: u32,
+    pub flags: u16,
+}
+
+/// Collects the replies the terminal would write to the PTY.
+#[derive(Clone, Default)]
+struct Replies(Rc<RefCell<Vec<u8>>>);
+
+impl EventListener for Replies {
+    fn event(&self) -> (Option<RioEvent>, bool) {
+        (None, false)
+    }
+
+    fn send_event(&self, event: RioEvent, _id: WindowId) {
+        if let RioEvent::PtyWrite(text) = event {
+            self.0.borrow_mut().extend_from_slice(text.as_bytes());
+        }
+    }
+}
+
+pub struct RioTerminal {
+    crosswords: Crosswords<Replies>,
+    parser: ParserProcessor,
+    replies: Replies,
+}
+
+fn cell(colors: &List, square: &Square) -> RioCell {
+    RioCell {
+        codepoint: square.c as u32,
+        foreground: resolve(colors, square.fg),
+        background: resolve(colors, square.bg),
+        flags: (square.flags & EXPOSED_FLAGS).bits() as u16,
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn rio_abi_version() -> u32 {
+    ABI_VERSION
+}
+
+#[no_mangle]
+pub extern "C" fn rio_terminal_new(columns: u16, lines: u16) -> *mut RioTerminal {
+    if columns == 0 || lines == 0 {
+        return std::ptr::null_mut();
+    }
+
+    let replies = Replies::default();
+    let crosswords = Crosswords::new(
+        CrosswordsSize::new(columns as usize, lines as usize),
+        CursorShape::Block,
+        replies.clone(),
+        WindowId::from(0),
+        0,
+    );
+
+    Box::into_raw(Box::new(RioTerminal {
+        crosswords,
+        parser: ParserProcessor::new(),
+        replies,
+    }))
+}
+
+/// # Safety
+///
+/// `terminal` must be NULL or a pointer returned by [`rio_terminal_new`],
+/// it can't be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn rio_terminal_free(terminal: *mut RioTerminal) {
+    if !terminal.is_null() {
+        drop(Box::from_raw(terminal));
+    }
+}
+
+/// # Safety
+///
+/// `bytes` must be valid for reads of `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn rio_terminal_feed(
+    terminal: *mut RioTerminal,
+    bytes: *const u8,
+    len: usize,
+) {
+    let terminal = &mut *terminal;
+    if bytes.is_null() || len == 0 {
+        return;
+    }
+
+    for byte in std::slice::from_raw_parts(bytes, len) {
+        terminal.parser.advance(&mut terminal.crosswords, *byte);
+    }
+}
+
+/// # Safety
+///
+/// See the module documentation.
+#[no_mangle]
+pub unsafe extern "C" fn rio_terminal_resize(
+    terminal: *mut RioTerminal,
+    columns: u16,
+    lines: u16,
+) {
+    if columns == 0 || lines == 0 {
+        return;
+    }
+
+    let terminal = &mut *terminal;
+    terminal
+        .crosswords
+        .resize(CrosswordsSize::new(columns as usize, lines as usize));
+}
+
+/// # Safety
+///
+/// See the module documentation.
+#[no_mangle]
+pub unsafe extern "C" fn rio_terminal_columns(terminal: *const RioTerminal) -> u16 {
+    (*terminal).crosswords.grid.columns() as u16
+}
+
+/// # Safety
+///
+/// See the module documentation.
+#[no_mangle]
+pub unsafe extern "C" fn rio_terminal_lines(terminal: *const RioTerminal) -> u16 {
+    (*terminal).crosswords.grid.screen_lines() as u16
+}
+
+/// # Safety
+///
+/// `out` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn rio_terminal_cell(
+    terminal: *const RioTerminal,
+    line: u16,
+    column: u16,
+    out: *mut RioCell,
+) -> bool {
+    let grid = &(*terminal).crosswords.grid;
+    if out.is_null()
+        || line as usize >= grid.screen_lines()
+        || column as usize >= grid.columns()
+    {
+        return false;
+    }
+
+    let square = &grid[Line(line as i32)][Column(column as usize)];
+    *out = cell(&(*terminal).crosswords.colors(), square);
+    true
+}
+
+/// # Safety
+///
+/// `line` and `column` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn rio_terminal_cursor(
+    terminal: *const RioTerminal,
+    line: *mut u16,
+    column: *mut u16,
+) {
+    let pos = (*terminal).crosswords.grid.cursor.pos;
+    if !line.is_null() {
+        *line = pos.row.0 as u16;
+    }
+    if !column.is_null() {
+        *column = pos.col.0 as u16;
+    }
+}
+
+/// # Safety
+///
+/// `buf` must be valid for writes of `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn rio_terminal_read_reply(
+    terminal: *mut RioTerminal,
+    buf: *mut u8,
+    len: usize,
+) -> usize {
+    if buf.is_null() {
+        return 0;
+    }
+
+    let mut replies = (*terminal).replies.0.borrow_mut();
+    let count = std::cmp::min(len, replies.len());
+    std::ptr::copy_nonoverlapping(replies.as_ptr(), buf, count);
+    replies.drain(..count);
+    count
+}
+
+/// # Safety
+///
+/// `buf` must be valid for writes of `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn rio_terminal_title(
+    terminal: *const RioTerminal,
+    buf: *mut u8,
+    len: usize,
+) -> usize {
+    let title = (*terminal).crosswords.title.as_bytes();
+    if !buf.is_null() {
+        let count = std::cmp::min(len, title.len());
+        std::ptr::copy_nonoverlapping(title.as_ptr(), buf, count);
+    }
+    title.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rio_backend::crosswords::square::Flags;
+
+    struct Terminal(*mut RioTerminal);
+
+    impl Terminal {
+        fn new(columns: u16, lines: u16) -> Terminal {
+            Terminal(rio_terminal_new(columns, lines))
+        }
+
+        fn feed(&self, bytes: &[u8]) {
+            unsafe { rio_terminal_feed(self.0, bytes.as_ptr(), bytes.len()) }
+        }
+
+        fn cell(&self, line: u16, column: u16) -> RioCell {
+            let mut cell = RioCell::default();
+            assert!(unsafe { rio_terminal_cell(self.0, line, column, &mut cell) });
+            cell
+        }
+
+        fn line(&self, line: u16) -> String {
+            let columns = unsafe { rio_terminal_columns(self.0) };
+            (0..columns)
+                .map(|column| char::from_u32(self.cell(line, column).codepoint).unwrap())
+                .collect()
+        }
+
+        fn cursor(&self) -> (u16, u16) {
+            let (mut line, mut column) = (0, 0);
+            unsafe { rio_terminal_cursor(self.0, &mut line, &mut column) };
+            (line, column)
+        }
+
+        fn reply(&self) -> String {
+            let mut buf = [0u8; 64];
+            let len = unsafe { rio_terminal_read_reply(self.0, buf.as_mut_ptr(), 64) };
+            String::from_utf8(buf[..len].to_vec()).unwrap()
+        }
+    }
+
+    impl Drop for Terminal {
+        fn drop(&mut self) {
+            unsafe { rio_terminal_free(self.0) }
+        }
+    }
+
+    #[test]
+    fn test_exposed_flags_match_header() {
+        assert_eq!(Flags::INVERSE.bits(), 1 << 0);
+        assert_eq!(Flags::BOLD.bits(), 1 << 1);
+        assert_eq!(Flags::ITALIC.bits(), 1 << 2);
+        assert_eq!(Flags::UNDERLINE.bits(), 1 << 3);
+        assert_eq!(Flags::WRAPLINE.bits(), 1 << 4);
+        assert_eq!(Flags::WIDE_CHAR.bits(), 1 << 5);
+        assert_eq!(Flags::WIDE_CHAR_SPACER.bits(), 1 << 6);
+        assert_eq!(Flags::DIM.bits(), 1 << 7);
+        assert_eq!(Flags::HIDDEN.bits(), 1 << 8);
+        assert_eq!(Flags::STRIKEOUT.bits(), 1 << 9);
+        assert_eq!(rio_abi_version(), 1);
+    }
+
+    #[test]
+    fn test_invalid_dimensions() {
+        assert!(rio_terminal_new(0, 10).is_null());
+        unsafe { rio_terminal_free(std::ptr::null_mut()) };
+
+        let terminal = Terminal::new(10, 5);
+        let mut cell = RioCell::default();
+        assert!(!unsafe { rio_terminal_cell(terminal.0, 5, 0, &mut cell) });
+        assert!(!unsafe { rio_terminal_cell(terminal.0, 0, 10, &mut cell) });
+    }
+
+    #[test]
+    fn test_text_and_cursor_movement() {
+        let terminal = Terminal::new(10, 5);
+        terminal.feed(b"hello\r\nworld");
+        assert_eq!(terminal.line(0), "hello     ");
+        assert_eq!(terminal.line(1), "world     ");
+        assert_eq!(terminal.cursor(), (1, 5));
+
+        // CUP, EL and ED.
+        terminal.feed(b"\x1b[1;3H\x1b[K");
+        assert_eq!(terminal.line(0), "he        ");
+        terminal.feed(b"\x1b[2J");
+        assert_eq!(terminal.line(1), "          ");
+        assert_eq!(terminal.cursor(), (0, 2));
+    }
+
+    #[test]
+    fn test_split_utf8_and_wide_chars() {
+        let terminal = Terminal::new(10, 5);
+        let bytes = "é漢".as_bytes();
+        terminal.feed(&bytes[..1]);
+        terminal.feed(&bytes[1..]);
+        assert_eq!(terminal.cell(0, 0).codepoint, 'é' as u32);
+        assert_eq!(terminal.cell(0, 1).codepoint, '漢' as u32);
+        assert_eq!(terminal.cell(0, 1).flags, Flags::WIDE_CHAR.bits() as u16);
+        assert_eq!(
+            terminal.cell(0, 2).flags,
+            Flags::WIDE_CHAR_SPACER.bits() as u16
+        );
+        assert_eq!(terminal.cursor(), (0, 3));
+    }
+
+    #[test]
+    fn test_attributes_and_colors() {
+        let terminal = Terminal::new(10, 5);
+        terminal.feed(b"\x1b[1;38;2;1;2;3;48;5;196mA\x1b[0;7mB");
+
+        let a = terminal.cell(0, 0);
+        assert_eq!(a.flags, Flags::BOLD.bits() as u16);
+        assert_eq!(a.foreground, 0x010203);
+        assert_eq!(a.background, 0xff0000);
+
+        let b = terminal.cell(0, 1);
+        assert_eq!(b.flags, Flags::INVERSE.bits() as u16);
+    }
+
+    #[test]
+    fn test_wrap_and_reflow() {
+        let terminal = Terminal::new(5, 3);
+        terminal.feed(b"abcdefg");
+        assert_eq!(terminal.line(0), "abcde");
+        assert_eq!(terminal.line(1), "fg   ");
+        assert_ne!(terminal.cell(0, 4).flags & Flags::WRAPLINE.bits() as u16, 0);
+
+        unsafe { rio_terminal_resize(terminal.0, 10, 3) };
+        assert_eq!(unsafe { rio_terminal_columns(terminal.0) }, 10);
+        assert_eq!(terminal.line(0), "abcdefg   ");
+        assert_eq!(terminal.cursor(), (0, 7));
+    }
+
+    #[test]
+    fn test_replies() {
+        let terminal = Terminal::new(10, 5);
+        terminal.feed(b"\x1b[2;3H\x1b[6n");
+        assert_eq!(terminal.reply(), "\x1b[2;3R");
+        assert_eq!(terminal.reply(), "");
+    }
+
+    #[test]
+    fn test_title() {
+        let terminal = Terminal::new(10, 5);
+        terminal.feed(b"\x1b]2;hello\x07");
+
+        let mut buf = [0u8; 3];
+        let len = unsafe { rio_terminal_title(terminal.0, buf.as_mut_ptr(), 3) };
+        assert_eq!(len, 5);
+        assert_eq!(&buf, b"hel");
+    }
+}
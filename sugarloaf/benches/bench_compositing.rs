@@ -0,0 +1,147 @@
+// Compares the experimental compute compositing path with instanced quads
+// for a 4K grid, run with:
+// cargo bench --workspace --features sugarloaf/compute-compositing --bench bench_compositing
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use sugarloaf::components::compute::{
+    create_output, ComputeCompositor, Grid, GridCell, QuadCompositor,
+};
+
+const ATLAS_SIZE: u32 = 1024;
+
+// 3840x2160 with 10x20 cells.
+const GRID: Grid = Grid {
+    columns: 384,
+    rows: 108,
+    cell_width: 10,
+    cell_height: 20,
+};
+
+fn device() -> Option<(wgpu::Device, wgpu::Queue)> {
+    let instance = wgpu::Instance::default();
+    let adapter = futures::executor::block_on(instance.request_adapter(
+        &wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            compatible_surface: None,
+            force_fallback_adapter: false,
+        },
+    ))?;
+    futures::executor::block_on(
+        adapter.request_device(&wgpu::DeviceDescriptor::default(), None),
+    )
+    .ok()
+}
+
+// Full color TUI screen: every cell has a glyph and its own background.
+fn cells() -> Vec<GridCell> {
+    let glyphs_per_row = ATLAS_SIZE / GRID.cell_width;
+    let mut seed: u32 = 0x2545f491;
+    let mut next = || {
+        seed ^= seed << 13;
+        seed ^= seed >> 17;
+        seed ^= seed << 5;
+        seed
+    };
+
+    (0..GRID.len())
+        .map(|_| {
+            let glyph = next() % 95;
+            let color = |value: u32| (value % 255) as f32 / 255.;
+            GridCell {
+                glyph: [
+                    (glyph % glyphs_per_row) * GRID.cell_width,
+                    (glyph / glyphs_per_row) * GRID.cell_height,
+                    GRID.cell_width - 2,
+                    GRID.cell_height - 4,
+                ],
+                offset: [1, 2],
+                fg: [color(next()), color(next()), color(next()), 1.0],
+                bg: [color(next()), color(next()), color(next()), 1.0],
+                ..GridCell::default()
+            }
+        })
+        .collect()
+}
+
+fn atlas(device: &wgpu::Device, queue: &wgpu::Queue) -> wgpu::Texture {
+    let size = wgpu::Extent3d {
+        width: ATLAS_SIZE,
+        height: ATLAS_SIZE,
+        depth_or_array_layers: 1,
+    };
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("bench atlas"),
+        size,
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: wgpu::TextureFormat::Rgba8Unorm,
+        usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+        view_formats: &[],
+    });
+    let data: Vec<u8> = (0..ATLAS_SIZE * ATLAS_SIZE * 4)
+        .map(|i| (i % 251) as u8)
+        .collect();
+    queue.write_texture(
+        texture.as_image_copy(),
+        &data,
+        wgpu::ImageDataLayout {
+            offset: 0,
+            bytes_per_row: Some(ATLAS_SIZE * 4),
+            rows_per_image: Some(ATLAS_SIZE),
+        },
+        size,
+    );
+    texture
+}
+
+fn bench_compositing(c: &mut Criterion) {
+    let Some((device, queue)) = device() else {
+        println!("bench_compositing: no GPU adapter available, skipping");
+        return;
+    };
+
+    let cells = cells();
+    let atlas = atlas(&device, &queue);
+    let atlas_view = atlas.create_view(&wgpu::TextureViewDescriptor::default());
+    let output = create_output(&device, GRID.width(), GRID.height());
+
+    let mut compute = ComputeCompositor::new(&device);
+    c.bench_function("bench_compositing_compute", |b| {
+        b.iter(|| {
+            let mut encoder = device.create_command_encoder(&Default::default());
+            compute.composite(
+                &device,
+                &queue,
+                &mut encoder,
+                &GRID,
+                &cells,
+                &atlas_view,
+                &output,
+            );
+            queue.submit(Some(encoder.finish()));
+            device.poll(wgpu::Maintain::Wait);
+        })
+    });
+
+    let mut quads = QuadCompositor::new(&device);
+    c.bench_function("bench_compositing_quads", |b| {
+        b.iter(|| {
+            let mut encoder = device.create_command_encoder(&Default::default());
+            quads.composite(
+                &device,
+                &queue,
+                &mut encoder,
+                &GRID,
+                &cells,
+                &atlas_view,
+                &output,
+            );
+            queue.submit(Some(encoder.finish()));
+            device.poll(wgpu::Maintain::Wait);
+        })
+    });
+}
+
+criterion_group!(benches, bench_compositing);
+criterion_main!(benches);
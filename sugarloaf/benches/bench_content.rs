@@ -0,0 +1,78 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use sugarloaf::font::FontLibrary;
+use sugarloaf::layout::RenderData;
+use sugarloaf::{Content, FragmentStyle};
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+const LINES: usize = 40;
+
+// A shell session: a prompt with powerline glyphs followed by output.
+fn frame(content: &mut Content, render_data: &mut RenderData) {
+    let prompt = FragmentStyle {
+        color: [0.2, 0.6, 1.0, 1.0],
+        ..FragmentStyle::default()
+    };
+    content.build(1.0, 14.0);
+    for line in 0..LINES {
+        if line % 4 == 0 {
+            content.add_text("\u{e0b6} ~/projects/rio \u{e0b4}", prompt);
+            content.add_text(" ", FragmentStyle::default());
+            content.add_text("cargo", FragmentStyle::default());
+            content.add_text(" ", FragmentStyle::default());
+            content.add_text("build", FragmentStyle::default());
+        } else {
+            content.add_text("   Compiling", prompt);
+            content.add_text(" ", FragmentStyle::default());
+            content.add_text("sugarloaf v0.2.0", FragmentStyle::default());
+        }
+        content.new_line();
+    }
+
+    render_data.clear();
+    content.resolve(render_data);
+}
+
+fn bench_content(c: &mut Criterion) {
+    let font_library = FontLibrary::default();
+    let mut content = Content::new(&font_library);
+    let mut render_data = RenderData::new();
+
+    // Warm up the shaping caches.
+    frame(&mut content, &mut render_data);
+
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    frame(&mut content, &mut render_data);
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+    println!("allocations per frame ({LINES} lines): {allocations}");
+
+    c.bench_function("bench_content_frame", |b| {
+        b.iter(|| frame(&mut content, &mut render_data))
+    });
+}
+
+criterion_group!(benches, bench_content);
+criterion_main!(benches);
@@ -0,0 +1,43 @@
+use sugarloaf::{layout::SugarloafLayout, FragmentStyle, Sugarloaf, SugarloafWindowSize};
+
+// Renders without a window and saves the frame, e.g.
+// `cargo run --example headless -- frame.png`
+fn main() {
+    let path = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| String::from("headless.png"));
+    let (width, height) = (400.0, 200.0);
+
+    let sugarloaf_layout =
+        SugarloafLayout::new(width, height, (10.0, 10.0, 0.0), 1.0, 25., 1.0);
+    let mut sugarloaf = Sugarloaf::new_headless(
+        SugarloafWindowSize { width, height },
+        1.0,
+        sugarloaf::SugarloafRenderer::default(),
+        &sugarloaf::font::FontLibrary::default(),
+        sugarloaf_layout,
+    );
+    sugarloaf.set_background_color(Some(wgpu::Color::RED));
+
+    let content = sugarloaf.content();
+    content.add_text(
+        "Sugarloaf",
+        FragmentStyle {
+            color: [1.0, 1.0, 1.0, 1.0],
+            background_color: Some([0.0, 0.0, 0.0, 1.0]),
+            ..FragmentStyle::default()
+        },
+    );
+    content.new_line();
+    content.add_text(
+        "headless",
+        FragmentStyle {
+            color: [0.0, 0.0, 0.0, 1.0],
+            background_color: Some([1.0, 1.0, 1.0, 1.0]),
+            ..FragmentStyle::default()
+        },
+    );
+
+    sugarloaf.screenshot(&path).expect("frame should be saved");
+    println!("saved {path}");
+}
@@ -89,6 +89,7 @@ impl ApplicationHandler for Application {
             height: Some(400.),
             x: 0.,
             y: 0.,
+            ..sugarloaf::ImageProperties::default()
         });
 
         window.request_redraw();
@@ -0,0 +1,28 @@
+// Prepended to the background shaders, they only have to provide
+// `@fragment fn fs_main(input: VertexOutput) -> @location(0) vec4<f32>`
+
+struct Globals {
+    // Size of the window in physical pixels
+    resolution: vec2<f32>,
+    // Seconds since the shader was loaded
+    time: f32,
+}
+
+@group(0) @binding(0) var<uniform> globals: Globals;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    // From (0, 0) at the top left to (1, 1) at the bottom right
+    @location(0) uv: vec2<f32>,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    // One triangle covering the whole window
+    let uv = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+
+    var out: VertexOutput;
+    out.uv = uv;
+    out.position = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+    return out;
+}
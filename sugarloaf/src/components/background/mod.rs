@@ -0,0 +1,143 @@
+use crate::context::Context;
+
+use std::mem;
+use std::time::Instant;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+struct Globals {
+    resolution: [f32; 2],
+    time: f32,
+    _padding: f32,
+}
+
+#[allow(unsafe_code)]
+unsafe impl bytemuck::Zeroable for Globals {}
+
+#[allow(unsafe_code)]
+unsafe impl bytemuck::Pod for Globals {}
+
+/// Renders a user provided WGSL fragment shader over the whole window,
+/// see `background.wgsl` for what the shader has access to.
+pub struct BackgroundShaderBrush {
+    pipeline: wgpu::RenderPipeline,
+    constants: wgpu::BindGroup,
+    globals: wgpu::Buffer,
+    started_at: Instant,
+}
+
+impl BackgroundShaderBrush {
+    /// Compiles `source`, shader and pipeline errors are returned instead
+    /// of being reported to the device.
+    pub fn new(context: &Context, source: &str) -> Result<Self, String> {
+        let device = &context.device;
+
+        let constant_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("sugarloaf::background uniforms layout"),
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: wgpu::BufferSize::new(
+                            mem::size_of::<Globals>() as wgpu::BufferAddress,
+                        ),
+                    },
+                    count: None,
+                }],
+            });
+
+        let globals = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("sugarloaf::background uniforms buffer"),
+            size: mem::size_of::<Globals>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let constants = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("sugarloaf::background uniforms bind group"),
+            layout: &constant_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: globals.as_entire_binding(),
+            }],
+        });
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("sugarloaf::background pipeline"),
+            push_constant_ranges: &[],
+            bind_group_layouts: &[&constant_layout],
+        });
+
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("sugarloaf::background shader"),
+            source: wgpu::ShaderSource::Wgsl(std::borrow::Cow::Owned(format!(
+                "{}\n{source}",
+                include_str!("./background.wgsl")
+            ))),
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            cache: None,
+            label: Some("sugarloaf::background render pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: context.format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        if let Some(error) = futures::executor::block_on(device.pop_error_scope()) {
+            return Err(error.to_string());
+        }
+
+        Ok(Self {
+            pipeline,
+            constants,
+            globals,
+            started_at: Instant::now(),
+        })
+    }
+
+    pub fn render<'a>(
+        &'a self,
+        context: &mut Context,
+        render_pass: &mut wgpu::RenderPass<'a>,
+    ) {
+        let globals = Globals {
+            resolution: [context.size.width, context.size.height],
+            time: self.started_at.elapsed().as_secs_f32(),
+            _padding: 0.,
+        };
+        context
+            .queue
+            .write_buffer(&self.globals, 0, bytemuck::bytes_of(&globals));
+
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.constants, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
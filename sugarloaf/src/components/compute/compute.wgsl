@@ -0,0 +1,55 @@
+struct Grid {
+    columns: u32,
+    rows: u32,
+    cell_width: u32,
+    cell_height: u32,
+}
+
+struct Cell {
+    // Glyph position and size in the atlas, in pixels.
+    glyph: vec4<u32>,
+    // Glyph position relative to the top left corner of the cell.
+    offset: vec2<i32>,
+    flags: u32,
+    padding: u32,
+    fg: vec4<f32>,
+    bg: vec4<f32>,
+}
+
+const COLORED_GLYPH: u32 = 1u;
+
+@group(0) @binding(0) var<uniform> grid: Grid;
+@group(0) @binding(1) var<storage, read> cells: array<Cell>;
+@group(0) @binding(2) var atlas: texture_2d<f32>;
+@group(0) @binding(3) var output: texture_storage_2d<rgba8unorm, write>;
+
+@compute @workgroup_size(8, 8)
+fn cs_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let size = textureDimensions(output);
+    if id.x >= size.x || id.y >= size.y {
+        return;
+    }
+
+    let column = id.x / grid.cell_width;
+    let row = id.y / grid.cell_height;
+    if column >= grid.columns || row >= grid.rows {
+        textureStore(output, vec2<i32>(id.xy), vec4<f32>(0.0));
+        return;
+    }
+
+    let cell = cells[row * grid.columns + column];
+    let origin = vec2<u32>(column * grid.cell_width, row * grid.cell_height);
+    let local = vec2<i32>(id.xy - origin) - cell.offset;
+
+    var color = cell.bg;
+    if local.x >= 0 && local.y >= 0 && u32(local.x) < cell.glyph.z && u32(local.y) < cell.glyph.w {
+        let texel = textureLoad(atlas, vec2<i32>(cell.glyph.xy) + local, 0);
+        if (cell.flags & COLORED_GLYPH) != 0u {
+            color = mix(color, texel, texel.a);
+        } else {
+            color = mix(color, cell.fg, texel.x * cell.fg.a);
+        }
+    }
+
+    textureStore(output, vec2<i32>(id.xy), color);
+}
@@ -0,0 +1,582 @@
+// Experimental compute shader compositing for very large grids, enabled
+// with the `compute-compositing` feature.
+//
+// Instead of emitting quads for every background and glyph, the grid is
+// uploaded to a storage buffer and a compute shader writes every pixel of
+// the output texture from the cell it belongs to. `QuadCompositor` renders
+// the same input with one instanced quad per background and per glyph, it's
+// the reference used by `benches/bench_compositing.rs`.
+//
+// Glyphs are clipped to their cell and the output is an offscreen rgba8
+// texture, so this path is not used by `Sugarloaf::render` yet.
+
+use bytemuck::{Pod, Zeroable};
+use std::{borrow::Cow, mem};
+
+/// The glyph in the atlas has its own colors (emoji, images).
+pub const COLORED_GLYPH: u32 = 1;
+
+const WORKGROUP_SIZE: u32 = 8;
+
+/// Dimensions of the grid, cells are laid out row by row.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Grid {
+    pub columns: u32,
+    pub rows: u32,
+    pub cell_width: u32,
+    pub cell_height: u32,
+}
+
+#[allow(unsafe_code)]
+unsafe impl Zeroable for Grid {}
+
+#[allow(unsafe_code)]
+unsafe impl Pod for Grid {}
+
+impl Grid {
+    #[inline]
+    pub fn width(&self) -> u32 {
+        self.columns * self.cell_width
+    }
+
+    #[inline]
+    pub fn height(&self) -> u32 {
+        self.rows * self.cell_height
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.columns as usize * self.rows as usize
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// A cell of the grid, matches `Cell` in `compute.wgsl`.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct GridCell {
+    /// Glyph position and size in the atlas in pixels, a zero
+    /// sized glyph only draws the background.
+    pub glyph: [u32; 4],
+    /// Glyph position relative to the top left corner of the cell.
+    pub offset: [i32; 2],
+    pub flags: u32,
+    pub padding: u32,
+    pub fg: [f32; 4],
+    pub bg: [f32; 4],
+}
+
+#[allow(unsafe_code)]
+unsafe impl Zeroable for GridCell {}
+
+#[allow(unsafe_code)]
+unsafe impl Pod for GridCell {}
+
+/// Creates a texture that can be used as output by both compositors.
+pub fn create_output(device: &wgpu::Device, width: u32, height: u32) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("compute::output"),
+        size: wgpu::Extent3d {
+            width: width.max(1),
+            height: height.max(1),
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: wgpu::TextureFormat::Rgba8Unorm,
+        usage: wgpu::TextureUsages::STORAGE_BINDING
+            | wgpu::TextureUsages::RENDER_ATTACHMENT
+            | wgpu::TextureUsages::TEXTURE_BINDING
+            | wgpu::TextureUsages::COPY_SRC,
+        view_formats: &[],
+    })
+}
+
+fn atlas_entry(
+    binding: u32,
+    visibility: wgpu::ShaderStages,
+) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility,
+        ty: wgpu::BindingType::Texture {
+            sample_type: wgpu::TextureSampleType::Float { filterable: false },
+            view_dimension: wgpu::TextureViewDimension::D2,
+            multisampled: false,
+        },
+        count: None,
+    }
+}
+
+fn uniform_entry(
+    binding: u32,
+    visibility: wgpu::ShaderStages,
+) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Uniform,
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    }
+}
+
+/// Grows `buffer` to fit `size` bytes.
+fn reserve(
+    device: &wgpu::Device,
+    buffer: &mut wgpu::Buffer,
+    size: u64,
+    label: &'static str,
+    usage: wgpu::BufferUsages,
+) {
+    if buffer.size() >= size {
+        return;
+    }
+    buffer.destroy();
+    *buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some(label),
+        size: size.next_power_of_two(),
+        usage,
+        mapped_at_creation: false,
+    });
+}
+
+/// Writes the grid straight into the output texture from a compute shader.
+pub struct ComputeCompositor {
+    pipeline: wgpu::ComputePipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    uniforms: wgpu::Buffer,
+    cells: wgpu::Buffer,
+}
+
+impl ComputeCompositor {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("compute::bind group layout"),
+                entries: &[
+                    uniform_entry(0, wgpu::ShaderStages::COMPUTE),
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: true },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    atlas_entry(2, wgpu::ShaderStages::COMPUTE),
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 3,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::StorageTexture {
+                            access: wgpu::StorageTextureAccess::WriteOnly,
+                            format: wgpu::TextureFormat::Rgba8Unorm,
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                        },
+                        count: None,
+                    },
+                ],
+            });
+
+        let pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: None,
+                bind_group_layouts: &[&bind_group_layout],
+                push_constant_ranges: &[],
+            });
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("compute::shader"),
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(include_str!("compute.wgsl"))),
+        });
+
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("compute::pipeline"),
+            layout: Some(&pipeline_layout),
+            module: &shader,
+            entry_point: "cs_main",
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+            cache: None,
+        });
+
+        let uniforms = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("compute::grid"),
+            size: mem::size_of::<Grid>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let cells = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("compute::cells"),
+            size: mem::size_of::<GridCell>() as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        Self {
+            pipeline,
+            bind_group_layout,
+            uniforms,
+            cells,
+        }
+    }
+
+    /// Records the compositing of `cells` into `output`, a view of
+    /// a texture created by [`create_output`].
+    #[allow(clippy::too_many_arguments)]
+    pub fn composite(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        grid: &Grid,
+        cells: &[GridCell],
+        atlas: &wgpu::TextureView,
+        output: &wgpu::Texture,
+    ) {
+        if grid.is_empty() || cells.len() < grid.len() {
+            return;
+        }
+
+        let bytes: &[u8] = bytemuck::cast_slice(&cells[..grid.len()]);
+        reserve(
+            device,
+            &mut self.cells,
+            bytes.len() as u64,
+            "compute::cells",
+            wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+        );
+        queue.write_buffer(&self.uniforms, 0, bytemuck::bytes_of(grid));
+        queue.write_buffer(&self.cells, 0, bytes);
+
+        let output_view = output.create_view(&wgpu::TextureViewDescriptor::default());
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("compute::bind group"),
+            layout: &self.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: self.uniforms.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: self.cells.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::TextureView(atlas),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: wgpu::BindingResource::TextureView(&output_view),
+                },
+            ],
+        });
+
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("compute::pass"),
+            timestamp_writes: None,
+        });
+        pass.set_pipeline(&self.pipeline);
+        pass.set_bind_group(0, &bind_group, &[]);
+        pass.dispatch_workgroups(
+            output.width().div_ceil(WORKGROUP_SIZE),
+            output.height().div_ceil(WORKGROUP_SIZE),
+            1,
+        );
+    }
+}
+
+/// Instance of `quad.wgsl`, `kind` is 0 for backgrounds,
+/// 1 for mask glyphs and 2 for colored glyphs.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct QuadInstance {
+    pub pos: [f32; 2],
+    pub size: [f32; 2],
+    pub uv: [f32; 2],
+    pub color: [f32; 4],
+    pub kind: u32,
+    pub padding: u32,
+}
+
+#[allow(unsafe_code)]
+unsafe impl Zeroable for QuadInstance {}
+
+#[allow(unsafe_code)]
+unsafe impl Pod for QuadInstance {}
+
+/// Quads for `cells`, a background per cell followed by its glyph.
+pub fn quad_instances(
+    grid: &Grid,
+    cells: &[GridCell],
+    instances: &mut Vec<QuadInstance>,
+) {
+    instances.clear();
+    for (index, cell) in cells.iter().take(grid.len()).enumerate() {
+        let column = index as u32 % grid.columns;
+        let row = index as u32 / grid.columns;
+        let x = (column * grid.cell_width) as f32;
+        let y = (row * grid.cell_height) as f32;
+
+        instances.push(QuadInstance {
+            pos: [x, y],
+            size: [grid.cell_width as f32, grid.cell_height as f32],
+            color: cell.bg,
+            ..QuadInstance::default()
+        });
+
+        if cell.glyph[2] == 0 || cell.glyph[3] == 0 {
+            continue;
+        }
+
+        // Clip to the cell like the compute path does.
+        let left = cell.offset[0].max(0) as u32;
+        let top = cell.offset[1].max(0) as u32;
+        let skip_x = (left as i32 - cell.offset[0]) as u32;
+        let skip_y = (top as i32 - cell.offset[1]) as u32;
+        let width = cell.glyph[2]
+            .saturating_sub(skip_x)
+            .min(grid.cell_width.saturating_sub(left));
+        let height = cell.glyph[3]
+            .saturating_sub(skip_y)
+            .min(grid.cell_height.saturating_sub(top));
+        if width == 0 || height == 0 {
+            continue;
+        }
+
+        instances.push(QuadInstance {
+            pos: [x + left as f32, y + top as f32],
+            size: [width as f32, height as f32],
+            uv: [
+                (cell.glyph[0] + skip_x) as f32,
+                (cell.glyph[1] + skip_y) as f32,
+            ],
+            color: cell.fg,
+            kind: if cell.flags & COLORED_GLYPH != 0 {
+                2
+            } else {
+                1
+            },
+            padding: 0,
+        });
+    }
+}
+
+/// Renders the grid with instanced quads, the path used by the
+/// rich text brush, kept as reference for the compute path.
+pub struct QuadCompositor {
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    uniforms: wgpu::Buffer,
+    instances: wgpu::Buffer,
+    data: Vec<QuadInstance>,
+}
+
+impl QuadCompositor {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("compute::quad bind group layout"),
+                entries: &[
+                    uniform_entry(0, wgpu::ShaderStages::VERTEX),
+                    atlas_entry(1, wgpu::ShaderStages::FRAGMENT),
+                ],
+            });
+
+        let pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: None,
+                bind_group_layouts: &[&bind_group_layout],
+                push_constant_ranges: &[],
+            });
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("compute::quad shader"),
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(include_str!("quad.wgsl"))),
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            cache: None,
+            label: Some("compute::quad pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: mem::size_of::<QuadInstance>() as u64,
+                    step_mode: wgpu::VertexStepMode::Instance,
+                    attributes: &wgpu::vertex_attr_array!(
+                        0 => Float32x2,
+                        1 => Float32x2,
+                        2 => Float32x2,
+                        3 => Float32x4,
+                        4 => Uint32,
+                    ),
+                }],
+            },
+            fragment: Some(wgpu::FragmentState {
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: wgpu::TextureFormat::Rgba8Unorm,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        let uniforms = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("compute::quad globals"),
+            size: mem::size_of::<[f32; 2]>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let instances = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("compute::quad instances"),
+            size: mem::size_of::<QuadInstance>() as u64,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        Self {
+            pipeline,
+            bind_group_layout,
+            uniforms,
+            instances,
+            data: Vec::new(),
+        }
+    }
+
+    /// Records the rendering of `cells` into `output`, a texture
+    /// created by [`create_output`].
+    #[allow(clippy::too_many_arguments)]
+    pub fn composite(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        grid: &Grid,
+        cells: &[GridCell],
+        atlas: &wgpu::TextureView,
+        output: &wgpu::Texture,
+    ) {
+        if grid.is_empty() || cells.len() < grid.len() {
+            return;
+        }
+
+        quad_instances(grid, cells, &mut self.data);
+        let bytes: &[u8] = bytemuck::cast_slice(&self.data);
+        reserve(
+            device,
+            &mut self.instances,
+            bytes.len() as u64,
+            "compute::quad instances",
+            wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        );
+        let size = [output.width() as f32, output.height() as f32];
+        queue.write_buffer(&self.uniforms, 0, bytemuck::cast_slice(&size));
+        queue.write_buffer(&self.instances, 0, bytes);
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("compute::quad bind group"),
+            layout: &self.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: self.uniforms.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(atlas),
+                },
+            ],
+        });
+
+        let output_view = output.create_view(&wgpu::TextureViewDescriptor::default());
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("compute::quad pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &output_view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        pass.set_pipeline(&self.pipeline);
+        pass.set_bind_group(0, &bind_group, &[]);
+        pass.set_vertex_buffer(0, self.instances.slice(..));
+        pass.draw(0..6, 0..self.data.len() as u32);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grid_cell_matches_shader_layout() {
+        assert_eq!(mem::size_of::<Grid>(), 16);
+        assert_eq!(mem::size_of::<GridCell>(), 64);
+        assert_eq!(mem::size_of::<QuadInstance>(), 48);
+    }
+
+    #[test]
+    fn quad_instances_are_clipped_to_cells() {
+        let grid = Grid {
+            columns: 2,
+            rows: 1,
+            cell_width: 10,
+            cell_height: 20,
+        };
+        let cells = [
+            GridCell {
+                glyph: [100, 200, 14, 16],
+                offset: [-2, 6],
+                fg: [1.0; 4],
+                ..GridCell::default()
+            },
+            GridCell {
+                bg: [0.0, 0.0, 1.0, 1.0],
+                ..GridCell::default()
+            },
+        ];
+
+        let mut instances = Vec::new();
+        quad_instances(&grid, &cells, &mut instances);
+
+        assert_eq!(instances.len(), 3);
+        assert_eq!(instances[1].pos, [0.0, 6.0]);
+        assert_eq!(instances[1].size, [10.0, 14.0]);
+        assert_eq!(instances[1].uv, [102.0, 200.0]);
+        assert_eq!(instances[1].kind, 1);
+        assert_eq!(instances[2].pos, [10.0, 0.0]);
+        assert_eq!(instances[2].color, [0.0, 0.0, 1.0, 1.0]);
+    }
+}
@@ -0,0 +1,56 @@
+struct Globals {
+    size: vec2<f32>,
+}
+
+@group(0) @binding(0) var<uniform> globals: Globals;
+@group(0) @binding(1) var atlas: texture_2d<f32>;
+
+struct Instance {
+    @builtin(vertex_index) vertex_index: u32,
+    @location(0) pos: vec2<f32>,
+    @location(1) size: vec2<f32>,
+    @location(2) uv: vec2<f32>,
+    @location(3) color: vec4<f32>,
+    @location(4) kind: u32,
+}
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) color: vec4<f32>,
+    @location(2) @interpolate(flat) kind: u32,
+}
+
+@vertex
+fn vs_main(input: Instance) -> VertexOutput {
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(0.0, 1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 0.0),
+    );
+    let corner = corners[input.vertex_index];
+    let pixel = input.pos + corner * input.size;
+
+    var out: VertexOutput;
+    out.position = vec4<f32>(pixel / globals.size * vec2<f32>(2.0, -2.0) + vec2<f32>(-1.0, 1.0), 0.0, 1.0);
+    out.uv = input.uv + corner * input.size;
+    out.color = input.color;
+    out.kind = input.kind;
+    return out;
+}
+
+@fragment
+fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    if input.kind == 0u {
+        return input.color;
+    }
+
+    let texel = textureLoad(atlas, vec2<i32>(input.uv), 0);
+    if input.kind == 2u {
+        return texel;
+    }
+    return vec4<f32>(input.color.xyz, texel.x * input.color.a);
+}
@@ -3,6 +3,7 @@ pub mod uniforms;
 // pub mod svg;
 pub mod buffer;
 pub mod shapes;
+pub mod staging;
 
 #[inline]
 pub fn orthographic_projection(width: f32, height: f32) -> [f32; 16] {
@@ -0,0 +1,180 @@
+use std::sync::{mpsc, Arc};
+
+/// Size of a staging chunk, large enough to hold the glyphs rasterized
+/// during a frame of heavy output. Bigger uploads get a dedicated chunk.
+pub const CHUNK_SIZE: wgpu::BufferAddress = 1 << 20;
+
+const ALIGNMENT: wgpu::BufferAddress = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT as u64;
+
+struct Chunk {
+    buffer: Arc<wgpu::Buffer>,
+    size: wgpu::BufferAddress,
+    offset: wgpu::BufferAddress,
+}
+
+/// Ring of staging buffers used to upload data to textures.
+///
+/// Chunks stay mapped while idle, so writing an upload is a plain copy into
+/// memory shared with the GPU. After the frame is submitted, [`recall`]
+/// maps the used chunks again and they return to the ring once the GPU
+/// is done reading from them.
+///
+/// [`recall`]: StagingRing::recall
+pub struct StagingRing {
+    chunk_size: wgpu::BufferAddress,
+    active: Vec<Chunk>,
+    closed: Vec<Chunk>,
+    free: Vec<Chunk>,
+    sender: mpsc::Sender<Chunk>,
+    receiver: mpsc::Receiver<Chunk>,
+}
+
+impl StagingRing {
+    pub fn new(chunk_size: wgpu::BufferAddress) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Self {
+            chunk_size,
+            active: Vec::new(),
+            closed: Vec::new(),
+            free: Vec::new(),
+            sender,
+            receiver,
+        }
+    }
+
+    /// Records a copy of a rectangle of `source` into `texture`.
+    ///
+    /// `source` is a tightly packed rgba8 image with `source_width` pixels
+    /// per row, `x` and `y` are used both as position in the source and
+    /// in the texture.
+    #[allow(clippy::too_many_arguments)]
+    pub fn write_texture(
+        &mut self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        texture: &wgpu::Texture,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        source: &[u8],
+        source_width: u32,
+    ) {
+        if width == 0 || height == 0 {
+            return;
+        }
+
+        let row_bytes = width as usize * 4;
+        let padded_row_bytes = wgpu::util::align_to(width * 4, ALIGNMENT as u32);
+        let size = padded_row_bytes as u64 * height as u64;
+        let source_pitch = source_width as usize * 4;
+
+        let chunk = self.chunk(device, size);
+        let offset = chunk.offset;
+        {
+            let mut view = chunk
+                .buffer
+                .slice(offset..offset + size)
+                .get_mapped_range_mut();
+            for (row, dest) in
+                view.chunks_exact_mut(padded_row_bytes as usize).enumerate()
+            {
+                let start = (y as usize + row) * source_pitch + x as usize * 4;
+                if let Some(src) = source.get(start..start + row_bytes) {
+                    dest[..row_bytes].copy_from_slice(src);
+                }
+            }
+        }
+
+        encoder.copy_buffer_to_texture(
+            wgpu::ImageCopyBuffer {
+                buffer: &chunk.buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset,
+                    bytes_per_row: Some(padded_row_bytes),
+                    rows_per_image: Some(height),
+                },
+            },
+            wgpu::ImageCopyTexture {
+                texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d { x, y, z: 0 },
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+
+        chunk.offset = wgpu::util::align_to(offset + size, ALIGNMENT);
+    }
+
+    /// Unmaps the chunks written in this frame, must be called
+    /// before the encoder is submitted.
+    pub fn finish(&mut self) {
+        for chunk in self.active.drain(..) {
+            chunk.buffer.unmap();
+            self.closed.push(chunk);
+        }
+    }
+
+    /// Maps the chunks used by the submitted frame so they can be reused,
+    /// must be called after the encoder is submitted.
+    pub fn recall(&mut self) {
+        self.receive();
+
+        for chunk in self.closed.drain(..) {
+            let sender = self.sender.clone();
+            chunk
+                .buffer
+                .clone()
+                .slice(..)
+                .map_async(wgpu::MapMode::Write, move |_| {
+                    let _ = sender.send(chunk);
+                });
+        }
+    }
+
+    fn chunk(&mut self, device: &wgpu::Device, size: wgpu::BufferAddress) -> &mut Chunk {
+        if let Some(index) = self
+            .active
+            .iter()
+            .position(|chunk| chunk.offset + size <= chunk.size)
+        {
+            return &mut self.active[index];
+        }
+
+        self.receive();
+
+        let chunk = match self.free.iter().position(|chunk| size <= chunk.size) {
+            Some(index) => self.free.swap_remove(index),
+            None => {
+                let size = self.chunk_size.max(size);
+                Chunk {
+                    buffer: Arc::new(device.create_buffer(&wgpu::BufferDescriptor {
+                        label: Some("sugarloaf::staging ring chunk"),
+                        size,
+                        usage: wgpu::BufferUsages::MAP_WRITE
+                            | wgpu::BufferUsages::COPY_SRC,
+                        mapped_at_creation: true,
+                    })),
+                    size,
+                    offset: 0,
+                }
+            }
+        };
+
+        self.active.push(chunk);
+        let last = self.active.len() - 1;
+        &mut self.active[last]
+    }
+
+    fn receive(&mut self) {
+        while let Ok(mut chunk) = self.receiver.try_recv() {
+            chunk.offset = 0;
+            self.free.push(chunk);
+        }
+    }
+}
@@ -0,0 +1,171 @@
+use crate::context::Context;
+
+/// Brushes render into an offscreen texture on HDR surfaces, which is
+/// then copied to the surface converting the colors to linear values.
+pub struct HdrOutput {
+    pipeline: wgpu::RenderPipeline,
+    layout: wgpu::BindGroupLayout,
+    bind_group: wgpu::BindGroup,
+    view: wgpu::TextureView,
+    size: (u32, u32),
+}
+
+impl HdrOutput {
+    pub fn new(context: &Context) -> Self {
+        let layout =
+            context
+                .device
+                .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                    label: Some("sugarloaf::hdr frame layout"),
+                    entries: &[wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            sample_type: wgpu::TextureSampleType::Float {
+                                filterable: false,
+                            },
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    }],
+                });
+
+        let pipeline_layout =
+            context
+                .device
+                .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                    label: Some("sugarloaf::hdr pipeline"),
+                    push_constant_ranges: &[],
+                    bind_group_layouts: &[&layout],
+                });
+
+        let shader = context
+            .device
+            .create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: Some("sugarloaf::hdr shader"),
+                source: wgpu::ShaderSource::Wgsl(std::borrow::Cow::Borrowed(
+                    include_str!("./hdr.wgsl"),
+                )),
+            });
+
+        let pipeline =
+            context
+                .device
+                .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                    cache: None,
+                    label: Some("sugarloaf::hdr render pipeline"),
+                    layout: Some(&pipeline_layout),
+                    vertex: wgpu::VertexState {
+                        compilation_options: wgpu::PipelineCompilationOptions::default(),
+                        module: &shader,
+                        entry_point: "vs_main",
+                        buffers: &[],
+                    },
+                    fragment: Some(wgpu::FragmentState {
+                        compilation_options: wgpu::PipelineCompilationOptions::default(),
+                        module: &shader,
+                        entry_point: "fs_main",
+                        targets: &[Some(wgpu::ColorTargetState {
+                            format: context.output.surface,
+                            blend: None,
+                            write_mask: wgpu::ColorWrites::ALL,
+                        })],
+                    }),
+                    primitive: wgpu::PrimitiveState::default(),
+                    depth_stencil: None,
+                    multisample: wgpu::MultisampleState::default(),
+                    multiview: None,
+                });
+
+        let size = Self::frame_size(context);
+        let (view, bind_group) = Self::create_frame(context, &layout, size);
+
+        Self {
+            pipeline,
+            layout,
+            bind_group,
+            view,
+            size,
+        }
+    }
+
+    #[inline]
+    fn frame_size(context: &Context) -> (u32, u32) {
+        (
+            (context.size.width as u32).max(1),
+            (context.size.height as u32).max(1),
+        )
+    }
+
+    fn create_frame(
+        context: &Context,
+        layout: &wgpu::BindGroupLayout,
+        size: (u32, u32),
+    ) -> (wgpu::TextureView, wgpu::BindGroup) {
+        let texture = context.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("sugarloaf::hdr frame"),
+            size: wgpu::Extent3d {
+                width: size.0,
+                height: size.1,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: context.format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let bind_group = context
+            .device
+            .create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("sugarloaf::hdr frame bind group"),
+                layout,
+                entries: &[wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                }],
+            });
+        (view, bind_group)
+    }
+
+    /// Recreates the offscreen texture when the window size changed.
+    pub fn resize(&mut self, context: &Context) {
+        let size = Self::frame_size(context);
+        if size != self.size {
+            (self.view, self.bind_group) =
+                Self::create_frame(context, &self.layout, size);
+            self.size = size;
+        }
+    }
+
+    /// View the brushes render to instead of the surface.
+    #[inline]
+    pub fn view(&self) -> &wgpu::TextureView {
+        &self.view
+    }
+
+    /// Copies the offscreen texture to `target`.
+    pub fn render(&self, encoder: &mut wgpu::CommandEncoder, target: &wgpu::TextureView) {
+        let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            timestamp_writes: None,
+            occlusion_query_set: None,
+            label: Some("sugarloaf::hdr render pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: target,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+        });
+        rpass.set_pipeline(&self.pipeline);
+        rpass.set_bind_group(0, &self.bind_group, &[]);
+        rpass.draw(0..3, 0..1);
+    }
+}
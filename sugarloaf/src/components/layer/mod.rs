@@ -218,6 +218,7 @@ impl LayerBrush {
                             3 => Float32x2,
                             4 => Float32x2,
                             5 => Sint32,
+                            6 => Float32,
                         ),
                     },
                 ],
@@ -328,6 +329,7 @@ impl LayerBrush {
                 add_instances(
                     [bounds.x, bounds.y],
                     [bounds.width, bounds.height],
+                    image.opacity,
                     atlas_entry,
                     instances,
                 );
@@ -389,6 +391,7 @@ impl LayerBrush {
             add_instances(
                 [bounds.x, bounds.y],
                 [bounds.width, bounds.height],
+                1.0,
                 atlas_entry,
                 instances,
             );
@@ -540,6 +543,7 @@ struct Instance {
     _position_in_atlas: [f32; 2],
     _size_in_atlas: [f32; 2],
     _layer: u32,
+    _opacity: f32,
 }
 
 impl Instance {
@@ -555,12 +559,13 @@ struct Uniforms {
 fn add_instances(
     image_position: [f32; 2],
     image_size: [f32; 2],
+    opacity: f32,
     entry: &atlas::Entry,
     instances: &mut Vec<Instance>,
 ) {
     match entry {
         atlas::Entry::Contiguous(allocation) => {
-            add_instance(image_position, image_size, allocation, instances);
+            add_instance(image_position, image_size, opacity, allocation, instances);
         }
         atlas::Entry::Fragmented { fragments, size } => {
             let scaling_x = image_size[0] / size.width as f32;
@@ -586,7 +591,7 @@ fn add_instances(
                     fragment_height as f32 * scaling_y,
                 ];
 
-                add_instance(position, size, allocation, instances);
+                add_instance(position, size, opacity, allocation, instances);
             }
         }
     }
@@ -596,6 +601,7 @@ fn add_instances(
 fn add_instance(
     position: [f32; 2],
     size: [f32; 2],
+    opacity: f32,
     allocation: &atlas::Allocation,
     instances: &mut Vec<Instance>,
 ) {
@@ -615,6 +621,7 @@ fn add_instance(
             (height as f32 - 1.0) / atlas::SIZE as f32,
         ],
         _layer: layer as u32,
+        _opacity: opacity,
     };
 
     instances.push(instance);
@@ -10,4 +10,7 @@ pub struct Raster {
 
     /// The bounds of the image.
     pub bounds: Rectangle,
+
+    /// Opacity of the image, from `0` to `1`.
+    pub opacity: f32,
 }
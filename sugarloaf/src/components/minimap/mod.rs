@@ -0,0 +1,117 @@
+use crate::components::rect::Rect;
+
+/// Height of a marker, markers are never thinner than this so a single
+/// line stays visible in a long scrollback.
+const MARKER_HEIGHT: f32 = 2.;
+
+/// Line on the minimap, e.g. a prompt or a search match.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MinimapMarker {
+    /// Position from the top, between 0 and 1.
+    pub position: f32,
+    pub color: [f32; 4],
+}
+
+/// Compressed view of a long buffer drawn as a narrow column, it's turned
+/// into rects when the objects are computed.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Minimap {
+    pub position: [f32; 2],
+    pub size: [f32; 2],
+    pub background: [f32; 4],
+    pub foreground: [f32; 4],
+    /// Color of the area shown in the viewport.
+    pub viewport_color: [f32; 4],
+    /// Occupied width of each row, between 0 and 1, rows are spread
+    /// over the whole height.
+    pub rows: Vec<f32>,
+    pub markers: Vec<MinimapMarker>,
+    /// Start and end of the viewport, between 0 and 1.
+    pub viewport: [f32; 2],
+}
+
+impl Minimap {
+    pub fn rects(&self, rects: &mut Vec<Rect>) {
+        let [x, y] = self.position;
+        let [width, height] = self.size;
+
+        rects.push(Rect {
+            position: [x, y],
+            color: self.background,
+            size: [width, height],
+        });
+
+        let row_height = height / self.rows.len().max(1) as f32;
+        for (i, occupied) in self.rows.iter().enumerate() {
+            if *occupied <= 0. {
+                continue;
+            }
+
+            rects.push(Rect {
+                position: [x, y + i as f32 * row_height],
+                color: self.foreground,
+                size: [width * occupied.min(1.), row_height],
+            });
+        }
+
+        let [start, end] = self.viewport;
+        rects.push(Rect {
+            position: [x, y + start.clamp(0., 1.) * height],
+            color: self.viewport_color,
+            size: [
+                width,
+                ((end - start).clamp(0., 1.) * height).max(MARKER_HEIGHT),
+            ],
+        });
+
+        for marker in &self.markers {
+            let position =
+                (marker.position.clamp(0., 1.) * height).min(height - MARKER_HEIGHT);
+            rects.push(Rect {
+                position: [x, y + position],
+                color: marker.color,
+                size: [width, MARKER_HEIGHT],
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_minimap_rects() {
+        let minimap = Minimap {
+            position: [100., 10.],
+            size: [8., 100.],
+            rows: vec![0.5, 0., 1., 2.],
+            markers: vec![MinimapMarker {
+                position: 1.,
+                color: [1., 0., 0., 1.],
+            }],
+            viewport: [0.75, 1.],
+            ..Minimap::default()
+        };
+
+        let mut rects = Vec::new();
+        minimap.rects(&mut rects);
+        let areas: Vec<_> = rects
+            .iter()
+            .map(|rect| (rect.position, rect.size))
+            .collect();
+        assert_eq!(
+            areas,
+            vec![
+                ([100., 10.], [8., 100.]),
+                ([100., 10.], [4., 25.]),
+                // Empty rows are skipped and rows are never wider than the minimap
+                ([100., 60.], [8., 25.]),
+                ([100., 85.], [8., 25.]),
+                ([100., 85.], [8., 25.]),
+                // Markers at the end are kept inside
+                ([100., 108.], [8., 2.]),
+            ]
+        );
+    }
+}
@@ -1,5 +1,11 @@
+pub mod background;
+#[cfg(feature = "compute-compositing")]
+pub mod compute;
 pub mod core;
+pub mod hdr;
 pub mod layer;
+pub mod minimap;
+pub mod pane_frames;
 pub mod quad;
 pub mod rect;
 pub mod rich_text;
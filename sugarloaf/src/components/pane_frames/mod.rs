@@ -0,0 +1,241 @@
+use crate::components::rect::Rect;
+
+/// Edges closer than this are considered shared by two panes.
+const EPSILON: f32 = 0.5;
+
+/// Position and size of a pane, in logical pixels.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PaneArea {
+    pub position: [f32; 2],
+    pub size: [f32; 2],
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DividerAxis {
+    /// Between panes side by side, resized horizontally.
+    Vertical,
+    /// Between panes on top of each other, resized vertically.
+    Horizontal,
+}
+
+/// Line between two panes, dragging it resizes both.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Divider {
+    pub axis: DividerAxis,
+    pub position: [f32; 2],
+    pub size: [f32; 2],
+    /// Index of the pane on the left or above.
+    pub before: usize,
+    /// Index of the pane on the right or below.
+    pub after: usize,
+}
+
+/// Dividers between panes and a frame around the active one, it's
+/// turned into rects when the objects are computed. Panes next to each
+/// other can leave a gap of up to `thickness` for the divider.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PaneFrames {
+    pub panes: Vec<PaneArea>,
+    pub active: Option<usize>,
+    pub thickness: f32,
+    pub color: [f32; 4],
+    /// Color of the frame around the active pane.
+    pub active_color: [f32; 4],
+}
+
+impl PaneFrames {
+    /// Dividers along the edges shared by panes.
+    pub fn dividers(&self) -> Vec<Divider> {
+        let mut dividers = Vec::new();
+        let max_gap = self.thickness + EPSILON;
+        for (i, a) in self.panes.iter().enumerate() {
+            let [a_x, a_y] = a.position;
+            let [a_width, a_height] = a.size;
+            for (j, b) in self.panes.iter().enumerate() {
+                if i == j {
+                    continue;
+                }
+                let [b_x, b_y] = b.position;
+                let [b_width, b_height] = b.size;
+
+                let gap = b_x - (a_x + a_width);
+                let top = a_y.max(b_y);
+                let bottom = (a_y + a_height).min(b_y + b_height);
+                if (-EPSILON..=max_gap).contains(&gap) && bottom > top {
+                    let center = a_x + a_width + gap / 2.;
+                    dividers.push(Divider {
+                        axis: DividerAxis::Vertical,
+                        position: [center - self.thickness / 2., top],
+                        size: [self.thickness, bottom - top],
+                        before: i,
+                        after: j,
+                    });
+                }
+
+                let gap = b_y - (a_y + a_height);
+                let left = a_x.max(b_x);
+                let right = (a_x + a_width).min(b_x + b_width);
+                if (-EPSILON..=max_gap).contains(&gap) && right > left {
+                    let center = a_y + a_height + gap / 2.;
+                    dividers.push(Divider {
+                        axis: DividerAxis::Horizontal,
+                        position: [left, center - self.thickness / 2.],
+                        size: [right - left, self.thickness],
+                        before: i,
+                        after: j,
+                    });
+                }
+            }
+        }
+        dividers
+    }
+
+    /// Divider under the point, `tolerance` widens the dividers so thin
+    /// ones are easy to grab.
+    pub fn hit(&self, x: f32, y: f32, tolerance: f32) -> Option<Divider> {
+        self.dividers().into_iter().find(|divider| {
+            let [left, top] = divider.position;
+            let [width, height] = divider.size;
+            let (x_tolerance, y_tolerance) = match divider.axis {
+                DividerAxis::Vertical => (tolerance, 0.),
+                DividerAxis::Horizontal => (0., tolerance),
+            };
+            x >= left - x_tolerance
+                && x <= left + width + x_tolerance
+                && y >= top - y_tolerance
+                && y <= top + height + y_tolerance
+        })
+    }
+
+    pub fn rects(&self, rects: &mut Vec<Rect>) {
+        if self.thickness <= 0. {
+            return;
+        }
+
+        for divider in self.dividers() {
+            rects.push(Rect {
+                position: divider.position,
+                color: self.color,
+                size: divider.size,
+            });
+        }
+
+        // A single pane is always the active one.
+        let active = self
+            .active
+            .filter(|_| self.panes.len() > 1)
+            .and_then(|active| self.panes.get(active));
+        if let Some(pane) = active {
+            let [x, y] = pane.position;
+            let [width, height] = pane.size;
+            let thickness = self.thickness;
+            let edges = [
+                ([x, y], [width, thickness]),
+                ([x, y + height - thickness], [width, thickness]),
+                ([x, y], [thickness, height]),
+                ([x + width - thickness, y], [thickness, height]),
+            ];
+            for (position, size) in edges {
+                rects.push(Rect {
+                    position,
+                    color: self.active_color,
+                    size,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Two panes side by side, the right one split in two with a gap of
+    // two pixels for the dividers.
+    fn pane_frames() -> PaneFrames {
+        PaneFrames {
+            panes: vec![
+                PaneArea {
+                    position: [0., 0.],
+                    size: [100., 200.],
+                },
+                PaneArea {
+                    position: [102., 0.],
+                    size: [100., 99.],
+                },
+                PaneArea {
+                    position: [102., 101.],
+                    size: [100., 99.],
+                },
+            ],
+            active: Some(1),
+            thickness: 2.,
+            ..PaneFrames::default()
+        }
+    }
+
+    #[test]
+    fn test_dividers() {
+        let dividers = pane_frames().dividers();
+        assert_eq!(
+            dividers,
+            vec![
+                Divider {
+                    axis: DividerAxis::Vertical,
+                    position: [100., 0.],
+                    size: [2., 99.],
+                    before: 0,
+                    after: 1,
+                },
+                Divider {
+                    axis: DividerAxis::Vertical,
+                    position: [100., 101.],
+                    size: [2., 99.],
+                    before: 0,
+                    after: 2,
+                },
+                Divider {
+                    axis: DividerAxis::Horizontal,
+                    position: [102., 99.],
+                    size: [100., 2.],
+                    before: 1,
+                    after: 2,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_hit() {
+        let pane_frames = pane_frames();
+        assert_eq!(pane_frames.hit(101., 50., 0.).map(|d| d.after), Some(1));
+        // Thin dividers are easier to grab with a tolerance
+        assert_eq!(pane_frames.hit(98., 50., 0.), None);
+        assert_eq!(pane_frames.hit(98., 50., 3.).map(|d| d.after), Some(1));
+        assert_eq!(
+            pane_frames.hit(150., 100., 0.).map(|d| d.axis),
+            Some(DividerAxis::Horizontal)
+        );
+        assert_eq!(pane_frames.hit(50., 50., 3.), None);
+    }
+
+    #[test]
+    fn test_rects() {
+        let mut rects = Vec::new();
+        pane_frames().rects(&mut rects);
+        // Dividers and the four edges of the active pane
+        assert_eq!(rects.len(), 7);
+        assert_eq!(rects[3].position, [102., 0.]);
+        assert_eq!(rects[3].size, [100., 2.]);
+
+        let single = PaneFrames {
+            panes: vec![PaneArea::default()],
+            active: Some(0),
+            thickness: 2.,
+            ..PaneFrames::default()
+        };
+        rects.clear();
+        single.rects(&mut rects);
+        assert!(rects.is_empty());
+    }
+}
@@ -19,10 +19,45 @@ pub struct Vertex {
     pub color: [f32; 4],
     pub uv: [f32; 2],
     pub layers: [i32; 2],
+    /// Color the glyph is drawn over, transparent for the window
+    /// background.
+    pub background: [f32; 4],
 }
 
+/// Solid color rectangle drawn as a single instance, used for
+/// backgrounds, decorations and the cursor.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quad {
+    pub pos: [f32; 2],
+    pub size: [f32; 2],
+    pub color: [f32; 4],
+    /// One of the `QUAD_*` shapes, patterns are drawn by the fragment
+    /// shader so a whole underline is a single instance.
+    pub shape: u32,
+}
+
+pub const QUAD_SOLID: u32 = 0;
+pub const QUAD_DOTTED: u32 = 1;
+pub const QUAD_DASHED: u32 = 2;
+pub const QUAD_CURLY: u32 = 3;
+// Triangles filling half of the quad, named by the corner or the side
+// they point to.
+pub const QUAD_TRIANGLE_RIGHT: u32 = 4;
+pub const QUAD_TRIANGLE_LEFT: u32 = 5;
+pub const QUAD_TRIANGLE_LOWER_LEFT: u32 = 6;
+pub const QUAD_TRIANGLE_LOWER_RIGHT: u32 = 7;
+pub const QUAD_TRIANGLE_UPPER_LEFT: u32 = 8;
+pub const QUAD_TRIANGLE_UPPER_RIGHT: u32 = 9;
+
+#[allow(unsafe_code)]
+unsafe impl Zeroable for Quad {}
+
+#[allow(unsafe_code)]
+unsafe impl Pod for Quad {}
+
 /// Rectangle with floating point coordinates.
-#[derive(Copy, Clone, Default, Debug)]
+#[derive(Copy, Clone, Default, Debug, PartialEq)]
 pub struct Rect {
     pub x: f32,
     pub y: f32,
@@ -77,6 +112,7 @@ impl Batch {
         image: Option<i32>,
         mask: Option<i32>,
         subpix: bool,
+        background: &[f32; 4],
     ) -> bool {
         if !self.vertices.is_empty() && subpix != self.subpix {
             return false;
@@ -93,7 +129,7 @@ impl Batch {
         self.image = image;
         self.mask = mask;
         let layers = [self.image.unwrap_or(0), self.mask.unwrap_or(0)];
-        self.push_rect(rect, depth, color, coords, layers);
+        self.push_rect(rect, depth, color, coords, layers, background);
         true
     }
 
@@ -105,6 +141,7 @@ impl Batch {
         color: &[f32; 4],
         coords: Option<&[f32; 4]>,
         layers: [i32; 2],
+        background: &[f32; 4],
     ) {
         let x = rect.x;
         let y = rect.y;
@@ -122,24 +159,28 @@ impl Batch {
                 color: *color,
                 uv: [l, t],
                 layers,
+                background: *background,
             },
             Vertex {
                 pos: [x, y + h, depth],
                 color: *color,
                 uv: [l, b],
                 layers,
+                background: *background,
             },
             Vertex {
                 pos: [x + w, y + h, depth],
                 color: *color,
                 uv: [r, b],
                 layers,
+                background: *background,
             },
             Vertex {
                 pos: [x + w, y, depth],
                 color: *color,
                 uv: [r, t],
                 layers,
+                background: *background,
             },
         ];
         let base = self.vertices.len() as u32;
@@ -167,6 +208,7 @@ pub struct BatchManager {
     batches: Vec<Batch>,
     opaque: Vec<Batch>,
     transparent: Vec<Batch>,
+    quads: Vec<Quad>,
 }
 
 impl BatchManager {
@@ -175,11 +217,13 @@ impl BatchManager {
             batches: Vec::new(),
             opaque: Vec::new(),
             transparent: Vec::new(),
+            quads: Vec::new(),
         }
     }
 
     #[inline]
     pub fn reset(&mut self) {
+        self.quads.clear();
         self.batches.append(&mut self.opaque);
         self.batches.append(&mut self.transparent);
         for batch in &mut self.batches {
@@ -195,9 +239,19 @@ impl BatchManager {
         color: &[f32; 4],
         coords: &[f32; 4],
         subpix: bool,
+        background: &[f32; 4],
     ) {
         for batch in &mut self.transparent {
-            if batch.add_rect(rect, depth, color, Some(coords), None, Some(1), subpix) {
+            if batch.add_rect(
+                rect,
+                depth,
+                color,
+                Some(coords),
+                None,
+                Some(1),
+                subpix,
+                background,
+            ) {
                 return;
             }
         }
@@ -209,6 +263,7 @@ impl BatchManager {
             None,
             Some(1),
             subpix,
+            background,
         );
     }
 
@@ -224,15 +279,31 @@ impl BatchManager {
         let transparent = has_alpha || color[3] != 1.0;
         if transparent {
             for batch in &mut self.transparent {
-                if batch.add_rect(rect, depth, color, Some(coords), Some(1), None, false)
-                {
+                if batch.add_rect(
+                    rect,
+                    depth,
+                    color,
+                    Some(coords),
+                    Some(1),
+                    None,
+                    false,
+                    &[0.; 4],
+                ) {
                     return;
                 }
             }
         } else {
             for batch in &mut self.opaque {
-                if batch.add_rect(rect, depth, color, Some(coords), Some(1), None, false)
-                {
+                if batch.add_rect(
+                    rect,
+                    depth,
+                    color,
+                    Some(coords),
+                    Some(1),
+                    None,
+                    false,
+                    &[0.; 4],
+                ) {
                     return;
                 }
             }
@@ -245,31 +316,34 @@ impl BatchManager {
             Some(1),
             None,
             false,
+            &[0.; 4],
         );
     }
 
+    /// Solid rectangles are drawn in a single instanced call
+    /// before the glyphs, in the order they were added.
     #[inline]
-    pub fn add_rect(&mut self, rect: &Rect, depth: f32, color: &[f32; 4]) {
-        let transparent = color[3] != 1.0;
-        if transparent {
-            for batch in &mut self.transparent {
-                if batch.add_rect(rect, depth, color, None, None, None, false) {
-                    return;
-                }
-            }
-        } else {
-            for batch in &mut self.opaque {
-                if batch.add_rect(rect, depth, color, None, None, None, false) {
-                    return;
-                }
-            }
+    pub fn add_rect(&mut self, rect: &Rect, color: &[f32; 4]) {
+        self.add_shaped_rect(rect, color, QUAD_SOLID);
+    }
+
+    /// Rectangle filled with one of the `QUAD_*` patterns.
+    #[inline]
+    pub fn add_shaped_rect(&mut self, rect: &Rect, color: &[f32; 4], shape: u32) {
+        if rect.width <= 0. || rect.height <= 0. || color[3] == 0. {
+            return;
         }
-        self.alloc_batch(transparent)
-            .add_rect(rect, depth, color, None, None, None, false);
+        self.quads.push(Quad {
+            pos: [rect.x, rect.y],
+            size: [rect.width, rect.height],
+            color: *color,
+            shape,
+        });
     }
 
     #[inline]
     pub fn build_display_list(&self, list: &mut DisplayList) {
+        list.quads.extend_from_slice(&self.quads);
         for batch in &self.opaque {
             if batch.vertices.is_empty() {
                 continue;
@@ -302,6 +376,7 @@ impl BatchManager {
 pub struct DisplayList {
     pub vertices: Vec<Vertex>,
     pub indices: Vec<u32>,
+    pub quads: Vec<Quad>,
 }
 
 impl DisplayList {
@@ -316,5 +391,59 @@ impl DisplayList {
     pub fn clear(&mut self) {
         self.vertices.clear();
         self.indices.clear();
+        self.quads.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn solid_rects_are_quad_instances() {
+        let mut batches = BatchManager::new();
+        batches.add_rect(&Rect::new(0., 0., 10., 20.), &[1., 0., 0., 1.]);
+        batches.add_rect(&Rect::new(10., 0., 10., 20.), &[0., 1., 0., 0.5]);
+        batches.add_rect(&Rect::new(20., 0., 0., 20.), &[0., 0., 1., 1.]);
+        batches.add_rect(&Rect::new(20., 0., 10., 20.), &[0., 0., 1., 0.]);
+        batches.add_shaped_rect(
+            &Rect::new(0., 18., 30., 4.),
+            &[1., 1., 0., 1.],
+            QUAD_CURLY,
+        );
+
+        let mut list = DisplayList::new();
+        batches.build_display_list(&mut list);
+
+        assert!(list.vertices.is_empty());
+        assert!(list.indices.is_empty());
+        assert_eq!(
+            list.quads,
+            [
+                Quad {
+                    pos: [0., 0.],
+                    size: [10., 20.],
+                    color: [1., 0., 0., 1.],
+                    shape: QUAD_SOLID,
+                },
+                Quad {
+                    pos: [10., 0.],
+                    size: [10., 20.],
+                    color: [0., 1., 0., 0.5],
+                    shape: QUAD_SOLID,
+                },
+                Quad {
+                    pos: [0., 18.],
+                    size: [30., 4.],
+                    color: [1., 1., 0., 1.],
+                    shape: QUAD_CURLY,
+                },
+            ]
+        );
+
+        batches.reset();
+        list.clear();
+        batches.build_display_list(&mut list);
+        assert!(list.quads.is_empty());
     }
 }
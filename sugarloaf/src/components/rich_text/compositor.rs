@@ -9,8 +9,11 @@
 // Eventually the file had updates to support other features like background-color,
 // text color, underline color and etc.
 
-use crate::components::rich_text::batch::BatchManager;
-pub use crate::components::rich_text::batch::{DisplayList, Rect, Vertex};
+use crate::components::rich_text::batch::{
+    BatchManager, QUAD_CURLY, QUAD_DASHED, QUAD_DOTTED,
+};
+pub use crate::components::rich_text::batch::{DisplayList, Quad, Rect, Vertex};
+use crate::components::rich_text::drawable_char;
 use crate::components::rich_text::image_cache::glyph::GlyphCacheSession;
 use crate::components::rich_text::image_cache::ImageCache;
 pub use crate::components::rich_text::image_cache::ImageId;
@@ -84,10 +87,10 @@ impl Compositor {
     // images.deallocate(image).is_some()
     // }
 
-    /// Draws a rectangle with the specified depth and color.
+    /// Draws a rectangle with the specified color.
     #[allow(unused)]
-    pub fn draw_rect(&mut self, rect: impl Into<Rect>, depth: f32, color: &[f32; 4]) {
-        self.batches.add_rect(&rect.into(), depth, color);
+    pub fn draw_rect(&mut self, rect: impl Into<Rect>, color: &[f32; 4]) {
+        self.batches.add_rect(&rect.into(), color);
     }
 
     /// Draws an image with the specified rectangle, depth and color.
@@ -153,19 +156,16 @@ impl Compositor {
                 is_doubled: info.is_doubled,
                 shape: info.shape,
             }),
-            Some(FragmentStyleDecoration::Strikethrough) => Some(RunUnderline {
-                enabled: true,
-                offset: (style.line_height / 3.5).round() as i32,
-                size: 2.0,
-                color: style.decoration_color.unwrap_or(style.color),
-                is_doubled: false,
-                shape: UnderlineShape::Regular,
-            }),
             _ => None,
         };
 
         let subpx_bias = (0.125, 0.);
         let color = style.color;
+        // Glyphs are drawn over the block cursor or the cell background.
+        let background = match style.cursor {
+            Some(SugarCursor::Block(cursor_color)) => cursor_color,
+            _ => style.background_color.unwrap_or([0.; 4]),
+        };
 
         for g in glyphs {
             let glyph = g.borrow();
@@ -173,13 +173,15 @@ impl Compositor {
             if let Some(entry) = entry {
                 if let Some(img) = session.get_image(entry.image) {
                     let gx = (glyph.x + subpx_bias.0).floor() + entry.left as f32;
-                    let gy = (glyph.y + subpx_bias.1).floor() - entry.top as f32;
+                    let gy = (glyph.y + subpx_bias.1).floor()
+                        - entry.top as f32 * style.vertical_scale;
+                    let height = entry.height as f32 * style.vertical_scale;
 
                     if entry.is_bitmap {
                         let color = [1.0, 1.0, 1.0, 1.0];
                         let coords = [img.min.0, img.min.1, img.max.0, img.max.1];
                         self.batches.add_image_rect(
-                            &Rect::new(gx, gy, entry.width as f32, entry.height as f32),
+                            &Rect::new(gx, gy, entry.width as f32, height),
                             depth,
                             &color,
                             &coords,
@@ -188,11 +190,12 @@ impl Compositor {
                     } else {
                         let coords = [img.min.0, img.min.1, img.max.0, img.max.1];
                         self.batches.add_mask_rect(
-                            &Rect::new(gx, gy, entry.width as f32, entry.height as f32),
+                            &Rect::new(gx, gy, entry.width as f32, height),
                             depth,
                             &color,
                             &coords,
                             true,
+                            &background,
                         );
                     }
                 }
@@ -202,7 +205,6 @@ impl Compositor {
         if let Some(bg_color) = style.background_color {
             self.batches.add_rect(
                 &Rect::new(rect.x, style.topline, rect.width, style.line_height),
-                depth,
                 &bg_color,
             );
         }
@@ -211,18 +213,44 @@ impl Compositor {
             Some(SugarCursor::Block(cursor_color)) => {
                 self.batches.add_rect(
                     &Rect::new(rect.x, style.topline, rect.width, style.line_height),
-                    depth,
                     &cursor_color,
                 );
             }
-            Some(SugarCursor::Caret(cursor_color)) => {
+            Some(SugarCursor::Caret(cursor_color, width)) => {
                 self.batches.add_rect(
-                    &Rect::new(rect.x, style.topline, 3.0, style.line_height),
-                    depth,
+                    &Rect::new(rect.x, style.topline, width, style.line_height),
                     &cursor_color,
                 );
             }
-            _ => {}
+            Some(SugarCursor::Underline(cursor_color, height)) => {
+                self.batches.add_rect(
+                    &Rect::new(
+                        rect.x,
+                        style.topline + style.line_height - height,
+                        rect.width,
+                        height,
+                    ),
+                    &cursor_color,
+                );
+            }
+            None => {}
+        }
+
+        if let Some(drawable_char) = style.drawable_char {
+            let cells = (rect.width / style.cell_width).round() as usize;
+            for cell in 0..cells {
+                let cell = Rect::new(
+                    rect.x + cell as f32 * style.cell_width,
+                    style.topline,
+                    style.cell_width,
+                    style.line_height,
+                );
+                for piece in drawable_char::pieces(drawable_char, cell) {
+                    let color = [color[0], color[1], color[2], color[3] * piece.opacity];
+                    self.batches
+                        .add_shaped_rect(&piece.rect, &color, piece.shape);
+                }
+            }
         }
 
         if let Some(underline) = underline {
@@ -231,11 +259,37 @@ impl Compositor {
                 rect.x,
                 rect.width,
                 style.baseline,
-                depth,
                 style.line_height,
             );
         }
 
+        // Fonts without the metrics fall back to a line at a third of the
+        // line height above the baseline.
+        if let Some((offset, size)) = style.strikethrough {
+            let offset = if offset > 0. {
+                offset
+            } else {
+                style.line_height / 3.5
+            };
+            let size = size.round().max(1.);
+            self.batches.add_rect(
+                &Rect::new(
+                    rect.x,
+                    (style.baseline - offset - size / 2.).round(),
+                    rect.width,
+                    size,
+                ),
+                &style.color,
+            );
+        }
+
+        if let Some(size) = style.overline {
+            self.batches.add_rect(
+                &Rect::new(rect.x, style.topline, rect.width, size.round().max(1.)),
+                &style.color,
+            );
+        }
+
         // let duration = start.elapsed();
         // println!(" - draw_glyphs() is: {:?}", duration);
     }
@@ -247,7 +301,6 @@ impl Compositor {
         x: f32,
         advance: f32,
         baseline: f32,
-        depth: f32,
         line_height: f32,
     ) {
         if underline.enabled {
@@ -259,7 +312,6 @@ impl Compositor {
                     UnderlineShape::Regular => {
                         self.batches.add_rect(
                             &Rect::new(ux, uy, end - ux, underline.size),
-                            depth,
                             &underline.color,
                         );
                         if underline.is_doubled {
@@ -270,66 +322,34 @@ impl Compositor {
                                     end - ux,
                                     underline.size,
                                 ),
-                                depth,
                                 &underline.color,
                             );
                         }
                     }
                     UnderlineShape::Dashed => {
-                        let mut start = ux;
-                        while start < end {
-                            start = start.min(end);
-                            self.batches.add_rect(
-                                &Rect::new(start, uy, 6.0, underline.size),
-                                depth,
-                                &underline.color,
-                            );
-                            start += 8.0;
-                        }
+                        self.batches.add_shaped_rect(
+                            &Rect::new(ux, uy, end - ux, underline.size),
+                            &underline.color,
+                            QUAD_DASHED,
+                        );
                     }
                     UnderlineShape::Dotted => {
-                        let mut start = ux;
-                        while start < end {
-                            start = start.min(end);
-                            self.batches.add_rect(
-                                &Rect::new(start, uy, 2.0, underline.size),
-                                depth,
-                                &underline.color,
-                            );
-                            start += 4.0;
-                        }
+                        self.batches.add_shaped_rect(
+                            &Rect::new(ux, uy, end - ux, underline.size),
+                            &underline.color,
+                            QUAD_DOTTED,
+                        );
                     }
                     UnderlineShape::Curly => {
-                        let style_line_height = (line_height / 10.).clamp(2.0, 16.0);
-                        let size = (style_line_height / 1.5).clamp(1.0, 4.0);
-                        let offset = style_line_height * 1.6;
-
-                        let mut curly_width = ux;
-                        let mut rect_width = 1.0f32.min(end - curly_width);
-
-                        while curly_width < end {
-                            rect_width = rect_width.min(end - curly_width);
-
-                            let dot_bottom_offset = match curly_width as u32 % 8 {
-                                3..=5 => offset + style_line_height,
-                                2 | 6 => offset + 2.0 * style_line_height / 3.0,
-                                1 | 7 => offset + 1.0 * style_line_height / 3.0,
-                                _ => offset,
-                            };
-
-                            self.batches.add_rect(
-                                &Rect::new(
-                                    curly_width,
-                                    uy - (dot_bottom_offset - offset),
-                                    rect_width,
-                                    size,
-                                ),
-                                depth,
-                                &underline.color,
-                            );
-
-                            curly_width += rect_width;
-                        }
+                        // The wave is drawn by the quad shader between the
+                        // underline position and `amplitude` above it.
+                        let amplitude = (line_height / 10.).clamp(2.0, 16.0);
+                        let size = (amplitude / 1.5).clamp(1.0, 4.0);
+                        self.batches.add_shaped_rect(
+                            &Rect::new(ux, uy - amplitude, end - ux, amplitude + size),
+                            &underline.color,
+                            QUAD_CURLY,
+                        );
                     }
                 }
             }
@@ -0,0 +1,408 @@
+// Box drawing (U+2500 to U+257F), block elements (U+2580 to U+259F),
+// braille (U+2800 to U+28FF) and Powerline triangles are drawn with quads
+// filling the cell, instead of glyphs from the font. Glyphs rarely match
+// the cell size, which leaves gaps between lines and misaligned separators.
+//
+// Arcs and diagonals are left to the font.
+
+use crate::components::rich_text::batch::{
+    Rect, QUAD_SOLID, QUAD_TRIANGLE_LEFT, QUAD_TRIANGLE_LOWER_LEFT,
+    QUAD_TRIANGLE_LOWER_RIGHT, QUAD_TRIANGLE_RIGHT, QUAD_TRIANGLE_UPPER_LEFT,
+    QUAD_TRIANGLE_UPPER_RIGHT,
+};
+
+// Weight of a line going from the center to an edge of the cell.
+const NONE: u8 = 0;
+const LIGHT: u8 = 1;
+const HEAVY: u8 = 2;
+const DOUBLE: u8 = 3;
+
+// Up, right, down and left lines for U+2500 to U+257F, dashed lines are
+// set apart in `dashes`, arcs and diagonals are all `NONE`.
+#[rustfmt::skip]
+const LINES: [[u8; 4]; 128] = [
+    // ─ ━ │ ┃
+    [0, 1, 0, 1], [0, 2, 0, 2], [1, 0, 1, 0], [2, 0, 2, 0],
+    // ┄ ┅ ┆ ┇ ┈ ┉ ┊ ┋
+    [0, 1, 0, 1], [0, 2, 0, 2], [1, 0, 1, 0], [2, 0, 2, 0],
+    [0, 1, 0, 1], [0, 2, 0, 2], [1, 0, 1, 0], [2, 0, 2, 0],
+    // ┌ ┍ ┎ ┏
+    [0, 1, 1, 0], [0, 2, 1, 0], [0, 1, 2, 0], [0, 2, 2, 0],
+    // ┐ ┑ ┒ ┓
+    [0, 0, 1, 1], [0, 0, 1, 2], [0, 0, 2, 1], [0, 0, 2, 2],
+    // └ ┕ ┖ ┗
+    [1, 1, 0, 0], [1, 2, 0, 0], [2, 1, 0, 0], [2, 2, 0, 0],
+    // ┘ ┙ ┚ ┛
+    [1, 0, 0, 1], [1, 0, 0, 2], [2, 0, 0, 1], [2, 0, 0, 2],
+    // ├ ┝ ┞ ┟ ┠ ┡ ┢ ┣
+    [1, 1, 1, 0], [1, 2, 1, 0], [2, 1, 1, 0], [1, 1, 2, 0],
+    [2, 1, 2, 0], [2, 2, 1, 0], [1, 2, 2, 0], [2, 2, 2, 0],
+    // ┤ ┥ ┦ ┧ ┨ ┩ ┪ ┫
+    [1, 0, 1, 1], [1, 0, 1, 2], [2, 0, 1, 1], [1, 0, 2, 1],
+    [2, 0, 2, 1], [2, 0, 1, 2], [1, 0, 2, 2], [2, 0, 2, 2],
+    // ┬ ┭ ┮ ┯ ┰ ┱ ┲ ┳
+    [0, 1, 1, 1], [0, 1, 1, 2], [0, 2, 1, 1], [0, 2, 1, 2],
+    [0, 1, 2, 1], [0, 1, 2, 2], [0, 2, 2, 1], [0, 2, 2, 2],
+    // ┴ ┵ ┶ ┷ ┸ ┹ ┺ ┻
+    [1, 1, 0, 1], [1, 1, 0, 2], [1, 2, 0, 1], [1, 2, 0, 2],
+    [2, 1, 0, 1], [2, 1, 0, 2], [2, 2, 0, 1], [2, 2, 0, 2],
+    // ┼ ┽ ┾ ┿ ╀ ╁ ╂ ╃
+    [1, 1, 1, 1], [1, 1, 1, 2], [1, 2, 1, 1], [1, 2, 1, 2],
+    [2, 1, 1, 1], [1, 1, 2, 1], [2, 1, 2, 1], [2, 1, 1, 2],
+    // ╄ ╅ ╆ ╇ ╈ ╉ ╊ ╋
+    [2, 2, 1, 1], [1, 1, 2, 2], [1, 2, 2, 1], [2, 2, 1, 2],
+    [1, 2, 2, 2], [2, 1, 2, 2], [2, 2, 2, 1], [2, 2, 2, 2],
+    // ╌ ╍ ╎ ╏
+    [0, 1, 0, 1], [0, 2, 0, 2], [1, 0, 1, 0], [2, 0, 2, 0],
+    // ═ ║ ╒ ╓ ╔ ╕ ╖ ╗
+    [0, 3, 0, 3], [3, 0, 3, 0], [0, 3, 1, 0], [0, 1, 3, 0],
+    [0, 3, 3, 0], [0, 0, 1, 3], [0, 0, 3, 1], [0, 0, 3, 3],
+    // ╘ ╙ ╚ ╛ ╜ ╝ ╞ ╟
+    [1, 3, 0, 0], [3, 1, 0, 0], [3, 3, 0, 0], [1, 0, 0, 3],
+    [3, 0, 0, 1], [3, 0, 0, 3], [1, 3, 1, 0], [3, 1, 3, 0],
+    // ╠ ╡ ╢ ╣ ╤ ╥ ╦ ╧
+    [3, 3, 3, 0], [1, 0, 1, 3], [3, 0, 3, 1], [3, 0, 3, 3],
+    [0, 3, 1, 3], [0, 1, 3, 1], [0, 3, 3, 3], [1, 3, 0, 3],
+    // ╨ ╩ ╪ ╫ ╬
+    [3, 1, 0, 1], [3, 3, 0, 3], [1, 3, 1, 3], [3, 1, 3, 1], [3, 3, 3, 3],
+    // ╭ ╮ ╯ ╰ ╱ ╲ ╳
+    [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0],
+    [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0],
+    // ╴ ╵ ╶ ╷ ╸ ╹ ╺ ╻
+    [0, 0, 0, 1], [1, 0, 0, 0], [0, 1, 0, 0], [0, 0, 1, 0],
+    [0, 0, 0, 2], [2, 0, 0, 0], [0, 2, 0, 0], [0, 0, 2, 0],
+    // ╼ ╽ ╾ ╿
+    [0, 2, 0, 1], [1, 0, 2, 0], [0, 1, 0, 2], [2, 0, 1, 0],
+];
+
+/// Character drawn with quads instead of a glyph.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct DrawableChar(char);
+
+impl DrawableChar {
+    /// `None` for characters drawn from the font.
+    #[inline]
+    pub fn new(c: char) -> Option<Self> {
+        let is_drawable = match c as u32 {
+            code @ 0x2500..=0x257F => LINES[code as usize - 0x2500] != [NONE; 4],
+            0x2580..=0x259F | 0x2800..=0x28FF => true,
+            0xE0B0 | 0xE0B2 | 0xE0B8 | 0xE0BA | 0xE0BC | 0xE0BE => true,
+            _ => false,
+        };
+        is_drawable.then_some(Self(c))
+    }
+}
+
+/// Quad of a drawable char.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Piece {
+    pub rect: Rect,
+    /// One of the `QUAD_*` shapes.
+    pub shape: u32,
+    /// Multiplies the alpha of the text color, for shades.
+    pub opacity: f32,
+}
+
+impl Piece {
+    #[inline]
+    fn solid(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Self {
+            rect: Rect::new(x, y, width, height),
+            shape: QUAD_SOLID,
+            opacity: 1.,
+        }
+    }
+}
+
+/// Quads to draw `c` in the cell, `cell` is the position and size of
+/// the cell.
+pub fn pieces(c: DrawableChar, cell: Rect) -> Vec<Piece> {
+    let mut pieces = Vec::new();
+    let Rect {
+        x,
+        y,
+        width,
+        height,
+    } = cell;
+    match c.0 as u32 {
+        code @ 0x2500..=0x257F => {
+            let index = code as usize - 0x2500;
+            match dashes(code) {
+                Some(count) => dashed_line(&mut pieces, cell, LINES[index], count),
+                None => lines(&mut pieces, cell, LINES[index]),
+            }
+        }
+        // Upper half and lower eighths
+        0x2580 => pieces.push(Piece::solid(x, y, width, (height / 2.).round())),
+        code @ 0x2581..=0x2588 => {
+            let size = (height * (code - 0x2580) as f32 / 8.).round();
+            pieces.push(Piece::solid(x, y + height - size, width, size));
+        }
+        // Left eighths
+        code @ 0x2589..=0x258F => {
+            let size = (width * (0x2590 - code) as f32 / 8.).round();
+            pieces.push(Piece::solid(x, y, size, height));
+        }
+        0x2590 => {
+            let size = (width / 2.).round();
+            pieces.push(Piece::solid(x + size, y, width - size, height));
+        }
+        // Shades
+        code @ 0x2591..=0x2593 => pieces.push(Piece {
+            opacity: (code - 0x2590) as f32 / 4.,
+            ..Piece::solid(x, y, width, height)
+        }),
+        0x2594 => pieces.push(Piece::solid(x, y, width, (height / 8.).round())),
+        0x2595 => {
+            let size = (width / 8.).round();
+            pieces.push(Piece::solid(x + width - size, y, size, height));
+        }
+        code @ 0x2596..=0x259F => {
+            // Upper left, upper right, lower left and lower right.
+            const QUADRANTS: [u8; 10] = [
+                0b0100, 0b1000, 0b0001, 0b1101, 0b1001, 0b0111, 0b1011, 0b0010, 0b0110,
+                0b1110,
+            ];
+            let half_width = (width / 2.).round();
+            let half_height = (height / 2.).round();
+            let quadrants = QUADRANTS[(code - 0x2596) as usize];
+            let areas = [
+                (x, y, half_width, half_height),
+                (x + half_width, y, width - half_width, half_height),
+                (x, y + half_height, half_width, height - half_height),
+                (
+                    x + half_width,
+                    y + half_height,
+                    width - half_width,
+                    height - half_height,
+                ),
+            ];
+            for (i, (x, y, width, height)) in areas.into_iter().enumerate() {
+                if quadrants & (1 << i) != 0 {
+                    pieces.push(Piece::solid(x, y, width, height));
+                }
+            }
+        }
+        code @ 0x2800..=0x28FF => braille(&mut pieces, cell, (code - 0x2800) as u8),
+        code => {
+            let shape = match code {
+                0xE0B0 => QUAD_TRIANGLE_RIGHT,
+                0xE0B2 => QUAD_TRIANGLE_LEFT,
+                0xE0B8 => QUAD_TRIANGLE_LOWER_LEFT,
+                0xE0BA => QUAD_TRIANGLE_LOWER_RIGHT,
+                0xE0BC => QUAD_TRIANGLE_UPPER_LEFT,
+                _ => QUAD_TRIANGLE_UPPER_RIGHT,
+            };
+            pieces.push(Piece {
+                shape,
+                ..Piece::solid(x, y, width, height)
+            });
+        }
+    }
+    pieces
+}
+
+/// Number of dashes of the dashed lines.
+#[inline]
+fn dashes(code: u32) -> Option<usize> {
+    match code {
+        0x2504..=0x2507 => Some(3),
+        0x2508..=0x250B => Some(4),
+        0x254C..=0x254F => Some(2),
+        _ => None,
+    }
+}
+
+/// Thickness of light lines, heavy lines are twice as thick.
+#[inline]
+fn light_thickness(cell: Rect) -> f32 {
+    (cell.width.min(cell.height) / 8.).round().max(1.)
+}
+
+#[inline]
+fn thickness(cell: Rect, weight: u8) -> f32 {
+    let light = light_thickness(cell);
+    match weight {
+        LIGHT | DOUBLE => light,
+        HEAVY => light * 2.,
+        _ => 0.,
+    }
+}
+
+fn lines(pieces: &mut Vec<Piece>, cell: Rect, lines: [u8; 4]) {
+    let [up, right, down, left] = lines;
+    let light = light_thickness(cell);
+    // Lines of a double line are a light line apart.
+    let double_offset = light;
+    let center_x = cell.x + (cell.width / 2.).round();
+    let center_y = cell.y + (cell.height / 2.).round();
+
+    // Lines go past the center to cover the lines across them.
+    let across = |weights: [u8; 2]| {
+        weights
+            .iter()
+            .map(|weight| match *weight {
+                NONE => 0.,
+                DOUBLE => double_offset + light / 2.,
+                weight => thickness(cell, weight) / 2.,
+            })
+            .fold(0., f32::max)
+    };
+    let vertical_extent = across([left, right]);
+    let horizontal_extent = across([up, down]);
+
+    let mut horizontal = |weight: u8, from: f32, to: f32| {
+        let offsets: &[f32] = match weight {
+            NONE => &[],
+            DOUBLE => &[-double_offset, double_offset],
+            _ => &[0.],
+        };
+        let size = thickness(cell, weight);
+        for offset in offsets {
+            let y = (center_y + offset - size / 2.).round();
+            pieces.push(Piece::solid(from, y, to - from, size));
+        }
+    };
+    horizontal(left, cell.x, center_x + horizontal_extent);
+    horizontal(right, center_x - horizontal_extent, cell.x + cell.width);
+
+    let mut vertical = |weight: u8, from: f32, to: f32| {
+        let offsets: &[f32] = match weight {
+            NONE => &[],
+            DOUBLE => &[-double_offset, double_offset],
+            _ => &[0.],
+        };
+        let size = thickness(cell, weight);
+        for offset in offsets {
+            let x = (center_x + offset - size / 2.).round();
+            pieces.push(Piece::solid(x, from, size, to - from));
+        }
+    };
+    vertical(up, cell.y, center_y + vertical_extent);
+    vertical(down, center_y - vertical_extent, cell.y + cell.height);
+}
+
+fn dashed_line(pieces: &mut Vec<Piece>, cell: Rect, lines: [u8; 4], count: usize) {
+    let is_horizontal = lines[1] != NONE;
+    let weight = lines[0].max(lines[1]);
+    let size = thickness(cell, weight);
+    let length = if is_horizontal {
+        cell.width
+    } else {
+        cell.height
+    };
+    // Dashes are centered in their part of the cell, with half a gap
+    // on both sides so dashes of cells next to each other are even.
+    let part = length / count as f32;
+    let gap = (part / 4.).round().max(1.);
+    for i in 0..count {
+        let start = (i as f32 * part + gap / 2.).round();
+        let end = ((i + 1) as f32 * part - gap / 2.).round();
+        if is_horizontal {
+            let y = (cell.y + cell.height / 2. - size / 2.).round();
+            pieces.push(Piece::solid(cell.x + start, y, end - start, size));
+        } else {
+            let x = (cell.x + cell.width / 2. - size / 2.).round();
+            pieces.push(Piece::solid(x, cell.y + start, size, end - start));
+        }
+    }
+}
+
+/// Dots of a braille pattern, in two columns of four.
+fn braille(pieces: &mut Vec<Piece>, cell: Rect, dots: u8) {
+    // Bits of dots 1 to 8 by row and column.
+    const DOTS: [[u8; 2]; 4] = [[0, 3], [1, 4], [2, 5], [6, 7]];
+    let size = (cell.width / 4.).min(cell.height / 8.).round().max(1.);
+    for (row, bits) in DOTS.iter().enumerate() {
+        for (column, bit) in bits.iter().enumerate() {
+            if dots & (1 << bit) == 0 {
+                continue;
+            }
+            let center_x = cell.x + cell.width * (2 * column + 1) as f32 / 4.;
+            let center_y = cell.y + cell.height * (2 * row + 1) as f32 / 8.;
+            pieces.push(Piece::solid(
+                (center_x - size / 2.).round(),
+                (center_y - size / 2.).round(),
+                size,
+                size,
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CELL: Rect = Rect {
+        x: 10.,
+        y: 20.,
+        width: 8.,
+        height: 16.,
+    };
+
+    fn areas(c: char) -> Vec<(f32, f32, f32, f32)> {
+        pieces(DrawableChar::new(c).unwrap(), CELL)
+            .iter()
+            .map(|piece| {
+                let Rect {
+                    x,
+                    y,
+                    width,
+                    height,
+                } = piece.rect;
+                (x, y, width, height)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_drawable_chars() {
+        assert!(DrawableChar::new('─').is_some());
+        assert!(DrawableChar::new('▒').is_some());
+        assert!(DrawableChar::new('⣿').is_some());
+        assert!(DrawableChar::new('\u{E0B0}').is_some());
+        // Arcs, diagonals and text use the font
+        assert!(DrawableChar::new('╭').is_none());
+        assert!(DrawableChar::new('╱').is_none());
+        assert!(DrawableChar::new('a').is_none());
+    }
+
+    #[test]
+    fn test_lines_fill_the_cell() {
+        // Lines reach the edges, so cells next to each other join
+        assert_eq!(areas('─'), vec![(10., 28., 4., 1.), (14., 28., 4., 1.)]);
+        assert_eq!(areas('│'), vec![(14., 20., 1., 8.), (14., 28., 1., 8.)]);
+        // Heavy lines cover each other at the corner
+        assert_eq!(areas('┏'), vec![(13., 27., 5., 2.), (13., 27., 2., 9.)]);
+        // Both lines of a double line
+        assert_eq!(areas('═').len(), 4);
+    }
+
+    #[test]
+    fn test_blocks() {
+        assert_eq!(areas('█'), vec![(10., 20., 8., 16.)]);
+        assert_eq!(areas('▄'), vec![(10., 28., 8., 8.)]);
+        assert_eq!(areas('▌'), vec![(10., 20., 4., 16.)]);
+        assert_eq!(areas('▐'), vec![(14., 20., 4., 16.)]);
+        assert_eq!(areas('▚'), vec![(10., 20., 4., 8.), (14., 28., 4., 8.)]);
+
+        let shade = pieces(DrawableChar::new('░').unwrap(), CELL);
+        assert_eq!(shade[0].opacity, 0.25);
+    }
+
+    #[test]
+    fn test_braille() {
+        assert!(areas('\u{2800}').is_empty());
+        assert_eq!(areas('⠁'), vec![(11., 21., 2., 2.)]);
+        assert_eq!(areas('⣿').len(), 8);
+    }
+
+    #[test]
+    fn test_powerline() {
+        let triangle = pieces(DrawableChar::new('\u{E0B0}').unwrap(), CELL);
+        assert_eq!(triangle.len(), 1);
+        assert_eq!(triangle[0].shape, QUAD_TRIANGLE_RIGHT);
+        assert_eq!(triangle[0].rect.width, CELL.width);
+    }
+}
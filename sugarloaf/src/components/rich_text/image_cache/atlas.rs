@@ -21,6 +21,12 @@ impl AtlasAllocator {
         }
     }
 
+    /// Extends the atlas to the specified height, existing
+    /// allocations keep their position.
+    pub fn grow(&mut self, height: u16) {
+        self.height = self.height.max(height);
+    }
+
     /// Allocates a rectangle in the atlas if possible. Returns the x and y
     /// coordinates of the allocated slot.
     pub fn allocate(&mut self, width: u16, height: u16) -> Option<(u16, u16)> {
@@ -1,3 +1,4 @@
+use crate::components::core::staging::{StagingRing, CHUNK_SIZE};
 use crate::context::Context;
 
 use super::atlas::*;
@@ -6,6 +7,11 @@ use super::*;
 #[derive(Default)]
 pub struct Entry {
     allocated: bool,
+    /// Bumped whenever the entry is freed, so handles to an evicted
+    /// image don't resolve to the image reusing its slot.
+    generation: u8,
+    /// Last frame the image was drawn in.
+    last_used: u64,
     /// X coordinate of the image in an atlas.
     x: u16,
     /// Y coordinate of the image in an atlas.
@@ -16,19 +22,200 @@ pub struct Entry {
     height: u16,
 }
 
+/// Region of the atlas that changed since the last upload.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct DirtyRect {
+    x: u16,
+    y: u16,
+    width: u16,
+    height: u16,
+}
+
+impl DirtyRect {
+    #[inline]
+    fn overlaps_rows(&self, other: &DirtyRect) -> bool {
+        self.y < other.y + other.height && other.y < self.y + self.height
+    }
+
+    #[inline]
+    fn union(&self, other: &DirtyRect) -> DirtyRect {
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        let right = (self.x + self.width).max(other.x + other.width);
+        let bottom = (self.y + self.height).max(other.y + other.height);
+        DirtyRect {
+            x,
+            y,
+            width: right - x,
+            height: bottom - y,
+        }
+    }
+}
+
+/// CPU side of the glyph atlas. It starts small and doubles its height
+/// when full, once it can't grow anymore the least recently drawn images
+/// are evicted to make room.
 pub struct Atlas {
     alloc: AtlasAllocator,
     buffer: Vec<u8>,
-    fresh: bool,
-    dirty: bool,
+    /// Pending updates, glyphs allocated in the same shelf share the
+    /// same rows so they are batched into a single copy.
+    dirty: Vec<DirtyRect>,
+    entries: Vec<Entry>,
+    free_entries: Vec<u32>,
+    width: u16,
+    height: u16,
+    max_height: u16,
+    epoch: u64,
+}
+
+impl Atlas {
+    fn new(width: u16, height: u16, max_height: u16) -> Self {
+        Self {
+            alloc: AtlasAllocator::new(width, height),
+            buffer: vec![0u8; width as usize * height as usize * 4],
+            dirty: Vec::new(),
+            entries: Vec::new(),
+            free_entries: Vec::new(),
+            width,
+            height,
+            max_height: max_height.max(height),
+            epoch: 0,
+        }
+    }
+
+    fn mark_dirty(&mut self, rect: DirtyRect) {
+        for dirty in self.dirty.iter_mut() {
+            if dirty.overlaps_rows(&rect) {
+                *dirty = dirty.union(&rect);
+                return;
+            }
+        }
+        self.dirty.push(rect);
+    }
+
+    fn allocate(&mut self, request: AddImage) -> Option<ImageId> {
+        let width = request.width;
+        let height = request.height;
+
+        // Check buffer size
+        buffer_size(width as u32, height as u32)?;
+
+        // Too big to allocate
+        if !(width <= self.width && height <= (self.width / 4)) {
+            return None;
+        }
+
+        let (x, y) = self.allocate_rect(width, height)?;
+        let entry = Entry {
+            allocated: true,
+            generation: 0,
+            last_used: self.epoch,
+            x,
+            y,
+            width,
+            height,
+        };
+        let (index, generation) = match self.free_entries.pop() {
+            Some(index) => {
+                let slot = &mut self.entries[index as usize];
+                let generation = slot.generation;
+                *slot = Entry {
+                    generation,
+                    ..entry
+                };
+                (index, generation)
+            }
+            None => {
+                self.entries.push(entry);
+                (self.entries.len() as u32 - 1, 0)
+            }
+        };
+        if let Some(data) = request.data() {
+            fill(x, y, width, height, data, self.width, &mut self.buffer);
+            self.mark_dirty(DirtyRect {
+                x,
+                y,
+                width,
+                height,
+            });
+        }
+        ImageId::new(index, request.has_alpha, generation)
+    }
+
+    fn allocate_rect(&mut self, width: u16, height: u16) -> Option<(u16, u16)> {
+        loop {
+            if let Some(position) = self.alloc.allocate(width, height) {
+                return Some(position);
+            }
+            if self.height >= self.max_height {
+                break;
+            }
+            self.grow();
+        }
+
+        // Images drawn in the current frame are kept, their
+        // coordinates are already part of the frame.
+        let mut stale: Vec<usize> = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| entry.allocated && entry.last_used < self.epoch)
+            .map(|(index, _)| index)
+            .collect();
+        stale.sort_unstable_by_key(|index| self.entries[*index].last_used);
+
+        for index in stale {
+            self.evict(index);
+            if let Some(position) = self.alloc.allocate(width, height) {
+                return Some(position);
+            }
+        }
+
+        None
+    }
+
+    fn grow(&mut self) {
+        let height = self.height.saturating_mul(2).min(self.max_height);
+        self.alloc.grow(height);
+        self.buffer
+            .resize(self.width as usize * height as usize * 4, 0);
+        self.height = height;
+    }
+
+    fn evict(&mut self, index: usize) {
+        let entry = &mut self.entries[index];
+        self.alloc.deallocate(entry.x, entry.y, entry.width);
+        entry.allocated = false;
+        entry.generation = entry.generation.wrapping_add(1);
+        self.free_entries.push(index as u32);
+    }
+
+    fn entry(&self, image: ImageId) -> Option<&Entry> {
+        self.entries
+            .get(image.index())
+            .filter(|entry| entry.allocated && entry.generation == image.generation())
+    }
+
+    fn clear(&mut self) {
+        self.alloc = AtlasAllocator::new(self.width, self.height);
+        for (index, entry) in self.entries.iter_mut().enumerate() {
+            if entry.allocated {
+                entry.allocated = false;
+                entry.generation = entry.generation.wrapping_add(1);
+                self.free_entries.push(index as u32);
+            }
+        }
+    }
 }
 
 pub struct ImageCache {
-    pub entries: Vec<Entry>,
     atlas: Atlas,
-    max_texture_size: u16,
     texture: wgpu::Texture,
+    texture_height: u16,
     pub texture_view: wgpu::TextureView,
+    generation: usize,
+    staging: StagingRing,
 }
 
 #[inline]
@@ -39,243 +226,161 @@ pub fn buffer_size(width: u32, height: u32) -> Option<usize> {
 }
 
 pub const SIZE: u16 = 2048;
+/// Height the atlas starts with, it grows on demand up to the
+/// device limit (capped to `MAX_SIZE`).
+const INITIAL_HEIGHT: u16 = 1024;
+const MAX_SIZE: u32 = 8192;
+
+fn create_texture(device: &wgpu::Device, width: u16, height: u16) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("rich_text create texture"),
+        size: wgpu::Extent3d {
+            width: width as u32,
+            height: height as u32,
+            depth_or_array_layers: 1,
+        },
+        view_formats: &[],
+        dimension: wgpu::TextureDimension::D2,
+        format: wgpu::TextureFormat::Rgba8Unorm,
+        usage: wgpu::TextureUsages::COPY_DST
+            | wgpu::TextureUsages::COPY_SRC
+            | wgpu::TextureUsages::TEXTURE_BINDING,
+        mip_level_count: 1,
+        sample_count: 1,
+    })
+}
 
 impl ImageCache {
     /// Creates a new image cache.
     pub fn new(context: &Context) -> Self {
         let device = &context.device;
-        // let max_texture_size = max_texture_size.clamp(1024, 8192);
-        let max_texture_size = SIZE;
-
-        let texture = device.create_texture(&wgpu::TextureDescriptor {
-            label: Some("rich_text create texture"),
-            size: wgpu::Extent3d {
-                width: SIZE as u32,
-                height: SIZE as u32,
-                depth_or_array_layers: 1,
-            },
-            view_formats: &[],
-            dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Rgba8Unorm,
-            usage: wgpu::TextureUsages::COPY_DST | wgpu::TextureUsages::TEXTURE_BINDING,
-            mip_level_count: 1,
-            sample_count: 1,
-        });
-        let texture_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let max_height = device.limits().max_texture_dimension_2d.min(MAX_SIZE) as u16;
+        let atlas = Atlas::new(SIZE, INITIAL_HEIGHT.min(max_height), max_height);
 
-        let alloc = AtlasAllocator::new(max_texture_size, max_texture_size);
+        let texture = create_texture(device, atlas.width, atlas.height);
+        let texture_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
 
         Self {
-            entries: Vec::new(),
-            atlas: Atlas {
-                alloc,
-                buffer: vec![
-                    0u8;
-                    max_texture_size as usize * max_texture_size as usize * 4
-                ],
-                fresh: true,
-                dirty: true,
-            },
-            max_texture_size,
+            texture_height: atlas.height,
+            atlas,
             texture_view,
             texture,
+            generation: 0,
+            staging: StagingRing::new(CHUNK_SIZE),
         }
     }
 
     /// Allocates a new image and optionally fills it with the specified data.
+    #[inline]
     pub fn allocate(&mut self, request: AddImage) -> Option<ImageId> {
-        let width = request.width;
-        let height = request.height;
-
-        // Check buffer size
-        buffer_size(width as u32, height as u32)?;
-
-        // Too big to allocate
-        if !(width <= self.max_texture_size && height <= (self.max_texture_size / 4)) {
-            return None;
-        }
-
-        let atlas_data = self.atlas.alloc.allocate(width, height);
-        // if atlas_data.is_none() {
-        // return None;
-        // Grow atlas to fit
-        // self.max_texture_size += SIZE;
-        // self.atlas.fresh = true;
-        // self.atlas.dirty = true;
-        // self.entries.clear();
-        // println!("{:?}", self.max_texture_size);
-        // self.atlas.alloc = AtlasAllocator::new(self.max_texture_size, self.max_texture_size);
-        // self.atlas.buffer = vec![
-        //     0u8;
-        //     self.max_texture_size as usize * self.max_texture_size as usize * 4
-        // ];
-        // atlas_data = self.atlas.alloc.allocate(width, height);
-        // println!("{:?}", atlas_data);
-
-        // if self.max_texture_size > MAX_SIZE {
-        //     println!("should try to grow or reset atlas");
-        // }
-        // }
-        let (x, y) = atlas_data?;
-        let entry_index = self.entries.len();
-        self.entries.push(Entry {
-            allocated: true,
-            x,
-            y,
-            width,
-            height,
-        });
-        if let Some(data) = request.data() {
-            fill(
-                x,
-                y,
-                width,
-                height,
-                data,
-                self.max_texture_size,
-                &mut self.atlas.buffer,
-            );
-            self.atlas.dirty = true;
-        }
-        ImageId::new(entry_index as u32, request.has_alpha)
+        self.atlas.allocate(request)
     }
 
-    // Evaluate if does make sense to deallocate from atlas and if yes, which case?
-    // considering that a terminal uses a short/limited of glyphs compared to a wide text editor
-    // if deallocate an image then is necessary to cleanup cache of draw_layout fn
     /// Deallocates the specified image.
     #[allow(unused)]
     pub fn deallocate(&mut self, image: ImageId) -> Option<()> {
-        let entry = self.entries.get_mut(image.index())?;
-        if !entry.allocated {
-            return None;
-        }
-
-        self.atlas.alloc.deallocate(entry.x, entry.y, entry.width);
-        entry.allocated = false;
+        self.atlas.entry(image)?;
+        self.atlas.evict(image.index());
         Some(())
     }
 
-    /// Retrieves the image for the specified handle and updates the epoch.
+    /// Deallocates every image, the atlas keeps its current size.
+    pub fn clear(&mut self) {
+        self.atlas.clear();
+    }
+
+    /// Starts a new frame, images not drawn since become
+    /// candidates for eviction.
+    #[inline]
+    pub fn begin_frame(&mut self) {
+        self.atlas.epoch += 1;
+    }
+
+    /// Retrieves the location in pixels of the image for the specified handle.
     pub fn get(&self, handle: &ImageId) -> Option<ImageLocation> {
-        let entry = self.entries.get(handle.index())?;
-        if !entry.allocated {
-            return None;
-        }
-        let s = 1. / self.max_texture_size as f32;
+        let entry = self.atlas.entry(*handle)?;
         Some(ImageLocation {
-            min: (entry.x as f32 * s, entry.y as f32 * s),
+            min: (entry.x as f32, entry.y as f32),
             max: (
-                (entry.x + entry.width) as f32 * s,
-                (entry.y + entry.height) as f32 * s,
+                (entry.x + entry.width) as f32,
+                (entry.y + entry.height) as f32,
             ),
         })
     }
 
-    /// Returns true if the image is valid.
-    pub fn is_valid(&self, image: ImageId) -> bool {
-        if let Some(entry) = self.entries.get(image.index()) {
-            entry.allocated
-        } else {
-            false
+    /// Returns true if the image is valid and marks it as drawn
+    /// in the current frame.
+    #[inline]
+    pub fn touch(&mut self, image: ImageId) -> bool {
+        let epoch = self.atlas.epoch;
+        match self.atlas.entries.get_mut(image.index()) {
+            Some(entry) if entry.allocated && entry.generation == image.generation() => {
+                entry.last_used = epoch;
+                true
+            }
+            _ => false,
         }
     }
 
-    /// Updates an image with the specified data.
-    // pub fn update(&mut self, handle: ImageId, data: &[u8]) -> Option<()> {
-    //     let entry = self.entries.get_mut(handle.index())?;
-    //     if entry.flags & ENTRY_ALLOCATED == 0 {
-    //         return None;
-    //     }
-    //         let atlas = self.atlases.get_mut(entry.owner as usize)?;
-    //         fill(
-    //             entry.x,
-    //             entry.y,
-    //             entry.width,
-    //             entry.height,
-    //             data,
-    //             ATLAS_DIM,
-    //             &mut atlas.buffer,
-    //             4,
-    //         );
-    //         atlas.dirty = true;
-    //     Some(())
-    // }
-
+    /// Changes every time the texture is recreated.
     #[inline]
-    pub fn process_atlases(&mut self, context: &mut Context) {
-        if !self.atlas.dirty {
-            return;
-        }
-        if self.atlas.fresh {
-            let texture_size = wgpu::Extent3d {
-                width: (self.max_texture_size).into(),
-                height: (self.max_texture_size).into(),
-                depth_or_array_layers: 1,
-            };
-            let new_texture = context.device.create_texture(&wgpu::TextureDescriptor {
-                size: texture_size,
-                mip_level_count: 1,
-                sample_count: 1,
-                dimension: wgpu::TextureDimension::D2,
-                format: wgpu::TextureFormat::Rgba8Unorm,
-                usage: wgpu::TextureUsages::TEXTURE_BINDING
-                    | wgpu::TextureUsages::COPY_DST,
-                label: Some("rich_text::fresh atlas"),
-                view_formats: &[],
-            });
+    pub fn generation(&self) -> usize {
+        self.generation
+    }
 
-            context.queue.write_texture(
-                // Tells wgpu where to copy the pixel data
-                wgpu::ImageCopyTexture {
-                    texture: &new_texture,
-                    mip_level: 0,
-                    origin: wgpu::Origin3d::ZERO,
-                    aspect: wgpu::TextureAspect::All,
-                },
-                // The actual pixel data
-                &self.atlas.buffer,
-                // The layout of the texture
-                wgpu::ImageDataLayout {
-                    offset: 0,
-                    bytes_per_row: Some((self.max_texture_size * 4).into()),
-                    rows_per_image: Some((self.max_texture_size).into()),
+    /// Records the copies of the regions changed since the last call,
+    /// the texture itself is created zeroed so only glyphs are uploaded.
+    /// If the atlas grew, the texture is recreated and its previous
+    /// content is copied over on the GPU.
+    #[inline]
+    pub fn process_atlases(
+        &mut self,
+        context: &mut Context,
+        encoder: &mut wgpu::CommandEncoder,
+    ) {
+        if self.texture_height != self.atlas.height {
+            let texture =
+                create_texture(&context.device, self.atlas.width, self.atlas.height);
+            encoder.copy_texture_to_texture(
+                self.texture.as_image_copy(),
+                texture.as_image_copy(),
+                wgpu::Extent3d {
+                    width: self.atlas.width as u32,
+                    height: self.texture_height as u32,
+                    depth_or_array_layers: 1,
                 },
-                texture_size,
             );
+            self.texture_view =
+                texture.create_view(&wgpu::TextureViewDescriptor::default());
+            self.texture = texture;
+            self.texture_height = self.atlas.height;
+            self.generation += 1;
+        }
 
-            self.texture = new_texture;
-            self.texture_view = self
-                .texture
-                .create_view(&wgpu::TextureViewDescriptor::default());
-        } else {
-            let texture_size = wgpu::Extent3d {
-                width: (self.max_texture_size).into(),
-                height: (self.max_texture_size).into(),
-                depth_or_array_layers: 1,
-            };
-
-            context.queue.write_texture(
-                // Tells wgpu where to copy the pixel data
-                wgpu::ImageCopyTexture {
-                    texture: &self.texture,
-                    mip_level: 0,
-                    origin: wgpu::Origin3d { x: 0, y: 0, z: 0 },
-                    aspect: wgpu::TextureAspect::All,
-                },
-                // The actual pixel data
+        if self.atlas.dirty.is_empty() {
+            return;
+        }
+
+        for rect in self.atlas.dirty.drain(..) {
+            self.staging.write_texture(
+                &context.device,
+                encoder,
+                &self.texture,
+                rect.x.into(),
+                rect.y.into(),
+                rect.width.into(),
+                rect.height.into(),
                 &self.atlas.buffer,
-                // The layout of the texture
-                wgpu::ImageDataLayout {
-                    offset: 0,
-                    bytes_per_row: Some((self.max_texture_size * 4).into()),
-                    rows_per_image: Some((self.max_texture_size).into()),
-                },
-                texture_size,
+                self.atlas.width.into(),
             );
         }
-        self.atlas.fresh = false;
-        self.atlas.dirty = false;
+        self.staging.finish();
+    }
+
+    /// Returns the staging buffers used by the last submitted frame.
+    #[inline]
+    pub fn recall(&mut self) {
+        self.staging.recall();
     }
 }
 
@@ -299,3 +404,75 @@ fn fill(
     }
     Some(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x: u16, y: u16, width: u16, height: u16) -> DirtyRect {
+        DirtyRect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    #[test]
+    fn dirty_rects_are_batched_by_rows() {
+        let mut atlas = Atlas::new(64, 64, 64);
+
+        atlas.mark_dirty(rect(0, 0, 8, 16));
+        atlas.mark_dirty(rect(9, 0, 8, 16));
+        atlas.mark_dirty(rect(0, 17, 8, 20));
+        atlas.mark_dirty(rect(18, 4, 4, 8));
+
+        assert_eq!(atlas.dirty, [rect(0, 0, 22, 16), rect(0, 17, 8, 20)]);
+    }
+
+    fn glyph(width: u16, height: u16) -> AddImage<'static> {
+        AddImage {
+            width,
+            height,
+            has_alpha: true,
+            data: ImageData::Owned(vec![255; width as usize * height as usize * 4]),
+        }
+    }
+
+    #[test]
+    fn atlas_grows_before_evicting() {
+        let mut atlas = Atlas::new(64, 16, 64);
+
+        let first = atlas.allocate(glyph(40, 15)).unwrap();
+        atlas.epoch += 1;
+        let second = atlas.allocate(glyph(40, 15)).unwrap();
+
+        assert_eq!(atlas.height, 32);
+        assert_eq!(atlas.buffer.len(), 64 * 32 * 4);
+        assert!(atlas.entry(first).is_some());
+        assert!(atlas.entry(second).is_some());
+    }
+
+    #[test]
+    fn atlas_evicts_least_recently_used() {
+        let mut atlas = Atlas::new(64, 32, 32);
+
+        let old = atlas.allocate(glyph(40, 15)).unwrap();
+        atlas.epoch += 1;
+        let recent = atlas.allocate(glyph(40, 15)).unwrap();
+        atlas.epoch += 1;
+
+        let new = atlas.allocate(glyph(40, 15)).unwrap();
+        assert!(atlas.entry(old).is_none());
+        assert!(atlas.entry(recent).is_some());
+        // The slot is reused but the evicted handle stays invalid
+        assert_eq!(new.index(), old.index());
+        assert_ne!(new, old);
+
+        // Images drawn in this frame are never evicted
+        atlas.entries[recent.index()].last_used = atlas.epoch;
+        assert!(atlas.allocate(glyph(40, 15)).is_none());
+        assert!(atlas.entry(recent).is_some());
+        assert!(atlas.entry(new).is_some());
+    }
+}
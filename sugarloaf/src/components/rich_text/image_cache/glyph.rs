@@ -119,7 +119,7 @@ impl<'a> GlyphCacheSession<'a> {
             size: self.quant_size,
         };
         if let Some(entry) = self.entry.glyphs.get(&key) {
-            if self.images.is_valid(entry.image) {
+            if self.images.touch(entry.image) {
                 return Some(*entry);
             }
         }
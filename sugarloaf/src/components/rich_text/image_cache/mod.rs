@@ -12,7 +12,7 @@ pub use glyph::GlyphCache;
 pub struct ImageId(u32);
 
 impl ImageId {
-    fn new(index: u32, alpha: bool) -> Option<Self> {
+    fn new(index: u32, alpha: bool, generation: u8) -> Option<Self> {
         if index & ID_INDEX_MASK != index {
             return None;
         }
@@ -20,6 +20,7 @@ impl ImageId {
         if alpha {
             handle |= ID_ALPHA_BIT
         }
+        handle |= (generation as u32) << ID_GENERATION_SHIFT;
         Some(Self(handle))
     }
 
@@ -27,13 +28,18 @@ impl ImageId {
         (self.0 & ID_INDEX_MASK) as usize
     }
 
+    fn generation(self) -> u8 {
+        (self.0 >> ID_GENERATION_SHIFT) as u8
+    }
+
     /// Returns true if the image contains transparency.
     pub fn has_alpha(self) -> bool {
         self.0 & ID_ALPHA_BIT != 0
     }
 }
 
-/// Location of an image in a texture.
+/// Location of an image in a texture, in pixels since the
+/// texture grows on demand.
 #[derive(Copy, Clone)]
 pub struct ImageLocation {
     /// Minimum x and y texture coordinates.
@@ -86,3 +92,4 @@ impl<'a> ImageData<'a> {
 
 const ID_INDEX_MASK: u32 = 0x007FFFFF;
 const ID_ALPHA_BIT: u32 = 0x00800000;
+const ID_GENERATION_SHIFT: u32 = 24;
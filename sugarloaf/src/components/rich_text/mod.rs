@@ -1,5 +1,6 @@
 mod batch;
 mod compositor;
+pub mod drawable_char;
 mod image_cache;
 pub mod text;
 
@@ -7,16 +8,16 @@ use crate::components::core::orthographic_projection;
 use crate::components::rich_text::image_cache::{GlyphCache, ImageCache};
 use crate::context::Context;
 use crate::font::FontLibrary;
-use crate::layout::SugarDimensions;
+use crate::layout::{LineSize, SugarDimensions};
 use crate::sugarloaf::graphics::GraphicRenderRequest;
 use crate::Graphics;
-use compositor::{Compositor, DisplayList, Rect, Vertex};
+use compositor::{Compositor, DisplayList, Quad, Rect, Vertex};
 use std::{borrow::Cow, mem};
 use text::{Glyph, TextRunStyle};
 use wgpu::util::DeviceExt;
 
-// Note: currently it's using Indexed drawing instead of Instance drawing could be worth to
-// evaluate if would make sense move to instance drawing instead
+// Note: glyphs and images use indexed drawing, while solid rectangles (backgrounds,
+// decorations and cursor) are drawn with instance drawing, one instance per quad.
 // https://math.hws.edu/graphicsbook/c9/s2.html
 // https://docs.rs/wgpu/latest/wgpu/enum.VertexStepMode.html
 
@@ -33,16 +34,38 @@ pub const BLEND: Option<wgpu::BlendState> = Some(wgpu::BlendState {
     },
 });
 
+/// Uniforms of the shaders, keep in sync with `Globals` of the shaders.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Globals {
+    transform: [f32; 16],
+    /// Window background, transparent when it's an image or a shader.
+    background: [f32; 4],
+    /// Gamma and contrast of the text.
+    text: [f32; 4],
+}
+
+#[allow(unsafe_code)]
+unsafe impl bytemuck::Zeroable for Globals {}
+
+#[allow(unsafe_code)]
+unsafe impl bytemuck::Pod for Globals {}
+
 pub struct RichTextBrush {
     vertex_buffer: wgpu::Buffer,
     constant_bind_group: wgpu::BindGroup,
     layout_bind_group: wgpu::BindGroup,
     layout_bind_group_layout: wgpu::BindGroupLayout,
-    transform: wgpu::Buffer,
+    globals_buffer: wgpu::Buffer,
     pipeline: wgpu::RenderPipeline,
+    quad_pipeline: wgpu::RenderPipeline,
+    quad_buffer: wgpu::Buffer,
+    supported_quad_buffer: usize,
     index_buffer: wgpu::Buffer,
     index_buffer_size: u64,
-    current_transform: [f32; 16],
+    current_globals: Globals,
+    background: [f32; 4],
+    text: [f32; 4],
     comp: Compositor,
     dlist: DisplayList,
     supported_vertex_buffer: usize,
@@ -57,13 +80,17 @@ impl RichTextBrush {
         let dlist = DisplayList::new();
         let supported_vertex_buffer = 2_000;
 
-        let current_transform =
-            orthographic_projection(context.size.width, context.size.height);
-        let transform = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: None,
-            contents: bytemuck::cast_slice(&current_transform),
-            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-        });
+        let current_globals = Globals {
+            transform: orthographic_projection(context.size.width, context.size.height),
+            background: [0., 0., 0., 1.],
+            text: [1., 0., 0., 0.],
+        };
+        let globals_buffer =
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: None,
+                contents: bytemuck::bytes_of(&current_globals),
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            });
 
         // Create pipeline layout
         let constant_bind_group_layout =
@@ -72,15 +99,14 @@ impl RichTextBrush {
                 entries: &[
                     wgpu::BindGroupLayoutEntry {
                         binding: 0,
-                        visibility: wgpu::ShaderStages::VERTEX,
+                        visibility: wgpu::ShaderStages::VERTEX
+                            | wgpu::ShaderStages::FRAGMENT,
                         ty: wgpu::BindingType::Buffer {
                             ty: wgpu::BufferBindingType::Uniform,
                             has_dynamic_offset: false,
-                            min_binding_size: wgpu::BufferSize::new(mem::size_of::<
-                                [f32; 16],
-                            >(
-                            )
-                                as wgpu::BufferAddress),
+                            min_binding_size: wgpu::BufferSize::new(
+                                mem::size_of::<Globals>() as wgpu::BufferAddress,
+                            ),
                         },
                         count: None,
                     },
@@ -141,7 +167,7 @@ impl RichTextBrush {
                 wgpu::BindGroupEntry {
                     binding: 0,
                     resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
-                        buffer: &transform,
+                        buffer: &globals_buffer,
                         offset: 0,
                         size: None,
                     }),
@@ -187,6 +213,7 @@ impl RichTextBrush {
                         1 => Float32x4,
                         2 => Float32x2,
                         3 => Sint32x2,
+                        4 => Float32x4,
                     ),
                 }],
             },
@@ -214,6 +241,58 @@ impl RichTextBrush {
             multiview: None,
         });
 
+        let quad_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: None,
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(include_str!("quad.wgsl"))),
+        });
+
+        let quad_pipeline =
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                cache: None,
+                label: Some("rich_text::quad pipeline"),
+                layout: Some(&pipeline_layout),
+                vertex: wgpu::VertexState {
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                    module: &quad_shader,
+                    entry_point: "vs_main",
+                    buffers: &[wgpu::VertexBufferLayout {
+                        array_stride: mem::size_of::<Quad>() as u64,
+                        step_mode: wgpu::VertexStepMode::Instance,
+                        attributes: &wgpu::vertex_attr_array!(
+                            0 => Float32x2,
+                            1 => Float32x2,
+                            2 => Float32x4,
+                            3 => Uint32,
+                        ),
+                    }],
+                },
+                fragment: Some(wgpu::FragmentState {
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                    module: &quad_shader,
+                    entry_point: "fs_main",
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: context.format,
+                        blend: BLEND,
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                }),
+                primitive: wgpu::PrimitiveState {
+                    topology: wgpu::PrimitiveTopology::TriangleList,
+                    ..Default::default()
+                },
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                multiview: None,
+            });
+
+        let supported_quad_buffer = 500;
+        let quad_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("rich_text::Quads Buffer"),
+            size: mem::size_of::<Quad>() as u64 * supported_quad_buffer as u64,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
         let vertex_buffer = device.create_buffer(&wgpu::BufferDescriptor {
             label: Some("rich_text::Instances Buffer"),
             size: mem::size_of::<Vertex>() as u64 * supported_vertex_buffer as u64,
@@ -231,6 +310,9 @@ impl RichTextBrush {
         });
 
         RichTextBrush {
+            quad_pipeline,
+            quad_buffer,
+            supported_quad_buffer,
             layout_bind_group,
             layout_bind_group_layout,
             constant_bind_group,
@@ -241,18 +323,19 @@ impl RichTextBrush {
             textures_version: 0,
             glyphs: GlyphCache::new(),
             dlist,
-            transform,
+            globals_buffer,
             pipeline,
             vertex_buffer,
             supported_vertex_buffer,
-            current_transform,
+            background: current_globals.background,
+            text: current_globals.text,
+            current_globals,
         }
     }
 
     #[inline]
     pub fn prepare(
         &mut self,
-        context: &mut crate::context::Context,
         state: &crate::sugarloaf::state::SugarState,
         graphics: &mut Graphics,
     ) {
@@ -264,21 +347,22 @@ impl RichTextBrush {
         }
 
         // Render
+        self.images.begin_frame();
         self.comp.begin();
 
         let library = state.compositors.advanced.font_library();
+        let (x, y) = state.layout.style.screen_position;
         draw_layout(
             &mut self.comp,
             (&mut self.images, &mut self.glyphs),
             &state.compositors.advanced.render_data,
-            state.layout.style.screen_position,
+            (x, y + state.scroll_offset * state.layout.dimensions.scale),
             library,
             &state.layout.dimensions,
             graphics,
         );
 
         self.dlist.clear();
-        self.images.process_atlases(context);
         self.comp.finish(&mut self.dlist);
         // let duration = start.elapsed();
         // println!(" - rich_text::prepare::draw_layout() is: {:?}", duration);
@@ -308,8 +392,37 @@ impl RichTextBrush {
         }
     }
 
+    /// Color glyphs without a cell background are blended over, `None`
+    /// for background images and shaders.
+    #[inline]
+    pub fn set_background(&mut self, color: Option<wgpu::Color>) {
+        self.background = color.map_or([0.; 4], |color| {
+            [color.r as f32, color.g as f32, color.b as f32, 1.]
+        });
+    }
+
+    /// Gamma above 1.0 thickens the text, contrast from 0.0 to 1.0
+    /// thickens text darker than its background.
+    #[inline]
+    pub fn set_text_gamma(&mut self, gamma: f32, contrast: f32) {
+        self.text = [gamma.max(0.1), contrast.clamp(0., 1.), 0., 0.];
+    }
+
     pub fn reset(&mut self) {
         self.glyphs = GlyphCache::new();
+        self.images.clear();
+    }
+
+    /// Uploads the glyphs rasterized since the last frame.
+    #[inline]
+    pub fn upload(&mut self, context: &mut Context, encoder: &mut wgpu::CommandEncoder) {
+        self.images.process_atlases(context, encoder);
+    }
+
+    /// Called once the frame is submitted to reuse its staging buffers.
+    #[inline]
+    pub fn recall(&mut self) {
+        self.images.recall();
     }
 
     #[inline]
@@ -321,18 +434,66 @@ impl RichTextBrush {
     ) {
         // let start = std::time::Instant::now();
         // There's nothing to render
-        if self.dlist.vertices.is_empty() {
+        if self.dlist.vertices.is_empty() && self.dlist.quads.is_empty() {
             return;
         }
 
         let queue = &mut ctx.queue;
 
-        let transform = orthographic_projection(state.layout.width, state.layout.height);
-        let transform_has_changed = transform != self.current_transform;
+        let globals = Globals {
+            transform: orthographic_projection(state.layout.width, state.layout.height),
+            background: self.background,
+            text: self.text,
+        };
+        if globals != self.current_globals {
+            queue.write_buffer(&self.globals_buffer, 0, bytemuck::bytes_of(&globals));
+            self.current_globals = globals;
+        }
 
-        if transform_has_changed {
-            queue.write_buffer(&self.transform, 0, bytemuck::bytes_of(&transform));
-            self.current_transform = transform;
+        if self.textures_version != self.images.generation() {
+            self.textures_version = self.images.generation();
+            self.layout_bind_group =
+                ctx.device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    layout: &self.layout_bind_group_layout,
+                    entries: &[wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(
+                            &self.images.texture_view,
+                        ),
+                    }],
+                    label: Some("rich_text::Pipeline uniforms"),
+                });
+        }
+
+        if !self.dlist.quads.is_empty() {
+            if self.dlist.quads.len() > self.supported_quad_buffer {
+                self.quad_buffer.destroy();
+
+                self.supported_quad_buffer = self.dlist.quads.len();
+                self.quad_buffer = ctx.device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("rich_text::Quads Buffer"),
+                    size: mem::size_of::<Quad>() as u64
+                        * self.supported_quad_buffer as u64,
+                    usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                    mapped_at_creation: false,
+                });
+            }
+
+            queue.write_buffer(
+                &self.quad_buffer,
+                0,
+                bytemuck::cast_slice(&self.dlist.quads),
+            );
+
+            rpass.set_pipeline(&self.quad_pipeline);
+            rpass.set_bind_group(0, &self.constant_bind_group, &[]);
+            rpass.set_bind_group(1, &self.layout_bind_group, &[]);
+            rpass.set_vertex_buffer(0, self.quad_buffer.slice(..));
+            rpass.draw(0..6, 0..self.dlist.quads.len() as u32);
+        }
+
+        if self.dlist.vertices.is_empty() {
+            return;
         }
 
         if self.dlist.vertices.len() > self.supported_vertex_buffer {
@@ -376,21 +537,6 @@ impl RichTextBrush {
             self.index_buffer_size = size;
         }
 
-        if self.textures_version != self.images.entries.len() {
-            self.textures_version = self.images.entries.len();
-            self.layout_bind_group =
-                ctx.device.create_bind_group(&wgpu::BindGroupDescriptor {
-                    layout: &self.layout_bind_group_layout,
-                    entries: &[wgpu::BindGroupEntry {
-                        binding: 0,
-                        resource: wgpu::BindingResource::TextureView(
-                            &self.images.texture_view,
-                        ),
-                    }],
-                    label: Some("rich_text::Pipeline uniforms"),
-                });
-        }
-
         rpass.set_pipeline(&self.pipeline);
         rpass.set_bind_group(0, &self.constant_bind_group, &[]);
         rpass.set_bind_group(1, &self.layout_bind_group, &[]);
@@ -447,20 +593,36 @@ fn draw_layout(
             glyphs.clear();
             let font = run.font();
             let char_width = run.char_width();
+            let line_size = run.line_size();
+            let scale = line_size.scale();
+            // The top half of a double height line draws glyphs through
+            // the row below, which is the bottom half.
+            let baseline = match line_size {
+                LineSize::DoubleHeightTop => py + line.ascent(),
+                _ => py,
+            };
 
             let run_x = px;
+            let cell_width = rect.width * char_width * scale;
+            // Double height lines keep the glyphs, they span two rows.
+            let drawable_char = run.drawable_char().filter(|_| {
+                matches!(line_size, LineSize::Normal | LineSize::DoubleWidth)
+            });
             for cluster in run.visual_clusters() {
                 for glyph in cluster.glyphs() {
-                    let x = px + glyph.x;
-                    let y = py - glyph.y;
+                    let x = px + glyph.x * scale;
+                    let y = baseline - glyph.y * scale;
                     // px += glyph.advance
-                    px += rect.width * char_width;
+                    px += cell_width;
                     glyphs.push(Glyph { id: glyph.id, x, y });
                 }
             }
+            if line_size == LineSize::DoubleHeightBottom || drawable_char.is_some() {
+                glyphs.clear();
+            }
             let style = TextRunStyle {
                 font_coords,
-                font_size: run.font_size(),
+                font_size: run.font_size() * scale,
                 color: run.color(),
                 cursor: run.cursor(),
                 background_color: run.background_color(),
@@ -468,8 +630,25 @@ fn draw_layout(
                 topline: py - line.ascent(),
                 line_height,
                 advance: px - run_x,
-                decoration: run.decoration(),
+                decoration: match line_size {
+                    LineSize::DoubleHeightTop => None,
+                    _ => run.decoration(),
+                },
                 decoration_color: run.decoration_color(),
+                strikethrough: match line_size {
+                    LineSize::DoubleHeightTop => None,
+                    _ => run.strikethrough(),
+                },
+                overline: match line_size {
+                    LineSize::DoubleHeightTop => None,
+                    _ => run.overline(),
+                },
+                vertical_scale: match line_size {
+                    LineSize::DoubleWidth => 0.5,
+                    _ => 1.0,
+                },
+                drawable_char,
+                cell_width,
             };
 
             if font != &current_font || style.font_size != current_font_size {
@@ -580,6 +759,11 @@ fn fetch_dimensions(
                 advance: px - run_x,
                 decoration: None,
                 decoration_color: None,
+                strikethrough: None,
+                overline: None,
+                vertical_scale: 1.0,
+                drawable_char: None,
+                cell_width: 0.,
             };
 
             if style.advance > 0. && line_height > 0. {
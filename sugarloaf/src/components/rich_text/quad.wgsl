@@ -0,0 +1,141 @@
+// Keep in sync with rich_text.wgsl
+struct Globals {
+    transform: mat4x4<f32>,
+    background: vec4<f32>,
+    text: vec4<f32>,
+}
+
+@group(0) @binding(0) var<uniform> globals: Globals;
+
+// Keep in sync with the `QUAD_*` shapes of batch.rs
+const SHAPE_DOTTED: u32 = 1u;
+const SHAPE_DASHED: u32 = 2u;
+const SHAPE_CURLY: u32 = 3u;
+const SHAPE_TRIANGLE_RIGHT: u32 = 4u;
+const SHAPE_TRIANGLE_LEFT: u32 = 5u;
+const SHAPE_TRIANGLE_LOWER_LEFT: u32 = 6u;
+const SHAPE_TRIANGLE_LOWER_RIGHT: u32 = 7u;
+const SHAPE_TRIANGLE_UPPER_LEFT: u32 = 8u;
+const SHAPE_TRIANGLE_UPPER_RIGHT: u32 = 9u;
+
+// Length of a curly underline wave
+const WAVE_LENGTH: f32 = 8.0;
+const TAU: f32 = 6.2831853;
+
+struct QuadInput {
+    @builtin(vertex_index) vertex_index: u32,
+    @location(0) pos: vec2<f32>,
+    @location(1) size: vec2<f32>,
+    @location(2) color: vec4<f32>,
+    @location(3) shape: u32,
+}
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+    // Position inside of the quad and in the layout, patterns follow
+    // the layout so runs next to each other line up.
+    @location(1) local: vec2<f32>,
+    @location(2) world_x: f32,
+    @location(3) height: f32,
+    @location(4) @interpolate(flat) shape: u32,
+    @location(5) width: f32,
+}
+
+@vertex
+fn vs_main(input: QuadInput) -> VertexOutput {
+    // Two triangles: (0, 0) (0, 1) (1, 1) and (1, 1) (0, 0) (1, 0)
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(0.0, 1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 0.0),
+    );
+
+    var out: VertexOutput;
+    let corner = corners[input.vertex_index];
+    let local = corner * input.size;
+    out.color = input.color;
+    out.local = local;
+    out.world_x = input.pos.x + local.x;
+    out.height = input.size.y;
+    out.width = input.size.x;
+    out.shape = input.shape;
+    out.position = globals.transform * vec4<f32>(input.pos + local, 0.0, 1.0);
+    return out;
+}
+
+// Coverage of the side of the edge from `a` to `b` on its left, by the
+// distance in pixels to the edge.
+fn edge_coverage(p: vec2<f32>, a: vec2<f32>, b: vec2<f32>) -> f32 {
+    let edge = b - a;
+    let distance = (edge.x * (p.y - a.y) - edge.y * (p.x - a.x)) / length(edge);
+    return clamp(distance + 0.5, 0.0, 1.0);
+}
+
+// Coverage of the triangle below the diagonal from the top left to the
+// bottom right corner, the other triangles flip the position.
+fn lower_left_coverage(p: vec2<f32>, size: vec2<f32>) -> f32 {
+    return edge_coverage(p, vec2<f32>(0.0, 0.0), size);
+}
+
+@fragment
+fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    switch input.shape {
+        case SHAPE_DOTTED: {
+            if input.world_x % 4.0 >= 2.0 {
+                discard;
+            }
+        }
+        case SHAPE_DASHED: {
+            if input.world_x % 8.0 >= 6.0 {
+                discard;
+            }
+        }
+        case SHAPE_CURLY: {
+            // Distance to a cosine wave filling the quad height,
+            // divided by the slope to keep the stroke width even.
+            let thickness = max(input.height / 2.5, 1.0);
+            let amplitude = (input.height - thickness) / 2.0;
+            let phase = input.world_x * TAU / WAVE_LENGTH;
+            let center = input.height / 2.0 - amplitude * cos(phase);
+            let slope = amplitude * TAU / WAVE_LENGTH * sin(phase);
+            let distance = abs(input.local.y - center) / sqrt(1.0 + slope * slope);
+            let coverage = clamp(thickness / 2.0 + 0.5 - distance, 0.0, 1.0);
+            return vec4<f32>(input.color.rgb, input.color.a * coverage);
+        }
+        case SHAPE_TRIANGLE_RIGHT, SHAPE_TRIANGLE_LEFT: {
+            let size = vec2<f32>(input.width, input.height);
+            var p = input.local;
+            if input.shape == SHAPE_TRIANGLE_LEFT {
+                p.x = size.x - p.x;
+            }
+            let tip = vec2<f32>(size.x, size.y / 2.0);
+            let coverage = min(
+                edge_coverage(p, vec2<f32>(0.0, 0.0), tip),
+                edge_coverage(p, tip, vec2<f32>(0.0, size.y)),
+            );
+            return vec4<f32>(input.color.rgb, input.color.a * coverage);
+        }
+        case SHAPE_TRIANGLE_LOWER_LEFT, SHAPE_TRIANGLE_LOWER_RIGHT,
+            SHAPE_TRIANGLE_UPPER_LEFT, SHAPE_TRIANGLE_UPPER_RIGHT: {
+            let size = vec2<f32>(input.width, input.height);
+            var p = input.local;
+            if input.shape == SHAPE_TRIANGLE_LOWER_RIGHT
+                || input.shape == SHAPE_TRIANGLE_UPPER_RIGHT {
+                p.x = size.x - p.x;
+            }
+            if input.shape == SHAPE_TRIANGLE_UPPER_LEFT
+                || input.shape == SHAPE_TRIANGLE_UPPER_RIGHT {
+                p.y = size.y - p.y;
+            }
+            let coverage = lower_left_coverage(p, size);
+            return vec4<f32>(input.color.rgb, input.color.a * coverage);
+        }
+        default: {}
+    }
+
+    return input.color;
+}
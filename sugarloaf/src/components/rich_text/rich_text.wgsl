@@ -1,5 +1,9 @@
 struct Globals {
     transform: mat4x4<f32>,
+    // Window background, transparent when it's an image or a shader.
+    background: vec4<f32>,
+    // Gamma and contrast of the text.
+    text: vec4<f32>,
 }
 
 @group(0) @binding(0) var<uniform> globals: Globals;
@@ -12,6 +16,7 @@ struct VertexInput {
     @location(1) v_color: vec4<f32>,
     @location(2) v_uv: vec2<f32>,
     @location(3) layers: vec2<i32>,
+    @location(4) v_background: vec4<f32>,
 }
 
 struct VertexOutput {
@@ -20,6 +25,7 @@ struct VertexOutput {
     @location(1) f_uv: vec2<f32>,
     @location(2) color_layer: i32,
     @location(3) mask_layer: i32,
+    @location(4) f_background: vec4<f32>,
 }
 
 @vertex
@@ -29,21 +35,80 @@ fn vs_main(input: VertexInput) -> VertexOutput {
     out.f_uv = input.v_uv;
     out.color_layer = input.layers.x;
     out.mask_layer = input.layers.y;
+    out.f_background = input.v_background;
 
     out.position = globals.transform * vec4<f32>(input.v_pos.xy, 0.0, 1.0);
     return out;
 }
 
+fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 {
+        return c / 12.92;
+    }
+    return pow((c + 0.055) / 1.055, 2.4);
+}
+
+fn linear_to_srgb(c: f32) -> f32 {
+    if c <= 0.0031308 {
+        return c * 12.92;
+    }
+    return 1.055 * pow(c, 1.0 / 2.4) - 0.055;
+}
+
+fn luminance(color: vec3<f32>) -> f32 {
+    let linear = vec3<f32>(
+        srgb_to_linear(color.r),
+        srgb_to_linear(color.g),
+        srgb_to_linear(color.b),
+    );
+    return dot(linear, vec3<f32>(0.2126, 0.7152, 0.0722));
+}
+
+// Alpha of a glyph pixel. Colors are blended while sRGB encoded, so the
+// coverage is corrected to give the luminance blending in linear light
+// would, then thickened by the gamma and the contrast.
+fn text_alpha(foreground: vec3<f32>, background: vec4<f32>, coverage: f32) -> f32 {
+    if coverage <= 0.0 {
+        return 0.0;
+    }
+
+    var alpha = pow(coverage, 1.0 / globals.text.x);
+    // Nothing to correct against over images and shaders
+    if background.a == 0.0 {
+        return alpha;
+    }
+
+    let fg = luminance(foreground);
+    let bg = luminance(background.rgb);
+    // Only text darker than its background is thickened
+    alpha = clamp(alpha * (1.0 + globals.text.y * max(bg - fg, 0.0)), 0.0, 1.0);
+
+    let fg_srgb = linear_to_srgb(fg);
+    let bg_srgb = linear_to_srgb(bg);
+    if abs(fg_srgb - bg_srgb) < 0.001 {
+        return alpha;
+    }
+    let blended = linear_to_srgb(mix(bg, fg, alpha));
+    return clamp((blended - bg_srgb) / (fg_srgb - bg_srgb), 0.0, 1.0);
+}
+
 @fragment
 fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
     var out: vec4<f32> = input.f_color;
+    // Coordinates are in pixels, the atlas height changes as it grows
+    let uv = input.f_uv / vec2<f32>(textureDimensions(font_texture));
 
     if input.color_layer > 0 {
-        out = textureSampleLevel(font_texture, font_sampler, input.f_uv, 0.0);
+        out = textureSampleLevel(font_texture, font_sampler, uv, 0.0);
     }
 
     if input.mask_layer > 0 {
-        out = vec4<f32>(out.xyz, textureSampleLevel(font_texture, font_sampler, input.f_uv, 0.0).x);
+        let coverage = textureSampleLevel(font_texture, font_sampler, uv, 0.0).x;
+        var background = globals.background;
+        if input.f_background.a > 0.0 {
+            background = input.f_background;
+        }
+        out = vec4<f32>(out.xyz, text_alpha(out.xyz, background, coverage));
     }
 
     return out;
@@ -9,6 +9,7 @@
 // Eventually the file had updates to support other features like background-color,
 // text color, underline color and etc.
 
+use crate::components::rich_text::drawable_char::DrawableChar;
 use crate::font_introspector::{GlyphId, NormalizedCoord};
 use crate::layout::FragmentStyleDecoration;
 use crate::sugarloaf::primitives::SugarCursor;
@@ -36,8 +37,19 @@ pub struct TextRunStyle<'a> {
     pub decoration: Option<FragmentStyleDecoration>,
     /// Underline style.
     pub decoration_color: Option<[f32; 4]>,
+    /// Strikethrough offset above the baseline and thickness.
+    pub strikethrough: Option<(f32, f32)>,
+    /// Overline thickness.
+    pub overline: Option<f32>,
     /// Cursor style.
     pub cursor: Option<SugarCursor>,
+    /// Vertical scale of the glyphs, double width lines rasterize glyphs
+    /// at twice the font size and squash them back to the line height.
+    pub vertical_scale: f32,
+    /// Drawn in every cell of the run instead of the glyphs.
+    pub drawable_char: Option<DrawableChar>,
+    /// Width of a cell of the run.
+    pub cell_width: f32,
 }
 
 /// Positioned glyph in a text run.
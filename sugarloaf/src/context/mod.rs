@@ -3,58 +3,223 @@ use crate::SugarloafRenderer;
 
 pub struct Context<'a> {
     pub device: wgpu::Device,
-    pub surface: wgpu::Surface<'a>,
+    /// `None` for headless contexts, which only render offscreen.
+    pub surface: Option<wgpu::Surface<'a>>,
     pub queue: wgpu::Queue,
+    /// Format of the render pipelines, same as `output.render`.
     pub format: wgpu::TextureFormat,
+    pub output: SurfaceFormat,
     pub size: SugarloafWindowSize,
     pub scale: f32,
     alpha_mode: wgpu::CompositeAlphaMode,
+    present_mode: wgpu::PresentMode,
+    supported_present_modes: Vec<wgpu::PresentMode>,
     pub adapter_info: wgpu::AdapterInfo,
+    /// Frames are rasterized on the CPU, see [`is_software_adapter`].
+    pub is_software: bool,
 }
 
-#[inline]
-#[cfg(not(target_os = "macos"))]
-fn find_best_texture_format(formats: Vec<wgpu::TextureFormat>) -> wgpu::TextureFormat {
-    let mut format: wgpu::TextureFormat = formats.first().unwrap().to_owned();
-
-    // TODO: Fix formats with signs
-    // FIXME: On Nvidia GPUs usage Rgba16Float texture format causes driver to enable HDR.
-    // Reason for this is currently output color space is poorly defined in wgpu and
-    // anything other than Srgb texture formats can cause undeterministic output color
-    // space selection which also causes colors to mismatch. Optionally we can whitelist
-    // only the Srgb texture formats for now until output color space selection lands in wgpu. See #205
-    // TODO: use output color format for the CanvasConfiguration when it lands on the wgpu
-    #[cfg(windows)]
-    let unsupported_formats = [
-        wgpu::TextureFormat::Rgba8Snorm,
-        wgpu::TextureFormat::Rgba16Float,
-    ];
-
-    // not reproduce-able on mac
-    #[cfg(not(windows))]
-    let unsupported_formats = [wgpu::TextureFormat::Rgba8Snorm];
-
-    let filtered_formats: Vec<wgpu::TextureFormat> = formats
+/// Formats negotiated with the surface, see [`select_surface_format`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SurfaceFormat {
+    /// Format of the surface, wgpu tags the color space of the swapchain
+    /// from it, e.g. extended linear sRGB for `Rgba16Float`.
+    pub surface: wgpu::TextureFormat,
+    /// Format the brushes render to, it always holds sRGB encoded colors
+    /// so blending looks the same whatever the surface is.
+    pub render: wgpu::TextureFormat,
+    /// Frames are rendered offscreen and converted to linear values for
+    /// an HDR surface.
+    pub is_hdr: bool,
+}
+
+impl SurfaceFormat {
+    #[inline]
+    fn new(format: wgpu::TextureFormat) -> Self {
+        SurfaceFormat {
+            surface: format,
+            render: format,
+            is_hdr: false,
+        }
+    }
+}
+
+/// Picks the surface format out of the surface capabilities:
+///
+/// - `Rgba16Float` when HDR is requested and the display supports it.
+/// - An 8 bit sRGB format, rendered through a view without the sRGB
+///   suffix since colors are already sRGB encoded.
+/// - An 8 bit format without the sRGB suffix.
+/// - Anything else which doesn't need the colors converted.
+pub fn select_surface_format(
+    formats: &[wgpu::TextureFormat],
+    hdr: bool,
+    supports_view_formats: bool,
+) -> SurfaceFormat {
+    use wgpu::TextureFormat;
+
+    if hdr && formats.contains(&TextureFormat::Rgba16Float) {
+        return SurfaceFormat {
+            surface: TextureFormat::Rgba16Float,
+            render: TextureFormat::Rgba16Float,
+            is_hdr: true,
+        };
+    }
+
+    let eight_bits = [TextureFormat::Bgra8Unorm, TextureFormat::Rgba8Unorm];
+    if supports_view_formats {
+        for format in eight_bits {
+            if formats.contains(&format.add_srgb_suffix()) {
+                return SurfaceFormat {
+                    surface: format.add_srgb_suffix(),
+                    render: format,
+                    is_hdr: false,
+                };
+            }
+        }
+    }
+
+    if let Some(format) = eight_bits
+        .into_iter()
+        .find(|format| formats.contains(format))
+    {
+        return SurfaceFormat::new(format);
+    }
+
+    // FIXME: On Nvidia GPUs usage Rgba16Float texture format causes driver to enable HDR,
+    // sRGB and float formats would also need the colors converted.
+    let unsupported_formats = [TextureFormat::Rgba8Snorm, TextureFormat::Rgba16Float];
+    let format = formats
         .iter()
         .copied()
-        .filter(|&x| {
-            !wgpu::TextureFormat::is_srgb(&x) && !unsupported_formats.contains(&x)
-        })
-        .collect();
+        .find(|format| !format.is_srgb() && !unsupported_formats.contains(format))
+        .or(formats.first().copied())
+        .unwrap_or(TextureFormat::Bgra8Unorm);
+    SurfaceFormat::new(format)
+}
 
-    if !filtered_formats.is_empty() {
-        filtered_formats.first().unwrap().clone_into(&mut format);
+/// Configuring the surface with an unsupported present mode fails,
+/// the automatic ones are always available.
+fn select_present_mode(
+    supported: &[wgpu::PresentMode],
+    requested: wgpu::PresentMode,
+) -> wgpu::PresentMode {
+    match requested {
+        wgpu::PresentMode::AutoVsync | wgpu::PresentMode::AutoNoVsync => requested,
+        _ if supported.contains(&requested) => requested,
+        _ => {
+            tracing::warn!(
+                "present mode {requested:?} is not supported, using AutoVsync"
+            );
+            wgpu::PresentMode::AutoVsync
+        }
+    }
+}
+
+/// Backends to try in order, configured ones first and then any other
+/// backend that can draw to the window.
+fn backend_fallbacks(backend: wgpu::Backends) -> Vec<wgpu::Backends> {
+    if backend.contains(wgpu::Backends::all()) {
+        vec![backend]
+    } else {
+        vec![backend, wgpu::Backends::all()]
+    }
+}
+
+/// Software rasterizers, like llvmpipe on Linux or WARP on Windows, are
+/// usually the only choice in virtual machines or without GPU drivers.
+pub fn is_software_adapter(info: &wgpu::AdapterInfo) -> bool {
+    if info.device_type == wgpu::DeviceType::Cpu {
+        return true;
     }
 
-    tracing::info!("Sugarloaf selected format: {format:?} from {:?}", formats);
+    let name = info.name.to_lowercase();
+    [
+        "llvmpipe",
+        "softpipe",
+        "swiftshader",
+        "microsoft basic render driver",
+    ]
+    .iter()
+    .any(|software| name.contains(software))
+}
+
+/// Case insensitive, `nvidia` matches `NVIDIA GeForce RTX 3060`.
+#[inline]
+fn adapter_matches(name: &str, query: &str) -> bool {
+    name.to_lowercase().contains(&query.trim().to_lowercase())
+}
+
+/// Adapter named in the config, otherwise the one for the power preference.
+/// Without a surface any adapter will do.
+async fn request_adapter(
+    instance: &wgpu::Instance,
+    surface: Option<&wgpu::Surface<'_>>,
+    renderer_config: &SugarloafRenderer,
+) -> Option<wgpu::Adapter> {
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(query) = &renderer_config.adapter {
+        let adapter = instance
+            .enumerate_adapters(wgpu::Backends::all())
+            .into_iter()
+            .find(|adapter| {
+                adapter_matches(&adapter.get_info().name, query)
+                    && surface.map_or(true, |s| adapter.is_surface_supported(s))
+            });
+        if adapter.is_some() {
+            return adapter;
+        }
+
+        tracing::warn!(
+            "no adapter named {query:?} can draw to the window, using the power preference"
+        );
+    }
+
+    instance
+        .request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: renderer_config.power_preference,
+            compatible_surface: surface,
+            force_fallback_adapter: false,
+        })
+        .await
+}
 
-    format
+async fn request_device(adapter: &wgpu::Adapter) -> (wgpu::Device, wgpu::Queue) {
+    if let Ok(result) = adapter
+        .request_device(&wgpu::DeviceDescriptor::default(), None)
+        .await
+    {
+        result
+    } else {
+        // These downlevel limits will allow the code to run on all possible hardware
+        adapter
+            .request_device(
+                &wgpu::DeviceDescriptor {
+                    memory_hints: wgpu::MemoryHints::Performance,
+                    label: None,
+                    required_features: wgpu::Features::empty(),
+                    required_limits: wgpu::Limits::downlevel_webgl2_defaults(),
+                },
+                None,
+            )
+            .await
+            .expect("Request device")
+    }
 }
 
 impl Context<'_> {
     pub fn new<'a>(
         sugarloaf_window: SugarloafWindow,
         renderer_config: SugarloafRenderer,
+    ) -> Context<'a> {
+        futures::executor::block_on(Self::new_async(sugarloaf_window, renderer_config))
+    }
+
+    /// Same as [`Context::new`] without blocking on the adapter and device
+    /// requests, browsers only resolve them from the event loop.
+    pub async fn new_async<'a>(
+        sugarloaf_window: SugarloafWindow,
+        renderer_config: SugarloafRenderer,
     ) -> Context<'a> {
         // The backend can be configured using the `WGPU_BACKEND`
         // environment variable. If the variable is not set, the primary backend
@@ -68,68 +233,80 @@ impl Context<'_> {
         // - `primary`
         let backend =
             wgpu::util::backend_bits_from_env().unwrap_or(renderer_config.backend);
-        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
-            backends: backend,
-            ..Default::default()
-        });
 
-        tracing::info!("selected instance: {instance:?}");
+        let size = sugarloaf_window.size;
+        let scale = sugarloaf_window.scale;
 
-        #[cfg(not(target_arch = "wasm32"))]
-        {
-            tracing::info!("Available adapters:");
-            for a in instance.enumerate_adapters(wgpu::Backends::all()) {
-                tracing::info!("    {:?}", a.get_info())
+        let mut selected = None;
+        for backend in backend_fallbacks(backend) {
+            let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+                backends: backend,
+                ..Default::default()
+            });
+
+            tracing::info!("selected instance: {instance:?}");
+
+            #[cfg(not(target_arch = "wasm32"))]
+            {
+                tracing::info!("Available adapters:");
+                for a in instance.enumerate_adapters(wgpu::Backends::all()) {
+                    tracing::info!("    {:?}", a.get_info())
+                }
             }
-        }
 
-        tracing::info!("initializing the surface");
+            tracing::info!("initializing the surface");
 
-        let size = sugarloaf_window.size;
-        let scale = sugarloaf_window.scale;
+            let surface: wgpu::Surface<'a> =
+                match instance.create_surface(sugarloaf_window.clone()) {
+                    Ok(surface) => surface,
+                    Err(error) => {
+                        tracing::warn!("unable to create a {backend:?} surface: {error}");
+                        continue;
+                    }
+                };
+            if let Some(adapter) =
+                request_adapter(&instance, Some(&surface), &renderer_config).await
+            {
+                selected = Some((surface, adapter));
+                break;
+            }
 
-        let surface: wgpu::Surface<'a> =
-            instance.create_surface(sugarloaf_window).unwrap();
-        let adapter = futures::executor::block_on(instance.request_adapter(
-            &wgpu::RequestAdapterOptions {
-                power_preference: renderer_config.power_preference,
-                compatible_surface: Some(&surface),
-                force_fallback_adapter: false,
-            },
-        ))
-        .expect("Request adapter");
+            tracing::warn!("no adapter for {backend:?} can draw to the window");
+        }
+        let (surface, adapter) = selected.expect("Request adapter");
 
         let adapter_info = adapter.get_info();
-        tracing::info!("Selected adapter: {:?}", adapter_info);
+        tracing::info!(
+            "Selected adapter: {} ({:?}, {:?})",
+            adapter_info.name,
+            adapter_info.backend,
+            adapter_info.device_type
+        );
+        tracing::debug!("{adapter_info:?}");
+
+        let is_software = is_software_adapter(&adapter_info);
+        if is_software {
+            tracing::warn!(
+                "{} is a software rasterizer, expensive effects are disabled",
+                adapter_info.name
+            );
+        }
 
         let caps = surface.get_capabilities(&adapter);
+        let output = select_surface_format(
+            &caps.formats,
+            renderer_config.hdr,
+            adapter
+                .get_downlevel_capabilities()
+                .flags
+                .contains(wgpu::DownlevelFlags::SURFACE_VIEW_FORMATS),
+        );
+        tracing::info!(
+            "Sugarloaf selected format: {output:?} from {:?}",
+            caps.formats
+        );
 
-        #[cfg(target_os = "macos")]
-        let format = wgpu::TextureFormat::Bgra8Unorm;
-        #[cfg(not(target_os = "macos"))]
-        let format = find_best_texture_format(caps.formats);
-
-        let (device, queue) = {
-            {
-                if let Ok(result) = futures::executor::block_on(
-                    adapter.request_device(&wgpu::DeviceDescriptor::default(), None),
-                ) {
-                    result
-                } else {
-                    // These downlevel limits will allow the code to run on all possible hardware
-                    futures::executor::block_on(adapter.request_device(
-                        &wgpu::DeviceDescriptor {
-                            memory_hints: wgpu::MemoryHints::Performance,
-                            label: None,
-                            required_features: wgpu::Features::empty(),
-                            required_limits: wgpu::Limits::downlevel_webgl2_defaults(),
-                        },
-                        None,
-                    ))
-                    .expect("Request device")
-                }
-            }
-        };
+        let (device, queue) = request_device(&adapter).await;
 
         let alpha_mode = if caps
             .alpha_modes
@@ -145,50 +322,263 @@ impl Context<'_> {
             wgpu::CompositeAlphaMode::Auto
         };
 
-        surface.configure(
-            &device,
-            &wgpu::SurfaceConfiguration {
-                usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-                format,
-                width: size.width as u32,
-                height: size.height as u32,
-                view_formats: vec![],
-                alpha_mode,
-                present_mode: wgpu::PresentMode::Fifo,
-                desired_maximum_frame_latency: 2,
-            },
-        );
+        let present_mode =
+            select_present_mode(&caps.present_modes, renderer_config.present_mode);
 
-        Context {
+        let context = Context {
             device,
             queue,
-            surface,
-            format,
+            surface: Some(surface),
+            format: output.render,
+            output,
             alpha_mode,
+            present_mode,
+            supported_present_modes: caps.present_modes,
             size: SugarloafWindowSize {
                 width: size.width,
                 height: size.height,
             },
             scale,
             adapter_info,
+            is_software,
+        };
+        context.configure(size.width as u32, size.height as u32);
+        context
+    }
+
+    /// Context without a window, frames are only rendered offscreen,
+    /// see [`crate::Sugarloaf::render_to_image`].
+    pub fn new_headless<'a>(
+        size: SugarloafWindowSize,
+        scale: f32,
+        renderer_config: SugarloafRenderer,
+    ) -> Context<'a> {
+        futures::executor::block_on(Self::new_headless_async(
+            size,
+            scale,
+            renderer_config,
+        ))
+    }
+
+    pub async fn new_headless_async<'a>(
+        size: SugarloafWindowSize,
+        scale: f32,
+        renderer_config: SugarloafRenderer,
+    ) -> Context<'a> {
+        let backend =
+            wgpu::util::backend_bits_from_env().unwrap_or(renderer_config.backend);
+
+        let mut selected = None;
+        for backend in backend_fallbacks(backend) {
+            let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+                backends: backend,
+                ..Default::default()
+            });
+
+            if let Some(adapter) =
+                request_adapter(&instance, None, &renderer_config).await
+            {
+                selected = Some(adapter);
+                break;
+            }
+
+            tracing::warn!("no adapter for {backend:?}");
+        }
+        let adapter = selected.expect("Request adapter");
+
+        let adapter_info = adapter.get_info();
+        tracing::info!(
+            "Selected headless adapter: {} ({:?}, {:?})",
+            adapter_info.name,
+            adapter_info.backend,
+            adapter_info.device_type
+        );
+
+        let (device, queue) = request_device(&adapter).await;
+
+        // Offscreen frames are read back as 8 bit sRGB encoded colors.
+        let output = SurfaceFormat::new(wgpu::TextureFormat::Rgba8Unorm);
+        Context {
+            device,
+            queue,
+            surface: None,
+            format: output.render,
+            output,
+            alpha_mode: wgpu::CompositeAlphaMode::Auto,
+            present_mode: wgpu::PresentMode::AutoVsync,
+            supported_present_modes: vec![],
+            size,
+            scale,
+            is_software: is_software_adapter(&adapter_info),
+            adapter_info,
         }
     }
 
-    pub fn resize(&mut self, width: u32, height: u32) {
-        self.size.width = width as f32;
-        self.size.height = height as f32;
-        self.surface.configure(
+    fn configure(&self, width: u32, height: u32) {
+        let view_formats = if self.output.render != self.output.surface {
+            vec![self.output.render]
+        } else {
+            vec![]
+        };
+
+        let Some(surface) = &self.surface else {
+            return;
+        };
+
+        surface.configure(
             &self.device,
             &wgpu::SurfaceConfiguration {
                 usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-                format: self.format,
+                format: self.output.surface,
                 width,
                 height,
-                view_formats: vec![],
+                view_formats,
                 alpha_mode: self.alpha_mode,
-                present_mode: wgpu::PresentMode::Fifo,
+                present_mode: self.present_mode,
                 desired_maximum_frame_latency: 2,
             },
         );
     }
+
+    pub fn set_present_mode(&mut self, present_mode: wgpu::PresentMode) {
+        let present_mode =
+            select_present_mode(&self.supported_present_modes, present_mode);
+        if present_mode != self.present_mode {
+            self.present_mode = present_mode;
+            self.configure(self.size.width as u32, self.size.height as u32);
+        }
+    }
+
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.size.width = width as f32;
+        self.size.height = height as f32;
+        self.configure(width, height);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wgpu::TextureFormat;
+
+    #[test]
+    fn test_backend_fallbacks() {
+        assert_eq!(
+            backend_fallbacks(wgpu::Backends::all()),
+            vec![wgpu::Backends::all()]
+        );
+        assert_eq!(
+            backend_fallbacks(wgpu::Backends::VULKAN),
+            vec![wgpu::Backends::VULKAN, wgpu::Backends::all()]
+        );
+    }
+
+    #[test]
+    fn test_is_software_adapter() {
+        let adapter = |name: &str, device_type| wgpu::AdapterInfo {
+            name: name.to_string(),
+            vendor: 0,
+            device: 0,
+            device_type,
+            driver: String::new(),
+            driver_info: String::new(),
+            backend: wgpu::Backend::Vulkan,
+        };
+
+        assert!(is_software_adapter(&adapter(
+            "llvmpipe (LLVM 15.0.7, 256 bits)",
+            wgpu::DeviceType::Other
+        )));
+        assert!(is_software_adapter(&adapter(
+            "Microsoft Basic Render Driver",
+            wgpu::DeviceType::Other
+        )));
+        assert!(is_software_adapter(&adapter(
+            "SwiftShader Device",
+            wgpu::DeviceType::Cpu
+        )));
+        assert!(!is_software_adapter(&adapter(
+            "NVIDIA GeForce RTX 3060",
+            wgpu::DeviceType::DiscreteGpu
+        )));
+    }
+
+    #[test]
+    fn test_adapter_matches() {
+        assert!(adapter_matches("NVIDIA GeForce RTX 3060", "nvidia"));
+        assert!(adapter_matches("NVIDIA GeForce RTX 3060", " rtx 3060 "));
+        assert!(adapter_matches(
+            "Intel(R) UHD Graphics 620 (KBL GT2)",
+            "Intel"
+        ));
+        assert!(!adapter_matches("AMD Radeon Pro 5500M", "nvidia"));
+    }
+
+    #[test]
+    fn test_select_surface_format() {
+        let metal = [
+            TextureFormat::Bgra8Unorm,
+            TextureFormat::Bgra8UnormSrgb,
+            TextureFormat::Rgba16Float,
+            TextureFormat::Rgb10a2Unorm,
+        ];
+        assert_eq!(
+            select_surface_format(&metal, false, true),
+            SurfaceFormat {
+                surface: TextureFormat::Bgra8UnormSrgb,
+                render: TextureFormat::Bgra8Unorm,
+                is_hdr: false,
+            }
+        );
+        assert_eq!(
+            select_surface_format(&metal, false, false),
+            SurfaceFormat::new(TextureFormat::Bgra8Unorm)
+        );
+        assert_eq!(
+            select_surface_format(&metal, true, true),
+            SurfaceFormat {
+                surface: TextureFormat::Rgba16Float,
+                render: TextureFormat::Rgba16Float,
+                is_hdr: true,
+            }
+        );
+
+        // HDR falls back to SDR when the display doesn't support it
+        let vulkan = [
+            TextureFormat::Rgba16Float,
+            TextureFormat::Rgba8UnormSrgb,
+            TextureFormat::Rgb10a2Unorm,
+        ];
+        assert_eq!(
+            select_surface_format(&vulkan[1..], true, true),
+            SurfaceFormat {
+                surface: TextureFormat::Rgba8UnormSrgb,
+                render: TextureFormat::Rgba8Unorm,
+                is_hdr: false,
+            }
+        );
+        assert_eq!(
+            select_surface_format(&vulkan, false, false),
+            SurfaceFormat::new(TextureFormat::Rgb10a2Unorm)
+        );
+    }
+
+    #[test]
+    fn test_select_present_mode() {
+        use wgpu::PresentMode;
+
+        let supported = [PresentMode::Fifo, PresentMode::Mailbox];
+        assert_eq!(
+            select_present_mode(&supported, PresentMode::Mailbox),
+            PresentMode::Mailbox
+        );
+        assert_eq!(
+            select_present_mode(&supported, PresentMode::Immediate),
+            PresentMode::AutoVsync
+        );
+        assert_eq!(
+            select_present_mode(&[], PresentMode::AutoVsync),
+            PresentMode::AutoVsync
+        );
+    }
 }
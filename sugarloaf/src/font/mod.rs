@@ -107,6 +107,80 @@ impl FontLibrary {
     }
 }
 
+#[cfg(not(target_arch = "wasm32"))]
+impl FontLibrary {
+    /// Loads the primary fonts right away and everything else (fallbacks,
+    /// emoji, extras and the UI font) on a background thread, so startup
+    /// doesn't wait for the system font database. The system fonts are only
+    /// scanned upfront when a primary font isn't the embedded one.
+    ///
+    /// `on_loaded` is called with the fonts that couldn't be found once the
+    /// remaining fonts are available, font matches cached before that point
+    /// should be discarded.
+    pub fn new_lazy<F>(
+        spec: SugarloafFonts,
+        on_loaded: F,
+    ) -> (Self, Option<SugarloafErrors>)
+    where
+        F: FnOnce(Option<SugarloafErrors>) + Send + 'static,
+    {
+        let spec = with_family_overwrite(spec);
+        let needs_system_fonts =
+            [&spec.regular, &spec.italic, &spec.bold, &spec.bold_italic]
+                .iter()
+                .any(|font| !font.is_default_family());
+
+        let mut db = loader::Database::new();
+        if needs_system_fonts {
+            db.load_system_fonts();
+        }
+
+        let mut font_library = FontLibraryData::default();
+        let fonts_not_found = font_library.load_primary(&db, &spec);
+        let sugarloaf_errors =
+            (!fonts_not_found.is_empty()).then_some(SugarloafErrors { fonts_not_found });
+
+        let library = Self {
+            inner: Arc::new(FairMutex::new(font_library)),
+        };
+
+        let inner = library.inner.clone();
+        let result = std::thread::Builder::new()
+            .name(String::from("font loader"))
+            .spawn(move || {
+                if !needs_system_fonts {
+                    db.load_system_fonts();
+                }
+
+                let has_ui = spec.ui.is_some();
+                let mut secondary = FontLibraryData::default();
+                let fonts_not_found = secondary.load_secondary(&db, spec);
+
+                let mut library = inner.lock();
+                for font_id in 0..secondary.inner.len() {
+                    if let Some(font) = secondary.inner.remove(&font_id) {
+                        library.insert(font);
+                    }
+                }
+                if has_ui {
+                    library.ui = secondary.ui;
+                }
+                drop(library);
+
+                on_loaded(
+                    (!fonts_not_found.is_empty())
+                        .then_some(SugarloafErrors { fonts_not_found }),
+                );
+            });
+
+        if let Err(err) = result {
+            tracing::error!("unable to spawn font loader: {err}");
+        }
+
+        (library, sugarloaf_errors)
+    }
+}
+
 impl Default for FontLibrary {
     fn default() -> Self {
         let mut font_library = FontLibraryData::default();
@@ -242,75 +316,62 @@ impl FontLibraryData {
     }
 
     #[cfg(not(target_arch = "wasm32"))]
-    pub fn load(&mut self, mut spec: SugarloafFonts) -> Vec<SugarloafFont> {
-        let mut fonts_not_fount: Vec<SugarloafFont> = vec![];
-
-        // If fonts.family does exist it will overwrite all families
-        if let Some(font_family_overwrite) = spec.family {
-            font_family_overwrite.clone_into(&mut spec.regular.family);
-            font_family_overwrite.clone_into(&mut spec.bold.family);
-            font_family_overwrite.clone_into(&mut spec.bold_italic.family);
-            font_family_overwrite.clone_into(&mut spec.italic.family);
-        }
+    pub fn load(&mut self, spec: SugarloafFonts) -> Vec<SugarloafFont> {
+        let spec = with_family_overwrite(spec);
 
         let mut db = loader::Database::new();
         db.load_system_fonts();
 
-        match find_font(&db, spec.regular, false, false) {
-            FindResult::Found(data) => {
-                self.insert(data);
-            }
-            FindResult::NotFound(spec) => {
-                if !spec.is_default_family() {
-                    fonts_not_fount.push(spec);
-                } else {
-                    self.insert(load_fallback_from_memory(&spec));
-                }
-            }
-        }
+        let mut fonts_not_fount = self.load_primary(&db, &spec);
+        fonts_not_fount.extend(self.load_secondary(&db, spec));
+        fonts_not_fount
+    }
 
-        match find_font(&db, spec.italic, false, false) {
-            FindResult::Found(data) => {
-                self.insert(data);
-            }
-            FindResult::NotFound(spec) => {
-                if !spec.is_default_family() {
-                    fonts_not_fount.push(spec);
-                } else {
-                    self.insert(load_fallback_from_memory(&spec));
-                }
-            }
-        }
+    /// Loads regular, italic, bold and bold italic, the first four font ids.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn load_primary(
+        &mut self,
+        db: &loader::Database,
+        spec: &SugarloafFonts,
+    ) -> Vec<SugarloafFont> {
+        let mut fonts_not_fount: Vec<SugarloafFont> = vec![];
 
-        match find_font(&db, spec.bold, false, false) {
-            FindResult::Found(data) => {
-                self.insert(data);
-            }
-            FindResult::NotFound(spec) => {
-                if !spec.is_default_family() {
-                    fonts_not_fount.push(spec);
-                } else {
-                    self.insert(load_fallback_from_memory(&spec));
+        let primary = [
+            (spec.regular.clone(), false),
+            (spec.italic.clone(), false),
+            (spec.bold.clone(), false),
+            (spec.bold_italic.clone(), true),
+        ];
+        for (font_spec, evictable) in primary {
+            match find_font(db, font_spec, evictable, false) {
+                FindResult::Found(data) => {
+                    self.insert(data);
                 }
-            }
-        }
-
-        match find_font(&db, spec.bold_italic, true, false) {
-            FindResult::Found(data) => {
-                self.insert(data);
-            }
-            FindResult::NotFound(spec) => {
-                if !spec.is_default_family() {
-                    fonts_not_fount.push(spec);
-                } else {
-                    self.insert(load_fallback_from_memory(&spec));
+                FindResult::NotFound(spec) => {
+                    if !spec.is_default_family() {
+                        fonts_not_fount.push(spec);
+                    } else {
+                        self.insert(load_fallback_from_memory(&spec));
+                    }
                 }
             }
         }
 
+        fonts_not_fount
+    }
+
+    /// Loads fallbacks, emoji, extras, symbols and the UI font.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn load_secondary(
+        &mut self,
+        db: &loader::Database,
+        spec: SugarloafFonts,
+    ) -> Vec<SugarloafFont> {
+        let mut fonts_not_fount: Vec<SugarloafFont> = vec![];
+
         for fallback in fallbacks::external_fallbacks() {
             match find_font(
-                &db,
+                db,
                 SugarloafFont {
                     family: fallback,
                     ..SugarloafFont::default()
@@ -329,7 +390,7 @@ impl FontLibraryData {
         }
 
         if let Some(emoji_font) = spec.emoji {
-            match find_font(&db, emoji_font, true, true) {
+            match find_font(db, emoji_font, true, true) {
                 FindResult::Found(data) => {
                     self.insert(data);
                 }
@@ -346,7 +407,7 @@ impl FontLibraryData {
 
         for extra_font in spec.extras {
             match find_font(
-                &db,
+                db,
                 SugarloafFont {
                     family: extra_font.family,
                     style: extra_font.style,
@@ -367,7 +428,7 @@ impl FontLibraryData {
         self.insert(FontData::from_slice(FONT_SYMBOLS_NERD_FONT_MONO, false).unwrap());
 
         if let Some(ui_spec) = spec.ui {
-            match find_font(&db, ui_spec, false, false) {
+            match find_font(db, ui_spec, false, false) {
                 FindResult::Found(data) => {
                     self.ui = FontArc::try_from_vec(data.data.unwrap().to_vec()).unwrap();
                 }
@@ -541,6 +602,19 @@ enum FindResult {
     NotFound(SugarloafFont),
 }
 
+/// If `fonts.family` is set it overwrites all families.
+#[cfg(not(target_arch = "wasm32"))]
+fn with_family_overwrite(mut spec: SugarloafFonts) -> SugarloafFonts {
+    if let Some(font_family_overwrite) = spec.family.take() {
+        font_family_overwrite.clone_into(&mut spec.regular.family);
+        font_family_overwrite.clone_into(&mut spec.bold.family);
+        font_family_overwrite.clone_into(&mut spec.bold_italic.family);
+        font_family_overwrite.clone_into(&mut spec.italic.family);
+    }
+
+    spec
+}
+
 #[cfg(not(target_arch = "wasm32"))]
 #[inline]
 fn find_font(
@@ -686,3 +760,22 @@ fn load_from_font_source(path: &PathBuf) -> Option<Vec<u8>> {
 
     None
 }
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lazy_font_library_matches_eager_load() {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let (library, errors) =
+            FontLibrary::new_lazy(SugarloafFonts::default(), move |errors| {
+                tx.send(errors.is_none()).unwrap();
+            });
+        assert!(errors.is_none());
+
+        assert!(rx.recv().unwrap());
+        let eager = FontLibrary::default();
+        assert_eq!(library.inner.lock().len(), eager.inner.lock().len());
+    }
+}
@@ -3,16 +3,19 @@
 // This source code is licensed under the MIT license found in the
 // LICENSE file in the root directory of this source tree.
 
+use crate::components::rich_text::drawable_char::DrawableChar;
 use crate::font::FontLibrary;
 use crate::font_introspector::shape::cluster::GlyphCluster;
 use crate::font_introspector::shape::cluster::OwnedGlyphCluster;
 use crate::font_introspector::shape::ShapeContext;
-use crate::font_introspector::text::Script;
+use crate::font_introspector::text::{Codepoint, Script};
 use crate::font_introspector::Metrics;
 use crate::layout::render_data::RenderData;
 use lru::LruCache;
-use rustc_hash::FxHashMap;
+use rustc_hash::{FxHashMap, FxHasher};
+use std::hash::{Hash, Hasher};
 use std::num::NonZeroUsize;
+use std::ops::Range;
 
 use crate::font_introspector::Attributes;
 use crate::font_introspector::Setting;
@@ -21,15 +24,29 @@ use crate::{sugarloaf::primitives::SugarCursor, Graphic};
 /// Data that describes a fragment.
 #[derive(Debug, Clone)]
 pub struct FragmentData {
-    pub content: String,
+    /// Range of the fragment in the line text.
+    pub range: Range<usize>,
     /// Style
     pub style: FragmentStyle,
 }
 
 #[derive(Default)]
 pub struct BuilderLine {
+    /// Text of all fragments.
+    pub text: String,
     /// Collection of fragments.
     pub fragments: Vec<FragmentData>,
+    /// Size of the line.
+    pub size: LineSize,
+}
+
+impl BuilderLine {
+    #[inline]
+    fn clear(&mut self) {
+        self.text.clear();
+        self.fragments.clear();
+        self.size = LineSize::Normal;
+    }
 }
 
 /// Builder state.
@@ -37,6 +54,8 @@ pub struct BuilderLine {
 pub struct BuilderState {
     /// Lines State
     pub lines: Vec<BuilderLine>,
+    /// Cleared lines kept to reuse their allocations on the next frame.
+    spare_lines: Vec<BuilderLine>,
     /// Font variation setting cache.
     pub vars: FontSettingCache<f32>,
     /// User specified scale.
@@ -56,7 +75,8 @@ impl BuilderState {
     }
     #[inline]
     pub fn new_line(&mut self) {
-        self.lines.push(BuilderLine::default());
+        let line = self.spare_lines.pop().unwrap_or_default();
+        self.lines.push(line);
     }
     #[inline]
     pub fn current_line(&self) -> usize {
@@ -69,13 +89,16 @@ impl BuilderState {
     }
     #[inline]
     pub fn clear(&mut self) {
-        self.lines.clear();
+        for mut line in self.lines.drain(..) {
+            line.clear();
+            self.spare_lines.push(line);
+        }
         self.vars.clear();
     }
 
     #[inline]
     pub fn begin(&mut self) {
-        self.lines.push(BuilderLine::default());
+        self.new_line();
     }
 }
 
@@ -137,6 +160,32 @@ pub enum UnderlineShape {
     Curly = 3,
 }
 
+/// Size of a line, double lines render glyphs at twice the font size and
+/// advance two cells per column (DECDWL and DECDHL).
+///
+/// Double height lines come in pairs: the top half draws the glyphs across
+/// both rows and the bottom half only draws backgrounds and decorations.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub enum LineSize {
+    #[default]
+    Normal,
+    DoubleWidth,
+    DoubleHeightTop,
+    DoubleHeightBottom,
+}
+
+impl LineSize {
+    /// Horizontal scale of the line.
+    #[inline]
+    pub fn scale(self) -> f32 {
+        if self == LineSize::Normal {
+            1.0
+        } else {
+            2.0
+        }
+    }
+}
+
 #[derive(Copy, Clone, PartialEq, Debug)]
 pub struct UnderlineInfo {
     pub offset: f32,
@@ -149,7 +198,6 @@ pub struct UnderlineInfo {
 pub enum FragmentStyleDecoration {
     // offset, size
     Underline(UnderlineInfo),
-    Strikethrough,
 }
 
 #[derive(Copy, Clone, PartialEq, Debug)]
@@ -175,8 +223,15 @@ pub struct FragmentStyle {
     pub decoration: Option<FragmentStyleDecoration>,
     /// Decoration color.
     pub decoration_color: Option<[f32; 4]>,
+    /// Line through the text, drawn along with underlines.
+    pub strikethrough: bool,
+    /// Line above the text, drawn along with underlines.
+    pub overline: bool,
     /// Cursor style.
     pub cursor: Option<SugarCursor>,
+    /// Box drawing and other chars drawn to fill the cell, instead of
+    /// the glyph from the font.
+    pub drawable_char: Option<DrawableChar>,
     /// Media
     pub media: Option<Graphic>,
 }
@@ -194,8 +249,11 @@ impl Default for FragmentStyle {
             color: [1.0, 1.0, 1.0, 1.0],
             background_color: None,
             cursor: None,
+            drawable_char: None,
             decoration: None,
             decoration_color: None,
+            strikethrough: false,
+            overline: false,
             media: None,
         }
     }
@@ -208,6 +266,7 @@ pub struct Content {
     scx: ShapeContext,
     state: BuilderState,
     word_cache: WordCache,
+    line_cache: LineCache,
     metrics_cache: MetricsCache,
 }
 
@@ -219,6 +278,7 @@ impl Content {
             scx: ShapeContext::new(),
             state: BuilderState::new(),
             word_cache: WordCache::new(),
+            line_cache: LineCache::new(),
             font_features: vec![],
             metrics_cache: MetricsCache::default(),
         }
@@ -235,6 +295,16 @@ impl Content {
         font_features: Vec<crate::font_introspector::Setting<u16>>,
     ) {
         self.font_features = font_features;
+        self.clear_caches();
+    }
+
+    /// Forgets the shaped text, glyphs are positioned for the font size
+    /// and features they were shaped with.
+    #[inline]
+    fn clear_caches(&mut self) {
+        self.word_cache.inner.clear();
+        self.line_cache.inner.clear();
+        self.metrics_cache.inner.clear();
     }
 
     #[inline]
@@ -246,7 +316,7 @@ impl Content {
         self.state.font_size = font_size * scale;
 
         if prev_font_size != self.state.font_size {
-            self.metrics_cache.inner.clear();
+            self.clear_caches();
         }
     }
 
@@ -255,13 +325,22 @@ impl Content {
         self.state.new_line();
     }
 
+    /// Sets the size of the current line.
+    #[inline]
+    pub fn set_line_size(&mut self, size: LineSize) {
+        let current_line = self.state.current_line();
+        self.state.lines[current_line].size = size;
+    }
+
     /// Adds a text fragment to the paragraph.
     pub fn add_text(&mut self, text: &str, style: FragmentStyle) -> Option<()> {
         let current_line = self.state.current_line();
         let line = &mut self.state.lines[current_line];
 
+        let start = line.text.len();
+        line.text.push_str(text);
         line.fragments.push(FragmentData {
-            content: text.to_string(),
+            range: start..line.text.len(),
             style,
         });
 
@@ -270,12 +349,35 @@ impl Content {
 
     #[inline]
     pub fn resolve(&mut self, render_data: &mut RenderData) {
-        let script = Script::Latin;
         for line_number in 0..self.state.lines.len() {
             let line = &self.state.lines[line_number];
+            let line_size = line.size;
+            let line_key = LineCache::key(line, &self.state.vars);
+
+            // Lines that didn't change skip looking up each fragment.
+            if let Some(shaped_line) = self.line_cache.inner.get(&line_key) {
+                let has_metrics = line.fragments.iter().all(|item| {
+                    self.metrics_cache.inner.contains_key(&item.style.font_id)
+                });
+                if has_metrics {
+                    for (item, clusters) in line.fragments.iter().zip(shaped_line) {
+                        render_data.push_run_without_shaper(
+                            item.style,
+                            self.state.font_size,
+                            line_number as u32,
+                            line_size,
+                            clusters,
+                            &self.metrics_cache.inner[&item.style.font_id],
+                        );
+                    }
+                    continue;
+                }
+            }
+
+            let mut shaped_line = Vec::with_capacity(line.fragments.len());
             for item in &line.fragments {
                 let vars = self.state.vars.get(item.style.font_vars);
-                let shaper_key = &item.content;
+                let shaper_key = &line.text[item.range.clone()];
 
                 // println!("{:?} -> {:?}", item.style.font_id, shaper_key);
 
@@ -288,22 +390,25 @@ impl Content {
                             item.style,
                             self.state.font_size,
                             line_number as u32,
+                            line_size,
                             shaper,
                             metrics,
                         ) {
+                            shaped_line.push(shaper.clone());
                             continue;
                         }
                     }
                 }
 
                 self.word_cache.font_id = item.style.font_id;
-                self.word_cache.content = item.content.clone();
+                self.word_cache.content.clear();
+                self.word_cache.content.push_str(shaper_key);
                 let font_library = { &mut self.fonts.inner.lock() };
                 if let Some(data) = font_library.get_data(&item.style.font_id) {
                     let mut shaper = self
                         .scx
                         .builder(data)
-                        .script(script)
+                        .script(run_script(shaper_key))
                         .size(self.state.font_size)
                         .features(self.font_features.iter().copied())
                         .variations(vars.iter().copied())
@@ -320,15 +425,64 @@ impl Content {
                         item.style,
                         self.state.font_size,
                         line_number as u32,
+                        line_size,
                         shaper,
                         &mut self.word_cache,
                     );
                 }
+
+                shaped_line.push(
+                    self.word_cache
+                        .get(&item.style.font_id, shaper_key)
+                        .cloned()
+                        .unwrap_or_default(),
+                );
             }
+            self.line_cache.inner.put(line_key, shaped_line);
         }
     }
 }
 
+/// Script to shape a run with, taken from its first letter so marks and
+/// contextual forms of other scripts than Latin are applied.
+#[inline]
+fn run_script(text: &str) -> Script {
+    text.chars()
+        .map(|c| c.script())
+        .find(|script| {
+            !matches!(script, Script::Common | Script::Inherited | Script::Unknown)
+        })
+        .unwrap_or(Script::Latin)
+}
+
+/// Shaped clusters of each fragment of a line, by the text and fonts of
+/// the line. Colors and the other styles don't change the shaping.
+struct LineCache {
+    inner: LruCache<u64, Vec<Vec<OwnedGlyphCluster>>>,
+}
+
+impl LineCache {
+    fn new() -> Self {
+        Self {
+            inner: LruCache::new(NonZeroUsize::new(1024).unwrap()),
+        }
+    }
+
+    fn key(line: &BuilderLine, vars: &FontSettingCache<f32>) -> u64 {
+        let mut hasher = FxHasher::default();
+        line.text.hash(&mut hasher);
+        for item in &line.fragments {
+            item.range.hash(&mut hasher);
+            item.style.font_id.hash(&mut hasher);
+            for setting in vars.get(item.style.font_vars) {
+                setting.tag.hash(&mut hasher);
+                setting.value.to_bits().hash(&mut hasher);
+            }
+        }
+        hasher.finish()
+    }
+}
+
 pub struct WordCache {
     pub inner: FxHashMap<usize, LruCache<String, Vec<OwnedGlyphCluster>>>,
     stash: Vec<OwnedGlyphCluster>,
@@ -350,7 +504,7 @@ impl WordCache {
     pub fn get(
         &mut self,
         font_id: &usize,
-        content: &String,
+        content: &str,
     ) -> Option<&Vec<OwnedGlyphCluster>> {
         if let Some(cache) = self.inner.get_mut(font_id) {
             return cache.get(content);
@@ -396,3 +550,41 @@ impl WordCache {
 struct MetricsCache {
     pub inner: FxHashMap<usize, Metrics>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_script() {
+        assert_eq!(run_script("=>"), Script::Latin);
+        assert_eq!(run_script("fn"), Script::Latin);
+        // Punctuation and marks take the script of the letters
+        assert_eq!(run_script("(\u{928}\u{94d})"), Script::Devanagari);
+        assert_eq!(run_script("\u{645}\u{64e}"), Script::Arabic);
+    }
+
+    #[test]
+    fn test_line_cache_key() {
+        let vars = FontSettingCache::default();
+        let mut line = BuilderLine::default();
+        line.text.push_str("a => b");
+        line.fragments.push(FragmentData {
+            range: 0..6,
+            style: FragmentStyle::default(),
+        });
+        let key = LineCache::key(&line, &vars);
+
+        // Colors don't change the shaping
+        line.fragments[0].style.color = [1., 0., 0., 1.];
+        assert_eq!(LineCache::key(&line, &vars), key);
+
+        // Splitting the ligature does
+        line.fragments[0].range = 0..3;
+        line.fragments.push(FragmentData {
+            range: 3..6,
+            style: FragmentStyle::default(),
+        });
+        assert_ne!(LineCache::key(&line, &vars), key);
+    }
+}
@@ -9,7 +9,7 @@
 use super::render_data::*;
 use super::Glyph;
 use crate::font_introspector::text::cluster::ClusterInfo;
-use crate::layout::FragmentStyle;
+use crate::layout::{FragmentStyle, LineSize};
 
 /// Cluster represents multiple glyphs.
 pub const CLUSTER_DETAILED: u8 = 1;
@@ -181,6 +181,7 @@ impl GlyphData {
 pub struct RunData {
     pub span: FragmentStyle,
     pub line: u32,
+    pub line_size: LineSize,
     pub size: f32,
     // pub whitespace: bool,
     // pub trailing_whitespace: bool,
@@ -19,7 +19,8 @@ pub mod iter {
 }
 
 pub use content::{
-    Content, FragmentStyle, FragmentStyleDecoration, UnderlineInfo, UnderlineShape,
+    Content, FragmentStyle, FragmentStyleDecoration, LineSize, UnderlineInfo,
+    UnderlineShape,
 };
 pub use render_data::{Cluster, Glyph, Line, Run};
 
@@ -11,12 +11,13 @@
 
 //! RenderData.
 use super::layout_data::*;
+use crate::components::rich_text::drawable_char::DrawableChar;
 use crate::font_introspector::shape::cluster::OwnedGlyphCluster;
 use crate::font_introspector::shape::{cluster::Glyph as ShapedGlyph, Shaper};
 use crate::font_introspector::text::cluster::ClusterInfo;
 use crate::font_introspector::GlyphId;
 use crate::font_introspector::Metrics;
-use crate::layout::content::{FragmentStyleDecoration, WordCache};
+use crate::layout::content::{FragmentStyleDecoration, LineSize, WordCache};
 use crate::layout::FragmentStyle;
 use crate::sugarloaf::primitives::SugarCursor;
 use crate::{Graphic, GraphicId};
@@ -81,6 +82,7 @@ impl RenderData {
         style: FragmentStyle,
         size: f32,
         line: u32,
+        line_size: LineSize,
         shaper: Shaper<'_>,
         shaper_cache: &mut WordCache,
     ) {
@@ -174,6 +176,7 @@ impl RenderData {
         let run_data = RunData {
             span: style,
             line,
+            line_size,
             size,
             clusters: (clusters_start, clusters_end),
             // ascent: metrics.ascent * span_data.line_spacing,
@@ -195,6 +198,7 @@ impl RenderData {
         style: FragmentStyle,
         size: f32,
         line: u32,
+        line_size: LineSize,
         glyph_clusters: &Vec<OwnedGlyphCluster>,
         metrics: &Metrics,
     ) -> bool {
@@ -285,6 +289,7 @@ impl RenderData {
         let run_data = RunData {
             span: style,
             line,
+            line_size,
             size,
             clusters: (clusters_start, clusters_end),
             // ascent: metrics.ascent * span_data.line_spacing,
@@ -356,6 +361,12 @@ impl<'a> Run<'a> {
         self.run.size
     }
 
+    /// Returns the size of the line the run belongs to.
+    #[inline]
+    pub fn line_size(&self) -> LineSize {
+        self.run.line_size
+    }
+
     /// Returns the color for the run.
     #[inline]
     pub fn color(&self) -> [f32; 4] {
@@ -367,6 +378,12 @@ impl<'a> Run<'a> {
         self.run.span.width
     }
 
+    /// Returns the char drawn instead of the glyphs
+    #[inline]
+    pub fn drawable_char(&self) -> Option<DrawableChar> {
+        self.run.span.drawable_char
+    }
+
     /// Returns the cursor
     #[inline]
     pub fn cursor(&self) -> Option<SugarCursor> {
@@ -404,6 +421,22 @@ impl<'a> Run<'a> {
         self.run.span.decoration_color
     }
 
+    /// Returns the offset above the baseline and the thickness of the
+    /// strikethrough, from the font metrics.
+    #[inline]
+    pub fn strikethrough(&self) -> Option<(f32, f32)> {
+        self.run
+            .span
+            .strikethrough
+            .then_some((self.run.strikeout_offset, self.run.strikeout_size))
+    }
+
+    /// Returns the thickness of the overline, from the font metrics.
+    #[inline]
+    pub fn overline(&self) -> Option<f32> {
+        self.run.span.overline.then_some(self.run.strikeout_size)
+    }
+
     /// Returns an iterator over the clusters in logical order.
     #[inline]
     pub fn clusters(&self) -> Clusters<'a> {
@@ -14,11 +14,15 @@ pub use crate::sugarloaf::{
         ResizeParameter, MAX_GRAPHIC_DIMENSIONS,
     },
     primitives::*,
-    Sugarloaf, SugarloafErrors, SugarloafRenderer, SugarloafWindow, SugarloafWindowSize,
-    SugarloafWithErrors,
+    OffscreenError, Sugarloaf, SugarloafErrors, SugarloafRenderer, SugarloafWindow,
+    SugarloafWindowSize, SugarloafWithErrors,
 };
+pub use components::minimap::{Minimap, MinimapMarker};
+pub use components::pane_frames::{Divider, DividerAxis, PaneArea, PaneFrames};
 pub use components::quad::{ComposedQuad, Quad};
 pub use components::rect::Rect;
+pub use components::rich_text::drawable_char::DrawableChar;
 pub use layout::{
-    Content, FragmentStyle, FragmentStyleDecoration, UnderlineInfo, UnderlineShape,
+    Content, FragmentStyle, FragmentStyleDecoration, LineSize, UnderlineInfo,
+    UnderlineShape,
 };
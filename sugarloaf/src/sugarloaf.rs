@@ -1,9 +1,12 @@
 pub mod compositors;
 pub mod graphics;
+pub mod offscreen;
 pub mod primitives;
 pub mod state;
 
+use crate::components::background::BackgroundShaderBrush;
 use crate::components::core::{image::Handle, shapes::Rectangle};
+use crate::components::hdr::HdrOutput;
 use crate::components::layer::{self, LayerBrush};
 use crate::components::quad::QuadBrush;
 use crate::components::rect::{Rect, RectBrush};
@@ -16,6 +19,7 @@ use crate::sugarloaf::layer::types;
 use crate::{context::Context, Object};
 use ab_glyph::{self, PxScale};
 use core::fmt::{Debug, Formatter};
+pub use offscreen::OffscreenError;
 use primitives::ImageProperties;
 use raw_window_handle::{
     DisplayHandle, HandleError, HasDisplayHandle, HasWindowHandle, WindowHandle,
@@ -29,6 +33,8 @@ pub struct Sugarloaf<'a> {
     quad_brush: QuadBrush,
     layer_brush: LayerBrush,
     rich_text_brush: RichTextBrush,
+    background_shader: Option<BackgroundShaderBrush>,
+    hdr_output: Option<HdrOutput>,
     state: state::SugarState,
     pub background_color: Option<wgpu::Color>,
     pub background_image: Option<ImageProperties>,
@@ -57,6 +63,7 @@ pub struct SugarloafWindowSize {
     pub height: f32,
 }
 
+#[derive(Clone)]
 pub struct SugarloafWindow {
     pub handle: raw_window_handle::RawWindowHandle,
     pub display: raw_window_handle::RawDisplayHandle,
@@ -67,7 +74,12 @@ pub struct SugarloafWindow {
 pub struct SugarloafRenderer {
     pub power_preference: wgpu::PowerPreference,
     pub backend: wgpu::Backends,
+    /// Part of the adapter name to prefer over `power_preference`.
+    pub adapter: Option<String>,
     pub font_features: Option<Vec<String>>,
+    /// Use an HDR surface when the display supports it.
+    pub hdr: bool,
+    pub present_mode: wgpu::PresentMode,
 }
 
 impl Default for SugarloafRenderer {
@@ -80,7 +92,10 @@ impl Default for SugarloafRenderer {
         SugarloafRenderer {
             power_preference: wgpu::PowerPreference::HighPerformance,
             backend: default_backend,
+            adapter: None,
             font_features: None,
+            hdr: false,
+            present_mode: wgpu::PresentMode::AutoVsync,
         }
     }
 }
@@ -121,7 +136,52 @@ impl Sugarloaf<'_> {
     ) -> Result<Sugarloaf<'a>, SugarloafWithErrors<'a>> {
         let font_features = renderer.font_features.to_owned();
         let ctx = Context::new(window, renderer);
+        Ok(Self::with_context(
+            ctx,
+            font_library,
+            layout,
+            &font_features,
+        ))
+    }
 
+    /// Create an instance without blocking the thread, required in the
+    /// browser (wasm32) where WebGPU resolves the adapter asynchronously.
+    pub async fn new_async<'a>(
+        window: SugarloafWindow,
+        renderer: SugarloafRenderer,
+        font_library: &FontLibrary,
+        layout: SugarloafLayout,
+    ) -> Result<Sugarloaf<'a>, SugarloafWithErrors<'a>> {
+        let font_features = renderer.font_features.to_owned();
+        let ctx = Context::new_async(window, renderer).await;
+        Ok(Self::with_context(
+            ctx,
+            font_library,
+            layout,
+            &font_features,
+        ))
+    }
+
+    /// Create an instance without a window, frames are read back with
+    /// [`Sugarloaf::render_to_image`], e.g. for screenshots in CI.
+    pub fn new_headless<'a>(
+        size: SugarloafWindowSize,
+        scale: f32,
+        renderer: SugarloafRenderer,
+        font_library: &FontLibrary,
+        layout: SugarloafLayout,
+    ) -> Sugarloaf<'a> {
+        let font_features = renderer.font_features.to_owned();
+        let ctx = Context::new_headless(size, scale, renderer);
+        Self::with_context(ctx, font_library, layout, &font_features)
+    }
+
+    fn with_context<'a>(
+        ctx: Context<'a>,
+        font_library: &FontLibrary,
+        layout: SugarloafLayout,
+        font_features: &Option<Vec<String>>,
+    ) -> Sugarloaf<'a> {
         let text_brush = {
             let data = { font_library.inner.lock().ui.to_owned() };
             text::GlyphBrushBuilder::using_fonts(vec![data])
@@ -132,22 +192,23 @@ impl Sugarloaf<'_> {
         let layer_brush = LayerBrush::new(&ctx);
         let quad_brush = QuadBrush::new(&ctx);
         let rich_text_brush = RichTextBrush::new(&ctx);
-        let state = SugarState::new(layout, font_library, &font_features);
+        let hdr_output = ctx.output.is_hdr.then(|| HdrOutput::new(&ctx));
+        let state = SugarState::new(layout, font_library, font_features);
 
-        let instance = Sugarloaf {
+        Sugarloaf {
             state,
             layer_brush,
             quad_brush,
             ctx,
             background_color: Some(wgpu::Color::BLACK),
             background_image: None,
+            background_shader: None,
+            hdr_output,
             rect_brush,
             rich_text_brush,
             text_brush,
             graphics: Graphics::default(),
-        };
-
-        Ok(instance)
+        }
     }
 
     #[inline]
@@ -184,30 +245,118 @@ impl Sugarloaf<'_> {
         self.state.compute_layout_font_size(operation);
     }
 
+    /// Gamma above 1.0 thickens the text, contrast from 0.0 to 1.0
+    /// thickens text darker than its background.
+    #[inline]
+    pub fn set_text_gamma(&mut self, gamma: f32, contrast: f32) -> &mut Self {
+        self.rich_text_brush.set_text_gamma(gamma, contrast);
+        self
+    }
+
+    /// Unsupported modes fall back to `AutoVsync`.
+    #[inline]
+    pub fn set_present_mode(&mut self, present_mode: wgpu::PresentMode) -> &mut Self {
+        self.ctx.set_present_mode(present_mode);
+        self
+    }
+
     #[inline]
     pub fn set_background_color(&mut self, color: Option<wgpu::Color>) -> &mut Self {
         self.background_color = color;
         self
     }
 
-    #[inline]
+    /// Loads the image right away, so calling it again picks up changes
+    /// to the file.
     pub fn set_background_image(&mut self, image: &ImageProperties) -> &mut Self {
-        let handle = Handle::from_path(image.path.to_owned());
-        self.graphics.bottom_layer = Some(BottomLayer {
-            should_fit: image.width.is_none() && image.height.is_none(),
-            data: types::Raster {
-                handle,
-                bounds: Rectangle {
-                    width: image.width.unwrap_or(self.ctx.size.width),
-                    height: image.height.unwrap_or(self.ctx.size.height),
-                    x: image.x,
-                    y: image.y,
-                },
-            },
-        });
+        let decoded = match image_rs::open(&image.path) {
+            Ok(decoded) => decoded,
+            Err(err) => {
+                tracing::warn!("unable to load background image {}: {err}", image.path);
+                self.graphics.bottom_layer = None;
+                return self;
+            }
+        };
+        // Blurring large images is slow without a GPU
+        let decoded = if image.blur > 0. && !self.ctx.is_software {
+            decoded.blur(image.blur)
+        } else {
+            decoded
+        };
+        let (width, height) = (decoded.width(), decoded.height());
+
+        let mut bottom_layer = BottomLayer {
+            handle: Handle::from_pixels(width, height, decoded.into_rgba8().into_raw()),
+            size: [
+                image.width.unwrap_or(width as f32),
+                image.height.unwrap_or(height as f32),
+            ],
+            has_size: image.width.is_some() || image.height.is_some(),
+            offset: [image.x, image.y],
+            fit: image.fit,
+            opacity: image.opacity.clamp(0., 1.),
+            data: Vec::new(),
+        };
+        bottom_layer.layout([self.ctx.size.width, self.ctx.size.height]);
+        self.graphics.bottom_layer = Some(bottom_layer);
         self
     }
 
+    #[inline]
+    pub fn remove_background_image(&mut self) -> &mut Self {
+        self.graphics.bottom_layer = None;
+        self
+    }
+
+    /// Renders the WGSL shader at `path` behind everything else, like
+    /// images it is read right away so calling it again reloads it.
+    /// Software adapters can't keep up with an animated shader, it's
+    /// ignored there.
+    pub fn set_background_shader(&mut self, path: &str) -> &mut Self {
+        if self.ctx.is_software {
+            tracing::warn!("background shader {path} is disabled on software rendering");
+            self.background_shader = None;
+            return self;
+        }
+
+        let brush = std::fs::read_to_string(path)
+            .map_err(|err| err.to_string())
+            .and_then(|source| BackgroundShaderBrush::new(&self.ctx, &source));
+        match brush {
+            Ok(brush) => self.background_shader = Some(brush),
+            Err(err) => {
+                tracing::warn!("unable to load background shader {path}: {err}");
+                self.background_shader = None;
+            }
+        }
+        self
+    }
+
+    #[inline]
+    pub fn remove_background_shader(&mut self) -> &mut Self {
+        self.background_shader = None;
+        self
+    }
+
+    /// Background shaders are animated so need a new frame regularly.
+    #[inline]
+    pub fn has_background_shader(&self) -> bool {
+        self.background_shader.is_some()
+    }
+
+    /// Translates the text vertically by `offset` logical pixels, lines
+    /// outside of the text area are clipped.
+    #[inline]
+    pub fn set_scroll_offset(&mut self, offset: f32) {
+        self.state.scroll_offset = offset;
+    }
+
+    /// Frames are rendered by a software rasterizer instead of a GPU.
+    #[inline]
+    pub fn is_software_rendered(&self) -> bool {
+        self.ctx.is_software
+    }
+
     #[inline]
     pub fn content(&mut self) -> &mut crate::Content {
         self.state.content()
@@ -227,11 +376,11 @@ impl Sugarloaf<'_> {
     pub fn resize(&mut self, width: u32, height: u32) {
         self.ctx.resize(width, height);
         self.state.compute_layout_resize(width, height);
+        if let Some(hdr_output) = &mut self.hdr_output {
+            hdr_output.resize(&self.ctx);
+        }
         if let Some(bottom_layer) = &mut self.graphics.bottom_layer {
-            if bottom_layer.should_fit {
-                bottom_layer.data.bounds.width = self.ctx.size.width;
-                bottom_layer.data.bounds.height = self.ctx.size.height;
-            }
+            bottom_layer.layout([self.ctx.size.width, self.ctx.size.height]);
         }
     }
 
@@ -240,10 +389,7 @@ impl Sugarloaf<'_> {
         self.ctx.scale = scale;
         self.state.compute_layout_rescale(scale);
         if let Some(bottom_layer) = &mut self.graphics.bottom_layer {
-            if bottom_layer.should_fit {
-                bottom_layer.data.bounds.width = self.ctx.size.width;
-                bottom_layer.data.bounds.height = self.ctx.size.height;
-            }
+            bottom_layer.layout([self.ctx.size.width, self.ctx.size.height]);
         }
     }
 
@@ -252,8 +398,9 @@ impl Sugarloaf<'_> {
         self.state.reset_compositors();
     }
 
+    /// Computes the objects of the frame, before it's drawn.
     #[inline]
-    pub fn render(&mut self) {
+    fn compute(&mut self) {
         self.state.compute_changes();
         self.state.compute_dimensions(&mut self.rich_text_brush);
 
@@ -265,106 +412,200 @@ impl Sugarloaf<'_> {
             &mut self.ctx,
             &mut self.graphics,
         );
+    }
 
-        match self.ctx.surface.get_current_texture() {
-            Ok(frame) => {
-                let mut encoder = self.ctx.device.create_command_encoder(
-                    &wgpu::CommandEncoderDescriptor { label: None },
-                );
+    #[inline]
+    pub fn render(&mut self) {
+        self.compute();
 
-                let view = &frame
-                    .texture
-                    .create_view(&wgpu::TextureViewDescriptor::default());
+        // Headless instances only render with `render_to_image`.
+        let Some(surface) = &self.ctx.surface else {
+            self.reset();
+            return;
+        };
 
-                if let Some(layer) = &self.graphics.bottom_layer {
-                    self.layer_brush
-                        .prepare(&mut encoder, &mut self.ctx, &[&layer.data]);
+        match surface.get_current_texture() {
+            Ok(frame) => {
+                // sRGB surfaces are rendered through a view without the suffix.
+                let frame_view =
+                    frame.texture.create_view(&wgpu::TextureViewDescriptor {
+                        format: Some(self.ctx.format),
+                        ..Default::default()
+                    });
+                let (width, height) = (frame.texture.width(), frame.texture.height());
+                self.draw(&frame_view, width, height);
+                frame.present();
+            }
+            Err(error) => {
+                if error == wgpu::SurfaceError::OutOfMemory {
+                    panic!("Swapchain error: {error}. Rendering cannot continue.")
                 }
+            }
+        }
+        self.reset();
+    }
 
-                if self.graphics.has_graphics_on_top_layer() {
-                    for request in &self.graphics.top_layer {
-                        if let Some(entry) = self.graphics.get(&request.id) {
-                            self.layer_brush.prepare_with_handle(
-                                &mut encoder,
-                                &mut self.ctx,
-                                &entry.handle,
-                                &Rectangle {
-                                    width: request.width.unwrap_or(entry.width),
-                                    height: request.height.unwrap_or(entry.height),
-                                    x: request.pos_x,
-                                    y: request.pos_y,
-                                },
-                            );
-                        }
-                    }
-                }
+    /// Renders the frame into an offscreen texture of the window size and
+    /// reads it back, it works with or without a window and goes through
+    /// the same brushes as [`Sugarloaf::render`].
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn render_to_image(&mut self) -> Result<image_rs::RgbaImage, OffscreenError> {
+        self.compute();
 
-                {
-                    let load = if let Some(background_color) = self.background_color {
-                        wgpu::LoadOp::Clear(background_color)
-                    } else {
-                        wgpu::LoadOp::Load
-                    };
-
-                    let mut rpass =
-                        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                            timestamp_writes: None,
-                            occlusion_query_set: None,
-                            label: None,
-                            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                                view,
-                                resolve_target: None,
-                                ops: wgpu::Operations {
-                                    load,
-                                    store: wgpu::StoreOp::Store,
-                                },
-                            })],
-                            depth_stencil_attachment: None,
-                        });
-
-                    if self.graphics.bottom_layer.is_some() {
-                        self.layer_brush.render(0, &mut rpass, None);
-                    }
-
-                    if self.graphics.has_graphics_on_top_layer() {
-                        let range_request = if self.graphics.bottom_layer.is_some() {
-                            1..(self.graphics.top_layer.len() + 1)
-                        } else {
-                            0..self.graphics.top_layer.len()
-                        };
-                        for request in range_request {
-                            self.layer_brush.render(request, &mut rpass, None);
-                        }
-                    }
-
-                    self.rich_text_brush
-                        .render(&mut self.ctx, &self.state, &mut rpass);
-
-                    self.quad_brush
-                        .render(&mut self.ctx, &self.state, &mut rpass);
-
-                    self.rect_brush
-                        .render(&mut rpass, &self.state, &mut self.ctx);
-
-                    self.text_brush.render(&mut self.ctx, &mut rpass);
-                }
+        let (width, height) = (
+            (self.ctx.size.width as u32).max(1),
+            (self.ctx.size.height as u32).max(1),
+        );
+        // HDR frames are converted to the surface format after rendering.
+        let format = match &self.hdr_output {
+            Some(_) => self.ctx.output.surface,
+            None => self.ctx.format,
+        };
+        let texture = offscreen::create_target(&self.ctx.device, format, width, height);
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        self.draw(&view, width, height);
+        self.reset();
+
+        let pixels =
+            offscreen::read_texture(&self.ctx.device, &self.ctx.queue, &texture)?;
+        Ok(image_rs::RgbaImage::from_raw(width, height, pixels)
+            .expect("pixels of the whole frame"))
+    }
+
+    /// Renders the frame with [`Sugarloaf::render_to_image`] and saves it
+    /// as a PNG.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn screenshot(
+        &mut self,
+        path: impl AsRef<std::path::Path>,
+    ) -> Result<(), OffscreenError> {
+        self.render_to_image()?
+            .save_with_format(path, image_rs::ImageFormat::Png)
+            .map_err(OffscreenError::Image)
+    }
 
-                if self.graphics.bottom_layer.is_some()
-                    || self.graphics.has_graphics_on_top_layer()
-                {
-                    self.layer_brush.end_frame();
-                    self.graphics.clear_top_layer();
+    /// Draws the computed frame to `frame_view`.
+    fn draw(&mut self, frame_view: &wgpu::TextureView, width: u32, height: u32) {
+        let mut encoder = self
+            .ctx
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+
+        let view = match &self.hdr_output {
+            Some(hdr_output) => hdr_output.view(),
+            None => frame_view,
+        };
+
+        self.rich_text_brush.upload(&mut self.ctx, &mut encoder);
+
+        if let Some(layer) = &self.graphics.bottom_layer {
+            self.layer_brush.prepare(
+                &mut encoder,
+                &mut self.ctx,
+                &layer.data.iter().collect::<Vec<_>>(),
+            );
+        }
+
+        if self.graphics.has_graphics_on_top_layer() {
+            for request in &self.graphics.top_layer {
+                if let Some(entry) = self.graphics.get(&request.id) {
+                    self.layer_brush.prepare_with_handle(
+                        &mut encoder,
+                        &mut self.ctx,
+                        &entry.handle,
+                        &Rectangle {
+                            width: request.width.unwrap_or(entry.width),
+                            height: request.height.unwrap_or(entry.height),
+                            x: request.pos_x,
+                            y: request.pos_y,
+                        },
+                    );
                 }
+            }
+        }
 
-                self.ctx.queue.submit(Some(encoder.finish()));
-                frame.present();
+        {
+            let load = if let Some(background_color) = self.background_color {
+                wgpu::LoadOp::Clear(background_color)
+            } else {
+                wgpu::LoadOp::Load
+            };
+
+            let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                timestamp_writes: None,
+                occlusion_query_set: None,
+                label: None,
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load,
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+            });
+
+            if let Some(background_shader) = &self.background_shader {
+                background_shader.render(&mut self.ctx, &mut rpass);
             }
-            Err(error) => {
-                if error == wgpu::SurfaceError::OutOfMemory {
-                    panic!("Swapchain error: {error}. Rendering cannot continue.")
+
+            if self.graphics.bottom_layer.is_some() {
+                self.layer_brush.render(0, &mut rpass, None);
+            }
+
+            if self.graphics.has_graphics_on_top_layer() {
+                let range_request = if self.graphics.bottom_layer.is_some() {
+                    1..(self.graphics.top_layer.len() + 1)
+                } else {
+                    0..self.graphics.top_layer.len()
+                };
+                for request in range_request {
+                    self.layer_brush.render(request, &mut rpass, None);
                 }
             }
+
+            // Lines translated by smooth scrolling stay in the text area.
+            let is_clipped = self.state.scroll_offset != 0.;
+            if is_clipped {
+                let (top, text_height) = self.state.text_area();
+                let top = top.min(height);
+                rpass.set_scissor_rect(0, top, width, text_height.min(height - top));
+            }
+
+            // Text over images and shaders isn't corrected against them
+            let has_solid_background =
+                self.graphics.bottom_layer.is_none() && self.background_shader.is_none();
+            self.rich_text_brush
+                .set_background(self.background_color.filter(|_| has_solid_background));
+            self.rich_text_brush
+                .render(&mut self.ctx, &self.state, &mut rpass);
+
+            if is_clipped {
+                rpass.set_scissor_rect(0, 0, width, height);
+            }
+
+            self.quad_brush
+                .render(&mut self.ctx, &self.state, &mut rpass);
+
+            self.rect_brush
+                .render(&mut rpass, &self.state, &mut self.ctx);
+
+            self.text_brush.render(&mut self.ctx, &mut rpass);
         }
-        self.reset();
+
+        if let Some(hdr_output) = &self.hdr_output {
+            hdr_output.render(&mut encoder, frame_view);
+        }
+
+        if self.graphics.bottom_layer.is_some()
+            || self.graphics.has_graphics_on_top_layer()
+        {
+            self.layer_brush.end_frame();
+            self.graphics.clear_top_layer();
+        }
+
+        self.ctx.queue.submit(Some(encoder.finish()));
+        self.rich_text_brush.recall();
     }
 }
@@ -3,6 +3,8 @@
 // This source code is licensed under the MIT license found in the
 // LICENSE file in the root directory of this source tree.
 
+use crate::components::core::shapes::Rectangle;
+use crate::sugarloaf::primitives::ImageFit;
 use crate::sugarloaf::types;
 use crate::sugarloaf::Handle;
 use image_rs::DynamicImage;
@@ -28,8 +30,90 @@ pub struct GraphicRenderRequest {
 }
 
 pub struct BottomLayer {
-    pub data: types::Raster,
-    pub should_fit: bool,
+    pub handle: Handle,
+    /// Size of the image, or the `width` and `height` of its properties.
+    pub size: [f32; 2],
+    /// Whether `width` or `height` were set.
+    pub has_size: bool,
+    pub offset: [f32; 2],
+    pub fit: ImageFit,
+    pub opacity: f32,
+    pub data: Vec<types::Raster>,
+}
+
+impl BottomLayer {
+    /// Places the image in a window of `window` size.
+    pub fn layout(&mut self, window: [f32; 2]) {
+        self.data = self
+            .bounds(window)
+            .into_iter()
+            .map(|bounds| types::Raster {
+                handle: self.handle.clone(),
+                bounds,
+                opacity: self.opacity,
+            })
+            .collect();
+    }
+
+    fn bounds(&self, window: [f32; 2]) -> Vec<Rectangle> {
+        let [x, y] = self.offset;
+        let [width, height] = [self.size[0].max(1.), self.size[1].max(1.)];
+        let centered = |width: f32, height: f32| Rectangle {
+            x: (window[0] - width) / 2. + x,
+            y: (window[1] - height) / 2. + y,
+            width,
+            height,
+        };
+
+        match self.fit {
+            ImageFit::Fill if self.has_size => vec![Rectangle {
+                x,
+                y,
+                width,
+                height,
+            }],
+            ImageFit::Fill => vec![Rectangle {
+                x,
+                y,
+                width: window[0],
+                height: window[1],
+            }],
+            ImageFit::Cover => {
+                let scale = (window[0] / width).max(window[1] / height);
+                vec![centered(width * scale, height * scale)]
+            }
+            ImageFit::Contain => {
+                let scale = (window[0] / width).min(window[1] / height);
+                vec![centered(width * scale, height * scale)]
+            }
+            ImageFit::Center => vec![centered(width, height)],
+            ImageFit::Tile => {
+                // The offset moves the pattern, tiles start before the
+                // window edge when needed.
+                let start = |offset: f32, size: f32| match offset.rem_euclid(size) {
+                    start if start > 0. => start - size,
+                    _ => 0.,
+                };
+                let (start_x, start_y) = (start(x, width), start(y, height));
+                let mut tiles = Vec::new();
+                let mut tile_y = start_y;
+                while tile_y < window[1] {
+                    let mut tile_x = start_x;
+                    while tile_x < window[0] {
+                        tiles.push(Rectangle {
+                            x: tile_x,
+                            y: tile_y,
+                            width,
+                            height,
+                        });
+                        tile_x += width;
+                    }
+                    tile_y += height;
+                }
+                tiles
+            }
+        }
+    }
 }
 
 #[derive(Default)]
@@ -363,3 +447,66 @@ fn check_opaque_region() {
     assert!(graphic.is_filled(0, 0, 3, 3));
     assert!(!graphic.is_filled(1, 1, 4, 4));
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layer(fit: ImageFit, size: [f32; 2], offset: [f32; 2]) -> BottomLayer {
+        BottomLayer {
+            handle: Handle::from_pixels(1, 1, vec![0; 4]),
+            size,
+            has_size: false,
+            offset,
+            fit,
+            opacity: 1.0,
+            data: Vec::new(),
+        }
+    }
+
+    fn rect(x: f32, y: f32, width: f32, height: f32) -> Rectangle {
+        Rectangle {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    #[test]
+    fn background_fit_modes() {
+        let window = [400., 200.];
+        let size = [100., 100.];
+
+        let fill = layer(ImageFit::Fill, size, [0., 0.]);
+        assert_eq!(fill.bounds(window), vec![rect(0., 0., 400., 200.)]);
+
+        let mut sized = layer(ImageFit::Fill, size, [10., 20.]);
+        sized.has_size = true;
+        assert_eq!(sized.bounds(window), vec![rect(10., 20., 100., 100.)]);
+
+        let cover = layer(ImageFit::Cover, size, [0., 0.]);
+        assert_eq!(cover.bounds(window), vec![rect(0., -100., 400., 400.)]);
+
+        let contain = layer(ImageFit::Contain, size, [0., 0.]);
+        assert_eq!(contain.bounds(window), vec![rect(100., 0., 200., 200.)]);
+
+        let center = layer(ImageFit::Center, size, [0., 0.]);
+        assert_eq!(center.bounds(window), vec![rect(150., 50., 100., 100.)]);
+    }
+
+    #[test]
+    fn background_tiles_cover_the_window() {
+        let tile = layer(ImageFit::Tile, [100., 100.], [0., 0.]);
+        let tiles = tile.bounds([250., 150.]);
+        assert_eq!(tiles.len(), 6);
+        assert_eq!(tiles[0], rect(0., 0., 100., 100.));
+        assert_eq!(tiles[5], rect(200., 100., 100., 100.));
+
+        // Offsets shift the pattern without leaving gaps
+        let tile = layer(ImageFit::Tile, [100., 100.], [30., 0.]);
+        let tiles = tile.bounds([250., 100.]);
+        assert_eq!(tiles[0], rect(-70., 0., 100., 100.));
+        assert_eq!(tiles.len(), 4);
+    }
+}
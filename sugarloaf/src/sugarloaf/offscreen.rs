@@ -0,0 +1,176 @@
+use core::fmt;
+
+#[derive(Debug)]
+pub enum OffscreenError {
+    /// Only 8 bit RGBA and BGRA frames can be read back, HDR ones aren't.
+    UnsupportedFormat(wgpu::TextureFormat),
+    /// The frame couldn't be copied out of the GPU.
+    Map(wgpu::BufferAsyncError),
+    Image(image_rs::ImageError),
+}
+
+impl fmt::Display for OffscreenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnsupportedFormat(format) => {
+                write!(f, "frames in {format:?} can't be read back")
+            }
+            Self::Map(error) => write!(f, "failed to read the frame back: {error}"),
+            Self::Image(error) => write!(f, "failed to encode the frame: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for OffscreenError {}
+
+/// Texture the frame is rendered to, it can be copied out of the GPU.
+pub fn create_target(
+    device: &wgpu::Device,
+    format: wgpu::TextureFormat,
+    width: u32,
+    height: u32,
+) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("sugarloaf::offscreen target"),
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+        view_formats: &[],
+    })
+}
+
+/// Copied rows are padded to `COPY_BYTES_PER_ROW_ALIGNMENT`.
+#[inline]
+pub fn padded_bytes_per_row(width: u32) -> u32 {
+    let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+    (4 * width).div_ceil(align) * align
+}
+
+/// Whether pixels of `format` are stored as BGRA, otherwise RGBA.
+#[inline]
+fn is_bgra(format: wgpu::TextureFormat) -> Result<bool, OffscreenError> {
+    match format {
+        wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => {
+            Ok(false)
+        }
+        wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => Ok(true),
+        format => Err(OffscreenError::UnsupportedFormat(format)),
+    }
+}
+
+/// Strips the row padding off the copied frame, BGRA pixels are swapped
+/// to RGBA.
+pub fn unpad_rows(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    format: wgpu::TextureFormat,
+) -> Result<Vec<u8>, OffscreenError> {
+    let is_bgra = is_bgra(format)?;
+
+    let padded = padded_bytes_per_row(width) as usize;
+    let row = 4 * width as usize;
+    let mut pixels = Vec::with_capacity(row * height as usize);
+    for line in data.chunks(padded).take(height as usize) {
+        pixels.extend_from_slice(&line[..row]);
+    }
+
+    if is_bgra {
+        for pixel in pixels.chunks_exact_mut(4) {
+            pixel.swap(0, 2);
+        }
+    }
+    Ok(pixels)
+}
+
+/// Copies `texture` out of the GPU, blocks until it's done.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn read_texture(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    texture: &wgpu::Texture,
+) -> Result<Vec<u8>, OffscreenError> {
+    let (width, height) = (texture.width(), texture.height());
+    let format = texture.format();
+    // Checked before copying anything.
+    is_bgra(format)?;
+
+    let bytes_per_row = padded_bytes_per_row(width);
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("sugarloaf::offscreen readback"),
+        size: (bytes_per_row * height) as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    let mut encoder =
+        device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+    encoder.copy_texture_to_buffer(
+        texture.as_image_copy(),
+        wgpu::ImageCopyBuffer {
+            buffer: &buffer,
+            layout: wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(bytes_per_row),
+                rows_per_image: Some(height),
+            },
+        },
+        texture.size(),
+    );
+    queue.submit(Some(encoder.finish()));
+
+    let slice = buffer.slice(..);
+    let (sender, receiver) = std::sync::mpsc::channel();
+    slice.map_async(wgpu::MapMode::Read, move |result| {
+        let _ = sender.send(result);
+    });
+    device.poll(wgpu::Maintain::Wait);
+    receiver
+        .recv()
+        .unwrap_or(Err(wgpu::BufferAsyncError))
+        .map_err(OffscreenError::Map)?;
+
+    let pixels = unpad_rows(&slice.get_mapped_range(), width, height, format);
+    buffer.unmap();
+    pixels
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_padded_bytes_per_row() {
+        assert_eq!(padded_bytes_per_row(1), 256);
+        assert_eq!(padded_bytes_per_row(64), 256);
+        assert_eq!(padded_bytes_per_row(65), 512);
+    }
+
+    #[test]
+    fn test_unpad_rows() {
+        // Two rows of a single pixel, each padded to 256 bytes
+        let mut data = vec![0; 512];
+        data[..4].copy_from_slice(&[1, 2, 3, 4]);
+        data[256..260].copy_from_slice(&[5, 6, 7, 8]);
+
+        assert_eq!(
+            unpad_rows(&data, 1, 2, wgpu::TextureFormat::Rgba8Unorm).unwrap(),
+            vec![1, 2, 3, 4, 5, 6, 7, 8]
+        );
+        assert_eq!(
+            unpad_rows(&data, 1, 2, wgpu::TextureFormat::Bgra8Unorm).unwrap(),
+            vec![3, 2, 1, 4, 7, 6, 5, 8]
+        );
+        assert!(matches!(
+            unpad_rows(&data, 1, 2, wgpu::TextureFormat::Rgba16Float),
+            Err(OffscreenError::UnsupportedFormat(_))
+        ));
+    }
+}
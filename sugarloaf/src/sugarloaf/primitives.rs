@@ -4,14 +4,16 @@
 // LICENSE file in the root directory of this source tree.
 
 use crate::sugarloaf::Rect;
-use crate::ComposedQuad;
+use crate::{ComposedQuad, Minimap, PaneFrames};
 use serde::Deserialize;
 
 #[derive(Debug, PartialEq, Copy, Clone)]
 pub enum SugarCursor {
     Block([f32; 4]),
-    Caret([f32; 4]),
-    Underline([f32; 4]),
+    /// Color and width of the beam.
+    Caret([f32; 4], f32),
+    /// Color and height of the line.
+    Underline([f32; 4], f32),
 }
 
 #[derive(Copy, PartialEq, Default, Debug, Clone)]
@@ -21,7 +23,24 @@ pub struct SugarloafStyle {
     pub text_scale: f32,
 }
 
-#[derive(Default, Clone, Deserialize, Debug, PartialEq)]
+/// How an image fills the window.
+#[derive(Default, Clone, Copy, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageFit {
+    /// Stretched to the window, or drawn with `width` and `height`.
+    #[default]
+    Fill,
+    /// Scaled to cover the window, keeping its aspect ratio.
+    Cover,
+    /// Scaled to fit in the window, keeping its aspect ratio.
+    Contain,
+    /// Repeated over the window.
+    Tile,
+    /// Centered in the window.
+    Center,
+}
+
+#[derive(Clone, Deserialize, Debug, PartialEq)]
 pub struct ImageProperties {
     #[serde(default = "String::default")]
     pub path: String,
@@ -33,6 +52,32 @@ pub struct ImageProperties {
     pub x: f32,
     #[serde(default = "f32::default")]
     pub y: f32,
+    #[serde(default = "ImageFit::default")]
+    pub fit: ImageFit,
+    #[serde(default = "default_image_opacity")]
+    pub opacity: f32,
+    /// Strength of the gaussian blur, `0` disables it.
+    #[serde(default = "f32::default")]
+    pub blur: f32,
+}
+
+fn default_image_opacity() -> f32 {
+    1.0
+}
+
+impl Default for ImageProperties {
+    fn default() -> Self {
+        Self {
+            path: String::default(),
+            width: None,
+            height: None,
+            x: 0.,
+            y: 0.,
+            fit: ImageFit::default(),
+            opacity: default_image_opacity(),
+            blur: 0.,
+        }
+    }
 }
 
 #[derive(Default, Debug, PartialEq, Clone)]
@@ -86,4 +131,6 @@ pub enum Object {
     Rect(Rect),
     Text(Text),
     Quad(ComposedQuad),
+    Minimap(Minimap),
+    PaneFrames(PaneFrames),
 }
@@ -19,6 +19,9 @@ pub struct SugarState {
     objects: Vec<Object>,
     pub layout: SugarloafLayout,
     pub compositors: SugarCompositors,
+    /// Vertical translation of the text in logical pixels, used by
+    /// smooth scrolling.
+    pub scroll_offset: f32,
 }
 
 impl SugarState {
@@ -33,12 +36,23 @@ impl SugarState {
             layout: initial_layout,
             objects: vec![],
             latest_change: SugarTreeDiff::Repaint,
+            scroll_offset: 0.,
         };
 
         state.compositors.advanced.set_font_features(font_features);
         state
     }
 
+    /// Area of the text lines in physical pixels, text translated by
+    /// `scroll_offset` is clipped to it.
+    pub fn text_area(&self) -> (u32, u32) {
+        let top = self.layout.margin.top_y * self.layout.dimensions.scale;
+        let height = self.layout.lines as f32
+            * self.layout.dimensions.height
+            * self.layout.line_height;
+        (top.max(0.) as u32, height.max(0.) as u32)
+    }
+
     #[inline]
     pub fn compute_layout_resize(&mut self, width: u32, height: u32) {
         self.layout.resize(width, height).update();
@@ -121,7 +135,7 @@ impl SugarState {
         context: &mut super::Context,
         graphics: &mut Graphics,
     ) {
-        advance_brush.prepare(context, self, graphics);
+        advance_brush.prepare(self, graphics);
         rect_brush.resize(context);
         quad_brush.resize(context);
 
@@ -146,6 +160,12 @@ impl SugarState {
                 Object::Quad(composed_quad) => {
                     self.compositors.elementary.quads.push(*composed_quad);
                 }
+                Object::Minimap(minimap) => {
+                    minimap.rects(&mut self.compositors.elementary.rects);
+                }
+                Object::PaneFrames(pane_frames) => {
+                    pane_frames.rects(&mut self.compositors.elementary.rects);
+                }
             }
         }
     }
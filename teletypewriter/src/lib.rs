@@ -47,8 +47,8 @@ pub trait ProcessReadWrite {
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum ChildEvent {
-    /// Indicates the child has exited.
-    Exited,
+    /// Indicates the child has exited, with its exit code when known.
+    Exited(Option<i32>),
 }
 
 pub trait EventedPty: ProcessReadWrite {
@@ -58,6 +58,12 @@ pub trait EventedPty: ProcessReadWrite {
     ///
     /// Returns `Some(event)` on success, or `None` if there are no events to retrieve.
     fn next_child_event(&mut self) -> Option<ChildEvent>;
+
+    /// Whether a hang up ends the session, for backends without a child
+    /// process to report its exit.
+    fn exits_on_hangup(&self) -> bool {
+        false
+    }
 }
 
 #[derive(Debug, Clone)]
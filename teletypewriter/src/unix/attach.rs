@@ -0,0 +1,426 @@
+// Backends that attach a terminal to an existing device or stream instead of
+// spawning a shell: serial ports, unix sockets, pipes and the stdin of Rio.
+
+use crate::{ChildEvent, EventedPty, ProcessReadWrite, WinsizeBuilder};
+use corcovado::unix::EventedFd;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Error, ErrorKind};
+use std::mem::MaybeUninit;
+use std::os::fd::{AsFd, AsRawFd, OwnedFd};
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+
+pub const DEFAULT_BAUD_RATE: u32 = 115_200;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum FlowControl {
+    #[default]
+    None,
+    /// XON/XOFF.
+    Software,
+    /// RTS/CTS.
+    Hardware,
+}
+
+impl FlowControl {
+    fn parse(value: &str) -> Option<FlowControl> {
+        match value.to_lowercase().as_str() {
+            "none" => Some(FlowControl::None),
+            "software" | "xonxoff" => Some(FlowControl::Software),
+            "hardware" | "rtscts" => Some(FlowControl::Hardware),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SerialSettings {
+    pub baud_rate: u32,
+    pub flow_control: FlowControl,
+}
+
+impl Default for SerialSettings {
+    fn default() -> SerialSettings {
+        SerialSettings {
+            baud_rate: DEFAULT_BAUD_RATE,
+            flow_control: FlowControl::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Target {
+    /// `serial:<path>[:<baud>[:<flow-control>]]`
+    Serial {
+        path: PathBuf,
+        settings: SerialSettings,
+    },
+    /// `unix:<path>`
+    Socket(PathBuf),
+    /// `pipe:<path>`, any path that can be opened for reading and writing
+    /// such as a FIFO or a character device.
+    Pipe(PathBuf),
+    /// `stdin:`, the output of `somecmd | rio --stdin`. Read only, there is
+    /// nothing to send input to.
+    Stdin,
+}
+
+impl Target {
+    /// Parse a command as an attach target, `Ok(None)` means the command
+    /// is a regular program. Missing serial settings are taken from
+    /// `defaults`.
+    pub fn parse(command: &str, defaults: SerialSettings) -> io::Result<Option<Target>> {
+        let Some((kind, rest)) = command.split_once(':') else {
+            return Ok(None);
+        };
+
+        let invalid = |message: String| Error::new(ErrorKind::InvalidInput, message);
+        if rest.is_empty() && matches!(kind, "serial" | "unix" | "pipe") {
+            return Err(invalid(format!("missing path in {command}")));
+        }
+
+        let target = match kind {
+            "serial" => {
+                let mut parts = rest.split(':');
+                let path = PathBuf::from(parts.next().unwrap_or_default());
+                let mut settings = defaults;
+                if let Some(baud_rate) = parts.next() {
+                    settings.baud_rate = baud_rate
+                        .parse()
+                        .map_err(|_| invalid(format!("invalid baud rate {baud_rate}")))?;
+                }
+                if let Some(flow_control) = parts.next() {
+                    settings.flow_control =
+                        FlowControl::parse(flow_control).ok_or_else(|| {
+                            invalid(format!("invalid flow control {flow_control}"))
+                        })?;
+                }
+                if parts.next().is_some() {
+                    return Err(invalid(format!("too many settings in {command}")));
+                }
+
+                Target::Serial { path, settings }
+            }
+            "unix" => Target::Socket(PathBuf::from(rest)),
+            "pipe" => Target::Pipe(PathBuf::from(rest)),
+            "stdin" if rest.is_empty() => Target::Stdin,
+            "stdin" => return Err(invalid(format!("unexpected path in {command}"))),
+            _ => return Ok(None),
+        };
+
+        Ok(Some(target))
+    }
+}
+
+/// A device or stream driven like a pty. There is no child process, the
+/// session ends when the other side hangs up.
+pub struct Attached {
+    file: File,
+    // Input goes somewhere else than `file`, only for read only streams.
+    output: Option<File>,
+    token: corcovado::Token,
+    exit_token: corcovado::Token,
+}
+
+impl Attached {
+    #[inline]
+    pub fn fd(&self) -> i32 {
+        self.file.as_raw_fd()
+    }
+}
+
+pub fn attach(target: &Target) -> io::Result<Attached> {
+    let mut output = None;
+    let file = match target {
+        Target::Serial { path, settings } => {
+            let file = open_read_write(path)?;
+            configure_serial(&file, settings)?;
+            file
+        }
+        Target::Socket(path) => {
+            let stream = UnixStream::connect(path)?;
+            stream.set_nonblocking(true)?;
+            File::from(OwnedFd::from(stream))
+        }
+        Target::Pipe(path) => open_read_write(path)?,
+        Target::Stdin => {
+            let stdin = io::stdin();
+            if unsafe { libc::isatty(stdin.as_raw_fd()) } == 1 {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    "stdin is a terminal, pipe the output of a command into it",
+                ));
+            }
+
+            let file = File::from(stdin.as_fd().try_clone_to_owned()?);
+            set_nonblocking(&file)?;
+            output = Some(OpenOptions::new().write(true).open("/dev/null")?);
+            file
+        }
+    };
+
+    Ok(Attached {
+        file,
+        output,
+        token: 0.into(),
+        exit_token: 0.into(),
+    })
+}
+
+fn open_read_write(path: &PathBuf) -> io::Result<File> {
+    OpenOptions::new()
+        .read(true)
+        .write(true)
+        .custom_flags(libc::O_NOCTTY | libc::O_NONBLOCK)
+        .open(path)
+}
+
+fn set_nonblocking(file: &File) -> io::Result<()> {
+    let fd = file.as_raw_fd();
+    unsafe {
+        let flags = libc::fcntl(fd, libc::F_GETFL);
+        if flags == -1 || libc::fcntl(fd, libc::F_SETFL, flags | libc::O_NONBLOCK) == -1 {
+            return Err(Error::last_os_error());
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn baud_rate_speed(baud_rate: u32) -> Option<libc::speed_t> {
+    Some(match baud_rate {
+        50 => libc::B50,
+        75 => libc::B75,
+        110 => libc::B110,
+        134 => libc::B134,
+        150 => libc::B150,
+        200 => libc::B200,
+        300 => libc::B300,
+        600 => libc::B600,
+        1200 => libc::B1200,
+        1800 => libc::B1800,
+        2400 => libc::B2400,
+        4800 => libc::B4800,
+        9600 => libc::B9600,
+        19200 => libc::B19200,
+        38400 => libc::B38400,
+        57600 => libc::B57600,
+        115200 => libc::B115200,
+        230400 => libc::B230400,
+        460800 => libc::B460800,
+        500000 => libc::B500000,
+        576000 => libc::B576000,
+        921600 => libc::B921600,
+        1000000 => libc::B1000000,
+        1152000 => libc::B1152000,
+        1500000 => libc::B1500000,
+        2000000 => libc::B2000000,
+        2500000 => libc::B2500000,
+        3000000 => libc::B3000000,
+        3500000 => libc::B3500000,
+        4000000 => libc::B4000000,
+        _ => return None,
+    })
+}
+
+// BSD and macOS take the baud rate itself as the speed.
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn baud_rate_speed(baud_rate: u32) -> Option<libc::speed_t> {
+    Some(baud_rate as libc::speed_t)
+}
+
+/// Put the device in raw 8N1 mode with the given speed and flow control.
+fn configure_serial(file: &File, settings: &SerialSettings) -> io::Result<()> {
+    let fd = file.as_raw_fd();
+    let speed = baud_rate_speed(settings.baud_rate).ok_or_else(|| {
+        Error::new(
+            ErrorKind::InvalidInput,
+            format!("unsupported baud rate {}", settings.baud_rate),
+        )
+    })?;
+
+    let mut termios = MaybeUninit::<libc::termios>::uninit();
+    if unsafe { libc::tcgetattr(fd, termios.as_mut_ptr()) } != 0 {
+        return Err(Error::last_os_error());
+    }
+    let mut termios = unsafe { termios.assume_init() };
+
+    unsafe { libc::cfmakeraw(&mut termios) };
+    termios.c_cflag |= libc::CLOCAL | libc::CREAD;
+    termios.c_cflag &= !(libc::CSTOPB | libc::PARENB | libc::CRTSCTS);
+    termios.c_iflag &= !(libc::IXON | libc::IXOFF | libc::IXANY);
+    match settings.flow_control {
+        FlowControl::None => (),
+        FlowControl::Software => termios.c_iflag |= libc::IXON | libc::IXOFF,
+        FlowControl::Hardware => termios.c_cflag |= libc::CRTSCTS,
+    }
+    termios.c_cc[libc::VMIN] = 1;
+    termios.c_cc[libc::VTIME] = 0;
+
+    unsafe {
+        if libc::cfsetispeed(&mut termios, speed) != 0
+            || libc::cfsetospeed(&mut termios, speed) != 0
+            || libc::tcsetattr(fd, libc::TCSANOW, &termios) != 0
+        {
+            return Err(Error::last_os_error());
+        }
+        libc::tcflush(fd, libc::TCIOFLUSH);
+    }
+
+    Ok(())
+}
+
+impl ProcessReadWrite for Attached {
+    type Reader = File;
+    type Writer = File;
+
+    #[inline]
+    fn reader(&mut self) -> &mut File {
+        &mut self.file
+    }
+
+    #[inline]
+    fn read_token(&self) -> corcovado::Token {
+        self.token
+    }
+
+    #[inline]
+    fn writer(&mut self) -> &mut File {
+        self.output.as_mut().unwrap_or(&mut self.file)
+    }
+
+    #[inline]
+    fn write_token(&self) -> corcovado::Token {
+        self.token
+    }
+
+    /// The other side has no notion of a window size.
+    #[inline]
+    fn set_winsize(&mut self, _: WinsizeBuilder) -> Result<(), io::Error> {
+        Ok(())
+    }
+
+    #[inline]
+    fn register(
+        &mut self,
+        poll: &corcovado::Poll,
+        token: &mut dyn Iterator<Item = corcovado::Token>,
+        interest: corcovado::Ready,
+        poll_opts: corcovado::PollOpt,
+    ) -> io::Result<()> {
+        self.token = token.next().unwrap();
+        // Reserved so it never matches another event source.
+        self.exit_token = token.next().unwrap();
+        poll.register(
+            &EventedFd(&self.file.as_raw_fd()),
+            self.token,
+            interest,
+            poll_opts,
+        )
+    }
+
+    fn reregister(
+        &mut self,
+        poll: &corcovado::Poll,
+        interest: corcovado::Ready,
+        poll_opts: corcovado::PollOpt,
+    ) -> io::Result<()> {
+        poll.reregister(
+            &EventedFd(&self.file.as_raw_fd()),
+            self.token,
+            interest,
+            poll_opts,
+        )
+    }
+
+    fn deregister(&mut self, poll: &corcovado::Poll) -> io::Result<()> {
+        poll.deregister(&EventedFd(&self.file.as_raw_fd()))
+    }
+}
+
+impl EventedPty for Attached {
+    #[inline]
+    fn next_child_event(&mut self) -> Option<ChildEvent> {
+        None
+    }
+
+    #[inline]
+    fn child_event_token(&self) -> corcovado::Token {
+        self.exit_token
+    }
+
+    #[inline]
+    fn exits_on_hangup(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_targets() {
+        let defaults = SerialSettings::default();
+        assert_eq!(Target::parse("bash", defaults).unwrap(), None);
+        assert_eq!(Target::parse("http://example.com", defaults).unwrap(), None);
+        assert_eq!(
+            Target::parse("serial:/dev/ttyUSB0", defaults).unwrap(),
+            Some(Target::Serial {
+                path: PathBuf::from("/dev/ttyUSB0"),
+                settings: defaults,
+            })
+        );
+        assert_eq!(
+            Target::parse("serial:/dev/ttyUSB0:9600:hardware", defaults).unwrap(),
+            Some(Target::Serial {
+                path: PathBuf::from("/dev/ttyUSB0"),
+                settings: SerialSettings {
+                    baud_rate: 9600,
+                    flow_control: FlowControl::Hardware,
+                },
+            })
+        );
+        assert_eq!(
+            Target::parse("unix:/tmp/console.sock", defaults).unwrap(),
+            Some(Target::Socket(PathBuf::from("/tmp/console.sock")))
+        );
+        assert_eq!(
+            Target::parse("pipe:/tmp/fifo", defaults).unwrap(),
+            Some(Target::Pipe(PathBuf::from("/tmp/fifo")))
+        );
+        assert_eq!(
+            Target::parse("stdin:", defaults).unwrap(),
+            Some(Target::Stdin)
+        );
+
+        assert!(Target::parse("serial:", defaults).is_err());
+        assert!(Target::parse("serial:/dev/ttyS0:fast", defaults).is_err());
+        assert!(Target::parse("serial:/dev/ttyS0:9600:rts", defaults).is_err());
+        assert!(Target::parse("serial:/dev/ttyS0:9600:none:1", defaults).is_err());
+        assert!(Target::parse("stdin:/tmp/fifo", defaults).is_err());
+    }
+
+    #[test]
+    fn attach_socket() {
+        use std::io::{Read, Write};
+        use std::os::unix::net::UnixListener;
+
+        let path =
+            std::env::temp_dir().join(format!("rio-attach-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let listener = UnixListener::bind(&path).unwrap();
+
+        let mut attached = attach(&Target::Socket(path.clone())).unwrap();
+        let (mut peer, _) = listener.accept().unwrap();
+
+        attached.writer().write_all(b"ping").unwrap();
+        let mut buf = [0; 4];
+        peer.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"ping");
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -60,6 +60,8 @@ impl From<IntoStringError> for Error {
 mod sys {
     use std::os::raw::{c_char, c_int, c_longlong, c_void};
 
+    pub const KERN_PROCARGS2: c_int = 49;
+    pub const PROC_PIDTASKINFO: c_int = 4;
     pub const PROC_PIDVNODEPATHINFO: c_int = 9;
 
     type gid_t = c_int;
@@ -122,9 +124,43 @@ mod sys {
         pub pvi_rdir: vnode_info_path,
     }
 
+    #[repr(C)]
+    #[derive(Debug, Copy, Clone)]
+    pub struct proc_taskinfo {
+        pub pti_virtual_size: u64,
+        pub pti_resident_size: u64,
+        pub pti_total_user: u64,
+        pub pti_total_system: u64,
+        pub pti_threads_user: u64,
+        pub pti_threads_system: u64,
+        pub pti_policy: i32,
+        pub pti_faults: i32,
+        pub pti_pageins: i32,
+        pub pti_cow_faults: i32,
+        pub pti_messages_sent: i32,
+        pub pti_messages_received: i32,
+        pub pti_syscalls_mach: i32,
+        pub pti_syscalls_unix: i32,
+        pub pti_csw: i32,
+        pub pti_threadnum: i32,
+        pub pti_numrunning: i32,
+        pub pti_priority: i32,
+    }
+
+    #[repr(C)]
+    #[derive(Debug, Copy, Clone, Default)]
+    pub struct mach_timebase_info {
+        pub numer: u32,
+        pub denom: u32,
+    }
+
     extern "C" {
+        pub fn mach_timebase_info(info: *mut mach_timebase_info) -> c_int;
+
         pub fn proc_pidpath(pid: c_int, buffer: *mut c_void, buffersize: u32) -> c_int;
 
+        pub fn proc_listallpids(buffer: *mut c_void, buffersize: c_int) -> c_int;
+
         pub fn proc_pidinfo(
             pid: c_int,
             flavor: c_int,
@@ -204,6 +240,106 @@ pub fn macos_cwd(pid: libc::c_int) -> Result<PathBuf, Error> {
     Ok(CString::from(c_str).into_string().map(PathBuf::from)?)
 }
 
+/// Arguments of a process, including the program.
+pub fn macos_process_args(pid: libc::c_int) -> Option<Vec<String>> {
+    let mut mib = [libc::CTL_KERN, sys::KERN_PROCARGS2, pid];
+    let mut size: libc::size_t = 0;
+
+    unsafe {
+        if libc::sysctl(
+            mib.as_mut_ptr(),
+            mib.len() as u32,
+            std::ptr::null_mut(),
+            &mut size,
+            std::ptr::null_mut(),
+            0,
+        ) != 0
+        {
+            return None;
+        }
+    }
+
+    let mut buffer = vec![0u8; size];
+    unsafe {
+        if libc::sysctl(
+            mib.as_mut_ptr(),
+            mib.len() as u32,
+            buffer.as_mut_ptr() as *mut libc::c_void,
+            &mut size,
+            std::ptr::null_mut(),
+            0,
+        ) != 0
+        {
+            return None;
+        }
+    }
+    buffer.truncate(size);
+
+    parse_procargs2(&buffer)
+}
+
+// KERN_PROCARGS2 is argc followed by the executable path, padding and
+// the arguments, all separated by NUL bytes.
+fn parse_procargs2(buffer: &[u8]) -> Option<Vec<String>> {
+    let argc = i32::from_ne_bytes(buffer.get(..4)?.try_into().ok()?);
+    let mut fields = buffer[4..]
+        .split(|&byte| byte == 0)
+        .skip(1)
+        .skip_while(|field| field.is_empty());
+
+    let args = (0..argc)
+        .map_while(|_| fields.next())
+        .map(|arg| String::from_utf8_lossy(arg).to_string())
+        .collect();
+    Some(args)
+}
+
+/// Processes of the session `sid`, including its leader.
+pub fn macos_session_pids(sid: libc::pid_t) -> Vec<libc::pid_t> {
+    // Called without a buffer it returns the number of processes
+    let count = unsafe { sys::proc_listallpids(std::ptr::null_mut(), 0) };
+    if count <= 0 {
+        return Vec::new();
+    }
+
+    // Room for processes started in between
+    let mut pids: Vec<libc::pid_t> = vec![0; count as usize + 32];
+    let size = (pids.len() * std::mem::size_of::<libc::pid_t>()) as c_int;
+    let count =
+        unsafe { sys::proc_listallpids(pids.as_mut_ptr() as *mut libc::c_void, size) };
+    pids.truncate(count.max(0) as usize);
+
+    pids.retain(|pid| *pid > 0 && unsafe { libc::getsid(*pid) } == sid);
+    pids
+}
+
+/// CPU times of `proc_taskinfo` are in mach absolute time units.
+pub fn macos_process_usage(pid: libc::c_int) -> Option<super::ProcessUsage> {
+    let mut info = MaybeUninit::<sys::proc_taskinfo>::uninit();
+    let info_ptr = info.as_mut_ptr() as *mut libc::c_void;
+    let size = std::mem::size_of::<sys::proc_taskinfo>() as c_int;
+
+    let info = unsafe {
+        if sys::proc_pidinfo(pid, sys::PROC_PIDTASKINFO, 0, info_ptr, size) != size {
+            return None;
+        }
+        info.assume_init()
+    };
+
+    let mut timebase = sys::mach_timebase_info::default();
+    if unsafe { sys::mach_timebase_info(&mut timebase) } != 0 || timebase.denom == 0 {
+        return None;
+    }
+
+    let ticks = (info.pti_total_user + info.pti_total_system) as u128;
+    let nanos = ticks * timebase.numer as u128 / timebase.denom as u128;
+
+    Some(super::ProcessUsage {
+        cpu_time: std::time::Duration::from_nanos(nanos as u64),
+        memory: info.pti_resident_size,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -217,4 +353,27 @@ mod tests {
             env::current_dir().ok()
         );
     }
+
+    #[test]
+    fn session_pids_include_current_process() {
+        let sid = unsafe { libc::getsid(0) };
+        assert!(macos_session_pids(sid).contains(&(process::id() as i32)));
+    }
+
+    #[test]
+    fn parse_args_of_procargs2() {
+        let mut buffer = 2i32.to_ne_bytes().to_vec();
+        buffer.extend_from_slice(b"/usr/bin/ssh\0\0\0ssh\0host\0TERM=rio\0");
+        assert_eq!(
+            parse_procargs2(&buffer),
+            Some(vec![String::from("ssh"), String::from("host")])
+        );
+        assert_eq!(parse_procargs2(&[0, 0]), None);
+    }
+
+    #[test]
+    fn usage_of_current_process() {
+        let usage = macos_process_usage(process::id() as i32).unwrap();
+        assert!(usage.memory > 0);
+    }
 }
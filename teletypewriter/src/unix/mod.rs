@@ -1,12 +1,15 @@
 #![cfg(unix)]
 
+mod attach;
 #[cfg(target_os = "macos")]
 mod macos;
+mod session;
 mod signals;
 
 extern crate libc;
 
 use crate::{ChildEvent, EventedPty, ProcessReadWrite, Winsize, WinsizeBuilder};
+pub use attach::*;
 use corcovado::unix::EventedFd;
 #[cfg(target_os = "macos")]
 use macos::*;
@@ -61,29 +64,74 @@ extern "C" {
     fn ptsname(fd: *mut libc::c_int) -> *mut libc::c_char;
 }
 
-#[cfg(target_os = "macos")]
-fn default_shell_command(shell: &str) {
-    let command_shell_string = CString::new(shell).unwrap();
-    let command_pointer = command_shell_string.as_ptr();
-    let args = CString::new("--login").unwrap();
-    let args_pointer = args.as_ptr();
-    unsafe {
-        libc::execvp(command_pointer, vec![args_pointer].as_ptr());
+/// Name used as `argv[0]`, login shells are started with a leading dash.
+fn shell_arg0(shell: &str, login: bool) -> String {
+    let name = std::path::Path::new(shell)
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_else(|| shell.into());
+    if login {
+        format!("-{name}")
+    } else {
+        name.into_owned()
     }
 }
 
-#[cfg(not(target_os = "macos"))]
-fn default_shell_command(shell: &str) {
-    let command_shell_string = CString::new(shell).unwrap();
-    let command_pointer = command_shell_string.as_ptr();
-    // let home = std::env::var("HOME").unwrap();
-    // let args = CString::new(home).unwrap();
-    // let args_pointer = args.as_ptr() as *const i8;
+/// Path of `program`, searched in `PATH` unless it already has a slash.
+/// Done before forking since `execvp` may allocate.
+fn resolve_program(program: &str) -> Option<PathBuf> {
+    use std::os::unix::fs::PermissionsExt;
+
+    if program.contains('/') {
+        return Some(PathBuf::from(program));
+    }
+
+    std::env::split_paths(&std::env::var_os("PATH")?)
+        .map(|dir| dir.join(program))
+        .find(|path| {
+            path.metadata().is_ok_and(|metadata| {
+                metadata.is_file() && metadata.permissions().mode() & 0o111 != 0
+            })
+        })
+}
+
+/// `KEY=value` entries of the current environment with `env` on top.
+fn child_environment(env: &[(String, String)]) -> Vec<CString> {
+    use std::os::unix::ffi::OsStrExt;
+
+    std::env::vars_os()
+        .filter(|(key, _)| {
+            !env.iter()
+                .any(|(name, _)| key.as_bytes() == name.as_bytes())
+        })
+        .map(|(key, value)| [key.as_bytes(), b"=", value.as_bytes()].concat())
+        .chain(
+            env.iter()
+                .map(|(key, value)| format!("{key}={value}").into_bytes()),
+        )
+        .filter_map(|entry| CString::new(entry).ok())
+        .collect()
+}
+
+/// NULL terminated array of pointers into `values`, for `execve`.
+fn null_terminated(values: &[CString]) -> Vec<*const libc::c_char> {
+    values
+        .iter()
+        .map(|value| value.as_ptr())
+        .chain(std::iter::once(ptr::null()))
+        .collect()
+}
+
+/// Runs in the forked child, everything is allocated before forking so
+/// only async-signal-safe calls are made here.
+fn default_shell_command(
+    shell: &CStr,
+    argv: &[*const libc::c_char],
+    envp: &[*const libc::c_char],
+) -> ! {
     unsafe {
-        libc::execvp(
-            command_pointer,
-            vec![command_pointer, std::ptr::null()].as_ptr(),
-        );
+        libc::execve(shell.as_ptr(), argv.as_ptr(), envp.as_ptr());
+        libc::_exit(127)
     }
 }
 
@@ -397,6 +445,8 @@ impl ShellUser {
 pub fn create_pty_with_spawn(
     shell: &str,
     args: Vec<String>,
+    env: &[(String, String)],
+    login: bool,
     working_directory: &Option<String>,
     columns: u16,
     rows: u16,
@@ -445,10 +495,11 @@ pub fn create_pty_with_spawn(
         shell_program = &user.shell;
     }
 
-    tracing::info!("spawn {:?} {:?}", shell_program, args);
+    tracing::info!("spawn {:?} {:?} login: {}", shell_program, args, login);
 
     let mut builder = {
         let mut cmd = Command::new(shell_program);
+        cmd.arg0(shell_arg0(shell_program, login));
         cmd.args(args);
         cmd
     };
@@ -475,6 +526,9 @@ pub fn create_pty_with_spawn(
             }
 
             with_args.push("--env=TERM_PROGRAM=rio".to_string());
+            for (key, value) in env {
+                with_args.push(format!("--env={key}={value}"));
+            }
 
             let output = std::process::Command::new("flatpak-spawn")
                 .args(["--host", "sh", "-c", "echo $SHELL"])
@@ -502,6 +556,7 @@ pub fn create_pty_with_spawn(
 
     builder.env("USER", user.user);
     builder.env("HOME", user.home);
+    builder.envs(env.iter().map(|(key, value)| (key, value)));
 
     unsafe {
         builder.pre_exec(move || {
@@ -581,7 +636,14 @@ pub fn create_pty_with_spawn(
 ///
 /// It returns two [`Pty`] along with respective process name [`String`] and process id (`libc::pid_`)
 ///
-pub fn create_pty_with_fork(shell: &str, columns: u16, rows: u16) -> Result<Pty, Error> {
+pub fn create_pty_with_fork(
+    shell: &str,
+    args: Vec<String>,
+    env: &[(String, String)],
+    login: bool,
+    columns: u16,
+    rows: u16,
+) -> Result<Pty, Error> {
     let mut main = 0;
     let winsize = Winsize {
         ws_row: rows as libc::c_ushort,
@@ -606,7 +668,24 @@ pub fn create_pty_with_fork(shell: &str, columns: u16, rows: u16) -> Result<Pty,
         shell_program = &user.shell;
     }
 
-    tracing::info!("fork {:?}", shell_program);
+    tracing::info!("fork {:?} {:?} login: {}", shell_program, args, login);
+
+    let to_cstring = |value: &str| {
+        CString::new(value).map_err(|err| Error::new(ErrorKind::InvalidInput, err))
+    };
+    let path = resolve_program(shell_program).ok_or_else(|| {
+        Error::new(
+            ErrorKind::NotFound,
+            format!("{shell_program} was not found in PATH"),
+        )
+    })?;
+    let command = to_cstring(&path.to_string_lossy())?;
+    let mut argv = vec![to_cstring(&shell_arg0(shell_program, login))?];
+    for arg in &args {
+        argv.push(to_cstring(arg)?);
+    }
+    let envp = child_environment(env);
+    let (argv_ptrs, envp_ptrs) = (null_terminated(&argv), null_terminated(&envp));
 
     match unsafe {
         forkpty(
@@ -616,13 +695,7 @@ pub fn create_pty_with_fork(shell: &str, columns: u16, rows: u16) -> Result<Pty,
             &winsize as *const _,
         )
     } {
-        0 => {
-            default_shell_command(shell_program);
-            Err(Error::new(
-                ErrorKind::Other,
-                format!("forkpty has reach unreachable with {}", shell_program),
-            ))
-        }
+        0 => default_shell_command(&command, &argv_ptrs, &envp_ptrs),
         id if id > 0 => {
             // TODO: Currently we fork the process and don't wait to know if led to failure
             // Whenever it happens it will just simply shut down the teletyperwriter
@@ -747,6 +820,18 @@ impl Child {
     }
 }
 
+/// Exit code from a `waitpid` status, processes killed by a signal
+/// report `128 + signal` like shells do.
+fn exit_code(status: libc::c_int) -> Option<i32> {
+    if libc::WIFEXITED(status) {
+        Some(libc::WEXITSTATUS(status))
+    } else if libc::WIFSIGNALED(status) {
+        Some(128 + libc::WTERMSIG(status))
+    } else {
+        None
+    }
+}
+
 pub fn kill_pid(pid: i32) {
     unsafe {
         libc::kill(pid, libc::SIGHUP);
@@ -797,7 +882,7 @@ impl EventedPty for Pty {
                     None
                 }
                 Ok(None) => None,
-                Ok(Some(..)) => Some(ChildEvent::Exited),
+                Ok(Some(status)) => Some(ChildEvent::Exited(exit_code(status))),
             }
         })
     }
@@ -902,6 +987,70 @@ pub fn foreground_process_name(main_fd: RawFd, shell_pid: u32) -> String {
     name
 }
 
+/// Command line of the foreground process, like `ssh host`, with the
+/// program name instead of its path.
+pub fn foreground_process_command(main_fd: RawFd, shell_pid: u32) -> String {
+    format_command(&foreground_process_args(main_fd, shell_pid))
+}
+
+/// Nested session running in the foreground like `ssh: host`, `docker: name`
+/// or `tmux`, otherwise the ssh session or container Rio is running in.
+pub fn foreground_session(main_fd: RawFd, shell_pid: u32) -> String {
+    let args = foreground_process_args(main_fd, shell_pid);
+    let mut args: Vec<&str> = args.iter().map(String::as_str).collect();
+    if let Some(program) = args.first_mut() {
+        *program = program_name(program);
+    }
+
+    session::session_badge(&args)
+        .or_else(|| session::local_session().map(str::to_owned))
+        .unwrap_or_default()
+}
+
+fn foreground_process_args(main_fd: RawFd, shell_pid: u32) -> Vec<String> {
+    let mut pid = unsafe { libc::tcgetpgrp(main_fd) };
+    if pid < 0 {
+        pid = shell_pid as libc::pid_t;
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "freebsd")))]
+    let cmdline_path = format!("/proc/{}/cmdline", pid);
+    #[cfg(target_os = "freebsd")]
+    let cmdline_path = format!("/compat/linux/proc/{}/cmdline", pid);
+
+    #[cfg(not(target_os = "macos"))]
+    let args = match std::fs::read(cmdline_path) {
+        Ok(cmdline) => cmdline
+            .split(|&byte| byte == 0)
+            .filter(|arg| !arg.is_empty())
+            .map(|arg| String::from_utf8_lossy(arg).to_string())
+            .collect(),
+        Err(..) => Vec::new(),
+    };
+
+    #[cfg(target_os = "macos")]
+    let args = macos_process_args(pid).unwrap_or_default();
+
+    args
+}
+
+fn format_command(args: &[String]) -> String {
+    let Some((program, args)) = args.split_first() else {
+        return String::new();
+    };
+
+    std::iter::once(program_name(program))
+        .chain(args.iter().map(String::as_str))
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+fn program_name(program: &str) -> &str {
+    let program = program.rsplit('/').next().unwrap_or(program);
+    // Login shells are started as `-zsh`
+    program.strip_prefix('-').unwrap_or(program)
+}
+
 pub fn foreground_process_path(
     main_fd: RawFd,
     shell_pid: u32,
@@ -925,6 +1074,127 @@ pub fn foreground_process_path(
     Ok(cwd)
 }
 
+/// Resources used by a process, `cpu_time` is the sum of user and system time.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ProcessUsage {
+    pub cpu_time: std::time::Duration,
+    pub memory: u64,
+}
+
+pub fn foreground_process_usage(main_fd: RawFd, shell_pid: u32) -> Option<ProcessUsage> {
+    let mut pid = unsafe { libc::tcgetpgrp(main_fd) };
+    if pid < 0 {
+        pid = shell_pid as libc::pid_t;
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    let usage = {
+        #[cfg(not(target_os = "freebsd"))]
+        let proc_path = format!("/proc/{}", pid);
+        #[cfg(target_os = "freebsd")]
+        let proc_path = format!("/compat/linux/proc/{}", pid);
+
+        let stat = std::fs::read_to_string(format!("{proc_path}/stat")).ok()?;
+        let statm = std::fs::read_to_string(format!("{proc_path}/statm")).ok()?;
+        let clock_ticks = unsafe { libc::sysconf(libc::_SC_CLK_TCK) };
+        let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+        if clock_ticks <= 0 || page_size <= 0 {
+            return None;
+        }
+
+        parse_proc_usage(&stat, &statm, clock_ticks as u64, page_size as u64)?
+    };
+
+    #[cfg(target_os = "macos")]
+    let usage = macos_process_usage(pid)?;
+
+    Some(usage)
+}
+
+/// Reads utime and stime (in clock ticks) out of `/proc/<pid>/stat` and
+/// the resident set (in pages) out of `/proc/<pid>/statm`.
+#[cfg(not(target_os = "macos"))]
+fn parse_proc_usage(
+    stat: &str,
+    statm: &str,
+    clock_ticks: u64,
+    page_size: u64,
+) -> Option<ProcessUsage> {
+    // The command name is between parentheses and may contain spaces.
+    let (_, fields) = stat.rsplit_once(')')?;
+    let mut fields = fields.split_whitespace().skip(11);
+    let utime: u64 = fields.next()?.parse().ok()?;
+    let stime: u64 = fields.next()?.parse().ok()?;
+
+    let resident: u64 = statm.split_whitespace().nth(1)?.parse().ok()?;
+
+    let ticks = utime + stime;
+    let cpu_time = std::time::Duration::from_secs(ticks / clock_ticks)
+        + std::time::Duration::from_nanos(
+            (ticks % clock_ticks) * 1_000_000_000 / clock_ticks,
+        );
+
+    Some(ProcessUsage {
+        cpu_time,
+        memory: resident * page_size,
+    })
+}
+
+/// Names of the processes started from the shell, like an editor or a
+/// background job, every process of its session besides the shell.
+pub fn running_processes(shell_pid: u32) -> Vec<String> {
+    // Kernel threads belong to the session 0
+    if shell_pid == 0 {
+        return Vec::new();
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    let processes = {
+        #[cfg(not(target_os = "freebsd"))]
+        let proc_path = "/proc";
+        #[cfg(target_os = "freebsd")]
+        let proc_path = "/compat/linux/proc";
+
+        let Ok(entries) = std::fs::read_dir(proc_path) else {
+            return Vec::new();
+        };
+
+        entries
+            .flatten()
+            .filter(|entry| {
+                entry
+                    .file_name()
+                    .to_str()
+                    .and_then(|pid| pid.parse::<u32>().ok())
+                    .is_some_and(|pid| pid != shell_pid)
+            })
+            .filter_map(|entry| std::fs::read_to_string(entry.path().join("stat")).ok())
+            .filter_map(|stat| parse_proc_session(&stat))
+            .filter(|(_, session)| *session == shell_pid)
+            .map(|(name, _)| name)
+            .collect()
+    };
+
+    #[cfg(target_os = "macos")]
+    let processes = macos_session_pids(shell_pid as libc::pid_t)
+        .into_iter()
+        .filter(|pid| *pid != shell_pid as libc::pid_t)
+        .map(macos_process_name)
+        .collect();
+
+    processes
+}
+
+/// Reads the command name and the session id out of `/proc/<pid>/stat`.
+#[cfg(not(target_os = "macos"))]
+fn parse_proc_session(stat: &str) -> Option<(String, u32)> {
+    let (_, rest) = stat.split_once('(')?;
+    let (name, fields) = rest.rsplit_once(')')?;
+    let session = fields.split_whitespace().nth(3)?.parse().ok()?;
+
+    Some((name.to_owned(), session))
+}
+
 /// Start a new process in the background.
 pub fn spawn_daemon<I, S>(
     program: &str,
@@ -965,3 +1235,111 @@ where
             .map(|_| ())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_command() {
+        let args = |args: &[&str]| -> Vec<String> {
+            args.iter().map(|arg| arg.to_string()).collect()
+        };
+
+        assert_eq!(format_command(&[]), "");
+        assert_eq!(format_command(&args(&["-zsh"])), "zsh");
+        assert_eq!(format_command(&args(&["/usr/bin/ssh", "host"])), "ssh host");
+        assert_eq!(
+            format_command(&args(&["cargo", "build", "--release"])),
+            "cargo build --release"
+        );
+    }
+
+    #[test]
+    fn test_resolve_program() {
+        assert_eq!(resolve_program("/bin/sh"), Some(PathBuf::from("/bin/sh")));
+        assert!(resolve_program("sh").is_some_and(|path| path.is_absolute()));
+        assert_eq!(resolve_program("rio-missing-program"), None);
+    }
+
+    #[test]
+    fn test_child_environment() {
+        let env = [(String::from("PATH"), String::from("/rio"))];
+        let envp = child_environment(&env);
+        let paths: Vec<&CStr> = envp
+            .iter()
+            .map(CString::as_c_str)
+            .filter(|entry| entry.to_bytes().starts_with(b"PATH="))
+            .collect();
+        assert_eq!(paths, [c"PATH=/rio"]);
+
+        let ptrs = null_terminated(&envp);
+        assert_eq!(ptrs.len(), envp.len() + 1);
+        assert!(ptrs[envp.len()].is_null());
+    }
+
+    #[test]
+    fn test_fork_passes_args_and_env() {
+        use std::io::Read;
+
+        let env = [(String::from("RIO_FORK_TEST"), String::from("forked"))];
+        let args = vec![String::from("-c"), String::from("echo $RIO_FORK_TEST")];
+        let mut pty = create_pty_with_fork("sh", args, &env, false, 80, 24).unwrap();
+
+        let mut output = Vec::new();
+        let mut buf = [0; 1024];
+        let start = std::time::Instant::now();
+        while !output.windows(6).any(|window| window == b"forked")
+            && start.elapsed() < std::time::Duration::from_secs(5)
+        {
+            match pty.reader().read(&mut buf) {
+                Ok(0) | Err(_) => {
+                    std::thread::sleep(std::time::Duration::from_millis(10))
+                }
+                Ok(n) => output.extend_from_slice(&buf[..n]),
+            }
+        }
+        assert!(String::from_utf8_lossy(&output).contains("forked"));
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    #[test]
+    fn test_parse_proc_usage() {
+        let stat = "4242 (cargo build) R 1 4242 4242 34816 4242 4194304 \
+                    1500 0 0 0 250 150 0 0 20 0 12 0 100 1000 200";
+        let statm = "5000 300 100 10 0 400 0";
+
+        assert_eq!(
+            parse_proc_usage(stat, statm, 100, 4096),
+            Some(ProcessUsage {
+                cpu_time: std::time::Duration::from_secs(4),
+                memory: 300 * 4096,
+            })
+        );
+        assert_eq!(parse_proc_usage("4242 (sh", statm, 100, 4096), None);
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    #[test]
+    fn test_parse_proc_session() {
+        let stat = "4242 (tmux: server) S 1 4242 4100 0 -1 4194560 \
+                    300 0 0 0 10 5 0 0 20 0 1 0 100 1000 200";
+
+        assert_eq!(
+            parse_proc_session(stat),
+            Some((String::from("tmux: server"), 4100))
+        );
+        assert_eq!(parse_proc_session("4242 (sh"), None);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_current_process_usage() {
+        let path = format!("/proc/{}", std::process::id());
+        let stat = std::fs::read_to_string(format!("{path}/stat")).unwrap();
+        let statm = std::fs::read_to_string(format!("{path}/statm")).unwrap();
+
+        let usage = parse_proc_usage(&stat, &statm, 100, 4096).unwrap();
+        assert!(usage.memory > 0);
+    }
+}
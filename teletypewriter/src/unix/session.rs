@@ -0,0 +1,185 @@
+// Nested sessions shown by `{session}` in title templates, like
+// `ssh: host`, `docker: name` or `tmux`. They are read out of the command
+// line of the foreground process, shells running inside of an ssh session
+// or a container fall back to the environment of Rio.
+
+use std::sync::OnceLock;
+
+// Options taking a value, the host or container is the first argument
+// which is neither an option nor one of these values.
+const SSH_OPTIONS: &str = "BbcDEeFIiJLlmOoPpQRSWw";
+const CONTAINER_OPTIONS: &[&str] = &[
+    "-e",
+    "--env",
+    "--env-file",
+    "-u",
+    "--user",
+    "-w",
+    "--workdir",
+    "--detach-keys",
+    "-c",
+    "--container",
+    "-n",
+    "--namespace",
+    "--name",
+    "-v",
+    "--volume",
+    "-p",
+    "--publish",
+    "--entrypoint",
+    "--network",
+];
+
+/// Nested session of a command line, the program is expected without
+/// its path (see `program_name`).
+pub fn session_badge(args: &[&str]) -> Option<String> {
+    let (program, args) = args.split_first()?;
+    match *program {
+        "ssh" | "mosh" | "et" => {
+            let host = positional(args, |arg| {
+                arg.len() == 2 && arg.starts_with('-') && SSH_OPTIONS.contains(&arg[1..])
+            })?;
+            Some(format!("{program}: {host}"))
+        }
+        "docker" | "podman" | "kubectl" => {
+            let args = match args {
+                // docker compose exec <service>
+                ["compose", rest @ ..] => rest,
+                _ => args,
+            };
+            let (subcommand, args) = args.split_first()?;
+            let name = match *subcommand {
+                "exec" | "attach" => positional(args, takes_value)?,
+                "run" => named(args).or_else(|| positional(args, takes_value))?,
+                _ => return None,
+            };
+            Some(format!("{program}: {name}"))
+        }
+        "tmux" | "screen" | "zellij" => Some(program.to_string()),
+        _ => None,
+    }
+}
+
+fn takes_value(arg: &str) -> bool {
+    CONTAINER_OPTIONS.contains(&arg)
+}
+
+// First argument which isn't an option or its value.
+fn positional<'a>(
+    args: &[&'a str],
+    takes_value: impl Fn(&str) -> bool,
+) -> Option<&'a str> {
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if !arg.starts_with('-') {
+            return Some(arg);
+        }
+
+        if takes_value(arg) {
+            args.next();
+        }
+    }
+
+    None
+}
+
+// Value of `--name`, used by `docker run`.
+fn named<'a>(args: &[&'a str]) -> Option<&'a str> {
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if let Some(name) = arg.strip_prefix("--name=") {
+            return Some(name);
+        }
+
+        if *arg == "--name" {
+            return args.next().copied();
+        }
+    }
+
+    None
+}
+
+/// Session Rio itself is running in, read once out of its environment.
+pub fn local_session() -> Option<&'static str> {
+    static SESSION: OnceLock<Option<String>> = OnceLock::new();
+
+    SESSION
+        .get_or_init(|| {
+            let runtime = if std::env::var_os("SSH_CONNECTION").is_some() {
+                String::from("ssh")
+            } else if let Some(container) = std::env::var("container")
+                .ok()
+                .filter(|container| !container.is_empty())
+            {
+                // Set by podman, toolbox and systemd-nspawn
+                container
+            } else if std::path::Path::new("/.dockerenv").exists() {
+                String::from("docker")
+            } else if std::path::Path::new("/run/.containerenv").exists() {
+                String::from("podman")
+            } else {
+                return None;
+            };
+
+            Some(format!("{runtime}: {}", hostname()?))
+        })
+        .as_deref()
+}
+
+fn hostname() -> Option<String> {
+    let mut buffer = [0u8; 256];
+    if unsafe {
+        libc::gethostname(buffer.as_mut_ptr() as *mut libc::c_char, buffer.len())
+    } != 0
+    {
+        return None;
+    }
+
+    let length = buffer.iter().position(|&byte| byte == 0)?;
+    Some(String::from_utf8_lossy(&buffer[..length]).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_badge() {
+        let badge = |command: &str| {
+            session_badge(&command.split_whitespace().collect::<Vec<_>>())
+        };
+
+        assert_eq!(badge("ssh host").as_deref(), Some("ssh: host"));
+        assert_eq!(
+            badge("ssh -p 2222 -i ~/.ssh/key -A rio@host uptime").as_deref(),
+            Some("ssh: rio@host")
+        );
+        assert_eq!(badge("mosh host").as_deref(), Some("mosh: host"));
+        assert_eq!(
+            badge("docker exec -it -u root web bash").as_deref(),
+            Some("docker: web")
+        );
+        assert_eq!(
+            badge("docker run --rm -it --name=dev ubuntu").as_deref(),
+            Some("docker: dev")
+        );
+        assert_eq!(
+            badge("podman run -it -v /src:/src fedora").as_deref(),
+            Some("podman: fedora")
+        );
+        assert_eq!(
+            badge("docker compose exec api sh").as_deref(),
+            Some("docker: api")
+        );
+        assert_eq!(
+            badge("kubectl exec -n prod -it api-0 -- sh").as_deref(),
+            Some("kubectl: api-0")
+        );
+        assert_eq!(badge("tmux attach -t main").as_deref(), Some("tmux"));
+
+        assert_eq!(badge("docker ps"), None);
+        assert_eq!(badge("ssh"), None);
+        assert_eq!(badge("vim"), None);
+        assert_eq!(badge(""), None);
+    }
+}
@@ -4,10 +4,10 @@ use std::io::Error;
 use std::num::NonZeroU32;
 use std::sync::atomic::{AtomicPtr, Ordering};
 
-use windows_sys::Win32::Foundation::{BOOLEAN, HANDLE};
+use windows_sys::Win32::Foundation::{BOOLEAN, HANDLE, STILL_ACTIVE};
 use windows_sys::Win32::System::Threading::{
-    GetProcessId, RegisterWaitForSingleObject, UnregisterWait, INFINITE,
-    WT_EXECUTEINWAITTHREAD, WT_EXECUTEONLYONCE,
+    GetExitCodeProcess, GetProcessId, RegisterWaitForSingleObject, UnregisterWait,
+    INFINITE, WT_EXECUTEINWAITTHREAD, WT_EXECUTEONLYONCE,
 };
 
 use crate::ChildEvent;
@@ -19,7 +19,7 @@ extern "system" fn child_exit_callback(ctx: *mut c_void, timed_out: BOOLEAN) {
     }
 
     let event_tx: Box<_> = unsafe { Box::from_raw(ctx as *mut Sender<ChildEvent>) };
-    let _ = event_tx.send(ChildEvent::Exited);
+    let _ = event_tx.send(ChildEvent::Exited(None));
 }
 
 pub struct ChildExitWatcher {
@@ -71,6 +71,17 @@ impl ChildExitWatcher {
     pub fn pid(&self) -> Option<NonZeroU32> {
         self.pid
     }
+
+    /// Exit code of the child, `None` while it is still running.
+    pub fn exit_code(&self) -> Option<i32> {
+        let mut code = 0u32;
+        let success = unsafe { GetExitCodeProcess(self.child_handle, &mut code) };
+        if success == 0 || code == STILL_ACTIVE as u32 {
+            None
+        } else {
+            Some(code as i32)
+        }
+    }
 }
 
 impl Drop for ChildExitWatcher {
@@ -119,7 +130,7 @@ mod tests {
         // Verify that at least one `ChildEvent::Exited` was received.
         assert_eq!(
             child_exit_watcher.event_rx().try_recv(),
-            Ok(ChildEvent::Exited)
+            Ok(ChildEvent::Exited(None))
         );
     }
 }
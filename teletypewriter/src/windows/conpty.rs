@@ -16,13 +16,13 @@ use windows_sys::{s, w};
 
 use windows_sys::Win32::System::Threading::{
     CreateProcessW, InitializeProcThreadAttributeList, UpdateProcThreadAttribute,
-    EXTENDED_STARTUPINFO_PRESENT, PROCESS_INFORMATION,
+    CREATE_UNICODE_ENVIRONMENT, EXTENDED_STARTUPINFO_PRESENT, PROCESS_INFORMATION,
     PROC_THREAD_ATTRIBUTE_PSEUDOCONSOLE, STARTF_USESTDHANDLES, STARTUPINFOEXW,
     STARTUPINFOW,
 };
 
 use crate::windows::child::ChildExitWatcher;
-use crate::windows::{cmdline, win32_string, Pty};
+use crate::windows::{cmdline, environment_block, win32_string, Pty};
 
 /// Load the pseudoconsole API from conpty.dll if possible, otherwise use the
 /// standard Windows API.
@@ -105,6 +105,7 @@ unsafe impl Send for Conpty {}
 
 pub fn new(
     shell: &str,
+    env: &[(String, String)],
     working_directory: &Option<String>,
     columns: u16,
     rows: u16,
@@ -137,7 +138,9 @@ pub fn new(
         )
     };
 
-    assert_eq!(result, S_OK);
+    if result != S_OK {
+        return Err(Error::from_raw_os_error(result));
+    }
 
     let mut success;
 
@@ -215,6 +218,7 @@ pub fn new(
 
     let cmdline = win32_string(&cmdline(shell));
     let cwd = working_directory.as_ref().map(win32_string);
+    let env_block = environment_block(env);
 
     let mut proc_info: PROCESS_INFORMATION = unsafe { mem::zeroed() };
     unsafe {
@@ -224,8 +228,10 @@ pub fn new(
             ptr::null_mut(),
             ptr::null_mut(),
             false as i32,
-            EXTENDED_STARTUPINFO_PRESENT,
-            ptr::null_mut(),
+            EXTENDED_STARTUPINFO_PRESENT | CREATE_UNICODE_ENVIRONMENT,
+            env_block.as_ref().map_or(ptr::null(), |block| {
+                block.as_ptr() as *const std::ffi::c_void
+            }),
             cwd.as_ref().map_or_else(ptr::null, |s| s.as_ptr()),
             &mut startup_info_ex.StartupInfo as *mut STARTUPINFOW,
             &mut proc_info as *mut PROCESS_INFORMATION,
@@ -249,16 +255,23 @@ pub fn new(
 }
 
 impl Conpty {
-    pub fn on_resize(&mut self, window_size: Winsize) {
+    pub fn on_resize(&mut self, window_size: Winsize) -> Result<()> {
         let result = unsafe { (self.api.resize)(self.handle, window_size.into()) };
-        assert_eq!(result, S_OK);
+        if result != S_OK {
+            // The pseudoconsole might be gone already, e.g. the shell exited.
+            warn!("Failed to resize the pseudoconsole: {result:#x}");
+            return Err(Error::from_raw_os_error(result));
+        }
+
+        Ok(())
     }
 }
 
 impl From<Winsize> for COORD {
     fn from(window_size: Winsize) -> Self {
-        let lines = window_size.ws_row;
-        let columns = window_size.ws_col;
+        // ConPTY rejects empty sizes, which happen while the window is minimized.
+        let lines = window_size.ws_row.max(1);
+        let columns = window_size.ws_col.max(1);
         COORD {
             X: columns as i16,
             Y: lines as i16,
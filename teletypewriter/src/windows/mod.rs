@@ -35,17 +35,85 @@ pub struct Pty {
 pub fn create_pty(
     shell: &str,
     args: Vec<String>,
+    env: &[(String, String)],
     working_directory: &Option<String>,
     columns: u16,
     rows: u16,
 ) -> Result<Pty, std::io::Error> {
-    let exec = if !args.is_empty() {
-        let args = args.join(" ");
-        &format!("{shell} {args}")
+    // Paths like `C:\Program Files\PowerShell\7\pwsh.exe` need to be quoted,
+    // but the program is also used as a whole command line (e.g. `wsl -d Ubuntu`).
+    let mut exec = if std::path::Path::new(shell).exists() {
+        quote_arg(shell)
     } else {
-        shell
+        shell.to_string()
     };
-    conpty::new(exec, working_directory, columns, rows)
+    for arg in &args {
+        exec.push(' ');
+        exec.push_str(&quote_arg(arg));
+    }
+    conpty::new(&exec, env, working_directory, columns.max(1), rows.max(1))
+}
+
+/// Builds a unicode environment block for `CreateProcessW` with the
+/// variables of the current process overridden by `env`.
+fn environment_block(env: &[(String, String)]) -> Option<Vec<u16>> {
+    if env.is_empty() {
+        return None;
+    }
+
+    // Names are case insensitive and the block is sorted by name.
+    let mut vars = std::collections::BTreeMap::new();
+    for (key, value) in std::env::vars_os() {
+        let key = key.to_string_lossy().into_owned();
+        vars.insert(
+            key.to_uppercase(),
+            (key, value.to_string_lossy().into_owned()),
+        );
+    }
+    for (key, value) in env {
+        vars.insert(key.to_uppercase(), (key.to_owned(), value.to_owned()));
+    }
+
+    let mut block = Vec::new();
+    for (key, value) in vars.values() {
+        block.extend(OsStr::new(&format!("{key}={value}")).encode_wide());
+        block.push(0);
+    }
+    block.push(0);
+    Some(block)
+}
+
+/// Quotes an argument following the rules used by `CommandLineToArgvW` and
+/// the MSVC runtime to split a command line.
+fn quote_arg(arg: &str) -> String {
+    if !arg.is_empty() && !arg.contains([' ', '\t', '\n', '\u{b}', '"']) {
+        return arg.to_string();
+    }
+
+    let mut quoted = String::with_capacity(arg.len() + 2);
+    quoted.push('"');
+    let mut backslashes = 0;
+    for ch in arg.chars() {
+        match ch {
+            '\\' => backslashes += 1,
+            '"' => {
+                // Backslashes before a quote are escaped, as is the quote.
+                quoted.extend((0..backslashes * 2 + 1).map(|_| '\\'));
+                backslashes = 0;
+            }
+            _ => {
+                quoted.extend((0..backslashes).map(|_| '\\'));
+                backslashes = 0;
+            }
+        }
+        if ch != '\\' {
+            quoted.push(ch);
+        }
+    }
+    // Backslashes before the closing quote are escaped too.
+    quoted.extend((0..backslashes * 2).map(|_| '\\'));
+    quoted.push('"');
+    quoted
 }
 
 impl Pty {
@@ -210,8 +278,7 @@ impl ProcessReadWrite for Pty {
         winsize_builder: WinsizeBuilder,
     ) -> Result<(), std::io::Error> {
         let winsize: Winsize = winsize_builder.build();
-        self.backend.on_resize(winsize);
-        Ok(())
+        self.backend.on_resize(winsize)
     }
 }
 
@@ -222,9 +289,10 @@ impl EventedPty for Pty {
 
     fn next_child_event(&mut self) -> Option<ChildEvent> {
         match self.child_watcher.event_rx().try_recv() {
-            Ok(ev) => Some(ev),
+            Ok(ChildEvent::Exited(_)) | Err(TryRecvError::Disconnected) => {
+                Some(ChildEvent::Exited(self.child_watcher.exit_code()))
+            }
             Err(TryRecvError::Empty) => None,
-            Err(TryRecvError::Disconnected) => Some(ChildEvent::Exited),
         }
     }
 }
@@ -260,3 +328,21 @@ where
         .spawn()
         .map(|_| ())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::quote_arg;
+
+    #[test]
+    fn test_quote_arg() {
+        assert_eq!(quote_arg("-NoLogo"), "-NoLogo");
+        assert_eq!(quote_arg(""), "\"\"");
+        assert_eq!(quote_arg("Ubuntu 22.04"), "\"Ubuntu 22.04\"");
+        assert_eq!(
+            quote_arg("C:\\Program Files\\"),
+            "\"C:\\Program Files\\\\\""
+        );
+        assert_eq!(quote_arg("say \"hi\""), "\"say \\\"hi\\\"\"");
+        assert_eq!(quote_arg("a\\\"b"), "\"a\\\\\\\"b\"");
+    }
+}